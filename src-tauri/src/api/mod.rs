@@ -0,0 +1,4 @@
+pub mod server;
+pub mod types;
+
+pub use server::*;
@@ -0,0 +1,447 @@
+//! Local HTTP API for tools outside the app: launchers, browser extensions
+//! and scripts.
+//!
+//! The server is off by default. Once enabled in the app settings it listens
+//! on `127.0.0.1` only, and every request has to carry the configured token
+//! as `Authorization: Bearer <token>`. It works on the vault open in the app:
+//!
+//! - `GET /search?q=<words>&limit=<n>`: lines of notes containing all words
+//! - `GET /notes/<path>`: markdown of a note or notebook
+//! - `POST /notes/<path>`: append the request body to a note, creating it
+//! - `GET /mcp/sse` and `POST /mcp/messages`: the vault's MCP server, when
+//!   its settings enable it
+//!
+//! Note paths are relative to the vault, with or without `.md`. Encrypted
+//! notes are neither served nor changed.
+
+use serde_json::{json, Value};
+use std::net::Ipv4Addr;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::async_runtime::JoinHandle;
+use tauri::State;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use url::Url;
+
+use super::types::{ApiServerSettings, ApiServerStatus};
+use crate::fs::echo::forget_write;
+use crate::fs::{
+    append_to_note, is_encrypted, read_markdown_text, search_vault, vault_note_path, FsError, WatcherState,
+};
+use crate::import::notion::percent_decode;
+use crate::mcp::{serve_sse, McpServer, SseSessions};
+
+/// Shortest token the server accepts
+const MIN_TOKEN_LEN: usize = 16;
+/// Largest request head and body read
+const MAX_HEAD: usize = 16 * 1024;
+const MAX_BODY: usize = 1024 * 1024;
+/// Time a client gets to send its request
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+/// Search results returned without a `limit`
+const DEFAULT_SEARCH_LIMIT: usize = 50;
+
+/// The running server, if any
+#[derive(Default)]
+pub struct ApiServer {
+    running: Option<(u16, JoinHandle<()>)>,
+}
+
+pub type ApiServerState = Arc<Mutex<ApiServer>>;
+
+#[derive(Debug, Default)]
+struct Request {
+    method: String,
+    target: String,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl Request {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Response {
+    status: u16,
+    body: Option<Value>,
+}
+
+impl Response {
+    fn json(body: Value) -> Self {
+        Self {
+            status: 200,
+            body: Some(body),
+        }
+    }
+
+    fn error(status: u16, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            body: Some(json!({ "error": message.into() })),
+        }
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let reason = match self.status {
+            200 => "OK",
+            202 => "Accepted",
+            204 => "No Content",
+            400 => "Bad Request",
+            401 => "Unauthorized",
+            403 => "Forbidden",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            413 => "Payload Too Large",
+            431 => "Request Header Fields Too Large",
+            503 => "Service Unavailable",
+            _ => "Internal Server Error",
+        };
+        let body = self.body.as_ref().map(Value::to_string).unwrap_or_default();
+        format!(
+            "HTTP/1.1 {} {}\r\n\
+             Content-Type: application/json\r\n\
+             Content-Length: {}\r\n\
+             Access-Control-Allow-Origin: *\r\n\
+             Access-Control-Allow-Headers: Authorization, Content-Type\r\n\
+             Access-Control-Allow-Methods: GET, POST, OPTIONS\r\n\
+             Connection: close\r\n\r\n{}",
+            self.status,
+            reason,
+            body.len(),
+            body
+        )
+        .into_bytes()
+    }
+}
+
+impl From<FsError> for Response {
+    fn from(error: FsError) -> Self {
+        let status = match error {
+            FsError::NotFound(_) => 404,
+            FsError::Encrypted(_) => 403,
+            FsError::InvalidPath(_) | FsError::PathTraversal => 400,
+            _ => 500,
+        };
+        Response::error(status, error.to_string())
+    }
+}
+
+/// Request line and headers of a request head, without the blank line
+fn parse_head(head: &str) -> Option<Request> {
+    let mut lines = head.split("\r\n");
+    let mut request_line = lines.next()?.split(' ');
+    let method = request_line.next()?.to_string();
+    let target = request_line.next()?.to_string();
+    if !request_line.next()?.starts_with("HTTP/1.") {
+        return None;
+    }
+    let headers = lines
+        .map(|line| {
+            let (key, value) = line.split_once(':')?;
+            Some((key.trim().to_string(), value.trim().to_string()))
+        })
+        .collect::<Option<Vec<_>>>()?;
+    Some(Request {
+        method,
+        target,
+        headers,
+        body: Vec::new(),
+    })
+}
+
+async fn read_request(stream: &mut TcpStream) -> std::io::Result<Result<Request, Response>> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 4096];
+    let head_end = loop {
+        if let Some(end) = buffer.windows(4).position(|w| w == b"\r\n\r\n") {
+            break end;
+        }
+        if buffer.len() > MAX_HEAD {
+            return Ok(Err(Response::error(431, "Request head too large")));
+        }
+        let read = stream.read(&mut chunk).await?;
+        if read == 0 {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+    };
+
+    let Some(mut request) = std::str::from_utf8(&buffer[..head_end]).ok().and_then(parse_head) else {
+        return Ok(Err(Response::error(400, "Malformed request")));
+    };
+    let length = match request.header("content-length").map(str::parse::<usize>) {
+        None => 0,
+        Some(Ok(length)) if length <= MAX_BODY => length,
+        Some(Ok(_)) => return Ok(Err(Response::error(413, "Request body too large"))),
+        Some(Err(_)) => return Ok(Err(Response::error(400, "Invalid Content-Length"))),
+    };
+
+    let mut body = buffer[head_end + 4..].to_vec();
+    while body.len() < length {
+        let read = stream.read(&mut chunk).await?;
+        if read == 0 {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+        body.extend_from_slice(&chunk[..read]);
+    }
+    body.truncate(length);
+    request.body = body;
+    Ok(Ok(request))
+}
+
+/// Compare secrets in time independent of where they differ
+fn same_secret(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+fn read_note(path: &Path, vault: &Path) -> Result<Response, FsError> {
+    if !path.exists() {
+        return Err(FsError::NotFound(path.display().to_string()));
+    }
+    let content = read_markdown_text(path)?;
+    if is_encrypted(&content) {
+        return Err(FsError::Encrypted(path.display().to_string()));
+    }
+    let relative = path.strip_prefix(vault).unwrap_or(path);
+    Ok(Response::json(json!({ "path": relative, "content": content })))
+}
+
+fn append(path: &Path, body: &[u8]) -> Result<Response, FsError> {
+    let text = std::str::from_utf8(body).map_err(|_| FsError::InvalidPath("Body is not UTF-8 text".to_string()))?;
+    if text.trim().is_empty() {
+        return Err(FsError::InvalidPath("Nothing to append".to_string()));
+    }
+    append_to_note(path, text, None)?;
+    // Let the app pick up the change like an edit from another program
+    forget_write(path);
+    Ok(Response::json(json!({ "appended": true })))
+}
+
+fn authorized(request: &Request, token: &str) -> bool {
+    let presented = request.header("authorization").and_then(|v| v.strip_prefix("Bearer "));
+    presented.is_some_and(|presented| same_secret(presented.trim().as_bytes(), token.as_bytes()))
+}
+
+fn handle(request: &Request, token: &str, vault: Option<&Path>, sessions: &SseSessions) -> Response {
+    if request.method == "OPTIONS" {
+        return Response {
+            status: 204,
+            body: None,
+        };
+    }
+    if !authorized(request, token) {
+        return Response::error(401, "Missing or wrong token");
+    }
+    let Some(vault) = vault else {
+        return Response::error(503, "No vault is open");
+    };
+    let Ok(url) = Url::parse(&format!("http://localhost{}", request.target)) else {
+        return Response::error(400, "Invalid request target");
+    };
+
+    let param = |name: &str| url.query_pairs().find(|(key, _)| key == name).map(|(_, value)| value);
+
+    let result = match (request.method.as_str(), url.path()) {
+        ("GET", "/search") => {
+            let query = param("q").unwrap_or_default();
+            let limit = param("limit").and_then(|n| n.parse().ok()).unwrap_or(DEFAULT_SEARCH_LIMIT);
+            let hits = search_vault(vault, &query, Some(limit), None);
+            let hits: Vec<Value> = hits
+                .iter()
+                .map(|hit| {
+                    json!({
+                        "path": hit.note.strip_prefix(vault).unwrap_or(&hit.note),
+                        "line": hit.line,
+                        "text": hit.text,
+                    })
+                })
+                .collect();
+            Ok(Response::json(json!({ "results": hits })))
+        }
+        ("POST", "/mcp/messages") => {
+            let session = param("session_id").unwrap_or_default();
+            let message = String::from_utf8_lossy(&request.body);
+            if sessions.post(&session, &message) {
+                Ok(Response {
+                    status: 202,
+                    body: None,
+                })
+            } else {
+                Ok(Response::error(404, "Unknown MCP session"))
+            }
+        }
+        (method, path) => match path.strip_prefix("/notes/") {
+            Some(name) => vault_note_path(vault, &percent_decode(name)).and_then(|note| match method {
+                "GET" => read_note(&note, vault),
+                "POST" => append(&note, &request.body),
+                _ => Ok(Response::error(405, "Use GET or POST")),
+            }),
+            None => Ok(Response::error(404, "Unknown endpoint")),
+        },
+    };
+    result.unwrap_or_else(Response::from)
+}
+
+/// MCP server for an event stream request, if the vault allows one
+fn mcp_server(request: &Request, token: &str, vault: Option<&Path>) -> Result<McpServer, Response> {
+    if !authorized(request, token) {
+        return Err(Response::error(401, "Missing or wrong token"));
+    }
+    let vault = vault.ok_or_else(|| Response::error(503, "No vault is open"))?;
+    let server = McpServer::new(vault, "sse");
+    if !server.settings().enabled {
+        return Err(Response::error(403, "MCP is disabled in the vault settings"));
+    }
+    Ok(server)
+}
+
+async fn respond(
+    mut stream: TcpStream,
+    token: Arc<String>,
+    watcher: WatcherState,
+    sessions: SseSessions,
+) -> std::io::Result<()> {
+    let response = match tokio::time::timeout(READ_TIMEOUT, read_request(&mut stream)).await {
+        Ok(Ok(Ok(request))) => {
+            let vault = watcher.lock().ok().and_then(|w| w.watched_path().cloned());
+            if request.method == "GET" && request.target.split('?').next() == Some("/mcp/sse") {
+                match mcp_server(&request, &token, vault.as_deref()) {
+                    Ok(server) => return serve_sse(&mut stream, &sessions, server).await,
+                    Err(response) => response,
+                }
+            } else {
+                tauri::async_runtime::spawn_blocking(move || handle(&request, &token, vault.as_deref(), &sessions))
+                    .await
+                    .unwrap_or_else(|_| Response::error(500, "Request failed"))
+            }
+        }
+        Ok(Ok(Err(response))) => response,
+        Ok(Err(e)) => return Err(e),
+        Err(_) => Response::error(400, "Request timed out"),
+    };
+    stream.write_all(&response.to_bytes()).await?;
+    stream.shutdown().await
+}
+
+async fn serve(listener: TcpListener, token: String, watcher: WatcherState) {
+    let token = Arc::new(token);
+    let sessions = SseSessions::default();
+    loop {
+        let Ok((stream, _)) = listener.accept().await else {
+            continue;
+        };
+        tauri::async_runtime::spawn(respond(stream, token.clone(), watcher.clone(), sessions.clone()));
+    }
+}
+
+/// Start, restart or stop the local API according to the app settings
+#[tauri::command]
+pub async fn configure_api_server(
+    settings: ApiServerSettings,
+    server: State<'_, ApiServerState>,
+    watcher: State<'_, WatcherState>,
+) -> Result<ApiServerStatus, FsError> {
+    if let Some((_, task)) = server.lock().unwrap().running.take() {
+        task.abort();
+    }
+    if !settings.enabled {
+        return Ok(ApiServerStatus::default());
+    }
+    let token = settings.token.trim().to_string();
+    if token.len() < MIN_TOKEN_LEN {
+        return Err(FsError::InvalidPath(format!(
+            "The API token needs at least {} characters",
+            MIN_TOKEN_LEN
+        )));
+    }
+
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, settings.port)).await?;
+    let port = listener.local_addr()?.port();
+    let task = tauri::async_runtime::spawn(serve(listener, token, watcher.inner().clone()));
+    server.lock().unwrap().running = Some((port, task));
+    Ok(ApiServerStatus {
+        running: true,
+        port: Some(port),
+    })
+}
+
+#[tauri::command]
+pub fn get_api_server_status(server: State<'_, ApiServerState>) -> ApiServerStatus {
+    match &server.lock().unwrap().running {
+        Some((port, _)) => ApiServerStatus {
+            running: true,
+            port: Some(*port),
+        },
+        None => ApiServerStatus::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    const TOKEN: &str = "0123456789abcdef";
+
+    fn request(method: &str, target: &str, body: &str) -> Request {
+        Request {
+            method: method.to_string(),
+            target: target.to_string(),
+            headers: vec![("Authorization".to_string(), format!("Bearer {}", TOKEN))],
+            body: body.as_bytes().to_vec(),
+        }
+    }
+
+    #[test]
+    fn parses_request_head() {
+        let request = parse_head("POST /notes/Inbox HTTP/1.1\r\nHost: localhost\r\nContent-Length: 4").unwrap();
+        assert_eq!((request.method.as_str(), request.target.as_str()), ("POST", "/notes/Inbox"));
+        assert_eq!(request.header("content-length"), Some("4"));
+        assert!(parse_head("garbage").is_none());
+    }
+
+    #[test]
+    fn serves_notes_with_token() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = dir.path();
+        fs::create_dir(vault.join("Work")).unwrap();
+        fs::write(vault.join("Work/Plan Q3.md"), "# Plan\nShip the release\n").unwrap();
+        let sessions = SseSessions::default();
+
+        let anonymous = Request {
+            headers: Vec::new(),
+            ..request("GET", "/search?q=release", "")
+        };
+        assert_eq!(handle(&anonymous, TOKEN, Some(vault), &sessions).status, 401);
+        assert_eq!(handle(&request("GET", "/search?q=x", ""), TOKEN, None, &sessions).status, 503);
+
+        let found = handle(&request("GET", "/search?q=ship+release", ""), TOKEN, Some(vault), &sessions);
+        assert_eq!(found.body.unwrap()["results"][0]["path"], "Work/Plan Q3.md");
+
+        let read = handle(&request("GET", "/notes/Work/Plan%20Q3", ""), TOKEN, Some(vault), &sessions);
+        assert_eq!(read.body.unwrap()["content"], "# Plan\nShip the release\n");
+        assert_eq!(handle(&request("GET", "/notes/Missing", ""), TOKEN, Some(vault), &sessions).status, 404);
+        assert_eq!(handle(&request("GET", "/notes/a%2F..%2Fsecret", ""), TOKEN, Some(vault), &sessions).status, 400);
+
+        let append = request("POST", "/notes/Work/Plan%20Q3.md", "- [ ] invite team");
+        assert_eq!(handle(&append, TOKEN, Some(vault), &sessions).status, 200);
+        assert_eq!(
+            fs::read_to_string(vault.join("Work/Plan Q3.md")).unwrap(),
+            "# Plan\nShip the release\n- [ ] invite team\n"
+        );
+
+        // MCP stays closed until the vault settings enable it
+        let sse = request("GET", "/mcp/sse", "");
+        assert_eq!(mcp_server(&sse, TOKEN, Some(vault)).unwrap_err().status, 403);
+        let message = request("POST", "/mcp/messages?session_id=nope", "{}");
+        assert_eq!(handle(&message, TOKEN, Some(vault), &sessions).status, 404);
+    }
+}
@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+
+/// Port the local API listens on unless configured otherwise
+pub const DEFAULT_API_PORT: u16 = 27123;
+
+/// Settings of the local HTTP API, kept with the app settings
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ApiServerSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_port")]
+    pub port: u16,
+    /// Bearer token every request has to carry
+    #[serde(default)]
+    pub token: String,
+}
+
+impl Default for ApiServerSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: DEFAULT_API_PORT,
+            token: String::new(),
+        }
+    }
+}
+
+fn default_port() -> u16 {
+    DEFAULT_API_PORT
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ApiServerStatus {
+    pub running: bool,
+    /// Port the server listens on while running
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+}
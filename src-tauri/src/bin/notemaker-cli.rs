@@ -0,0 +1,176 @@
+//! Command-line access to a vault: capture, search, export and sync notes
+//! without opening the app.
+
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use notemaker_lib::headless::{
+    append_to_note, find_vault_root, new_note, read_vault_template, render_note_html, search_vault, serve_stdio,
+    sync_vault, unlock_session_from_keychain, vault_note_path, vault_remote, EncryptionSession, HtmlExportOptions,
+    McpServer,
+};
+
+const USAGE: &str = "\
+Usage: notemaker-cli [--vault <dir>] <command> [<args>]
+
+Commands:
+  new <note> [--title <title>] [--template <name>]  Create a note
+  search <words>... [--limit <n>]                   Print lines containing all words
+  append <note> [<text>...]                         Append text, or standard input
+  export <note> [--output <file>]                   Render a note to HTML
+  sync                                              Commit, pull and push the vault
+  mcp                                               Serve the vault's MCP tools on stdin/stdout
+
+Notes are given relative to the vault, with or without `.md`. The vault is
+--vault, else $NOTEMAKER_VAULT, else the vault around the current directory.
+Encrypted notes are opened with the credentials saved in the keychain.";
+
+/// Options taking a value
+const OPTIONS: [&str; 5] = ["--vault", "--title", "--template", "--limit", "--output"];
+
+struct Args {
+    command: String,
+    positional: Vec<String>,
+    options: HashMap<&'static str, String>,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut positional = Vec::new();
+    let mut options = HashMap::new();
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            positional.extend(args.by_ref());
+        } else if let Some(option) = OPTIONS.iter().find(|o| **o == arg) {
+            let value = args.next().ok_or_else(|| format!("{} needs a value", option))?;
+            options.insert(*option, value);
+        } else if arg.starts_with("--") {
+            return Err(format!("Unknown option: {}", arg));
+        } else {
+            positional.push(arg);
+        }
+    }
+    if positional.is_empty() {
+        return Err("No command given".to_string());
+    }
+    Ok(Args {
+        command: positional.remove(0),
+        positional,
+        options,
+    })
+}
+
+fn find_vault(explicit: Option<&String>) -> Result<PathBuf, String> {
+    let vault = match explicit.cloned().or_else(|| std::env::var("NOTEMAKER_VAULT").ok()) {
+        Some(path) => PathBuf::from(path),
+        None => {
+            let cwd = std::env::current_dir().map_err(|e| e.to_string())?;
+            find_vault_root(&cwd).ok_or("Not inside a vault; pass --vault or set NOTEMAKER_VAULT")?
+        }
+    };
+    vault
+        .canonicalize()
+        .map_err(|_| format!("Vault not found: {}", vault.display()))
+}
+
+/// Path of a note given relative to the vault
+fn note_path(vault: &Path, note: Option<&String>) -> Result<PathBuf, String> {
+    let note = note.ok_or("No note given")?;
+    vault_note_path(vault, note).map_err(|e| e.to_string())
+}
+
+fn run(args: Args) -> Result<ExitCode, String> {
+    let vault = find_vault(args.options.get("--vault"))?;
+    // Without stored credentials encrypted notes stay closed
+    let session = EncryptionSession::new();
+    let _ = unlock_session_from_keychain(&session, &vault);
+    let session = Some(&session);
+
+    match args.command.as_str() {
+        "new" => {
+            let path = note_path(&vault, args.positional.first())?;
+            let template = match args.options.get("--template") {
+                Some(name) => Some(
+                    read_vault_template(&vault, name)
+                        .map_err(|e| e.to_string())?
+                        .ok_or_else(|| format!("Template not found: {}", name))?,
+                ),
+                None => None,
+            };
+            new_note(&path, args.options.get("--title").cloned(), template, session).map_err(|e| e.to_string())?;
+            println!("{}", path.display());
+        }
+        "search" => {
+            let limit = match args.options.get("--limit") {
+                Some(n) => Some(n.parse().map_err(|_| format!("Invalid limit: {}", n))?),
+                None => None,
+            };
+            let hits = search_vault(&vault, &args.positional.join(" "), limit, session);
+            for hit in &hits {
+                let path = hit.path.strip_prefix(&vault).unwrap_or(&hit.path);
+                println!("{}:{}: {}", path.display(), hit.line, hit.text);
+            }
+            if hits.is_empty() {
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+        "append" => {
+            let path = note_path(&vault, args.positional.first())?;
+            let text = if args.positional.len() > 1 {
+                args.positional[1..].join(" ")
+            } else {
+                let mut text = String::new();
+                std::io::stdin().read_to_string(&mut text).map_err(|e| e.to_string())?;
+                text
+            };
+            append_to_note(&path, &text, session).map_err(|e| e.to_string())?;
+        }
+        "export" => {
+            let path = note_path(&vault, args.positional.first())?;
+            let export = render_note_html(&path, &HtmlExportOptions::default(), session).map_err(|e| e.to_string())?;
+            match args.options.get("--output") {
+                Some(output) => std::fs::write(output, export.html).map_err(|e| e.to_string())?,
+                None => print!("{}", export.html),
+            }
+        }
+        "sync" => {
+            let result = sync_vault(&vault, &vault_remote(&vault), true, |stage| eprintln!("{:?}...", stage))
+                .map_err(|e| e.to_string())?;
+            if let Some(commit) = &result.commit {
+                println!("Committed {} files as {}", result.files_committed, commit);
+            }
+            println!("{}", result.message);
+            if let Some(pull) = result.pull.as_ref().filter(|pull| !pull.conflicts.is_empty()) {
+                for file in &pull.conflicts {
+                    println!("Conflict: {}", file);
+                }
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+        "mcp" => {
+            let server = McpServer::new(&vault, "stdio");
+            if !server.settings().enabled {
+                return Err("MCP is disabled in the vault settings (mcp.enabled)".to_string());
+            }
+            serve_stdio(&server, std::io::stdin().lock(), std::io::stdout().lock()).map_err(|e| e.to_string())?;
+        }
+        other => return Err(format!("Unknown command: {}", other)),
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.is_empty() || args[0] == "help" || args.iter().any(|a| a == "-h" || a == "--help") {
+        println!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+    match parse_args(args.into_iter()).and_then(run) {
+        Ok(code) => code,
+        Err(message) => {
+            eprintln!("notemaker-cli: {}", message);
+            ExitCode::from(2)
+        }
+    }
+}
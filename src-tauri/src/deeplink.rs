@@ -0,0 +1,258 @@
+//! `notemaker://` links from other apps.
+//!
+//! - `notemaker://open?vault=<dir>&path=<note>` opens a note, or just the vault
+//! - `notemaker://new?template=<name>&path=<note>&title=<title>` creates a note
+//!   from a vault template, once the user agrees, and opens it
+//! - `notemaker://search?q=<words>` opens search with the words
+//!
+//! `vault` is a vault folder (`~/` allowed) or the name of the open vault,
+//! and may be left out to use the open one. Any web page can fire these
+//! links, so only folders that already hold `.notemaker` count as vaults;
+//! every vault opened before does. Notes are named relative to the vault,
+//! with or without `.md`. Each link is resolved here and the frontend is sent
+//! a `deep-link` event saying where to go; links the app was launched with
+//! are kept until the frontend asks for them.
+
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter, Manager, State};
+use url::Url;
+
+use crate::fs::{
+    expand_tilde, new_note, read_vault_template, vault_note_path, EncryptionSession, EncryptionState, WatcherState,
+};
+
+pub const SCHEME: &str = "notemaker";
+
+/// Where a link sends the app
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum DeepLinkEvent {
+    /// Open the vault and, when given, one of its notes
+    Open { vault: PathBuf, path: Option<PathBuf> },
+    /// Ask the user, then create a note through [`create_link_note`]
+    New {
+        vault: PathBuf,
+        name: String,
+        title: Option<String>,
+        template: Option<String>,
+    },
+    /// Open search in the vault
+    Search { vault: PathBuf, query: String },
+    /// The link couldn't be followed
+    Error { url: String, message: String },
+}
+
+/// Links the app was launched with, until the frontend asks for them
+pub type DeepLinkState = Arc<Mutex<Vec<Url>>>;
+
+fn resolve_vault(name: Option<&str>, open: Option<&Path>) -> Result<PathBuf, String> {
+    let Some(name) = name.filter(|name| !name.is_empty()) else {
+        return open.map(Path::to_path_buf).ok_or_else(|| "No vault is open".to_string());
+    };
+    let path = expand_tilde(name);
+    if path.is_absolute() && path.is_dir() {
+        let path = path.canonicalize().map_err(|e| e.to_string())?;
+        if !path.join(".notemaker").is_dir() && open != Some(path.as_path()) {
+            return Err(format!("Not a vault: {}", name));
+        }
+        return Ok(path);
+    }
+    match open {
+        Some(open) if open.file_name().is_some_and(|dir| dir == name) => Ok(open.to_path_buf()),
+        _ => Err(format!("Vault not found: {}", name)),
+    }
+}
+
+/// A path for a new note that doesn't exist yet: `Name.md`, `Name 2.md`, ...
+fn free_note_path(vault: &Path, name: &str) -> Result<PathBuf, String> {
+    let mut candidate = name.to_string();
+    for n in 2.. {
+        let path = vault_note_path(vault, &candidate).map_err(|e| e.to_string())?;
+        if !path.exists() {
+            return Ok(path);
+        }
+        candidate = format!("{} {}", name, n);
+    }
+    unreachable!()
+}
+
+fn load_template(vault: &Path, name: Option<&str>) -> Result<Option<String>, String> {
+    match name.filter(|name| !name.is_empty()) {
+        Some(name) => read_vault_template(vault, name)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("Template not found: {}", name))
+            .map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Follow a link: check the vault, note and template
+pub(crate) fn resolve_link(url: &Url, open_vault: Option<&Path>) -> Result<DeepLinkEvent, String> {
+    if url.scheme() != SCHEME {
+        return Err(format!("Not a {}:// link", SCHEME));
+    }
+    let param = |name: &str| {
+        url.query_pairs()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.into_owned())
+    };
+    let vault = resolve_vault(param("vault").as_deref(), open_vault)?;
+
+    match url.host_str().unwrap_or_default() {
+        "open" => {
+            let path = match param("path").filter(|p| !p.is_empty()) {
+                Some(name) => {
+                    let path = vault_note_path(&vault, &name).map_err(|e| e.to_string())?;
+                    if !path.exists() {
+                        return Err(format!("Note not found: {}", name));
+                    }
+                    Some(path)
+                }
+                None => None,
+            };
+            Ok(DeepLinkEvent::Open { vault, path })
+        }
+        "new" => {
+            let template = param("template").filter(|t| !t.is_empty());
+            load_template(&vault, template.as_deref())?;
+            let title = param("title").filter(|t| !t.trim().is_empty());
+            let name = param("path")
+                .filter(|p| !p.is_empty())
+                .or_else(|| title.clone())
+                .unwrap_or_else(|| "Untitled".to_string());
+            // Checked now so a bad name is reported before the user is asked
+            vault_note_path(&vault, &name).map_err(|e| e.to_string())?;
+            Ok(DeepLinkEvent::New {
+                vault,
+                name,
+                title,
+                template,
+            })
+        }
+        "search" => Ok(DeepLinkEvent::Search {
+            vault,
+            query: param("q").unwrap_or_default(),
+        }),
+        other => Err(format!("Unknown link action: {}", other)),
+    }
+}
+
+/// Resolve a link against the vault open right now
+fn follow_link(app: &AppHandle, url: &Url) -> DeepLinkEvent {
+    let open_vault = app
+        .state::<WatcherState>()
+        .lock()
+        .ok()
+        .and_then(|watcher| watcher.watched_path().cloned());
+    resolve_link(url, open_vault.as_deref()).unwrap_or_else(|message| DeepLinkEvent::Error {
+        url: url.to_string(),
+        message,
+    })
+}
+
+/// Follow a link opened while the app runs and tell the frontend
+pub fn handle_deep_link(app: &AppHandle, url: &Url) {
+    let _ = app.emit("deep-link", follow_link(app, url));
+}
+
+/// Follow the links the app was launched with, once
+///
+/// The frontend calls this after reopening the last vault, so links without
+/// a vault go to it.
+#[tauri::command]
+pub fn take_deep_links(app: AppHandle, state: State<'_, DeepLinkState>) -> Vec<DeepLinkEvent> {
+    let urls = std::mem::take(&mut *state.lock().unwrap());
+    urls.iter().map(|url| follow_link(&app, url)).collect()
+}
+
+/// Create a note as a `new` link asked, at the first free name
+fn create_note(
+    vault: &Path,
+    name: &str,
+    title: Option<String>,
+    template: Option<&str>,
+    session: Option<&EncryptionSession>,
+) -> Result<PathBuf, String> {
+    let vault = resolve_vault(Some(&vault.to_string_lossy()), None)?;
+    let template = load_template(&vault, template)?;
+    let path = free_note_path(&vault, name)?;
+    new_note(&path, title, template, session).map_err(|e| e.to_string())?;
+    Ok(path)
+}
+
+/// Create the note of a `new` link the user agreed to
+#[tauri::command]
+pub fn create_link_note(
+    state: State<'_, EncryptionState>,
+    vault_path: PathBuf,
+    name: String,
+    title: Option<String>,
+    template: Option<String>,
+) -> Result<PathBuf, String> {
+    create_note(&vault_path, &name, title, template.as_deref(), Some(&state.session(&vault_path)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn link(url: &str, open: Option<&Path>) -> Result<DeepLinkEvent, String> {
+        resolve_link(&Url::parse(url).unwrap(), open)
+    }
+
+    #[test]
+    fn resolves_links() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = dir.path().join("notes");
+        fs::create_dir_all(vault.join("Work")).unwrap();
+        fs::create_dir_all(vault.join(".notemaker/templates")).unwrap();
+        let vault = vault.canonicalize().unwrap();
+        fs::write(vault.join("Work/Plan Q3.md"), "# Plan").unwrap();
+
+        let url = format!("notemaker://open?vault={}&path=Work/Plan%20Q3", vault.display());
+        assert_eq!(
+            link(&url, None),
+            Ok(DeepLinkEvent::Open {
+                vault: vault.clone(),
+                path: Some(vault.join("Work/Plan Q3.md")),
+            })
+        );
+        assert!(link("notemaker://open?vault=notes&path=Missing", Some(&vault)).is_err());
+        assert!(link("notemaker://open?path=../secret", Some(&vault)).is_err());
+        assert_eq!(link("notemaker://open", None), Err("No vault is open".to_string()));
+        let url = format!("notemaker://open?vault={}", dir.path().display());
+        assert!(link(&url, None).unwrap_err().starts_with("Not a vault"));
+        let url = format!("notemaker://new?vault={}&path=x", dir.path().display());
+        assert!(link(&url, None).is_err());
+
+        assert_eq!(
+            link("notemaker://search?q=release+notes", Some(&vault)),
+            Ok(DeepLinkEvent::Search {
+                vault: vault.clone(),
+                query: "release notes".to_string(),
+            })
+        );
+
+        fs::write(vault.join(".notemaker/templates/meeting.md"), "# {{title}}\n\n## Agenda\n").unwrap();
+        let new = link("notemaker://new?template=meeting&title=Standup", Some(&vault)).unwrap();
+        assert_eq!(
+            new,
+            DeepLinkEvent::New {
+                vault: vault.clone(),
+                name: "Standup".to_string(),
+                title: Some("Standup".to_string()),
+                template: Some("meeting".to_string()),
+            }
+        );
+        assert!(!vault.join("Standup.md").exists());
+        assert!(link("notemaker://new?template=nope", Some(&vault)).is_err());
+        assert!(link("notemaker://new?path=.notemaker/config", Some(&vault)).is_err());
+
+        let create = || create_note(&vault, "Standup", Some("Standup".to_string()), Some("meeting"), None).unwrap();
+        assert_eq!((create(), create()), (vault.join("Standup.md"), vault.join("Standup 2.md")));
+        assert_eq!(fs::read_to_string(vault.join("Standup.md")).unwrap(), "# Standup\n\n## Agenda\n");
+    }
+}
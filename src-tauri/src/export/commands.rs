@@ -0,0 +1,278 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter, State};
+
+use super::folder::export_folder_tree;
+use super::ipynb::notebook_to_ipynb;
+use super::pandoc::{convert_markdown, resolve_pandoc};
+use super::pdf::{page_style, PdfEngine};
+use super::render::{
+    flatten_wikilinks, html_document, load_note_document, load_note_document_with, render_markdown, RenderOptions,
+};
+use super::site::build_site;
+use super::types::{
+    DocumentExportOptions, ExportError, ExportFormat, FolderExport, HtmlExport, HtmlExportOptions, PdfExport,
+    PdfExportOptions, SiteExport, SiteExportOptions,
+};
+use crate::fs::{
+    find_vault_root, read_vault_config, EncryptionSession, EncryptionState, ExportSettings, PdfSettings, WatchPause,
+};
+
+/// Directory relative asset links of a note are resolved against
+pub(crate) fn note_base_dir(path: &Path) -> PathBuf {
+    path.parent().map(Path::to_path_buf).unwrap_or_default()
+}
+
+/// Render a note or notebook to a standalone HTML document, decrypting it
+/// when the session is unlocked
+pub fn render_note_html(
+    path: &Path,
+    options: &HtmlExportOptions,
+    session: Option<&EncryptionSession>,
+) -> Result<HtmlExport, ExportError> {
+    let document = load_note_document_with(path, session)?;
+    let base_dir = note_base_dir(path);
+
+    let body = render_markdown(
+        &document.markdown,
+        &RenderOptions {
+            base_dir: &base_dir,
+            embed_assets: options.embed_assets,
+            highlight_code: options.highlight_code,
+            theme: options.theme.as_deref(),
+            link_resolver: None,
+            md_links_to_html: false,
+        },
+    );
+
+    Ok(HtmlExport {
+        html: html_document(&document.title, &body, ""),
+        title: document.title,
+        output_path: None,
+    })
+}
+
+/// Export a note or notebook to standalone HTML
+///
+/// Notebook blocks are concatenated in order, code is syntax-highlighted and
+/// local images are embedded as data URIs. When `output_path` is set the
+/// document is also written to disk.
+#[tauri::command]
+pub async fn export_note_html(path: PathBuf, options: Option<HtmlExportOptions>) -> Result<HtmlExport, ExportError> {
+    let options = options.unwrap_or_default();
+    let mut export = render_note_html(&path, &options, None)?;
+
+    if let Some(output_path) = &options.output_path {
+        if let Some(parent) = output_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(output_path, &export.html)?;
+        export.output_path = Some(output_path.clone());
+    }
+
+    Ok(export)
+}
+
+/// Export a note or notebook to PDF
+///
+/// The note is rendered to self-contained HTML and printed by an external
+/// converter (Chromium-based browser or wkhtmltopdf). Page size, margins and
+/// the converter come from the vault's `export.pdf` settings unless
+/// overridden in `options`.
+#[tauri::command]
+pub async fn export_note_pdf(path: PathBuf, options: PdfExportOptions) -> Result<PdfExport, ExportError> {
+    let settings = match find_vault_root(&path) {
+        Some(vault) => read_vault_config(&vault)?.export.pdf,
+        None => PdfSettings::default(),
+    };
+    let page_size = options.page_size.clone().unwrap_or(settings.page_size);
+    let margin_mm = options.margin_mm.unwrap_or(settings.margin_mm);
+    let engine = PdfEngine::resolve(settings.engine.as_deref())?;
+
+    let document = load_note_document(&path)?;
+    let base_dir = note_base_dir(&path);
+    let body = render_markdown(
+        &document.markdown,
+        &RenderOptions {
+            base_dir: &base_dir,
+            embed_assets: true,
+            highlight_code: options.highlight_code,
+            theme: options.theme.as_deref(),
+            link_resolver: None,
+            md_links_to_html: false,
+        },
+    );
+    let html = html_document(&document.title, &body, &page_style(&page_size, margin_mm));
+    engine.print_html(&html, &options.output_path, &page_size, margin_mm)?;
+
+    Ok(PdfExport {
+        title: document.title,
+        output_path: options.output_path,
+        engine: engine.name(),
+    })
+}
+
+/// Export a notebook to a Jupyter notebook (nbformat 4)
+///
+/// Writes next to the notebook as `<name>.ipynb` unless `output_path` is set.
+/// Returns the path of the written file.
+#[tauri::command]
+pub async fn export_notebook_ipynb(notebook_path: PathBuf, output_path: Option<PathBuf>) -> Result<PathBuf, ExportError> {
+    let notebook = notebook_to_ipynb(&notebook_path)?;
+    let output_path = output_path.unwrap_or_else(|| notebook_path.with_extension("ipynb"));
+
+    if let Some(parent) = output_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let content = serde_json::to_string_pretty(&notebook)
+        .map_err(|e| ExportError::Io(std::io::Error::other(e)))?;
+    fs::write(&output_path, content + "\n")?;
+
+    Ok(output_path)
+}
+
+/// Export the vault as a static HTML site
+///
+/// Every non-encrypted note is rendered to a page at its vault-relative path
+/// with a navigation sidebar and a backlinks section. Notes with
+/// `publish: false` are left out; with `only_published` only notes marked
+/// `publish: true` are included. Images referenced by published pages are
+/// copied next to them.
+#[tauri::command]
+pub async fn export_site(
+    vault_path: PathBuf,
+    output_dir: PathBuf,
+    options: Option<SiteExportOptions>,
+) -> Result<SiteExport, ExportError> {
+    let _pause = WatchPause::new(&output_dir);
+    build_site(&vault_path, &output_dir, &options.unwrap_or_default())
+}
+
+/// Export a note or notebook to DOCX (or ODT) through pandoc
+///
+/// Headings, code blocks, tables and local images are preserved; wiki links
+/// become their display text. pandoc is taken from the vault's
+/// `export.pandoc` settings or PATH. Writes next to the note unless
+/// `output_path` is set and returns the written path.
+#[tauri::command]
+pub async fn export_note_docx(path: PathBuf, options: Option<DocumentExportOptions>) -> Result<PathBuf, ExportError> {
+    let options = options.unwrap_or_default();
+    let vault = find_vault_root(&path);
+    let settings = match &vault {
+        Some(vault) => read_vault_config(vault)?.export,
+        None => ExportSettings::default(),
+    };
+    let pandoc = resolve_pandoc(&settings.pandoc)?;
+    let reference_doc = match (&vault, &settings.pandoc.reference_doc) {
+        (Some(vault), Some(reference)) => Some(vault.join(reference)),
+        _ => None,
+    };
+
+    let document = load_note_document(&path)?;
+    let markdown = flatten_wikilinks(&document.markdown);
+    let output_path = options
+        .output_path
+        .unwrap_or_else(|| path.with_extension(options.format.extension()));
+    if let Some(parent) = output_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let base_dir = note_base_dir(&path);
+    convert_markdown(
+        &pandoc,
+        &markdown,
+        options.format,
+        &base_dir,
+        reference_doc.as_deref(),
+        &output_path,
+    )?;
+
+    Ok(output_path)
+}
+
+/// Export a whole folder tree in one format
+///
+/// Notebooks are flattened into a single document, encrypted notes are
+/// decrypted when the session is unlocked (and skipped otherwise), and
+/// attachments such as `Note.assets/` images are copied into an `assets/`
+/// folder next to each exported file with links rewritten to match.
+/// Emits `export-progress` before each note.
+#[tauri::command]
+pub async fn export_folder(
+    app: AppHandle,
+    state: State<'_, EncryptionState>,
+    folder_path: PathBuf,
+    format: ExportFormat,
+    output_dir: PathBuf,
+) -> Result<FolderExport, ExportError> {
+    let _pause = WatchPause::new(&output_dir);
+    export_folder_tree(&folder_path, format, &output_dir, Some(&state.session(&folder_path)), |progress| {
+        let _ = app.emit("export-progress", progress);
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::{write_notebook_index_with, BlockType, NotebookBlock, NotebookIndex};
+
+    #[test]
+    fn renders_standalone_html() {
+        let dir = tempfile::tempdir().unwrap();
+        let note = dir.path().join("Note.md");
+        fs::write(dir.path().join("pic.png"), [0x89, b'P', b'N', b'G']).unwrap();
+        fs::write(
+            &note,
+            "---\ntitle: Release notes\n---\n# Changes\n\n```rust\nfn main() {}\n```\n\n![pic](pic.png)\n",
+        )
+        .unwrap();
+
+        let export = render_note_html(&note, &HtmlExportOptions::default(), None).unwrap();
+        assert_eq!(export.title, "Release notes");
+        assert!(export.html.starts_with("<!DOCTYPE html>"));
+        assert!(export.html.contains("<title>Release notes</title>"));
+        assert!(!export.html.contains("title: Release notes"));
+        assert!(export.html.contains("src=\"data:image/png;base64,"));
+        assert!(!export.html.contains("<code class=\"language-rust\">"));
+
+        let plain = HtmlExportOptions {
+            embed_assets: false,
+            highlight_code: false,
+            ..Default::default()
+        };
+        let export = render_note_html(&note, &plain, None).unwrap();
+        assert!(export.html.contains("src=\"pic.png\""));
+        assert!(export.html.contains("<code class=\"language-rust\">fn main() {}"));
+    }
+
+    #[test]
+    fn renders_notebook_blocks_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let notebook = dir.path().join("Analysis.md");
+        fs::create_dir(&notebook).unwrap();
+        fs::write(notebook.join("b.md"), "Intro text").unwrap();
+        fs::write(notebook.join("a.py"), "print('hi')").unwrap();
+        let block = |id: &str, block_type, file: &str, language: Option<&str>| NotebookBlock {
+            id: id.to_string(),
+            block_type,
+            file: file.to_string(),
+            language: language.map(str::to_string),
+            ..Default::default()
+        };
+        let blocks = vec![
+            block("b", BlockType::Markdown, "b.md", None),
+            block("a", BlockType::Code, "a.py", Some("python")),
+        ];
+        write_notebook_index_with(&notebook, &NotebookIndex { blocks, ..Default::default() }, None).unwrap();
+
+        let plain = HtmlExportOptions {
+            highlight_code: false,
+            ..Default::default()
+        };
+        let export = render_note_html(&notebook, &plain, None).unwrap();
+        assert_eq!(export.title, "Analysis");
+        let intro = export.html.find("Intro text").unwrap();
+        let code = export.html.find("<code class=\"language-python\">print(").unwrap();
+        assert!(intro < code);
+    }
+}
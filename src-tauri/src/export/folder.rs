@@ -0,0 +1,271 @@
+//! Bulk export of a folder tree.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::commands::note_base_dir;
+use super::pandoc::{convert_markdown, resolve_pandoc};
+use super::pdf::{page_style, PdfEngine};
+use super::render::{flatten_wikilinks, html_document, load_note_document_with, render_markdown, RenderOptions};
+use super::types::{DocumentFormat, ExportError, ExportFormat, ExportProgress, FolderExport, SkippedExport};
+use crate::fs::links::{extract_wikilinks, rewrite_markdown_links};
+use crate::fs::{find_vault_root, note_assets_dir, read_vault_config, walk_notes, EncryptionSession, ExportSettings};
+
+/// Folder that attachments are collected into, next to each exported file
+const ASSETS_DIR: &str = "assets";
+
+/// External converter prepared once for the whole export
+enum Converter {
+    None,
+    Pdf { engine: PdfEngine, page_size: String, margin_mm: u32 },
+    Pandoc { pandoc: PathBuf, format: DocumentFormat, reference_doc: Option<PathBuf> },
+}
+
+impl Converter {
+    fn prepare(format: ExportFormat, folder_path: &Path) -> Result<Self, ExportError> {
+        let vault = find_vault_root(folder_path);
+        let settings = match &vault {
+            Some(vault) => read_vault_config(vault)?.export,
+            None => ExportSettings::default(),
+        };
+        let reference_doc = match (&vault, &settings.pandoc.reference_doc) {
+            (Some(vault), Some(reference)) => Some(vault.join(reference)),
+            _ => None,
+        };
+
+        Ok(match format {
+            ExportFormat::Markdown | ExportFormat::Html => Converter::None,
+            ExportFormat::Pdf => Converter::Pdf {
+                engine: PdfEngine::resolve(settings.pdf.engine.as_deref())?,
+                page_size: settings.pdf.page_size,
+                margin_mm: settings.pdf.margin_mm,
+            },
+            ExportFormat::Docx | ExportFormat::Odt => Converter::Pandoc {
+                pandoc: resolve_pandoc(&settings.pandoc)?,
+                format: if format == ExportFormat::Odt {
+                    DocumentFormat::Odt
+                } else {
+                    DocumentFormat::Docx
+                },
+                reference_doc,
+            },
+        })
+    }
+}
+
+/// Turn `![[file.png]]` embeds into regular image links
+fn embeds_to_images(markdown: &str) -> String {
+    let mut result = String::with_capacity(markdown.len());
+    let mut last = 0;
+
+    for link in extract_wikilinks(markdown) {
+        let is_file = Path::new(&link.target)
+            .extension()
+            .map(|e| e != "md")
+            .unwrap_or(false);
+        if !link.embed || !is_file {
+            continue;
+        }
+        result.push_str(&markdown[last..link.start]);
+        result.push_str(&format!(
+            "![{}](<{}>)",
+            link.display.clone().unwrap_or_default(),
+            link.target
+        ));
+        last = link.end;
+    }
+
+    result.push_str(&markdown[last..]);
+    result
+}
+
+/// Copy the local files a note links to into `assets/` next to the exported
+/// file and point the links there
+///
+/// `copied` maps source files to their new name within an output folder so
+/// notes sharing an attachment reuse one copy.
+fn relocate_assets(
+    markdown: &str,
+    note: &Path,
+    base_dir: &Path,
+    out_dir: &Path,
+    copied: &mut HashMap<(PathBuf, PathBuf), String>,
+    assets: &mut usize,
+) -> Result<String, ExportError> {
+    let mut error = None;
+    // Embeds name the file only; attachments live in `<note>.assets/`
+    let note_assets = note_assets_dir(note);
+
+    let rewritten = rewrite_markdown_links(&embeds_to_images(markdown), |target| {
+        if error.is_some() || target.contains("://") || target.starts_with('#') || target.starts_with("data:") {
+            return None;
+        }
+        let path_part = target.split('#').next().unwrap_or(target).replace("%20", " ");
+        let path_part = path_part.trim_start_matches("./");
+        let mut source = base_dir.join(path_part);
+        let fallback = note_assets.join(path_part);
+        if !source.is_file() && fallback.is_file() {
+            source = fallback;
+        }
+        if !source.is_file() || source.extension().map(|e| e == "md").unwrap_or(false) {
+            return None;
+        }
+
+        let key = (out_dir.to_path_buf(), source.clone());
+        if let Some(name) = copied.get(&key) {
+            return Some(format!("{}/{}", ASSETS_DIR, name));
+        }
+
+        let file_name = source.file_name()?.to_string_lossy().to_string();
+        let assets_dir = out_dir.join(ASSETS_DIR);
+        let mut name = file_name.clone();
+        let mut n = 2;
+        while assets_dir.join(&name).exists() {
+            name = format!("{}-{}", n, file_name);
+            n += 1;
+        }
+
+        let result = fs::create_dir_all(&assets_dir).and_then(|_| fs::copy(&source, assets_dir.join(&name)));
+        if let Err(e) = result {
+            error = Some(e);
+            return None;
+        }
+
+        *assets += 1;
+        copied.insert(key, name.clone());
+        Some(format!("{}/{}", ASSETS_DIR, name))
+    });
+
+    match error {
+        Some(e) => Err(e.into()),
+        None => Ok(rewritten),
+    }
+}
+
+/// Export every note and notebook below `folder_path` into `output_dir`
+///
+/// The folder structure is kept; each note becomes one file in `format`.
+/// `on_progress` is called before each note is exported.
+pub(crate) fn export_folder_tree<F>(
+    folder_path: &Path,
+    format: ExportFormat,
+    output_dir: &Path,
+    session: Option<&EncryptionSession>,
+    mut on_progress: F,
+) -> Result<FolderExport, ExportError>
+where
+    F: FnMut(ExportProgress),
+{
+    if !folder_path.is_dir() {
+        return Err(ExportError::NotFound(folder_path.display().to_string()));
+    }
+    let converter = Converter::prepare(format, folder_path)?;
+
+    let mut notes = Vec::new();
+    walk_notes(folder_path, &mut notes);
+    notes.sort();
+
+    let mut report = FolderExport {
+        output_dir: output_dir.to_path_buf(),
+        exported: Vec::new(),
+        assets: 0,
+        skipped: Vec::new(),
+    };
+    let mut copied = HashMap::new();
+    let total = notes.len();
+
+    for (i, note) in notes.iter().enumerate() {
+        on_progress(ExportProgress {
+            current: i + 1,
+            total,
+            path: note.clone(),
+        });
+
+        let document = match load_note_document_with(note, session) {
+            Ok(document) => document,
+            Err(ExportError::Encrypted(_)) => {
+                report.skipped.push(SkippedExport {
+                    path: note.clone(),
+                    reason: "Encrypted and the session is locked".to_string(),
+                });
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+
+        let Ok(rel) = note.strip_prefix(folder_path) else {
+            continue;
+        };
+        let output_path = output_dir.join(rel).with_extension(format.extension());
+        let out_dir = output_path.parent().unwrap_or(output_dir).to_path_buf();
+        fs::create_dir_all(&out_dir)?;
+        let base_dir = note_base_dir(note);
+
+        let result = match &converter {
+            Converter::Pdf {
+                engine,
+                page_size,
+                margin_mm,
+            } => {
+                // Images are embedded, so nothing is copied next to the PDF
+                let body = render_markdown(
+                    &document.markdown,
+                    &RenderOptions {
+                        base_dir: &base_dir,
+                        embed_assets: true,
+                        highlight_code: true,
+                        theme: None,
+                        link_resolver: None,
+                        md_links_to_html: false,
+                    },
+                );
+                let html = html_document(&document.title, &body, &page_style(page_size, *margin_mm));
+                engine.print_html(&html, &output_path, page_size, *margin_mm)
+            }
+            _ => {
+                let markdown = relocate_assets(&document.markdown, note, &base_dir, &out_dir, &mut copied, &mut report.assets)?;
+                match &converter {
+                    Converter::Pandoc {
+                        pandoc,
+                        format,
+                        reference_doc,
+                    } => convert_markdown(
+                        pandoc,
+                        &flatten_wikilinks(&markdown),
+                        *format,
+                        &out_dir,
+                        reference_doc.as_deref(),
+                        &output_path,
+                    ),
+                    _ if format == ExportFormat::Html => {
+                        let body = render_markdown(
+                            &markdown,
+                            &RenderOptions {
+                                base_dir: &out_dir,
+                                embed_assets: false,
+                                highlight_code: true,
+                                theme: None,
+                                link_resolver: None,
+                                md_links_to_html: true,
+                            },
+                        );
+                        fs::write(&output_path, html_document(&document.title, &body, "")).map_err(ExportError::from)
+                    }
+                    _ => fs::write(&output_path, markdown).map_err(ExportError::from),
+                }
+            }
+        };
+
+        match result {
+            Ok(()) => report.exported.push(output_path),
+            Err(ExportError::Converter(reason)) => report.skipped.push(SkippedExport {
+                path: note.clone(),
+                reason,
+            }),
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(report)
+}
@@ -0,0 +1,225 @@
+//! Conversion of notemaker notebooks to Jupyter nbformat 4.
+
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::path::Path;
+
+use super::types::ExportError;
+use crate::fs::notebook::math_markdown;
+use crate::fs::table::table_markdown;
+use crate::fs::{is_encrypted, is_notebook, read_block_outputs, read_notebook_index, BlockType, StoredOutput};
+
+/// Split cell text into nbformat's list-of-lines representation
+fn source_lines(content: &str) -> Vec<String> {
+    let content = content.trim_end_matches('\n');
+    if content.is_empty() {
+        return Vec::new();
+    }
+    content.split_inclusive('\n').map(str::to_string).collect()
+}
+
+/// Make a block ID valid as an nbformat cell ID (`^[a-zA-Z0-9-_]+$`, max 64 chars)
+fn cell_id(id: &str, index: usize) -> String {
+    let id: String = id
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+        .take(64)
+        .collect();
+    if id.is_empty() {
+        format!("cell-{}", index)
+    } else {
+        id
+    }
+}
+
+/// nbformat stream outputs of a stored block result
+fn cell_outputs(output: Option<&StoredOutput>) -> Vec<Value> {
+    let Some(output) = output else {
+        return Vec::new();
+    };
+    [("stdout", &output.stdout), ("stderr", &output.stderr)]
+        .into_iter()
+        .filter(|(_, text)| !text.is_empty())
+        .map(|(name, text)| {
+            json!({
+                "output_type": "stream",
+                "name": name,
+                "text": source_lines(text),
+            })
+        })
+        .collect()
+}
+
+/// Kernel spec and language info for the notebook's dominant language
+fn kernel_metadata(language: &str) -> (Value, Value) {
+    let (name, display_name, language_name, extension) = match language {
+        "python" => ("python3", "Python 3", "python", ".py"),
+        "javascript" => ("javascript", "JavaScript (Node.js)", "javascript", ".js"),
+        "typescript" => ("tslab", "TypeScript", "typescript", ".ts"),
+        "bash" | "shell" | "sh" => ("bash", "Bash", "bash", ".sh"),
+        "ruby" => ("ruby", "Ruby", "ruby", ".rb"),
+        "r" => ("ir", "R", "R", ".r"),
+        "julia" => ("julia", "Julia", "julia", ".jl"),
+        "go" => ("gophernotes", "Go", "go", ".go"),
+        "rust" => ("rust", "Rust", "rust", ".rs"),
+        other => (other, other, other, ""),
+    };
+
+    let kernelspec = json!({
+        "name": name,
+        "display_name": display_name,
+        "language": language_name,
+    });
+    let mut language_info = json!({ "name": language_name });
+    if !extension.is_empty() {
+        language_info["file_extension"] = json!(extension);
+    }
+    (kernelspec, language_info)
+}
+
+/// Convert a notebook into an nbformat 4 document
+///
+/// Markdown blocks become markdown cells and code blocks become code cells
+/// with their language recorded in the cell metadata and their stored output.
+/// Encrypted blocks are exported as a placeholder markdown cell.
+pub(crate) fn notebook_to_ipynb(notebook_path: &Path) -> Result<Value, ExportError> {
+    if !is_notebook(notebook_path) {
+        return Err(ExportError::NotFound(notebook_path.display().to_string()));
+    }
+
+    let index = read_notebook_index(notebook_path)?;
+    // Encrypted outputs belong to encrypted blocks, which are left out anyway
+    let outputs = read_block_outputs(notebook_path, None).unwrap_or_default();
+    let mut cells = Vec::new();
+    let mut language_counts: HashMap<String, usize> = HashMap::new();
+
+    for (i, block) in index.blocks.iter().enumerate() {
+        let block_path = notebook_path.join(&block.file);
+        let content = if block_path.exists() {
+            std::fs::read_to_string(&block_path)?
+        } else {
+            String::new()
+        };
+        let id = cell_id(&block.id, i);
+
+        if is_encrypted(&content) {
+            cells.push(json!({
+                "cell_type": "markdown",
+                "id": id,
+                "metadata": { "notemaker": { "encrypted": true } },
+                "source": ["*[Encrypted block]*"],
+            }));
+            continue;
+        }
+
+        match block.block_type {
+            BlockType::Markdown => cells.push(json!({
+                "cell_type": "markdown",
+                "id": id,
+                "metadata": {},
+                "source": source_lines(&content),
+            })),
+            BlockType::Table => cells.push(json!({
+                "cell_type": "markdown",
+                "id": id,
+                "metadata": { "notemaker": { "table": true } },
+                "source": source_lines(&table_markdown(&content, block.language.as_deref())),
+            })),
+            BlockType::Math => cells.push(json!({
+                "cell_type": "markdown",
+                "id": id,
+                "metadata": {},
+                "source": source_lines(&math_markdown(&content)),
+            })),
+            BlockType::Code => {
+                let mut metadata = Map::new();
+                if let Some(language) = &block.language {
+                    *language_counts.entry(language.clone()).or_default() += 1;
+                    metadata.insert("language".to_string(), json!(language));
+                    metadata.insert("vscode".to_string(), json!({ "languageId": language }));
+                }
+                cells.push(json!({
+                    "cell_type": "code",
+                    "id": id,
+                    "metadata": metadata,
+                    "execution_count": null,
+                    "outputs": cell_outputs(outputs.get(&block.id)),
+                    "source": source_lines(&content),
+                }));
+            }
+        }
+    }
+
+    let language = language_counts
+        .into_iter()
+        .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(&a.0)))
+        .map(|(language, _)| language)
+        .unwrap_or_else(|| "python".to_string());
+    let (kernelspec, language_info) = kernel_metadata(&language);
+
+    Ok(json!({
+        "nbformat": 4,
+        "nbformat_minor": 5,
+        "metadata": {
+            "kernelspec": kernelspec,
+            "language_info": language_info,
+        },
+        "cells": cells,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::{write_notebook_index_with, NotebookBlock, NotebookIndex};
+    use std::fs;
+
+    #[test]
+    fn converts_blocks_to_cells() {
+        let dir = tempfile::tempdir().unwrap();
+        let notebook = dir.path().join("Analysis.md");
+        fs::create_dir(&notebook).unwrap();
+        let files = [
+            ("intro", BlockType::Markdown, "intro.md", None, "# Title\n\nText\n"),
+            ("load", BlockType::Code, "load.py", Some("python"), "import csv\nrows = []"),
+            ("plot", BlockType::Code, "plot.py", Some("python"), "print(rows)"),
+            ("fetch", BlockType::Code, "fetch.sh", Some("shell"), "curl example.com"),
+        ];
+        let mut blocks = Vec::new();
+        for (id, block_type, file, language, content) in files {
+            fs::write(notebook.join(file), content).unwrap();
+            blocks.push(NotebookBlock {
+                id: id.to_string(),
+                block_type,
+                file: file.to_string(),
+                language: language.map(str::to_string),
+                ..Default::default()
+            });
+        }
+        write_notebook_index_with(&notebook, &NotebookIndex { blocks, ..Default::default() }, None).unwrap();
+
+        let ipynb = notebook_to_ipynb(&notebook).unwrap();
+        assert_eq!(ipynb["nbformat"], 4);
+        assert_eq!(ipynb["metadata"]["kernelspec"]["name"], "python3");
+        assert_eq!(ipynb["metadata"]["language_info"]["file_extension"], ".py");
+
+        let cells = ipynb["cells"].as_array().unwrap();
+        assert_eq!(cells.len(), 4);
+        assert_eq!(cells[0]["cell_type"], "markdown");
+        assert_eq!(cells[0]["source"], json!(["# Title\n", "\n", "Text"]));
+        assert_eq!(cells[1]["cell_type"], "code");
+        assert_eq!(cells[1]["id"], "load");
+        assert_eq!(cells[1]["source"], json!(["import csv\n", "rows = []"]));
+        assert_eq!(cells[1]["outputs"], json!([]));
+        assert_eq!(cells[1]["execution_count"], Value::Null);
+        assert_eq!(cells[3]["metadata"]["language"], "shell");
+    }
+
+    #[test]
+    fn sanitizes_cell_ids() {
+        assert_eq!(cell_id("block-1_a", 0), "block-1_a");
+        assert_eq!(cell_id("a.b c", 0), "abc");
+        assert_eq!(cell_id("…", 3), "cell-3");
+        assert_eq!(cell_id(&"x".repeat(80), 0).len(), 64);
+    }
+}
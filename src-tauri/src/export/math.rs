@@ -0,0 +1,470 @@
+//! Conversion of LaTeX math to MathML for HTML and PDF export.
+//!
+//! Covers the commonly used subset: scripts, fractions, roots, `\left`/
+//! `\right`, matrix-like environments, `\text` and friends, greek letters and
+//! the usual operators and functions. Unknown commands are kept as text so
+//! nothing silently disappears.
+
+use super::render::escape_html;
+
+const GREEK: &[(&str, &str)] = &[
+    ("alpha", "α"), ("beta", "β"), ("gamma", "γ"), ("delta", "δ"), ("epsilon", "ϵ"), ("varepsilon", "ε"),
+    ("zeta", "ζ"), ("eta", "η"), ("theta", "θ"), ("vartheta", "ϑ"), ("iota", "ι"), ("kappa", "κ"),
+    ("lambda", "λ"), ("mu", "μ"), ("nu", "ν"), ("xi", "ξ"), ("pi", "π"), ("rho", "ρ"), ("sigma", "σ"),
+    ("tau", "τ"), ("upsilon", "υ"), ("phi", "ϕ"), ("varphi", "φ"), ("chi", "χ"), ("psi", "ψ"),
+    ("omega", "ω"), ("Gamma", "Γ"), ("Delta", "Δ"), ("Theta", "Θ"), ("Lambda", "Λ"), ("Xi", "Ξ"),
+    ("Pi", "Π"), ("Sigma", "Σ"), ("Phi", "Φ"), ("Psi", "Ψ"), ("Omega", "Ω"), ("infty", "∞"),
+    ("partial", "∂"), ("nabla", "∇"), ("ell", "ℓ"), ("hbar", "ℏ"), ("emptyset", "∅"),
+];
+
+const OPERATORS: &[(&str, &str)] = &[
+    ("times", "×"), ("cdot", "⋅"), ("div", "÷"), ("pm", "±"), ("mp", "∓"), ("le", "≤"), ("leq", "≤"),
+    ("ge", "≥"), ("geq", "≥"), ("ne", "≠"), ("neq", "≠"), ("approx", "≈"), ("equiv", "≡"), ("sim", "∼"),
+    ("propto", "∝"), ("to", "→"), ("rightarrow", "→"), ("leftarrow", "←"), ("Rightarrow", "⇒"),
+    ("Leftarrow", "⇐"), ("leftrightarrow", "↔"), ("iff", "⟺"), ("implies", "⟹"), ("mapsto", "↦"),
+    ("in", "∈"), ("notin", "∉"), ("subset", "⊂"), ("subseteq", "⊆"), ("supset", "⊃"), ("supseteq", "⊇"),
+    ("cup", "∪"), ("cap", "∩"), ("setminus", "∖"), ("forall", "∀"), ("exists", "∃"), ("neg", "¬"),
+    ("land", "∧"), ("lor", "∨"), ("wedge", "∧"), ("vee", "∨"), ("cdots", "⋯"), ("ldots", "…"),
+    ("dots", "…"), ("circ", "∘"), ("ast", "∗"), ("star", "⋆"), ("langle", "⟨"), ("rangle", "⟩"),
+    ("lfloor", "⌊"), ("rfloor", "⌋"), ("lceil", "⌈"), ("rceil", "⌉"), ("vert", "|"), ("mid", "∣"),
+    ("parallel", "∥"), ("perp", "⊥"), ("angle", "∠"), ("lbrace", "{"), ("rbrace", "}"),
+];
+
+/// Operators whose scripts go above and below in display math
+const LIMIT_OPERATORS: &[(&str, &str)] = &[
+    ("sum", "∑"), ("prod", "∏"), ("coprod", "∐"), ("bigcup", "⋃"), ("bigcap", "⋂"),
+];
+
+const INTEGRALS: &[(&str, &str)] = &[("int", "∫"), ("iint", "∬"), ("iiint", "∭"), ("oint", "∮")];
+
+const FUNCTIONS: &[&str] = &[
+    "sin", "cos", "tan", "cot", "sec", "csc", "arcsin", "arccos", "arctan", "sinh", "cosh", "tanh", "log", "ln",
+    "exp", "det", "gcd", "deg", "dim", "ker", "arg", "Pr",
+];
+
+/// Functions whose subscript goes below in display math
+const LIMIT_FUNCTIONS: &[&str] = &["lim", "max", "min", "sup", "inf"];
+
+fn lookup(table: &[(&str, &'static str)], name: &str) -> Option<&'static str> {
+    table.iter().find(|(n, _)| *n == name).map(|(_, s)| *s)
+}
+
+fn mrow(nodes: Vec<String>) -> String {
+    if nodes.len() == 1 {
+        nodes.into_iter().next().unwrap_or_default()
+    } else {
+        format!("<mrow>{}</mrow>", nodes.concat())
+    }
+}
+
+fn mo(op: &str) -> String {
+    format!("<mo>{}</mo>", escape_html(op))
+}
+
+/// One parsed item and whether its scripts may become limits
+struct Atom {
+    node: String,
+    limits: bool,
+}
+
+impl Atom {
+    fn new(node: String) -> Self {
+        Atom { node, limits: false }
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    display: bool,
+}
+
+impl Parser {
+    fn skip_space(&mut self) {
+        while self.chars.get(self.pos).is_some_and(|c| c.is_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_space();
+        self.chars.get(self.pos).copied()
+    }
+
+    /// Whether a command starts at the current position
+    fn at_command(&mut self, names: &[&str]) -> bool {
+        if self.peek() != Some('\\') {
+            return false;
+        }
+        let start = self.pos;
+        self.pos += 1;
+        let name = self.command_name();
+        self.pos = start;
+        names.contains(&name.as_str())
+    }
+
+    /// Name of the command whose backslash was just consumed
+    fn command_name(&mut self) -> String {
+        let start = self.pos;
+        while self.chars.get(self.pos).is_some_and(|c| c.is_ascii_alphabetic()) {
+            self.pos += 1;
+        }
+        if self.pos == start {
+            if let Some(c) = self.chars.get(self.pos) {
+                self.pos += 1;
+                return c.to_string();
+            }
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+
+    /// Unparsed content of a `{...}` group, or the next character
+    fn raw_group(&mut self) -> String {
+        if self.peek() != Some('{') {
+            let c = self.chars.get(self.pos).copied();
+            self.pos += usize::from(c.is_some());
+            return c.map(String::from).unwrap_or_default();
+        }
+        self.pos += 1;
+        let start = self.pos;
+        let mut depth = 1;
+        while let Some(&c) = self.chars.get(self.pos) {
+            match c {
+                '{' => depth += 1,
+                '}' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        break;
+                    }
+                }
+                _ => {}
+            }
+            self.pos += 1;
+        }
+        let text = self.chars[start..self.pos].iter().collect();
+        self.pos += 1;
+        text
+    }
+
+    /// Items up to the end of the enclosing group, cell or `\left` pair
+    fn row(&mut self, stop: Option<char>) -> Vec<String> {
+        let mut nodes = Vec::new();
+        loop {
+            match self.peek() {
+                None | Some('}') | Some('&') => break,
+                Some(c) if Some(c) == stop => break,
+                Some('\\') if self.at_command(&["\\", "right", "end"]) => break,
+                _ => {}
+            }
+            nodes.push(self.scripted());
+        }
+        nodes
+    }
+
+    /// Argument of a command or script: a group or a single item
+    fn argument(&mut self) -> String {
+        match self.peek() {
+            Some('{') => {
+                self.pos += 1;
+                let nodes = self.row(None);
+                if self.peek() == Some('}') {
+                    self.pos += 1;
+                }
+                mrow(nodes)
+            }
+            Some(c) if c.is_ascii_digit() => {
+                self.pos += 1;
+                format!("<mn>{}</mn>", c)
+            }
+            Some(_) => self.atom().node,
+            None => String::new(),
+        }
+    }
+
+    /// An item with its sub- and superscripts
+    fn scripted(&mut self) -> String {
+        let base = self.atom();
+        let (mut sub, mut sup) = (None, None);
+        loop {
+            match self.peek() {
+                Some('_') if sub.is_none() => {
+                    self.pos += 1;
+                    sub = Some(self.argument());
+                }
+                Some('^') if sup.is_none() => {
+                    self.pos += 1;
+                    sup = Some(self.argument());
+                }
+                Some('\'') if sup.is_none() => {
+                    let mut primes = String::new();
+                    while self.peek() == Some('\'') {
+                        self.pos += 1;
+                        primes.push('′');
+                    }
+                    sup = Some(mo(&primes));
+                }
+                _ => break,
+            }
+        }
+
+        let (under, over, both) = if base.limits && self.display {
+            ("munder", "mover", "munderover")
+        } else {
+            ("msub", "msup", "msubsup")
+        };
+        match (sub, sup) {
+            (None, None) => base.node,
+            (Some(sub), None) => format!("<{0}>{1}{2}</{0}>", under, base.node, sub),
+            (None, Some(sup)) => format!("<{0}>{1}{2}</{0}>", over, base.node, sup),
+            (Some(sub), Some(sup)) => format!("<{0}>{1}{2}{3}</{0}>", both, base.node, sub, sup),
+        }
+    }
+
+    fn atom(&mut self) -> Atom {
+        let Some(c) = self.peek() else {
+            return Atom::new(String::new());
+        };
+        self.pos += 1;
+        match c {
+            '{' => {
+                let nodes = self.row(None);
+                if self.peek() == Some('}') {
+                    self.pos += 1;
+                }
+                Atom::new(mrow(nodes))
+            }
+            '\\' => {
+                let name = self.command_name();
+                self.command(&name)
+            }
+            c if c.is_ascii_digit() || c == '.' => {
+                let mut number = c.to_string();
+                while let Some(&d) = self.chars.get(self.pos).filter(|d| d.is_ascii_digit() || **d == '.') {
+                    number.push(d);
+                    self.pos += 1;
+                }
+                Atom::new(format!("<mn>{}</mn>", number))
+            }
+            c if c.is_alphabetic() => Atom::new(format!("<mi>{}</mi>", c)),
+            c => Atom::new(mo(&c.to_string())),
+        }
+    }
+
+    /// Delimiter following `\left` or `\right`; `.` means none
+    fn delimiter(&mut self) -> String {
+        match self.peek() {
+            Some('\\') => {
+                self.pos += 1;
+                let name = self.command_name();
+                lookup(OPERATORS, &name).map(str::to_string).unwrap_or(name)
+            }
+            Some('.') => {
+                self.pos += 1;
+                String::new()
+            }
+            Some(c) => {
+                self.pos += 1;
+                c.to_string()
+            }
+            None => String::new(),
+        }
+    }
+
+    fn fenced(open: &str, body: String, close: &str) -> String {
+        let fence = |d: &str| match d {
+            "" => String::new(),
+            d => format!("<mo fence=\"true\">{}</mo>", escape_html(d)),
+        };
+        format!("<mrow>{}{}{}</mrow>", fence(open), body, fence(close))
+    }
+
+    /// Rows and cells of an environment up to its `\end`
+    fn environment(&mut self, name: &str) -> String {
+        let mut rows = Vec::new();
+        let mut cells = Vec::new();
+        loop {
+            cells.push(format!("<mtd>{}</mtd>", mrow(self.row(None))));
+            match self.peek() {
+                Some('&') => self.pos += 1,
+                Some('\\') if self.at_command(&["\\"]) => {
+                    self.pos += 2;
+                    rows.push(format!("<mtr>{}</mtr>", std::mem::take(&mut cells).concat()));
+                }
+                Some('\\') if self.at_command(&["end"]) => {
+                    self.pos += 4;
+                    self.raw_group();
+                    break;
+                }
+                Some('}') => self.pos += 1,
+                _ => break,
+            }
+        }
+        rows.push(format!("<mtr>{}</mtr>", cells.concat()));
+        let table = format!("<mtable>{}</mtable>", rows.concat());
+
+        match name {
+            "pmatrix" => Self::fenced("(", table, ")"),
+            "bmatrix" => Self::fenced("[", table, "]"),
+            "Bmatrix" => Self::fenced("{", table, "}"),
+            "vmatrix" => Self::fenced("|", table, "|"),
+            "Vmatrix" => Self::fenced("‖", table, "‖"),
+            "cases" => Self::fenced("{", table, ""),
+            _ => table,
+        }
+    }
+
+    fn command(&mut self, name: &str) -> Atom {
+        let node = match name {
+            "frac" | "dfrac" | "tfrac" => {
+                let numerator = self.argument();
+                let denominator = self.argument();
+                format!("<mfrac>{}{}</mfrac>", numerator, denominator)
+            }
+            "sqrt" => {
+                if self.peek() == Some('[') {
+                    self.pos += 1;
+                    let index = mrow(self.row(Some(']')));
+                    self.pos += 1;
+                    format!("<mroot>{}{}</mroot>", self.argument(), index)
+                } else {
+                    format!("<msqrt>{}</msqrt>", self.argument())
+                }
+            }
+            "text" | "textrm" | "mbox" => format!("<mtext>{}</mtext>", escape_html(&self.raw_group())),
+            "mathrm" => format!("<mi mathvariant=\"normal\">{}</mi>", escape_html(&self.raw_group())),
+            "mathbf" | "boldsymbol" => format!("<mi mathvariant=\"bold\">{}</mi>", escape_html(&self.raw_group())),
+            "mathbb" => {
+                let text: String = self.raw_group().chars().map(double_struck).collect();
+                format!("<mi>{}</mi>", escape_html(&text))
+            }
+            "operatorname" => format!("<mi>{}</mi>", escape_html(&self.raw_group())),
+            "left" => {
+                let open = self.delimiter();
+                let body = self.row(None).concat();
+                let close = if self.at_command(&["right"]) {
+                    self.pos += 6;
+                    self.delimiter()
+                } else {
+                    String::new()
+                };
+                Self::fenced(&open, body, &close)
+            }
+            "begin" => {
+                let environment = self.raw_group();
+                self.environment(&environment)
+            }
+            "," | ":" | ">" => "<mspace width=\"0.1667em\"/>".to_string(),
+            ";" => "<mspace width=\"0.2778em\"/>".to_string(),
+            "quad" => "<mspace width=\"1em\"/>".to_string(),
+            "qquad" => "<mspace width=\"2em\"/>".to_string(),
+            " " => "<mspace width=\"0.25em\"/>".to_string(),
+            "!" => String::new(),
+            "{" | "}" | "%" | "$" | "#" | "&" | "_" | "|" => mo(name),
+            _ => {
+                if let Some(symbol) = lookup(GREEK, name) {
+                    format!("<mi>{}</mi>", symbol)
+                } else if let Some(op) = lookup(OPERATORS, name) {
+                    mo(op)
+                } else if let Some(op) = lookup(LIMIT_OPERATORS, name) {
+                    return Atom {
+                        node: mo(op),
+                        limits: true,
+                    };
+                } else if let Some(op) = lookup(INTEGRALS, name) {
+                    mo(op)
+                } else if LIMIT_FUNCTIONS.contains(&name) {
+                    return Atom {
+                        node: format!("<mi>{}</mi>", name),
+                        limits: true,
+                    };
+                } else if FUNCTIONS.contains(&name) {
+                    format!("<mi>{}</mi>", name)
+                } else {
+                    format!("<mtext>\\{}</mtext>", escape_html(name))
+                }
+            }
+        };
+        Atom::new(node)
+    }
+}
+
+/// Blackboard bold form of a letter, for `\mathbb`
+fn double_struck(c: char) -> char {
+    match c {
+        'C' => 'ℂ',
+        'H' => 'ℍ',
+        'N' => 'ℕ',
+        'P' => 'ℙ',
+        'Q' => 'ℚ',
+        'R' => 'ℝ',
+        'Z' => 'ℤ',
+        c => c,
+    }
+}
+
+/// Convert a LaTeX math fragment to a `<math>` element
+pub(crate) fn tex_to_mathml(tex: &str, display: bool) -> String {
+    let mut parser = Parser {
+        chars: tex.chars().collect(),
+        pos: 0,
+        display,
+    };
+    let mut nodes = Vec::new();
+    while parser.peek().is_some() {
+        nodes.extend(parser.row(None));
+        // Stray closing braces, cell separators, line breaks and `\right`/`\end`
+        match parser.peek() {
+            Some('\\') => {
+                parser.pos += 1;
+                parser.command_name();
+            }
+            Some(_) => parser.pos += 1,
+            None => {}
+        }
+    }
+
+    let display = if display { " display=\"block\"" } else { "" };
+    format!(
+        "<math xmlns=\"http://www.w3.org/1998/Math/MathML\"{}><semantics>{}\
+         <annotation encoding=\"application/x-tex\">{}</annotation></semantics></math>",
+        display,
+        mrow(nodes),
+        escape_html(tex.trim())
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn body(tex: &str, display: bool) -> String {
+        let html = tex_to_mathml(tex, display);
+        let start = html.find("<semantics>").unwrap() + "<semantics>".len();
+        let end = html.find("<annotation").unwrap();
+        html[start..end].to_string()
+    }
+
+    #[test]
+    fn converts_tex() {
+        assert_eq!(
+            body("x^2 + \\frac{a}{b}", false),
+            "<mrow><msup><mi>x</mi><mn>2</mn></msup><mo>+</mo><mfrac><mi>a</mi><mi>b</mi></mfrac></mrow>"
+        );
+        assert_eq!(
+            body("\\sum_{i=1}^n i", true),
+            "<mrow><munderover><mo>∑</mo><mrow><mi>i</mi><mo>=</mo><mn>1</mn></mrow><mi>n</mi></munderover><mi>i</mi></mrow>"
+        );
+        assert!(body("\\sum_i", false).starts_with("<msub>"));
+        assert_eq!(
+            body("\\begin{pmatrix} 1 & 0 \\\\ 0 & 1 \\end{pmatrix}", true),
+            "<mrow><mo fence=\"true\">(</mo><mtable><mtr><mtd><mn>1</mn></mtd><mtd><mn>0</mn></mtd></mtr>\
+             <mtr><mtd><mn>0</mn></mtd><mtd><mn>1</mn></mtd></mtr></mtable><mo fence=\"true\">)</mo></mrow>"
+        );
+        assert_eq!(
+            body("\\left( \\alpha \\right) \\text{if } \\unknown", false),
+            "<mrow><mrow><mo fence=\"true\">(</mo><mi>α</mi><mo fence=\"true\">)</mo></mrow>\
+             <mtext>if </mtext><mtext>\\unknown</mtext></mrow>"
+        );
+        assert!(tex_to_mathml("a < b", false).contains("<mo>&lt;</mo>"));
+    }
+}
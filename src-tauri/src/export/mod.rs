@@ -0,0 +1,11 @@
+pub mod commands;
+pub mod folder;
+pub mod ipynb;
+pub mod math;
+pub mod pandoc;
+pub mod pdf;
+pub mod render;
+pub mod site;
+pub mod types;
+
+pub use commands::*;
@@ -0,0 +1,66 @@
+//! Office document export through pandoc.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use super::pdf::find_program;
+use super::types::{DocumentFormat, ExportError};
+use crate::fs::PandocSettings;
+
+/// Locate pandoc from the vault settings or PATH
+pub(crate) fn resolve_pandoc(settings: &PandocSettings) -> Result<PathBuf, ExportError> {
+    match settings.path.as_deref().filter(|p| !p.trim().is_empty()) {
+        Some(path) => find_program(path)
+            .ok_or_else(|| ExportError::Converter(format!("Configured pandoc not found: {}", path))),
+        None => find_program("pandoc").ok_or_else(|| {
+            ExportError::Converter("pandoc not found; install it or set export.pandoc.path".to_string())
+        }),
+    }
+}
+
+/// Convert markdown to a DOCX/ODT file
+///
+/// `resource_dir` is where pandoc looks up relative image paths.
+pub(crate) fn convert_markdown(
+    pandoc: &Path,
+    markdown: &str,
+    format: DocumentFormat,
+    resource_dir: &Path,
+    reference_doc: Option<&Path>,
+    output_path: &Path,
+) -> Result<(), ExportError> {
+    let mut command = Command::new(pandoc);
+    command
+        .args(["--from", "markdown+pipe_tables+task_lists+strikeout"])
+        .args(["--to", format.extension()])
+        .arg("--resource-path")
+        .arg(resource_dir)
+        .arg("--output")
+        .arg(output_path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped());
+    if let Some(reference_doc) = reference_doc {
+        command.arg("--reference-doc").arg(reference_doc);
+    }
+
+    let mut child = command
+        .spawn()
+        .map_err(|e| ExportError::Converter(format!("Failed to run pandoc: {}", e)))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(markdown.as_bytes())?;
+    }
+    let output = child.wait_with_output()?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(ExportError::Converter(format!(
+            "pandoc exited with {}: {}",
+            output.status,
+            stderr.trim()
+        )));
+    }
+
+    Ok(())
+}
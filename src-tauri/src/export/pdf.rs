@@ -0,0 +1,199 @@
+//! HTML-to-PDF conversion through an external converter.
+//!
+//! Chromium-based browsers (`--headless --print-to-pdf`) and wkhtmltopdf are
+//! supported. The converter is taken from the vault config or detected on PATH.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use super::types::ExportError;
+
+const CHROMIUM_CANDIDATES: &[&str] = &[
+    "chromium",
+    "chromium-browser",
+    "google-chrome",
+    "google-chrome-stable",
+    "microsoft-edge",
+    "brave-browser",
+];
+
+#[cfg(target_os = "macos")]
+const APP_CANDIDATES: &[&str] = &[
+    "/Applications/Google Chrome.app/Contents/MacOS/Google Chrome",
+    "/Applications/Chromium.app/Contents/MacOS/Chromium",
+    "/Applications/Microsoft Edge.app/Contents/MacOS/Microsoft Edge",
+];
+
+#[cfg(not(target_os = "macos"))]
+const APP_CANDIDATES: &[&str] = &[];
+
+/// Kind of converter, decided from the executable name
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EngineKind {
+    Chromium,
+    Wkhtmltopdf,
+}
+
+/// A resolved HTML-to-PDF converter
+#[derive(Debug, Clone)]
+pub(crate) struct PdfEngine {
+    program: PathBuf,
+    kind: EngineKind,
+}
+
+impl PdfEngine {
+    /// Display name of the converter executable
+    pub fn name(&self) -> String {
+        self.program
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| self.program.display().to_string())
+    }
+
+    fn from_program(program: PathBuf) -> Self {
+        let name = program
+            .file_name()
+            .map(|n| n.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        let kind = if name.contains("wkhtmltopdf") {
+            EngineKind::Wkhtmltopdf
+        } else {
+            EngineKind::Chromium
+        };
+        Self { program, kind }
+    }
+
+    /// Use the configured converter, or detect one on PATH
+    pub fn resolve(configured: Option<&str>) -> Result<Self, ExportError> {
+        if let Some(engine) = configured.filter(|e| !e.trim().is_empty()) {
+            let program = find_program(engine).ok_or_else(|| {
+                ExportError::Converter(format!("Configured PDF engine not found: {}", engine))
+            })?;
+            return Ok(Self::from_program(program));
+        }
+
+        CHROMIUM_CANDIDATES
+            .iter()
+            .chain(std::iter::once(&"wkhtmltopdf"))
+            .find_map(|name| find_program(name))
+            .or_else(|| {
+                APP_CANDIDATES
+                    .iter()
+                    .map(PathBuf::from)
+                    .find(|path| path.is_file())
+            })
+            .map(Self::from_program)
+            .ok_or_else(|| {
+                ExportError::Converter(
+                    "No PDF engine found; install Chromium/Chrome or wkhtmltopdf, or set export.pdf.engine"
+                        .to_string(),
+                )
+            })
+    }
+
+    /// Convert an HTML file into a PDF
+    pub fn convert(&self, html_path: &Path, output_path: &Path, page_size: &str, margin_mm: u32) -> Result<(), ExportError> {
+        let mut command = Command::new(&self.program);
+
+        match self.kind {
+            EngineKind::Chromium => {
+                // Page size and margins come from the @page rule in the document
+                command
+                    .arg("--headless")
+                    .arg("--disable-gpu")
+                    .arg("--no-sandbox")
+                    .arg("--no-pdf-header-footer")
+                    .arg(format!("--print-to-pdf={}", output_path.display()))
+                    .arg(file_url(html_path));
+            }
+            EngineKind::Wkhtmltopdf => {
+                let margin = format!("{}mm", margin_mm);
+                command
+                    .arg("--quiet")
+                    .arg("--enable-local-file-access")
+                    .args(["--page-size", page_size])
+                    .args(["--margin-top", &margin])
+                    .args(["--margin-bottom", &margin])
+                    .args(["--margin-left", &margin])
+                    .args(["--margin-right", &margin])
+                    .arg(html_path)
+                    .arg(output_path);
+            }
+        }
+
+        let output = command
+            .output()
+            .map_err(|e| ExportError::Converter(format!("Failed to run {}: {}", self.name(), e)))?;
+
+        if !output.status.success() || !output_path.exists() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(ExportError::Converter(format!(
+                "{} exited with {}: {}",
+                self.name(),
+                output.status,
+                stderr.trim()
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Write an HTML document to a temporary file and convert it to a PDF
+    pub fn print_html(&self, html: &str, output_path: &Path, page_size: &str, margin_mm: u32) -> Result<(), ExportError> {
+        if let Some(parent) = output_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or(0);
+        let html_path = std::env::temp_dir().join(format!("notemaker-export-{}-{}.html", std::process::id(), nanos));
+        std::fs::write(&html_path, html)?;
+
+        let result = self.convert(&html_path, output_path, page_size, margin_mm);
+        let _ = std::fs::remove_file(&html_path);
+        result
+    }
+}
+
+/// `@page` rule applying the page size and margins
+pub(crate) fn page_style(page_size: &str, margin_mm: u32) -> String {
+    format!(
+        "<style>@page {{ size: {}; margin: {}mm; }} body {{ max-width: none; margin: 0; padding: 0; }} pre {{ white-space: pre-wrap; }}</style>\n",
+        page_size, margin_mm
+    )
+}
+
+/// Resolve a program name or path to an executable
+pub(crate) fn find_program(program: &str) -> Option<PathBuf> {
+    let path = Path::new(program);
+    if path.components().count() > 1 {
+        return path.is_file().then(|| path.to_path_buf());
+    }
+
+    let paths = std::env::var_os("PATH")?;
+    std::env::split_paths(&paths).find_map(|dir| {
+        let candidate = dir.join(program);
+        if candidate.is_file() {
+            return Some(candidate);
+        }
+        if cfg!(windows) {
+            let exe = dir.join(format!("{}.exe", program));
+            if exe.is_file() {
+                return Some(exe);
+            }
+        }
+        None
+    })
+}
+
+/// `file://` URL for a local file
+fn file_url(path: &Path) -> String {
+    let path = path.to_string_lossy().replace('\\', "/").replace(' ', "%20");
+    if path.starts_with('/') {
+        format!("file://{}", path)
+    } else {
+        format!("file:///{}", path)
+    }
+}
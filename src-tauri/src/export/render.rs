@@ -0,0 +1,371 @@
+//! Markdown to HTML rendering shared by the export commands.
+
+use pulldown_cmark::{CodeBlockKind, CowStr, Event, Options, Parser, Tag, TagEnd};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use syntect::highlighting::ThemeSet;
+use syntect::parsing::SyntaxSet;
+
+use super::math::tex_to_mathml;
+use super::types::ExportError;
+use crate::fs::frontmatter::{parse_frontmatter, split_frontmatter, string_field};
+use crate::fs::links::{extract_wikilinks, WikiLink};
+use crate::fs::notebook::math_markdown;
+use crate::fs::table::table_markdown;
+use crate::fs::{
+    dearmor_decrypt, decrypt_with_session, is_encrypted, is_notebook, mime_type, read_block_outputs,
+    read_notebook_index_with, BlockType, EncryptionSession, FsError,
+};
+
+const DEFAULT_THEME: &str = "InspiredGitHub";
+
+fn syntax_set() -> &'static SyntaxSet {
+    static SYNTAXES: OnceLock<SyntaxSet> = OnceLock::new();
+    SYNTAXES.get_or_init(SyntaxSet::load_defaults_newlines)
+}
+
+fn theme_set() -> &'static ThemeSet {
+    static THEMES: OnceLock<ThemeSet> = OnceLock::new();
+    THEMES.get_or_init(ThemeSet::load_defaults)
+}
+
+/// Maps a wiki link to an href
+pub(crate) type LinkResolver<'a> = &'a dyn Fn(&WikiLink) -> Option<String>;
+
+/// How a markdown document should be turned into HTML
+pub(crate) struct RenderOptions<'a> {
+    /// Directory that relative image paths are resolved against
+    pub base_dir: &'a Path,
+    pub embed_assets: bool,
+    pub highlight_code: bool,
+    pub theme: Option<&'a str>,
+    /// Maps a wiki link to an href; unresolved links render as plain text
+    pub link_resolver: Option<LinkResolver<'a>>,
+    /// Point relative links to `.md` files at the matching `.html` page
+    pub md_links_to_html: bool,
+}
+
+/// A note or notebook flattened into a single markdown document
+pub(crate) struct NoteDocument {
+    pub title: String,
+    pub markdown: String,
+}
+
+/// Map a notebook language to the fence info string used in markdown
+pub(crate) fn fence_language(language: Option<&str>) -> &str {
+    match language {
+        Some("shell") => "bash",
+        Some(lang) => lang,
+        None => "",
+    }
+}
+
+/// Load a note or notebook as markdown, dropping the frontmatter
+///
+/// Notebook blocks are concatenated in index order, with code blocks wrapped
+/// in fences carrying their language and followed by their stored output.
+/// Encrypted notes are rejected and encrypted notebook blocks are replaced by
+/// a placeholder.
+pub(crate) fn load_note_document(path: &Path) -> Result<NoteDocument, ExportError> {
+    load_note_document_with(path, None)
+}
+
+/// Decrypt armored content with an unlocked session
+fn decrypt_content(session: Option<&EncryptionSession>, content: &str) -> Option<String> {
+    let session = session.filter(|s| s.is_unlocked())?;
+    let ciphertext = dearmor_decrypt(content).ok()?;
+    let plaintext = decrypt_with_session(session, &ciphertext).ok()?;
+    String::from_utf8(plaintext).ok()
+}
+
+/// Like [`load_note_document`], but decrypts encrypted notes and blocks when
+/// the session is unlocked
+pub(crate) fn load_note_document_with(
+    path: &Path,
+    session: Option<&EncryptionSession>,
+) -> Result<NoteDocument, ExportError> {
+    if !path.exists() {
+        return Err(ExportError::NotFound(path.display().to_string()));
+    }
+
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().trim_end_matches(".md").to_string())
+        .unwrap_or_else(|| "Untitled".to_string());
+
+    if is_notebook(path) {
+        let index = match read_notebook_index_with(path, session) {
+            Ok(index) => index,
+            Err(FsError::Encrypted(_)) => return Err(ExportError::Encrypted(path.display().to_string())),
+            Err(e) => return Err(e.into()),
+        };
+        let outputs = read_block_outputs(path, session).unwrap_or_default();
+        let mut parts = Vec::new();
+        let mut title = None;
+
+        for block in &index.blocks {
+            let block_path = path.join(&block.file);
+            let mut content = if block_path.exists() {
+                std::fs::read_to_string(&block_path)?
+            } else {
+                String::new()
+            };
+
+            if is_encrypted(&content) {
+                match decrypt_content(session, &content) {
+                    Some(plaintext) => content = plaintext,
+                    None => {
+                        parts.push("*[Encrypted block]*".to_string());
+                        continue;
+                    }
+                }
+            }
+
+            match block.block_type {
+                BlockType::Code => {
+                    let lang = fence_language(block.language.as_deref());
+                    parts.push(format!("```{}\n{}\n```", lang, content.trim_end()));
+                    if let Some(output) = outputs.get(&block.id) {
+                        let text = format!("{}{}", output.stdout, output.stderr);
+                        if !text.trim().is_empty() {
+                            parts.push(format!("```\n{}\n```", text.trim_end()));
+                        }
+                    }
+                }
+                BlockType::Table => parts.push(table_markdown(&content, block.language.as_deref())),
+                BlockType::Math => parts.push(math_markdown(&content)),
+                BlockType::Markdown => {
+                    if title.is_none() {
+                        title = parse_frontmatter(&content).and_then(|fm| string_field(&fm, "title"));
+                    }
+                    let (_, body) = split_frontmatter(&content);
+                    parts.push(body.trim().to_string());
+                }
+            }
+        }
+
+        return Ok(NoteDocument {
+            title: title.unwrap_or(name),
+            markdown: parts.join("\n\n"),
+        });
+    }
+
+    let mut content = std::fs::read_to_string(path)?;
+    if is_encrypted(&content) {
+        content = decrypt_content(session, &content)
+            .ok_or_else(|| ExportError::Encrypted(path.display().to_string()))?;
+    }
+
+    let title = parse_frontmatter(&content)
+        .and_then(|fm| string_field(&fm, "title"))
+        .unwrap_or(name);
+    let (_, body) = split_frontmatter(&content);
+
+    Ok(NoteDocument {
+        title,
+        markdown: body.to_string(),
+    })
+}
+
+/// Replace wiki links with regular markdown links (or plain text)
+fn convert_wikilinks(markdown: &str, resolver: Option<LinkResolver>) -> String {
+    let mut result = String::with_capacity(markdown.len());
+    let mut last = 0;
+
+    for link in extract_wikilinks(markdown) {
+        result.push_str(&markdown[last..link.start]);
+        let text = link.display.clone().unwrap_or_else(|| link.target.clone());
+
+        if link.embed {
+            result.push_str(&format!("![{}](<{}>)", text, link.target));
+        } else {
+            match resolver.and_then(|resolve| resolve(&link)) {
+                Some(href) => result.push_str(&format!("[{}](<{}>)", text, href)),
+                None => result.push_str(&format!("<span class=\"wikilink\">{}</span>", escape_html(&text))),
+            }
+        }
+        last = link.end;
+    }
+
+    result.push_str(&markdown[last..]);
+    result
+}
+
+/// Replace wiki links with their display text and embeds with images, for
+/// converters that do not understand HTML
+pub(crate) fn flatten_wikilinks(markdown: &str) -> String {
+    let mut result = String::with_capacity(markdown.len());
+    let mut last = 0;
+
+    for link in extract_wikilinks(markdown) {
+        result.push_str(&markdown[last..link.start]);
+        let text = link.display.clone().unwrap_or_else(|| link.target.clone());
+        if link.embed {
+            result.push_str(&format!("![{}](<{}>)", text, link.target));
+        } else {
+            result.push_str(&text);
+        }
+        last = link.end;
+    }
+
+    result.push_str(&markdown[last..]);
+    result
+}
+
+/// Inline a local image as a data URI
+fn embed_image(base_dir: &Path, url: &str) -> Option<String> {
+    use base64::Engine;
+
+    if url.contains("://") || url.starts_with("data:") {
+        return None;
+    }
+    let decoded = url.replace("%20", " ");
+    let path: PathBuf = base_dir.join(decoded.trim_start_matches("./"));
+    let bytes = std::fs::read(&path).ok()?;
+    let encoded = base64::engine::general_purpose::STANDARD.encode(bytes);
+    Some(format!("data:{};base64,{}", mime_type(&path), encoded))
+}
+
+/// Map a relative `note.md#heading` link to `note.html#heading`
+fn md_link_to_html(url: &str) -> Option<String> {
+    if url.contains("://") || url.starts_with('#') || url.starts_with("mailto:") {
+        return None;
+    }
+    let (path, fragment) = match url.split_once('#') {
+        Some((path, fragment)) => (path, Some(fragment)),
+        None => (url, None),
+    };
+    let stem = path.strip_suffix(".md")?;
+    Some(match fragment {
+        Some(fragment) => format!("{}.html#{}", stem, fragment),
+        None => format!("{}.html", stem),
+    })
+}
+
+/// Local image targets referenced by markdown, including `![[embeds]]`
+pub(crate) fn image_targets(markdown: &str) -> Vec<String> {
+    let markdown = convert_wikilinks(markdown, None);
+    Parser::new(&markdown)
+        .filter_map(|event| match event {
+            Event::Start(Tag::Image { dest_url, .. })
+                if !dest_url.contains("://") && !dest_url.starts_with("data:") =>
+            {
+                Some(dest_url.replace("%20", " "))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Highlight a code block, falling back to an escaped `<pre>`
+fn highlight_code(code: &str, language: &str, theme: Option<&str>) -> String {
+    let class = if language.is_empty() {
+        String::new()
+    } else {
+        format!(" class=\"language-{}\"", escape_html(language))
+    };
+
+    if language == "mermaid" {
+        return format!("<pre class=\"mermaid\">{}</pre>\n", escape_html(code));
+    }
+
+    let syntaxes = syntax_set();
+    let syntax = syntaxes
+        .find_syntax_by_token(language)
+        .unwrap_or_else(|| syntaxes.find_syntax_plain_text());
+    let themes = theme_set();
+    let theme = theme
+        .and_then(|name| themes.themes.get(name))
+        .or_else(|| themes.themes.get(DEFAULT_THEME));
+
+    match theme.map(|t| syntect::html::highlighted_html_for_string(code, syntaxes, syntax, t)) {
+        Some(Ok(html)) => html,
+        _ => format!("<pre><code{}>{}</code></pre>\n", class, escape_html(code)),
+    }
+}
+
+/// Render markdown to an HTML fragment
+pub(crate) fn render_markdown(markdown: &str, options: &RenderOptions) -> String {
+    let markdown = convert_wikilinks(markdown, options.link_resolver);
+
+    let mut parser_options = Options::empty();
+    parser_options.insert(Options::ENABLE_TABLES);
+    parser_options.insert(Options::ENABLE_STRIKETHROUGH);
+    parser_options.insert(Options::ENABLE_TASKLISTS);
+    parser_options.insert(Options::ENABLE_FOOTNOTES);
+    parser_options.insert(Options::ENABLE_MATH);
+
+    let mut events = Vec::new();
+    let mut code: Option<(String, String)> = None;
+
+    for event in Parser::new_ext(&markdown, parser_options) {
+        match event {
+            Event::Start(Tag::CodeBlock(kind)) if options.highlight_code => {
+                let language = match kind {
+                    CodeBlockKind::Fenced(info) => info.split_whitespace().next().unwrap_or("").to_string(),
+                    CodeBlockKind::Indented => String::new(),
+                };
+                code = Some((language, String::new()));
+            }
+            Event::Text(text) if code.is_some() => {
+                if let Some((_, buffer)) = code.as_mut() {
+                    buffer.push_str(&text);
+                }
+            }
+            Event::End(TagEnd::CodeBlock) if code.is_some() => {
+                if let Some((language, buffer)) = code.take() {
+                    events.push(Event::Html(CowStr::from(highlight_code(&buffer, &language, options.theme))));
+                }
+            }
+            Event::Start(Tag::Link { link_type, dest_url, title, id }) if options.md_links_to_html => {
+                let dest_url = md_link_to_html(&dest_url).map(CowStr::from).unwrap_or(dest_url);
+                events.push(Event::Start(Tag::Link { link_type, dest_url, title, id }));
+            }
+            Event::Start(Tag::Image { link_type, dest_url, title, id }) if options.embed_assets => {
+                let dest_url = embed_image(options.base_dir, &dest_url)
+                    .map(CowStr::from)
+                    .unwrap_or(dest_url);
+                events.push(Event::Start(Tag::Image { link_type, dest_url, title, id }));
+            }
+            Event::InlineMath(tex) => events.push(Event::InlineHtml(CowStr::from(tex_to_mathml(&tex, false)))),
+            Event::DisplayMath(tex) => events.push(Event::InlineHtml(CowStr::from(tex_to_mathml(&tex, true)))),
+            other => events.push(other),
+        }
+    }
+
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, events.into_iter());
+    html
+}
+
+/// Escape text for inclusion in HTML
+pub(crate) fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Base stylesheet for exported documents
+pub(crate) const EXPORT_CSS: &str = r#"
+body { font-family: -apple-system, BlinkMacSystemFont, "Segoe UI", Helvetica, Arial, sans-serif; line-height: 1.6; max-width: 48rem; margin: 2rem auto; padding: 0 1rem; color: #1f2328; }
+h1, h2, h3, h4 { line-height: 1.25; margin-top: 1.5em; }
+pre { padding: 1rem; overflow-x: auto; border-radius: 6px; background: #f6f8fa; font-size: 0.875rem; }
+code { font-family: "JetBrains Mono", ui-monospace, SFMono-Regular, Menlo, monospace; }
+table { border-collapse: collapse; }
+th, td { border: 1px solid #d0d7de; padding: 0.4rem 0.8rem; }
+blockquote { margin: 0; padding: 0 1rem; color: #59636e; border-left: 0.25rem solid #d0d7de; }
+img { max-width: 100%; }
+.wikilink { color: #8250df; }
+"#;
+
+/// Wrap an HTML fragment into a standalone document
+pub(crate) fn html_document(title: &str, body: &str, extra_head: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n<title>{}</title>\n<style>{}</style>\n{}</head>\n<body>\n<article>\n{}</article>\n</body>\n</html>\n",
+        escape_html(title),
+        EXPORT_CSS,
+        extra_head,
+        body
+    )
+}
@@ -0,0 +1,288 @@
+//! Static site generation for a whole vault.
+
+use serde_yaml::Value;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::commands::note_base_dir;
+use super::render::{escape_html, image_targets, load_note_document, render_markdown, RenderOptions, EXPORT_CSS};
+use super::types::{ExportError, SiteExport, SiteExportOptions};
+use crate::fs::frontmatter::parse_frontmatter;
+use crate::fs::links::{extract_wikilinks, relative_path, LinkIndex, WikiLink};
+use crate::fs::read_markdown_text;
+
+/// Layout additions for the site navigation
+const SITE_CSS: &str = r#"
+body { max-width: none; margin: 0; padding: 0; display: flex; min-height: 100vh; }
+nav.site-nav { width: 16rem; flex-shrink: 0; padding: 1.5rem 1rem; border-right: 1px solid #d0d7de; background: #f6f8fa; font-size: 0.9rem; }
+nav.site-nav ul { list-style: none; padding-left: 0.9rem; margin: 0.2rem 0; }
+nav.site-nav > ul { padding-left: 0; }
+nav.site-nav a { color: #1f2328; text-decoration: none; }
+nav.site-nav a.current { font-weight: 600; color: #0969da; }
+nav.site-nav .site-title { display: block; font-weight: 700; font-size: 1.1rem; margin-bottom: 1rem; }
+nav.site-nav .folder > span { color: #59636e; }
+main { flex: 1; min-width: 0; padding: 0 2rem; }
+main article { max-width: 48rem; margin: 2rem auto; }
+section.backlinks { max-width: 48rem; margin: 2rem auto; padding-top: 1rem; border-top: 1px solid #d0d7de; font-size: 0.9rem; }
+"#;
+
+/// A note that ends up as a page of the site
+struct SitePage {
+    source: PathBuf,
+    /// Output path relative to the site root
+    rel: PathBuf,
+    title: String,
+    markdown: String,
+}
+
+/// The `publish` frontmatter flag of a note, if set
+fn publish_flag(path: &Path) -> Option<bool> {
+    let content = read_markdown_text(path).ok()?;
+    match parse_frontmatter(&content)?.get("publish")? {
+        Value::Bool(flag) => Some(*flag),
+        Value::String(s) => match s.trim().to_lowercase().as_str() {
+            "true" | "yes" => Some(true),
+            "false" | "no" => Some(false),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Link from one page of the site to another
+fn page_href(from: &Path, to: &Path) -> String {
+    let from_dir = from.parent().unwrap_or(Path::new(""));
+    relative_path(from_dir, to).to_string_lossy().replace('\\', "/")
+}
+
+/// Navigation tree of all pages, with folders as nested lists
+fn nav_html(pages: &[SitePage], current: &Path) -> String {
+    let mut sorted: Vec<&SitePage> = pages.iter().collect();
+    sorted.sort_by(|a, b| a.rel.cmp(&b.rel));
+
+    let mut html = String::from("<ul>\n");
+    let mut open: Vec<String> = Vec::new();
+
+    for page in sorted {
+        let folders: Vec<String> = page
+            .rel
+            .parent()
+            .map(|p| p.iter().map(|c| c.to_string_lossy().to_string()).collect())
+            .unwrap_or_default();
+        let common = open.iter().zip(&folders).take_while(|(a, b)| a == b).count();
+
+        for _ in common..open.len() {
+            html.push_str("</ul></li>\n");
+        }
+        open.truncate(common);
+        for folder in &folders[common..] {
+            html.push_str(&format!("<li class=\"folder\"><span>{}</span><ul>\n", escape_html(folder)));
+            open.push(folder.clone());
+        }
+
+        let class = if page.rel == current { " class=\"current\"" } else { "" };
+        html.push_str(&format!(
+            "<li><a href=\"{}\"{}>{}</a></li>\n",
+            escape_html(&page_href(current, &page.rel)),
+            class,
+            escape_html(&page.title)
+        ));
+    }
+
+    for _ in 0..open.len() {
+        html.push_str("</ul></li>\n");
+    }
+    html.push_str("</ul>\n");
+    html
+}
+
+/// Full HTML page with navigation
+fn site_document(site_title: &str, page_title: &str, current: &Path, nav: &str, body: &str) -> String {
+    let home = page_href(current, Path::new("index.html"));
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n<title>{} · {}</title>\n<style>{}{}</style>\n</head>\n<body>\n<nav class=\"site-nav\">\n<a class=\"site-title\" href=\"{}\">{}</a>\n{}</nav>\n<main>\n{}</main>\n</body>\n</html>\n",
+        escape_html(page_title),
+        escape_html(site_title),
+        EXPORT_CSS,
+        SITE_CSS,
+        escape_html(&home),
+        escape_html(site_title),
+        nav,
+        body
+    )
+}
+
+/// Copy an image referenced by a page into the site, keeping its vault-relative path
+fn copy_asset(
+    vault: &Path,
+    base_dir: &Path,
+    url: &str,
+    output_dir: &Path,
+    copied: &mut HashSet<PathBuf>,
+) -> Result<bool, ExportError> {
+    let Ok(source) = base_dir.join(url.trim_start_matches("./")).canonicalize() else {
+        return Ok(false);
+    };
+    // Never publish files from outside the vault
+    let Ok(rel) = source.strip_prefix(vault) else {
+        return Ok(false);
+    };
+    if !source.is_file() || !copied.insert(rel.to_path_buf()) {
+        return Ok(false);
+    }
+
+    let dest = output_dir.join(rel);
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::copy(&source, &dest)?;
+    Ok(true)
+}
+
+/// Render every published note of a vault into `output_dir`
+pub(crate) fn build_site(
+    vault_path: &Path,
+    output_dir: &Path,
+    options: &SiteExportOptions,
+) -> Result<SiteExport, ExportError> {
+    let index = LinkIndex::build(vault_path)?;
+    let vault = vault_path.canonicalize()?;
+    let site_title = options.title.clone().unwrap_or_else(|| {
+        vault_path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| "Notes".to_string())
+    });
+
+    let mut pages = Vec::new();
+    let mut skipped = Vec::new();
+
+    for note in &index.notes {
+        let published = match publish_flag(&note.path) {
+            Some(flag) => flag,
+            None => !options.only_published,
+        };
+        if !published {
+            skipped.push(note.path.clone());
+            continue;
+        }
+
+        let document = match load_note_document(&note.path) {
+            Ok(document) => document,
+            Err(ExportError::Encrypted(_)) => {
+                skipped.push(note.path.clone());
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+
+        let Ok(rel) = note.path.strip_prefix(vault_path) else {
+            continue;
+        };
+        pages.push(SitePage {
+            source: note.path.clone(),
+            rel: rel.with_extension("html"),
+            title: document.title,
+            markdown: document.markdown,
+        });
+    }
+
+    let by_source: HashMap<&Path, usize> = pages.iter().enumerate().map(|(i, p)| (p.source.as_path(), i)).collect();
+
+    // Backlinks between published pages only
+    let mut backlinks: Vec<Vec<usize>> = vec![Vec::new(); pages.len()];
+    for (i, page) in pages.iter().enumerate() {
+        for link in extract_wikilinks(&page.markdown) {
+            let Some(&target) = index
+                .resolve(&link.target)
+                .and_then(|note| by_source.get(note.path.as_path()))
+            else {
+                continue;
+            };
+            if target != i && !backlinks[target].contains(&i) {
+                backlinks[target].push(i);
+            }
+        }
+    }
+
+    fs::create_dir_all(output_dir)?;
+    let mut copied = HashSet::new();
+    let mut assets = 0;
+
+    for (i, page) in pages.iter().enumerate() {
+        let base_dir = note_base_dir(&page.source);
+        let resolver = |link: &WikiLink| -> Option<String> {
+            let note = index.resolve(&link.target)?;
+            let &target = by_source.get(note.path.as_path())?;
+            Some(page_href(&page.rel, &pages[target].rel))
+        };
+
+        let mut body = String::from("<article>\n");
+        body.push_str(&render_markdown(
+            &page.markdown,
+            &RenderOptions {
+                base_dir: &base_dir,
+                embed_assets: false,
+                highlight_code: options.highlight_code,
+                theme: options.theme.as_deref(),
+                link_resolver: Some(&resolver),
+                md_links_to_html: true,
+            },
+        ));
+        body.push_str("</article>\n");
+
+        if !backlinks[i].is_empty() {
+            body.push_str("<section class=\"backlinks\">\n<h2>Backlinks</h2>\n<ul>\n");
+            for &source in &backlinks[i] {
+                body.push_str(&format!(
+                    "<li><a href=\"{}\">{}</a></li>\n",
+                    escape_html(&page_href(&page.rel, &pages[source].rel)),
+                    escape_html(&pages[source].title)
+                ));
+            }
+            body.push_str("</ul>\n</section>\n");
+        }
+
+        for url in image_targets(&page.markdown) {
+            if copy_asset(&vault, &base_dir.canonicalize()?, &url, output_dir, &mut copied)? {
+                assets += 1;
+            }
+        }
+
+        let dest = output_dir.join(&page.rel);
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let nav = nav_html(&pages, &page.rel);
+        fs::write(&dest, site_document(&site_title, &page.title, &page.rel, &nav, &body))?;
+    }
+
+    // A vault note called `index` doubles as the home page
+    let index_rel = Path::new("index.html");
+    if !pages.iter().any(|p| p.rel == index_rel) {
+        let mut body = format!("<article>\n<h1>{}</h1>\n<ul>\n", escape_html(&site_title));
+        let mut sorted: Vec<&SitePage> = pages.iter().collect();
+        sorted.sort_by_key(|page| page.title.to_lowercase());
+        for page in sorted {
+            body.push_str(&format!(
+                "<li><a href=\"{}\">{}</a></li>\n",
+                escape_html(&page_href(index_rel, &page.rel)),
+                escape_html(&page.title)
+            ));
+        }
+        body.push_str("</ul>\n</article>\n");
+        let nav = nav_html(&pages, index_rel);
+        fs::write(
+            output_dir.join(index_rel),
+            site_document(&site_title, &site_title, index_rel, &nav, &body),
+        )?;
+    }
+
+    Ok(SiteExport {
+        output_dir: output_dir.to_path_buf(),
+        pages: pages.len(),
+        assets,
+        skipped,
+    })
+}
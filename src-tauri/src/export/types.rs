@@ -0,0 +1,218 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use thiserror::Error;
+
+use crate::fs::FsError;
+
+#[derive(Error, Debug)]
+pub enum ExportError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Path not found: {0}")]
+    NotFound(String),
+    #[error("Note is encrypted: {0}")]
+    Encrypted(String),
+    #[error("{0}")]
+    Fs(#[from] FsError),
+    #[error("Conversion failed: {0}")]
+    Converter(String),
+}
+
+impl serde::Serialize for ExportError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+/// Options for exporting a note to HTML
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HtmlExportOptions {
+    /// Where to write the HTML file; when omitted the HTML is only returned
+    #[serde(default)]
+    pub output_path: Option<PathBuf>,
+    /// Inline local images as data URIs so the file is self-contained
+    #[serde(default = "default_true")]
+    pub embed_assets: bool,
+    /// Syntax-highlight fenced code blocks
+    #[serde(default = "default_true")]
+    pub highlight_code: bool,
+    /// Highlighting theme name (default: "InspiredGitHub")
+    #[serde(default)]
+    pub theme: Option<String>,
+}
+
+impl Default for HtmlExportOptions {
+    fn default() -> Self {
+        Self {
+            output_path: None,
+            embed_assets: true,
+            highlight_code: true,
+            theme: None,
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Result of an HTML export
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HtmlExport {
+    pub title: String,
+    pub html: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_path: Option<PathBuf>,
+}
+
+/// Options for exporting a note to PDF
+///
+/// Page size and margins default to the vault's `export.pdf` settings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PdfExportOptions {
+    /// Where to write the PDF file
+    pub output_path: PathBuf,
+    /// CSS page size override ("A4", "Letter", ...)
+    #[serde(default)]
+    pub page_size: Option<String>,
+    /// Page margin override in millimeters
+    #[serde(default)]
+    pub margin_mm: Option<u32>,
+    /// Syntax-highlight fenced code blocks
+    #[serde(default = "default_true")]
+    pub highlight_code: bool,
+    /// Highlighting theme name (default: "InspiredGitHub")
+    #[serde(default)]
+    pub theme: Option<String>,
+}
+
+/// Result of a PDF export
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PdfExport {
+    pub title: String,
+    pub output_path: PathBuf,
+    /// Converter that produced the PDF
+    pub engine: String,
+}
+
+/// Options for exporting a vault as a static site
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SiteExportOptions {
+    /// Site title shown in the navigation (default: vault folder name)
+    #[serde(default)]
+    pub title: Option<String>,
+    /// Only publish notes with `publish: true` in their frontmatter; otherwise
+    /// every note is published unless it has `publish: false`
+    #[serde(default)]
+    pub only_published: bool,
+    /// Syntax-highlight fenced code blocks
+    #[serde(default = "default_true")]
+    pub highlight_code: bool,
+    /// Highlighting theme name (default: "InspiredGitHub")
+    #[serde(default)]
+    pub theme: Option<String>,
+}
+
+impl Default for SiteExportOptions {
+    fn default() -> Self {
+        Self {
+            title: None,
+            only_published: false,
+            highlight_code: true,
+            theme: None,
+        }
+    }
+}
+
+/// Result of a static site export
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SiteExport {
+    pub output_dir: PathBuf,
+    /// Number of HTML pages written (excluding the generated index)
+    pub pages: usize,
+    /// Number of assets copied
+    pub assets: usize,
+    /// Notes left out because they are encrypted or not published
+    pub skipped: Vec<PathBuf>,
+}
+
+/// Office document format produced through pandoc
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DocumentFormat {
+    #[default]
+    Docx,
+    Odt,
+}
+
+impl DocumentFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            DocumentFormat::Docx => "docx",
+            DocumentFormat::Odt => "odt",
+        }
+    }
+}
+
+/// Options for exporting a note to DOCX or ODT
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DocumentExportOptions {
+    /// Where to write the document (default: next to the note)
+    #[serde(default)]
+    pub output_path: Option<PathBuf>,
+    #[serde(default)]
+    pub format: DocumentFormat,
+}
+
+/// Output format of a bulk export
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Markdown,
+    Html,
+    Pdf,
+    Docx,
+    Odt,
+}
+
+impl ExportFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Markdown => "md",
+            ExportFormat::Html => "html",
+            ExportFormat::Pdf => "pdf",
+            ExportFormat::Docx => "docx",
+            ExportFormat::Odt => "odt",
+        }
+    }
+}
+
+/// Progress of a bulk export, emitted as `export-progress`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportProgress {
+    /// 1-based index of the note being exported
+    pub current: usize,
+    pub total: usize,
+    pub path: PathBuf,
+}
+
+/// A note left out of a bulk export
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkippedExport {
+    pub path: PathBuf,
+    pub reason: String,
+}
+
+/// Result of a bulk export
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FolderExport {
+    pub output_dir: PathBuf,
+    /// Files written, one per exported note
+    pub exported: Vec<PathBuf>,
+    /// Number of attachments copied
+    pub assets: usize,
+    pub skipped: Vec<SkippedExport>,
+}
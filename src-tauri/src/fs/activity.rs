@@ -0,0 +1,208 @@
+//! Activity log of kanban tasks.
+//!
+//! Each task has an append-only `<id>.activity.jsonl` next to its
+//! description, one JSON entry per line, so logs of a board shared through
+//! git merge cleanly. On encrypted boards the whole log is stored encrypted
+//! and rewritten on each append.
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tauri::State;
+
+use super::commands::{decrypt_armored, encrypt_armored, read_kanban_index, FsError};
+use super::encryption::{is_encrypted, EncryptionSession};
+use super::encryption_commands::EncryptionState;
+use super::types::{ActivityKind, TaskActivity};
+
+/// Activity log file of a task
+pub(crate) fn activity_path(kanban_path: &Path, task_id: &str) -> PathBuf {
+    kanban_path.join(format!("{}.activity.jsonl", task_id))
+}
+
+/// Name of the person making changes, taken from the git configuration
+pub(crate) fn activity_author(kanban_path: &Path) -> Option<String> {
+    let repo = git2::Repository::discover(kanban_path).ok()?;
+    let signature = repo.signature().ok()?;
+    signature.name().map(str::to_string)
+}
+
+/// Entries of a task's log, oldest first; unreadable lines are skipped
+pub(crate) fn read_task_activity(
+    kanban_path: &Path,
+    task_id: &str,
+    session: Option<&EncryptionSession>,
+) -> Result<Vec<TaskActivity>, FsError> {
+    let path = activity_path(kanban_path, task_id);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let mut content = fs::read_to_string(&path)?;
+    if is_encrypted(&content) {
+        content = decrypt_armored(session, &content, &path)?;
+    }
+    Ok(content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+fn to_lines(entries: &[TaskActivity]) -> Result<String, FsError> {
+    let mut content = String::new();
+    for entry in entries {
+        let line = serde_json::to_string(entry).map_err(|e| FsError::InvalidPath(format!("Invalid activity: {}", e)))?;
+        content.push_str(&line);
+        content.push('\n');
+    }
+    Ok(content)
+}
+
+/// Append entries to a task's log
+pub(crate) fn append_task_activity(
+    kanban_path: &Path,
+    encrypted: bool,
+    task_id: &str,
+    entries: &[TaskActivity],
+    session: Option<&EncryptionSession>,
+) -> Result<(), FsError> {
+    if entries.is_empty() {
+        return Ok(());
+    }
+    let path = activity_path(kanban_path, task_id);
+    let stored_encrypted = path.exists() && is_encrypted(&fs::read_to_string(&path)?);
+    if encrypted || stored_encrypted {
+        let mut all = read_task_activity(kanban_path, task_id, session)?;
+        all.extend_from_slice(entries);
+        let content = to_lines(&all)?;
+        let content = if encrypted { encrypt_armored(session, &content, &path)? } else { content };
+        fs::write(&path, content)?;
+        return Ok(());
+    }
+
+    let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+    file.write_all(to_lines(entries)?.as_bytes())?;
+    Ok(())
+}
+
+/// Rewrite a task's log after its board was encrypted or decrypted
+pub(crate) fn reseal_task_activity(
+    kanban_path: &Path,
+    encrypted: bool,
+    task_id: &str,
+    session: Option<&EncryptionSession>,
+) -> Result<(), FsError> {
+    let path = activity_path(kanban_path, task_id);
+    if !path.exists() {
+        return Ok(());
+    }
+    let entries = read_task_activity(kanban_path, task_id, session)?;
+    let content = to_lines(&entries)?;
+    let content = if encrypted { encrypt_armored(session, &content, &path)? } else { content };
+    fs::write(&path, content)?;
+    Ok(())
+}
+
+/// Entry without details
+pub(crate) fn activity_entry(kind: ActivityKind, author: Option<String>, at: &str) -> TaskActivity {
+    TaskActivity {
+        at: at.to_string(),
+        author,
+        kind,
+        field: None,
+        from: None,
+        to: None,
+        text: None,
+    }
+}
+
+/// Entry recording a change of one field
+pub(crate) fn field_change(
+    field: &str,
+    from: Option<String>,
+    to: Option<String>,
+    author: Option<String>,
+    at: &str,
+) -> TaskActivity {
+    TaskActivity {
+        field: Some(field.to_string()),
+        from,
+        to,
+        ..activity_entry(ActivityKind::Change, author, at)
+    }
+}
+
+/// Comment on a task; the author defaults to the git user name
+#[tauri::command]
+pub async fn add_task_comment(
+    kanban_path: PathBuf,
+    task_id: String,
+    text: String,
+    author: Option<String>,
+    state: State<'_, EncryptionState>,
+) -> Result<TaskActivity, FsError> {
+    if text.trim().is_empty() {
+        return Err(FsError::InvalidPath("Comment cannot be empty".to_string()));
+    }
+    let session = state.session(&kanban_path);
+    let session = Some(session.as_ref());
+    let index = read_kanban_index(&kanban_path, session)?;
+    if !index.tasks.iter().any(|t| t.id == task_id) {
+        return Err(FsError::NotFound(format!("Task not found: {}", task_id)));
+    }
+
+    let author = author.filter(|a| !a.trim().is_empty()).or_else(|| activity_author(&kanban_path));
+    let entry = TaskActivity {
+        text: Some(text),
+        ..activity_entry(ActivityKind::Comment, author, &chrono::Utc::now().to_rfc3339())
+    };
+    append_task_activity(&kanban_path, index.encrypted, &task_id, std::slice::from_ref(&entry), session)?;
+    Ok(entry)
+}
+
+/// Comments and changes of a task, oldest first
+#[tauri::command]
+pub async fn get_task_activity(
+    kanban_path: PathBuf,
+    task_id: String,
+    state: State<'_, EncryptionState>,
+) -> Result<Vec<TaskActivity>, FsError> {
+    read_task_activity(&kanban_path, &task_id, Some(&state.session(&kanban_path)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn appends_activity() {
+        let dir = tempfile::tempdir().unwrap();
+        let at = "2024-05-01T09:00:00Z";
+        append_task_activity(
+            dir.path(),
+            false,
+            "t1",
+            &[field_change("status", Some("todo".to_string()), Some("done".to_string()), None, at)],
+            None,
+        )
+        .unwrap();
+        fs::OpenOptions::new()
+            .append(true)
+            .open(activity_path(dir.path(), "t1"))
+            .unwrap()
+            .write_all(b"<<<<<<< HEAD\n")
+            .unwrap();
+        let comment = TaskActivity {
+            text: Some("Shipped".to_string()),
+            ..activity_entry(ActivityKind::Comment, Some("Dana".to_string()), at)
+        };
+        append_task_activity(dir.path(), false, "t1", &[comment], None).unwrap();
+
+        let entries = read_task_activity(dir.path(), "t1", None).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].kind, ActivityKind::Change);
+        assert_eq!(entries[0].to.as_deref(), Some("done"));
+        assert_eq!(entries[1].text.as_deref(), Some("Shipped"));
+        assert!(read_task_activity(dir.path(), "t2", None).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,131 @@
+//! Archiving notes out of the way without deleting them.
+//!
+//! Archived notes, notebooks and boards move into the vault's archive folder
+//! (`Archive/` unless configured otherwise), mirroring the folder they came
+//! from, and get `archived: true` in their frontmatter. The file tree and
+//! note search leave them out by default.
+
+use serde_yaml::{Mapping, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::bulk::{frontmatter_file, move_entry};
+use super::commands::{find_vault_root, is_notebook, read_vault_config, walk_notes, write_atomic, FsError};
+use super::encryption::is_encrypted;
+use super::frontmatter::patch_frontmatter;
+use super::links::{LinkIndex, NoteRef};
+
+/// Archive folder of a vault
+pub(crate) fn archive_dir(vault_path: &Path) -> PathBuf {
+    let config = read_vault_config(vault_path).unwrap_or_default();
+    vault_path.join(config.archive.folder)
+}
+
+/// Set or clear the `archived` frontmatter flag where the note is readable
+fn set_archived_flag(path: &Path, archived: bool) -> Result<(), FsError> {
+    if !path.is_file() && !is_notebook(path) {
+        return Ok(());
+    }
+    let file = frontmatter_file(path)?;
+    let content = fs::read_to_string(&file)?;
+    // Encrypted notes are archived by location only
+    if is_encrypted(&content) {
+        return Ok(());
+    }
+
+    let mut patch = Mapping::new();
+    let value = if archived { Value::Bool(true) } else { Value::Null };
+    patch.insert(Value::String("archived".to_string()), value);
+    let patched = patch_frontmatter(&content, &patch)?;
+    if patched != content {
+        write_atomic(&file, patched)?;
+    }
+    Ok(())
+}
+
+/// Move an entry to `target`, creating the folders on the way
+fn move_to(path: &Path, target: &Path) -> Result<PathBuf, FsError> {
+    let dir = target
+        .parent()
+        .ok_or_else(|| FsError::InvalidPath(target.display().to_string()))?;
+    fs::create_dir_all(dir)?;
+    move_entry(path, dir)
+}
+
+/// Move a note, notebook or board into the archive folder
+///
+/// Returns its new path.
+#[tauri::command]
+pub async fn archive_note(path: PathBuf) -> Result<PathBuf, FsError> {
+    let vault = find_vault_root(&path).ok_or_else(|| FsError::InvalidPath("Not inside a vault".to_string()))?;
+    let archive = archive_dir(&vault);
+    if path.starts_with(&archive) {
+        return Err(FsError::InvalidPath("Already archived".to_string()));
+    }
+    let relative = path
+        .strip_prefix(&vault)
+        .map_err(|_| FsError::InvalidPath(path.display().to_string()))?;
+
+    let archived = move_to(&path, &archive.join(relative))?;
+    set_archived_flag(&archived, true)?;
+    Ok(archived)
+}
+
+/// Move an archived entry back to the folder it was archived from
+///
+/// Returns its restored path.
+#[tauri::command]
+pub async fn unarchive_note(path: PathBuf) -> Result<PathBuf, FsError> {
+    let vault = find_vault_root(&path).ok_or_else(|| FsError::InvalidPath("Not inside a vault".to_string()))?;
+    let archive = archive_dir(&vault);
+    let relative = path
+        .strip_prefix(&archive)
+        .map_err(|_| FsError::InvalidPath("Not archived".to_string()))?;
+
+    let restored = move_to(&path, &vault.join(relative))?;
+    set_archived_flag(&restored, false)?;
+    Ok(restored)
+}
+
+/// Notes and notebooks in the archive folder
+#[tauri::command]
+pub async fn list_archived_notes(vault_path: PathBuf) -> Result<Vec<NoteRef>, FsError> {
+    let archive = archive_dir(&vault_path);
+    if !archive.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut paths = Vec::new();
+    walk_notes(&archive, &mut paths);
+    Ok(LinkIndex::build(&vault_path)?
+        .notes
+        .into_iter()
+        .filter(|note| paths.contains(&note.path))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn archives_and_restores_notes() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = dir.path();
+        fs::create_dir_all(vault.join(".notemaker")).unwrap();
+        fs::create_dir_all(vault.join("Projects")).unwrap();
+        let note = vault.join("Projects/Old.md");
+        fs::write(&note, "---\ntitle: Old\n---\nBody\n").unwrap();
+
+        let archived = tauri::async_runtime::block_on(archive_note(note.clone())).unwrap();
+        assert_eq!(archived, vault.join("Archive/Projects/Old.md"));
+        assert_eq!(fs::read_to_string(&archived).unwrap(), "---\ntitle: Old\narchived: true\n---\nBody\n");
+
+        let listed = tauri::async_runtime::block_on(list_archived_notes(vault.to_path_buf())).unwrap();
+        assert_eq!(listed.len(), 1);
+        assert!(listed[0].archived);
+
+        let restored = tauri::async_runtime::block_on(unarchive_note(archived)).unwrap();
+        assert_eq!(restored, note);
+        assert_eq!(fs::read_to_string(&note).unwrap(), "---\ntitle: Old\n---\nBody\n");
+    }
+}
@@ -0,0 +1,332 @@
+//! Rolling zip backups of a vault.
+//!
+//! A backup holds every vault file except `.git` and the machine-local
+//! `.notemaker/.local` folder, where the archives themselves go unless
+//! `backup.location` says otherwise. While `backup.enabled` is set, a
+//! background scheduler writes one every `interval_hours` and keeps the
+//! newest `keep` archives. Symlinks are not followed.
+
+use chrono::Local;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use super::commands::{modified_secs, read_vault_config, FsError};
+use super::types::{BackupInfo, BackupSettings};
+use super::watcher::{WatchPause, WatcherState};
+
+/// How often the scheduler checks whether a backup is due
+const TICK: Duration = Duration::from_secs(300);
+
+/// File name prefix of backup archives
+const BACKUP_PREFIX: &str = "backup-";
+
+/// Machine-local folder of a vault, never backed up or restored
+const LOCAL_DIR: &str = ".notemaker/.local";
+
+fn zip_error(e: zip::result::ZipError) -> FsError {
+    FsError::Io(io::Error::other(e))
+}
+
+/// Whether a vault-relative path is left out of backups
+fn is_excluded(rel: &Path) -> bool {
+    rel.starts_with(".git") || rel.starts_with(LOCAL_DIR)
+}
+
+/// Folder the backups of a vault are written to
+fn backup_dir(vault_path: &Path, settings: &BackupSettings) -> PathBuf {
+    match &settings.location {
+        Some(location) => vault_path.join(location),
+        None => vault_path.join(LOCAL_DIR).join("backups"),
+    }
+}
+
+/// Add the files below `dir` to the archive, skipping `skip` (the backup folder)
+fn add_dir(
+    zip: &mut ZipWriter<fs::File>,
+    vault_path: &Path,
+    dir: &Path,
+    skip: &Path,
+    options: SimpleFileOptions,
+) -> Result<(), FsError> {
+    let mut entries: Vec<_> = fs::read_dir(dir)?.flatten().collect();
+    entries.sort_by_key(|e| e.file_name());
+
+    for entry in entries {
+        let path = entry.path();
+        let rel = path.strip_prefix(vault_path).unwrap_or(&path);
+        let file_type = entry.file_type()?;
+        if is_excluded(rel) || path == skip || file_type.is_symlink() {
+            continue;
+        }
+        let name = rel.to_string_lossy().replace('\\', "/");
+        if file_type.is_dir() {
+            zip.add_directory(name, options).map_err(zip_error)?;
+            add_dir(zip, vault_path, &path, skip, options)?;
+        } else {
+            zip.start_file(name, options).map_err(zip_error)?;
+            io::copy(&mut fs::File::open(&path)?, zip)?;
+        }
+    }
+    Ok(())
+}
+
+/// Describe a backup archive
+fn backup_info(path: &Path) -> Result<BackupInfo, FsError> {
+    Ok(BackupInfo {
+        name: path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default(),
+        path: path.to_path_buf(),
+        created: modified_secs(path),
+        size: fs::metadata(path)?.len(),
+    })
+}
+
+/// Write a new backup archive without pruning old ones
+fn write_backup(vault_path: &Path, settings: &BackupSettings) -> Result<BackupInfo, FsError> {
+    let dir = backup_dir(vault_path, settings);
+    fs::create_dir_all(&dir)?;
+
+    let stamp = Local::now().format("%Y%m%d-%H%M%S-%3f").to_string();
+    let mut name = format!("{}{}.zip", BACKUP_PREFIX, stamp);
+    let mut n = 2;
+    while dir.join(&name).exists() {
+        name = format!("{}{}-{}.zip", BACKUP_PREFIX, stamp, n);
+        n += 1;
+    }
+    let path = dir.join(&name);
+    let tmp = dir.join(format!(".{}.tmp", name));
+
+    let written = (|| -> Result<(), FsError> {
+        let mut zip = ZipWriter::new(fs::File::create(&tmp)?);
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+        add_dir(&mut zip, vault_path, vault_path, &dir, options)?;
+        zip.finish().map_err(zip_error)?.sync_all()?;
+        fs::rename(&tmp, &path)?;
+        Ok(())
+    })();
+    if let Err(e) = written {
+        let _ = fs::remove_file(&tmp);
+        return Err(e);
+    }
+    backup_info(&path)
+}
+
+/// Backups of a vault, newest first
+pub(crate) fn list_backups_in(vault_path: &Path, settings: &BackupSettings) -> Result<Vec<BackupInfo>, FsError> {
+    let dir = backup_dir(vault_path, settings);
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut paths: Vec<PathBuf> = fs::read_dir(&dir)?
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| {
+            let name = p.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+            name.starts_with(BACKUP_PREFIX) && name.ends_with(".zip") && p.is_file()
+        })
+        .collect();
+    // Timestamped names sort by age; compare stems so `-2` duplicates come later
+    paths.sort_by_key(|p| std::cmp::Reverse(p.file_stem().map(|s| s.to_os_string())));
+    paths.iter().map(|p| backup_info(p)).collect()
+}
+
+/// Write a backup and delete the ones beyond `keep`
+pub(crate) fn create_backup(vault_path: &Path, settings: &BackupSettings) -> Result<BackupInfo, FsError> {
+    let info = write_backup(vault_path, settings)?;
+    let keep = settings.keep.max(1) as usize;
+    for old in list_backups_in(vault_path, settings)?.iter().skip(keep) {
+        fs::remove_file(&old.path)?;
+    }
+    Ok(info)
+}
+
+/// Remove the vault contents a restore replaces
+///
+/// Folders holding `.notemaker/.local` or the backup folder are emptied
+/// rather than removed.
+fn clear_vault(vault_path: &Path, dir: &Path, skip: &Path) -> Result<(), FsError> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let rel = path.strip_prefix(vault_path).unwrap_or(&path);
+        if is_excluded(rel) || path == skip {
+            continue;
+        }
+        if entry.file_type()?.is_dir() {
+            if skip.starts_with(&path) || Path::new(LOCAL_DIR).starts_with(rel) {
+                clear_vault(vault_path, &path, skip)?;
+            } else {
+                fs::remove_dir_all(&path)?;
+            }
+        } else {
+            fs::remove_file(&path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Replace the vault contents with a backup
+///
+/// The current state is backed up first so the restore can be undone.
+pub(crate) fn restore_backup_in(vault_path: &Path, settings: &BackupSettings, name: &str) -> Result<(), FsError> {
+    if name.contains(['/', '\\']) || !name.starts_with(BACKUP_PREFIX) || !name.ends_with(".zip") {
+        return Err(FsError::InvalidPath(name.to_string()));
+    }
+    let dir = backup_dir(vault_path, settings);
+    let archive_path = dir.join(name);
+    if !archive_path.is_file() {
+        return Err(FsError::NotFound(name.to_string()));
+    }
+
+    let mut archive = ZipArchive::new(fs::File::open(&archive_path)?).map_err(zip_error)?;
+    // Check every entry before touching the vault
+    for i in 0..archive.len() {
+        let file = archive.by_index(i).map_err(zip_error)?;
+        match file.enclosed_name() {
+            Some(rel) if !is_excluded(&rel) => {}
+            _ => return Err(FsError::PathTraversal),
+        }
+    }
+
+    write_backup(vault_path, settings)?;
+    let _pause = WatchPause::new(vault_path);
+    clear_vault(vault_path, vault_path, &dir)?;
+
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i).map_err(zip_error)?;
+        let Some(rel) = file.enclosed_name() else {
+            continue;
+        };
+        let target = vault_path.join(rel);
+        if file.is_dir() {
+            fs::create_dir_all(&target)?;
+            continue;
+        }
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        io::copy(&mut file, &mut fs::File::create(&target)?)?;
+    }
+    Ok(())
+}
+
+/// Write a backup if the newest one is older than the configured interval
+fn backup_if_due(vault_path: &Path, settings: &BackupSettings) -> Result<Option<BackupInfo>, FsError> {
+    let interval = u64::from(settings.interval_hours) * 3600;
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let latest = list_backups_in(vault_path, settings)?.into_iter().next();
+    if latest.is_some_and(|b| now.saturating_sub(b.created) < interval) {
+        return Ok(None);
+    }
+    create_backup(vault_path, settings).map(Some)
+}
+
+/// Back up a vault right away
+#[tauri::command]
+pub async fn create_backup_now(vault_path: PathBuf) -> Result<BackupInfo, FsError> {
+    let settings = read_vault_config(&vault_path)?.backup;
+    create_backup(&vault_path, &settings)
+}
+
+/// List the backups of a vault, newest first
+#[tauri::command]
+pub async fn list_backups(vault_path: PathBuf) -> Result<Vec<BackupInfo>, FsError> {
+    let settings = read_vault_config(&vault_path)?.backup;
+    list_backups_in(&vault_path, &settings)
+}
+
+/// Replace the vault contents with the backup called `name`
+#[tauri::command]
+pub async fn restore_backup(vault_path: PathBuf, name: String) -> Result<(), FsError> {
+    let settings = read_vault_config(&vault_path)?.backup;
+    restore_backup_in(&vault_path, &settings, &name)
+}
+
+/// Spawn the backup scheduler for the open vault; it runs for the lifetime
+/// of the app
+pub fn spawn_backups(app_handle: AppHandle, watcher_state: WatcherState) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(TICK).await;
+
+            let vault = match watcher_state.lock() {
+                Ok(watcher) => watcher.watched_path().cloned(),
+                Err(_) => None,
+            };
+            let Some(vault) = vault else {
+                continue;
+            };
+            let Ok(config) = read_vault_config(&vault) else {
+                continue;
+            };
+            let settings = config.backup;
+            if !settings.enabled || settings.interval_hours == 0 {
+                continue;
+            }
+
+            let result = tauri::async_runtime::spawn_blocking(move || backup_if_due(&vault, &settings)).await;
+            if let Ok(Ok(Some(info))) = result {
+                let _ = app_handle.emit("vault-backup", info);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backs_up_and_restores_vault() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = dir.path();
+        fs::create_dir_all(vault.join(".git")).unwrap();
+        fs::create_dir_all(vault.join(LOCAL_DIR)).unwrap();
+        fs::create_dir_all(vault.join("Projects")).unwrap();
+        fs::write(vault.join(".git/HEAD"), "ref").unwrap();
+        fs::write(vault.join(LOCAL_DIR).join("state.json"), "{}").unwrap();
+        fs::write(vault.join(".notemaker/config.yaml"), "version: 1").unwrap();
+        fs::write(vault.join("Projects/Plan.md"), "# Plan\n").unwrap();
+
+        let settings = BackupSettings {
+            keep: 2,
+            ..Default::default()
+        };
+        let first = create_backup(vault, &settings).unwrap();
+        let mut archive = ZipArchive::new(fs::File::open(&first.path).unwrap()).unwrap();
+        let names: Vec<String> = archive.file_names().map(str::to_string).collect();
+        assert!(names.contains(&"Projects/Plan.md".to_string()));
+        assert!(names.contains(&".notemaker/config.yaml".to_string()));
+        assert!(!names.iter().any(|n| n.starts_with(".git") || n.starts_with(LOCAL_DIR)));
+        assert!(archive.by_name("Projects/Plan.md").is_ok());
+
+        fs::write(vault.join("Projects/Plan.md"), "# Changed\n").unwrap();
+        fs::write(vault.join("Scratch.md"), "tmp").unwrap();
+        restore_backup_in(vault, &settings, &first.name).unwrap();
+        assert_eq!(fs::read_to_string(vault.join("Projects/Plan.md")).unwrap(), "# Plan\n");
+        assert!(!vault.join("Scratch.md").exists());
+        assert!(vault.join(".git/HEAD").is_file());
+        assert!(vault.join(LOCAL_DIR).join("state.json").is_file());
+
+        // The pre-restore state was kept as a backup
+        let backups = list_backups_in(vault, &settings).unwrap();
+        assert_eq!(backups.len(), 2);
+        assert_eq!(backups[1].name, first.name);
+
+        create_backup(vault, &settings).unwrap();
+        let backups = list_backups_in(vault, &settings).unwrap();
+        assert_eq!(backups.len(), 2);
+        assert!(!backups.iter().any(|b| b.name == first.name));
+
+        assert!(restore_backup_in(vault, &settings, "../Plan.zip").is_err());
+        assert!(backup_if_due(vault, &BackupSettings::default()).unwrap().is_none());
+    }
+}
@@ -0,0 +1,263 @@
+//! Operations on many notes, notebooks and boards at once.
+//!
+//! Every path is handled on its own: a failure is recorded in the report and
+//! the remaining paths are still processed.
+
+use serde_yaml::{Mapping, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::commands::{copy_dir, is_notebook, note_assets_dir, read_notebook_index, write_atomic, FsError};
+use super::encryption::is_encrypted;
+use super::frontmatter::{parse_frontmatter, replace_frontmatter, split_frontmatter, string_list};
+use super::links::relink_moved_note;
+use super::types::{BlockType, BulkFailure, BulkReport, BulkSuccess};
+
+/// Run `op` for every path, collecting the outcomes
+fn for_each_path(
+    paths: Vec<PathBuf>,
+    mut op: impl FnMut(&Path) -> Result<Option<PathBuf>, FsError>,
+) -> BulkReport {
+    let mut report = BulkReport::default();
+    for path in paths {
+        match op(&path) {
+            Ok(new_path) => report.succeeded.push(BulkSuccess { path, new_path }),
+            Err(e) => report.failed.push(BulkFailure {
+                path,
+                error: e.to_string(),
+            }),
+        }
+    }
+    report
+}
+
+/// Whether `path` holds markdown whose links may need fixing after a move
+pub(crate) fn is_note(path: &Path) -> bool {
+    is_notebook(path) || (path.is_file() && path.extension().is_some_and(|e| e == "md"))
+}
+
+/// Move an entry and its `.assets` folder into `to_dir`
+pub fn move_entry(from: &Path, to_dir: &Path) -> Result<PathBuf, FsError> {
+    if !from.exists() {
+        return Err(FsError::NotFound(from.display().to_string()));
+    }
+    if !to_dir.is_dir() {
+        return Err(FsError::InvalidPath("Target is not a directory".to_string()));
+    }
+    if to_dir.starts_with(from) {
+        return Err(FsError::InvalidPath("Cannot move a folder into itself".to_string()));
+    }
+    let file_name = from
+        .file_name()
+        .ok_or_else(|| FsError::InvalidPath("Invalid file name".to_string()))?;
+    let to = to_dir.join(file_name);
+    let assets = note_assets_dir(from);
+    let to_assets = assets.file_name().map(|name| to_dir.join(name));
+    if to.exists() || (assets.exists() && to_assets.as_ref().is_some_and(|p| p.exists())) {
+        return Err(FsError::InvalidPath("Target already exists".to_string()));
+    }
+
+    fs::rename(from, &to)?;
+    if let (true, Some(to_assets)) = (assets.exists(), to_assets) {
+        fs::rename(&assets, to_assets)?;
+    }
+    if is_note(&to) {
+        relink_moved_note(from, &assets, &to)?;
+    }
+    Ok(to)
+}
+
+/// Copy a note or notebook and its `.assets` folder to `to`
+pub fn copy_entry(from: &Path, to: &Path) -> Result<(), FsError> {
+    if !from.exists() {
+        return Err(FsError::NotFound(from.display().to_string()));
+    }
+    if from.is_dir() && !is_notebook(from) {
+        return Err(FsError::InvalidPath("Only notes and notebooks can be copied".to_string()));
+    }
+    let assets = note_assets_dir(from);
+    if to.exists() || (assets.exists() && note_assets_dir(to).exists()) {
+        return Err(FsError::InvalidPath("Target already exists".to_string()));
+    }
+
+    if from.is_dir() {
+        copy_dir(from, to)?;
+    } else {
+        fs::copy(from, to)?;
+    }
+    if assets.exists() {
+        copy_dir(&assets, &note_assets_dir(to))?;
+    }
+    if is_note(to) {
+        relink_moved_note(from, &assets, to)?;
+    }
+    Ok(())
+}
+
+/// Move a file or folder to the trash, deleting it where there is none
+fn remove_entry(path: &Path) -> Result<(), FsError> {
+    #[cfg(any(target_os = "macos", target_os = "windows", target_os = "linux"))]
+    if trash::delete(path).is_ok() {
+        return Ok(());
+    }
+
+    if path.is_dir() {
+        fs::remove_dir_all(path)?;
+    } else {
+        fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+/// Delete an entry and its `.assets` folder
+pub fn delete_entry(path: &Path) -> Result<(), FsError> {
+    if !path.exists() {
+        return Err(FsError::NotFound(path.display().to_string()));
+    }
+    let assets = note_assets_dir(path);
+    remove_entry(path)?;
+    if assets.exists() {
+        remove_entry(&assets)?;
+    }
+    Ok(())
+}
+
+/// Content with `labels` added to the frontmatter, `None` if all of them
+/// were already there
+pub(crate) fn add_labels(content: &str, labels: &[String]) -> Result<Option<String>, FsError> {
+    let mut map: Mapping = match (split_frontmatter(content).0, parse_frontmatter(content)) {
+        (Some(_), None) => return Err(FsError::InvalidPath("Invalid frontmatter".to_string())),
+        (_, map) => map.unwrap_or_default(),
+    };
+    let mut merged = string_list(&map, "labels");
+    let before = merged.len();
+    for label in labels {
+        let label = label.trim().trim_start_matches('#');
+        if !label.is_empty() && !merged.iter().any(|l| l.eq_ignore_ascii_case(label)) {
+            merged.push(label.to_string());
+        }
+    }
+    if merged.len() == before {
+        return Ok(None);
+    }
+
+    map.insert(
+        Value::String("labels".to_string()),
+        Value::Sequence(merged.into_iter().map(Value::String).collect()),
+    );
+    Ok(Some(replace_frontmatter(content, &map)?))
+}
+
+/// File holding the frontmatter of a note: the note itself, or the first
+/// markdown block of a notebook
+pub(crate) fn frontmatter_file(path: &Path) -> Result<PathBuf, FsError> {
+    if !is_notebook(path) {
+        return Ok(path.to_path_buf());
+    }
+    let index = read_notebook_index(path)?;
+    index
+        .blocks
+        .iter()
+        .find(|b| b.block_type == BlockType::Markdown)
+        .map(|b| path.join(&b.file))
+        .ok_or_else(|| FsError::InvalidPath("Notebook has no markdown block".to_string()))
+}
+
+fn tag_entry(path: &Path, labels: &[String]) -> Result<(), FsError> {
+    if !path.exists() {
+        return Err(FsError::NotFound(path.display().to_string()));
+    }
+    let file = frontmatter_file(path)?;
+    let content = fs::read_to_string(&file)?;
+    if is_encrypted(&content) {
+        return Err(FsError::Encrypted(path.display().to_string()));
+    }
+    if let Some(content) = add_labels(&content, labels)? {
+        write_atomic(&file, content)?;
+    }
+    Ok(())
+}
+
+/// Move notes, notebooks, boards or folders into `target_dir`
+#[tauri::command]
+pub async fn bulk_move(paths: Vec<PathBuf>, target_dir: PathBuf) -> Result<BulkReport, FsError> {
+    Ok(for_each_path(paths, |path| move_entry(path, &target_dir).map(Some)))
+}
+
+/// Delete notes, notebooks, boards or folders, to the trash where possible
+#[tauri::command]
+pub async fn bulk_delete(paths: Vec<PathBuf>) -> Result<BulkReport, FsError> {
+    Ok(for_each_path(paths, |path| delete_entry(path).map(|_| None)))
+}
+
+/// Add labels to the frontmatter of notes and notebooks
+#[tauri::command]
+pub async fn bulk_tag(paths: Vec<PathBuf>, labels: Vec<String>) -> Result<BulkReport, FsError> {
+    Ok(for_each_path(paths, |path| tag_entry(path, &labels).map(|_| None)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn moves_and_tags_in_bulk() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("Archive");
+        fs::create_dir(&archive).unwrap();
+        fs::write(dir.path().join("One.md"), "---\nlabels: [work]\n---\n# One\n").unwrap();
+        fs::write(dir.path().join("Two.md"), "# Two\n").unwrap();
+        fs::create_dir(dir.path().join("One.assets")).unwrap();
+        fs::write(archive.join("Two.md"), "taken").unwrap();
+
+        let labels = vec!["Work".to_string(), "#review".to_string()];
+        let one = dir.path().join("One.md");
+        let two = dir.path().join("Two.md");
+        let report = for_each_path(vec![one.clone(), two.clone()], |p| tag_entry(p, &labels).map(|_| None));
+        assert_eq!(report.succeeded.len(), 2);
+        assert_eq!(
+            fs::read_to_string(&one).unwrap(),
+            "---\nlabels:\n- work\n- review\n---\n# One\n"
+        );
+        assert_eq!(
+            fs::read_to_string(&two).unwrap(),
+            "---\nlabels:\n- Work\n- review\n---\n\n# Two\n"
+        );
+
+        let missing = dir.path().join("Missing.md");
+        let report = for_each_path(vec![one, two.clone(), missing], |p| move_entry(p, &archive).map(Some));
+        assert_eq!(report.succeeded.len(), 1);
+        assert_eq!(report.succeeded[0].new_path.as_deref(), Some(archive.join("One.md").as_path()));
+        assert!(archive.join("One.assets").is_dir());
+        assert_eq!(report.failed.len(), 2);
+        assert_eq!(report.failed[0].path, two);
+    }
+
+    #[test]
+    fn copies_and_moves_assets_with_links() {
+        let dir = tempfile::tempdir().unwrap();
+        let projects = dir.path().join("Projects");
+        fs::create_dir_all(dir.path().join("Note.assets")).unwrap();
+        fs::create_dir_all(projects.join("Deep")).unwrap();
+        fs::write(dir.path().join("Note.assets/img.png"), "png").unwrap();
+        let note = dir.path().join("Note.md");
+        fs::write(&note, "![img](./Note.assets/img.png) [spec](Spec.md#intro) [web](https://example.com)\n").unwrap();
+
+        let copy = projects.join("Copy.md");
+        copy_entry(&note, &copy).unwrap();
+        assert!(projects.join("Copy.assets/img.png").is_file());
+        assert_eq!(
+            fs::read_to_string(&copy).unwrap(),
+            "![img](<./Copy.assets/img.png>) [spec](<../Spec.md#intro>) [web](https://example.com)\n"
+        );
+        assert!(copy_entry(&note, &copy).is_err());
+
+        let moved = move_entry(&copy, &projects.join("Deep")).unwrap();
+        assert!(projects.join("Deep/Copy.assets/img.png").is_file());
+        assert_eq!(
+            fs::read_to_string(&moved).unwrap(),
+            "![img](<./Copy.assets/img.png>) [spec](<../../Spec.md#intro>) [web](https://example.com)\n"
+        );
+        assert!(dir.path().join("Note.assets/img.png").is_file());
+    }
+}
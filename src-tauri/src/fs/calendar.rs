@@ -0,0 +1,167 @@
+//! Calendar of a vault: what was written, is due or takes place on each day.
+//!
+//! A note counts as created on its `created:` date, or when its file was
+//! created if it has none, and as modified on its file's last change. Notes
+//! with a `date:` field are events on that day. Encrypted notes only show
+//! up through their files' dates.
+
+use chrono::{DateTime, Local, NaiveDate};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use super::commands::{read_markdown_text, read_vault_config, walk_notes, FsError};
+use super::encryption::is_encrypted;
+use super::frontmatter::{parse_frontmatter, string_field};
+use super::kanban::parse_date;
+use super::links::note_name;
+use super::periodic::periodic_note_with;
+use super::reminders::collect_due_items;
+use super::types::{CalendarDay, CalendarEvent, DateRange, PeriodKind};
+
+/// Most days one calendar request may cover
+const MAX_DAYS: i64 = 400;
+
+fn local_day(time: SystemTime) -> NaiveDate {
+    DateTime::<Local>::from(time).date_naive()
+}
+
+fn calendar_day(days: &mut BTreeMap<NaiveDate, CalendarDay>, date: NaiveDate) -> &mut CalendarDay {
+    days.entry(date).or_insert_with(|| CalendarDay {
+        date: date.format("%Y-%m-%d").to_string(),
+        ..Default::default()
+    })
+}
+
+/// Days between `from` and `to` (inclusive) that have anything on them
+pub(crate) fn build_calendar(
+    vault: &Path,
+    from: NaiveDate,
+    to: NaiveDate,
+    today: NaiveDate,
+) -> Result<Vec<CalendarDay>, FsError> {
+    let mut days = BTreeMap::new();
+    let in_range = |date: &NaiveDate| (from..=to).contains(date);
+
+    let mut notes = Vec::new();
+    walk_notes(vault, &mut notes);
+    for note in &notes {
+        let metadata = fs::metadata(note).ok();
+        let content = read_markdown_text(note).unwrap_or_default();
+        let frontmatter = if is_encrypted(&content) {
+            None
+        } else {
+            parse_frontmatter(&content)
+        };
+        let field = |name: &str| frontmatter.as_ref().and_then(|fm| string_field(fm, name));
+
+        let created = field("created")
+            .as_deref()
+            .and_then(parse_date)
+            .or_else(|| metadata.as_ref()?.created().ok().map(local_day));
+        if let Some(date) = created.filter(in_range) {
+            calendar_day(&mut days, date).created.push(note.clone());
+        }
+        let modified = metadata.as_ref().and_then(|m| m.modified().ok()).map(local_day);
+        if let Some(date) = modified.filter(in_range) {
+            calendar_day(&mut days, date).modified.push(note.clone());
+        }
+        if let Some(date) = field("date").as_deref().and_then(parse_date).filter(in_range) {
+            calendar_day(&mut days, date).events.push(CalendarEvent {
+                path: note.clone(),
+                title: field("title").unwrap_or_else(|| note_name(note)),
+            });
+        }
+    }
+
+    for item in collect_due_items(vault, to, today) {
+        if let Some(date) = parse_date(&item.due).filter(in_range) {
+            calendar_day(&mut days, date).due.push(item);
+        }
+    }
+
+    let periodic = read_vault_config(vault)?.periodic;
+    for date in from.iter_days().take_while(|date| *date <= to) {
+        let Ok(note) = periodic_note_with(vault, &periodic, PeriodKind::Daily, date) else {
+            continue;
+        };
+        if note.exists {
+            calendar_day(&mut days, date).daily_note = Some(note.path);
+        }
+    }
+
+    Ok(days.into_values().collect())
+}
+
+/// Calendar entries of the days in `range`, for a calendar view
+///
+/// Both ends of the range are required; days with nothing on them are left
+/// out.
+#[tauri::command]
+pub async fn get_calendar(vault_path: PathBuf, range: DateRange) -> Result<Vec<CalendarDay>, FsError> {
+    if !vault_path.is_dir() {
+        return Err(FsError::NotFound(vault_path.display().to_string()));
+    }
+    let bound = |date: Option<&str>| {
+        date.and_then(parse_date)
+            .ok_or_else(|| FsError::InvalidPath("Calendar range needs from and to dates (YYYY-MM-DD)".to_string()))
+    };
+    let from = bound(range.from.as_deref())?;
+    let to = bound(range.to.as_deref())?;
+    if to < from || (to - from).num_days() >= MAX_DAYS {
+        return Err(FsError::InvalidPath(format!("Invalid calendar range: {} to {}", from, to)));
+    }
+    build_calendar(&vault_path, from, to, Local::now().date_naive())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::commands::write_kanban_index;
+    use crate::fs::types::{KanbanIndex, KanbanTask};
+
+    fn day(date: &str) -> NaiveDate {
+        parse_date(date).unwrap()
+    }
+
+    #[test]
+    fn collects_days_in_range() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = dir.path();
+        fs::create_dir(vault.join("daily")).unwrap();
+        fs::write(vault.join("daily/2024-05-02.md"), "---\ncreated: 2024-05-02T08:00:00Z\n---\n").unwrap();
+        fs::write(vault.join("Meetup.md"), "---\ntitle: Rust meetup\ndate: 2024-05-03\n---\n").unwrap();
+        fs::write(vault.join("Report.md"), "---\ncreated: 2024-04-01\ndue: 2024-05-03\n---\n").unwrap();
+
+        let board = vault.join("Team.kanban");
+        fs::create_dir(&board).unwrap();
+        let index = KanbanIndex {
+            tasks: vec![KanbanTask {
+                id: "t1".to_string(),
+                title: "Release".to_string(),
+                status: "todo".to_string(),
+                due: Some("2024-05-02".to_string()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        write_kanban_index(&board, &index).unwrap();
+
+        let days = build_calendar(vault, day("2024-05-01"), day("2024-05-03"), day("2024-05-01")).unwrap();
+        let dates: Vec<&str> = days.iter().map(|d| d.date.as_str()).collect();
+        assert_eq!(dates, vec!["2024-05-02", "2024-05-03"]);
+
+        assert_eq!(days[0].created, vec![vault.join("daily/2024-05-02.md")]);
+        assert_eq!(days[0].daily_note, Some(vault.join("daily/2024-05-02.md")));
+        assert_eq!(days[0].due[0].title, "Release");
+        assert_eq!(days[1].events[0].title, "Rust meetup");
+        assert_eq!(days[1].due[0].path, vault.join("Report.md"));
+
+        let range = DateRange {
+            from: Some("2024-05-01".to_string()),
+            to: None,
+        };
+        assert!(tauri::async_runtime::block_on(get_calendar(vault.to_path_buf(), range)).is_err());
+    }
+}
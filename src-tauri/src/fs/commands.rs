@@ -1,13 +1,39 @@
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashSet};
 use std::fs;
+use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 use std::time::UNIX_EPOCH;
+use tauri::{AppHandle, State};
 
+use super::activity::{
+    activity_author, activity_entry, activity_path, append_task_activity, field_change, reseal_task_activity,
+};
+use super::bulk::{copy_entry, delete_entry, is_note, move_entry};
+use super::encryption::{
+    armor_encrypt, dearmor_decrypt, decrypt_with_session, encrypt_with_session, is_encrypted, EncryptionSession,
+};
+use super::encryption_commands::EncryptionState;
+use super::echo::record_write;
+use super::env::execution_env;
+use super::exclude::VaultIgnore;
+use super::migrations::load_notebook_index;
+use super::notebook::{math_markdown, recover_notebook_save, strip_math_delimiters};
+use super::links::{relink_moved_note, update_links_after_folder_rename};
+use super::policy::apply_encryption_policy;
+use super::recent::record_recent;
+use super::results::{read_block_outputs, reseal_block_outputs};
+use super::runtime::{prepare_invocation, resolve_working_dir};
+use super::scope::VaultScope;
+use super::templates::{read_vault_template, TemplateContext};
 use super::types::{
-    BlockType, FileEntry, Kanban, KanbanIndex, KanbanSettings, KanbanTask, KanbanTaskWithContent,
+    ActivityKind, BinaryFile, BlockType, CreateVaultOptions, FileEntry, Kanban, KanbanIndex, KanbanSettings, KanbanTask, KanbanTaskWithContent,
     LocalState, NoteContent, Notebook, NotebookBlock, NotebookBlockWithContent, NotebookIndex,
-    TaskUpdates, VaultConfig, VaultInfo,
+    StatusChange, TaskActivity, TaskUpdates, VaultConfig, VaultInfo, WebhookEvent, WriteConflict,
 };
+use super::webhooks::{dispatch_webhooks, note_saved};
 
 /// Error type for file system operations
 #[derive(Debug, thiserror::Error)]
@@ -22,6 +48,20 @@ pub enum FsError {
     Yaml(#[from] serde_yaml::Error),
     #[error("Path traversal detected")]
     PathTraversal,
+    #[error("Encrypted: {0}")]
+    Encrypted(String),
+    #[error("Keychain error: {0}")]
+    Keychain(String),
+    #[error("Database error: {0}")]
+    Database(String),
+    #[error("Renderer failed: {0}")]
+    Renderer(String),
+    #[error("HTTP error: {0}")]
+    Http(String),
+    #[error("Block is locked: {0}")]
+    Locked(String),
+    #[error("Write conflict: {}", .0.path.display())]
+    Conflict(Box<WriteConflict>),
 }
 
 impl serde::Serialize for FsError {
@@ -29,41 +69,100 @@ impl serde::Serialize for FsError {
     where
         S: serde::Serializer,
     {
-        serializer.serialize_str(&self.to_string())
+        match self {
+            // The frontend merges with the content on disk
+            FsError::Conflict(conflict) => conflict.serialize(serializer),
+            _ => serializer.serialize_str(&self.to_string()),
+        }
     }
 }
 
 /// Validate that a path doesn't contain traversal attempts
-fn validate_path(base: &Path, target: &Path) -> Result<PathBuf, FsError> {
+///
+/// Paths that don't exist yet are checked through their nearest existing
+/// ancestor.
+pub(crate) fn validate_path(base: &Path, target: &Path) -> Result<PathBuf, FsError> {
     let canonical_base = base.canonicalize().map_err(|_| FsError::NotFound(base.display().to_string()))?;
+    if target.components().any(|c| c == std::path::Component::ParentDir) {
+        return Err(FsError::PathTraversal);
+    }
     let full_path = base.join(target);
 
-    // For new files that don't exist yet, check the parent
-    let check_path = if full_path.exists() {
-        full_path.canonicalize()?
-    } else {
-        let parent = full_path.parent().ok_or(FsError::InvalidPath(target.display().to_string()))?;
-        if parent.exists() {
-            let canonical_parent = parent.canonicalize()?;
-            if !canonical_parent.starts_with(&canonical_base) {
-                return Err(FsError::PathTraversal);
-            }
-            return Ok(full_path);
-        } else {
-            return Err(FsError::NotFound(parent.display().to_string()));
-        }
-    };
-
-    if !check_path.starts_with(&canonical_base) {
+    let existing = full_path
+        .ancestors()
+        .find(|p| p.exists())
+        .ok_or_else(|| FsError::InvalidPath(target.display().to_string()))?;
+    if !existing.canonicalize()?.starts_with(&canonical_base) {
         return Err(FsError::PathTraversal);
     }
 
-    Ok(check_path)
+    Ok(full_path)
+}
+
+/// Path of a note named relative to the vault by another program
+///
+/// `.md` is added unless the name has it or names an existing file or
+/// folder, like a board. Hidden files and folders such as `.notemaker` or
+/// `.git` are never reachable this way.
+pub fn vault_note_path(vault: &Path, name: &str) -> Result<PathBuf, FsError> {
+    let relative = Path::new(name);
+    let visible = |c: std::path::Component| match c {
+        std::path::Component::Normal(part) => !part.to_string_lossy().starts_with('.'),
+        _ => false,
+    };
+    if name.is_empty() || !relative.components().all(visible) {
+        return Err(FsError::InvalidPath(name.to_string()));
+    }
+    if relative.extension().is_some_and(|ext| ext == "md") || vault.join(relative).exists() {
+        validate_path(vault, relative)
+    } else {
+        validate_path(vault, Path::new(&format!("{}.md", name)))
+    }
+}
+
+/// Write a file without leaving it half-written on a crash
+///
+/// The contents go to a hidden `.<name>.tmp` sibling first, are synced to
+/// disk and then renamed over the target, so readers only ever see the old
+/// or the new file. A symlink is written through to the file it points at,
+/// and an existing file keeps its permissions.
+pub(crate) fn write_atomic(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> std::io::Result<()> {
+    let link = path.as_ref();
+    let path = if link.is_symlink() { fs::canonicalize(link)? } else { link.to_path_buf() };
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, "path has no file name"))?;
+    let tmp = path.with_file_name(format!(".{}.tmp", name));
+    let permissions = fs::metadata(&path).ok().map(|meta| meta.permissions());
+
+    let written = fs::File::create(&tmp).and_then(|mut file| {
+        file.write_all(contents.as_ref())?;
+        if let Some(permissions) = permissions {
+            file.set_permissions(permissions)?;
+        }
+        file.sync_all()
+    });
+    if let Err(e) = written.and_then(|_| fs::rename(&tmp, &path)) {
+        let _ = fs::remove_file(&tmp);
+        return Err(e);
+    }
+    record_write(link);
+    Ok(())
 }
 
 /// Open a vault directory and return information about it
+///
+/// Later commands are limited to paths inside this vault.
 #[tauri::command]
-pub async fn open_vault(path: PathBuf) -> Result<VaultInfo, FsError> {
+pub async fn open_vault(path: PathBuf, scope: State<'_, VaultScope>) -> Result<VaultInfo, FsError> {
+    let info = vault_info(path)?;
+    scope.open(&info.path)?;
+    Ok(info)
+}
+
+/// Describe a vault directory, adding its configuration if missing
+fn vault_info(path: PathBuf) -> Result<VaultInfo, FsError> {
     if !path.exists() {
         return Err(FsError::NotFound(path.display().to_string()));
     }
@@ -78,7 +177,7 @@ pub async fn open_vault(path: PathBuf) -> Result<VaultInfo, FsError> {
         .unwrap_or_else(|| "Vault".to_string());
 
     // Count markdown files
-    let note_count = count_notes(&path);
+    let note_count = count_notes(&path, &mut TreeWalk::for_path(&path)?);
 
     // Check for git
     let has_git = path.join(".git").exists();
@@ -101,6 +200,72 @@ pub async fn open_vault(path: PathBuf) -> Result<VaultInfo, FsError> {
     })
 }
 
+/// Create a new vault directory and open it
+///
+/// The directory may already exist as long as it is empty. Starter folders,
+/// a welcome note and a git repository are added as the options ask.
+#[tauri::command]
+pub async fn create_vault(
+    path: PathBuf,
+    options: CreateVaultOptions,
+    scope: State<'_, VaultScope>,
+) -> Result<VaultInfo, FsError> {
+    let info = init_vault(path, options)?;
+    scope.open(&info.path)?;
+    Ok(info)
+}
+
+/// Lay out a new vault directory as `create_vault` describes
+fn init_vault(path: PathBuf, options: CreateVaultOptions) -> Result<VaultInfo, FsError> {
+    if path.exists() && (!path.is_dir() || fs::read_dir(&path)?.next().is_some()) {
+        return Err(FsError::InvalidPath("Target exists and is not an empty directory".to_string()));
+    }
+    for folder in &options.folders {
+        if !Path::new(folder).components().all(|c| matches!(c, std::path::Component::Normal(_))) {
+            return Err(FsError::InvalidPath(folder.clone()));
+        }
+    }
+
+    fs::create_dir_all(&path)?;
+    initialize_vault_config(&path)?;
+
+    if let Some(name) = options.name.as_deref().map(str::trim).filter(|n| !n.is_empty()) {
+        let mut config = read_vault_config(&path)?;
+        config.vault.name = name.to_string();
+        write_vault_config(&path, &config)?;
+    }
+
+    for folder in &options.folders {
+        fs::create_dir_all(path.join(folder))?;
+    }
+
+    if options.welcome_note {
+        write_atomic(path.join("Welcome.md"), WELCOME_NOTE)?;
+    }
+
+    if options.init_git {
+        crate::git::git_init(path.to_string_lossy().to_string())
+            .map_err(|e| FsError::Io(std::io::Error::other(e.to_string())))?;
+    }
+
+    vault_info(path)
+}
+
+/// First note of a vault created with `welcome_note`
+const WELCOME_NOTE: &str = r#"---
+title: "Welcome"
+labels: []
+---
+
+# Welcome
+
+This vault keeps your notes as plain markdown files.
+
+- Create notes and folders from the sidebar
+- Link notes with `[[Note name]]`
+- Add code, diagrams and tables to notebooks
+"#;
+
 /// Initialize the .notemaker configuration directory
 fn initialize_vault_config(vault_path: &Path) -> Result<(), FsError> {
     let config_dir = vault_path.join(".notemaker");
@@ -110,7 +275,7 @@ fn initialize_vault_config(vault_path: &Path) -> Result<(), FsError> {
 
     let config = VaultConfig::default();
     let config_content = serde_yaml::to_string(&config)?;
-    fs::write(config_dir.join("config.yaml"), config_content)?;
+    write_atomic(config_dir.join("config.yaml"), config_content)?;
 
     // Create default template
     let default_template = r#"---
@@ -122,25 +287,101 @@ labels: []
 # {{title}}
 
 "#;
-    fs::write(config_dir.join("templates").join("default.md"), default_template)?;
+    write_atomic(config_dir.join("templates").join("default.md"), default_template)?;
 
     Ok(())
 }
 
+/// State of one walk over a directory tree
+///
+/// Carries the vault's ignore patterns and symlink setting, and remembers
+/// the real path of every directory entered so a symlink pointing back up
+/// the tree (or at an already walked folder) isn't descended into again.
+pub(crate) struct TreeWalk {
+    ignore: VaultIgnore,
+    follow_symlinks: bool,
+    /// Keep files other than markdown notes
+    all_files: bool,
+    /// The vault's archive folder
+    archive: PathBuf,
+    hide_archive: bool,
+    visited: HashSet<PathBuf>,
+}
+
+impl TreeWalk {
+    /// Walk settings of the vault containing `path`
+    pub(crate) fn for_path(path: &Path) -> Result<Self, FsError> {
+        let vault = find_vault_root(path).unwrap_or_else(|| path.to_path_buf());
+        let ignore = VaultIgnore::load(&vault)?;
+        Ok(Self::new(path, &vault, ignore))
+    }
+
+    /// Like [`TreeWalk::for_path`], but a broken `.notemakerignore` is
+    /// treated as empty; the file tree reports it
+    fn for_path_lenient(path: &Path) -> Self {
+        let vault = find_vault_root(path).unwrap_or_else(|| path.to_path_buf());
+        let ignore = VaultIgnore::load(&vault).unwrap_or_default();
+        Self::new(path, &vault, ignore)
+    }
+
+    fn new(path: &Path, vault: &Path, ignore: VaultIgnore) -> Self {
+        let config = read_vault_config(vault).unwrap_or_default();
+        let mut walk = Self {
+            ignore,
+            follow_symlinks: config.file_tree.follow_symlinks,
+            all_files: false,
+            archive: vault.join(&config.archive.folder),
+            hide_archive: false,
+            visited: HashSet::new(),
+        };
+        walk.enter(path);
+        walk
+    }
+
+    /// Also walk PDFs, images and other non-markdown files
+    pub(crate) fn with_all_files(mut self, all_files: bool) -> Self {
+        self.all_files = all_files;
+        self
+    }
+
+    /// Leave out the archive folder
+    pub(crate) fn without_archive(mut self, hide: bool) -> Self {
+        self.hide_archive = hide;
+        self
+    }
+
+    /// Whether an entry is left out of the walk
+    fn skips(&self, entry: &fs::DirEntry) -> bool {
+        let path = entry.path();
+        let is_symlink = entry.file_type().map(|t| t.is_symlink()).unwrap_or(false);
+        let is_dir = path.is_dir();
+        let is_note = path.extension().map(|e| e == "md").unwrap_or(false);
+        entry.file_name().to_string_lossy().starts_with('.')
+            || (is_symlink && !self.follow_symlinks)
+            || (!is_dir && !is_note && !self.all_files)
+            || (self.hide_archive && path == self.archive)
+            || self.ignore.is_ignored(&path, is_dir)
+    }
+
+    /// Mark a directory as entered; false when it was walked already
+    fn enter(&mut self, dir: &Path) -> bool {
+        dir.canonicalize().map(|real| self.visited.insert(real)).unwrap_or(false)
+    }
+}
+
 /// Count markdown files in a directory recursively
-fn count_notes(path: &Path) -> usize {
+fn count_notes(path: &Path, walk: &mut TreeWalk) -> usize {
     let mut count = 0;
     if let Ok(entries) = fs::read_dir(path) {
         for entry in entries.flatten() {
+            // Skip hidden and ignored entries
+            if walk.skips(&entry) {
+                continue;
+            }
             let entry_path = entry.path();
             if entry_path.is_dir() {
-                // Skip hidden directories
-                if !entry_path
-                    .file_name()
-                    .map(|n| n.to_string_lossy().starts_with('.'))
-                    .unwrap_or(false)
-                {
-                    count += count_notes(&entry_path);
+                if walk.enter(&entry_path) {
+                    count += count_notes(&entry_path, walk);
                 }
             } else if entry_path.extension().map(|e| e == "md").unwrap_or(false) {
                 count += 1;
@@ -150,8 +391,45 @@ fn count_notes(path: &Path) -> usize {
     count
 }
 
-/// Recursively list contents of a directory
-fn list_directory_recursive(path: &Path) -> Result<Vec<FileEntry>, FsError> {
+/// Collect note files and notebook directories below a path, skipping hidden
+/// entries, kanban boards and anything matched by `.notemakerignore`
+pub(crate) fn walk_notes(path: &Path, notes: &mut Vec<PathBuf>) {
+    walk_notes_with(path, &mut TreeWalk::for_path_lenient(path), notes);
+}
+
+fn walk_notes_with(path: &Path, walk: &mut TreeWalk, notes: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(path) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let entry_path = entry.path();
+        if walk.skips(&entry) || is_kanban(&entry_path) {
+            continue;
+        }
+
+        if is_notebook(&entry_path) {
+            notes.push(entry_path);
+        } else if entry_path.is_dir() {
+            if walk.enter(&entry_path) {
+                walk_notes_with(&entry_path, walk, notes);
+            }
+        } else if entry_path.extension().map(|e| e == "md").unwrap_or(false) {
+            notes.push(entry_path);
+        }
+    }
+}
+
+/// Number of visible entries in a directory
+fn visible_child_count(path: &Path, walk: &TreeWalk) -> u64 {
+    fs::read_dir(path)
+        .map(|entries| entries.flatten().filter(|e| !walk.skips(e)).count() as u64)
+        .unwrap_or(0)
+}
+
+/// List contents of a directory, recursing `depth` levels below it (without
+/// limit when `None`)
+pub(crate) fn list_directory_recursive(path: &Path, depth: Option<u32>, walk: &mut TreeWalk) -> Result<Vec<FileEntry>, FsError> {
     if !path.exists() {
         return Err(FsError::NotFound(path.display().to_string()));
     }
@@ -161,18 +439,25 @@ fn list_directory_recursive(path: &Path) -> Result<Vec<FileEntry>, FsError> {
     for entry in fs::read_dir(path)? {
         let entry = entry?;
         let entry_path = entry.path();
-        let metadata = entry.metadata()?;
+
+        // Skip all hidden files and folders, ignored ones and unfollowed links
+        if walk.skips(&entry) {
+            continue;
+        }
+
+        // Describe what a link points to; a dangling link as the link itself
+        let is_symlink = entry.file_type()?.is_symlink();
+        let metadata = match fs::metadata(&entry_path) {
+            Ok(metadata) => metadata,
+            Err(_) if is_symlink => entry.metadata()?,
+            Err(e) => return Err(e.into()),
+        };
 
         let name = entry_path
             .file_name()
             .map(|n| n.to_string_lossy().to_string())
             .unwrap_or_default();
 
-        // Skip all hidden files and folders
-        if name.starts_with('.') {
-            continue;
-        }
-
         let modified = metadata
             .modified()
             .ok()
@@ -180,20 +465,32 @@ fn list_directory_recursive(path: &Path) -> Result<Vec<FileEntry>, FsError> {
             .map(|d| d.as_secs())
             .unwrap_or(0);
 
-        // Recursively get children for directories
-        let children = if metadata.is_dir() {
-            Some(list_directory_recursive(&entry_path).unwrap_or_default())
-        } else {
-            None
+        // Recursively get children for directories, unless at the depth limit
+        // or already listed through another path
+        let children = match depth {
+            _ if !metadata.is_dir() => None,
+            Some(0) => None,
+            _ if !walk.enter(&entry_path) => Some(Vec::new()),
+            depth => Some(list_directory_recursive(&entry_path, depth.map(|d| d - 1), walk).unwrap_or_default()),
         };
+        let child_count = match &children {
+            Some(children) => Some(children.len() as u64),
+            None if metadata.is_dir() => Some(visible_child_count(&entry_path, walk)),
+            None => None,
+        };
+
+        let mime = (!metadata.is_dir()).then(|| mime_type(&entry_path).to_string());
 
         let file_entry = FileEntry {
             name,
             path: entry_path,
             is_directory: metadata.is_dir(),
+            is_symlink,
+            mime_type: mime,
             modified,
             size: metadata.len(),
             children,
+            child_count,
         };
 
         entries.push(file_entry);
@@ -212,20 +509,57 @@ fn list_directory_recursive(path: &Path) -> Result<Vec<FileEntry>, FsError> {
 }
 
 /// List contents of a directory
+///
+/// Without `depth` the whole tree below it is listed; `depth` limits how
+/// many levels of subdirectories get their children loaded (0 lists only
+/// the directory itself). Only folders and markdown notes are listed unless
+/// `include_files` asks for PDFs, images and other files as well, and the
+/// archive folder only with `include_archived`.
+#[tauri::command]
+pub async fn list_directory(
+    path: PathBuf,
+    depth: Option<u32>,
+    include_files: Option<bool>,
+    include_archived: Option<bool>,
+) -> Result<Vec<FileEntry>, FsError> {
+    let mut walk = TreeWalk::for_path(&path)?
+        .with_all_files(include_files.unwrap_or(false))
+        .without_archive(!include_archived.unwrap_or(false));
+    list_directory_recursive(&path, depth, &mut walk)
+}
+
+/// Direct children of a directory, for expanding the tree on demand
 #[tauri::command]
-pub async fn list_directory(path: PathBuf) -> Result<Vec<FileEntry>, FsError> {
-    list_directory_recursive(&path)
+pub async fn list_directory_children(
+    path: PathBuf,
+    include_files: Option<bool>,
+    include_archived: Option<bool>,
+) -> Result<Vec<FileEntry>, FsError> {
+    let mut walk = TreeWalk::for_path(&path)?
+        .with_all_files(include_files.unwrap_or(false))
+        .without_archive(!include_archived.unwrap_or(false));
+    list_directory_recursive(&path, Some(0), &mut walk)
 }
 
 /// Read a note's content
 #[tauri::command]
 pub async fn read_note(path: PathBuf) -> Result<NoteContent, FsError> {
+    let note = load_note(&path)?;
+    // Opening the note matters more than remembering it; get_recent_files
+    // reports broken local state
+    let _ = record_recent(&path);
+    Ok(note)
+}
+
+/// A note's content as stored, encrypted or not
+pub fn load_note(path: &Path) -> Result<NoteContent, FsError> {
     if !path.exists() {
         return Err(FsError::NotFound(path.display().to_string()));
     }
 
-    let content = fs::read_to_string(&path)?;
-    let metadata = fs::metadata(&path)?;
+    let content = fs::read_to_string(path)?;
+    let metadata = fs::metadata(path)?;
+    let hash = content_hash(&content);
 
     let modified = metadata
         .modified()
@@ -235,22 +569,88 @@ pub async fn read_note(path: PathBuf) -> Result<NoteContent, FsError> {
         .unwrap_or(0);
 
     Ok(NoteContent {
-        path,
+        path: path.to_path_buf(),
         content,
         modified,
+        hash,
     })
 }
 
+/// Hex SHA-256 of a file's content as stored
+pub(crate) fn content_hash(content: impl AsRef<[u8]>) -> String {
+    format!("{:x}", Sha256::digest(content.as_ref()))
+}
+
+/// Seconds since the epoch a file was last modified, 0 when unknown
+pub(crate) fn modified_secs(path: &Path) -> u64 {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
 /// Write content to a note
+///
+/// With `expected_hash` (the `hash` the note was read with) the write is
+/// refused with a [`WriteConflict`] when the file changed since, so external
+/// edits aren't overwritten; the conflict carries the content on disk,
+/// decrypted when the session can. Returns the hash of the written note.
 #[tauri::command]
-pub async fn write_note(path: PathBuf, content: String) -> Result<(), FsError> {
+pub async fn write_note(
+    app_handle: AppHandle,
+    path: PathBuf,
+    content: String,
+    expected_hash: Option<String>,
+    state: State<'_, EncryptionState>,
+) -> Result<String, FsError> {
+    let hash = save_note(&path, content, expected_hash.as_deref(), Some(&state.session(&path)))?;
+    note_saved(&app_handle, &path);
+    Ok(hash)
+}
+
+/// Write a note, encrypting it when a vault policy requires, unless it
+/// changed on disk since it had `expected_hash`; see [`write_note`]
+pub fn save_note(
+    path: &Path,
+    content: String,
+    expected_hash: Option<&str>,
+    session: Option<&EncryptionSession>,
+) -> Result<String, FsError> {
+    if let Some(expected) = expected_hash {
+        if path.exists() {
+            let stored = fs::read_to_string(path)?;
+            let hash = content_hash(&stored);
+            if hash != expected {
+                return Err(write_conflict(path, stored, hash, session));
+            }
+        }
+    }
+
     // Ensure parent directory exists
     if let Some(parent) = path.parent() {
         fs::create_dir_all(parent)?;
     }
 
-    fs::write(&path, content)?;
-    Ok(())
+    let content = apply_encryption_policy(path, content, session)?;
+    let hash = content_hash(&content);
+    write_atomic(path, content)?;
+    Ok(hash)
+}
+
+/// Conflict over a note stored as `stored`, decrypted when possible
+fn write_conflict(path: &Path, stored: String, hash: String, session: Option<&EncryptionSession>) -> FsError {
+    let decrypted = is_encrypted(&stored)
+        .then(|| decrypt_armored(session, &stored, path).ok())
+        .flatten();
+    let encrypted = is_encrypted(&stored) && decrypted.is_none();
+    FsError::Conflict(Box::new(WriteConflict {
+        path: path.to_path_buf(),
+        content: decrypted.unwrap_or(stored),
+        encrypted,
+        hash,
+    }))
 }
 
 /// Create a new note
@@ -259,6 +659,18 @@ pub async fn create_note(
     path: PathBuf,
     title: Option<String>,
     template: Option<String>,
+    state: State<'_, EncryptionState>,
+) -> Result<(), FsError> {
+    new_note(&path, title, template, Some(&state.session(&path)))
+}
+
+/// Create a note from a template, or the vault's default template when none
+/// is given, encrypting it when a vault policy requires
+pub fn new_note(
+    path: &Path,
+    title: Option<String>,
+    template: Option<String>,
+    session: Option<&EncryptionSession>,
 ) -> Result<(), FsError> {
     if path.exists() {
         return Err(FsError::InvalidPath("File already exists".to_string()));
@@ -275,14 +687,15 @@ pub async fn create_note(
             .unwrap_or_else(|| "Untitled".to_string())
     });
 
-    let now = chrono::Utc::now().to_rfc3339();
-
-    // If template content is provided, use it directly
-    // Otherwise, create a basic note with frontmatter
-    let content = if let Some(template_content) = template {
-        template_content
-    } else {
-        format!(
+    // The given template, else the vault's default one, with variables
+    // expanded; a basic note with frontmatter when there is neither
+    let vault_default = match find_vault_root(path) {
+        Some(vault) => read_vault_template(&vault, "default")?,
+        None => None,
+    };
+    let content = match template.or(vault_default) {
+        Some(template) => TemplateContext::for_path(path, &title).render(&template)?,
+        None => format!(
             r#"---
 title: "{}"
 created: "{}"
@@ -292,46 +705,67 @@ labels: []
 # {}
 
 "#,
-            title, now, title
-        )
+            title,
+            chrono::Utc::now().to_rfc3339(),
+            title
+        ),
     };
 
-    fs::write(&path, content)?;
+    let content = apply_encryption_policy(path, content, session)?;
+    write_atomic(path, content)?;
     Ok(())
 }
 
-/// Delete a note (moves to trash on supported platforms)
-#[tauri::command]
-pub async fn delete_note(path: PathBuf) -> Result<(), FsError> {
-    if !path.exists() {
-        return Err(FsError::NotFound(path.display().to_string()));
+/// Append text to the end of a note on a line of its own, creating the
+/// note first when it doesn't exist
+///
+/// An encrypted note is decrypted with the session and encrypted again.
+pub fn append_to_note(path: &Path, text: &str, session: Option<&EncryptionSession>) -> Result<(), FsError> {
+    if is_notebook(path) {
+        return Err(FsError::InvalidPath(format!("Cannot append to a notebook: {}", path.display())));
     }
-
-    // Also delete associated .assets folder if it exists
-    let assets_path = path.with_extension("").join(".assets");
-    if assets_path.exists() {
-        fs::remove_dir_all(&assets_path)?;
+    if !path.exists() {
+        new_note(path, None, None, session)?;
     }
 
-    // Try to move to trash, fallback to direct delete
-    #[cfg(any(target_os = "macos", target_os = "windows", target_os = "linux"))]
-    {
-        if trash::delete(&path).is_err() {
-            fs::remove_file(&path)?;
-        }
+    let stored = fs::read_to_string(path)?;
+    let encrypted = is_encrypted(&stored);
+    let mut content = if encrypted {
+        decrypt_armored(session, &stored, path)?
+    } else {
+        stored
+    };
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
     }
+    content.push_str(text.trim_end_matches('\n'));
+    content.push('\n');
 
-    #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
-    {
-        fs::remove_file(&path)?;
+    let mut content = apply_encryption_policy(path, content, session)?;
+    if encrypted && !is_encrypted(&content) {
+        content = encrypt_armored(session, &content, path)?;
     }
-
+    write_atomic(path, content)?;
     Ok(())
 }
 
+/// Delete a note and its `.assets` folder (moves to trash on supported platforms)
+#[tauri::command]
+pub async fn delete_note(path: PathBuf) -> Result<(), FsError> {
+    delete_entry(&path)
+}
+
 /// Rename a note
+///
+/// Its `.assets` folder is renamed along with it and links into that folder
+/// are updated.
 #[tauri::command]
 pub async fn rename_note(from: PathBuf, to: PathBuf) -> Result<(), FsError> {
+    rename_entry(&from, &to)
+}
+
+/// Rename a note or notebook along with its `.assets` folder; see [`rename_note`]
+pub fn rename_entry(from: &Path, to: &Path) -> Result<(), FsError> {
     if !from.exists() {
         return Err(FsError::NotFound(from.display().to_string()));
     }
@@ -340,61 +774,120 @@ pub async fn rename_note(from: PathBuf, to: PathBuf) -> Result<(), FsError> {
         return Err(FsError::InvalidPath("Target already exists".to_string()));
     }
 
-    fs::rename(&from, &to)?;
+    let from_assets = note_assets_dir(from);
+    fs::rename(from, to)?;
 
-    // Also rename associated .assets folder if it exists
-    let from_assets = from.with_extension("").to_string_lossy().to_string() + ".assets";
-    let to_assets = to.with_extension("").to_string_lossy().to_string() + ".assets";
-    let from_assets_path = PathBuf::from(&from_assets);
-    let to_assets_path = PathBuf::from(&to_assets);
-
-    if from_assets_path.exists() {
-        fs::rename(from_assets_path, to_assets_path)?;
+    if from_assets.exists() {
+        let to_assets = note_assets_dir(to);
+        if to_assets != from_assets {
+            fs::rename(&from_assets, &to_assets)?;
+        }
+    }
+    if is_note(to) {
+        relink_moved_note(from, &from_assets, to)?;
     }
 
     Ok(())
 }
 
-/// Move a note to a different directory
+/// Move a note and its `.assets` folder to a different directory
 #[tauri::command]
 pub async fn move_note(from: PathBuf, to_dir: PathBuf) -> Result<PathBuf, FsError> {
-    if !from.exists() {
-        return Err(FsError::NotFound(from.display().to_string()));
-    }
-
-    if !to_dir.is_dir() {
-        return Err(FsError::InvalidPath("Target is not a directory".to_string()));
-    }
-
-    let file_name = from
-        .file_name()
-        .ok_or_else(|| FsError::InvalidPath("Invalid file name".to_string()))?;
-
-    let to = to_dir.join(file_name);
-
-    if to.exists() {
-        return Err(FsError::InvalidPath("Target already exists".to_string()));
-    }
-
-    fs::rename(&from, &to)?;
+    move_entry(&from, &to_dir)
+}
 
-    Ok(to)
+/// Copy a note or notebook along with its `.assets` folder
+///
+/// Relative links in the copy are adjusted to its new location.
+#[tauri::command]
+pub async fn copy_note(from: PathBuf, to: PathBuf) -> Result<(), FsError> {
+    copy_entry(&from, &to)
 }
 
 /// Create a new directory
 #[tauri::command]
 pub async fn create_directory(path: PathBuf) -> Result<(), FsError> {
+    make_directory(&path)
+}
+
+/// Create a directory and any missing parents; it must not exist yet
+pub fn make_directory(path: &Path) -> Result<(), FsError> {
     if path.exists() {
         return Err(FsError::InvalidPath("Directory already exists".to_string()));
     }
 
-    fs::create_dir_all(&path)?;
+    fs::create_dir_all(path)?;
     Ok(())
 }
 
+/// Point local state paths at or below `from` to `to`
+fn move_state_paths(state: &mut LocalState, from: &Path, to: &Path) {
+    let moved = |path: &str| -> Option<String> {
+        let rest = Path::new(path).strip_prefix(from).ok()?;
+        let target = if rest.as_os_str().is_empty() { to.to_path_buf() } else { to.join(rest) };
+        Some(target.to_string_lossy().to_string())
+    };
+
+    for path in state.expanded_paths.iter_mut().chain(state.pinned.iter_mut()) {
+        if let Some(target) = moved(path.as_str()) {
+            *path = target;
+        }
+    }
+    for recent in &mut state.recent_files {
+        if let Some(target) = moved(recent.path.as_str()) {
+            recent.path = target;
+        }
+    }
+    if let Some(target) = state.last_opened.as_deref().and_then(moved) {
+        state.last_opened = Some(target);
+    }
+}
+
+/// Rename or move a directory
+///
+/// Path-style links into it are rewritten and the file tree's expanded
+/// folders, pins and recent files follow it. Returns the notes whose links
+/// were updated.
+#[tauri::command]
+pub async fn rename_directory(from: PathBuf, to: PathBuf) -> Result<Vec<PathBuf>, FsError> {
+    move_directory(&from, &to)
+}
+
+/// Rename or move a directory; see [`rename_directory`]
+pub fn move_directory(from: &Path, to: &Path) -> Result<Vec<PathBuf>, FsError> {
+    if !from.is_dir() {
+        return Err(FsError::NotFound(from.display().to_string()));
+    }
+    if to.exists() {
+        return Err(FsError::InvalidPath("Target already exists".to_string()));
+    }
+    if to.starts_with(from) {
+        return Err(FsError::InvalidPath("Cannot move a folder into itself".to_string()));
+    }
+
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::rename(from, to)?;
+
+    let Some(vault) = find_vault_root(to) else {
+        return Ok(Vec::new());
+    };
+    let mut state = read_local_state(&vault)?;
+    move_state_paths(&mut state, from, to);
+    write_local_state(&vault, &state)?;
+
+    update_links_after_folder_rename(&vault, from, to)
+}
+
 /// Delete a directory
 #[tauri::command]
 pub async fn delete_directory(path: PathBuf) -> Result<(), FsError> {
+    remove_directory(&path)
+}
+
+/// Move a directory to the trash, or delete it where there is none
+pub fn remove_directory(path: &Path) -> Result<(), FsError> {
     if !path.exists() {
         return Err(FsError::NotFound(path.display().to_string()));
     }
@@ -406,14 +899,14 @@ pub async fn delete_directory(path: PathBuf) -> Result<(), FsError> {
     // Try to move to trash, fallback to direct delete
     #[cfg(any(target_os = "macos", target_os = "windows", target_os = "linux"))]
     {
-        if trash::delete(&path).is_err() {
-            fs::remove_dir_all(&path)?;
+        if trash::delete(path).is_err() {
+            fs::remove_dir_all(path)?;
         }
     }
 
     #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
     {
-        fs::remove_dir_all(&path)?;
+        fs::remove_dir_all(path)?;
     }
 
     Ok(())
@@ -422,6 +915,11 @@ pub async fn delete_directory(path: PathBuf) -> Result<(), FsError> {
 /// Get vault configuration
 #[tauri::command]
 pub async fn get_vault_config(vault_path: PathBuf) -> Result<VaultConfig, FsError> {
+    read_vault_config(&vault_path)
+}
+
+/// Read the vault configuration, falling back to defaults when missing
+pub(crate) fn read_vault_config(vault_path: &Path) -> Result<VaultConfig, FsError> {
     let config_path = vault_path.join(".notemaker").join("config.yaml");
 
     if !config_path.exists() {
@@ -434,21 +932,32 @@ pub async fn get_vault_config(vault_path: PathBuf) -> Result<VaultConfig, FsErro
     Ok(config)
 }
 
+/// Find the vault containing a path by looking for a `.notemaker` directory
+pub fn find_vault_root(path: &Path) -> Option<PathBuf> {
+    path.ancestors()
+        .find(|dir| dir.join(".notemaker").is_dir())
+        .map(Path::to_path_buf)
+}
+
 /// Save vault configuration
 #[tauri::command]
 pub async fn save_vault_config(vault_path: PathBuf, config: VaultConfig) -> Result<(), FsError> {
+    write_vault_config(&vault_path, &config)
+}
+
+/// Write the vault configuration to `.notemaker/config.yaml`
+pub(crate) fn write_vault_config(vault_path: &Path, config: &VaultConfig) -> Result<(), FsError> {
     let config_dir = vault_path.join(".notemaker");
     fs::create_dir_all(&config_dir)?;
 
-    let config_content = serde_yaml::to_string(&config)?;
-    fs::write(config_dir.join("config.yaml"), config_content)?;
+    let config_content = serde_yaml::to_string(config)?;
+    write_atomic(config_dir.join("config.yaml"), config_content)?;
 
     Ok(())
 }
 
-/// Get local state (not versioned)
-#[tauri::command]
-pub async fn get_local_state(vault_path: PathBuf) -> Result<LocalState, FsError> {
+/// Read local state, defaulting when the vault has none yet
+pub(crate) fn read_local_state(vault_path: &Path) -> Result<LocalState, FsError> {
     let state_path = vault_path.join(".notemaker").join(".local").join("state.json");
 
     if !state_path.exists() {
@@ -462,19 +971,39 @@ pub async fn get_local_state(vault_path: PathBuf) -> Result<LocalState, FsError>
     Ok(state)
 }
 
-/// Save local state (not versioned)
-#[tauri::command]
-pub async fn save_local_state(vault_path: PathBuf, state: LocalState) -> Result<(), FsError> {
+/// Write local state to `.notemaker/.local/state.json`
+pub(crate) fn write_local_state(vault_path: &Path, state: &LocalState) -> Result<(), FsError> {
     let local_dir = vault_path.join(".notemaker").join(".local");
     fs::create_dir_all(&local_dir)?;
 
-    let state_content = serde_json::to_string_pretty(&state)
+    let state_content = serde_json::to_string_pretty(state)
         .map_err(|e| FsError::InvalidPath(format!("Failed to serialize state: {}", e)))?;
-    fs::write(local_dir.join("state.json"), state_content)?;
+    write_atomic(local_dir.join("state.json"), state_content)?;
 
     Ok(())
 }
 
+/// Get local state (not versioned)
+#[tauri::command]
+pub async fn get_local_state(vault_path: PathBuf) -> Result<LocalState, FsError> {
+    read_local_state(&vault_path)
+}
+
+/// Save local state (not versioned)
+///
+/// Recent files and pins are maintained by the backend, so the ones on disk
+/// are kept rather than replaced by a possibly stale copy from a window.
+#[tauri::command]
+pub async fn save_local_state(vault_path: PathBuf, state: LocalState) -> Result<(), FsError> {
+    let current = read_local_state(&vault_path).unwrap_or_default();
+    let state = LocalState {
+        recent_files: current.recent_files,
+        pinned: current.pinned,
+        ..state
+    };
+    write_local_state(&vault_path, &state)
+}
+
 /// Select a directory using native dialog
 #[tauri::command]
 pub async fn select_directory() -> Result<Option<PathBuf>, FsError> {
@@ -485,44 +1014,321 @@ pub async fn select_directory() -> Result<Option<PathBuf>, FsError> {
     Ok(None)
 }
 
-// =============================================================================
-// Notebook Commands
-// =============================================================================
+// =============================================================================
+// Notebook Commands
+// =============================================================================
+
+/// Check if a path is a notebook (directory ending with .md)
+pub fn is_notebook(path: &Path) -> bool {
+    path.is_dir() && path.extension().map(|e| e == "md").unwrap_or(false)
+}
+
+/// Get the index file path for a notebook
+pub(crate) fn notebook_index_path(notebook_path: &Path) -> PathBuf {
+    notebook_path.join(".index.json")
+}
+
+/// Read notebook index, creating default if doesn't exist
+pub(crate) fn read_notebook_index(notebook_path: &Path) -> Result<NotebookIndex, FsError> {
+    read_notebook_index_with(notebook_path, None)
+}
+
+/// Decrypt armored content with an unlocked session
+pub(crate) fn decrypt_armored(session: Option<&EncryptionSession>, content: &str, what: &Path) -> Result<String, FsError> {
+    let session = session
+        .filter(|s| s.is_unlocked())
+        .ok_or_else(|| FsError::Encrypted(format!("{} (session is locked)", what.display())))?;
+    let plaintext = dearmor_decrypt(content)
+        .and_then(|ciphertext| decrypt_with_session(session, &ciphertext))
+        .map_err(|e| FsError::Encrypted(format!("{}: {}", what.display(), e)))?;
+    String::from_utf8(plaintext).map_err(|e| FsError::Encrypted(format!("{}: {}", what.display(), e)))
+}
+
+/// Encrypt and armor content with an unlocked session
+pub(crate) fn encrypt_armored(session: Option<&EncryptionSession>, content: &str, what: &Path) -> Result<String, FsError> {
+    let session = session
+        .filter(|s| s.is_unlocked())
+        .ok_or_else(|| FsError::Encrypted(format!("{} (session is locked)", what.display())))?;
+    let ciphertext = encrypt_with_session(session, content.as_bytes())
+        .map_err(|e| FsError::Encrypted(format!("{}: {}", what.display(), e)))?;
+    Ok(armor_encrypt(&ciphertext))
+}
+
+/// Read notebook index, decrypting an encrypted index with the session
+pub(crate) fn read_notebook_index_with(
+    notebook_path: &Path,
+    session: Option<&EncryptionSession>,
+) -> Result<NotebookIndex, FsError> {
+    let index_path = notebook_index_path(notebook_path);
+    if index_path.exists() {
+        let mut content = fs::read_to_string(&index_path)?;
+        if is_encrypted(&content) {
+            content = decrypt_armored(session, &content, notebook_path)?;
+        }
+        load_notebook_index(notebook_path, &content, session)
+    } else {
+        Ok(NotebookIndex::default())
+    }
+}
+
+/// Refuse changes to a locked block
+pub(crate) fn ensure_unlocked(block: &NotebookBlock) -> Result<(), FsError> {
+    if block.locked {
+        return Err(FsError::Locked(block.id.clone()));
+    }
+    Ok(())
+}
+
+/// Whether a block is stored encrypted, on its own or as part of an
+/// encrypted notebook
+pub(crate) fn is_block_encrypted(index: &NotebookIndex, block: &NotebookBlock) -> bool {
+    index.encrypted || block.encrypted.unwrap_or(false)
+}
+
+/// Content of a block file; encrypted blocks are decrypted in memory when
+/// the session is unlocked and returned armored otherwise
+pub(crate) fn read_block_content(
+    notebook_path: &Path,
+    index: &NotebookIndex,
+    block: &NotebookBlock,
+    session: Option<&EncryptionSession>,
+) -> Result<String, FsError> {
+    let block_path = notebook_path.join(&block.file);
+    if !block_path.exists() {
+        return Ok(String::new());
+    }
+    let content = fs::read_to_string(&block_path)?;
+    if is_block_encrypted(index, block) && is_encrypted(&content) && session.is_some_and(|s| s.is_unlocked()) {
+        return decrypt_armored(session, &content, &block_path);
+    }
+    Ok(content)
+}
+
+/// Write a block file, encrypting plaintext for encrypted blocks and for
+/// notebooks an encryption policy covers
+pub(crate) fn write_block_content(
+    notebook_path: &Path,
+    encrypted: bool,
+    file: &str,
+    content: &str,
+    session: Option<&EncryptionSession>,
+) -> Result<(), FsError> {
+    let block_path = notebook_path.join(file);
+    if encrypted && !is_encrypted(content) {
+        let armored = encrypt_armored(session, content, &block_path)?;
+        write_atomic(&block_path, armored)?;
+    } else {
+        write_atomic(&block_path, apply_encryption_policy(&block_path, content.to_string(), session)?)?;
+    }
+    Ok(())
+}
+
+/// File name of a block; encrypted indexes hide the language
+pub(crate) fn block_file_name(index: &NotebookIndex, block_id: &str, block_type: &BlockType, language: Option<&str>) -> String {
+    let extension = if index.encrypted_index {
+        "age"
+    } else if *block_type == BlockType::Code {
+        language_to_extension(language.unwrap_or("txt"))
+    } else if *block_type == BlockType::Math {
+        "tex"
+    } else if *block_type == BlockType::Table {
+        if language == Some("tsv") {
+            "tsv"
+        } else {
+            "csv"
+        }
+    } else {
+        "md"
+    };
+    format!("{}.{}", block_id, extension)
+}
+
+/// Encrypt every block file of a notebook, and the index when `encrypt_index`
+/// is set
+///
+/// With an encrypted index, block files are renamed to `<id>.age` so their
+/// names no longer reveal the language.
+pub(crate) fn encrypt_notebook_files(
+    notebook_path: &Path,
+    session: &EncryptionSession,
+    encrypt_index: bool,
+) -> Result<(), FsError> {
+    if !is_notebook(notebook_path) {
+        return Err(FsError::InvalidPath("Not a notebook".to_string()));
+    }
+    let mut index = read_notebook_index_with(notebook_path, Some(session))?;
+    index.encrypted = true;
+    index.encrypted_index = encrypt_index;
+
+    let blocks = std::mem::take(&mut index.blocks);
+    for mut block in blocks {
+        let old_path = notebook_path.join(&block.file);
+        let content = if old_path.exists() {
+            fs::read_to_string(&old_path)?
+        } else {
+            String::new()
+        };
+        let file = block_file_name(&index, &block.id, &block.block_type, block.language.as_deref());
+        write_block_content(notebook_path, true, &file, &content, Some(session))?;
+        if file != block.file && old_path.exists() {
+            fs::remove_file(&old_path)?;
+        }
+        block.file = file;
+        block.encrypted = Some(true);
+        index.blocks.push(block);
+    }
+
+    write_notebook_index_with(notebook_path, &index, Some(session))?;
+    reseal_block_outputs(notebook_path, Some(session))
+}
+
+/// Decrypt every block file and the index of a notebook
+pub(crate) fn decrypt_notebook_files(notebook_path: &Path, session: &EncryptionSession) -> Result<(), FsError> {
+    if !is_notebook(notebook_path) {
+        return Err(FsError::InvalidPath("Not a notebook".to_string()));
+    }
+    let mut index = read_notebook_index_with(notebook_path, Some(session))?;
+
+    let blocks = std::mem::take(&mut index.blocks);
+    let mut plain = NotebookIndex {
+        encrypted: false,
+        encrypted_index: false,
+        ..index.clone()
+    };
+    for mut block in blocks {
+        let old_path = notebook_path.join(&block.file);
+        let content = read_block_content(notebook_path, &index, &block, Some(session))?;
+        let content = if is_encrypted(&content) {
+            decrypt_armored(Some(session), &content, &old_path)?
+        } else {
+            content
+        };
+        let file = block_file_name(&plain, &block.id, &block.block_type, block.language.as_deref());
+        write_atomic(notebook_path.join(&file), content)?;
+        if file != block.file && old_path.exists() {
+            fs::remove_file(&old_path)?;
+        }
+        block.file = file;
+        block.encrypted = None;
+        plain.blocks.push(block);
+    }
+
+    write_notebook_index_with(notebook_path, &plain, Some(session))?;
+    reseal_block_outputs(notebook_path, Some(session))
+}
+
+/// Encrypt a single block of a notebook on disk and flag it in the index
+pub(crate) fn encrypt_block_file(
+    notebook_path: &Path,
+    block_id: &str,
+    session: &EncryptionSession,
+) -> Result<(), FsError> {
+    let mut index = read_notebook_index_with(notebook_path, Some(session))?;
+    let block = index
+        .blocks
+        .iter_mut()
+        .find(|b| b.id == block_id)
+        .ok_or_else(|| FsError::NotFound(format!("Block not found: {}", block_id)))?;
+
+    let block_path = notebook_path.join(&block.file);
+    let content = if block_path.exists() {
+        fs::read_to_string(&block_path)?
+    } else {
+        String::new()
+    };
+    write_block_content(notebook_path, true, &block.file, &content, Some(session))?;
+    block.encrypted = Some(true);
+
+    write_notebook_index_with(notebook_path, &index, Some(session))?;
+    reseal_block_outputs(notebook_path, Some(session))
+}
+
+/// Decrypt a single block of a notebook on disk and clear its index flag
+///
+/// Blocks of a notebook encrypted as a whole stay encrypted; use
+/// [`decrypt_notebook_files`] for those.
+pub(crate) fn decrypt_block_file(
+    notebook_path: &Path,
+    block_id: &str,
+    session: &EncryptionSession,
+) -> Result<(), FsError> {
+    let mut index = read_notebook_index_with(notebook_path, Some(session))?;
+    if index.encrypted {
+        return Err(FsError::InvalidPath("The whole notebook is encrypted".to_string()));
+    }
+    let block = index
+        .blocks
+        .iter_mut()
+        .find(|b| b.id == block_id)
+        .ok_or_else(|| FsError::NotFound(format!("Block not found: {}", block_id)))?;
 
-/// Check if a path is a notebook (directory ending with .md)
-pub fn is_notebook(path: &Path) -> bool {
-    path.is_dir() && path.extension().map(|e| e == "md").unwrap_or(false)
+    let block_path = notebook_path.join(&block.file);
+    if block_path.exists() {
+        let content = fs::read_to_string(&block_path)?;
+        if is_encrypted(&content) {
+            write_atomic(&block_path, decrypt_armored(Some(session), &content, &block_path)?)?;
+        }
+    }
+    block.encrypted = None;
+
+    write_notebook_index_with(notebook_path, &index, Some(session))?;
+    reseal_block_outputs(notebook_path, Some(session))
 }
 
-/// Get the index file path for a notebook
-fn notebook_index_path(notebook_path: &Path) -> PathBuf {
-    notebook_path.join(".index.json")
+/// Files holding the markdown text of a note: the note itself, or the
+/// markdown blocks of a notebook in index order
+pub(crate) fn markdown_files(path: &Path) -> Result<Vec<PathBuf>, FsError> {
+    if !is_notebook(path) {
+        return Ok(vec![path.to_path_buf()]);
+    }
+
+    let index = match read_notebook_index(path) {
+        Ok(index) => index,
+        // Nothing readable without the key
+        Err(FsError::Encrypted(_)) => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    Ok(index
+        .blocks
+        .iter()
+        .filter(|b| b.block_type == BlockType::Markdown)
+        .map(|b| path.join(&b.file))
+        .filter(|p| p.exists())
+        .collect())
 }
 
-/// Read notebook index, creating default if doesn't exist
-fn read_notebook_index(notebook_path: &Path) -> Result<NotebookIndex, FsError> {
-    let index_path = notebook_index_path(notebook_path);
-    if index_path.exists() {
-        let content = fs::read_to_string(&index_path)?;
-        let index: NotebookIndex = serde_json::from_str(&content)
-            .map_err(|e| FsError::InvalidPath(format!("Invalid index.json: {}", e)))?;
-        Ok(index)
-    } else {
-        Ok(NotebookIndex::default())
+/// Read the markdown text of a note or notebook
+pub(crate) fn read_markdown_text(path: &Path) -> Result<String, FsError> {
+    let mut parts = Vec::new();
+    for file in markdown_files(path)? {
+        parts.push(fs::read_to_string(&file)?);
     }
+    Ok(parts.join("\n\n"))
 }
 
 /// Write notebook index
 fn write_notebook_index(notebook_path: &Path, index: &NotebookIndex) -> Result<(), FsError> {
+    write_notebook_index_with(notebook_path, index, None)
+}
+
+/// Write notebook index, encrypting it when the notebook has an encrypted index
+pub(crate) fn write_notebook_index_with(
+    notebook_path: &Path,
+    index: &NotebookIndex,
+    session: Option<&EncryptionSession>,
+) -> Result<(), FsError> {
     let index_path = notebook_index_path(notebook_path);
-    let content = serde_json::to_string_pretty(index)
+    let mut content = serde_json::to_string_pretty(index)
         .map_err(|e| FsError::InvalidPath(format!("Failed to serialize index: {}", e)))?;
-    fs::write(&index_path, content)?;
+    if index.encrypted_index {
+        content = encrypt_armored(session, &content, notebook_path)?;
+    }
+    write_atomic(&index_path, content)?;
     Ok(())
 }
 
 /// Generate a unique block ID
-fn generate_block_id() -> String {
+pub(crate) fn generate_block_id() -> String {
     use std::time::{SystemTime, UNIX_EPOCH};
     let now = SystemTime::now()
         .duration_since(UNIX_EPOCH)
@@ -573,19 +1379,26 @@ pub async fn create_notebook(path: PathBuf, title: Option<String>) -> Result<Not
             .unwrap_or_else(|| "Untitled".to_string())
     });
 
-    let initial_content = format!("# {}\n\n", title);
-    fs::write(path.join(&block_file), &initial_content)?;
+    // The first block comes from the vault's notebook template if it has one
+    let template = match find_vault_root(&path) {
+        Some(vault) => read_vault_template(&vault, "notebook")?,
+        None => None,
+    };
+    let initial_content = TemplateContext::for_path(&path, &title)
+        .render(template.as_deref().unwrap_or("# {{title}}\n\n"))?;
+    write_atomic(path.join(&block_file), &initial_content)?;
 
     // Create index
     let index = NotebookIndex {
-        version: 1,
         blocks: vec![NotebookBlock {
             id: block_id.clone(),
             block_type: BlockType::Markdown,
             file: block_file,
             language: None,
             encrypted: None,
+            ..Default::default()
         }],
+        ..Default::default()
     };
     write_notebook_index(&path, &index)?;
 
@@ -599,39 +1412,44 @@ pub async fn create_notebook(path: PathBuf, title: Option<String>) -> Result<Not
             language: None,
             content: initial_content,
             encrypted: None,
+            ..Default::default()
         }],
     })
 }
 
 /// Read a notebook and all its blocks
+///
+/// Encrypted notebooks are decrypted in memory when the session is unlocked.
 #[tauri::command]
-pub async fn read_notebook(path: PathBuf) -> Result<Notebook, FsError> {
+pub async fn read_notebook(path: PathBuf, state: State<'_, EncryptionState>) -> Result<Notebook, FsError> {
+    recover_notebook_save(&path)?;
+    let session = state.session(&path);
+    let notebook = load_notebook(path, Some(&session))?;
+    let _ = record_recent(&notebook.path);
+    Ok(notebook)
+}
+
+pub(crate) fn load_notebook(path: PathBuf, session: Option<&EncryptionSession>) -> Result<Notebook, FsError> {
     if !is_notebook(&path) {
         return Err(FsError::InvalidPath("Not a notebook".to_string()));
     }
 
-    let index = read_notebook_index(&path)?;
+    let index = read_notebook_index_with(&path, session)?;
     let name = path
         .file_stem()
         .and_then(|s| s.to_str())
         .map(|s| s.trim_end_matches(".md").to_string())
         .unwrap_or_else(|| "Untitled".to_string());
 
+    // Outputs are only missing from the result while the session is locked
+    let mut outputs = read_block_outputs(&path, session).unwrap_or_default();
     let mut blocks = Vec::new();
     for block in &index.blocks {
-        let block_path = path.join(&block.file);
-        let content = if block_path.exists() {
-            fs::read_to_string(&block_path)?
-        } else {
-            String::new()
-        };
+        let content = read_block_content(&path, &index, block, session)?;
 
         blocks.push(NotebookBlockWithContent {
-            id: block.id.clone(),
-            block_type: block.block_type.clone(),
-            language: block.language.clone(),
-            content,
-            encrypted: block.encrypted,
+            last_output: outputs.remove(&block.id),
+            ..NotebookBlockWithContent::from_block(block, content)
         });
     }
 
@@ -645,27 +1463,27 @@ pub async fn add_notebook_block(
     block_type: BlockType,
     language: Option<String>,
     after_block_id: Option<String>,
+    state: State<'_, EncryptionState>,
 ) -> Result<NotebookBlockWithContent, FsError> {
-    let mut index = read_notebook_index(&notebook_path)?;
+    let session = state.session(&notebook_path);
+    let session = Some(session.as_ref());
+    let mut index = read_notebook_index_with(&notebook_path, session)?;
 
     let block_id = generate_block_id();
-    let extension = if block_type == BlockType::Code {
-        language_to_extension(language.as_deref().unwrap_or("txt"))
-    } else {
-        "md"
-    };
-    let block_file = format!("{}.{}", block_id, extension);
+    let block_file = block_file_name(&index, &block_id, &block_type, language.as_deref());
 
     // Create empty block file
     let initial_content = String::new();
-    fs::write(notebook_path.join(&block_file), &initial_content)?;
+    write_block_content(&notebook_path, index.encrypted, &block_file, &initial_content, session)?;
 
+    let encrypted = index.encrypted.then_some(true);
     let new_block = NotebookBlock {
         id: block_id.clone(),
         block_type: block_type.clone(),
         file: block_file,
         language: language.clone(),
-        encrypted: None,
+        encrypted,
+        ..Default::default()
     };
 
     // Insert at correct position
@@ -679,14 +1497,15 @@ pub async fn add_notebook_block(
         index.blocks.push(new_block);
     }
 
-    write_notebook_index(&notebook_path, &index)?;
+    write_notebook_index_with(&notebook_path, &index, session)?;
 
     Ok(NotebookBlockWithContent {
         id: block_id,
         block_type,
         language,
         content: initial_content,
-        encrypted: None,
+        encrypted,
+        ..Default::default()
     })
 }
 
@@ -696,17 +1515,29 @@ pub async fn update_notebook_block(
     notebook_path: PathBuf,
     block_id: String,
     content: String,
+    state: State<'_, EncryptionState>,
 ) -> Result<(), FsError> {
-    let index = read_notebook_index(&notebook_path)?;
+    let session = state.session(&notebook_path);
+    update_block(&notebook_path, &block_id, &content, Some(&session))
+}
+
+/// Replace a block's content, refusing locked blocks
+pub(crate) fn update_block(
+    notebook_path: &Path,
+    block_id: &str,
+    content: &str,
+    session: Option<&EncryptionSession>,
+) -> Result<(), FsError> {
+    let index = read_notebook_index_with(notebook_path, session)?;
 
     let block = index
         .blocks
         .iter()
         .find(|b| b.id == block_id)
         .ok_or_else(|| FsError::NotFound(format!("Block not found: {}", block_id)))?;
+    ensure_unlocked(block)?;
 
-    let block_path = notebook_path.join(&block.file);
-    fs::write(&block_path, content)?;
+    write_block_content(notebook_path, is_block_encrypted(&index, block), &block.file, content, session)?;
 
     Ok(())
 }
@@ -716,14 +1547,26 @@ pub async fn update_notebook_block(
 pub async fn delete_notebook_block(
     notebook_path: PathBuf,
     block_id: String,
+    state: State<'_, EncryptionState>,
 ) -> Result<(), FsError> {
-    let mut index = read_notebook_index(&notebook_path)?;
+    let session = state.session(&notebook_path);
+    delete_block(&notebook_path, &block_id, Some(&session))
+}
+
+/// Remove a block and its file, refusing locked blocks
+pub(crate) fn delete_block(
+    notebook_path: &Path,
+    block_id: &str,
+    session: Option<&EncryptionSession>,
+) -> Result<(), FsError> {
+    let mut index = read_notebook_index_with(notebook_path, session)?;
 
     let block_pos = index
         .blocks
         .iter()
         .position(|b| b.id == block_id)
         .ok_or_else(|| FsError::NotFound(format!("Block not found: {}", block_id)))?;
+    ensure_unlocked(&index.blocks[block_pos])?;
 
     let block = index.blocks.remove(block_pos);
 
@@ -733,7 +1576,7 @@ pub async fn delete_notebook_block(
         fs::remove_file(&block_path)?;
     }
 
-    write_notebook_index(&notebook_path, &index)?;
+    write_notebook_index_with(notebook_path, &index, session)?;
 
     Ok(())
 }
@@ -744,8 +1587,11 @@ pub async fn move_notebook_block(
     notebook_path: PathBuf,
     block_id: String,
     new_index: usize,
+    state: State<'_, EncryptionState>,
 ) -> Result<(), FsError> {
-    let mut index = read_notebook_index(&notebook_path)?;
+    let session = state.session(&notebook_path);
+    let session = Some(session.as_ref());
+    let mut index = read_notebook_index_with(&notebook_path, session)?;
 
     let block_pos = index
         .blocks
@@ -757,11 +1603,49 @@ pub async fn move_notebook_block(
     let insert_pos = new_index.min(index.blocks.len());
     index.blocks.insert(insert_pos, block);
 
-    write_notebook_index(&notebook_path, &index)?;
+    write_notebook_index_with(&notebook_path, &index, session)?;
+
+    Ok(())
+}
+
+/// Put an index's blocks in the given order; `ordered_ids` must list every
+/// block exactly once
+pub(crate) fn reorder_blocks(index: &mut NotebookIndex, ordered_ids: &[String]) -> Result<(), FsError> {
+    if ordered_ids.len() != index.blocks.len() {
+        return Err(FsError::InvalidPath(format!(
+            "Expected {} block IDs, got {}",
+            index.blocks.len(),
+            ordered_ids.len()
+        )));
+    }
 
+    let mut remaining = index.blocks.clone();
+    let mut blocks = Vec::with_capacity(remaining.len());
+    for id in ordered_ids {
+        let pos = remaining
+            .iter()
+            .position(|b| &b.id == id)
+            .ok_or_else(|| FsError::NotFound(format!("Block not found: {}", id)))?;
+        blocks.push(remaining.remove(pos));
+    }
+    index.blocks = blocks;
     Ok(())
 }
 
+/// Set the order of all blocks at once
+#[tauri::command]
+pub async fn reorder_notebook_blocks(
+    notebook_path: PathBuf,
+    ordered_ids: Vec<String>,
+    state: State<'_, EncryptionState>,
+) -> Result<(), FsError> {
+    let session = state.session(&notebook_path);
+    let session = Some(session.as_ref());
+    let mut index = read_notebook_index_with(&notebook_path, session)?;
+    reorder_blocks(&mut index, &ordered_ids)?;
+    write_notebook_index_with(&notebook_path, &index, session)
+}
+
 /// Change a block's type/language
 #[tauri::command]
 pub async fn change_block_type(
@@ -769,57 +1653,67 @@ pub async fn change_block_type(
     block_id: String,
     new_type: BlockType,
     new_language: Option<String>,
+    state: State<'_, EncryptionState>,
+) -> Result<NotebookBlockWithContent, FsError> {
+    let session = state.session(&notebook_path);
+    retype_block(&notebook_path, &block_id, new_type, new_language, Some(&session))
+}
+
+/// Change a block's type and language, moving its content to a file with
+/// the matching extension; locked blocks are refused
+pub(crate) fn retype_block(
+    notebook_path: &Path,
+    block_id: &str,
+    new_type: BlockType,
+    new_language: Option<String>,
+    session: Option<&EncryptionSession>,
 ) -> Result<NotebookBlockWithContent, FsError> {
-    let mut index = read_notebook_index(&notebook_path)?;
+    let mut index = read_notebook_index_with(notebook_path, session)?;
+    let new_file = block_file_name(&index, block_id, &new_type, new_language.as_deref());
 
     let block = index
         .blocks
         .iter_mut()
         .find(|b| b.id == block_id)
         .ok_or_else(|| FsError::NotFound(format!("Block not found: {}", block_id)))?;
+    ensure_unlocked(block)?;
 
-    // Read current content
+    // Read current content, as stored so encrypted blocks stay encrypted
     let old_path = notebook_path.join(&block.file);
-    let content = if old_path.exists() {
+    let raw_content = if old_path.exists() {
         fs::read_to_string(&old_path)?
     } else {
         String::new()
     };
 
-    // Determine new extension
-    let new_extension = if new_type == BlockType::Code {
-        language_to_extension(new_language.as_deref().unwrap_or("txt"))
-    } else {
-        "md"
+    // Math blocks are stored without the `$$` delimiters markdown needs
+    let raw_content = match (&block.block_type, &new_type) {
+        _ if is_encrypted(&raw_content) => raw_content,
+        (BlockType::Markdown, BlockType::Math) => strip_math_delimiters(&raw_content),
+        (BlockType::Math, BlockType::Markdown) => math_markdown(&raw_content),
+        _ => raw_content,
     };
 
-    // Create new file name
-    let new_file = format!("{}.{}", block_id, new_extension);
     let new_path = notebook_path.join(&new_file);
 
     // Write content to new file
-    fs::write(&new_path, &content)?;
+    write_atomic(&new_path, &raw_content)?;
 
     // Delete old file if different
     if old_path != new_path && old_path.exists() {
         fs::remove_file(&old_path)?;
     }
 
-    // Update block info and capture encrypted before releasing mutable borrow
-    block.block_type = new_type.clone();
-    block.language = new_language.clone();
+    // Update block info before releasing the mutable borrow
+    block.block_type = new_type;
+    block.language = new_language;
     block.file = new_file;
-    let encrypted = block.encrypted;
+    let block = block.clone();
 
-    write_notebook_index(&notebook_path, &index)?;
+    write_notebook_index_with(notebook_path, &index, session)?;
+    let content = read_block_content(notebook_path, &index, &block, session)?;
 
-    Ok(NotebookBlockWithContent {
-        id: block_id,
-        block_type: new_type,
-        language: new_language,
-        content,
-        encrypted,
-    })
+    Ok(NotebookBlockWithContent::from_block(&block, content))
 }
 
 // =============================================================================
@@ -827,7 +1721,7 @@ pub async fn change_block_type(
 // =============================================================================
 
 /// Result of code execution
-#[derive(Debug, Clone, serde::Serialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct CodeExecutionResult {
     pub stdout: String,
     pub stderr: String,
@@ -840,71 +1734,51 @@ pub struct CodeExecutionResult {
 /// - shell: Executes via shell interpreter (default: bash, configurable)
 /// - python: Executes via python3 -c
 /// - ruby: Executes via ruby -e
+/// - node/javascript: Executes via node -e
+/// - deno/typescript: Executes via deno eval
+/// - go: Runs a temporary main.go via go run
+/// - rust: Runs a temporary cargo project, or a script via rust-script
+///
+/// Without an explicit `interpreter` the vault's interpreter settings are used.
+/// `container` runs the block in a Docker/Podman container (or on the host
+/// when false) instead of following the vault's container setting. Without a
+/// `working_dir` the block runs in the folder containing `notebook_path`.
+/// The process receives the vault's `env.yaml` variables and the `.env` file
+/// next to `notebook_path` (see [`execution_env`]).
 #[tauri::command]
 pub async fn execute_code_block(
     language: String,
     code: String,
     working_dir: Option<PathBuf>,
     interpreter: Option<String>,
+    notebook_path: Option<PathBuf>,
+    container: Option<bool>,
 ) -> Result<CodeExecutionResult, FsError> {
-    let work_dir = working_dir.unwrap_or_else(|| std::env::temp_dir());
-
-    let result = match language.to_lowercase().as_str() {
-        "shell" => {
-            let shell = interpreter.unwrap_or_else(|| "bash".to_string());
-            execute_shell(&code, &work_dir, &shell)
-        }
-        "python" => {
-            let python = interpreter.unwrap_or_else(|| "python3".to_string());
-            execute_python(&code, &work_dir, &python)
-        }
-        "ruby" => {
-            let ruby = interpreter.unwrap_or_else(|| "ruby".to_string());
-            execute_ruby(&code, &work_dir, &ruby)
-        }
-        _ => Err(FsError::InvalidPath(format!("Unsupported language: {}", language))),
-    }?;
-
-    Ok(result)
-}
-
-fn execute_shell(code: &str, working_dir: &Path, interpreter: &str) -> Result<CodeExecutionResult, FsError> {
-    let output = Command::new(interpreter)
-        .arg("-c")
-        .arg(code)
-        .current_dir(working_dir)
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .output()?;
-
-    Ok(CodeExecutionResult {
-        stdout: String::from_utf8_lossy(&output.stdout).to_string(),
-        stderr: String::from_utf8_lossy(&output.stderr).to_string(),
-        exit_code: output.status.code().unwrap_or(-1),
-    })
+    let work_dir = resolve_working_dir(working_dir, notebook_path.as_deref(), None)?;
+    run_code_block(&language, &code, &work_dir, interpreter, notebook_path.as_deref(), container, BTreeMap::new())
 }
 
-fn execute_python(code: &str, working_dir: &Path, interpreter: &str) -> Result<CodeExecutionResult, FsError> {
-    let output = Command::new(interpreter)
-        .arg("-c")
-        .arg(code)
-        .current_dir(working_dir)
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .output()?;
-
-    Ok(CodeExecutionResult {
-        stdout: String::from_utf8_lossy(&output.stdout).to_string(),
-        stderr: String::from_utf8_lossy(&output.stderr).to_string(),
-        exit_code: output.status.code().unwrap_or(-1),
-    })
-}
-
-fn execute_ruby(code: &str, working_dir: &Path, interpreter: &str) -> Result<CodeExecutionResult, FsError> {
-    let output = Command::new(interpreter)
-        .arg("-e")
-        .arg(code)
-        .current_dir(working_dir)
+/// Run a code block and wait for it
+///
+/// `extra_env` is added on top of the vault and `.env` variables.
+pub(crate) fn run_code_block(
+    language: &str,
+    code: &str,
+    work_dir: &Path,
+    interpreter: Option<String>,
+    notebook_path: Option<&Path>,
+    container: Option<bool>,
+    extra_env: BTreeMap<String, String>,
+) -> Result<CodeExecutionResult, FsError> {
+    let mut env = execution_env(notebook_path, work_dir)?;
+    env.extend(extra_env);
+    let anchor = notebook_path.unwrap_or(work_dir);
+    let invocation = prepare_invocation(language, code, interpreter, container, anchor, work_dir, &env)?;
+
+    let output = Command::new(&invocation.program)
+        .args(&invocation.args)
+        .current_dir(work_dir)
+        .envs(&env)
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
         .output()?;
@@ -985,7 +1859,7 @@ pub async fn convert_note_to_notebook(
         let file_path = note_path.join(&file_name);
 
         // Write block content to file
-        fs::write(&file_path, &parsed.content)?;
+        write_atomic(&file_path, &parsed.content)?;
 
         // Add to index
         index_blocks.push(NotebookBlock {
@@ -994,6 +1868,7 @@ pub async fn convert_note_to_notebook(
             file: file_name,
             language: language.clone(),
             encrypted: None,
+            ..Default::default()
         });
 
         // Add to result
@@ -1003,6 +1878,7 @@ pub async fn convert_note_to_notebook(
             language,
             content: parsed.content.clone(),
             encrypted: None,
+            ..Default::default()
         });
     }
 
@@ -1012,7 +1888,7 @@ pub async fn convert_note_to_notebook(
         let file_name = format!("{}.md", block_id);
         let initial_content = format!("# {}\n\n", title);
 
-        fs::write(note_path.join(&file_name), &initial_content)?;
+        write_atomic(note_path.join(&file_name), &initial_content)?;
 
         index_blocks.push(NotebookBlock {
             id: block_id.clone(),
@@ -1020,6 +1896,7 @@ pub async fn convert_note_to_notebook(
             file: file_name,
             language: None,
             encrypted: None,
+            ..Default::default()
         });
 
         notebook_blocks.push(NotebookBlockWithContent {
@@ -1028,13 +1905,14 @@ pub async fn convert_note_to_notebook(
             language: None,
             content: initial_content,
             encrypted: None,
+            ..Default::default()
         });
     }
 
     // Write index
     let index = NotebookIndex {
-        version: 1,
         blocks: index_blocks,
+        ..Default::default()
     };
     write_notebook_index(&note_path, &index)?;
 
@@ -1137,34 +2015,127 @@ fn parse_markdown_blocks(content: &str) -> Vec<ParsedMarkdownBlock> {
 // Kanban Operations
 // =============================================================================
 
-const KANBAN_INDEX_FILE: &str = ".index.json";
-const DEFAULT_COLUMNS: [&str; 5] = ["backlog", "ready", "working", "done", "closed"];
+pub(crate) const KANBAN_INDEX_FILE: &str = ".index.json";
+pub(crate) const DEFAULT_COLUMNS: [&str; 5] = ["backlog", "ready", "working", "done", "closed"];
 
 /// Check if a path is a kanban board (directory ending with .kanban)
 pub fn is_kanban(path: &Path) -> bool {
     path.is_dir() && path.extension().map_or(false, |ext| ext == "kanban")
 }
 
-/// Read kanban index
-fn read_kanban_index(kanban_path: &Path) -> Result<KanbanIndex, FsError> {
+/// Read kanban index, decrypting an encrypted board with the session
+pub(crate) fn read_kanban_index(kanban_path: &Path, session: Option<&EncryptionSession>) -> Result<KanbanIndex, FsError> {
     let index_path = kanban_path.join(KANBAN_INDEX_FILE);
-    let content = fs::read_to_string(&index_path)?;
+    let mut content = fs::read_to_string(&index_path)?;
+    if is_encrypted(&content) {
+        content = decrypt_armored(session, &content, kanban_path)?;
+    }
     let index: KanbanIndex = serde_json::from_str(&content)
         .map_err(|e| FsError::InvalidPath(format!("Invalid kanban index: {}", e)))?;
     Ok(index)
 }
 
 /// Write kanban index
-fn write_kanban_index(kanban_path: &Path, index: &KanbanIndex) -> Result<(), FsError> {
+pub(crate) fn write_kanban_index(kanban_path: &Path, index: &KanbanIndex) -> Result<(), FsError> {
+    write_kanban_index_with(kanban_path, index, None)
+}
+
+/// Write kanban index, encrypting it for encrypted boards
+pub(crate) fn write_kanban_index_with(
+    kanban_path: &Path,
+    index: &KanbanIndex,
+    session: Option<&EncryptionSession>,
+) -> Result<(), FsError> {
     let index_path = kanban_path.join(KANBAN_INDEX_FILE);
-    let content = serde_json::to_string_pretty(index)
+    let mut content = serde_json::to_string_pretty(index)
         .map_err(|e| FsError::InvalidPath(format!("Failed to serialize kanban index: {}", e)))?;
-    fs::write(&index_path, content)?;
+    if index.encrypted {
+        content = encrypt_armored(session, &content, kanban_path)?;
+    }
+    write_atomic(&index_path, content)?;
+    Ok(())
+}
+
+/// Description of a task; descriptions of encrypted boards are decrypted in
+/// memory
+pub(crate) fn read_task_description(
+    kanban_path: &Path,
+    encrypted: bool,
+    task_id: &str,
+    session: Option<&EncryptionSession>,
+) -> Result<String, FsError> {
+    let task_path = get_task_file_path(kanban_path, task_id);
+    if !task_path.exists() {
+        return Ok(String::new());
+    }
+    let content = fs::read_to_string(&task_path)?;
+    if encrypted && is_encrypted(&content) {
+        return decrypt_armored(session, &content, &task_path);
+    }
+    Ok(content)
+}
+
+/// Write a task description, encrypting it for encrypted boards and boards
+/// an encryption policy covers
+pub(crate) fn write_task_description(
+    kanban_path: &Path,
+    encrypted: bool,
+    task_id: &str,
+    description: &str,
+    session: Option<&EncryptionSession>,
+) -> Result<(), FsError> {
+    let task_path = get_task_file_path(kanban_path, task_id);
+    let stored = if encrypted && !is_encrypted(description) {
+        encrypt_armored(session, description, &task_path)?
+    } else {
+        apply_encryption_policy(&task_path, description.to_string(), session)?
+    };
+    write_atomic(&task_path, stored)?;
     Ok(())
 }
 
+/// Encrypt the index and every task description of a kanban board
+pub(crate) fn encrypt_kanban_files(kanban_path: &Path, session: &EncryptionSession) -> Result<(), FsError> {
+    if !is_kanban(kanban_path) {
+        return Err(FsError::InvalidPath("Not a kanban board".to_string()));
+    }
+    let mut index = read_kanban_index(kanban_path, Some(session))?;
+    index.encrypted = true;
+
+    for task in &index.tasks {
+        let task_path = get_task_file_path(kanban_path, &task.id);
+        if task_path.exists() {
+            let description = fs::read_to_string(&task_path)?;
+            write_task_description(kanban_path, true, &task.id, &description, Some(session))?;
+        }
+        reseal_task_activity(kanban_path, true, &task.id, Some(session))?;
+    }
+
+    write_kanban_index_with(kanban_path, &index, Some(session))
+}
+
+/// Decrypt the index and every task description of a kanban board
+pub(crate) fn decrypt_kanban_files(kanban_path: &Path, session: &EncryptionSession) -> Result<(), FsError> {
+    if !is_kanban(kanban_path) {
+        return Err(FsError::InvalidPath("Not a kanban board".to_string()));
+    }
+    let mut index = read_kanban_index(kanban_path, Some(session))?;
+
+    for task in &index.tasks {
+        let task_path = get_task_file_path(kanban_path, &task.id);
+        if task_path.exists() {
+            let description = read_task_description(kanban_path, true, &task.id, Some(session))?;
+            write_atomic(&task_path, description)?;
+        }
+        reseal_task_activity(kanban_path, false, &task.id, Some(session))?;
+    }
+
+    index.encrypted = false;
+    write_kanban_index_with(kanban_path, &index, Some(session))
+}
+
 /// Generate a unique task ID
-fn generate_task_id() -> String {
+pub(crate) fn generate_task_id() -> String {
     use std::time::{SystemTime, UNIX_EPOCH};
     let now = SystemTime::now()
         .duration_since(UNIX_EPOCH)
@@ -1181,7 +2152,7 @@ fn generate_task_id() -> String {
 }
 
 /// Get the file path for a task's description
-fn get_task_file_path(kanban_path: &Path, task_id: &str) -> PathBuf {
+pub(crate) fn get_task_file_path(kanban_path: &Path, task_id: &str) -> PathBuf {
     kanban_path.join(format!("{}.md", task_id))
 }
 
@@ -1202,12 +2173,32 @@ pub async fn create_kanban(path: PathBuf, title: Option<String>) -> Result<Kanba
             .unwrap_or_else(|| "Untitled".to_string())
     });
 
+    // The vault's kanban template lists one column per line
+    let template = match find_vault_root(&path) {
+        Some(vault) => read_vault_template(&vault, "kanban")?,
+        None => None,
+    };
+    let columns = match template {
+        Some(template) => TemplateContext::for_path(&path, &name)
+            .render(&template)?
+            .lines()
+            .map(str::trim)
+            .filter(|column| !column.is_empty())
+            .map(str::to_string)
+            .collect(),
+        None => Vec::new(),
+    };
+
     // Create default index with default columns
     let index = KanbanIndex {
         version: 1,
-        columns: DEFAULT_COLUMNS.iter().map(|s| s.to_string()).collect(),
+        columns: if columns.is_empty() {
+            DEFAULT_COLUMNS.iter().map(|s| s.to_string()).collect()
+        } else {
+            columns
+        },
         tasks: vec![],
-        settings: KanbanSettings::default(),
+        ..Default::default()
     };
     write_kanban_index(&path, &index)?;
 
@@ -1220,13 +2211,20 @@ pub async fn create_kanban(path: PathBuf, title: Option<String>) -> Result<Kanba
 }
 
 /// Read a kanban board and all its tasks
+///
+/// Encrypted boards are decrypted in memory when the session is unlocked.
 #[tauri::command]
-pub async fn read_kanban(path: PathBuf) -> Result<Kanban, FsError> {
+pub async fn read_kanban(path: PathBuf, state: State<'_, EncryptionState>) -> Result<Kanban, FsError> {
+    let session = state.session(&path);
+    load_kanban(path, Some(&session))
+}
+
+pub(crate) fn load_kanban(path: PathBuf, session: Option<&EncryptionSession>) -> Result<Kanban, FsError> {
     if !is_kanban(&path) {
         return Err(FsError::InvalidPath("Not a kanban board".to_string()));
     }
 
-    let index = read_kanban_index(&path)?;
+    let index = read_kanban_index(&path, session)?;
     let name = path
         .file_stem()
         .and_then(|s| s.to_str())
@@ -1235,23 +2233,9 @@ pub async fn read_kanban(path: PathBuf) -> Result<Kanban, FsError> {
 
     let mut tasks = Vec::new();
     for task in &index.tasks {
-        let task_path = get_task_file_path(&path, &task.id);
-        let description = if task_path.exists() {
-            fs::read_to_string(&task_path)?
-        } else {
-            String::new()
-        };
+        let description = read_task_description(&path, index.encrypted, &task.id, session)?;
 
-        tasks.push(KanbanTaskWithContent {
-            id: task.id.clone(),
-            title: task.title.clone(),
-            status: task.status.clone(),
-            priority: task.priority.clone(),
-            due: task.due.clone(),
-            created: task.created.clone(),
-            updated: task.updated.clone(),
-            description,
-        });
+        tasks.push(KanbanTaskWithContent::from_task(task, description));
     }
 
     Ok(Kanban {
@@ -1271,13 +2255,16 @@ pub async fn add_kanban_task(
     priority: Option<String>,
     due: Option<String>,
     description: Option<String>,
+    state: State<'_, EncryptionState>,
 ) -> Result<KanbanTaskWithContent, FsError> {
     // Validate title is not empty
     if title.trim().is_empty() {
         return Err(FsError::InvalidPath("Task title cannot be empty".to_string()));
     }
 
-    let mut index = read_kanban_index(&kanban_path)?;
+    let session = state.session(&kanban_path);
+    let session = Some(session.as_ref());
+    let mut index = read_kanban_index(&kanban_path, session)?;
 
     let task_id = generate_task_id();
     let now = chrono::Utc::now().to_rfc3339();
@@ -1295,18 +2282,28 @@ pub async fn add_kanban_task(
         due: due.clone(),
         created: now.clone(),
         updated: now.clone(),
+        history: vec![StatusChange {
+            status: task_status.clone(),
+            at: now.clone(),
+        }],
+        ..Default::default()
     };
 
     index.tasks.push(task);
-    write_kanban_index(&kanban_path, &index)?;
+    write_kanban_index_with(&kanban_path, &index, session)?;
 
     // Write description file if provided
     let task_description = description.unwrap_or_default();
     if !task_description.is_empty() {
-        let task_path = get_task_file_path(&kanban_path, &task_id);
-        fs::write(&task_path, &task_description)?;
+        write_task_description(&kanban_path, index.encrypted, &task_id, &task_description, session)?;
     }
 
+    let created = TaskActivity {
+        to: Some(task_status.clone()),
+        ..activity_entry(ActivityKind::Created, activity_author(&kanban_path), &now)
+    };
+    append_task_activity(&kanban_path, index.encrypted, &task_id, &[created], session)?;
+
     Ok(KanbanTaskWithContent {
         id: task_id,
         title,
@@ -1316,17 +2313,23 @@ pub async fn add_kanban_task(
         created: now.clone(),
         updated: now,
         description: task_description,
+        ..Default::default()
     })
 }
 
-/// Update a task's metadata (title, status, priority, due)
+/// Update a task's metadata (title, status, priority, due, labels, assignee)
 #[tauri::command]
 pub async fn update_kanban_task(
+    app_handle: AppHandle,
     kanban_path: PathBuf,
     task_id: String,
     updates: TaskUpdates,
+    state: State<'_, EncryptionState>,
 ) -> Result<KanbanTaskWithContent, FsError> {
-    let mut index = read_kanban_index(&kanban_path)?;
+    let session = state.session(&kanban_path);
+    let session = Some(session.as_ref());
+    let mut index = read_kanban_index(&kanban_path, session)?;
+    let encrypted = index.encrypted;
 
     let task = index
         .tasks
@@ -1334,48 +2337,80 @@ pub async fn update_kanban_task(
         .find(|t| t.id == task_id)
         .ok_or_else(|| FsError::NotFound(format!("Task not found: {}", task_id)))?;
 
+    let now = chrono::Utc::now().to_rfc3339();
+    let author = activity_author(&kanban_path);
+    let mut changes = Vec::new();
+    let mut changed = |field: &str, from: Option<String>, to: Option<String>| {
+        if from != to {
+            changes.push(field_change(field, from, to, author.clone(), &now));
+        }
+    };
+
     // Apply updates
     if let Some(title) = updates.title {
+        changed("title", Some(task.title.clone()), Some(title.clone()));
         task.title = title;
     }
+    let mut moved = None;
     if let Some(status) = updates.status {
+        if status != task.status {
+            changed("status", Some(task.status.clone()), Some(status.clone()));
+            moved = Some((task.status.clone(), status.clone()));
+            task.history.push(StatusChange {
+                status: status.clone(),
+                at: now.clone(),
+            });
+        }
         task.status = status;
     }
     // Empty string means "clear the field"
     if let Some(priority) = updates.priority {
-        task.priority = if priority.is_empty() { None } else { Some(priority) };
+        let priority = if priority.is_empty() { None } else { Some(priority) };
+        changed("priority", task.priority.clone(), priority.clone());
+        task.priority = priority;
     }
     if let Some(due) = updates.due {
-        task.due = if due.is_empty() { None } else { Some(due) };
+        let due = if due.is_empty() { None } else { Some(due) };
+        changed("due", task.due.clone(), due.clone());
+        task.due = due;
+    }
+    if let Some(labels) = updates.labels {
+        changed("labels", Some(task.labels.join(", ")), Some(labels.join(", ")));
+        task.labels = labels;
+    }
+    if let Some(assignee) = updates.assignee {
+        let assignee = if assignee.is_empty() { None } else { Some(assignee) };
+        changed("assignee", task.assignee.clone(), assignee.clone());
+        task.assignee = assignee;
     }
 
     // Update timestamp
-    task.updated = chrono::Utc::now().to_rfc3339();
+    task.updated = now.clone();
 
     // Handle description: update if provided, otherwise read existing
-    let task_path = get_task_file_path(&kanban_path, &task_id);
     let description = if let Some(desc) = updates.description {
-        fs::write(&task_path, &desc)?;
+        let previous = read_task_description(&kanban_path, encrypted, &task_id, session)?;
+        if previous != desc {
+            // Descriptions are logged as changed without their content
+            changes.push(field_change("description", None, None, author.clone(), &now));
+        }
+        write_task_description(&kanban_path, encrypted, &task_id, &desc, session)?;
         desc
-    } else if task_path.exists() {
-        fs::read_to_string(&task_path)?
     } else {
-        String::new()
+        read_task_description(&kanban_path, encrypted, &task_id, session)?
     };
 
     // Build result with all data ready
-    let result_task = KanbanTaskWithContent {
-        id: task.id.clone(),
-        title: task.title.clone(),
-        status: task.status.clone(),
-        priority: task.priority.clone(),
-        due: task.due.clone(),
-        created: task.created.clone(),
-        updated: task.updated.clone(),
-        description,
-    };
+    let result_task = KanbanTaskWithContent::from_task(task, description);
+
+    write_kanban_index_with(&kanban_path, &index, session)?;
+    append_task_activity(&kanban_path, encrypted, &task_id, &changes, session)?;
 
-    write_kanban_index(&kanban_path, &index)?;
+    if let (Some((from, to)), Some(vault)) = (moved, find_vault_root(&kanban_path)) {
+        let board = kanban_path.strip_prefix(&vault).unwrap_or(&kanban_path);
+        let data = json!({ "board": board, "task_id": task_id, "title": result_task.title, "from": from, "to": to });
+        dispatch_webhooks(&app_handle, &vault, WebhookEvent::TaskMoved, data);
+    }
 
     Ok(result_task)
 }
@@ -1385,8 +2420,11 @@ pub async fn update_kanban_task(
 pub async fn delete_kanban_task(
     kanban_path: PathBuf,
     task_id: String,
+    state: State<'_, EncryptionState>,
 ) -> Result<(), FsError> {
-    let mut index = read_kanban_index(&kanban_path)?;
+    let session = state.session(&kanban_path);
+    let session = Some(session.as_ref());
+    let mut index = read_kanban_index(&kanban_path, session)?;
 
     let task_pos = index
         .tasks
@@ -1395,12 +2433,13 @@ pub async fn delete_kanban_task(
         .ok_or_else(|| FsError::NotFound(format!("Task not found: {}", task_id)))?;
 
     index.tasks.remove(task_pos);
-    write_kanban_index(&kanban_path, &index)?;
+    write_kanban_index_with(&kanban_path, &index, session)?;
 
-    // Delete the description file if it exists
-    let task_path = get_task_file_path(&kanban_path, &task_id);
-    if task_path.exists() {
-        fs::remove_file(&task_path)?;
+    // Delete the description and activity files if they exist
+    for path in [get_task_file_path(&kanban_path, &task_id), activity_path(&kanban_path, &task_id)] {
+        if path.exists() {
+            fs::remove_file(&path)?;
+        }
     }
 
     Ok(())
@@ -1412,8 +2451,11 @@ pub async fn update_task_description(
     kanban_path: PathBuf,
     task_id: String,
     description: String,
+    state: State<'_, EncryptionState>,
 ) -> Result<(), FsError> {
-    let mut index = read_kanban_index(&kanban_path)?;
+    let session = state.session(&kanban_path);
+    let session = Some(session.as_ref());
+    let mut index = read_kanban_index(&kanban_path, session)?;
 
     // Verify task exists and update timestamp
     let task = index
@@ -1423,11 +2465,10 @@ pub async fn update_task_description(
         .ok_or_else(|| FsError::NotFound(format!("Task not found: {}", task_id)))?;
 
     task.updated = chrono::Utc::now().to_rfc3339();
-    write_kanban_index(&kanban_path, &index)?;
+    write_kanban_index_with(&kanban_path, &index, session)?;
 
     // Write description to file
-    let task_path = get_task_file_path(&kanban_path, &task_id);
-    fs::write(&task_path, description)?;
+    write_task_description(&kanban_path, index.encrypted, &task_id, &description, session)?;
 
     Ok(())
 }
@@ -1437,10 +2478,13 @@ pub async fn update_task_description(
 pub async fn update_kanban_settings(
     kanban_path: PathBuf,
     settings: KanbanSettings,
+    state: State<'_, EncryptionState>,
 ) -> Result<(), FsError> {
-    let mut index = read_kanban_index(&kanban_path)?;
+    let session = state.session(&kanban_path);
+    let session = Some(session.as_ref());
+    let mut index = read_kanban_index(&kanban_path, session)?;
     index.settings = settings;
-    write_kanban_index(&kanban_path, &index)?;
+    write_kanban_index_with(&kanban_path, &index, session)?;
     Ok(())
 }
 
@@ -1448,6 +2492,76 @@ pub async fn update_kanban_settings(
 // Attachment Operations
 // =============================================================================
 
+/// Guess a MIME type from a file extension
+pub(crate) fn mime_type(path: &Path) -> &'static str {
+    let ext = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match ext.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "svg" => "image/svg+xml",
+        "webp" => "image/webp",
+        "bmp" => "image/bmp",
+        "ico" => "image/x-icon",
+        "pdf" => "application/pdf",
+        "md" => "text/markdown",
+        "txt" => "text/plain",
+        "html" | "htm" => "text/html",
+        "css" => "text/css",
+        "json" => "application/json",
+        "csv" => "text/csv",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Read any vault file, e.g. a PDF or image, for display in the app
+#[tauri::command]
+pub async fn read_file_binary(path: PathBuf) -> Result<BinaryFile, FsError> {
+    use base64::Engine;
+
+    if !path.is_file() {
+        return Err(FsError::NotFound(path.display().to_string()));
+    }
+
+    let bytes = fs::read(&path)?;
+    Ok(BinaryFile {
+        mime_type: mime_type(&path).to_string(),
+        size: bytes.len() as u64,
+        data: base64::engine::general_purpose::STANDARD.encode(bytes),
+        path,
+    })
+}
+
+/// Copy a directory and everything below it
+pub(crate) fn copy_dir(from: &Path, to: &Path) -> Result<(), FsError> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
+
+/// `.assets` folder next to a note or notebook
+pub(crate) fn note_assets_dir(note_path: &Path) -> PathBuf {
+    let parent = note_path.parent().unwrap_or(note_path);
+    let name = note_path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let stem = if note_path.is_dir() {
+        name.trim_end_matches(".md").to_string()
+    } else {
+        Path::new(&name).file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or(name)
+    };
+    parent.join(format!("{}.assets", stem))
+}
+
 /// Save an attachment (image) to the .assets folder of a note
 #[tauri::command]
 pub async fn save_attachment(
@@ -1464,16 +2578,7 @@ pub async fn save_attachment(
         return Err(FsError::NotFound(note_path.display().to_string()));
     }
 
-    let assets_dir = if note_path.is_dir() {
-        let parent = note_path.parent().unwrap_or(&note_path);
-        let name = note_path.file_name().unwrap().to_string_lossy();
-        let name_without_ext = name.trim_end_matches(".md");
-        parent.join(format!("{}.assets", name_without_ext))
-    } else {
-        let name = note_path.file_stem().unwrap().to_string_lossy();
-        let parent = note_path.parent().unwrap();
-        parent.join(format!("{}.assets", name))
-    };
+    let assets_dir = note_assets_dir(&note_path);
 
     if !assets_dir.exists() {
         fs::create_dir_all(&assets_dir)?;
@@ -1500,10 +2605,411 @@ pub async fn save_attachment(
             format!("Invalid base64: {}", e)
         )))?;
 
-    fs::write(&file_path, decoded)?;
+    write_atomic(&file_path, decoded)?;
 
     let assets_folder_name = assets_dir.file_name().unwrap().to_string_lossy();
     let relative_path = format!("./{}/{}", assets_folder_name, new_filename);
 
     Ok(relative_path)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::encryption::generate_identity;
+
+    fn unlocked_session(dir: &Path) -> EncryptionSession {
+        let identity = dir.join("identity.txt").to_string_lossy().to_string();
+        generate_identity(&identity).unwrap();
+        let session = EncryptionSession::new();
+        session.set_identity_file(identity);
+        session
+    }
+
+    #[test]
+    fn writes_atomically() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Note.md");
+        write_atomic(&path, "first").unwrap();
+        write_atomic(&path, "second").unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second");
+        assert!(!dir.path().join(".Note.md.tmp").exists());
+        assert!(write_atomic(dir.path().join("Missing/Note.md"), "x").is_err());
+        assert!(!dir.path().join("Missing").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn writes_atomically_through_symlinks_keeping_permissions() {
+        use std::os::unix::fs::{symlink, PermissionsExt};
+
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("Shared.md");
+        let link = dir.path().join("Link.md");
+        fs::write(&target, "first").unwrap();
+        fs::set_permissions(&target, fs::Permissions::from_mode(0o600)).unwrap();
+        symlink(&target, &link).unwrap();
+
+        write_atomic(&link, "second").unwrap();
+        assert!(fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+        assert_eq!(fs::read_to_string(&target).unwrap(), "second");
+        assert_eq!(fs::metadata(&target).unwrap().permissions().mode() & 0o777, 0o600);
+    }
+
+    #[test]
+    fn refuses_writes_over_external_edits() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Note.md");
+        let hash = save_note(&path, "first".to_string(), None, None).unwrap();
+        assert_eq!(load_note(&path).unwrap().hash, hash);
+        let hash = save_note(&path, "second".to_string(), Some(&hash), None).unwrap();
+
+        // Same size and second as our write, only the content differs
+        fs::write(&path, "secnod").unwrap();
+        let Err(FsError::Conflict(conflict)) = save_note(&path, "third".to_string(), Some(&hash), None) else {
+            panic!("external edit was overwritten");
+        };
+        assert_eq!((conflict.content.as_str(), conflict.encrypted), ("secnod", false));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "secnod");
+        save_note(&path, "third".to_string(), Some(&conflict.hash), None).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "third");
+
+        let session = unlocked_session(dir.path());
+        fs::write(&path, encrypt_armored(Some(&session), "secret", &path).unwrap()).unwrap();
+        let Err(FsError::Conflict(locked)) = save_note(&path, "x".to_string(), Some(&hash), None) else {
+            panic!("external edit was overwritten");
+        };
+        assert!(locked.encrypted && is_encrypted(&locked.content));
+        let Err(FsError::Conflict(unlocked)) = save_note(&path, "x".to_string(), Some(&hash), Some(&session)) else {
+            panic!("external edit was overwritten");
+        };
+        assert_eq!((unlocked.content.as_str(), unlocked.encrypted), ("secret", false));
+        assert_eq!(serde_json::to_value(FsError::Conflict(unlocked)).unwrap()["content"], "secret");
+    }
+
+    #[test]
+    fn resolves_note_names_inside_vault() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join(".notemaker")).unwrap();
+        fs::write(dir.path().join(".notemaker/config.yaml"), "").unwrap();
+        fs::create_dir_all(dir.path().join("Projects")).unwrap();
+
+        assert_eq!(vault_note_path(dir.path(), "Projects/Plan").unwrap(), dir.path().join("Projects/Plan.md"));
+        assert_eq!(vault_note_path(dir.path(), "Projects").unwrap(), dir.path().join("Projects"));
+        for name in ["", ".notemaker/config.yaml", ".git/config", "Projects/.hidden", "../Plan", "/etc/passwd"] {
+            assert!(vault_note_path(dir.path(), name).is_err(), "{} was accepted", name);
+        }
+    }
+
+    #[test]
+    fn lists_directory_lazily() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("Projects/Deep")).unwrap();
+        fs::write(dir.path().join("Projects/One.md"), "").unwrap();
+        fs::write(dir.path().join("Projects/.hidden"), "").unwrap();
+        fs::write(dir.path().join("Projects/Deep/Two.md"), "").unwrap();
+
+        let top = list_directory_recursive(dir.path(), Some(0), &mut TreeWalk::for_path(dir.path()).unwrap()).unwrap();
+        assert!(top[0].children.is_none());
+        assert_eq!(top[0].child_count, Some(2));
+
+        let mut walk = TreeWalk::for_path(dir.path()).unwrap();
+        let two_levels = list_directory_recursive(dir.path(), Some(1), &mut walk).unwrap();
+        let deep = &two_levels[0].children.as_ref().unwrap()[0];
+        assert_eq!(deep.name, "Deep");
+        assert!(deep.children.is_none());
+        assert_eq!(deep.child_count, Some(1));
+
+        let full = list_directory_recursive(dir.path(), None, &mut TreeWalk::for_path(dir.path()).unwrap()).unwrap();
+        assert_eq!(full[0].children.as_ref().unwrap()[0].children.as_ref().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn lists_other_files_on_request() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("Note.md"), "").unwrap();
+        fs::write(dir.path().join("Paper.pdf"), "%PDF").unwrap();
+
+        let notes = tauri::async_runtime::block_on(list_directory(dir.path().to_path_buf(), None, None, None)).unwrap();
+        assert_eq!(notes.len(), 1);
+
+        let all = tauri::async_runtime::block_on(list_directory(dir.path().to_path_buf(), None, Some(true), None)).unwrap();
+        let pdf = all.iter().find(|e| e.name == "Paper.pdf").unwrap();
+        assert_eq!(pdf.mime_type.as_deref(), Some("application/pdf"));
+
+        let file = tauri::async_runtime::block_on(read_file_binary(pdf.path.clone())).unwrap();
+        assert_eq!(file.data, "JVBERg==");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn stops_at_symlink_loops() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("Projects")).unwrap();
+        fs::write(dir.path().join("Projects/One.md"), "").unwrap();
+        std::os::unix::fs::symlink(dir.path(), dir.path().join("Projects/Back")).unwrap();
+
+        let tree = list_directory_recursive(dir.path(), None, &mut TreeWalk::for_path(dir.path()).unwrap()).unwrap();
+        let projects = tree[0].children.as_ref().unwrap();
+        let back = projects.iter().find(|e| e.name == "Back").unwrap();
+        assert!(back.is_symlink && back.is_directory);
+        assert_eq!(back.children.as_ref().unwrap().len(), 0);
+        assert_eq!(count_notes(dir.path(), &mut TreeWalk::for_path(dir.path()).unwrap()), 1);
+
+        fs::create_dir(dir.path().join(".notemaker")).unwrap();
+        fs::write(
+            dir.path().join(".notemaker/config.yaml"),
+            "version: 1\nvault:\n  name: Test\n  created: now\nfile_tree:\n  follow_symlinks: false\n",
+        )
+        .unwrap();
+        let tree = list_directory_recursive(dir.path(), None, &mut TreeWalk::for_path(dir.path()).unwrap()).unwrap();
+        assert_eq!(tree[0].children.as_ref().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn renames_directory_with_links_and_state() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = dir.path();
+        fs::create_dir_all(vault.join(".notemaker")).unwrap();
+        fs::create_dir_all(vault.join("Projects/Old")).unwrap();
+        fs::write(vault.join("Projects/Old/Plan.md"), "# Plan").unwrap();
+        fs::write(vault.join("Index.md"), "See [[Projects/Old/Plan|the plan]] and [[Plan]].").unwrap();
+        let old = vault.join("Projects/Old");
+        let new = vault.join("Work/New");
+        let state = LocalState {
+            expanded_paths: vec![old.to_string_lossy().to_string(), vault.join("Projects").to_string_lossy().to_string()],
+            last_opened: Some(old.join("Plan.md").to_string_lossy().to_string()),
+            ..Default::default()
+        };
+        write_local_state(vault, &state).unwrap();
+
+        let modified = tauri::async_runtime::block_on(rename_directory(old, new.clone())).unwrap();
+        assert_eq!(modified, vec![vault.join("Index.md")]);
+        assert_eq!(
+            fs::read_to_string(vault.join("Index.md")).unwrap(),
+            "See [[Work/New/Plan|the plan]] and [[Plan]]."
+        );
+
+        let state = read_local_state(vault).unwrap();
+        assert_eq!(state.expanded_paths[0], new.to_string_lossy());
+        assert_eq!(state.expanded_paths[1], vault.join("Projects").to_string_lossy());
+        assert_eq!(state.last_opened, Some(new.join("Plan.md").to_string_lossy().to_string()));
+    }
+
+    #[test]
+    fn creates_vault_with_starter_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Notes");
+        let options = CreateVaultOptions {
+            name: Some("Team notes".to_string()),
+            folders: vec!["Projects".to_string(), "Areas/Home".to_string()],
+            welcome_note: true,
+            ..Default::default()
+        };
+
+        let info = init_vault(path.clone(), options).unwrap();
+        assert_eq!(info.note_count, 1);
+        assert!(path.join("Areas/Home").is_dir());
+        assert_eq!(read_vault_config(&path).unwrap().vault.name, "Team notes");
+
+        let again = init_vault(path, CreateVaultOptions::default());
+        assert!(again.is_err());
+
+        let escaping = CreateVaultOptions {
+            folders: vec!["../Outside".to_string()],
+            ..Default::default()
+        };
+        assert!(init_vault(dir.path().join("Other"), escaping).is_err());
+        assert!(!dir.path().join("Other").exists());
+    }
+
+    #[test]
+    fn reorders_blocks() {
+        let block = |id: &str| NotebookBlock {
+            id: id.to_string(),
+            ..Default::default()
+        };
+        let mut index = NotebookIndex {
+            blocks: vec![block("a"), block("b"), block("c")],
+            ..Default::default()
+        };
+        let ids = |ids: &[&str]| -> Vec<String> { ids.iter().map(|id| id.to_string()).collect() };
+
+        reorder_blocks(&mut index, &ids(&["c", "a", "b"])).unwrap();
+        let order: Vec<&str> = index.blocks.iter().map(|b| b.id.as_str()).collect();
+        assert_eq!(order, vec!["c", "a", "b"]);
+
+        assert!(reorder_blocks(&mut index, &ids(&["a", "b"])).is_err());
+        assert!(reorder_blocks(&mut index, &ids(&["a", "a", "b"])).is_err());
+        assert_eq!(index.blocks.len(), 3);
+    }
+
+    #[test]
+    fn encrypts_notebook_with_index() {
+        let dir = tempfile::tempdir().unwrap();
+        let session = unlocked_session(dir.path());
+        let notebook = dir.path().join("Notes.md");
+        fs::create_dir(&notebook).unwrap();
+        fs::write(notebook.join("a.md"), "# Secret").unwrap();
+        fs::write(notebook.join("b.py"), "print(1)").unwrap();
+        let index = NotebookIndex {
+            blocks: vec![
+                NotebookBlock {
+                    id: "a".to_string(),
+                    block_type: BlockType::Markdown,
+                    file: "a.md".to_string(),
+                    language: None,
+                    encrypted: None,
+                    ..Default::default()
+                },
+                NotebookBlock {
+                    id: "b".to_string(),
+                    block_type: BlockType::Code,
+                    file: "b.py".to_string(),
+                    language: Some("python".to_string()),
+                    encrypted: None,
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        write_notebook_index(&notebook, &index).unwrap();
+
+        encrypt_notebook_files(&notebook, &session, true).unwrap();
+        assert!(is_encrypted(&fs::read_to_string(notebook.join(".index.json")).unwrap()));
+        assert!(is_encrypted(&fs::read_to_string(notebook.join("b.age")).unwrap()));
+        assert!(!notebook.join("b.py").exists());
+        assert!(matches!(read_notebook_index(&notebook), Err(FsError::Encrypted(_))));
+
+        let loaded = load_notebook(notebook.clone(), Some(&session)).unwrap();
+        assert_eq!(loaded.blocks[0].content, "# Secret");
+        assert_eq!(loaded.blocks[1].content, "print(1)");
+
+        decrypt_notebook_files(&notebook, &session).unwrap();
+        let index = read_notebook_index(&notebook).unwrap();
+        assert!(!index.encrypted && !index.encrypted_index);
+        assert_eq!(fs::read_to_string(notebook.join("b.py")).unwrap(), "print(1)");
+    }
+
+    #[test]
+    fn encrypts_kanban_board() {
+        let dir = tempfile::tempdir().unwrap();
+        let session = unlocked_session(dir.path());
+        let board = dir.path().join("Work.kanban");
+        fs::create_dir(&board).unwrap();
+        let index = KanbanIndex {
+            tasks: vec![KanbanTask {
+                id: "t1".to_string(),
+                title: "Quarterly numbers".to_string(),
+                status: "todo".to_string(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        write_kanban_index(&board, &index).unwrap();
+        fs::write(get_task_file_path(&board, "t1"), "Revenue details").unwrap();
+
+        encrypt_kanban_files(&board, &session).unwrap();
+        let stored_index = fs::read_to_string(board.join(KANBAN_INDEX_FILE)).unwrap();
+        assert!(is_encrypted(&stored_index));
+        assert!(is_encrypted(&fs::read_to_string(get_task_file_path(&board, "t1")).unwrap()));
+        assert!(matches!(load_kanban(board.clone(), None), Err(FsError::Encrypted(_))));
+
+        let kanban = load_kanban(board.clone(), Some(&session)).unwrap();
+        assert_eq!(kanban.tasks[0].title, "Quarterly numbers");
+        assert_eq!(kanban.tasks[0].description, "Revenue details");
+
+        decrypt_kanban_files(&board, &session).unwrap();
+        assert!(!read_kanban_index(&board, None).unwrap().encrypted);
+        assert_eq!(fs::read_to_string(get_task_file_path(&board, "t1")).unwrap(), "Revenue details");
+    }
+
+    #[test]
+    fn encrypts_single_block() {
+        let dir = tempfile::tempdir().unwrap();
+        let session = unlocked_session(dir.path());
+        let notebook = dir.path().join("Mixed.md");
+        fs::create_dir(&notebook).unwrap();
+        fs::write(notebook.join("a.md"), "public").unwrap();
+        fs::write(notebook.join("b.md"), "private").unwrap();
+        let blocks = ["a", "b"]
+            .iter()
+            .map(|id| NotebookBlock {
+                id: id.to_string(),
+                block_type: BlockType::Markdown,
+                file: format!("{}.md", id),
+                language: None,
+                encrypted: None,
+                ..Default::default()
+            })
+            .collect();
+        write_notebook_index(&notebook, &NotebookIndex { blocks, ..Default::default() }).unwrap();
+
+        encrypt_block_file(&notebook, "b", &session).unwrap();
+        assert!(is_encrypted(&fs::read_to_string(notebook.join("b.md")).unwrap()));
+        assert_eq!(read_notebook_index(&notebook).unwrap().blocks[1].encrypted, Some(true));
+
+        let locked = load_notebook(notebook.clone(), None).unwrap();
+        assert_eq!(locked.blocks[0].content, "public");
+        assert!(is_encrypted(&locked.blocks[1].content));
+        let unlocked = load_notebook(notebook.clone(), Some(&session)).unwrap();
+        assert_eq!(unlocked.blocks[1].content, "private");
+
+        decrypt_block_file(&notebook, "b", &session).unwrap();
+        assert_eq!(fs::read_to_string(notebook.join("b.md")).unwrap(), "private");
+        assert_eq!(read_notebook_index(&notebook).unwrap().blocks[1].encrypted, None);
+    }
+
+    #[test]
+    fn refuses_changes_to_locked_blocks() {
+        let dir = tempfile::tempdir().unwrap();
+        let notebook = dir.path().join("Reference.md");
+        fs::create_dir(&notebook).unwrap();
+        fs::write(notebook.join("a.md"), "kept").unwrap();
+        fs::write(notebook.join("b.md"), "editable").unwrap();
+        let blocks = ["a", "b"]
+            .iter()
+            .map(|id| NotebookBlock {
+                id: id.to_string(),
+                block_type: BlockType::Markdown,
+                file: format!("{}.md", id),
+                locked: *id == "a",
+                ..Default::default()
+            })
+            .collect();
+        write_notebook_index(&notebook, &NotebookIndex { blocks, ..Default::default() }).unwrap();
+
+        let locked = |result: Result<_, FsError>| matches!(result, Err(FsError::Locked(id)) if id == "a");
+        assert!(locked(update_block(&notebook, "a", "changed", None)));
+        assert!(locked(retype_block(&notebook, "a", BlockType::Code, Some("python".to_string()), None).map(|_| ())));
+        assert!(locked(delete_block(&notebook, "a", None)));
+        assert_eq!(fs::read_to_string(notebook.join("a.md")).unwrap(), "kept");
+        assert_eq!(read_notebook_index(&notebook).unwrap().blocks.len(), 2);
+
+        update_block(&notebook, "b", "changed", None).unwrap();
+        assert_eq!(fs::read_to_string(notebook.join("b.md")).unwrap(), "changed");
+        delete_block(&notebook, "b", None).unwrap();
+        assert_eq!(read_notebook_index(&notebook).unwrap().blocks.len(), 1);
+    }
+
+    #[test]
+    fn appends_to_notes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Inbox.md");
+        append_to_note(&path, "- first\n", None).unwrap();
+        append_to_note(&path, "- second", None).unwrap();
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(content.starts_with("---\ntitle: \"Inbox\""));
+        assert!(content.ends_with("# Inbox\n\n- first\n- second\n"));
+
+        let session = unlocked_session(dir.path());
+        let secret = dir.path().join("Secret.md");
+        fs::write(&secret, encrypt_armored(Some(&session), "hidden", &secret).unwrap()).unwrap();
+        assert!(matches!(append_to_note(&secret, "more", None), Err(FsError::Encrypted(_))));
+        append_to_note(&secret, "more", Some(&session)).unwrap();
+        let stored = fs::read_to_string(&secret).unwrap();
+        assert!(is_encrypted(&stored));
+        assert_eq!(decrypt_armored(Some(&session), &stored, &secret).unwrap(), "hidden\nmore\n");
+    }
+}
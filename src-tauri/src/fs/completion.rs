@@ -0,0 +1,269 @@
+//! Data for editor autocomplete: tags, notes, templates and people.
+//!
+//! What each note and board contributes is cached until one of its files
+//! changes, so a request after an edit only rereads the edited note.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+use super::commands::{markdown_files, read_kanban_index, read_markdown_text, walk_notes, FsError, KANBAN_INDEX_FILE};
+use super::encryption::is_encrypted;
+use super::frontmatter::{extract_aliases, parse_frontmatter, string_field};
+use super::links::{extract_wikilinks, link_key, note_name, relative_key};
+use super::reminders::walk_boards;
+use super::stats::{fingerprint, Fingerprint};
+use super::tags::note_tags;
+use super::types::{CompletionItem, CompletionKind};
+
+/// What one note contributes to completions
+#[derive(Clone, Default)]
+struct NoteFacts {
+    title: Option<String>,
+    aliases: Vec<String>,
+    tags: Vec<String>,
+    /// Lookup keys of the notes it links to
+    links: Vec<String>,
+}
+
+/// Board people and the number of tasks assigned to each
+type BoardPeople = Vec<(String, usize)>;
+
+fn note_cache() -> &'static Mutex<HashMap<PathBuf, (Fingerprint, NoteFacts)>> {
+    static CACHE: OnceLock<Mutex<HashMap<PathBuf, (Fingerprint, NoteFacts)>>> = OnceLock::new();
+    CACHE.get_or_init(Default::default)
+}
+
+fn board_cache() -> &'static Mutex<HashMap<PathBuf, (Fingerprint, BoardPeople)>> {
+    static CACHE: OnceLock<Mutex<HashMap<PathBuf, (Fingerprint, BoardPeople)>>> = OnceLock::new();
+    CACHE.get_or_init(Default::default)
+}
+
+fn note_facts(path: &Path) -> NoteFacts {
+    let content = read_markdown_text(path).unwrap_or_default();
+    if is_encrypted(&content) {
+        return NoteFacts::default();
+    }
+    NoteFacts {
+        title: parse_frontmatter(&content).and_then(|fm| string_field(&fm, "title")),
+        aliases: extract_aliases(&content),
+        tags: note_tags(&content),
+        links: extract_wikilinks(&content).iter().map(|l| link_key(&l.target)).collect(),
+    }
+}
+
+fn board_people(board: &Path) -> BoardPeople {
+    let Ok(index) = read_kanban_index(board, None) else {
+        return Vec::new();
+    };
+    let mut people: BoardPeople = index.settings.people.iter().map(|p| (p.clone(), 0)).collect();
+    for assignee in index.tasks.iter().filter_map(|t| t.assignee.as_deref()) {
+        match people.iter_mut().find(|(p, _)| p.eq_ignore_ascii_case(assignee)) {
+            Some((_, count)) => *count += 1,
+            None => people.push((assignee.to_string(), 1)),
+        }
+    }
+    people
+}
+
+/// Current value of every entry, recomputing only those whose files changed
+///
+/// Entries of the vault that no longer exist are dropped from the cache.
+fn refresh<T: Clone>(
+    cache: &Mutex<HashMap<PathBuf, (Fingerprint, T)>>,
+    vault: &Path,
+    entries: Vec<PathBuf>,
+    files: impl Fn(&Path) -> Vec<PathBuf>,
+    compute: impl Fn(&Path) -> T,
+) -> Vec<(PathBuf, T)> {
+    let mut current = Vec::new();
+    for entry in entries {
+        let Ok(print) = fingerprint(&files(&entry)) else {
+            continue;
+        };
+        let cached = match cache.lock().unwrap().get(&entry) {
+            Some((cached, value)) if *cached == print => Some(value.clone()),
+            _ => None,
+        };
+        let value = match cached {
+            Some(value) => value,
+            None => {
+                let value = compute(&entry);
+                cache.lock().unwrap().insert(entry.clone(), (print, value.clone()));
+                value
+            }
+        };
+        current.push((entry, value));
+    }
+
+    let seen: HashSet<&PathBuf> = current.iter().map(|(path, _)| path).collect();
+    cache
+        .lock()
+        .unwrap()
+        .retain(|path, _| !path.starts_with(vault) || seen.contains(path));
+    current
+}
+
+/// Add one use of `value`, merging case-insensitively with earlier uses
+fn count_use(items: &mut Vec<CompletionItem>, value: &str, uses: usize, path: Option<&Path>) {
+    match items.iter_mut().find(|i| i.value.eq_ignore_ascii_case(value) && i.path.as_deref() == path) {
+        Some(item) => item.count += uses,
+        None => items.push(CompletionItem {
+            value: value.to_string(),
+            count: uses,
+            path: path.map(Path::to_path_buf),
+        }),
+    }
+}
+
+fn tag_items(notes: &[(PathBuf, NoteFacts)]) -> Vec<CompletionItem> {
+    let mut items = Vec::new();
+    for (_, facts) in notes {
+        let mut seen: HashSet<String> = HashSet::new();
+        for tag in &facts.tags {
+            if seen.insert(tag.to_lowercase()) {
+                count_use(&mut items, tag, 1, None);
+            }
+        }
+    }
+    items
+}
+
+/// Names, titles and aliases of notes, counted by the links pointing at them
+fn note_items(vault: &Path, notes: &[(PathBuf, NoteFacts)]) -> Vec<CompletionItem> {
+    let mut inbound: HashMap<&str, usize> = HashMap::new();
+    for (_, facts) in notes {
+        for link in &facts.links {
+            *inbound.entry(link.as_str()).or_default() += 1;
+        }
+    }
+
+    let mut items = Vec::new();
+    for (path, facts) in notes {
+        let name = note_name(path);
+        let mut keys = vec![link_key(&name)];
+        if let Some(key) = relative_key(vault, path).filter(|key| *key != keys[0]) {
+            keys.push(key);
+        }
+        let uses = keys.iter().map(|key| inbound.get(key.as_str()).copied().unwrap_or(0)).sum();
+
+        let names = std::iter::once(&name).chain(&facts.title).chain(&facts.aliases);
+        let mut added: Vec<&str> = Vec::new();
+        for value in names {
+            if !added.iter().any(|a| a.eq_ignore_ascii_case(value)) {
+                added.push(value);
+                count_use(&mut items, value, uses, Some(path));
+            }
+        }
+    }
+    items
+}
+
+/// Vault templates; they carry no usage count
+fn template_items(vault: &Path) -> Vec<CompletionItem> {
+    let Ok(entries) = fs::read_dir(vault.join(".notemaker").join("templates")) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && path.extension().is_some_and(|e| e == "md"))
+        .filter_map(|path| {
+            Some(CompletionItem {
+                value: path.file_stem()?.to_string_lossy().to_string(),
+                count: 0,
+                path: Some(path),
+            })
+        })
+        .collect()
+}
+
+fn people_items(vault: &Path) -> Vec<CompletionItem> {
+    let mut boards = Vec::new();
+    walk_boards(vault, &mut boards);
+    let boards = refresh(
+        board_cache(),
+        vault,
+        boards,
+        |board| vec![board.join(KANBAN_INDEX_FILE)],
+        board_people,
+    );
+
+    let mut items = Vec::new();
+    for (_, people) in &boards {
+        for (person, tasks) in people {
+            count_use(&mut items, person, *tasks, None);
+        }
+    }
+    items
+}
+
+/// Completion candidates of one kind, most used first
+#[tauri::command]
+pub async fn get_completion_data(vault_path: PathBuf, kind: CompletionKind) -> Result<Vec<CompletionItem>, FsError> {
+    if !vault_path.is_dir() {
+        return Err(FsError::NotFound(vault_path.display().to_string()));
+    }
+
+    let notes = || {
+        let mut paths = Vec::new();
+        walk_notes(&vault_path, &mut paths);
+        let files = |path: &Path| markdown_files(path).unwrap_or_default();
+        refresh(note_cache(), &vault_path, paths, files, note_facts)
+    };
+    let mut items = match kind {
+        CompletionKind::Tags => tag_items(&notes()),
+        CompletionKind::Notes => note_items(&vault_path, &notes()),
+        CompletionKind::Templates => template_items(&vault_path),
+        CompletionKind::People => people_items(&vault_path),
+    };
+    items.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.to_lowercase().cmp(&b.value.to_lowercase())));
+    Ok(items)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::commands::write_kanban_index;
+    use crate::fs::types::{KanbanIndex, KanbanTask};
+
+    fn complete(vault: &Path, kind: CompletionKind) -> Vec<(String, usize)> {
+        tauri::async_runtime::block_on(get_completion_data(vault.to_path_buf(), kind))
+            .unwrap()
+            .into_iter()
+            .map(|item| (item.value, item.count))
+            .collect()
+    }
+
+    #[test]
+    fn counts_uses_and_follows_edits() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = dir.path();
+        fs::write(vault.join("Plan.md"), "---\ntitle: Q3 plan\nlabels: [work]\n---\nSee [[Goals]] #work #idea\n").unwrap();
+        fs::write(vault.join("Goals.md"), "---\naliases: [Objectives]\n---\nBack to [[plan]] #Work\n").unwrap();
+        fs::write(vault.join("Log.md"), "[[Goals]]\n").unwrap();
+
+        assert_eq!(complete(vault, CompletionKind::Tags), vec![("work".to_string(), 2), ("idea".to_string(), 1)]);
+        let notes = complete(vault, CompletionKind::Notes);
+        assert_eq!(notes[..2], [("Goals".to_string(), 2), ("Objectives".to_string(), 2)]);
+        assert!(notes.contains(&("Q3 plan".to_string(), 1)));
+
+        fs::write(vault.join("Log.md"), "#idea and #later, still no links\n").unwrap();
+        assert_eq!(
+            complete(vault, CompletionKind::Tags),
+            vec![("idea".to_string(), 2), ("work".to_string(), 2), ("later".to_string(), 1)]
+        );
+
+        let board = vault.join("Team.kanban");
+        fs::create_dir(&board).unwrap();
+        let mut index = KanbanIndex::default();
+        index.settings.people = vec!["Ann".to_string(), "Bob".to_string()];
+        index.tasks = vec![KanbanTask {
+            assignee: Some("bob".to_string()),
+            ..Default::default()
+        }];
+        write_kanban_index(&board, &index).unwrap();
+        assert_eq!(complete(vault, CompletionKind::People), vec![("Bob".to_string(), 1), ("Ann".to_string(), 0)]);
+    }
+}
@@ -0,0 +1,187 @@
+//! Rendering Mermaid, Graphviz and PlantUML blocks to images.
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use super::commands::{find_vault_root, note_assets_dir, read_vault_config, FsError};
+use super::runtime::ScratchDir;
+use super::types::{DiagramSettings, RenderedDiagram};
+use crate::export::pdf::find_program;
+
+/// Diagram languages by renderer
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DiagramKind {
+    Mermaid,
+    Graphviz,
+    PlantUml,
+}
+
+impl DiagramKind {
+    fn from_language(language: &str) -> Option<Self> {
+        match language.to_lowercase().as_str() {
+            "mermaid" | "mmd" => Some(Self::Mermaid),
+            "dot" | "graphviz" => Some(Self::Graphviz),
+            "plantuml" | "puml" => Some(Self::PlantUml),
+            _ => None,
+        }
+    }
+
+    /// Configured renderer, or the program looked up on PATH
+    fn renderer(self, settings: &DiagramSettings) -> Result<PathBuf, FsError> {
+        let (configured, default) = match self {
+            Self::Mermaid => (&settings.mermaid, "mmdc"),
+            Self::Graphviz => (&settings.graphviz, "dot"),
+            Self::PlantUml => (&settings.plantuml, "plantuml"),
+        };
+        let program = configured.as_deref().filter(|p| !p.trim().is_empty()).unwrap_or(default);
+        // A PlantUML jar is run with java
+        if program.ends_with(".jar") {
+            return Path::new(program)
+                .is_file()
+                .then(|| PathBuf::from(program))
+                .ok_or_else(|| FsError::NotFound(program.to_string()));
+        }
+        find_program(program).ok_or_else(|| FsError::NotFound(format!("Diagram renderer {}", program)))
+    }
+}
+
+/// Command rendering `source` (a file with the block content) to `output`
+fn render_command(kind: DiagramKind, renderer: &Path, source: &Path, output: &Path, format: &str) -> Command {
+    match kind {
+        DiagramKind::Mermaid => {
+            let mut cmd = Command::new(renderer);
+            cmd.arg("-q").arg("-i").arg(source).arg("-o").arg(output);
+            cmd
+        }
+        DiagramKind::Graphviz => {
+            let mut cmd = Command::new(renderer);
+            cmd.arg(format!("-T{}", format)).arg("-o").arg(output).arg(source);
+            cmd
+        }
+        DiagramKind::PlantUml => {
+            let mut cmd = if renderer.extension().is_some_and(|e| e == "jar") {
+                let mut java = Command::new("java");
+                java.arg("-jar").arg(renderer);
+                java
+            } else {
+                Command::new(renderer)
+            };
+            // `-pipe` renders stdin to stdout
+            cmd.arg(format!("-t{}", format)).arg("-pipe");
+            cmd
+        }
+    }
+}
+
+/// Render a diagram block into the notebook's `.assets` folder
+///
+/// The image is named after the block, so rendering again replaces it.
+pub(crate) fn render_diagram(
+    notebook_path: &Path,
+    block_id: &str,
+    language: &str,
+    code: &str,
+    format: &str,
+) -> Result<RenderedDiagram, FsError> {
+    let kind = DiagramKind::from_language(language)
+        .ok_or_else(|| FsError::InvalidPath(format!("Unsupported diagram language: {}", language)))?;
+    if !matches!(format, "svg" | "png") {
+        return Err(FsError::InvalidPath(format!("Unsupported diagram format: {}", format)));
+    }
+    let settings = find_vault_root(notebook_path)
+        .and_then(|vault| read_vault_config(&vault).ok())
+        .map(|config| config.diagrams)
+        .unwrap_or_default();
+    let renderer = kind.renderer(&settings)?;
+
+    let assets_dir = note_assets_dir(notebook_path);
+    fs::create_dir_all(&assets_dir)?;
+    let file_name = format!(
+        "diagram-{}.{}",
+        block_id.chars().filter(|c| c.is_ascii_alphanumeric() || *c == '-').collect::<String>(),
+        format
+    );
+    let output = assets_dir.join(&file_name);
+
+    let scratch = ScratchDir::create()?;
+    let source = scratch.path().join("diagram.src");
+    fs::write(&source, code)?;
+
+    let mut cmd = render_command(kind, &renderer, &source, &output, format);
+    let mut child = cmd
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    if kind == DiagramKind::PlantUml {
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(code.as_bytes())?;
+        }
+    }
+    let result = child.wait_with_output()?;
+    if !result.status.success() {
+        return Err(FsError::Renderer(String::from_utf8_lossy(&result.stderr).trim().to_string()));
+    }
+    if kind == DiagramKind::PlantUml {
+        fs::write(&output, &result.stdout)?;
+    }
+
+    let folder = assets_dir.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    Ok(RenderedDiagram {
+        path: output,
+        relative_path: format!("./{}/{}", folder, file_name),
+    })
+}
+
+/// Render a Mermaid, Graphviz or PlantUML block to an SVG (default) or PNG
+#[tauri::command]
+pub async fn render_diagram_block(
+    notebook_path: PathBuf,
+    block_id: String,
+    language: String,
+    code: String,
+    format: Option<String>,
+) -> Result<RenderedDiagram, FsError> {
+    let format = format.unwrap_or_else(|| "svg".to_string());
+    render_diagram(&notebook_path, &block_id, &language, &code, &format)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_renderer_commands() {
+        assert_eq!(DiagramKind::from_language("Mermaid"), Some(DiagramKind::Mermaid));
+        assert_eq!(DiagramKind::from_language("python"), None);
+
+        let dot = render_command(
+            DiagramKind::Graphviz,
+            Path::new("dot"),
+            Path::new("in.dot"),
+            Path::new("out.svg"),
+            "svg",
+        );
+        let args: Vec<_> = dot.get_args().map(|a| a.to_string_lossy().to_string()).collect();
+        assert_eq!(args, vec!["-Tsvg", "-o", "out.svg", "in.dot"]);
+
+        let jar = render_command(
+            DiagramKind::PlantUml,
+            Path::new("/opt/plantuml.jar"),
+            Path::new("in"),
+            Path::new("out.png"),
+            "png",
+        );
+        assert_eq!(jar.get_program(), "java");
+        let args: Vec<_> = jar.get_args().map(|a| a.to_string_lossy().to_string()).collect();
+        assert_eq!(args, vec!["-jar", "/opt/plantuml.jar", "-tpng", "-pipe"]);
+
+        let dir = tempfile::tempdir().unwrap();
+        let notebook = dir.path().join("Flow.md");
+        fs::create_dir_all(&notebook).unwrap();
+        assert_eq!(note_assets_dir(&notebook), dir.path().join("Flow.assets"));
+        assert!(render_diagram(&notebook, "b1", "mermaid", "graph TD; A-->B", "gif").is_err());
+    }
+}
@@ -0,0 +1,123 @@
+//! Diff of an open note's unsaved buffer against the file on disk.
+//!
+//! When a note changes externally while it has unsaved edits, the editor
+//! shows these hunks in a merge prompt. The buffer is the old side and the
+//! disk the new one, so added lines are what the external edit brings in.
+
+use similar::{ChangeTag, TextDiff};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::commands::FsError;
+use crate::git::word_diff::add_word_segments;
+use crate::git::{DiffFile, DiffHunk, DiffLine};
+
+/// Unchanged lines shown around each change
+const CONTEXT_LINES: usize = 3;
+
+/// Hunks turning `old` into `new`, in the shape git diffs use
+pub(crate) fn text_hunks(old: &str, new: &str) -> Vec<DiffHunk> {
+    let diff = TextDiff::from_lines(old, new);
+    let mut hunks = Vec::new();
+
+    for group in diff.grouped_ops(CONTEXT_LINES) {
+        let (Some(first), Some(last)) = (group.first(), group.last()) else {
+            continue;
+        };
+        let old_range = first.old_range().start..last.old_range().end;
+        let new_range = first.new_range().start..last.new_range().end;
+        let header = format!(
+            "@@ -{},{} +{},{} @@",
+            old_range.start + 1,
+            old_range.len(),
+            new_range.start + 1,
+            new_range.len()
+        );
+
+        let mut lines = Vec::new();
+        for op in &group {
+            for change in diff.iter_changes(op) {
+                let line_type = match change.tag() {
+                    ChangeTag::Equal => "context",
+                    ChangeTag::Delete => "delete",
+                    ChangeTag::Insert => "add",
+                };
+                lines.push(DiffLine {
+                    line_type: line_type.to_string(),
+                    old_line_no: change.old_index().map(|i| i as u32 + 1),
+                    new_line_no: change.new_index().map(|i| i as u32 + 1),
+                    content: change.value().to_string(),
+                    segments: None,
+                });
+            }
+        }
+
+        let mut hunk = DiffHunk { header, lines };
+        add_word_segments(&mut hunk);
+        hunks.push(hunk);
+    }
+
+    hunks
+}
+
+fn disk_diff(path: &Path, buffer_content: &str) -> Result<Option<DiffFile>, FsError> {
+    let (disk, status) = match fs::read_to_string(path) {
+        Ok(content) => (content, "modified"),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => (String::new(), "deleted"),
+        Err(e) => return Err(e.into()),
+    };
+
+    let hunks = text_hunks(buffer_content, &disk);
+    if hunks.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(DiffFile {
+        path: path.to_string_lossy().to_string(),
+        status: status.to_string(),
+        hunks,
+    }))
+}
+
+/// Diff of unsaved editor content against the note's file
+///
+/// Returns `None` when both are the same. A note deleted externally diffs
+/// against empty content with the status "deleted".
+#[tauri::command]
+pub fn diff_against_disk(path: PathBuf, buffer_content: String) -> Result<Option<DiffFile>, FsError> {
+    disk_diff(&path, &buffer_content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn diffs_buffer_against_external_edit() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("Note.md");
+        fs::write(&path, "# Title\n\nFirst line\nSecond line\n").unwrap();
+
+        let buffer = "# Title\n\nFirst line\nSecond line\n";
+        assert!(diff_against_disk(path.clone(), buffer.to_string()).unwrap().is_none());
+
+        let buffer = "# Title\n\nFirst line edited\nSecond line\n";
+        let file = diff_against_disk(path.clone(), buffer.to_string()).unwrap().unwrap();
+        assert_eq!(file.status, "modified");
+        assert_eq!(file.hunks.len(), 1);
+        assert_eq!(file.hunks[0].header, "@@ -1,4 +1,4 @@");
+        let changed: Vec<_> = file.hunks[0]
+            .lines
+            .iter()
+            .filter(|l| l.line_type != "context")
+            .map(|l| (l.line_type.as_str(), l.content.as_str()))
+            .collect();
+        assert_eq!(changed, vec![("delete", "First line edited\n"), ("add", "First line\n")]);
+        assert!(file.hunks[0].lines[2].segments.is_some());
+
+        fs::remove_file(&path).unwrap();
+        let file = diff_against_disk(path, buffer.to_string()).unwrap().unwrap();
+        assert_eq!(file.status, "deleted");
+    }
+}
@@ -0,0 +1,204 @@
+//! Finding notes with the same or nearly the same body.
+//!
+//! Imports from several tools tend to leave copies of a note behind. Bodies
+//! are compared without frontmatter and with whitespace collapsed: equal
+//! hashes make an exact group, and notes sharing most of their word
+//! shingles make a near group. Encrypted and empty notes are skipped.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+use super::commands::{read_markdown_text, walk_notes, FsError};
+use super::encryption::is_encrypted;
+use super::frontmatter::split_frontmatter;
+use super::types::{DuplicateGroup, DuplicateKind, DuplicateNote};
+
+/// Similarity from which two notes count as near duplicates
+const DEFAULT_THRESHOLD: f64 = 0.8;
+
+/// Words per shingle when comparing wording
+const SHINGLE_WORDS: usize = 3;
+
+/// A note's body prepared for comparison
+struct Body {
+    path: PathBuf,
+    size: u64,
+    hash: u64,
+    shingles: HashSet<u64>,
+}
+
+fn hash_of(value: impl Hash) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Body of a note without frontmatter, `None` when encrypted or empty
+fn note_body(path: &Path) -> Option<Body> {
+    let content = read_markdown_text(path).ok()?;
+    if is_encrypted(&content) {
+        return None;
+    }
+    let words: Vec<String> = split_frontmatter(&content)
+        .1
+        .split_whitespace()
+        .map(str::to_lowercase)
+        .collect();
+    if words.is_empty() {
+        return None;
+    }
+
+    let shingles = words.windows(SHINGLE_WORDS.min(words.len())).map(hash_of).collect();
+    Some(Body {
+        path: path.to_path_buf(),
+        size: content.len() as u64,
+        hash: hash_of(&words),
+        shingles,
+    })
+}
+
+fn jaccard(a: &HashSet<u64>, b: &HashSet<u64>) -> f64 {
+    let shared = a.intersection(b).count();
+    shared as f64 / (a.len() + b.len() - shared) as f64
+}
+
+/// Root of `i` in a union-find forest, compressing the path
+fn find(parents: &mut [usize], i: usize) -> usize {
+    let mut root = i;
+    while parents[root] != root {
+        root = parents[root];
+    }
+    let mut i = i;
+    while parents[i] != root {
+        let next = parents[i];
+        parents[i] = root;
+        i = next;
+    }
+    root
+}
+
+/// Group duplicate bodies: exact groups first, then near groups
+fn group_duplicates(bodies: &[Body], threshold: f64) -> Vec<DuplicateGroup> {
+    let entry = |i: usize| DuplicateNote {
+        path: bodies[i].path.clone(),
+        size: bodies[i].size,
+    };
+    let mut groups = Vec::new();
+
+    // Exact copies share a hash; one of each takes part in the near search
+    let mut by_hash: HashMap<u64, Vec<usize>> = HashMap::new();
+    for (i, body) in bodies.iter().enumerate() {
+        by_hash.entry(body.hash).or_default().push(i);
+    }
+    let mut copies: Vec<Vec<usize>> = by_hash.into_values().collect();
+    copies.sort();
+    for members in copies.iter().filter(|m| m.len() > 1) {
+        groups.push(DuplicateGroup {
+            kind: DuplicateKind::Exact,
+            similarity: 1.0,
+            notes: members.iter().map(|&i| entry(i)).collect(),
+        });
+    }
+
+    // Similar notes have similar shingle counts, so only nearby sizes are compared
+    let mut order: Vec<usize> = (0..copies.len()).collect();
+    order.sort_by_key(|&c| bodies[copies[c][0]].shingles.len());
+    let mut parents: Vec<usize> = (0..copies.len()).collect();
+    let mut lowest: HashMap<usize, f64> = HashMap::new();
+    for (n, &a) in order.iter().enumerate() {
+        let a_shingles = &bodies[copies[a][0]].shingles;
+        for &b in &order[n + 1..] {
+            let b_shingles = &bodies[copies[b][0]].shingles;
+            if (a_shingles.len() as f64) < b_shingles.len() as f64 * threshold {
+                break;
+            }
+            let similarity = jaccard(a_shingles, b_shingles);
+            if similarity < threshold {
+                continue;
+            }
+            let (root_a, root_b) = (find(&mut parents, a), find(&mut parents, b));
+            let low = [lowest.get(&root_a), lowest.get(&root_b)]
+                .into_iter()
+                .flatten()
+                .fold(similarity, |low, &s| low.min(s));
+            parents[root_b] = root_a;
+            lowest.remove(&root_b);
+            lowest.insert(root_a, low);
+        }
+    }
+
+    let mut near: HashMap<usize, Vec<usize>> = HashMap::new();
+    for (c, members) in copies.iter().enumerate() {
+        let root = find(&mut parents, c);
+        near.entry(root).or_default().extend(members);
+    }
+    let mut near_groups: Vec<DuplicateGroup> = near
+        .into_iter()
+        .filter(|(root, _)| lowest.contains_key(root))
+        .map(|(root, mut members)| {
+            members.sort();
+            DuplicateGroup {
+                kind: DuplicateKind::Near,
+                similarity: lowest[&root],
+                notes: members.into_iter().map(entry).collect(),
+            }
+        })
+        .collect();
+    near_groups.sort_by(|a, b| {
+        b.similarity
+            .total_cmp(&a.similarity)
+            .then_with(|| a.notes[0].path.cmp(&b.notes[0].path))
+    });
+    groups.extend(near_groups);
+    groups
+}
+
+/// Report groups of notes with identical or nearly identical bodies
+///
+/// `threshold` is the share of wording (0 to 1) two notes must have in
+/// common to be near duplicates, 0.8 by default.
+#[tauri::command]
+pub async fn find_duplicate_notes(vault_path: PathBuf, threshold: Option<f64>) -> Result<Vec<DuplicateGroup>, FsError> {
+    if !vault_path.is_dir() {
+        return Err(FsError::NotFound(vault_path.display().to_string()));
+    }
+    let threshold = threshold.unwrap_or(DEFAULT_THRESHOLD).clamp(0.01, 1.0);
+
+    let mut paths = Vec::new();
+    walk_notes(&vault_path, &mut paths);
+    paths.sort();
+    let bodies: Vec<Body> = paths.iter().filter_map(|p| note_body(p)).collect();
+    Ok(group_duplicates(&bodies, threshold))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn groups_exact_and_near_duplicates() {
+        let dir = tempfile::tempdir().unwrap();
+        let text = "Weekly planning covers goals for the team, open risks, hiring and the release schedule for next quarter";
+        fs::write(dir.path().join("A.md"), format!("---\ntitle: A\n---\n{}\n", text)).unwrap();
+        fs::write(dir.path().join("B.md"), format!("{}\n\n", text.replace(", ", ",\n"))).unwrap();
+        fs::write(dir.path().join("C.md"), format!("{} and beyond", text)).unwrap();
+        fs::write(dir.path().join("D.md"), "Something else entirely").unwrap();
+        fs::write(dir.path().join("E.md"), "---\ntitle: Empty\n---\n").unwrap();
+        fs::write(dir.path().join("F.md"), "").unwrap();
+
+        let groups = tauri::async_runtime::block_on(find_duplicate_notes(dir.path().to_path_buf(), None)).unwrap();
+        assert_eq!(groups.len(), 2);
+
+        let names = |group: &DuplicateGroup| -> Vec<String> {
+            group.notes.iter().map(|n| n.path.file_name().unwrap().to_string_lossy().to_string()).collect()
+        };
+        assert_eq!(groups[0].kind, DuplicateKind::Exact);
+        assert_eq!(names(&groups[0]), vec!["A.md", "B.md"]);
+        assert_eq!(groups[1].kind, DuplicateKind::Near);
+        assert_eq!(names(&groups[1]), vec!["A.md", "B.md", "C.md"]);
+        assert!(groups[1].similarity >= 0.8 && groups[1].similarity < 1.0);
+    }
+}
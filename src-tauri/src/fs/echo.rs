@@ -0,0 +1,88 @@
+//! Telling the app's own file writes apart from external changes.
+//!
+//! Every [`write_atomic`](super::commands::write_atomic) records the path
+//! along with the size and modification time it left the file with. When
+//! the watcher later sees an event for that path and the file still looks
+//! exactly like that, the event is an echo of our own write and isn't sent
+//! to the frontend, which already has the content. A change made by another
+//! program alters the fingerprint and still gets through.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime};
+
+/// How long after a write its events are still considered echoes
+const ECHO_WINDOW: Duration = Duration::from_secs(5);
+
+/// A recorded write: the file's fingerprint right after it and when it happened
+struct OwnWrite {
+    modified: SystemTime,
+    len: u64,
+    at: Instant,
+}
+
+fn own_writes() -> &'static Mutex<HashMap<PathBuf, OwnWrite>> {
+    static WRITES: OnceLock<Mutex<HashMap<PathBuf, OwnWrite>>> = OnceLock::new();
+    WRITES.get_or_init(Default::default)
+}
+
+/// Remember that the app just wrote `path`
+pub(crate) fn record_write(path: &Path) {
+    let Ok(metadata) = fs::metadata(path) else {
+        return;
+    };
+    let Ok(modified) = metadata.modified() else {
+        return;
+    };
+    let mut writes = own_writes().lock().unwrap();
+    writes.retain(|_, write| write.at.elapsed() < ECHO_WINDOW);
+    writes.insert(
+        path.to_path_buf(),
+        OwnWrite {
+            modified,
+            len: metadata.len(),
+            at: Instant::now(),
+        },
+    );
+}
+
+/// Report the next change of `path` like an external one, for writes made on
+/// behalf of another program
+pub(crate) fn forget_write(path: &Path) {
+    own_writes().lock().unwrap().remove(path);
+}
+
+/// Whether a change event for `path` comes from the app's own recent write
+pub(crate) fn is_own_write(path: &Path) -> bool {
+    let writes = own_writes().lock().unwrap();
+    let Some(write) = writes.get(path).filter(|w| w.at.elapsed() < ECHO_WINDOW) else {
+        return false;
+    };
+    fs::metadata(path)
+        .ok()
+        .and_then(|m| Some((m.modified().ok()?, m.len())))
+        .is_some_and(|(modified, len)| modified == write.modified && len == write.len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recognizes_own_writes_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let note = dir.path().join("Note.md");
+        fs::write(&note, "# Mine\n").unwrap();
+        assert!(!is_own_write(&note));
+
+        record_write(&note);
+        assert!(is_own_write(&note));
+
+        // Another program appends to the note
+        fs::write(&note, "# Mine\n\nAnd theirs\n").unwrap();
+        assert!(!is_own_write(&note));
+        assert!(!is_own_write(&dir.path().join("Other.md")));
+    }
+}
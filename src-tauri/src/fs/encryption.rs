@@ -8,12 +8,20 @@
 use std::io::{Read, Write};
 use std::path::Path;
 use std::sync::RwLock;
+use age::secrecy::zeroize::Zeroizing;
+use age::secrecy::{ExposeSecret, SecretString};
 use thiserror::Error;
 use keyring::Entry;
 
 const KEYCHAIN_SERVICE: &str = "com.notemaker.encryption";
 const KEYCHAIN_PASSWORD_KEY: &str = "encryption_password";
 const KEYCHAIN_IDENTITY_KEY: &str = "encryption_identity_path";
+const KEYCHAIN_ENV_PREFIX: &str = "env";
+const KEYCHAIN_DATABASE_PREFIX: &str = "database";
+
+/// Range of scrypt work factors (log2 of N) accepted in settings
+pub const MIN_SCRYPT_WORK_FACTOR: u8 = 10;
+pub const MAX_SCRYPT_WORK_FACTOR: u8 = 22;
 
 /// Encryption errors
 #[derive(Error, Debug)]
@@ -53,9 +61,11 @@ pub enum EncryptionError {
 }
 
 /// Encryption method configuration
-#[derive(Debug, Clone, PartialEq)]
+///
+/// The password is wiped from memory when the method is replaced or dropped.
+#[derive(Debug)]
 pub enum EncryptionMethod {
-    Password(String),
+    Password(SecretString),
     IdentityFile(String),
     /// Multiple identity files for multi-recipient decryption
     Recipients(Vec<String>),
@@ -66,6 +76,8 @@ pub struct EncryptionSession {
     method: RwLock<Option<EncryptionMethod>>,
     /// Cached public keys for multi-recipient encryption
     public_keys: RwLock<Vec<String>>,
+    /// scrypt work factor for password encryption; calibrated by age when unset
+    scrypt_work_factor: RwLock<Option<u8>>,
 }
 
 impl Default for EncryptionSession {
@@ -79,13 +91,14 @@ impl EncryptionSession {
         Self {
             method: RwLock::new(None),
             public_keys: RwLock::new(Vec::new()),
+            scrypt_work_factor: RwLock::new(None),
         }
     }
 
     /// Set password for the session
     pub fn set_password(&self, password: String) {
         let mut method = self.method.write().unwrap();
-        *method = Some(EncryptionMethod::Password(password));
+        *method = Some(EncryptionMethod::Password(SecretString::from(password)));
     }
 
     /// Set identity file path for the session
@@ -100,6 +113,17 @@ impl EncryptionSession {
         *method = Some(EncryptionMethod::Recipients(paths));
     }
 
+    /// Set the scrypt work factor used for password encryption
+    pub fn set_scrypt_work_factor(&self, work_factor: Option<u8>) {
+        let mut current = self.scrypt_work_factor.write().unwrap();
+        *current = work_factor;
+    }
+
+    /// Get the configured scrypt work factor
+    pub fn scrypt_work_factor(&self) -> Option<u8> {
+        *self.scrypt_work_factor.read().unwrap()
+    }
+
     /// Set public keys for multi-recipient encryption
     pub fn set_public_keys(&self, keys: Vec<String>) {
         let mut public_keys = self.public_keys.write().unwrap();
@@ -141,16 +165,28 @@ impl EncryptionSession {
         method.is_some()
     }
 
-    /// Get current method (cloned)
-    pub fn get_method(&self) -> Option<EncryptionMethod> {
+    /// Run `f` with the current method, without copying credentials out
+    /// of the session
+    fn with_method<R>(&self, f: impl FnOnce(Option<&EncryptionMethod>) -> R) -> R {
         let method = self.method.read().unwrap();
-        method.clone()
+        f(method.as_ref())
     }
 }
 
 /// Encrypt data with password using age's scrypt
-pub fn encrypt_with_password(plaintext: &[u8], password: &str) -> Result<Vec<u8>, EncryptionError> {
-    let encryptor = age::Encryptor::with_user_passphrase(age::secrecy::Secret::new(password.to_owned()));
+///
+/// Without a `work_factor`, age picks one that takes about a second here.
+pub fn encrypt_with_password(
+    plaintext: &[u8],
+    password: &str,
+    work_factor: Option<u8>,
+) -> Result<Vec<u8>, EncryptionError> {
+    let mut recipient = age::scrypt::Recipient::new(SecretString::from(password.to_owned()));
+    if let Some(log_n) = work_factor {
+        recipient.set_work_factor(log_n.clamp(MIN_SCRYPT_WORK_FACTOR, MAX_SCRYPT_WORK_FACTOR));
+    }
+    let encryptor = age::Encryptor::with_recipients(std::iter::once(&recipient as &dyn age::Recipient))
+        .map_err(|e| EncryptionError::EncryptionFailed(e.to_string()))?;
 
     let mut encrypted = vec![];
     let mut writer = encryptor
@@ -169,17 +205,29 @@ pub fn encrypt_with_password(plaintext: &[u8], password: &str) -> Result<Vec<u8>
 }
 
 /// Decrypt data with password
-pub fn decrypt_with_password(ciphertext: &[u8], password: &str) -> Result<Vec<u8>, EncryptionError> {
-    let decryptor = match age::Decryptor::new(ciphertext)
-        .map_err(|e| EncryptionError::DecryptionFailed(e.to_string()))?
-    {
-        age::Decryptor::Passphrase(d) => d,
-        _ => return Err(EncryptionError::DecryptionFailed("Not password-encrypted".to_string())),
-    };
+///
+/// A tuned `work_factor` raises the limit on the work factor accepted from
+/// files to the largest one settings allow, so files written with any
+/// allowed setting still open.
+pub fn decrypt_with_password(
+    ciphertext: &[u8],
+    password: &str,
+    work_factor: Option<u8>,
+) -> Result<Vec<u8>, EncryptionError> {
+    let decryptor = age::Decryptor::new(ciphertext)
+        .map_err(|e| EncryptionError::DecryptionFailed(e.to_string()))?;
+    if !decryptor.is_scrypt() {
+        return Err(EncryptionError::DecryptionFailed("Not password-encrypted".to_string()));
+    }
+
+    let mut identity = age::scrypt::Identity::new(SecretString::from(password.to_owned()));
+    if let Some(log_n) = work_factor {
+        identity.set_max_work_factor(log_n.max(MAX_SCRYPT_WORK_FACTOR));
+    }
 
     let mut decrypted = vec![];
     let mut reader = decryptor
-        .decrypt(&age::secrecy::Secret::new(password.to_owned()), None)
+        .decrypt(std::iter::once(&identity as &dyn age::Identity))
         .map_err(|_| EncryptionError::InvalidPassword)?;
 
     reader
@@ -196,7 +244,7 @@ fn load_x25519_identity(path: &str) -> Result<age::x25519::Identity, EncryptionE
         return Err(EncryptionError::IdentityFileNotFound(path.display().to_string()));
     }
 
-    let contents = std::fs::read_to_string(path)?;
+    let contents = Zeroizing::new(std::fs::read_to_string(path)?);
 
     // Parse the identity file line by line, looking for AGE-SECRET-KEY-
     for line in contents.lines() {
@@ -215,8 +263,8 @@ pub fn encrypt_with_identity_file(plaintext: &[u8], identity_path: &str) -> Resu
     let identity = load_x25519_identity(identity_path)?;
     let recipient = identity.to_public();
 
-    let encryptor = age::Encryptor::with_recipients(vec![Box::new(recipient)])
-        .expect("Recipients should not be empty");
+    let encryptor = age::Encryptor::with_recipients(std::iter::once(&recipient as &dyn age::Recipient))
+        .map_err(|e| EncryptionError::EncryptionFailed(e.to_string()))?;
 
     let mut encrypted = vec![];
     let mut writer = encryptor
@@ -238,12 +286,11 @@ pub fn encrypt_with_identity_file(plaintext: &[u8], identity_path: &str) -> Resu
 pub fn decrypt_with_identity_file(ciphertext: &[u8], identity_path: &str) -> Result<Vec<u8>, EncryptionError> {
     let identity = load_x25519_identity(identity_path)?;
 
-    let decryptor = match age::Decryptor::new(ciphertext)
-        .map_err(|e| EncryptionError::DecryptionFailed(e.to_string()))?
-    {
-        age::Decryptor::Recipients(d) => d,
-        _ => return Err(EncryptionError::DecryptionFailed("Not key-encrypted".to_string())),
-    };
+    let decryptor = age::Decryptor::new(ciphertext)
+        .map_err(|e| EncryptionError::DecryptionFailed(e.to_string()))?;
+    if decryptor.is_scrypt() {
+        return Err(EncryptionError::DecryptionFailed("Not key-encrypted".to_string()));
+    }
 
     let mut decrypted = vec![];
     let mut reader = decryptor
@@ -274,7 +321,6 @@ pub fn get_public_key_from_identity(identity_path: &str) -> Result<String, Encry
 pub fn generate_identity(path: &str) -> Result<String, EncryptionError> {
     use std::fs;
     use std::path::Path;
-    use age::secrecy::ExposeSecret;
 
     let path = Path::new(path);
 
@@ -290,15 +336,15 @@ pub fn generate_identity(path: &str) -> Result<String, EncryptionError> {
     let public_key = identity.to_public();
 
     // Format the identity file content
-    let content = format!(
+    let content = Zeroizing::new(format!(
         "# created: {}\n# public key: {}\n{}\n",
         chrono::Utc::now().format("%Y-%m-%dT%H:%M:%SZ"),
         public_key,
         identity.to_string().expose_secret()
-    );
+    ));
 
     // Write to file
-    fs::write(path, content)?;
+    fs::write(path, content.as_bytes())?;
 
     // Set restrictive permissions on Unix (owner read/write only)
     #[cfg(unix)]
@@ -317,18 +363,13 @@ pub fn encrypt_with_recipients(plaintext: &[u8], public_keys: &[String]) -> Resu
         return Err(EncryptionError::NoRecipients);
     }
 
-    let recipients: Result<Vec<Box<dyn age::Recipient + Send>>, EncryptionError> = public_keys
+    let recipients = public_keys
         .iter()
-        .map(|key| {
-            let recipient = parse_public_key(key)?;
-            Ok(Box::new(recipient) as Box<dyn age::Recipient + Send>)
-        })
-        .collect();
+        .map(|key| parse_public_key(key))
+        .collect::<Result<Vec<_>, EncryptionError>>()?;
 
-    let recipients = recipients?;
-
-    let encryptor = age::Encryptor::with_recipients(recipients)
-        .expect("Recipients should not be empty");
+    let encryptor = age::Encryptor::with_recipients(recipients.iter().map(|r| r as &dyn age::Recipient))
+        .map_err(|e| EncryptionError::EncryptionFailed(e.to_string()))?;
 
     let mut encrypted = vec![];
     let mut writer = encryptor
@@ -362,12 +403,11 @@ pub fn decrypt_with_recipient_identities(ciphertext: &[u8], identity_paths: &[St
         return Err(EncryptionError::NoMatchingKey);
     }
 
-    let decryptor = match age::Decryptor::new(ciphertext)
-        .map_err(|e| EncryptionError::DecryptionFailed(e.to_string()))?
-    {
-        age::Decryptor::Recipients(d) => d,
-        _ => return Err(EncryptionError::DecryptionFailed("Not key-encrypted".to_string())),
-    };
+    let decryptor = age::Decryptor::new(ciphertext)
+        .map_err(|e| EncryptionError::DecryptionFailed(e.to_string()))?;
+    if decryptor.is_scrypt() {
+        return Err(EncryptionError::DecryptionFailed("Not key-encrypted".to_string()));
+    }
 
     // Try decrypting with all identities
     let identity_refs: Vec<&dyn age::Identity> = identities
@@ -396,25 +436,29 @@ pub fn encrypt_with_session(session: &EncryptionSession, plaintext: &[u8]) -> Re
     }
 
     // Fall back to single-method encryption
-    match session.get_method() {
-        Some(EncryptionMethod::Password(password)) => encrypt_with_password(plaintext, &password),
-        Some(EncryptionMethod::IdentityFile(path)) => encrypt_with_identity_file(plaintext, &path),
+    session.with_method(|method| match method {
+        Some(EncryptionMethod::Password(password)) => {
+            encrypt_with_password(plaintext, password.expose_secret(), session.scrypt_work_factor())
+        }
+        Some(EncryptionMethod::IdentityFile(path)) => encrypt_with_identity_file(plaintext, path),
         Some(EncryptionMethod::Recipients(_)) => {
             // Recipients mode but no public keys set
             Err(EncryptionError::NoRecipients)
         }
         None => Err(EncryptionError::SessionLocked),
-    }
+    })
 }
 
 /// Decrypt using session credentials
 pub fn decrypt_with_session(session: &EncryptionSession, ciphertext: &[u8]) -> Result<Vec<u8>, EncryptionError> {
-    match session.get_method() {
-        Some(EncryptionMethod::Password(password)) => decrypt_with_password(ciphertext, &password),
-        Some(EncryptionMethod::IdentityFile(path)) => decrypt_with_identity_file(ciphertext, &path),
-        Some(EncryptionMethod::Recipients(paths)) => decrypt_with_recipient_identities(ciphertext, &paths),
+    session.with_method(|method| match method {
+        Some(EncryptionMethod::Password(password)) => {
+            decrypt_with_password(ciphertext, password.expose_secret(), session.scrypt_work_factor())
+        }
+        Some(EncryptionMethod::IdentityFile(path)) => decrypt_with_identity_file(ciphertext, path),
+        Some(EncryptionMethod::Recipients(paths)) => decrypt_with_recipient_identities(ciphertext, paths),
         None => Err(EncryptionError::SessionLocked),
-    }
+    })
 }
 
 /// Armor encrypted data (base64 with markers)
@@ -461,76 +505,130 @@ pub fn is_encrypted(content: &str) -> bool {
 // Keychain functions for persistent storage
 // ============================================================================
 
-/// Save password to system keychain
-pub fn save_password_to_keychain(password: &str) -> Result<(), EncryptionError> {
-    let entry = Entry::new(KEYCHAIN_SERVICE, KEYCHAIN_PASSWORD_KEY)
-        .map_err(|e| EncryptionError::KeychainError(e.to_string()))?;
-
-    entry.set_password(password)
-        .map_err(|e| EncryptionError::KeychainError(e.to_string()))
+/// Keychain account of a vault, so vaults with different credentials don't
+/// overwrite each other
+fn vault_account(key: &str, vault: &Path) -> String {
+    let vault = vault.canonicalize().unwrap_or_else(|_| vault.to_path_buf());
+    format!("{}:{}", key, vault.display())
 }
 
-/// Load password from system keychain
-pub fn load_password_from_keychain() -> Result<Option<String>, EncryptionError> {
-    let entry = Entry::new(KEYCHAIN_SERVICE, KEYCHAIN_PASSWORD_KEY)
-        .map_err(|e| EncryptionError::KeychainError(e.to_string()))?;
+fn keychain_entry(account: &str) -> Result<Entry, EncryptionError> {
+    Entry::new(KEYCHAIN_SERVICE, account).map_err(|e| EncryptionError::KeychainError(e.to_string()))
+}
 
-    match entry.get_password() {
-        Ok(password) => Ok(Some(password)),
+fn load_keychain_value(account: &str) -> Result<Option<String>, EncryptionError> {
+    match keychain_entry(account)?.get_password() {
+        Ok(value) => Ok(Some(value)),
         Err(keyring::Error::NoEntry) => Ok(None),
         Err(e) => Err(EncryptionError::KeychainError(e.to_string())),
     }
 }
 
-/// Delete password from system keychain
-pub fn delete_password_from_keychain() -> Result<(), EncryptionError> {
-    let entry = Entry::new(KEYCHAIN_SERVICE, KEYCHAIN_PASSWORD_KEY)
-        .map_err(|e| EncryptionError::KeychainError(e.to_string()))?;
-
-    match entry.delete_credential() {
+fn delete_keychain_value(account: &str) -> Result<(), EncryptionError> {
+    match keychain_entry(account)?.delete_credential() {
         Ok(()) => Ok(()),
         Err(keyring::Error::NoEntry) => Ok(()), // Already deleted
         Err(e) => Err(EncryptionError::KeychainError(e.to_string())),
     }
 }
 
-/// Save identity file path to system keychain
-pub fn save_identity_path_to_keychain(path: &str) -> Result<(), EncryptionError> {
-    let entry = Entry::new(KEYCHAIN_SERVICE, KEYCHAIN_IDENTITY_KEY)
+/// Value stored for a vault
+///
+/// Versions that did not scope entries per vault kept a single global entry.
+/// It is moved to the first vault that asks for it and removed, so it can't
+/// unlock any other vault later on.
+fn load_vault_value(key: &str, vault: &Path) -> Result<Option<String>, EncryptionError> {
+    let account = vault_account(key, vault);
+    if let Some(value) = load_keychain_value(&account)? {
+        return Ok(Some(value));
+    }
+    let Some(legacy) = load_keychain_value(key)? else {
+        return Ok(None);
+    };
+    keychain_entry(&account)?
+        .set_password(&legacy)
         .map_err(|e| EncryptionError::KeychainError(e.to_string()))?;
+    delete_keychain_value(key)?;
+    Ok(Some(legacy))
+}
 
-    entry.set_password(path)
+/// Delete the vault's own entry
+fn delete_vault_value(key: &str, vault: &Path) -> Result<(), EncryptionError> {
+    delete_keychain_value(&vault_account(key, vault))
+}
+
+/// Save a vault's password to the system keychain
+pub fn save_password_to_keychain(vault: &Path, password: &str) -> Result<(), EncryptionError> {
+    keychain_entry(&vault_account(KEYCHAIN_PASSWORD_KEY, vault))?
+        .set_password(password)
         .map_err(|e| EncryptionError::KeychainError(e.to_string()))
 }
 
-/// Load identity file path from system keychain
-pub fn load_identity_path_from_keychain() -> Result<Option<String>, EncryptionError> {
-    let entry = Entry::new(KEYCHAIN_SERVICE, KEYCHAIN_IDENTITY_KEY)
-        .map_err(|e| EncryptionError::KeychainError(e.to_string()))?;
+/// Load a vault's password from the system keychain
+pub fn load_password_from_keychain(vault: &Path) -> Result<Option<String>, EncryptionError> {
+    load_vault_value(KEYCHAIN_PASSWORD_KEY, vault)
+}
 
-    match entry.get_password() {
-        Ok(path) => Ok(Some(path)),
-        Err(keyring::Error::NoEntry) => Ok(None),
-        Err(e) => Err(EncryptionError::KeychainError(e.to_string())),
-    }
+/// Delete a vault's password from the system keychain
+pub fn delete_password_from_keychain(vault: &Path) -> Result<(), EncryptionError> {
+    delete_vault_value(KEYCHAIN_PASSWORD_KEY, vault)
 }
 
-/// Delete identity path from system keychain
-pub fn delete_identity_path_from_keychain() -> Result<(), EncryptionError> {
-    let entry = Entry::new(KEYCHAIN_SERVICE, KEYCHAIN_IDENTITY_KEY)
-        .map_err(|e| EncryptionError::KeychainError(e.to_string()))?;
+/// Save a vault's identity file path to the system keychain
+pub fn save_identity_path_to_keychain(vault: &Path, path: &str) -> Result<(), EncryptionError> {
+    keychain_entry(&vault_account(KEYCHAIN_IDENTITY_KEY, vault))?
+        .set_password(path)
+        .map_err(|e| EncryptionError::KeychainError(e.to_string()))
+}
 
-    match entry.delete_credential() {
-        Ok(()) => Ok(()),
-        Err(keyring::Error::NoEntry) => Ok(()), // Already deleted
-        Err(e) => Err(EncryptionError::KeychainError(e.to_string())),
-    }
+/// Load a vault's identity file path from the system keychain
+pub fn load_identity_path_from_keychain(vault: &Path) -> Result<Option<String>, EncryptionError> {
+    load_vault_value(KEYCHAIN_IDENTITY_KEY, vault)
+}
+
+/// Delete a vault's identity path from the system keychain
+pub fn delete_identity_path_from_keychain(vault: &Path) -> Result<(), EncryptionError> {
+    delete_vault_value(KEYCHAIN_IDENTITY_KEY, vault)
+}
+
+/// Save a secret environment variable for code execution in a vault
+pub fn save_env_secret(vault: &Path, name: &str, value: &str) -> Result<(), EncryptionError> {
+    keychain_entry(&vault_account(&format!("{}:{}", KEYCHAIN_ENV_PREFIX, name), vault))?
+        .set_password(value)
+        .map_err(|e| EncryptionError::KeychainError(e.to_string()))
 }
 
-/// Check if credentials are stored in keychain
-pub fn has_stored_credentials() -> bool {
-    load_password_from_keychain().ok().flatten().is_some() ||
-    load_identity_path_from_keychain().ok().flatten().is_some()
+/// Load a secret environment variable of a vault
+pub fn load_env_secret(vault: &Path, name: &str) -> Result<Option<String>, EncryptionError> {
+    load_keychain_value(&vault_account(&format!("{}:{}", KEYCHAIN_ENV_PREFIX, name), vault))
+}
+
+/// Delete a secret environment variable of a vault
+pub fn delete_env_secret(vault: &Path, name: &str) -> Result<(), EncryptionError> {
+    delete_keychain_value(&vault_account(&format!("{}:{}", KEYCHAIN_ENV_PREFIX, name), vault))
+}
+
+/// Save the password of a named database connection of a vault
+pub fn save_database_password(vault: &Path, name: &str, password: &str) -> Result<(), EncryptionError> {
+    keychain_entry(&vault_account(&format!("{}:{}", KEYCHAIN_DATABASE_PREFIX, name), vault))?
+        .set_password(password)
+        .map_err(|e| EncryptionError::KeychainError(e.to_string()))
+}
+
+/// Load the password of a named database connection of a vault
+pub fn load_database_password(vault: &Path, name: &str) -> Result<Option<String>, EncryptionError> {
+    load_keychain_value(&vault_account(&format!("{}:{}", KEYCHAIN_DATABASE_PREFIX, name), vault))
+}
+
+/// Delete the password of a named database connection of a vault
+pub fn delete_database_password(vault: &Path, name: &str) -> Result<(), EncryptionError> {
+    delete_keychain_value(&vault_account(&format!("{}:{}", KEYCHAIN_DATABASE_PREFIX, name), vault))
+}
+
+/// Check if credentials are stored in keychain for a vault
+pub fn has_stored_credentials(vault: &Path) -> bool {
+    load_password_from_keychain(vault).ok().flatten().is_some() ||
+    load_identity_path_from_keychain(vault).ok().flatten().is_some()
 }
 
 #[cfg(test)]
@@ -542,20 +640,29 @@ mod tests {
         let plaintext = b"Hello, secret world!";
         let password = "test_password_123";
 
-        let encrypted = encrypt_with_password(plaintext, password).unwrap();
-        let decrypted = decrypt_with_password(&encrypted, password).unwrap();
+        let encrypted = encrypt_with_password(plaintext, password, None).unwrap();
+        let decrypted = decrypt_with_password(&encrypted, password, None).unwrap();
 
         assert_eq!(plaintext.to_vec(), decrypted);
     }
 
+    #[test]
+    fn test_password_work_factor() {
+        let encrypted = encrypt_with_password(b"tuned", "pw", Some(MIN_SCRYPT_WORK_FACTOR)).unwrap();
+        let header = String::from_utf8_lossy(&encrypted[..encrypted.len().min(200)]).to_string();
+
+        assert!(header.contains(&format!(" {}\n", MIN_SCRYPT_WORK_FACTOR)));
+        assert_eq!(decrypt_with_password(&encrypted, "pw", Some(MIN_SCRYPT_WORK_FACTOR)).unwrap(), b"tuned");
+    }
+
     #[test]
     fn test_wrong_password() {
         let plaintext = b"Hello, secret world!";
         let password = "correct_password";
         let wrong_password = "wrong_password";
 
-        let encrypted = encrypt_with_password(plaintext, password).unwrap();
-        let result = decrypt_with_password(&encrypted, wrong_password);
+        let encrypted = encrypt_with_password(plaintext, password, None).unwrap();
+        let result = decrypt_with_password(&encrypted, wrong_password, None);
 
         assert!(matches!(result, Err(EncryptionError::InvalidPassword)));
     }
@@ -578,6 +685,15 @@ mod tests {
         assert!(!is_encrypted(plain));
     }
 
+    #[test]
+    fn test_keychain_accounts_are_per_vault() {
+        let a = vault_account(KEYCHAIN_PASSWORD_KEY, Path::new("/vaults/a"));
+        let b = vault_account(KEYCHAIN_PASSWORD_KEY, Path::new("/vaults/b"));
+
+        assert_ne!(a, b);
+        assert!(a.starts_with("encryption_password:"));
+    }
+
     #[test]
     fn test_session_lifecycle() {
         let session = EncryptionSession::new();
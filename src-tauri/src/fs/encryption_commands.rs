@@ -5,15 +5,24 @@ use super::encryption::{
     EncryptionError, EncryptionSession, get_public_key_from_identity,
     save_password_to_keychain, load_password_from_keychain, delete_password_from_keychain,
     save_identity_path_to_keychain, load_identity_path_from_keychain, delete_identity_path_from_keychain,
-    has_stored_credentials,
+    has_stored_credentials, MAX_SCRYPT_WORK_FACTOR, MIN_SCRYPT_WORK_FACTOR,
 };
-use std::path::PathBuf;
-use std::sync::Arc;
+use super::commands::{
+    decrypt_block_file, decrypt_kanban_files, decrypt_notebook_files, encrypt_block_file, encrypt_kanban_files,
+    encrypt_notebook_files, find_vault_root, write_atomic,
+};
+use super::reencrypt::{reencrypt_vault_files, session_opens_vault};
+use super::strength::passphrase_strength;
+use super::types::{PassphraseStrength, ReencryptReport};
+use age::secrecy::zeroize::Zeroizing;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 use tauri::State;
 use serde::{Deserialize, Serialize};
 
 /// Expand tilde (~) to home directory
-fn expand_tilde(path: &str) -> PathBuf {
+pub(crate) fn expand_tilde(path: &str) -> PathBuf {
     if path.starts_with("~/") {
         if let Some(home) = std::env::var_os("HOME") {
             return PathBuf::from(home).join(&path[2..]);
@@ -27,15 +36,20 @@ fn expand_tilde(path: &str) -> PathBuf {
 }
 
 /// Encryption state managed by Tauri
+///
+/// Each vault has its own session, so unlocking one vault never lets
+/// another one be read with its credentials.
+#[derive(Default)]
 pub struct EncryptionState {
-    pub session: Arc<EncryptionSession>,
+    sessions: Mutex<HashMap<PathBuf, Arc<EncryptionSession>>>,
 }
 
-impl Default for EncryptionState {
-    fn default() -> Self {
-        Self {
-            session: Arc::new(EncryptionSession::new()),
-        }
+impl EncryptionState {
+    /// Session of the vault containing `path`
+    pub fn session(&self, path: &Path) -> Arc<EncryptionSession> {
+        let vault = find_vault_root(path).unwrap_or_else(|| path.to_path_buf());
+        let vault = vault.canonicalize().unwrap_or(vault);
+        self.sessions.lock().unwrap().entry(vault).or_default().clone()
     }
 }
 
@@ -50,9 +64,10 @@ impl From<EncryptionError> for String {
 #[tauri::command]
 pub fn set_encryption_password(
     state: State<'_, EncryptionState>,
+    vault_path: String,
     password: String,
 ) -> Result<(), String> {
-    state.session.set_password(password);
+    state.session(&expand_tilde(&vault_path)).set_password(password);
     Ok(())
 }
 
@@ -60,6 +75,7 @@ pub fn set_encryption_password(
 #[tauri::command]
 pub fn set_encryption_identity(
     state: State<'_, EncryptionState>,
+    vault_path: String,
     path: String,
 ) -> Result<(), String> {
     // Expand tilde and validate file exists
@@ -67,30 +83,33 @@ pub fn set_encryption_identity(
     if !expanded_path.exists() {
         return Err(format!("Identity file not found: {}", path));
     }
-    state.session.set_identity_file(expanded_path.to_string_lossy().to_string());
+    state
+        .session(&expand_tilde(&vault_path))
+        .set_identity_file(expanded_path.to_string_lossy().to_string());
     Ok(())
 }
 
 /// Lock the encryption session (clear credentials)
 #[tauri::command]
-pub fn lock_encryption_session(state: State<'_, EncryptionState>) -> Result<(), String> {
-    state.session.lock();
+pub fn lock_encryption_session(state: State<'_, EncryptionState>, vault_path: String) -> Result<(), String> {
+    state.session(&expand_tilde(&vault_path)).lock();
     Ok(())
 }
 
 /// Check if encryption session is unlocked
 #[tauri::command]
-pub fn is_encryption_unlocked(state: State<'_, EncryptionState>) -> bool {
-    state.session.is_unlocked()
+pub fn is_encryption_unlocked(state: State<'_, EncryptionState>, vault_path: String) -> bool {
+    state.session(&expand_tilde(&vault_path)).is_unlocked()
 }
 
 /// Encrypt a text block
 #[tauri::command]
 pub fn encrypt_block(
     state: State<'_, EncryptionState>,
+    vault_path: String,
     content: String,
 ) -> Result<String, String> {
-    let encrypted = encrypt_with_session(&state.session, content.as_bytes())
+    let encrypted = encrypt_with_session(&state.session(&expand_tilde(&vault_path)), content.as_bytes())
         .map_err(|e| e.to_string())?;
     Ok(armor_encrypt(&encrypted))
 }
@@ -99,6 +118,7 @@ pub fn encrypt_block(
 #[tauri::command]
 pub fn decrypt_block(
     state: State<'_, EncryptionState>,
+    vault_path: String,
     content: String,
 ) -> Result<String, String> {
     if !is_encrypted(&content) {
@@ -106,7 +126,7 @@ pub fn decrypt_block(
     }
 
     let ciphertext = dearmor_decrypt(&content).map_err(|e| e.to_string())?;
-    let decrypted = decrypt_with_session(&state.session, &ciphertext)
+    let decrypted = decrypt_with_session(&state.session(&expand_tilde(&vault_path)), &ciphertext)
         .map_err(|e| e.to_string())?;
 
     String::from_utf8(decrypted)
@@ -119,25 +139,30 @@ pub fn encrypt_note(
     state: State<'_, EncryptionState>,
     path: String,
 ) -> Result<(), String> {
-    let file_path = PathBuf::from(&path);
+    let path = PathBuf::from(path);
+    encrypt_note_file(&path, &state.session(&path))
+}
 
+/// Encrypt a note in place with the session's credentials
+pub fn encrypt_note_file(file_path: &Path, session: &EncryptionSession) -> Result<(), String> {
     if !file_path.exists() {
-        return Err(format!("Note file not found: {}", path));
+        return Err(format!("Note file not found: {}", file_path.display()));
     }
 
-    let content = std::fs::read_to_string(&file_path)
-        .map_err(|e| format!("Failed to read note: {}", e))?;
+    let content = Zeroizing::new(
+        std::fs::read_to_string(file_path).map_err(|e| format!("Failed to read note: {}", e))?,
+    );
 
     // Don't double-encrypt
     if is_encrypted(&content) {
         return Err("Note is already encrypted".to_string());
     }
 
-    let encrypted = encrypt_with_session(&state.session, content.as_bytes())
+    let encrypted = encrypt_with_session(session, content.as_bytes())
         .map_err(|e| e.to_string())?;
     let armored = armor_encrypt(&encrypted);
 
-    std::fs::write(&file_path, armored)
+    write_atomic(file_path, armored)
         .map_err(|e| format!("Failed to write encrypted note: {}", e))?;
 
     Ok(())
@@ -149,13 +174,17 @@ pub fn decrypt_note(
     state: State<'_, EncryptionState>,
     path: String,
 ) -> Result<(), String> {
-    let file_path = PathBuf::from(&path);
+    let path = PathBuf::from(path);
+    decrypt_note_file(&path, &state.session(&path))
+}
 
+/// Decrypt a note in place with the session's credentials
+pub fn decrypt_note_file(file_path: &Path, session: &EncryptionSession) -> Result<(), String> {
     if !file_path.exists() {
-        return Err(format!("Note file not found: {}", path));
+        return Err(format!("Note file not found: {}", file_path.display()));
     }
 
-    let content = std::fs::read_to_string(&file_path)
+    let content = std::fs::read_to_string(file_path)
         .map_err(|e| format!("Failed to read note: {}", e))?;
 
     if !is_encrypted(&content) {
@@ -163,18 +192,181 @@ pub fn decrypt_note(
     }
 
     let ciphertext = dearmor_decrypt(&content).map_err(|e| e.to_string())?;
-    let decrypted = decrypt_with_session(&state.session, &ciphertext)
+    let decrypted = decrypt_with_session(session, &ciphertext)
         .map_err(|e| e.to_string())?;
 
-    let plaintext = String::from_utf8(decrypted)
-        .map_err(|e| format!("Invalid UTF-8 in decrypted content: {}", e))?;
+    let plaintext = Zeroizing::new(
+        String::from_utf8(decrypted).map_err(|e| format!("Invalid UTF-8 in decrypted content: {}", e))?,
+    );
 
-    std::fs::write(&file_path, plaintext)
+    write_atomic(file_path, plaintext.as_bytes())
         .map_err(|e| format!("Failed to write decrypted note: {}", e))?;
 
     Ok(())
 }
 
+/// Encrypt every block of a notebook, optionally including its index
+#[tauri::command]
+pub fn encrypt_notebook(
+    state: State<'_, EncryptionState>,
+    path: String,
+    encrypt_index: Option<bool>,
+) -> Result<(), String> {
+    let path = PathBuf::from(path);
+    let session = state.session(&path);
+    if !session.is_unlocked() {
+        return Err("Encryption session is locked".to_string());
+    }
+    encrypt_notebook_files(&path, &session, encrypt_index.unwrap_or(false))
+        .map_err(|e| e.to_string())
+}
+
+/// Decrypt every block and the index of a notebook
+#[tauri::command]
+pub fn decrypt_notebook(
+    state: State<'_, EncryptionState>,
+    path: String,
+) -> Result<(), String> {
+    let path = PathBuf::from(path);
+    let session = state.session(&path);
+    if !session.is_unlocked() {
+        return Err("Encryption session is locked".to_string());
+    }
+    decrypt_notebook_files(&path, &session).map_err(|e| e.to_string())
+}
+
+/// Encrypt one block of a notebook
+#[tauri::command]
+pub fn encrypt_notebook_block(
+    state: State<'_, EncryptionState>,
+    notebook_path: String,
+    block_id: String,
+) -> Result<(), String> {
+    let notebook_path = PathBuf::from(notebook_path);
+    let session = state.session(&notebook_path);
+    if !session.is_unlocked() {
+        return Err("Encryption session is locked".to_string());
+    }
+    encrypt_block_file(&notebook_path, &block_id, &session).map_err(|e| e.to_string())
+}
+
+/// Decrypt one block of a notebook
+#[tauri::command]
+pub fn decrypt_notebook_block(
+    state: State<'_, EncryptionState>,
+    notebook_path: String,
+    block_id: String,
+) -> Result<(), String> {
+    let notebook_path = PathBuf::from(notebook_path);
+    let session = state.session(&notebook_path);
+    if !session.is_unlocked() {
+        return Err("Encryption session is locked".to_string());
+    }
+    decrypt_block_file(&notebook_path, &block_id, &session).map_err(|e| e.to_string())
+}
+
+/// Encrypt the index and task descriptions of a kanban board
+#[tauri::command]
+pub fn encrypt_kanban(
+    state: State<'_, EncryptionState>,
+    path: String,
+) -> Result<(), String> {
+    let path = PathBuf::from(path);
+    let session = state.session(&path);
+    if !session.is_unlocked() {
+        return Err("Encryption session is locked".to_string());
+    }
+    encrypt_kanban_files(&path, &session).map_err(|e| e.to_string())
+}
+
+/// Decrypt the index and task descriptions of a kanban board
+#[tauri::command]
+pub fn decrypt_kanban(
+    state: State<'_, EncryptionState>,
+    path: String,
+) -> Result<(), String> {
+    let path = PathBuf::from(path);
+    let session = state.session(&path);
+    if !session.is_unlocked() {
+        return Err("Encryption session is locked".to_string());
+    }
+    decrypt_kanban_files(&path, &session).map_err(|e| e.to_string())
+}
+
+/// Re-encrypt every encrypted file in a vault with new credentials
+///
+/// Exactly one of `new_recipients` (age public keys) or `new_password` is
+/// used. The session switches to the new credentials afterwards.
+#[tauri::command]
+pub fn reencrypt_vault(
+    state: State<'_, EncryptionState>,
+    vault_path: String,
+    new_recipients: Option<Vec<String>>,
+    new_password: Option<String>,
+) -> Result<ReencryptReport, String> {
+    let new_password = new_password.map(Zeroizing::new);
+    let next = EncryptionSession::new();
+    match (&new_recipients, &new_password) {
+        (Some(keys), None) if !keys.is_empty() => next.set_public_keys(keys.clone()),
+        (None, Some(password)) if !password.is_empty() => next.set_password(password.to_string()),
+        _ => return Err("Provide either new recipients or a new password".to_string()),
+    }
+
+    let vault = expand_tilde(&vault_path);
+    let session = state.session(&vault);
+    let report = reencrypt_vault_files(&vault, &session, &next)?;
+
+    if let Some(keys) = new_recipients {
+        session.set_public_keys(keys);
+    } else if let Some(password) = new_password {
+        // Keep a remembered password in step with the files
+        if load_password_from_keychain(&vault).ok().flatten().is_some() {
+            save_password_to_keychain(&vault, &password).map_err(|e| e.to_string())?;
+        }
+        session.set_password(password.to_string());
+    }
+
+    Ok(report)
+}
+
+/// Change the encryption passphrase of a vault
+///
+/// The old passphrase is checked against the vault's encrypted files before
+/// anything is rewritten. The keychain entry is only replaced once every
+/// file encrypted with the old passphrase carries the new one.
+#[tauri::command]
+pub fn change_encryption_password(
+    state: State<'_, EncryptionState>,
+    vault_path: String,
+    old_password: String,
+    new_password: String,
+    update_keychain: bool,
+) -> Result<ReencryptReport, String> {
+    let new_password = Zeroizing::new(new_password);
+    if new_password.is_empty() {
+        return Err("New password must not be empty".to_string());
+    }
+
+    let vault = expand_tilde(&vault_path);
+    let current = EncryptionSession::new();
+    current.set_password(old_password);
+    if !session_opens_vault(&vault, &current) {
+        return Err(EncryptionError::InvalidPassword.to_string());
+    }
+
+    let next = EncryptionSession::new();
+    next.set_password(new_password.to_string());
+    let report = reencrypt_vault_files(&vault, &current, &next)?;
+
+    if update_keychain {
+        let _ = delete_identity_path_from_keychain(&vault);
+        save_password_to_keychain(&vault, &new_password).map_err(|e| e.to_string())?;
+    }
+    state.session(&vault).set_password(new_password.to_string());
+
+    Ok(report)
+}
+
 /// Check if a note file is encrypted
 #[tauri::command]
 pub fn is_note_encrypted(path: String) -> Result<bool, String> {
@@ -196,66 +388,106 @@ pub fn is_content_encrypted(content: String) -> bool {
     is_encrypted(&content)
 }
 
+/// Set the scrypt work factor (log2 of N) used for password encryption
+///
+/// `None` lets age calibrate one that takes about a second on this device.
+#[tauri::command]
+pub fn set_scrypt_work_factor(
+    state: State<'_, EncryptionState>,
+    vault_path: String,
+    work_factor: Option<u8>,
+) -> Result<(), String> {
+    if let Some(log_n) = work_factor {
+        if !(MIN_SCRYPT_WORK_FACTOR..=MAX_SCRYPT_WORK_FACTOR).contains(&log_n) {
+            return Err(format!(
+                "Work factor must be between {} and {}",
+                MIN_SCRYPT_WORK_FACTOR, MAX_SCRYPT_WORK_FACTOR
+            ));
+        }
+    }
+    state.session(&expand_tilde(&vault_path)).set_scrypt_work_factor(work_factor);
+    Ok(())
+}
+
+/// Estimate the strength of a passphrase
+#[tauri::command]
+pub fn check_passphrase_strength(password: String) -> PassphraseStrength {
+    let password = Zeroizing::new(password);
+    passphrase_strength(&password)
+}
+
 // ============================================================================
 // Keychain commands for persistent credential storage
 // ============================================================================
 
-/// Set password and optionally save to keychain
+/// Set password and optionally save it to the vault's keychain entry
 #[tauri::command]
 pub fn set_encryption_password_with_save(
     state: State<'_, EncryptionState>,
+    vault_path: String,
     password: String,
     save_to_keychain: bool,
 ) -> Result<(), String> {
-    state.session.set_password(password.clone());
+    let vault = expand_tilde(&vault_path);
+    let password = Zeroizing::new(password);
+    state.session(&vault).set_password(password.to_string());
 
     if save_to_keychain {
         // Clear any stored identity path since we're using password now
-        let _ = delete_identity_path_from_keychain();
-        save_password_to_keychain(&password).map_err(|e| e.to_string())?;
+        let _ = delete_identity_path_from_keychain(&vault);
+        save_password_to_keychain(&vault, &password).map_err(|e| e.to_string())?;
     }
 
     Ok(())
 }
 
-/// Set identity and optionally save to keychain
+/// Set identity and optionally save it to the vault's keychain entry
 #[tauri::command]
 pub fn set_encryption_identity_with_save(
     state: State<'_, EncryptionState>,
+    vault_path: String,
     path: String,
     save_to_keychain: bool,
 ) -> Result<(), String> {
+    let vault = expand_tilde(&vault_path);
     let expanded_path = expand_tilde(&path);
     if !expanded_path.exists() {
         return Err(format!("Identity file not found: {}", path));
     }
 
     let path_str = expanded_path.to_string_lossy().to_string();
-    state.session.set_identity_file(path_str.clone());
+    state.session(&vault).set_identity_file(path_str.clone());
 
     if save_to_keychain {
         // Clear any stored password since we're using identity now
-        let _ = delete_password_from_keychain();
-        save_identity_path_to_keychain(&path_str).map_err(|e| e.to_string())?;
+        let _ = delete_password_from_keychain(&vault);
+        save_identity_path_to_keychain(&vault, &path_str).map_err(|e| e.to_string())?;
     }
 
     Ok(())
 }
 
-/// Try to unlock from stored keychain credentials
+/// Try to unlock from the keychain credentials stored for a vault
 #[tauri::command]
-pub fn unlock_from_keychain(state: State<'_, EncryptionState>) -> Result<bool, String> {
+pub fn unlock_from_keychain(state: State<'_, EncryptionState>, vault_path: String) -> Result<bool, String> {
+    let vault = expand_tilde(&vault_path);
+    Ok(unlock_session_from_keychain(&state.session(&vault), &vault)?)
+}
+
+/// Unlock a session with the keychain credentials stored for a vault,
+/// returning whether there were any
+pub fn unlock_session_from_keychain(session: &EncryptionSession, vault: &Path) -> Result<bool, EncryptionError> {
     // Try password first
-    if let Some(password) = load_password_from_keychain().map_err(|e| e.to_string())? {
-        state.session.set_password(password);
+    if let Some(password) = load_password_from_keychain(vault)? {
+        session.set_password(password);
         return Ok(true);
     }
 
     // Try identity path
-    if let Some(path) = load_identity_path_from_keychain().map_err(|e| e.to_string())? {
+    if let Some(path) = load_identity_path_from_keychain(vault)? {
         let expanded = expand_tilde(&path);
         if expanded.exists() {
-            state.session.set_identity_file(expanded.to_string_lossy().to_string());
+            session.set_identity_file(expanded.to_string_lossy().to_string());
             return Ok(true);
         }
     }
@@ -263,31 +495,34 @@ pub fn unlock_from_keychain(state: State<'_, EncryptionState>) -> Result<bool, S
     Ok(false)
 }
 
-/// Check if credentials are stored in keychain
+/// Check if credentials are stored in keychain for a vault
 #[tauri::command]
-pub fn has_keychain_credentials() -> bool {
-    has_stored_credentials()
+pub fn has_keychain_credentials(vault_path: String) -> bool {
+    has_stored_credentials(&expand_tilde(&vault_path))
 }
 
-/// Clear all stored credentials from keychain
+/// Clear the credentials stored in keychain for a vault
 #[tauri::command]
-pub fn clear_keychain_credentials() -> Result<(), String> {
-    delete_password_from_keychain().map_err(|e| e.to_string())?;
-    delete_identity_path_from_keychain().map_err(|e| e.to_string())?;
+pub fn clear_keychain_credentials(vault_path: String) -> Result<(), String> {
+    let vault = expand_tilde(&vault_path);
+    delete_password_from_keychain(&vault).map_err(|e| e.to_string())?;
+    delete_identity_path_from_keychain(&vault).map_err(|e| e.to_string())?;
     Ok(())
 }
 
-/// Lock session and optionally clear keychain
+/// Lock session and optionally clear the vault's keychain entries
 #[tauri::command]
 pub fn lock_encryption_session_with_clear(
     state: State<'_, EncryptionState>,
+    vault_path: String,
     clear_keychain: bool,
 ) -> Result<(), String> {
-    state.session.lock();
+    let vault = expand_tilde(&vault_path);
+    state.session(&vault).lock();
 
     if clear_keychain {
-        delete_password_from_keychain().map_err(|e| e.to_string())?;
-        delete_identity_path_from_keychain().map_err(|e| e.to_string())?;
+        delete_password_from_keychain(&vault).map_err(|e| e.to_string())?;
+        delete_identity_path_from_keychain(&vault).map_err(|e| e.to_string())?;
     }
 
     Ok(())
@@ -334,6 +569,7 @@ pub fn generate_identity_file(path: String) -> Result<String, String> {
 #[tauri::command]
 pub fn setup_recipients_encryption(
     state: State<'_, EncryptionState>,
+    vault_path: String,
     public_keys: Vec<String>,
     identity_paths: Vec<String>,
 ) -> Result<(), String> {
@@ -347,11 +583,12 @@ pub fn setup_recipients_encryption(
     }
 
     // Set public keys for encryption
-    state.session.set_public_keys(public_keys);
+    let session = state.session(&expand_tilde(&vault_path));
+    session.set_public_keys(public_keys);
 
     // Set identity paths for decryption
     if !expanded_paths.is_empty() {
-        state.session.set_recipient_identities(expanded_paths);
+        session.set_recipient_identities(expanded_paths);
     }
 
     Ok(())
@@ -361,6 +598,7 @@ pub fn setup_recipients_encryption(
 #[tauri::command]
 pub fn add_recipient_identity(
     state: State<'_, EncryptionState>,
+    vault_path: String,
     path: String,
 ) -> Result<String, String> {
     let expanded_path = expand_tilde(&path);
@@ -375,7 +613,7 @@ pub fn add_recipient_identity(
         .map_err(|e| e.to_string())?;
 
     // Add to session
-    state.session.add_recipient_identity(path_str);
+    state.session(&expand_tilde(&vault_path)).add_recipient_identity(path_str);
 
     Ok(public_key)
 }
@@ -384,6 +622,7 @@ pub fn add_recipient_identity(
 #[tauri::command]
 pub fn add_recipient_public_key(
     state: State<'_, EncryptionState>,
+    vault_path: String,
     public_key: String,
 ) -> Result<(), String> {
     // Validate the public key format
@@ -391,10 +630,11 @@ pub fn add_recipient_public_key(
         return Err("Invalid public key format. Expected age1...".to_string());
     }
 
-    let mut keys = state.session.get_public_keys();
+    let session = state.session(&expand_tilde(&vault_path));
+    let mut keys = session.get_public_keys();
     if !keys.contains(&public_key) {
         keys.push(public_key);
-        state.session.set_public_keys(keys);
+        session.set_public_keys(keys);
     }
 
     Ok(())
@@ -404,16 +644,40 @@ pub fn add_recipient_public_key(
 #[tauri::command]
 pub fn get_recipient_public_keys(
     state: State<'_, EncryptionState>,
+    vault_path: String,
 ) -> Vec<String> {
-    state.session.get_public_keys()
+    state.session(&expand_tilde(&vault_path)).get_public_keys()
 }
 
 /// Clear all recipient configuration
 #[tauri::command]
 pub fn clear_recipients(
     state: State<'_, EncryptionState>,
+    vault_path: String,
 ) -> Result<(), String> {
-    state.session.set_public_keys(Vec::new());
-    state.session.lock();
+    let session = state.session(&expand_tilde(&vault_path));
+    session.set_public_keys(Vec::new());
+    session.lock();
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn sessions_are_per_vault() {
+        let dir = tempfile::tempdir().unwrap();
+        let (a, b) = (dir.path().join("A"), dir.path().join("B"));
+        fs::create_dir_all(a.join(".notemaker")).unwrap();
+        fs::create_dir_all(b.join(".notemaker")).unwrap();
+
+        let state = EncryptionState::default();
+        state.session(&a).set_password("secret".to_string());
+
+        assert!(state.session(&a.join("Projects/Note.md")).is_unlocked());
+        assert!(!state.session(&b).is_unlocked());
+        assert!(!state.session(&b.join("Note.md")).is_unlocked());
+    }
+}
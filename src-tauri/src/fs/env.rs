@@ -0,0 +1,163 @@
+//! Environment variables passed to executed code blocks.
+//!
+//! Variables come from the vault's `.notemaker/env.yaml` and an optional
+//! `.env` file next to the notebook, which overrides the vault values.
+//! Values in `env.yaml` can reference a secret stored in the keychain:
+//!
+//! ```yaml
+//! API_URL: https://example.com
+//! API_TOKEN:
+//!   keychain: api-token
+//! ```
+
+use serde_yaml::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::commands::{find_vault_root, FsError};
+use super::encryption::{delete_env_secret, load_env_secret, save_env_secret};
+
+/// Vault-wide environment file
+const ENV_YAML: &str = "env.yaml";
+
+/// Resolve one `env.yaml` value to a string
+fn yaml_env_value(vault: &Path, name: &str, value: &Value) -> Result<String, FsError> {
+    match value {
+        Value::Null => Ok(String::new()),
+        Value::Bool(b) => Ok(b.to_string()),
+        Value::Number(n) => Ok(n.to_string()),
+        Value::String(s) => Ok(s.clone()),
+        Value::Mapping(map) => {
+            let secret = map
+                .get("keychain")
+                .and_then(Value::as_str)
+                .ok_or_else(|| FsError::InvalidPath(format!("{}: expected a value or `keychain: <name>`", name)))?;
+            load_env_secret(vault, secret)
+                .map_err(|e| FsError::Keychain(e.to_string()))?
+                .ok_or_else(|| FsError::Keychain(format!("No secret '{}' stored for {}", secret, name)))
+        }
+        _ => Err(FsError::InvalidPath(format!("{}: expected a value or `keychain: <name>`", name))),
+    }
+}
+
+/// Variables from a vault's `.notemaker/env.yaml`
+pub(crate) fn read_vault_env(vault: &Path) -> Result<BTreeMap<String, String>, FsError> {
+    let path = vault.join(".notemaker").join(ENV_YAML);
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+
+    let raw: BTreeMap<String, Value> = serde_yaml::from_str(&fs::read_to_string(&path)?)?;
+    raw.iter()
+        .map(|(name, value)| Ok((name.clone(), yaml_env_value(vault, name, value)?)))
+        .collect()
+}
+
+/// Parse `KEY=value` lines of a `.env` file
+///
+/// Blank lines and `#` comments are skipped, an `export ` prefix is allowed
+/// and values may be wrapped in single or double quotes.
+pub(crate) fn parse_dotenv(content: &str) -> BTreeMap<String, String> {
+    let mut vars = BTreeMap::new();
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let key = key.trim();
+        if key.is_empty() {
+            continue;
+        }
+
+        let value = value.trim();
+        let value = if let Some(inner) = value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+            inner.replace("\\n", "\n").replace("\\\"", "\"")
+        } else if let Some(inner) = value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')) {
+            inner.to_string()
+        } else {
+            // Unquoted values end at an inline comment
+            value.split(" #").next().unwrap_or("").trim_end().to_string()
+        };
+        vars.insert(key.to_string(), value);
+    }
+
+    vars
+}
+
+/// Environment for code run from a notebook or working directory
+///
+/// `notebook_path` takes precedence over `working_dir` for finding the vault
+/// and the `.env` file.
+pub(crate) fn execution_env(
+    notebook_path: Option<&Path>,
+    working_dir: &Path,
+) -> Result<BTreeMap<String, String>, FsError> {
+    let anchor = notebook_path.unwrap_or(working_dir);
+    let mut vars = match find_vault_root(anchor) {
+        Some(vault) => read_vault_env(&vault)?,
+        None => BTreeMap::new(),
+    };
+
+    let dotenv = match notebook_path {
+        Some(notebook) => notebook.parent().map(|dir| dir.join(".env")),
+        None => Some(working_dir.join(".env")),
+    };
+    if let Some(dotenv) = dotenv.filter(|p| p.is_file()) {
+        vars.extend(parse_dotenv(&fs::read_to_string(dotenv)?));
+    }
+
+    Ok(vars)
+}
+
+/// Store a secret that `env.yaml` can reference with `keychain: <name>`
+#[tauri::command]
+pub async fn set_execution_secret(vault_path: PathBuf, name: String, value: String) -> Result<(), FsError> {
+    save_env_secret(&vault_path, &name, &value).map_err(|e| FsError::Keychain(e.to_string()))
+}
+
+/// Remove a stored execution secret
+#[tauri::command]
+pub async fn delete_execution_secret(vault_path: PathBuf, name: String) -> Result<(), FsError> {
+    delete_env_secret(&vault_path, &name).map_err(|e| FsError::Keychain(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merges_vault_env_and_dotenv() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = dir.path();
+        fs::create_dir_all(vault.join(".notemaker")).unwrap();
+        fs::write(
+            vault.join(".notemaker").join(ENV_YAML),
+            "API_URL: https://example.com\nPORT: 8080\nDEBUG: false\n",
+        )
+        .unwrap();
+
+        let folder = vault.join("Projects");
+        let notebook = folder.join("Demo.md");
+        fs::create_dir_all(&notebook).unwrap();
+        fs::write(
+            folder.join(".env"),
+            "# local overrides\nexport DEBUG=true\nNAME=\"two words\"\nRAW='$HOME'\nEMPTY=\nTRAILING=value # note\n",
+        )
+        .unwrap();
+
+        let vars = execution_env(Some(&notebook), &notebook).unwrap();
+        assert_eq!(vars["API_URL"], "https://example.com");
+        assert_eq!(vars["PORT"], "8080");
+        assert_eq!(vars["DEBUG"], "true");
+        assert_eq!(vars["NAME"], "two words");
+        assert_eq!(vars["RAW"], "$HOME");
+        assert_eq!(vars["EMPTY"], "");
+        assert_eq!(vars["TRAILING"], "value");
+    }
+}
@@ -0,0 +1,105 @@
+//! Vault-wide ignore patterns.
+//!
+//! A `.notemakerignore` at the vault root uses gitignore syntax. Entries it
+//! matches are left out of the file tree, note counts, watcher events, the
+//! link index and exports, so build artifacts or scratch folders stay out of
+//! the way everywhere.
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use std::path::Path;
+
+use super::commands::FsError;
+
+/// Name of the ignore file at the vault root
+pub const IGNORE_FILE: &str = ".notemakerignore";
+
+/// Compiled `.notemakerignore` patterns of one vault
+#[derive(Clone)]
+pub(crate) struct VaultIgnore {
+    matcher: Gitignore,
+}
+
+impl Default for VaultIgnore {
+    /// No patterns
+    fn default() -> Self {
+        Self {
+            matcher: Gitignore::empty(),
+        }
+    }
+}
+
+impl VaultIgnore {
+    /// Patterns of the vault at `vault_path`; none when the file is missing
+    ///
+    /// A file that can't be read or has invalid patterns is an error.
+    pub(crate) fn load(vault_path: &Path) -> Result<Self, FsError> {
+        let file = vault_path.join(IGNORE_FILE);
+        if !file.is_file() {
+            return Ok(Self::default());
+        }
+
+        let invalid = |err: ignore::Error| FsError::InvalidPath(format!("{}: {}", file.display(), err));
+        let mut builder = GitignoreBuilder::new(vault_path);
+        if let Some(err) = builder.add(&file) {
+            return Err(invalid(err));
+        }
+        Ok(Self {
+            matcher: builder.build().map_err(invalid)?,
+        })
+    }
+
+    /// Whether an entry, or any folder containing it, is ignored
+    pub(crate) fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        if self.matcher.is_empty() || !path.starts_with(self.matcher.path()) || path == self.matcher.path() {
+            return false;
+        }
+        self.matcher.matched_path_or_any_parents(path, is_dir).is_ignore()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::commands::{list_directory, walk_notes};
+    use std::fs;
+
+    #[test]
+    fn ignores_matching_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = dir.path();
+        fs::create_dir(vault.join(".notemaker")).unwrap();
+        fs::create_dir_all(vault.join("Scratch/deep")).unwrap();
+        fs::create_dir_all(vault.join("Projects/node_modules")).unwrap();
+        fs::write(vault.join(IGNORE_FILE), "Scratch/\nnode_modules\n*.draft.md\n!keep.draft.md\n").unwrap();
+        for note in [
+            "Scratch/deep/a.md",
+            "Projects/node_modules/b.md",
+            "Projects/plan.md",
+            "Projects/x.draft.md",
+            "keep.draft.md",
+        ] {
+            fs::write(vault.join(note), "# Note\n").unwrap();
+        }
+
+        let ignore = VaultIgnore::load(vault).unwrap();
+        assert!(ignore.is_ignored(&vault.join("Scratch/deep/a.md"), false));
+        assert!(!ignore.is_ignored(&vault.join("keep.draft.md"), false));
+
+        let mut notes = Vec::new();
+        walk_notes(vault, &mut notes);
+        let mut names: Vec<String> = notes
+            .iter()
+            .map(|p| p.strip_prefix(vault).unwrap().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["Projects/plan.md", "keep.draft.md"]);
+
+        let tree = tauri::async_runtime::block_on(list_directory(vault.join("Projects"), None, None, None)).unwrap();
+        let listed: Vec<&str> = tree.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(listed, vec!["plan.md"]);
+
+        fs::write(vault.join(IGNORE_FILE), "Scratch/[z-a]\n").unwrap();
+        assert!(VaultIgnore::load(vault).is_err());
+        assert!(tauri::async_runtime::block_on(list_directory(vault.to_path_buf(), None, None, None)).is_err());
+    }
+}
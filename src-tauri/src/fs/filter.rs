@@ -0,0 +1,133 @@
+//! Filtering the file tree on the backend.
+//!
+//! Big vaults are too slow to filter on the frontend over a full listing, so
+//! `filter_vault` walks the tree once and returns only the matching entries
+//! together with the folders leading to them.
+
+use glob::{MatchOptions, Pattern};
+use std::path::{Path, PathBuf};
+
+use super::commands::{is_kanban, is_notebook, list_directory_recursive, read_markdown_text, FsError, TreeWalk};
+use super::frontmatter::{parse_frontmatter, string_list};
+use super::tags::tag_matches;
+use super::types::{EntryKind, FileEntry, FilterCriteria};
+
+/// Criteria with the name pattern compiled
+struct Filter<'a> {
+    criteria: &'a FilterCriteria,
+    name: Option<Pattern>,
+}
+
+impl Filter<'_> {
+    fn matches(&self, entry: &FileEntry) -> bool {
+        let kind = if is_notebook(&entry.path) {
+            EntryKind::Notebook
+        } else if is_kanban(&entry.path) {
+            EntryKind::Kanban
+        } else {
+            EntryKind::Note
+        };
+        let criteria = self.criteria;
+        let options = MatchOptions {
+            case_sensitive: false,
+            ..MatchOptions::new()
+        };
+
+        (criteria.kinds.is_empty() || criteria.kinds.contains(&kind))
+            && criteria.modified_after.is_none_or(|after| entry.modified >= after)
+            && criteria.modified_before.is_none_or(|before| entry.modified <= before)
+            && self.name.as_ref().is_none_or(|p| p.matches_with(&entry.name, options))
+            && (criteria.labels.is_empty() || has_labels(&entry.path, &criteria.labels))
+    }
+
+    /// Matching entries of a listing; folders are kept when anything below
+    /// them matches
+    fn prune(&self, entries: Vec<FileEntry>) -> Vec<FileEntry> {
+        entries
+            .into_iter()
+            .filter_map(|mut entry| {
+                let is_folder = entry.is_directory && !is_notebook(&entry.path) && !is_kanban(&entry.path);
+                if !is_folder {
+                    return self.matches(&entry).then_some(entry);
+                }
+                let children = self.prune(entry.children.take().unwrap_or_default());
+                (!children.is_empty()).then_some(FileEntry {
+                    child_count: Some(children.len() as u64),
+                    children: Some(children),
+                    ..entry
+                })
+            })
+            .collect()
+    }
+}
+
+/// Whether a note or notebook carries all of `labels`
+fn has_labels(path: &Path, labels: &[String]) -> bool {
+    let Some(frontmatter) = read_markdown_text(path).ok().and_then(|c| parse_frontmatter(&c)) else {
+        return false;
+    };
+    let present = string_list(&frontmatter, "labels");
+    labels
+        .iter()
+        .all(|label| present.iter().any(|p| tag_matches(p, label)))
+}
+
+/// File tree of a vault pruned to the entries matching `criteria`
+#[tauri::command]
+pub async fn filter_vault(vault_path: PathBuf, criteria: FilterCriteria) -> Result<Vec<FileEntry>, FsError> {
+    let name = criteria
+        .name
+        .as_deref()
+        .map(Pattern::new)
+        .transpose()
+        .map_err(|e| FsError::InvalidPath(format!("Invalid name pattern: {}", e)))?;
+    let mut walk = TreeWalk::for_path(&vault_path)?.without_archive(true);
+    let tree = list_directory_recursive(&vault_path, None, &mut walk)?;
+    Ok(Filter {
+        criteria: &criteria,
+        name,
+    }
+    .prune(tree))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn prunes_tree_to_matches() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = dir.path();
+        fs::create_dir_all(vault.join("Work/Empty")).unwrap();
+        fs::create_dir_all(vault.join("Board.kanban")).unwrap();
+        fs::write(vault.join("Work/Plan.md"), "---\nlabels: [q3, Work]\n---\n").unwrap();
+        fs::write(vault.join("Work/Notes.md"), "---\nlabels: [q3]\n---\n").unwrap();
+        fs::write(vault.join("Todo.md"), "").unwrap();
+
+        let filter = |criteria: FilterCriteria| {
+            tauri::async_runtime::block_on(filter_vault(vault.to_path_buf(), criteria)).unwrap()
+        };
+
+        let tree = filter(FilterCriteria {
+            labels: vec!["work".to_string(), "#q3".to_string()],
+            ..Default::default()
+        });
+        assert_eq!(tree.len(), 1);
+        assert_eq!(tree[0].name, "Work");
+        let children = tree[0].children.as_ref().unwrap();
+        assert_eq!(children.iter().map(|e| e.name.as_str()).collect::<Vec<_>>(), vec!["Plan.md"]);
+
+        let tree = filter(FilterCriteria {
+            kinds: vec![EntryKind::Kanban],
+            ..Default::default()
+        });
+        assert_eq!(tree.iter().map(|e| e.name.as_str()).collect::<Vec<_>>(), vec!["Board.kanban"]);
+
+        let tree = filter(FilterCriteria {
+            name: Some("to*".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(tree.iter().map(|e| e.name.as_str()).collect::<Vec<_>>(), vec!["Todo.md"]);
+    }
+}
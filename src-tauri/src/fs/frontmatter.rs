@@ -0,0 +1,178 @@
+//! YAML frontmatter helpers shared by the link index and note commands.
+
+use serde_yaml::{Mapping, Value};
+use std::ops::Range;
+
+/// Split a note into its raw frontmatter block (without the `---` fences) and body.
+///
+/// Returns `None` for the frontmatter when the note does not start with a fence
+/// or the closing fence is missing.
+pub fn split_frontmatter(content: &str) -> (Option<&str>, &str) {
+    let rest = match content
+        .strip_prefix("---\n")
+        .or_else(|| content.strip_prefix("---\r\n"))
+    {
+        Some(rest) => rest,
+        None => return (None, content),
+    };
+
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == "---" {
+            let yaml = &rest[..offset];
+            let body = &rest[offset + line.len()..];
+            return (Some(yaml), body);
+        }
+        offset += line.len();
+    }
+
+    (None, content)
+}
+
+/// Parse the frontmatter of a note into a YAML mapping
+pub fn parse_frontmatter(content: &str) -> Option<Mapping> {
+    let (yaml, _) = split_frontmatter(content);
+    match serde_yaml::from_str::<Value>(yaml?) {
+        Ok(Value::Mapping(map)) => Some(map),
+        _ => None,
+    }
+}
+
+/// Replace the frontmatter of a note, adding one if it has none
+pub fn replace_frontmatter(content: &str, map: &Mapping) -> Result<String, serde_yaml::Error> {
+    let yaml = serde_yaml::to_string(map)?;
+    Ok(match split_frontmatter(content) {
+        (Some(_), body) => format!("---\n{}---\n{}", yaml, body),
+        (None, _) => format!("---\n{}---\n\n{}", yaml, content),
+    })
+}
+
+/// Name of the top-level key a frontmatter line starts, if any
+fn top_level_key(line: &str) -> Option<&str> {
+    if line.starts_with([' ', '\t', '#', '-']) {
+        return None;
+    }
+    let (key, rest) = line.split_once(':')?;
+    if !rest.is_empty() && !rest.starts_with([' ', '\t']) {
+        return None;
+    }
+    Some(key.trim().trim_matches(|c| c == '"' || c == '\''))
+}
+
+/// Lines of a top-level key and its nested values or list items
+fn key_lines(lines: &[String], key: &str) -> Option<Range<usize>> {
+    let start = lines.iter().position(|l| top_level_key(l) == Some(key))?;
+    let mut end = start + 1;
+    for (i, line) in lines.iter().enumerate().skip(start + 1) {
+        if line.trim().is_empty() {
+            continue;
+        }
+        if !line.starts_with([' ', '\t', '-']) {
+            break;
+        }
+        end = i + 1;
+    }
+    Some(start..end)
+}
+
+/// Set or remove (with a null value) top-level frontmatter fields, adding a
+/// frontmatter block when the note has none
+///
+/// Only the lines of the patched keys are rewritten, so comments, key order
+/// and the formatting of other fields survive.
+pub fn patch_frontmatter(content: &str, patch: &Mapping) -> Result<String, serde_yaml::Error> {
+    let (yaml, body) = split_frontmatter(content);
+    let mut lines: Vec<String> = yaml.unwrap_or_default().lines().map(str::to_string).collect();
+
+    for (key, value) in patch {
+        let Value::String(name) = key else {
+            continue;
+        };
+        let replacement: Vec<String> = if value.is_null() {
+            Vec::new()
+        } else {
+            let mut entry = Mapping::new();
+            entry.insert(key.clone(), value.clone());
+            serde_yaml::to_string(&entry)?.lines().map(str::to_string).collect()
+        };
+        match key_lines(&lines, name) {
+            Some(range) => {
+                lines.splice(range, replacement);
+            }
+            None => lines.extend(replacement),
+        }
+    }
+
+    let patched: String = lines.iter().map(|l| format!("{}\n", l)).collect();
+    Ok(match yaml {
+        Some(_) => format!("---\n{}---\n{}", patched, body),
+        None if lines.is_empty() => content.to_string(),
+        None => format!("---\n{}---\n\n{}", patched, content),
+    })
+}
+
+/// Rename a top-level frontmatter key in place, keeping its value, position
+/// and formatting. `None` when the note has no such key.
+pub fn rename_frontmatter_key(content: &str, from: &str, to: &str) -> Result<Option<String>, serde_yaml::Error> {
+    let (Some(yaml), body) = split_frontmatter(content) else {
+        return Ok(None);
+    };
+    let mut lines: Vec<String> = yaml.lines().map(str::to_string).collect();
+    let Some(range) = key_lines(&lines, from) else {
+        return Ok(None);
+    };
+    let Some((_, rest)) = lines[range.start].split_once(':') else {
+        return Ok(None);
+    };
+
+    let key = serde_yaml::to_string(&Value::String(to.to_string()))?;
+    lines[range.start] = format!("{}:{}", key.trim_end(), rest);
+    let renamed: String = lines.iter().map(|l| format!("{}\n", l)).collect();
+    Ok(Some(format!("---\n{}---\n{}", renamed, body)))
+}
+
+/// Read a string-or-list field as a list of non-empty strings
+pub fn string_list(map: &Mapping, key: &str) -> Vec<String> {
+    match map.get(key) {
+        Some(Value::String(s)) => s
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect(),
+        Some(Value::Sequence(seq)) => seq
+            .iter()
+            .filter_map(|v| match v {
+                Value::String(s) => Some(s.trim().to_string()),
+                Value::Number(n) => Some(n.to_string()),
+                _ => None,
+            })
+            .filter(|s| !s.is_empty())
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Read a scalar field as a string
+pub fn string_field(map: &Mapping, key: &str) -> Option<String> {
+    match map.get(key) {
+        Some(Value::String(s)) => Some(s.clone()),
+        Some(Value::Number(n)) => Some(n.to_string()),
+        Some(Value::Bool(b)) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+/// Aliases declared in a note's frontmatter (`aliases:` or the singular `alias:`)
+pub fn extract_aliases(content: &str) -> Vec<String> {
+    let Some(map) = parse_frontmatter(content) else {
+        return Vec::new();
+    };
+
+    let mut aliases = string_list(&map, "aliases");
+    for alias in string_list(&map, "alias") {
+        if !aliases.contains(&alias) {
+            aliases.push(alias);
+        }
+    }
+    aliases
+}
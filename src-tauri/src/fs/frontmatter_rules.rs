@@ -0,0 +1,217 @@
+//! Normalizing frontmatter across a whole vault.
+//!
+//! Vaults imported from other tools use their own field names and date
+//! formats. `migrate_frontmatter` applies a list of [`FrontmatterRule`]s to
+//! every note in order, touching only the affected fields, and can report
+//! what it would change without writing anything.
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use serde_yaml::{Mapping, Value};
+use std::fmt::Write;
+use std::fs;
+use std::path::PathBuf;
+
+use super::bulk::frontmatter_file;
+use super::commands::{walk_notes, write_atomic, FsError};
+use super::encryption::is_encrypted;
+use super::frontmatter::{
+    parse_frontmatter, patch_frontmatter, rename_frontmatter_key, split_frontmatter, string_field, string_list,
+};
+use super::types::{BulkFailure, FrontmatterChange, FrontmatterMigration, FrontmatterRule};
+
+/// Date formats tried when a rule doesn't name one
+const DATE_FORMATS: [&str; 7] = ["%Y-%m-%d", "%Y/%m/%d", "%d.%m.%Y", "%m/%d/%Y", "%B %d, %Y", "%b %d, %Y", "%d %B %Y"];
+
+/// Date and time formats tried when a rule doesn't name one
+const DATE_TIME_FORMATS: [&str; 3] = ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M"];
+
+/// Parse a date, with or without time of day
+fn parse_date_value(value: &str, format: Option<&str>) -> Option<NaiveDateTime> {
+    let value = value.trim();
+    let (dates, date_times): (Vec<&str>, Vec<&str>) = match format {
+        Some(format) => (vec![format], vec![format]),
+        None => {
+            if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+                return Some(time.naive_local());
+            }
+            (DATE_FORMATS.to_vec(), DATE_TIME_FORMATS.to_vec())
+        }
+    };
+    date_times
+        .iter()
+        .find_map(|f| NaiveDateTime::parse_from_str(value, f).ok())
+        .or_else(|| {
+            dates
+                .iter()
+                .find_map(|f| NaiveDate::parse_from_str(value, f).ok())
+                .and_then(|d| d.and_hms_opt(0, 0, 0))
+        })
+}
+
+/// Apply one rule to a note's content, describing the change if it made one
+fn apply_rule(content: &str, rule: &FrontmatterRule) -> Result<Option<(String, String)>, FsError> {
+    let map = parse_frontmatter(content).unwrap_or_default();
+    match rule {
+        FrontmatterRule::RenameField { from, to } => {
+            if from == to || map.contains_key(to.as_str()) {
+                return Ok(None);
+            }
+            Ok(rename_frontmatter_key(content, from, to)?
+                .map(|renamed| (renamed, format!("Renamed `{}` to `{}`", from, to))))
+        }
+        FrontmatterRule::ConvertDate {
+            field,
+            from_format,
+            to_format,
+        } => {
+            let Some(value) = string_field(&map, field) else {
+                return Ok(None);
+            };
+            let date = parse_date_value(&value, from_format.as_deref())
+                .ok_or_else(|| FsError::InvalidPath(format!("Unrecognized date in `{}`: {}", field, value)))?;
+            let mut converted = String::new();
+            write!(converted, "{}", date.format(to_format))
+                .map_err(|_| FsError::InvalidPath(format!("Invalid date format: {}", to_format)))?;
+            if converted == value {
+                return Ok(None);
+            }
+
+            let mut patch = Mapping::new();
+            patch.insert(Value::String(field.clone()), Value::String(converted.clone()));
+            let patched = patch_frontmatter(content, &patch)?;
+            Ok(Some((patched, format!("Changed `{}` from {} to {}", field, value, converted))))
+        }
+        FrontmatterRule::AddLabel { label } => {
+            let label = label.trim().trim_start_matches('#');
+            let mut labels = string_list(&map, "labels");
+            if label.is_empty() || labels.iter().any(|l| l.eq_ignore_ascii_case(label)) {
+                return Ok(None);
+            }
+            labels.push(label.to_string());
+
+            let mut patch = Mapping::new();
+            patch.insert(
+                Value::String("labels".to_string()),
+                Value::Sequence(labels.into_iter().map(Value::String).collect()),
+            );
+            let patched = patch_frontmatter(content, &patch)?;
+            Ok(Some((patched, format!("Added label `{}`", label))))
+        }
+    }
+}
+
+/// Apply all rules to a note's content in order
+fn migrate_content(content: &str, rules: &[FrontmatterRule]) -> Result<(String, Vec<String>), FsError> {
+    if split_frontmatter(content).0.is_some() && parse_frontmatter(content).is_none() {
+        return Err(FsError::InvalidPath("Invalid frontmatter".to_string()));
+    }
+    let mut content = content.to_string();
+    let mut changes = Vec::new();
+    for rule in rules {
+        if let Some((migrated, change)) = apply_rule(&content, rule)? {
+            content = migrated;
+            changes.push(change);
+        }
+    }
+    Ok((content, changes))
+}
+
+/// Apply frontmatter rules to every note of a vault
+///
+/// With `dry_run` the report lists the changes without writing them.
+#[tauri::command]
+pub async fn migrate_frontmatter(
+    vault_path: PathBuf,
+    rules: Vec<FrontmatterRule>,
+    dry_run: bool,
+) -> Result<FrontmatterMigration, FsError> {
+    if !vault_path.is_dir() {
+        return Err(FsError::NotFound(vault_path.display().to_string()));
+    }
+    let mut paths = Vec::new();
+    walk_notes(&vault_path, &mut paths);
+    paths.sort();
+
+    let mut report = FrontmatterMigration {
+        dry_run,
+        ..Default::default()
+    };
+    for path in paths {
+        let migrated = frontmatter_file(&path).and_then(|file| {
+            let content = fs::read_to_string(&file)?;
+            if is_encrypted(&content) {
+                return Err(FsError::Encrypted(path.display().to_string()));
+            }
+            let (migrated, changes) = migrate_content(&content, &rules)?;
+            if !dry_run && migrated != content {
+                write_atomic(&file, migrated)?;
+            }
+            Ok(changes)
+        });
+        match migrated {
+            Ok(changes) if changes.is_empty() => report.unchanged += 1,
+            Ok(changes) => report.changed.push(FrontmatterChange { path, changes }),
+            Err(e) => report.failed.push(BulkFailure {
+                path,
+                error: e.to_string(),
+            }),
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn applies_rules_in_order() {
+        let rules: Vec<FrontmatterRule> = serde_json::from_str(
+            r#"[
+                {"type": "rename_field", "from": "tags", "to": "labels"},
+                {"type": "convert_date", "field": "created", "to_format": "%Y-%m-%d"},
+                {"type": "add_label", "label": "imported"}
+            ]"#,
+        )
+        .unwrap();
+
+        let content = "---\ntitle: Trip # draft\ntags:\n  - travel\ncreated: 24.12.2023\n---\nBody\n";
+        let (migrated, changes) = migrate_content(content, &rules).unwrap();
+        assert_eq!(
+            migrated,
+            "---\ntitle: Trip # draft\nlabels:\n- travel\n- imported\ncreated: 2023-12-24\n---\nBody\n"
+        );
+        assert_eq!(changes.len(), 3);
+
+        let (again, changes) = migrate_content(&migrated, &rules).unwrap();
+        assert_eq!(again, migrated);
+        assert!(changes.is_empty());
+
+        assert!(migrate_content("---\ncreated: someday\n---\n", &rules).is_err());
+        assert_eq!(
+            parse_date_value("March 5, 2024", None),
+            NaiveDate::from_ymd_opt(2024, 3, 5).unwrap().and_hms_opt(0, 0, 0)
+        );
+    }
+
+    #[test]
+    fn dry_run_leaves_notes_alone() {
+        let dir = tempfile::tempdir().unwrap();
+        let note = dir.path().join("Note.md");
+        fs::write(&note, "---\nauthor: me\n---\n").unwrap();
+        fs::write(dir.path().join("Other.md"), "# Other\n").unwrap();
+        let rules = vec![FrontmatterRule::RenameField {
+            from: "author".to_string(),
+            to: "owner".to_string(),
+        }];
+
+        let report =
+            tauri::async_runtime::block_on(migrate_frontmatter(dir.path().to_path_buf(), rules.clone(), true)).unwrap();
+        assert_eq!(report.changed.len(), 1);
+        assert_eq!(report.unchanged, 1);
+        assert_eq!(fs::read_to_string(&note).unwrap(), "---\nauthor: me\n---\n");
+
+        tauri::async_runtime::block_on(migrate_frontmatter(dir.path().to_path_buf(), rules, false)).unwrap();
+        assert_eq!(fs::read_to_string(&note).unwrap(), "---\nowner: me\n---\n");
+    }
+}
@@ -0,0 +1,249 @@
+//! HTTP request blocks in the style of `.http` / REST Client files.
+//!
+//! ```text
+//! @base = https://api.example.com
+//!
+//! POST {{base}}/items
+//! Content-Type: application/json
+//! Authorization: Bearer {{API_TOKEN}}
+//!
+//! {"name": "pen"}
+//!
+//! ###
+//! GET {{base}}/items
+//! ```
+//!
+//! `{{name}}` is replaced by `@name = value` definitions of the block or by
+//! the execution environment (`env.yaml` and `.env`). Requests are separated
+//! by `###`.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use super::commands::FsError;
+use super::env::execution_env;
+use super::runtime::resolve_working_dir;
+use super::types::HttpResponse;
+
+/// Time allowed for each request
+const HTTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A request parsed from a block
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct HttpRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Option<String>,
+}
+
+/// Replace `{{name}}` placeholders; unknown names are left as written
+fn substitute(text: &str, vars: &BTreeMap<String, String>) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        let name = rest[start + 2..start + 2 + len].trim();
+        result.push_str(&rest[..start]);
+        match vars.get(name) {
+            Some(value) => result.push_str(value),
+            None => result.push_str(&rest[start..start + len + 4]),
+        }
+        rest = &rest[start + len + 4..];
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Parse the requests of a block, with `vars` as the initial variables
+pub(crate) fn parse_http_block(
+    content: &str,
+    mut vars: BTreeMap<String, String>,
+) -> Result<Vec<HttpRequest>, FsError> {
+    let mut requests = Vec::new();
+
+    let mut sections = vec![Vec::new()];
+    for line in content.lines() {
+        if line.trim_start().starts_with("###") {
+            sections.push(Vec::new());
+        } else if let Some(section) = sections.last_mut() {
+            section.push(line);
+        }
+    }
+
+    for section in sections {
+        let mut lines = section.into_iter();
+        let mut request_line = None;
+
+        // Variables, comments and blank lines before the request line
+        for line in lines.by_ref() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') || trimmed.starts_with("//") {
+                continue;
+            }
+            if let Some(definition) = trimmed.strip_prefix('@') {
+                if let Some((name, value)) = definition.split_once('=') {
+                    let value = substitute(value.trim(), &vars);
+                    vars.insert(name.trim().to_string(), value);
+                    continue;
+                }
+            }
+            request_line = Some(substitute(trimmed, &vars));
+            break;
+        }
+        let Some(request_line) = request_line else {
+            continue;
+        };
+
+        let mut parts = request_line.split_whitespace();
+        let first = parts.next().unwrap_or_default();
+        let (method, url) = if first.contains("://") || first.starts_with('/') {
+            ("GET".to_string(), first.to_string())
+        } else {
+            let url = parts
+                .next()
+                .ok_or_else(|| FsError::InvalidPath(format!("Missing URL in `{}`", request_line)))?;
+            (first.to_uppercase(), url.to_string())
+        };
+
+        let mut headers = Vec::new();
+        for line in lines.by_ref() {
+            if line.trim().is_empty() {
+                break;
+            }
+            let (name, value) = line
+                .split_once(':')
+                .ok_or_else(|| FsError::InvalidPath(format!("Invalid header `{}`", line.trim())))?;
+            headers.push((name.trim().to_string(), substitute(value.trim(), &vars)));
+        }
+
+        let body: Vec<&str> = lines.collect();
+        let body = substitute(body.join("\n").trim(), &vars);
+        requests.push(HttpRequest {
+            method,
+            url,
+            headers,
+            body: (!body.is_empty()).then_some(body),
+        });
+    }
+
+    if requests.is_empty() {
+        return Err(FsError::InvalidPath("No request found in the block".to_string()));
+    }
+    Ok(requests)
+}
+
+/// Pretty-print JSON bodies, leave everything else as received
+fn format_body(body: &str) -> String {
+    serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .and_then(|json| serde_json::to_string_pretty(&json).ok())
+        .unwrap_or_else(|| body.to_string())
+}
+
+/// Perform the requests one after another
+pub(crate) async fn send_requests(requests: Vec<HttpRequest>) -> Result<Vec<HttpResponse>, FsError> {
+    let client = reqwest::Client::builder()
+        .timeout(HTTP_TIMEOUT)
+        .build()
+        .map_err(|e| FsError::Http(e.to_string()))?;
+
+    let mut responses = Vec::new();
+    for request in requests {
+        let method = reqwest::Method::from_bytes(request.method.as_bytes())
+            .map_err(|_| FsError::InvalidPath(format!("Invalid method {}", request.method)))?;
+        let mut builder = client.request(method, &request.url);
+        for (name, value) in &request.headers {
+            builder = builder.header(name, value);
+        }
+        if let Some(body) = request.body {
+            builder = builder.body(body);
+        }
+
+        let started = Instant::now();
+        let response = builder.send().await.map_err(|e| FsError::Http(e.to_string()))?;
+        let status = response.status();
+        let headers = response
+            .headers()
+            .iter()
+            .map(|(name, value)| (name.to_string(), String::from_utf8_lossy(value.as_bytes()).to_string()))
+            .collect();
+        let body = response.text().await.map_err(|e| FsError::Http(e.to_string()))?;
+
+        responses.push(HttpResponse {
+            method: request.method,
+            url: request.url,
+            status: status.as_u16(),
+            status_text: status.canonical_reason().unwrap_or_default().to_string(),
+            headers,
+            body: format_body(&body),
+            elapsed_ms: started.elapsed().as_millis() as u64,
+        });
+    }
+    Ok(responses)
+}
+
+/// Execute an HTTP block
+///
+/// Variables from the vault's `env.yaml` and the `.env` next to
+/// `notebook_path` can be used as `{{NAME}}`.
+#[tauri::command]
+pub async fn execute_http_block(
+    code: String,
+    working_dir: Option<PathBuf>,
+    notebook_path: Option<PathBuf>,
+) -> Result<Vec<HttpResponse>, FsError> {
+    let work_dir = resolve_working_dir(working_dir, notebook_path.as_deref(), None)?;
+    let vars = execution_env(notebook_path.as_deref(), &work_dir)?;
+    send_requests(parse_http_block(&code, vars)?).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    #[test]
+    fn parses_requests_and_variables() {
+        let vars = BTreeMap::from([("TOKEN".to_string(), "abc".to_string())]);
+        let block = "# create\n@base = https://api.test\nPOST {{base}}/items HTTP/1.1\nAuthorization: Bearer {{TOKEN}}\n\n{\"name\": \"{{missing}}\"}\n\n###\n{{base}}/items\n";
+        let requests = parse_http_block(block, vars).unwrap();
+
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].method, "POST");
+        assert_eq!(requests[0].url, "https://api.test/items");
+        assert_eq!(requests[0].headers, vec![("Authorization".to_string(), "Bearer abc".to_string())]);
+        assert_eq!(requests[0].body.as_deref(), Some("{\"name\": \"{{missing}}\"}"));
+        assert_eq!(requests[1].method, "GET");
+        assert_eq!(requests[1].body, None);
+    }
+
+    #[tokio::test]
+    async fn sends_request_and_formats_json() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buf = [0u8; 1024];
+            let _ = stream.read(&mut buf);
+            let body = "{\"ok\":true}";
+            write!(
+                stream,
+                "HTTP/1.1 201 Created\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                body.len(),
+                body
+            )
+            .unwrap();
+        });
+
+        let requests = parse_http_block(&format!("PUT http://127.0.0.1:{}/x\n\nhello", port), BTreeMap::new()).unwrap();
+        let responses = send_requests(requests).await.unwrap();
+        assert_eq!(responses[0].status, 201);
+        assert_eq!(responses[0].status_text, "Created");
+        assert_eq!(responses[0].body, "{\n  \"ok\": true\n}");
+    }
+}
@@ -0,0 +1,633 @@
+//! Kanban labels, assignees, task filtering and flow statistics.
+//!
+//! Labels are defined once per board in its settings, with a color, and
+//! tasks refer to them by name. Renaming or deleting a label updates every
+//! task carrying it. Tasks are assigned to people from the board's people
+//! list, and removing a person unassigns their tasks.
+//!
+//! Statistics are derived from the status history recorded on each task.
+//!
+//! Tasks can be moved or copied to another board together with their
+//! description, activity log and `<id>.assets` attachments.
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, State};
+
+use super::activity::{activity_author, activity_entry, activity_path, append_task_activity, read_task_activity};
+use super::commands::{
+    copy_dir, find_vault_root, generate_task_id, get_task_file_path, note_assets_dir, read_kanban_index,
+    read_task_description, write_kanban_index_with, write_task_description, FsError,
+};
+use super::encryption::EncryptionSession;
+use super::encryption_commands::EncryptionState;
+use super::types::{
+    ActivityKind, DateRange, DurationStats, FlowPoint, KanbanIndex, KanbanLabel, KanbanStats, KanbanTask,
+    KanbanTaskWithContent, StatusChange, TaskActivity, TaskFilter, WebhookEvent, WeeklyThroughput,
+};
+use super::webhooks::dispatch_webhooks;
+
+/// Days of cumulative flow returned at most
+const MAX_FLOW_DAYS: i64 = 366;
+
+/// Date part of an ISO 8601 date or timestamp
+pub(crate) fn parse_date(value: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(value.get(..10)?, "%Y-%m-%d").ok()
+}
+
+/// Time of an ISO 8601 timestamp; plain dates count as midnight UTC
+fn parse_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|t| t.with_timezone(&Utc))
+        .ok()
+        .or_else(|| parse_date(value)?.and_hms_opt(0, 0, 0).map(|t| t.and_utc()))
+}
+
+/// Whether a task matches all criteria of the filter
+pub(crate) fn task_matches(task: &KanbanTaskWithContent, filter: &TaskFilter) -> bool {
+    if !filter.labels.iter().all(|label| task.labels.contains(label)) {
+        return false;
+    }
+    if !filter.priorities.is_empty()
+        && !task.priority.as_ref().is_some_and(|p| filter.priorities.contains(p))
+    {
+        return false;
+    }
+    if let Some(assignee) = filter.assignee.as_deref() {
+        if task.assignee.as_deref().unwrap_or_default() != assignee {
+            return false;
+        }
+    }
+
+    let from = filter.due_from.as_deref().and_then(parse_date);
+    let to = filter.due_to.as_deref().and_then(parse_date);
+    if from.is_some() || to.is_some() {
+        let Some(due) = task.due.as_deref().and_then(parse_date) else {
+            return false;
+        };
+        if from.is_some_and(|from| due < from) || to.is_some_and(|to| due > to) {
+            return false;
+        }
+    }
+
+    match filter.text.as_deref().map(str::trim).filter(|t| !t.is_empty()) {
+        Some(text) => {
+            let text = text.to_lowercase();
+            task.title.to_lowercase().contains(&text) || task.description.to_lowercase().contains(&text)
+        }
+        None => true,
+    }
+}
+
+/// Tasks of a board matching a filter, in board order
+#[tauri::command]
+pub async fn filter_kanban_tasks(
+    kanban_path: PathBuf,
+    filter: TaskFilter,
+    state: State<'_, EncryptionState>,
+) -> Result<Vec<KanbanTaskWithContent>, FsError> {
+    let session = state.session(&kanban_path);
+    let session = Some(session.as_ref());
+    let index = read_kanban_index(&kanban_path, session)?;
+
+    let mut tasks = Vec::new();
+    for task in &index.tasks {
+        let description = read_task_description(&kanban_path, index.encrypted, &task.id, session)?;
+        let task = KanbanTaskWithContent::from_task(task, description);
+        if task_matches(&task, &filter) {
+            tasks.push(task);
+        }
+    }
+    Ok(tasks)
+}
+
+/// Add a label to a board
+#[tauri::command]
+pub async fn create_kanban_label(
+    kanban_path: PathBuf,
+    name: String,
+    color: String,
+    state: State<'_, EncryptionState>,
+) -> Result<Vec<KanbanLabel>, FsError> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err(FsError::InvalidPath("Label name cannot be empty".to_string()));
+    }
+
+    let session = state.session(&kanban_path);
+    let session = Some(session.as_ref());
+    let mut index = read_kanban_index(&kanban_path, session)?;
+    if index.settings.labels.iter().any(|l| l.name == name) {
+        return Err(FsError::InvalidPath(format!("Label already exists: {}", name)));
+    }
+    index.settings.labels.push(KanbanLabel { name, color });
+    write_kanban_index_with(&kanban_path, &index, session)?;
+    Ok(index.settings.labels)
+}
+
+/// Rename a label or change its color; tasks follow a rename
+#[tauri::command]
+pub async fn update_kanban_label(
+    kanban_path: PathBuf,
+    name: String,
+    new_name: Option<String>,
+    color: Option<String>,
+    state: State<'_, EncryptionState>,
+) -> Result<Vec<KanbanLabel>, FsError> {
+    let session = state.session(&kanban_path);
+    let session = Some(session.as_ref());
+    let mut index = read_kanban_index(&kanban_path, session)?;
+    let new_name = new_name.map(|n| n.trim().to_string()).filter(|n| !n.is_empty() && *n != name);
+    if let Some(new_name) = &new_name {
+        if index.settings.labels.iter().any(|l| &l.name == new_name) {
+            return Err(FsError::InvalidPath(format!("Label already exists: {}", new_name)));
+        }
+    }
+
+    let label = index
+        .settings
+        .labels
+        .iter_mut()
+        .find(|l| l.name == name)
+        .ok_or_else(|| FsError::NotFound(format!("Label not found: {}", name)))?;
+    if let Some(color) = color {
+        label.color = color;
+    }
+    if let Some(new_name) = new_name {
+        label.name = new_name.clone();
+        for task in &mut index.tasks {
+            for label in task.labels.iter_mut().filter(|l| **l == name) {
+                *label = new_name.clone();
+            }
+        }
+    }
+
+    write_kanban_index_with(&kanban_path, &index, session)?;
+    Ok(index.settings.labels)
+}
+
+/// Remove a label from a board and from all its tasks
+#[tauri::command]
+pub async fn delete_kanban_label(
+    kanban_path: PathBuf,
+    name: String,
+    state: State<'_, EncryptionState>,
+) -> Result<Vec<KanbanLabel>, FsError> {
+    let session = state.session(&kanban_path);
+    let session = Some(session.as_ref());
+    let mut index = read_kanban_index(&kanban_path, session)?;
+    let count = index.settings.labels.len();
+    index.settings.labels.retain(|l| l.name != name);
+    if index.settings.labels.len() == count {
+        return Err(FsError::NotFound(format!("Label not found: {}", name)));
+    }
+    for task in &mut index.tasks {
+        task.labels.retain(|l| *l != name);
+    }
+
+    write_kanban_index_with(&kanban_path, &index, session)?;
+    Ok(index.settings.labels)
+}
+
+/// Add a person tasks can be assigned to
+#[tauri::command]
+pub async fn add_kanban_person(
+    kanban_path: PathBuf,
+    name: String,
+    state: State<'_, EncryptionState>,
+) -> Result<Vec<String>, FsError> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err(FsError::InvalidPath("Name cannot be empty".to_string()));
+    }
+
+    let session = state.session(&kanban_path);
+    let session = Some(session.as_ref());
+    let mut index = read_kanban_index(&kanban_path, session)?;
+    if !index.settings.people.contains(&name) {
+        index.settings.people.push(name);
+        write_kanban_index_with(&kanban_path, &index, session)?;
+    }
+    Ok(index.settings.people)
+}
+
+/// Remove a person from a board, unassigning their tasks
+#[tauri::command]
+pub async fn remove_kanban_person(
+    kanban_path: PathBuf,
+    name: String,
+    state: State<'_, EncryptionState>,
+) -> Result<Vec<String>, FsError> {
+    let session = state.session(&kanban_path);
+    let session = Some(session.as_ref());
+    let mut index = read_kanban_index(&kanban_path, session)?;
+    index.settings.people.retain(|p| *p != name);
+    for task in index.tasks.iter_mut().filter(|t| t.assignee.as_ref() == Some(&name)) {
+        task.assignee = None;
+    }
+
+    write_kanban_index_with(&kanban_path, &index, session)?;
+    Ok(index.settings.people)
+}
+
+/// Status history of a task; tasks from before history was recorded count as
+/// having been in their current status since they were created
+fn task_history(task: &KanbanTask) -> Vec<(DateTime<Utc>, &str)> {
+    if task.history.is_empty() {
+        return parse_timestamp(&task.created)
+            .map(|at| vec![(at, task.status.as_str())])
+            .unwrap_or_default();
+    }
+    task.history
+        .iter()
+        .filter_map(|change| Some((parse_timestamp(&change.at)?, change.status.as_str())))
+        .collect()
+}
+
+fn duration_stats(mut days: Vec<f64>) -> DurationStats {
+    if days.is_empty() {
+        return DurationStats::default();
+    }
+    days.sort_by(f64::total_cmp);
+    let middle = days.len() / 2;
+    let median = if days.len().is_multiple_of(2) { (days[middle - 1] + days[middle]) / 2.0 } else { days[middle] };
+    DurationStats {
+        count: days.len(),
+        average_days: Some(days.iter().sum::<f64>() / days.len() as f64),
+        median_days: Some(median),
+    }
+}
+
+fn days_between(start: DateTime<Utc>, end: DateTime<Utc>) -> f64 {
+    (end - start).num_seconds().max(0) as f64 / 86_400.0
+}
+
+fn week_start(date: NaiveDate) -> NaiveDate {
+    date - Duration::days(date.weekday().num_days_from_monday() as i64)
+}
+
+/// Columns of finished tasks: the "done" column and the ones after it, or
+/// the last column on boards without one
+pub(crate) fn done_statuses(columns: &[String]) -> &[String] {
+    let done_from = columns
+        .iter()
+        .position(|c| c.eq_ignore_ascii_case("done"))
+        .unwrap_or(columns.len().saturating_sub(1));
+    columns.get(done_from..).unwrap_or_default()
+}
+
+/// Flow statistics of a board for the days in `range`
+///
+/// Tasks are done once they reach one of the `done_statuses`. Work starts
+/// when a task first leaves the first column. The range defaults to
+/// everything up to `today`; cumulative flow covers at most its last year.
+pub(crate) fn kanban_stats(index: &KanbanIndex, range: &DateRange, today: NaiveDate) -> KanbanStats {
+    let done_statuses = done_statuses(&index.columns);
+    let first_column = index.columns.first().map(String::as_str);
+
+    let to = range.to.as_deref().and_then(parse_date).unwrap_or(today);
+    let earliest = index
+        .tasks
+        .iter()
+        .filter_map(|t| parse_timestamp(&t.created))
+        .map(|t| t.date_naive())
+        .min()
+        .unwrap_or(to);
+    let from = range.from.as_deref().and_then(parse_date).unwrap_or(earliest);
+
+    let mut cycle_times = Vec::new();
+    let mut lead_times = Vec::new();
+    let mut weeks: BTreeMap<NaiveDate, usize> = BTreeMap::new();
+    let mut week = week_start(from);
+    while week <= to {
+        weeks.insert(week, 0);
+        week += Duration::days(7);
+    }
+
+    let histories: Vec<_> = index.tasks.iter().map(task_history).collect();
+    for (task, history) in index.tasks.iter().zip(&histories) {
+        let mut done_since = None;
+        for (at, status) in history {
+            let done = done_statuses.iter().any(|s| s == status);
+            if done && done_since.is_none() {
+                done_since = Some(*at);
+            } else if !done {
+                done_since = None;
+            }
+        }
+        let Some(done_at) = done_since else {
+            continue;
+        };
+        if done_at.date_naive() < from || done_at.date_naive() > to {
+            continue;
+        }
+
+        *weeks.entry(week_start(done_at.date_naive())).or_default() += 1;
+        if let Some(created) = parse_timestamp(&task.created) {
+            lead_times.push(days_between(created, done_at));
+        }
+        if let Some((started, _)) = history.iter().find(|(_, status)| Some(*status) != first_column) {
+            cycle_times.push(days_between(*started, done_at));
+        }
+    }
+
+    let mut cumulative_flow = Vec::new();
+    let mut day = from.max(to - Duration::days(MAX_FLOW_DAYS - 1));
+    while day <= to {
+        let mut counts: BTreeMap<String, usize> = index.columns.iter().map(|c| (c.clone(), 0)).collect();
+        for history in &histories {
+            if let Some((_, status)) = history.iter().rev().find(|(at, _)| at.date_naive() <= day) {
+                *counts.entry(status.to_string()).or_default() += 1;
+            }
+        }
+        cumulative_flow.push(FlowPoint {
+            date: day.format("%Y-%m-%d").to_string(),
+            counts,
+        });
+        day += Duration::days(1);
+    }
+
+    KanbanStats {
+        cycle_time: duration_stats(cycle_times),
+        lead_time: duration_stats(lead_times),
+        throughput: weeks
+            .into_iter()
+            .map(|(week, completed)| WeeklyThroughput {
+                week_start: week.format("%Y-%m-%d").to_string(),
+                completed,
+            })
+            .collect(),
+        cumulative_flow,
+    }
+}
+
+/// Cycle time, lead time, weekly throughput and cumulative flow of a board
+#[tauri::command]
+pub async fn get_kanban_stats(
+    kanban_path: PathBuf,
+    range: Option<DateRange>,
+    state: State<'_, EncryptionState>,
+) -> Result<KanbanStats, FsError> {
+    let index = read_kanban_index(&kanban_path, Some(&state.session(&kanban_path)))?;
+    Ok(kanban_stats(&index, &range.unwrap_or_default(), Utc::now().date_naive()))
+}
+
+/// Move or copy a task to another board
+///
+/// The task keeps its ID unless the target board already has it, in which
+/// case links to its attachments are rewritten to the new ID. The source
+/// board only changes once the task is stored on the target board.
+pub(crate) fn transfer_task(
+    from_board: &Path,
+    task_id: &str,
+    to_board: &Path,
+    target_column: Option<&str>,
+    copy: bool,
+    session: Option<&EncryptionSession>,
+) -> Result<KanbanTaskWithContent, FsError> {
+    if from_board == to_board {
+        return Err(FsError::InvalidPath("Task is already on this board".to_string()));
+    }
+    let mut source = read_kanban_index(from_board, session)?;
+    let mut target = read_kanban_index(to_board, session)?;
+
+    let position = source
+        .tasks
+        .iter()
+        .position(|t| t.id == task_id)
+        .ok_or_else(|| FsError::NotFound(format!("Task not found: {}", task_id)))?;
+    let column = match target_column {
+        Some(column) if target.columns.iter().any(|c| c == column) => column.to_string(),
+        Some(column) => return Err(FsError::NotFound(format!("Column not found: {}", column))),
+        None => target.columns.first().cloned().unwrap_or_else(|| "backlog".to_string()),
+    };
+
+    let mut task = source.tasks[position].clone();
+    let mut description = read_task_description(from_board, source.encrypted, task_id, session)?;
+    while target.tasks.iter().any(|t| t.id == task.id) || get_task_file_path(to_board, &task.id).exists() {
+        task.id = generate_task_id();
+    }
+    if task.id != task_id {
+        description = description.replace(&format!("./{}.assets/", task_id), &format!("./{}.assets/", task.id));
+    }
+
+    let now = chrono::Utc::now().to_rfc3339();
+    if task.status != column {
+        task.history.push(StatusChange {
+            status: column.clone(),
+            at: now.clone(),
+        });
+        task.status = column;
+    }
+    task.updated = now;
+
+    // Labels and assignees only carry over when the target board knows them
+    task.labels.retain(|l| target.settings.labels.iter().any(|known| &known.name == l));
+    if task.assignee.as_ref().is_some_and(|a| !target.settings.people.contains(a)) {
+        task.assignee = None;
+    }
+
+    let mut activity = read_task_activity(from_board, task_id, session)?;
+    activity.push(TaskActivity {
+        from: Some(from_board.display().to_string()),
+        to: Some(to_board.display().to_string()),
+        ..activity_entry(ActivityKind::Moved, activity_author(to_board), &task.updated)
+    });
+
+    let source_assets = note_assets_dir(&get_task_file_path(from_board, task_id));
+    let target_assets = note_assets_dir(&get_task_file_path(to_board, &task.id));
+    target.tasks.push(task.clone());
+    let stored = (|| {
+        if source_assets.is_dir() {
+            copy_dir(&source_assets, &target_assets)?;
+        }
+        if !description.is_empty() {
+            write_task_description(to_board, target.encrypted, &task.id, &description, session)?;
+        }
+        append_task_activity(to_board, target.encrypted, &task.id, &activity, session)?;
+        write_kanban_index_with(to_board, &target, session)
+    })();
+    if let Err(e) = stored {
+        let _ = fs::remove_dir_all(&target_assets);
+        let _ = fs::remove_file(get_task_file_path(to_board, &task.id));
+        let _ = fs::remove_file(activity_path(to_board, &task.id));
+        return Err(e);
+    }
+
+    if !copy {
+        source.tasks.remove(position);
+        write_kanban_index_with(from_board, &source, session)?;
+        for path in [get_task_file_path(from_board, task_id), activity_path(from_board, task_id)] {
+            if path.exists() {
+                fs::remove_file(&path)?;
+            }
+        }
+        if source_assets.is_dir() {
+            fs::remove_dir_all(&source_assets)?;
+        }
+    }
+
+    Ok(KanbanTaskWithContent::from_task(&task, description))
+}
+
+/// Move a task to another board, or copy it when `copy` is set
+#[tauri::command]
+pub async fn move_kanban_task(
+    app_handle: AppHandle,
+    from_board: PathBuf,
+    task_id: String,
+    to_board: PathBuf,
+    target_column: Option<String>,
+    copy: Option<bool>,
+    state: State<'_, EncryptionState>,
+) -> Result<KanbanTaskWithContent, FsError> {
+    let copy = copy.unwrap_or(false);
+    let task = transfer_task(
+        &from_board,
+        &task_id,
+        &to_board,
+        target_column.as_deref(),
+        copy,
+        Some(&state.session(&from_board)),
+    )?;
+
+    if let Some(vault) = find_vault_root(&to_board) {
+        let relative = |board: &Path| board.strip_prefix(&vault).unwrap_or(board).to_path_buf();
+        let data = json!({
+            "board": relative(&to_board),
+            "from_board": relative(&from_board),
+            "task_id": task.id,
+            "title": task.title,
+            "to": task.status,
+            "copy": copy,
+        });
+        dispatch_webhooks(&app_handle, &vault, WebhookEvent::TaskMoved, data);
+    }
+    Ok(task)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::commands::write_kanban_index;
+
+    #[test]
+    fn filters_tasks() {
+        let task = KanbanTaskWithContent {
+            title: "Ship release".to_string(),
+            priority: Some("high".to_string()),
+            due: Some("2024-05-10".to_string()),
+            labels: vec!["backend".to_string(), "urgent".to_string()],
+            assignee: Some("Dana".to_string()),
+            description: "Tag and publish the build".to_string(),
+            ..Default::default()
+        };
+
+        assert!(task_matches(&task, &TaskFilter::default()));
+        let filter = TaskFilter {
+            labels: vec!["urgent".to_string()],
+            priorities: vec!["high".to_string(), "medium".to_string()],
+            assignee: Some("Dana".to_string()),
+            due_from: Some("2024-05-01".to_string()),
+            due_to: Some("2024-05-10T23:59:00Z".to_string()),
+            text: Some("PUBLISH".to_string()),
+        };
+        assert!(task_matches(&task, &filter));
+
+        assert!(!task_matches(&task, &TaskFilter { labels: vec!["frontend".to_string()], ..filter.clone() }));
+        assert!(!task_matches(&task, &TaskFilter { assignee: Some(String::new()), ..filter.clone() }));
+        assert!(!task_matches(&task, &TaskFilter { due_to: Some("2024-05-09".to_string()), ..filter.clone() }));
+        assert!(!task_matches(&task, &TaskFilter { text: Some("docs".to_string()), ..filter.clone() }));
+        let undated = KanbanTaskWithContent { due: None, ..task };
+        assert!(!task_matches(&undated, &filter));
+    }
+
+    #[test]
+    fn computes_flow_stats() {
+        let change = |status: &str, at: &str| StatusChange {
+            status: status.to_string(),
+            at: at.to_string(),
+        };
+        let index = KanbanIndex {
+            columns: ["backlog", "working", "done"].iter().map(|c| c.to_string()).collect(),
+            tasks: vec![
+                KanbanTask {
+                    status: "done".to_string(),
+                    created: "2024-05-01T09:00:00Z".to_string(),
+                    history: vec![
+                        change("backlog", "2024-05-01T09:00:00Z"),
+                        change("working", "2024-05-02T09:00:00Z"),
+                        change("done", "2024-05-04T09:00:00Z"),
+                    ],
+                    ..Default::default()
+                },
+                KanbanTask {
+                    status: "working".to_string(),
+                    created: "2024-05-03T09:00:00Z".to_string(),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+
+        let range = DateRange {
+            from: Some("2024-05-01".to_string()),
+            to: Some("2024-05-05".to_string()),
+        };
+        let stats = kanban_stats(&index, &range, NaiveDate::from_ymd_opt(2024, 6, 1).unwrap());
+        assert_eq!(stats.cycle_time.average_days, Some(2.0));
+        assert_eq!(stats.lead_time.median_days, Some(3.0));
+        assert_eq!(stats.throughput.len(), 1);
+        assert_eq!(stats.throughput[0].week_start, "2024-04-29");
+        assert_eq!(stats.throughput[0].completed, 1);
+
+        assert_eq!(stats.cumulative_flow.len(), 5);
+        let day = |i: usize| &stats.cumulative_flow[i].counts;
+        assert_eq!(day(0)["backlog"], 1);
+        assert_eq!(day(2)["working"], 2);
+        assert_eq!(day(4)["done"], 1);
+        assert_eq!(day(4)["working"], 1);
+    }
+
+    #[test]
+    fn moves_task_between_boards() {
+        let dir = tempfile::tempdir().unwrap();
+        let from = dir.path().join("A.kanban");
+        let to = dir.path().join("B.kanban");
+        for board in [&from, &to] {
+            fs::create_dir(board).unwrap();
+        }
+        let task = |id: &str| KanbanTask {
+            id: id.to_string(),
+            title: format!("Task {}", id),
+            status: "todo".to_string(),
+            ..Default::default()
+        };
+        write_kanban_index(&from, &KanbanIndex { tasks: vec![task("t1")], ..Default::default() }).unwrap();
+        write_kanban_index(&to, &KanbanIndex { tasks: vec![task("t1")], ..Default::default() }).unwrap();
+        fs::write(get_task_file_path(&from, "t1"), "See ![](./t1.assets/a.png)").unwrap();
+        fs::create_dir(from.join("t1.assets")).unwrap();
+        fs::write(from.join("t1.assets/a.png"), "png").unwrap();
+
+        assert!(transfer_task(&from, "t1", &to, Some("nope"), false, None).is_err());
+        let moved = transfer_task(&from, "t1", &to, Some("done"), false, None).unwrap();
+        assert_ne!(moved.id, "t1");
+        assert_eq!(moved.status, "done");
+        assert_eq!(moved.description, format!("See ![](./{}.assets/a.png)", moved.id));
+        assert!(to.join(format!("{}.assets/a.png", moved.id)).exists());
+
+        assert!(read_kanban_index(&from, None).unwrap().tasks.is_empty());
+        assert!(!from.join("t1.assets").exists() && !get_task_file_path(&from, "t1").exists());
+        assert_eq!(read_kanban_index(&to, None).unwrap().tasks.len(), 2);
+
+        let activity = read_task_activity(&to, &moved.id, None).unwrap();
+        assert_eq!(activity.last().map(|a| &a.kind), Some(&ActivityKind::Moved));
+
+        let copied = transfer_task(&to, &moved.id, &from, None, true, None).unwrap();
+        assert_eq!(copied.id, moved.id);
+        assert_eq!(read_kanban_index(&to, None).unwrap().tasks.len(), 2);
+    }
+}
@@ -0,0 +1,669 @@
+//! Wiki link index: resolves `[[links]]` by note name, vault-relative path or
+//! frontmatter alias, and finds/rewrites links across the vault.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use super::archive::archive_dir;
+use super::commands::{markdown_files, note_assets_dir, read_markdown_text, walk_notes, write_atomic, FsError};
+use super::encryption::is_encrypted;
+use super::exclude::VaultIgnore;
+use super::frontmatter::{extract_aliases, parse_frontmatter, string_field};
+
+/// A note as seen by the quick switcher and link resolver
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NoteRef {
+    pub path: PathBuf,
+    /// File name without the `.md` extension
+    pub name: String,
+    /// Title from frontmatter, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// Alternative names from the `aliases` frontmatter field
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    /// In the archive folder or marked `archived: true`
+    #[serde(default)]
+    pub archived: bool,
+}
+
+/// A link pointing at a note from somewhere else in the vault
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Backlink {
+    /// Note or notebook containing the link
+    pub source: PathBuf,
+    /// Link target as written (may be an alias)
+    pub target: String,
+    /// 1-based line number within the source text
+    pub line: usize,
+    /// The line containing the link
+    pub context: String,
+}
+
+/// A `[[target#heading|display]]` occurrence in markdown text
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct WikiLink {
+    pub target: String,
+    pub heading: Option<String>,
+    pub display: Option<String>,
+    pub embed: bool,
+    /// Byte range of the whole link including brackets (and `!` for embeds)
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Extract all wiki links from markdown text
+pub(crate) fn extract_wikilinks(content: &str) -> Vec<WikiLink> {
+    let mut links = Vec::new();
+    let mut pos = 0;
+
+    while let Some(found) = content[pos..].find("[[") {
+        let open = pos + found;
+        let Some(close_rel) = content[open + 2..].find("]]") else {
+            break;
+        };
+        let close = open + 2 + close_rel;
+        let inner = &content[open + 2..close];
+
+        if inner.is_empty() || inner.contains('\n') || inner.contains("[[") {
+            pos = open + 2;
+            continue;
+        }
+
+        let (target_part, display) = match inner.split_once('|') {
+            Some((t, d)) => (t, Some(d.trim().to_string())),
+            None => (inner, None),
+        };
+        let (target, heading) = match target_part.split_once('#') {
+            Some((t, h)) => (t.trim().to_string(), Some(h.trim().to_string())),
+            None => (target_part.trim().to_string(), None),
+        };
+
+        let embed = open > 0 && content.as_bytes()[open - 1] == b'!';
+        links.push(WikiLink {
+            target,
+            heading,
+            display,
+            embed,
+            start: if embed { open - 1 } else { open },
+            end: close + 2,
+        });
+        pos = close + 2;
+    }
+
+    links
+}
+
+/// Render a wiki link back to text
+pub(crate) fn format_wikilink(link: &WikiLink) -> String {
+    let mut out = String::new();
+    if link.embed {
+        out.push('!');
+    }
+    out.push_str("[[");
+    out.push_str(&link.target);
+    if let Some(heading) = &link.heading {
+        out.push('#');
+        out.push_str(heading);
+    }
+    if let Some(display) = &link.display {
+        out.push('|');
+        out.push_str(display);
+    }
+    out.push_str("]]");
+    out
+}
+
+/// Replace the targets of selected links, returning the new text if anything changed
+pub(crate) fn rewrite_wikilinks<F>(content: &str, mut new_target: F) -> Option<String>
+where
+    F: FnMut(&WikiLink) -> Option<String>,
+{
+    let mut result = String::with_capacity(content.len());
+    let mut last = 0;
+    let mut changed = false;
+
+    for link in extract_wikilinks(content) {
+        if let Some(target) = new_target(&link) {
+            result.push_str(&content[last..link.start]);
+            result.push_str(&format_wikilink(&WikiLink { target, ..link.clone() }));
+            last = link.end;
+            changed = true;
+        }
+    }
+
+    if !changed {
+        return None;
+    }
+    result.push_str(&content[last..]);
+    Some(result)
+}
+
+/// Replace the targets of inline markdown links and images (`[text](target)`)
+///
+/// The callback receives the target without surrounding `<>` and returns the
+/// new target, which is written in `<>` form so it may contain spaces.
+pub(crate) fn rewrite_markdown_links<F>(content: &str, mut new_target: F) -> String
+where
+    F: FnMut(&str) -> Option<String>,
+{
+    let mut result = String::with_capacity(content.len());
+    let mut rest = content;
+
+    while let Some(pos) = rest.find("](") {
+        let (before, after) = rest.split_at(pos + 2);
+        result.push_str(before);
+
+        // Find the closing parenthesis, allowing balanced parentheses inside
+        let mut depth = 0;
+        let mut end = None;
+        for (i, c) in after.char_indices() {
+            match c {
+                '(' => depth += 1,
+                ')' if depth == 0 => {
+                    end = Some(i);
+                    break;
+                }
+                ')' => depth -= 1,
+                '\n' => break,
+                _ => {}
+            }
+        }
+        let Some(end) = end else {
+            rest = after;
+            continue;
+        };
+
+        let raw = &after[..end];
+        let target = raw.trim().trim_start_matches('<').trim_end_matches('>');
+        match new_target(target) {
+            Some(href) => {
+                result.push('<');
+                result.push_str(&href);
+                result.push('>');
+            }
+            None => result.push_str(raw),
+        }
+        rest = &after[end..];
+    }
+
+    result.push_str(rest);
+    result
+}
+
+/// Normalize a link target or note name for lookup
+pub(crate) fn link_key(target: &str) -> String {
+    let target = target.trim().trim_start_matches("./").replace('\\', "/");
+    let target = target.strip_suffix(".md").unwrap_or(&target);
+    target.to_lowercase()
+}
+
+/// Display name of a note or notebook
+pub(crate) fn note_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().trim_end_matches(".md").to_string())
+        .unwrap_or_default()
+}
+
+/// Vault-relative path without extension, used for `[[folder/note]]` links
+pub(crate) fn relative_key(vault_path: &Path, path: &Path) -> Option<String> {
+    path.strip_prefix(vault_path)
+        .ok()
+        .map(|rel| link_key(&rel.to_string_lossy()))
+}
+
+/// Path of `to` relative to the directory `from_dir` (both vault-relative)
+pub(crate) fn relative_path(from_dir: &Path, to: &Path) -> PathBuf {
+    let from: Vec<Component> = from_dir.components().collect();
+    let target: Vec<Component> = to.components().collect();
+    let common = from.iter().zip(&target).take_while(|(a, b)| a == b).count();
+
+    let mut result = PathBuf::new();
+    for _ in common..from.len() {
+        result.push("..");
+    }
+    for component in &target[common..] {
+        result.push(component.as_os_str());
+    }
+    result
+}
+
+/// Index of all notes in a vault keyed by name, relative path and alias
+pub(crate) struct LinkIndex {
+    pub notes: Vec<NoteRef>,
+    lookup: HashMap<String, usize>,
+}
+
+impl LinkIndex {
+    /// Scan the vault and index every note and notebook
+    pub fn build(vault_path: &Path) -> Result<Self, FsError> {
+        if !vault_path.is_dir() {
+            return Err(FsError::NotFound(vault_path.display().to_string()));
+        }
+
+        let mut paths = Vec::new();
+        walk_notes(vault_path, &mut paths);
+        let archive = archive_dir(vault_path);
+
+        let notes: Vec<NoteRef> = paths
+            .into_iter()
+            .map(|path| {
+                let content = read_markdown_text(&path).unwrap_or_default();
+                let frontmatter = parse_frontmatter(&content);
+                let title = frontmatter.as_ref().and_then(|fm| string_field(fm, "title"));
+                let flagged = frontmatter.as_ref().and_then(|fm| string_field(fm, "archived"));
+                NoteRef {
+                    name: note_name(&path),
+                    title,
+                    aliases: extract_aliases(&content),
+                    archived: path.starts_with(&archive) || flagged.as_deref() == Some("true"),
+                    path,
+                }
+            })
+            .collect();
+
+        let mut lookup = HashMap::new();
+        // Real names and paths win over aliases when they collide
+        for (i, note) in notes.iter().enumerate() {
+            if let Some(key) = relative_key(vault_path, &note.path) {
+                lookup.entry(key).or_insert(i);
+            }
+            lookup.entry(link_key(&note.name)).or_insert(i);
+        }
+        for (i, note) in notes.iter().enumerate() {
+            for alias in &note.aliases {
+                lookup.entry(link_key(alias)).or_insert(i);
+            }
+        }
+
+        Ok(Self { notes, lookup })
+    }
+
+    /// Resolve a link target to a note
+    pub fn resolve(&self, target: &str) -> Option<&NoteRef> {
+        self.lookup.get(&link_key(target)).map(|&i| &self.notes[i])
+    }
+}
+
+/// All notes in the vault with their aliases, for the quick switcher
+///
+/// Archived notes are left out unless `include_archived` is set; links to
+/// them still resolve.
+#[tauri::command]
+pub async fn get_note_index(vault_path: PathBuf, include_archived: Option<bool>) -> Result<Vec<NoteRef>, FsError> {
+    let include_archived = include_archived.unwrap_or(false);
+    Ok(LinkIndex::build(&vault_path)?
+        .notes
+        .into_iter()
+        .filter(|note| include_archived || !note.archived)
+        .collect())
+}
+
+/// Find notes whose name, title or alias matches a query
+///
+/// Exact matches rank first, then prefix matches, then substring matches;
+/// a match on the note name beats a match on an alias. Archived notes are
+/// only searched with `include_archived`.
+#[tauri::command]
+pub async fn find_notes(
+    vault_path: PathBuf,
+    query: String,
+    limit: Option<usize>,
+    include_archived: Option<bool>,
+) -> Result<Vec<NoteRef>, FsError> {
+    let index = LinkIndex::build(&vault_path)?;
+    let include_archived = include_archived.unwrap_or(false);
+    let query = query.trim().to_lowercase();
+
+    let score = |candidate: &str, bonus: u32| -> Option<u32> {
+        let candidate = candidate.to_lowercase();
+        if candidate == query {
+            Some(30 + bonus)
+        } else if candidate.starts_with(&query) {
+            Some(20 + bonus)
+        } else if candidate.contains(&query) {
+            Some(10 + bonus)
+        } else {
+            None
+        }
+    };
+
+    let mut matches: Vec<(u32, NoteRef)> = index
+        .notes
+        .into_iter()
+        .filter(|note| include_archived || !note.archived)
+        .filter_map(|note| {
+            let best = std::iter::once(score(&note.name, 2))
+                .chain(note.title.iter().map(|t| score(t, 1)))
+                .chain(note.aliases.iter().map(|a| score(a, 0)))
+                .flatten()
+                .max()?;
+            Some((best, note))
+        })
+        .collect();
+
+    matches.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.name.cmp(&b.1.name)));
+
+    Ok(matches
+        .into_iter()
+        .take(limit.unwrap_or(50))
+        .map(|(_, note)| note)
+        .collect())
+}
+
+/// Resolve a wiki link target (name, path or alias) to a note path
+#[tauri::command]
+pub async fn resolve_wikilink(vault_path: PathBuf, target: String) -> Result<Option<PathBuf>, FsError> {
+    let index = LinkIndex::build(&vault_path)?;
+    Ok(index.resolve(&target).map(|note| note.path.clone()))
+}
+
+/// Find every link in the vault that resolves to the given note
+#[tauri::command]
+pub async fn get_backlinks(vault_path: PathBuf, note_path: PathBuf) -> Result<Vec<Backlink>, FsError> {
+    let index = LinkIndex::build(&vault_path)?;
+    let mut backlinks = Vec::new();
+
+    for source in &index.notes {
+        if source.path == note_path {
+            continue;
+        }
+        let content = read_markdown_text(&source.path).unwrap_or_default();
+
+        for link in extract_wikilinks(&content) {
+            let Some(resolved) = index.resolve(&link.target) else {
+                continue;
+            };
+            if resolved.path != note_path {
+                continue;
+            }
+
+            let line_start = content[..link.start].rfind('\n').map(|i| i + 1).unwrap_or(0);
+            let line_end = content[link.end..]
+                .find('\n')
+                .map(|i| link.end + i)
+                .unwrap_or(content.len());
+
+            backlinks.push(Backlink {
+                source: source.path.clone(),
+                target: link.target.clone(),
+                line: content[..link.start].matches('\n').count() + 1,
+                context: content[line_start..line_end].trim().to_string(),
+            });
+        }
+    }
+
+    Ok(backlinks)
+}
+
+/// Rewrite links pointing at a renamed or moved note
+///
+/// Call after the rename. Links written as the old name or old vault-relative
+/// path are updated to the new name; links written as an alias still resolve
+/// and are left untouched. Returns the files that were modified.
+#[tauri::command]
+pub async fn update_links_after_rename(
+    vault_path: PathBuf,
+    old_path: PathBuf,
+    new_path: PathBuf,
+) -> Result<Vec<PathBuf>, FsError> {
+    let index = LinkIndex::build(&vault_path)?;
+    let old_keys: Vec<String> = [Some(link_key(&note_name(&old_path))), relative_key(&vault_path, &old_path)]
+        .into_iter()
+        .flatten()
+        .collect();
+
+    // Keep path-style links path-style so they stay unambiguous
+    let new_name = note_name(&new_path);
+    let new_relative = new_path
+        .strip_prefix(&vault_path)
+        .map(|rel| rel.to_string_lossy().trim_end_matches(".md").replace('\\', "/"))
+        .unwrap_or_else(|_| new_name.clone());
+
+    let mut modified = Vec::new();
+    for note in &index.notes {
+        for file in markdown_files(&note.path)? {
+            let content = fs::read_to_string(&file)?;
+            let rewritten = rewrite_wikilinks(&content, |link| {
+                let key = link_key(&link.target);
+                if !old_keys.contains(&key) {
+                    return None;
+                }
+                // Another note may legitimately own the old name now
+                if let Some(current) = index.resolve(&link.target) {
+                    if current.path != new_path {
+                        return None;
+                    }
+                }
+                let target = if link.target.contains('/') {
+                    new_relative.clone()
+                } else {
+                    new_name.clone()
+                };
+                (target != link.target).then_some(target)
+            });
+
+            if let Some(rewritten) = rewritten {
+                write_atomic(&file, rewritten)?;
+                if !modified.contains(&note.path) {
+                    modified.push(note.path.clone());
+                }
+            }
+        }
+    }
+
+    Ok(modified)
+}
+
+/// Rewrite path-style links into a renamed or moved folder
+///
+/// Call after the rename. Links by note name or alias still resolve and are
+/// left untouched. Returns the notes that were modified.
+pub(crate) fn update_links_after_folder_rename(
+    vault_path: &Path,
+    old_dir: &Path,
+    new_dir: &Path,
+) -> Result<Vec<PathBuf>, FsError> {
+    let (Some(old_prefix), Ok(new_prefix)) = (relative_key(vault_path, old_dir), new_dir.strip_prefix(vault_path))
+    else {
+        return Ok(Vec::new());
+    };
+    let old_prefix = format!("{}/", old_prefix);
+    let new_prefix = format!("{}/", new_prefix.to_string_lossy().replace('\\', "/"));
+
+    let index = LinkIndex::build(vault_path)?;
+    let mut modified = Vec::new();
+    for note in &index.notes {
+        for file in markdown_files(&note.path)? {
+            let content = fs::read_to_string(&file)?;
+            let rewritten = rewrite_wikilinks(&content, |link| {
+                let target = link.target.trim().trim_start_matches("./").replace('\\', "/");
+                let head = target.get(..old_prefix.len())?;
+                (head.to_lowercase() == old_prefix).then(|| format!("{}{}", new_prefix, &target[old_prefix.len()..]))
+            });
+
+            if let Some(rewritten) = rewritten {
+                write_atomic(&file, rewritten)?;
+                if !modified.contains(&note.path) {
+                    modified.push(note.path.clone());
+                }
+            }
+        }
+    }
+
+    Ok(modified)
+}
+
+/// `path` with `.` and `..` components resolved without touching the disk
+fn normalize_path(path: &Path) -> PathBuf {
+    let mut result = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                result.pop();
+            }
+            other => result.push(other.as_os_str()),
+        }
+    }
+    result
+}
+
+/// Fix the relative markdown links of a note moved or copied from `from`
+///
+/// Call after the note landed at `to` along with its `.assets` folder, which
+/// was `from_assets` before. Links into that folder follow it; other relative
+/// links keep pointing at the same files. Encrypted files are left alone.
+pub(crate) fn relink_moved_note(from: &Path, from_assets: &Path, to: &Path) -> Result<(), FsError> {
+    let (Some(from_dir), Some(to_dir)) = (from.parent(), to.parent()) else {
+        return Ok(());
+    };
+    let to_assets = note_assets_dir(to);
+    if from_dir == to_dir && from_assets == to_assets {
+        return Ok(());
+    }
+
+    for file in markdown_files(to)? {
+        let content = fs::read_to_string(&file)?;
+        if is_encrypted(&content) {
+            continue;
+        }
+        let rewritten = rewrite_markdown_links(&content, |target| {
+            if target.is_empty()
+                || target.contains("://")
+                || target.contains('"')
+                || target.starts_with(['#', '/'])
+                || target.starts_with("mailto:")
+                || target.starts_with("data:")
+            {
+                return None;
+            }
+            let (path_part, fragment) = target.split_at(target.find('#').unwrap_or(target.len()));
+            let old = normalize_path(&from_dir.join(path_part));
+            let new = match old.strip_prefix(from_assets) {
+                Ok(rest) => to_assets.join(rest),
+                Err(_) => old,
+            };
+            if normalize_path(&to_dir.join(path_part)) == new {
+                return None;
+            }
+            let relative = relative_path(to_dir, &new).to_string_lossy().replace('\\', "/");
+            let relative = if relative.starts_with("..") { relative } else { format!("./{}", relative) };
+            Some(format!("{}{}", relative, fragment))
+        });
+        if rewritten != content {
+            write_atomic(&file, rewritten)?;
+        }
+    }
+    Ok(())
+}
+
+/// Resolve an `![[embed]]` attachment target to a file in the vault
+///
+/// Tries the vault-relative path first, then matches the file name anywhere
+/// in the vault, preferring the shallowest match as Obsidian does. Used when
+/// the vault keeps Obsidian-style links (`compatibility.obsidian_links`).
+#[tauri::command]
+pub async fn resolve_attachment(vault_path: PathBuf, target: String) -> Result<Option<PathBuf>, FsError> {
+    let target = target.trim().trim_start_matches("./").replace('\\', "/");
+    let direct = vault_path.join(&target);
+    if direct.is_file() {
+        return Ok(Some(direct));
+    }
+
+    let Some(file_name) = Path::new(&target).file_name().map(|n| n.to_string_lossy().to_lowercase()) else {
+        return Ok(None);
+    };
+
+    let ignore = VaultIgnore::load(&vault_path)?;
+    let mut level = vec![vault_path];
+    while !level.is_empty() {
+        let mut next = Vec::new();
+        for dir in level {
+            let Ok(entries) = fs::read_dir(&dir) else {
+                continue;
+            };
+            let mut entries: Vec<PathBuf> = entries.flatten().map(|e| e.path()).collect();
+            entries.sort();
+            for path in entries {
+                let name = path
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_lowercase())
+                    .unwrap_or_default();
+                if name.starts_with('.') || ignore.is_ignored(&path, path.is_dir()) {
+                    continue;
+                }
+                if path.is_dir() {
+                    next.push(path);
+                } else if name == file_name {
+                    return Ok(Some(path));
+                }
+            }
+        }
+        level = next;
+    }
+
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_wikilinks_parts() {
+        let links = extract_wikilinks("See [[Project Plan#Goals|the plan]] and ![[diagram.png]].");
+
+        assert_eq!(links.len(), 2);
+        assert_eq!(links[0].target, "Project Plan");
+        assert_eq!(links[0].heading.as_deref(), Some("Goals"));
+        assert_eq!(links[0].display.as_deref(), Some("the plan"));
+        assert!(!links[0].embed);
+        assert!(links[1].embed);
+        assert_eq!(links[1].target, "diagram.png");
+    }
+
+    #[test]
+    fn test_rewrite_wikilinks_preserves_heading_and_display() {
+        let content = "[[Old#Intro|intro]] and [[Other]]";
+        let rewritten = rewrite_wikilinks(content, |link| {
+            (link.target == "Old").then(|| "New".to_string())
+        });
+
+        assert_eq!(rewritten.as_deref(), Some("[[New#Intro|intro]] and [[Other]]"));
+        assert_eq!(rewrite_wikilinks(content, |_| None), None);
+    }
+
+    #[test]
+    fn test_resolve_by_alias() {
+        let temp = tempfile::tempdir().unwrap();
+        std::fs::write(
+            temp.path().join("Meeting Notes.md"),
+            "---\ntitle: Weekly\naliases: [standup, \"Team Sync\"]\n---\n\n# Weekly\n",
+        )
+        .unwrap();
+        std::fs::write(temp.path().join("standup.md"), "# A real note named standup\n").unwrap();
+
+        let index = LinkIndex::build(temp.path()).unwrap();
+        let meeting = temp.path().join("Meeting Notes.md");
+
+        assert_eq!(index.resolve("team sync").map(|n| &n.path), Some(&meeting));
+        assert_eq!(index.resolve("Meeting Notes").map(|n| &n.path), Some(&meeting));
+        // A real note name takes precedence over another note's alias
+        assert_eq!(
+            index.resolve("standup").map(|n| &n.path),
+            Some(&temp.path().join("standup.md"))
+        );
+    }
+
+    #[test]
+    fn relative_paths_between_folders() {
+        assert_eq!(
+            relative_path(Path::new("a/b"), Path::new("a/c/img.png")),
+            PathBuf::from("../c/img.png")
+        );
+        assert_eq!(relative_path(Path::new(""), Path::new("note.md")), PathBuf::from("note.md"));
+    }
+}
@@ -0,0 +1,143 @@
+//! Typed access to a note's frontmatter.
+//!
+//! The frontend gets parsed metadata and simple body statistics from here
+//! instead of parsing YAML itself, and updates single fields without
+//! rewriting the rest of the frontmatter.
+
+use pulldown_cmark::{Event, Options, Parser, Tag, TagEnd};
+use serde_yaml::{Mapping, Value};
+use std::fs;
+use std::path::PathBuf;
+
+use super::bulk::frontmatter_file;
+use super::commands::{read_markdown_text, write_atomic, FsError};
+use super::encryption::is_encrypted;
+use super::frontmatter::{parse_frontmatter, patch_frontmatter, split_frontmatter, string_field, string_list};
+use super::types::{NoteMetadata, OutlineHeading};
+
+/// Frontmatter keys with a typed field in [`NoteMetadata`]
+const KNOWN_FIELDS: [&str; 3] = ["title", "labels", "created"];
+
+/// Headings of a markdown body with 1-based line numbers counted from
+/// `first_line`
+fn heading_outline(body: &str, first_line: usize) -> Vec<OutlineHeading> {
+    let mut outline = Vec::new();
+    let mut current: Option<(u8, usize, String)> = None;
+
+    for (event, range) in Parser::new_ext(body, Options::empty()).into_offset_iter() {
+        match event {
+            Event::Start(Tag::Heading { level, .. }) => {
+                let line = first_line + body[..range.start].matches('\n').count();
+                current = Some((level as u8, line, String::new()));
+            }
+            Event::Text(text) | Event::Code(text) => {
+                if let Some((_, _, heading)) = current.as_mut() {
+                    heading.push_str(&text);
+                }
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                if let Some((level, line, text)) = current.take() {
+                    outline.push(OutlineHeading {
+                        level,
+                        text: text.trim().to_string(),
+                        line,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+    outline
+}
+
+/// Metadata of a note's markdown text
+pub(crate) fn note_metadata(content: &str) -> NoteMetadata {
+    let (_, body) = split_frontmatter(content);
+    let map = parse_frontmatter(content).unwrap_or_default();
+    let custom: Mapping = map
+        .iter()
+        .filter(|(key, _)| !matches!(key, Value::String(k) if KNOWN_FIELDS.contains(&k.as_str())))
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect();
+    let first_line = content[..content.len() - body.len()].matches('\n').count() + 1;
+
+    NoteMetadata {
+        title: string_field(&map, "title"),
+        labels: string_list(&map, "labels"),
+        created: string_field(&map, "created"),
+        custom,
+        word_count: body.split_whitespace().count(),
+        outline: heading_outline(body, first_line),
+    }
+}
+
+/// Parsed frontmatter and body statistics of a note or notebook
+#[tauri::command]
+pub async fn get_note_metadata(path: PathBuf) -> Result<NoteMetadata, FsError> {
+    if !path.exists() {
+        return Err(FsError::NotFound(path.display().to_string()));
+    }
+    let content = read_markdown_text(&path)?;
+    if is_encrypted(&content) {
+        return Err(FsError::Encrypted(path.display().to_string()));
+    }
+    Ok(note_metadata(&content))
+}
+
+/// Set fields of a note's or notebook's frontmatter; a null value removes
+/// the field. Returns the updated metadata.
+#[tauri::command]
+pub async fn update_note_metadata(path: PathBuf, patch: Mapping) -> Result<NoteMetadata, FsError> {
+    if !path.exists() {
+        return Err(FsError::NotFound(path.display().to_string()));
+    }
+    let file = frontmatter_file(&path)?;
+    let content = fs::read_to_string(&file)?;
+    if is_encrypted(&content) {
+        return Err(FsError::Encrypted(path.display().to_string()));
+    }
+    if split_frontmatter(&content).0.is_some() && parse_frontmatter(&content).is_none() {
+        return Err(FsError::InvalidPath("Invalid frontmatter".to_string()));
+    }
+
+    let patched = patch_frontmatter(&content, &patch)?;
+    if patched != content {
+        write_atomic(&file, &patched)?;
+    }
+    Ok(note_metadata(&read_markdown_text(&path)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_metadata_and_outline() {
+        let content = "---\ntitle: Plan\nlabels: [work, q3]\ncreated: 2026-01-02\npriority: 2\n---\n# Goals\n\nShip the `v2` release.\n\n```\n# not a heading\n```\n\n## Next *steps*\n";
+        let metadata = note_metadata(content);
+
+        assert_eq!(metadata.title.as_deref(), Some("Plan"));
+        assert_eq!(metadata.labels, vec!["work", "q3"]);
+        assert_eq!(metadata.created.as_deref(), Some("2026-01-02"));
+        assert_eq!(metadata.custom.len(), 1);
+        assert_eq!(string_field(&metadata.custom, "priority").as_deref(), Some("2"));
+        assert_eq!(metadata.word_count, 15);
+
+        let outline: Vec<(u8, &str, usize)> =
+            metadata.outline.iter().map(|h| (h.level, h.text.as_str(), h.line)).collect();
+        assert_eq!(outline, vec![(1, "Goals", 7), (2, "Next steps", 15)]);
+    }
+
+    #[test]
+    fn patches_only_changed_fields() {
+        let content = "---\n# keep me\ntitle: Old\nlabels:\n  - a\n  - b\ndue: 2026-01-01\nstatus: draft # inline\n---\nBody\n";
+        let patch: Mapping = serde_yaml::from_str("labels: [c]\ndue: null\narchived: true\n").unwrap();
+        assert_eq!(
+            patch_frontmatter(content, &patch).unwrap(),
+            "---\n# keep me\ntitle: Old\nlabels:\n- c\nstatus: draft # inline\narchived: true\n---\nBody\n"
+        );
+
+        let patch: Mapping = serde_yaml::from_str("title: New").unwrap();
+        assert_eq!(patch_frontmatter("Body\n", &patch).unwrap(), "---\ntitle: New\n---\n\nBody\n");
+    }
+}
@@ -0,0 +1,136 @@
+//! Upgrades of the notebook index format.
+//!
+//! Each migration turns the raw JSON of one index version into the next, so
+//! an index is upgraded step by step whatever version it was written with.
+//! Before the upgraded index is written back, the stored file is kept as
+//! `.index.v<N>.json.bak`. Indexes newer than this build are refused rather
+//! than rewritten.
+
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::State;
+
+use super::commands::{decrypt_armored, notebook_index_path, write_notebook_index_with, FsError};
+use super::encryption::{is_encrypted, EncryptionSession};
+use super::encryption_commands::EncryptionState;
+use super::types::NotebookIndex;
+
+/// Index version written by this build
+pub(crate) const NOTEBOOK_INDEX_VERSION: u32 = 1;
+
+type Migration = fn(&mut Value) -> Result<(), FsError>;
+
+/// Migration `i` upgrades version `i` to `i + 1`
+const MIGRATIONS: &[Migration] = &[migrate_v0];
+
+/// Indexes from before versioning: no `version` and possibly no `blocks`
+fn migrate_v0(index: &mut Value) -> Result<(), FsError> {
+    let object = index
+        .as_object_mut()
+        .ok_or_else(|| FsError::InvalidPath("Invalid index.json: not an object".to_string()))?;
+    object.entry("blocks").or_insert_with(|| Value::Array(Vec::new()));
+    Ok(())
+}
+
+/// Version of a raw index; a missing version means 0
+fn index_version(index: &Value) -> u32 {
+    index
+        .get("version")
+        .and_then(Value::as_u64)
+        .map_or(0, |v| u32::try_from(v).unwrap_or(u32::MAX))
+}
+
+/// Whether a raw index has to be upgraded; errors for unsupported versions
+fn needs_migration(index: &Value) -> Result<bool, FsError> {
+    let version = index_version(index);
+    if version > NOTEBOOK_INDEX_VERSION {
+        return Err(FsError::InvalidPath(format!(
+            "Notebook index version {} is newer than supported ({})",
+            version, NOTEBOOK_INDEX_VERSION
+        )));
+    }
+    Ok(version < NOTEBOOK_INDEX_VERSION)
+}
+
+/// Upgrade a raw index to the current version
+pub(crate) fn migrate_index(index: &mut Value) -> Result<(), FsError> {
+    needs_migration(index)?;
+    for version in index_version(index)..NOTEBOOK_INDEX_VERSION {
+        MIGRATIONS[version as usize](index)?;
+        index["version"] = Value::from(version + 1);
+    }
+    Ok(())
+}
+
+fn parse_index(content: &str) -> Result<Value, FsError> {
+    serde_json::from_str(content).map_err(|e| FsError::InvalidPath(format!("Invalid index.json: {}", e)))
+}
+
+/// Parse a stored index, upgrading it on disk first when it is outdated
+pub(crate) fn load_notebook_index(
+    notebook_path: &Path,
+    content: &str,
+    session: Option<&EncryptionSession>,
+) -> Result<NotebookIndex, FsError> {
+    let mut value = parse_index(content)?;
+    let outdated = needs_migration(&value)?;
+    let from = index_version(&value);
+    migrate_index(&mut value)?;
+
+    let index: NotebookIndex =
+        serde_json::from_value(value).map_err(|e| FsError::InvalidPath(format!("Invalid index.json: {}", e)))?;
+    if outdated {
+        let index_path = notebook_index_path(notebook_path);
+        fs::copy(&index_path, notebook_path.join(format!(".index.v{}.json.bak", from)))?;
+        write_notebook_index_with(notebook_path, &index, session)?;
+    }
+    Ok(index)
+}
+
+/// Whether a notebook's index is from an older version and will be upgraded
+/// when the notebook is opened
+#[tauri::command]
+pub async fn notebook_needs_migration(
+    notebook_path: PathBuf,
+    state: State<'_, EncryptionState>,
+) -> Result<bool, FsError> {
+    let index_path = notebook_index_path(&notebook_path);
+    if !index_path.exists() {
+        return Ok(false);
+    }
+    let mut content = fs::read_to_string(&index_path)?;
+    if is_encrypted(&content) {
+        content = decrypt_armored(Some(&state.session(&notebook_path)), &content, &notebook_path)?;
+    }
+    needs_migration(&parse_index(&content)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::commands::read_notebook_index;
+
+    #[test]
+    fn migrates_old_indexes() {
+        let dir = tempfile::tempdir().unwrap();
+        let notebook = dir.path().join("Old.md");
+        fs::create_dir(&notebook).unwrap();
+        let legacy = r#"{"blocks":[{"id":"a","type":"markdown","file":"a.md"}]}"#;
+        fs::write(notebook.join(".index.json"), legacy).unwrap();
+
+        let index = read_notebook_index(&notebook).unwrap();
+        assert_eq!(index.version, NOTEBOOK_INDEX_VERSION);
+        assert_eq!(index.blocks[0].id, "a");
+        assert_eq!(fs::read_to_string(notebook.join(".index.v0.json.bak")).unwrap(), legacy);
+        let stored = parse_index(&fs::read_to_string(notebook.join(".index.json")).unwrap()).unwrap();
+        assert!(!needs_migration(&stored).unwrap());
+
+        let mut empty = serde_json::json!({});
+        migrate_index(&mut empty).unwrap();
+        assert_eq!(empty, serde_json::json!({"version": NOTEBOOK_INDEX_VERSION, "blocks": []}));
+
+        fs::write(notebook.join(".index.json"), r#"{"version":99,"blocks":[]}"#).unwrap();
+        assert!(read_notebook_index(&notebook).is_err());
+    }
+}
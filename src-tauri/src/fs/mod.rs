@@ -1,13 +1,85 @@
+pub mod activity;
+pub mod archive;
+pub mod backup;
+pub mod calendar;
+pub mod bulk;
 pub mod commands;
+pub mod completion;
+pub mod diagram;
+pub mod disk_diff;
+pub mod duplicates;
+pub mod echo;
 pub mod encryption;
 pub mod encryption_commands;
+pub mod env;
+pub mod exclude;
+pub mod filter;
+pub mod frontmatter;
+pub mod frontmatter_rules;
+pub mod http;
+pub mod kanban;
+pub mod links;
+pub mod metadata;
+pub mod migrations;
+pub mod notebook;
+pub mod periodic;
+pub mod pipeline;
+pub mod policy;
 pub mod process;
+pub mod query;
+pub mod recent;
+pub mod reencrypt;
+pub mod reminders;
+pub mod results;
+pub mod runtime;
+pub mod scope;
+pub mod search;
+pub mod sql;
+pub mod stats;
+pub mod strength;
+pub mod table;
+pub mod tags;
+pub mod tasks;
+pub mod templates;
 pub mod types;
 pub mod watcher;
+pub mod webhooks;
 
+pub use activity::*;
+pub use archive::*;
+pub use backup::*;
+pub use calendar::*;
+pub use bulk::*;
 pub use commands::*;
+pub use completion::*;
+pub use diagram::*;
+pub use disk_diff::*;
+pub use duplicates::*;
 pub use encryption::*;
 pub use encryption_commands::*;
+pub use env::*;
+pub use filter::*;
+pub use frontmatter_rules::*;
+pub use http::*;
+pub use kanban::*;
+pub use links::*;
+pub use metadata::*;
+pub use migrations::*;
+pub use notebook::*;
+pub use periodic::*;
+pub use pipeline::*;
 pub use process::*;
+pub use query::*;
+pub use recent::*;
+pub use reminders::*;
+pub use results::*;
+pub use scope::VaultScope;
+pub use search::*;
+pub use sql::*;
+pub use stats::*;
+pub use table::*;
+pub use tags::*;
+pub use tasks::*;
+pub use templates::*;
 pub use types::*;
 pub use watcher::*;
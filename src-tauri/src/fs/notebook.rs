@@ -0,0 +1,756 @@
+//! Notebook operations beyond editing single blocks: saving a whole
+//! notebook at once, moving blocks between notebooks, block metadata,
+//! repairing an index out of sync with its files and converting a notebook
+//! back into a note.
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use tauri::State;
+
+use super::commands::{
+    block_file_name, content_hash, copy_dir, encrypt_armored, ensure_unlocked, generate_block_id, is_block_encrypted, is_notebook,
+    load_notebook, notebook_index_path, read_block_content, read_notebook_index_with, write_block_content,
+    write_notebook_index_with, FsError,
+};
+use super::encryption::{is_encrypted, EncryptionSession};
+use super::encryption_commands::EncryptionState;
+use super::policy::apply_encryption_policy;
+use super::results::{read_block_outputs, write_block_outputs};
+use super::table::table_markdown;
+use super::types::{
+    BlockMeta, BlockType, NoteContent, Notebook, NotebookBlock, NotebookBlockWithContent, NotebookIndex, RepairReport,
+};
+
+/// Write the blocks of the new index into `staging`, a copy of the notebook
+fn stage_blocks(
+    notebook_path: &Path,
+    staging: &Path,
+    old_index: &NotebookIndex,
+    blocks: Vec<NotebookBlockWithContent>,
+    session: Option<&EncryptionSession>,
+) -> Result<(), FsError> {
+    let mut index = NotebookIndex {
+        blocks: Vec::new(),
+        ..old_index.clone()
+    };
+    let mut seen = HashSet::new();
+
+    for block in blocks {
+        if !seen.insert(block.id.clone()) {
+            return Err(FsError::InvalidPath(format!("Duplicate block: {}", block.id)));
+        }
+        let existing = old_index.blocks.iter().find(|b| b.id == block.id);
+        let encrypted = existing.map_or(old_index.encrypted, |b| is_block_encrypted(old_index, b));
+        let file = block_file_name(&index, &block.id, &block.block_type, block.language.as_deref());
+
+        let changed = match existing {
+            Some(old) => old.file != file || read_block_content(notebook_path, old_index, old, session)? != block.content,
+            None => true,
+        };
+        if let Some(old) = existing.filter(|_| changed) {
+            ensure_unlocked(old)?;
+        }
+        if changed {
+            write_block_content(staging, encrypted, &file, &block.content, session)?;
+        }
+        // Type or language changes rename the block file
+        if let Some(old) = existing.filter(|old| old.file != file) {
+            let stale = staging.join(&old.file);
+            if stale.exists() {
+                fs::remove_file(stale)?;
+            }
+        }
+
+        index.blocks.push(NotebookBlock {
+            id: block.id,
+            block_type: block.block_type,
+            file,
+            language: block.language,
+            encrypted: encrypted.then_some(true),
+            depends_on: block.depends_on,
+            outputs: block.outputs,
+            cwd: block.cwd,
+            meta: block.meta,
+            // Locking is changed with `set_block_locked` only
+            locked: existing.map_or(block.locked, |old| old.locked),
+        });
+    }
+
+    for removed in old_index.blocks.iter().filter(|b| !seen.contains(&b.id)) {
+        ensure_unlocked(removed)?;
+        let path = staging.join(&removed.file);
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+    }
+    write_notebook_index_with(staging, &index, session)
+}
+
+/// Hidden siblings of a notebook used while saving it: the staged copy and
+/// the previous version moved aside
+fn save_dirs(notebook_path: &Path) -> (PathBuf, PathBuf) {
+    let name = notebook_path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    (
+        notebook_path.with_file_name(format!(".{}.saving", name)),
+        notebook_path.with_file_name(format!(".{}.previous", name)),
+    )
+}
+
+/// Swap the staged copy in for the notebook
+///
+/// The notebook is moved aside and the copy renamed into its place, so the
+/// notebook is always either all old or all new, never an index next to
+/// block files of another version. A save interrupted between the two
+/// renames is undone by `recover_notebook_save`.
+fn commit_staged(staging: &Path, notebook_path: &Path, previous: &Path) -> Result<(), FsError> {
+    fs::rename(notebook_path, previous)?;
+    if let Err(e) = fs::rename(staging, notebook_path) {
+        fs::rename(previous, notebook_path)?;
+        return Err(e.into());
+    }
+    let _ = fs::remove_dir_all(previous);
+    Ok(())
+}
+
+/// Clean up after a save of the notebook that was interrupted, putting the
+/// previous version back if it was moved aside but not replaced
+pub(crate) fn recover_notebook_save(notebook_path: &Path) -> Result<(), FsError> {
+    let (staging, previous) = save_dirs(notebook_path);
+    if previous.is_dir() {
+        if notebook_path.exists() {
+            fs::remove_dir_all(&previous)?;
+        } else {
+            fs::rename(&previous, notebook_path)?;
+        }
+    }
+    if staging.exists() {
+        fs::remove_dir_all(&staging)?;
+    }
+    Ok(())
+}
+
+/// Replace a notebook's blocks and index in one operation
+///
+/// `blocks` is the complete notebook in order: blocks left out are deleted
+/// and unknown IDs are added. Only changed block files are rewritten. The
+/// changes are made in a copy of the notebook which is then swapped in for
+/// the original.
+pub(crate) fn save_notebook_blocks(
+    notebook_path: &Path,
+    blocks: Vec<NotebookBlockWithContent>,
+    session: Option<&EncryptionSession>,
+) -> Result<(), FsError> {
+    recover_notebook_save(notebook_path)?;
+    if !is_notebook(notebook_path) {
+        return Err(FsError::InvalidPath("Not a notebook".to_string()));
+    }
+    let old_index = read_notebook_index_with(notebook_path, session)?;
+
+    let (staging, previous) = save_dirs(notebook_path);
+    let staged = copy_dir(notebook_path, &staging)
+        .and_then(|_| stage_blocks(notebook_path, &staging, &old_index, blocks, session))
+        .and_then(|_| commit_staged(&staging, notebook_path, &previous));
+    if staged.is_err() {
+        let _ = fs::remove_dir_all(&staging);
+    }
+    staged
+}
+
+/// Save all blocks of a notebook and its index at once
+#[tauri::command]
+pub async fn save_notebook(
+    notebook_path: PathBuf,
+    blocks: Vec<NotebookBlockWithContent>,
+    state: State<'_, EncryptionState>,
+) -> Result<Notebook, FsError> {
+    let session = state.session(&notebook_path);
+    let session = Some(session.as_ref());
+    save_notebook_blocks(&notebook_path, blocks, session)?;
+    load_notebook(notebook_path, session)
+}
+
+/// Move or copy a block to another notebook
+///
+/// The block keeps its ID unless the target notebook already has it.
+/// Dependencies on blocks the target does not have are dropped, as are the
+/// source's dependencies on a moved block. The source notebook only changes
+/// once the block is stored in the target.
+pub(crate) fn transfer_block(
+    source: &Path,
+    block_id: &str,
+    target: &Path,
+    position: Option<usize>,
+    copy: bool,
+    session: Option<&EncryptionSession>,
+) -> Result<NotebookBlockWithContent, FsError> {
+    if source == target {
+        return Err(FsError::InvalidPath("Block is already in this notebook".to_string()));
+    }
+    let mut source_index = read_notebook_index_with(source, session)?;
+    let mut target_index = read_notebook_index_with(target, session)?;
+
+    let pos = source_index
+        .blocks
+        .iter()
+        .position(|b| b.id == block_id)
+        .ok_or_else(|| FsError::NotFound(format!("Block not found: {}", block_id)))?;
+    let mut block = source_index.blocks[pos].clone();
+    if !copy {
+        ensure_unlocked(&block)?;
+    }
+    let content = read_block_content(source, &source_index, &block, session)?;
+    if is_block_encrypted(&source_index, &block) && is_encrypted(&content) {
+        return Err(FsError::Encrypted(format!("{} (session is locked)", source.join(&block.file).display())));
+    }
+
+    while target_index.blocks.iter().any(|b| b.id == block.id) {
+        block.id = generate_block_id();
+    }
+    block.file = block_file_name(&target_index, &block.id, &block.block_type, block.language.as_deref());
+    block.depends_on.retain(|dep| target_index.blocks.iter().any(|b| &b.id == dep));
+    let encrypted = target_index.encrypted || block.encrypted.unwrap_or(false);
+    block.encrypted = encrypted.then_some(true);
+
+    let insert_at = position.unwrap_or(target_index.blocks.len()).min(target_index.blocks.len());
+    target_index.blocks.insert(insert_at, block.clone());
+    let stored = write_block_content(target, encrypted, &block.file, &content, session)
+        .and_then(|_| write_notebook_index_with(target, &target_index, session));
+    if let Err(e) = stored {
+        let _ = fs::remove_file(target.join(&block.file));
+        return Err(e);
+    }
+
+    // The last output travels with the block
+    let mut source_outputs = read_block_outputs(source, session).unwrap_or_default();
+    if let Some(output) = source_outputs.get(block_id) {
+        let mut target_outputs = read_block_outputs(target, session)?;
+        target_outputs.insert(block.id.clone(), output.clone());
+        write_block_outputs(target, target_outputs, session)?;
+    }
+
+    if !copy {
+        let removed = source_index.blocks.remove(pos);
+        for other in &mut source_index.blocks {
+            other.depends_on.retain(|dep| dep != block_id);
+        }
+        write_notebook_index_with(source, &source_index, session)?;
+        let source_file = source.join(&removed.file);
+        if source_file.exists() {
+            fs::remove_file(&source_file)?;
+        }
+        source_outputs.remove(block_id);
+        write_block_outputs(source, source_outputs, session)?;
+    }
+
+    Ok(NotebookBlockWithContent::from_block(&block, content))
+}
+
+/// Move a block to another notebook at `position` (the end by default), or
+/// copy it when `copy` is set
+#[tauri::command]
+pub async fn transfer_notebook_block(
+    source_notebook: PathBuf,
+    block_id: String,
+    target_notebook: PathBuf,
+    position: Option<usize>,
+    copy: bool,
+    state: State<'_, EncryptionState>,
+) -> Result<NotebookBlockWithContent, FsError> {
+    transfer_block(&source_notebook, &block_id, &target_notebook, position, copy, Some(&state.session(&source_notebook)))
+}
+
+/// Replace the presentation state (title, collapsed, tags, pinned) of a block
+#[tauri::command]
+pub async fn update_block_metadata(
+    notebook_path: PathBuf,
+    block_id: String,
+    meta: BlockMeta,
+    state: State<'_, EncryptionState>,
+) -> Result<(), FsError> {
+    let session = state.session(&notebook_path);
+    let session = Some(session.as_ref());
+    let mut index = read_notebook_index_with(&notebook_path, session)?;
+    let block = index
+        .blocks
+        .iter_mut()
+        .find(|b| b.id == block_id)
+        .ok_or_else(|| FsError::NotFound(format!("Block not found: {}", block_id)))?;
+    block.meta = BlockMeta {
+        title: meta.title.filter(|t| !t.trim().is_empty()),
+        ..meta
+    };
+    write_notebook_index_with(&notebook_path, &index, session)
+}
+
+/// Lock a block against edits and deletion, or unlock it
+#[tauri::command]
+pub async fn set_block_locked(
+    notebook_path: PathBuf,
+    block_id: String,
+    locked: bool,
+    state: State<'_, EncryptionState>,
+) -> Result<(), FsError> {
+    let session = state.session(&notebook_path);
+    let session = Some(session.as_ref());
+    let mut index = read_notebook_index_with(&notebook_path, session)?;
+    let block = index
+        .blocks
+        .iter_mut()
+        .find(|b| b.id == block_id)
+        .ok_or_else(|| FsError::NotFound(format!("Block not found: {}", block_id)))?;
+    block.locked = locked;
+    write_notebook_index_with(&notebook_path, &index, session)
+}
+
+/// Type and language of a block file found by `repair_notebook`, from its
+/// extension; `None` for files that aren't blocks
+fn adopted_block_kind(extension: &str) -> Option<(BlockType, Option<String>)> {
+    let language = match extension {
+        "md" | "age" => return Some((BlockType::Markdown, None)),
+        "csv" => return Some((BlockType::Table, None)),
+        "tsv" => return Some((BlockType::Table, Some("tsv".to_string()))),
+        "tex" => return Some((BlockType::Math, None)),
+        "py" => "python",
+        "js" => "javascript",
+        "ts" => "typescript",
+        "rs" => "rust",
+        "sql" => "sql",
+        "sh" => "shell",
+        "txt" => "text",
+        _ => return None,
+    };
+    Some((BlockType::Code, Some(language.to_string())))
+}
+
+/// Block ID not in `taken`, `preferred` if possible
+fn unused_block_id(preferred: Option<&str>, taken: &HashSet<String>) -> String {
+    let mut id = preferred.map(str::to_string).unwrap_or_else(generate_block_id);
+    while taken.contains(&id) {
+        id = generate_block_id();
+    }
+    id
+}
+
+/// Bring a notebook's index back in line with its block files
+///
+/// Duplicate IDs get a new ID, entries whose file is missing are removed (or
+/// get an empty file with `recreate_missing`), block files missing from the
+/// index are added and files with the wrong extension for their block are
+/// renamed. An index that can't be parsed is kept as `.index.broken.json` and
+/// rebuilt from the files.
+pub(crate) fn repair_blocks(
+    notebook_path: &Path,
+    recreate_missing: bool,
+    session: Option<&EncryptionSession>,
+) -> Result<RepairReport, FsError> {
+    if !is_notebook(notebook_path) {
+        return Err(FsError::InvalidPath("Not a notebook".to_string()));
+    }
+    let mut report = RepairReport::default();
+    let mut index = match read_notebook_index_with(notebook_path, session) {
+        Ok(index) => index,
+        Err(FsError::InvalidPath(msg)) if msg.starts_with("Invalid index.json") => {
+            fs::copy(notebook_index_path(notebook_path), notebook_path.join(".index.broken.json"))?;
+            report.rebuilt_index = true;
+            NotebookIndex::default()
+        }
+        Err(e) => return Err(e),
+    };
+
+    // Duplicate IDs, from merges that added a block on both sides
+    let mut ids = HashSet::new();
+    let mut blocks: Vec<NotebookBlock> = Vec::new();
+    for mut block in std::mem::take(&mut index.blocks) {
+        if ids.contains(&block.id) {
+            if blocks.iter().any(|b| b.file == block.file) {
+                report.pruned.push(block.id);
+                continue;
+            }
+            let old_id = std::mem::replace(&mut block.id, unused_block_id(None, &ids));
+            let file = block_file_name(&index, &block.id, &block.block_type, block.language.as_deref());
+            let old_path = notebook_path.join(&block.file);
+            if old_path.exists() {
+                fs::rename(old_path, notebook_path.join(&file))?;
+            }
+            block.file = file;
+            report.reassigned_ids.push(format!("{} -> {}", old_id, block.id));
+        }
+        ids.insert(block.id.clone());
+        blocks.push(block);
+    }
+
+    // Entries without a file
+    let mut kept = Vec::new();
+    for block in blocks {
+        if notebook_path.join(&block.file).exists() {
+            kept.push(block);
+        } else if recreate_missing {
+            write_block_content(notebook_path, is_block_encrypted(&index, &block), &block.file, "", session)?;
+            report.recreated.push(block.id.clone());
+            kept.push(block);
+        } else {
+            ids.remove(&block.id);
+            report.pruned.push(block.id);
+        }
+    }
+
+    // Files without an entry
+    let referenced: HashSet<String> = kept.iter().map(|b| b.file.clone()).collect();
+    let mut files: Vec<PathBuf> = fs::read_dir(notebook_path)?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .collect();
+    files.sort();
+    for path in files {
+        let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        if name.starts_with('.') || referenced.contains(&name) {
+            continue;
+        }
+        let kind = path.extension().and_then(|e| adopted_block_kind(&e.to_string_lossy()));
+        let Some((block_type, language)) = kind else {
+            continue;
+        };
+        let stem = path.file_stem().map(|s| s.to_string_lossy().to_string());
+        let id = unused_block_id(stem.as_deref(), &ids);
+        let encrypted = is_encrypted(&fs::read_to_string(&path).unwrap_or_default());
+        ids.insert(id.clone());
+        kept.push(NotebookBlock {
+            id,
+            block_type,
+            file: name.clone(),
+            language,
+            encrypted: (encrypted && !index.encrypted).then_some(true),
+            ..Default::default()
+        });
+        report.adopted.push(name);
+    }
+
+    // Files named for another type or language
+    for block in &mut kept {
+        let expected = block_file_name(&index, &block.id, &block.block_type, block.language.as_deref());
+        if block.file != expected && !notebook_path.join(&expected).exists() {
+            fs::rename(notebook_path.join(&block.file), notebook_path.join(&expected))?;
+            report.renamed_files.push(format!("{} -> {}", block.file, expected));
+            block.file = expected;
+        }
+    }
+
+    let mut dangling = false;
+    for block in &mut kept {
+        let before = block.depends_on.len();
+        block.depends_on.retain(|dep| ids.contains(dep));
+        dangling |= block.depends_on.len() != before;
+    }
+
+    let changed = report.rebuilt_index
+        || dangling
+        || !(report.adopted.is_empty()
+            && report.pruned.is_empty()
+            && report.recreated.is_empty()
+            && report.reassigned_ids.is_empty()
+            && report.renamed_files.is_empty());
+    if changed {
+        index.blocks = kept;
+        write_notebook_index_with(notebook_path, &index, session)?;
+    }
+    Ok(report)
+}
+
+/// Repair a notebook whose index and block files got out of sync, e.g. after
+/// a git merge
+#[tauri::command]
+pub async fn repair_notebook(
+    notebook_path: PathBuf,
+    recreate_missing: Option<bool>,
+    state: State<'_, EncryptionState>,
+) -> Result<RepairReport, FsError> {
+    repair_blocks(&notebook_path, recreate_missing.unwrap_or(false), Some(&state.session(&notebook_path)))
+}
+
+/// Fenced code block with a fence longer than any backtick run in `code`
+fn fenced_code(language: Option<&str>, code: &str) -> String {
+    let mut longest = 0;
+    let mut run = 0;
+    for c in code.chars() {
+        run = if c == '`' { run + 1 } else { 0 };
+        longest = longest.max(run);
+    }
+    let fence = "`".repeat(longest.max(2) + 1);
+    format!("{}{}\n{}\n{}", fence, language.unwrap_or_default(), code.trim_end_matches('\n'), fence)
+}
+
+/// Display math in markdown for the content of a math block
+pub(crate) fn math_markdown(tex: &str) -> String {
+    format!("$$\n{}\n$$", tex.trim())
+}
+
+/// Content of a markdown block holding only display math, without the `$$`
+pub(crate) fn strip_math_delimiters(markdown: &str) -> String {
+    let trimmed = markdown.trim();
+    match trimmed.strip_prefix("$$").and_then(|m| m.strip_suffix("$$")) {
+        Some(tex) if !tex.contains("$$") => tex.trim().to_string(),
+        _ => markdown.to_string(),
+    }
+}
+
+/// Markdown of a notebook as a single note, and whether any of its blocks
+/// is encrypted
+pub(crate) fn notebook_markdown(
+    notebook_path: &Path,
+    session: Option<&EncryptionSession>,
+) -> Result<(String, bool), FsError> {
+    let index = read_notebook_index_with(notebook_path, session)?;
+    let mut parts = Vec::new();
+    let mut encrypted = false;
+    for block in &index.blocks {
+        let content = read_block_content(notebook_path, &index, block, session)?;
+        if is_block_encrypted(&index, block) {
+            if is_encrypted(&content) {
+                return Err(FsError::Encrypted(format!(
+                    "{} (session is locked)",
+                    notebook_path.join(&block.file).display()
+                )));
+            }
+            encrypted = true;
+        }
+        parts.push(match block.block_type {
+            BlockType::Markdown => content.trim_end().to_string(),
+            BlockType::Code => fenced_code(block.language.as_deref(), &content),
+            BlockType::Table => table_markdown(&content, block.language.as_deref()),
+            BlockType::Math => math_markdown(&content),
+        });
+    }
+    let mut markdown = parts.join("\n\n");
+    markdown.push('\n');
+    Ok((markdown, encrypted))
+}
+
+/// Replace a notebook with a single markdown note
+///
+/// Markdown blocks are kept verbatim and code blocks become fenced code.
+/// A notebook with encrypted blocks becomes an encrypted note. Stored
+/// outputs are dropped; the `.assets` folder is shared and stays.
+#[tauri::command]
+pub async fn convert_notebook_to_note(
+    notebook_path: PathBuf,
+    state: State<'_, EncryptionState>,
+) -> Result<NoteContent, FsError> {
+    if !is_notebook(&notebook_path) {
+        return Err(FsError::InvalidPath("Not a notebook".to_string()));
+    }
+    let session = state.session(&notebook_path);
+    let session = Some(session.as_ref());
+    let (content, encrypted) = notebook_markdown(&notebook_path, session)?;
+    let stored = if encrypted {
+        encrypt_armored(session, &content, &notebook_path)?
+    } else {
+        apply_encryption_policy(&notebook_path, content.clone(), session)?
+    };
+
+    // Write next to the notebook first so a failure leaves it untouched
+    let name = notebook_path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let temp_path = notebook_path.with_file_name(format!(".{}.converting", name));
+    let hash = content_hash(&stored);
+    fs::write(&temp_path, stored)?;
+    if let Err(e) = fs::remove_dir_all(&notebook_path) {
+        let _ = fs::remove_file(&temp_path);
+        return Err(e.into());
+    }
+    fs::rename(&temp_path, &notebook_path)?;
+
+    let modified = fs::metadata(&notebook_path)?
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    Ok(NoteContent {
+        path: notebook_path,
+        content,
+        modified,
+        hash,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn notebook(dir: &Path, name: &str, ids: &[&str]) -> PathBuf {
+        let path = dir.join(name);
+        fs::create_dir(&path).unwrap();
+        let blocks = ids
+            .iter()
+            .map(|id| {
+                fs::write(path.join(format!("{}.py", id)), format!("print('{}')", id)).unwrap();
+                NotebookBlock {
+                    id: id.to_string(),
+                    block_type: BlockType::Code,
+                    file: format!("{}.py", id),
+                    language: Some("python".to_string()),
+                    ..Default::default()
+                }
+            })
+            .collect();
+        write_notebook_index_with(&path, &NotebookIndex { blocks, ..Default::default() }, None).unwrap();
+        path
+    }
+
+    #[test]
+    fn transfers_blocks() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = notebook(dir.path(), "A.md", &["a", "b"]);
+        let target = notebook(dir.path(), "B.md", &["b"]);
+        let mut index = read_notebook_index_with(&source, None).unwrap();
+        index.blocks[0].depends_on = vec!["b".to_string()];
+        write_notebook_index_with(&source, &index, None).unwrap();
+
+        let moved = transfer_block(&source, "b", &target, Some(0), false, None).unwrap();
+        assert_ne!(moved.id, "b");
+        assert_eq!(moved.content, "print('b')");
+        let target_index = read_notebook_index_with(&target, None).unwrap();
+        assert_eq!(target_index.blocks[0].id, moved.id);
+        assert!(target.join(format!("{}.py", moved.id)).exists());
+
+        let source_index = read_notebook_index_with(&source, None).unwrap();
+        assert_eq!(source_index.blocks.len(), 1);
+        assert!(source_index.blocks[0].depends_on.is_empty());
+        assert!(!source.join("b.py").exists());
+
+        let copied = transfer_block(&source, "a", &target, None, true, None).unwrap();
+        assert_eq!(copied.id, "a");
+        assert!(source.join("a.py").exists());
+        assert_eq!(read_notebook_index_with(&target, None).unwrap().blocks.len(), 3);
+    }
+
+    #[test]
+    fn renders_notebook_as_markdown() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = notebook(dir.path(), "Run.md", &["code"]);
+        fs::write(path.join("intro.md"), "# Run\n\nSteps:\n").unwrap();
+        fs::write(path.join("code.py"), "print('```')\n").unwrap();
+        let mut index = read_notebook_index_with(&path, None).unwrap();
+        index.blocks.insert(
+            0,
+            NotebookBlock {
+                id: "intro".to_string(),
+                file: "intro.md".to_string(),
+                ..Default::default()
+            },
+        );
+        write_notebook_index_with(&path, &index, None).unwrap();
+
+        let (markdown, encrypted) = notebook_markdown(&path, None).unwrap();
+        assert!(!encrypted);
+        assert_eq!(markdown, "# Run\n\nSteps:\n\n````python\nprint('```')\n````\n");
+    }
+
+    #[test]
+    fn saves_whole_notebook() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = notebook(dir.path(), "Run.md", &["a", "b", "c"]);
+        let untouched = fs::read_to_string(path.join("c.py")).unwrap();
+
+        let mut blocks = load_notebook(path.clone(), None).unwrap().blocks;
+        blocks.remove(0);
+        blocks.swap(0, 1);
+        blocks[1].content = "print('changed')".to_string();
+        blocks[1].language = Some("shell".to_string());
+        blocks.push(NotebookBlockWithContent {
+            id: "d".to_string(),
+            content: "New".to_string(),
+            ..Default::default()
+        });
+        save_notebook_blocks(&path, blocks, None).unwrap();
+
+        let index = read_notebook_index_with(&path, None).unwrap();
+        let ids: Vec<&str> = index.blocks.iter().map(|b| b.id.as_str()).collect();
+        assert_eq!(ids, vec!["c", "b", "d"]);
+        assert_eq!(index.blocks[1].file, "b.sh");
+        assert_eq!(fs::read_to_string(path.join("b.sh")).unwrap(), "print('changed')");
+        assert_eq!(fs::read_to_string(path.join("d.md")).unwrap(), "New");
+        assert!(!path.join("a.py").exists() && !path.join("b.py").exists());
+        assert_eq!(fs::read_to_string(path.join("c.py")).unwrap(), untouched);
+        assert!(!dir.path().join(".Run.md.saving").exists());
+
+        let duplicate = vec![NotebookBlockWithContent { id: "c".to_string(), ..Default::default() }; 2];
+        assert!(save_notebook_blocks(&path, duplicate, None).is_err());
+        assert_eq!(read_notebook_index_with(&path, None).unwrap().blocks.len(), 3);
+
+        let mut index = read_notebook_index_with(&path, None).unwrap();
+        index.blocks[0].locked = true;
+        write_notebook_index_with(&path, &index, None).unwrap();
+        let blocks = load_notebook(path.clone(), None).unwrap().blocks;
+        assert!(matches!(save_notebook_blocks(&path, blocks[1..].to_vec(), None), Err(FsError::Locked(_))));
+        let mut edited = blocks.clone();
+        edited[0].content = "edited".to_string();
+        assert!(matches!(save_notebook_blocks(&path, edited, None), Err(FsError::Locked(_))));
+        save_notebook_blocks(&path, blocks, None).unwrap();
+        assert!(!dir.path().join(".Run.md.previous").exists());
+    }
+
+    #[test]
+    fn recovers_interrupted_save() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = notebook(dir.path(), "Run.md", &["a", "b"]);
+        let (staging, previous) = save_dirs(&path);
+
+        // Interrupted between moving the notebook aside and swapping in the copy
+        copy_dir(&path, &staging).unwrap();
+        fs::remove_file(staging.join("a.py")).unwrap();
+        fs::rename(&path, &previous).unwrap();
+
+        recover_notebook_save(&path).unwrap();
+        assert!(path.join("a.py").exists());
+        assert!(!staging.exists() && !previous.exists());
+        assert_eq!(load_notebook(path.clone(), None).unwrap().blocks.len(), 2);
+    }
+
+    #[test]
+    fn repairs_notebook() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = notebook(dir.path(), "Merged.md", &["a", "b", "c"]);
+        let mut index = read_notebook_index_with(&path, None).unwrap();
+        fs::remove_file(path.join("c.py")).unwrap();
+        fs::write(path.join("x.py"), "print('x')").unwrap();
+        fs::write(path.join("d.txt"), "# Notes").unwrap();
+        fs::write(path.join("e.sh"), "echo e").unwrap();
+        fs::write(path.join("image.png"), "").unwrap();
+        index.blocks[0].depends_on = vec!["c".to_string()];
+        index.blocks.push(NotebookBlock {
+            file: "x.py".to_string(),
+            ..index.blocks[1].clone()
+        });
+        index.blocks.push(NotebookBlock {
+            id: "d".to_string(),
+            file: "d.txt".to_string(),
+            ..Default::default()
+        });
+        write_notebook_index_with(&path, &index, None).unwrap();
+
+        let report = repair_blocks(&path, false, None).unwrap();
+        assert_eq!(report.pruned, vec!["c"]);
+        assert_eq!(report.adopted, vec!["e.sh"]);
+        assert_eq!(report.renamed_files, vec!["d.txt -> d.md"]);
+        assert_eq!(report.reassigned_ids.len(), 1);
+
+        let index = read_notebook_index_with(&path, None).unwrap();
+        let ids: Vec<&str> = index.blocks.iter().map(|b| b.id.as_str()).collect();
+        assert_eq!(ids.len(), 5);
+        assert_eq!(&ids[..2], &["a", "b"]);
+        assert_eq!(ids[4], "e");
+        assert_eq!(fs::read_to_string(path.join(&index.blocks[2].file)).unwrap(), "print('x')");
+        assert!(index.blocks[0].depends_on.is_empty());
+        assert_eq!(index.blocks[4].language.as_deref(), Some("shell"));
+
+        fs::write(path.join(".index.json"), "<<<<<<< HEAD").unwrap();
+        let report = repair_blocks(&path, false, None).unwrap();
+        assert!(report.rebuilt_index);
+        assert_eq!(report.adopted.len(), 5);
+        assert!(path.join(".index.broken.json").exists());
+        assert!(repair_blocks(&path, false, None).unwrap().adopted.is_empty());
+    }
+}
@@ -0,0 +1,197 @@
+//! Daily, weekly, monthly and quarterly notes.
+//!
+//! Each kind has a path pattern and an optional template in the vault
+//! config. Both are expanded for the first day of the period, so weeks start
+//! on Monday and quarters on the first of January, April, July and October.
+
+use chrono::{Datelike, Days, Local, Months, NaiveDate, NaiveTime, TimeZone};
+use std::path::{Path, PathBuf};
+use tauri::State;
+
+use super::commands::{read_vault_config, validate_path, write_atomic, FsError};
+use super::encryption_commands::EncryptionState;
+use super::policy::apply_encryption_policy;
+use super::templates::{read_vault_template, TemplateContext};
+use super::types::{PeriodKind, PeriodicNote, PeriodicSettings};
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+fn parse_date(date: &str) -> Result<NaiveDate, FsError> {
+    NaiveDate::parse_from_str(date.trim(), DATE_FORMAT)
+        .map_err(|_| FsError::InvalidPath(format!("Invalid date: {}", date)))
+}
+
+/// First day of the period containing `date`
+fn period_start(kind: PeriodKind, date: NaiveDate) -> NaiveDate {
+    match kind {
+        PeriodKind::Daily => date,
+        PeriodKind::Weekly => date - Days::new(date.weekday().num_days_from_monday() as u64),
+        PeriodKind::Monthly => date.with_day(1).unwrap_or(date),
+        PeriodKind::Quarterly => {
+            let month = (date.month0() / 3) * 3 + 1;
+            NaiveDate::from_ymd_opt(date.year(), month, 1).unwrap_or(date)
+        }
+    }
+}
+
+/// Start of the period `offset` periods away from the one starting at `start`
+fn shift_period(kind: PeriodKind, start: NaiveDate, offset: i32) -> Option<NaiveDate> {
+    let steps = offset.unsigned_abs();
+    match kind {
+        PeriodKind::Daily | PeriodKind::Weekly => {
+            let days = Days::new(steps as u64 * if kind == PeriodKind::Weekly { 7 } else { 1 });
+            if offset < 0 {
+                start.checked_sub_days(days)
+            } else {
+                start.checked_add_days(days)
+            }
+        }
+        PeriodKind::Monthly | PeriodKind::Quarterly => {
+            let months = Months::new(steps * if kind == PeriodKind::Quarterly { 3 } else { 1 });
+            if offset < 0 {
+                start.checked_sub_months(months)
+            } else {
+                start.checked_add_months(months)
+            }
+        }
+    }
+}
+
+/// Template context for a period, dated at its first day
+fn period_context(vault: &Path, start: NaiveDate) -> TemplateContext {
+    let mut context = TemplateContext::for_path(vault, "");
+    context.vars.remove("title");
+    context.vars.insert("quarter".to_string(), (start.month0() / 3 + 1).to_string());
+    if let Some(now) = Local.from_local_datetime(&start.and_time(NaiveTime::MIN)).earliest() {
+        context.now = now;
+    }
+    context
+}
+
+fn periodic_note(vault: &Path, kind: PeriodKind, date: NaiveDate) -> Result<PeriodicNote, FsError> {
+    periodic_note_with(vault, &read_vault_config(vault)?.periodic, kind, date)
+}
+
+/// Periodic note for the period containing `date`, under given settings
+pub(crate) fn periodic_note_with(
+    vault: &Path,
+    periodic: &PeriodicSettings,
+    kind: PeriodKind,
+    date: NaiveDate,
+) -> Result<PeriodicNote, FsError> {
+    let settings = periodic.get(kind);
+    let start = period_start(kind, date);
+    let end = shift_period(kind, start, 1)
+        .and_then(|next| next.pred_opt())
+        .unwrap_or(start);
+
+    let relative = period_context(vault, start).render(&settings.path)?;
+    let relative = relative.trim().trim_end_matches(".md");
+    if relative.is_empty() {
+        return Err(FsError::InvalidPath(format!("Empty {:?} note path", kind)));
+    }
+    let path = validate_path(vault, Path::new(&format!("{}.md", relative)))?;
+    let title = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    Ok(PeriodicNote {
+        kind,
+        exists: path.exists(),
+        path,
+        title,
+        start: start.format(DATE_FORMAT).to_string(),
+        end: end.format(DATE_FORMAT).to_string(),
+    })
+}
+
+/// Periodic note for the period containing `date` (`YYYY-MM-DD`)
+///
+/// With `create`, a missing note is created from the kind's template.
+#[tauri::command]
+pub async fn get_periodic_note(
+    vault_path: PathBuf,
+    kind: PeriodKind,
+    date: String,
+    create: bool,
+    state: State<'_, EncryptionState>,
+) -> Result<PeriodicNote, FsError> {
+    let mut note = periodic_note(&vault_path, kind, parse_date(&date)?)?;
+    if note.exists || !create {
+        return Ok(note);
+    }
+
+    let config = read_vault_config(&vault_path)?;
+    let template = match &config.periodic.get(kind).template {
+        Some(name) => read_vault_template(&vault_path, name)?,
+        None => None,
+    };
+    let mut context = period_context(&vault_path, parse_date(&note.start)?);
+    context.vars.insert("title".to_string(), note.title.clone());
+    let content = context.render(template.as_deref().unwrap_or("# {{title}}\n\n"))?;
+
+    if let Some(parent) = note.path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let content = apply_encryption_policy(&note.path, content, Some(&state.session(&vault_path)))?;
+    write_atomic(&note.path, content)?;
+    note.exists = true;
+    Ok(note)
+}
+
+/// Periodic note `offset` periods before (negative) or after the one
+/// containing `date`, without creating it
+#[tauri::command]
+pub fn get_adjacent_periodic_note(
+    vault_path: PathBuf,
+    kind: PeriodKind,
+    date: String,
+    offset: i32,
+) -> Result<PeriodicNote, FsError> {
+    let start = period_start(kind, parse_date(&date)?);
+    let target = shift_period(kind, start, offset)
+        .ok_or_else(|| FsError::InvalidPath(format!("Date out of range: {}", date)))?;
+    periodic_note(&vault_path, kind, target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::commands::write_vault_config;
+    use crate::fs::types::VaultConfig;
+    use tempfile::TempDir;
+
+    fn day(date: &str) -> NaiveDate {
+        parse_date(date).unwrap()
+    }
+
+    #[test]
+    fn finds_period_bounds() {
+        assert_eq!(period_start(PeriodKind::Weekly, day("2024-03-09")), day("2024-03-04"));
+        assert_eq!(period_start(PeriodKind::Monthly, day("2024-03-09")), day("2024-03-01"));
+        assert_eq!(period_start(PeriodKind::Quarterly, day("2024-05-20")), day("2024-04-01"));
+        assert_eq!(shift_period(PeriodKind::Quarterly, day("2024-01-01"), -1), Some(day("2023-10-01")));
+        assert_eq!(shift_period(PeriodKind::Weekly, day("2024-03-04"), 2), Some(day("2024-03-18")));
+    }
+
+    #[test]
+    fn resolves_paths_from_config() {
+        let dir = TempDir::new().unwrap();
+        let vault = dir.path();
+        write_vault_config(vault, &VaultConfig::default()).unwrap();
+
+        let note = periodic_note(vault, PeriodKind::Weekly, day("2024-01-03")).unwrap();
+        assert_eq!(note.path, vault.join("weekly/2024-W01.md"));
+        assert_eq!((note.start.as_str(), note.end.as_str()), ("2024-01-01", "2024-01-07"));
+        assert!(!note.exists);
+
+        let note = periodic_note(vault, PeriodKind::Quarterly, day("2024-11-15")).unwrap();
+        assert_eq!(note.path, vault.join("quarterly/2024-Q4.md"));
+        assert_eq!(note.title, "2024-Q4");
+
+        let note = get_adjacent_periodic_note(vault.to_path_buf(), PeriodKind::Monthly, "2024-01-31".to_string(), 1).unwrap();
+        assert_eq!(note.path, vault.join("monthly/2024-02.md"));
+        assert_eq!(note.end, "2024-02-29");
+    }
+}
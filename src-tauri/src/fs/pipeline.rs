@@ -0,0 +1,314 @@
+//! Running notebook code blocks in dependency order.
+//!
+//! Blocks declare `depends_on` and `outputs` in the notebook index. A block
+//! sets its outputs by writing `name=value` lines (or a JSON object) to the
+//! file named by `NOTEMAKER_OUTPUTS`; a block with a single declared output
+//! that writes nothing passes its trimmed stdout instead. Downstream blocks
+//! get each upstream output as an upper-case environment variable and all of
+//! them in the JSON file named by `NOTEMAKER_INPUTS`.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::State;
+
+use super::commands::{
+    read_block_content, read_notebook_index_with, run_code_block, write_notebook_index_with, CodeExecutionResult,
+    FsError,
+};
+use super::encryption_commands::EncryptionState;
+use super::env::parse_dotenv;
+use super::results::record_block_output;
+use super::runtime::{resolve_working_dir, ScratchDir};
+use super::types::{BlockType, NotebookIndex};
+
+/// Outcome of one block of a notebook run
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct BlockRun {
+    pub block_id: String,
+    pub result: CodeExecutionResult,
+    /// Declared outputs the block produced
+    pub outputs: BTreeMap<String, String>,
+}
+
+/// Outcome of running a notebook
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct NotebookRun {
+    /// Blocks that ran, in execution order
+    pub runs: Vec<BlockRun>,
+    /// Block whose failure stopped the run
+    pub failed: Option<String>,
+}
+
+/// Indices of the code blocks to run, dependencies first
+///
+/// Blocks without ordering constraints keep their notebook order. With a
+/// `target` only that block and its upstream blocks are included.
+pub(crate) fn execution_order(index: &NotebookIndex, target: Option<&str>) -> Result<Vec<usize>, FsError> {
+    let positions: HashMap<&str, usize> = index.blocks.iter().enumerate().map(|(i, b)| (b.id.as_str(), i)).collect();
+    for block in &index.blocks {
+        if let Some(missing) = block.depends_on.iter().find(|dep| !positions.contains_key(dep.as_str())) {
+            return Err(FsError::NotFound(format!("Block {} depends on unknown block {}", block.id, missing)));
+        }
+    }
+
+    let mut included: HashSet<usize> = HashSet::new();
+    match target {
+        Some(target) => {
+            let start = *positions
+                .get(target)
+                .ok_or_else(|| FsError::NotFound(format!("Block not found: {}", target)))?;
+            let mut stack = vec![start];
+            while let Some(i) = stack.pop() {
+                if included.insert(i) {
+                    stack.extend(index.blocks[i].depends_on.iter().map(|dep| positions[dep.as_str()]));
+                }
+            }
+        }
+        None => included.extend(0..index.blocks.len()),
+    }
+
+    let mut order = Vec::new();
+    let mut done: HashSet<usize> = HashSet::new();
+    while done.len() < included.len() {
+        let next = (0..index.blocks.len()).find(|i| {
+            included.contains(i)
+                && !done.contains(i)
+                && index.blocks[*i]
+                    .depends_on
+                    .iter()
+                    .all(|dep| done.contains(&positions[dep.as_str()]))
+        });
+        let Some(next) = next else {
+            return Err(FsError::InvalidPath("Block dependencies form a cycle".to_string()));
+        };
+        done.insert(next);
+        order.push(next);
+    }
+
+    Ok(order
+        .into_iter()
+        .filter(|&i| index.blocks[i].block_type == BlockType::Code)
+        .collect())
+}
+
+/// Environment variable name of an output
+fn output_env_name(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+        .collect()
+}
+
+/// Values a block wrote to its outputs file, limited to the declared names
+fn read_outputs(file: &Path, declared: &[String], stdout: &str) -> BTreeMap<String, String> {
+    let content = fs::read_to_string(file).unwrap_or_default();
+    let written: BTreeMap<String, String> = if content.trim_start().starts_with('{') {
+        serde_json::from_str::<BTreeMap<String, serde_json::Value>>(&content)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(k, v)| match v {
+                serde_json::Value::String(s) => (k, s),
+                other => (k, other.to_string()),
+            })
+            .collect()
+    } else {
+        parse_dotenv(&content)
+    };
+
+    let mut outputs: BTreeMap<String, String> = declared
+        .iter()
+        .filter_map(|name| written.get(name).map(|value| (name.clone(), value.clone())))
+        .collect();
+    if outputs.is_empty() && declared.len() == 1 {
+        outputs.insert(declared[0].clone(), stdout.trim().to_string());
+    }
+    outputs
+}
+
+/// Run a notebook's code blocks in dependency order
+///
+/// `target` runs just that block and the blocks it depends on. The run stops
+/// at the first block that fails. Each result is stored with the notebook.
+pub(crate) fn run_notebook(
+    notebook_path: &Path,
+    target: Option<&str>,
+    working_dir: &Path,
+    state: &EncryptionState,
+) -> Result<NotebookRun, FsError> {
+    let session = state.session(notebook_path);
+    let session = Some(session.as_ref());
+    let index = read_notebook_index_with(notebook_path, session)?;
+    let scratch = ScratchDir::create()?;
+
+    let mut produced: BTreeMap<String, BTreeMap<String, String>> = BTreeMap::new();
+    let mut run = NotebookRun {
+        runs: Vec::new(),
+        failed: None,
+    };
+
+    for i in execution_order(&index, target)? {
+        let block = &index.blocks[i];
+        let code = read_block_content(notebook_path, &index, block, session)?;
+        let language = block.language.clone().unwrap_or_else(|| "shell".to_string());
+
+        let inputs: BTreeMap<&String, &BTreeMap<String, String>> = block
+            .depends_on
+            .iter()
+            .filter_map(|dep| produced.get(dep).map(|outputs| (dep, outputs)))
+            .collect();
+        let inputs_file = scratch.path().join(format!("{}.inputs.json", block.id));
+        let outputs_file = scratch.path().join(format!("{}.outputs", block.id));
+        fs::write(
+            &inputs_file,
+            serde_json::to_string(&inputs).map_err(|e| FsError::InvalidPath(e.to_string()))?,
+        )?;
+        fs::write(&outputs_file, "")?;
+
+        let mut env: BTreeMap<String, String> = inputs
+            .values()
+            .flat_map(|outputs| outputs.iter().map(|(k, v)| (output_env_name(k), v.clone())))
+            .collect();
+        env.insert("NOTEMAKER_INPUTS".to_string(), inputs_file.to_string_lossy().to_string());
+        env.insert("NOTEMAKER_OUTPUTS".to_string(), outputs_file.to_string_lossy().to_string());
+
+        let block_dir = resolve_working_dir(Some(working_dir.to_path_buf()), Some(notebook_path), block.cwd.as_deref())?;
+        // Scratch files are on the host, so dependent blocks run outside containers
+        let result = run_code_block(&language, &code, &block_dir, None, Some(notebook_path), Some(false), env)?;
+        record_block_output(notebook_path, &block.id, &result, session)?;
+        let outputs = read_outputs(&outputs_file, &block.outputs, &result.stdout);
+        let failed = result.exit_code != 0;
+        produced.insert(block.id.clone(), outputs.clone());
+        run.runs.push(BlockRun {
+            block_id: block.id.clone(),
+            result,
+            outputs,
+        });
+
+        if failed {
+            run.failed = Some(block.id.clone());
+            break;
+        }
+    }
+
+    Ok(run)
+}
+
+/// Execute a notebook's code blocks, dependencies first
+#[tauri::command]
+pub async fn execute_notebook(
+    notebook_path: PathBuf,
+    target_block_id: Option<String>,
+    working_dir: Option<PathBuf>,
+    state: State<'_, EncryptionState>,
+) -> Result<NotebookRun, FsError> {
+    let work_dir = resolve_working_dir(working_dir, Some(&notebook_path), None)?;
+    run_notebook(&notebook_path, target_block_id.as_deref(), &work_dir, &state)
+}
+
+/// Set the dependencies and outputs a block declares
+#[tauri::command]
+pub async fn set_block_dependencies(
+    notebook_path: PathBuf,
+    block_id: String,
+    depends_on: Vec<String>,
+    outputs: Vec<String>,
+    state: State<'_, EncryptionState>,
+) -> Result<(), FsError> {
+    let session = state.session(&notebook_path);
+    let session = Some(session.as_ref());
+    let mut index = read_notebook_index_with(&notebook_path, session)?;
+
+    let block = index
+        .blocks
+        .iter_mut()
+        .find(|b| b.id == block_id)
+        .ok_or_else(|| FsError::NotFound(format!("Block not found: {}", block_id)))?;
+    block.depends_on = depends_on;
+    block.outputs = outputs;
+
+    // Reject unknown blocks and cycles before saving
+    execution_order(&index, None)?;
+    write_notebook_index_with(&notebook_path, &index, session)
+}
+
+/// Set or clear the working directory a block runs in
+#[tauri::command]
+pub async fn set_block_cwd(
+    notebook_path: PathBuf,
+    block_id: String,
+    cwd: Option<String>,
+    state: State<'_, EncryptionState>,
+) -> Result<(), FsError> {
+    let session = state.session(&notebook_path);
+    let session = Some(session.as_ref());
+    let mut index = read_notebook_index_with(&notebook_path, session)?;
+    let cwd = cwd.filter(|c| !c.trim().is_empty());
+    if let Some(cwd) = &cwd {
+        resolve_working_dir(None, Some(&notebook_path), Some(cwd))?;
+    }
+
+    let block = index
+        .blocks
+        .iter_mut()
+        .find(|b| b.id == block_id)
+        .ok_or_else(|| FsError::NotFound(format!("Block not found: {}", block_id)))?;
+    block.cwd = cwd;
+    write_notebook_index_with(&notebook_path, &index, session)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::types::NotebookBlock;
+
+    fn code_block(id: &str, depends_on: &[&str]) -> NotebookBlock {
+        NotebookBlock {
+            id: id.to_string(),
+            block_type: BlockType::Code,
+            file: format!("{}.sh", id),
+            language: Some("shell".to_string()),
+            depends_on: depends_on.iter().map(|d| d.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn orders_blocks_by_dependencies() {
+        let mut index = NotebookIndex {
+            blocks: vec![
+                code_block("report", &["load", "clean"]),
+                code_block("clean", &["load"]),
+                code_block("load", &[]),
+                code_block("other", &[]),
+            ],
+            ..Default::default()
+        };
+
+        let ids = |order: Vec<usize>| -> Vec<String> { order.into_iter().map(|i| index.blocks[i].id.clone()).collect() };
+        assert_eq!(ids(execution_order(&index, None).unwrap()), vec!["load", "clean", "report", "other"]);
+        assert_eq!(ids(execution_order(&index, Some("clean")).unwrap()), vec!["load", "clean"]);
+
+        index.blocks[2].depends_on = vec!["report".to_string()];
+        assert!(execution_order(&index, None).is_err());
+    }
+
+    #[test]
+    fn reads_declared_outputs() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("outputs");
+
+        fs::write(&file, "rows=42\nignored=1\n").unwrap();
+        let outputs = read_outputs(&file, &["rows".to_string()], "");
+        assert_eq!(outputs, BTreeMap::from([("rows".to_string(), "42".to_string())]));
+
+        fs::write(&file, "{\"rows\": 7, \"name\": \"x\"}").unwrap();
+        let outputs = read_outputs(&file, &["rows".to_string(), "name".to_string()], "");
+        assert_eq!(outputs["rows"], "7");
+        assert_eq!(outputs["name"], "x");
+
+        fs::write(&file, "").unwrap();
+        let outputs = read_outputs(&file, &["result".to_string()], "hello\n");
+        assert_eq!(outputs["result"], "hello");
+        assert_eq!(output_env_name("row-count"), "ROW_COUNT");
+    }
+}
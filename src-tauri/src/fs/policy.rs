@@ -0,0 +1,119 @@
+//! Folder-scoped encryption policies.
+
+use glob::{MatchOptions, Pattern};
+use std::path::Path;
+
+use super::commands::{find_vault_root, read_vault_config, FsError};
+use super::encryption::{armor_encrypt, encrypt_with_recipients, encrypt_with_session, is_encrypted, EncryptionSession};
+use super::types::{EncryptionPolicy, EncryptionSettings};
+
+/// First policy whose pattern matches a vault-relative path
+pub(crate) fn matching_policy<'a>(settings: &'a EncryptionSettings, rel: &Path) -> Option<&'a EncryptionPolicy> {
+    let rel = rel.to_string_lossy().replace('\\', "/");
+    let options = MatchOptions {
+        case_sensitive: true,
+        require_literal_separator: true,
+        require_literal_leading_dot: false,
+    };
+    settings.policies.iter().find(|policy| {
+        Pattern::new(&policy.pattern)
+            .map(|pattern| pattern.matches_with(&rel, options))
+            .unwrap_or(false)
+    })
+}
+
+/// Public keys of the recipients a policy names
+///
+/// Entries are looked up by recipient id or name; anything else is taken as
+/// an age public key.
+fn policy_public_keys(settings: &EncryptionSettings, policy: &EncryptionPolicy) -> Vec<String> {
+    policy
+        .recipients
+        .iter()
+        .map(|entry| {
+            settings
+                .recipients
+                .iter()
+                .find(|r| &r.id == entry || &r.name == entry)
+                .map(|r| r.public_key.clone())
+                .unwrap_or_else(|| entry.clone())
+        })
+        .collect()
+}
+
+/// Encrypt content about to be written to `path` when a vault policy
+/// requires it
+///
+/// Content that is already encrypted, and paths no encrypting policy covers,
+/// are returned unchanged.
+pub(crate) fn apply_encryption_policy(
+    path: &Path,
+    content: String,
+    session: Option<&EncryptionSession>,
+) -> Result<String, FsError> {
+    if is_encrypted(&content) {
+        return Ok(content);
+    }
+    let Some(vault) = find_vault_root(path) else {
+        return Ok(content);
+    };
+    let settings = read_vault_config(&vault)?.encryption;
+    if !settings.enabled {
+        return Ok(content);
+    }
+    let Ok(rel) = path.strip_prefix(&vault) else {
+        return Ok(content);
+    };
+    let Some(policy) = matching_policy(&settings, rel).filter(|p| p.encrypt) else {
+        return Ok(content);
+    };
+
+    let keys = policy_public_keys(&settings, policy);
+    let ciphertext = if keys.is_empty() {
+        let session = session
+            .filter(|s| s.is_unlocked())
+            .ok_or_else(|| FsError::Encrypted(format!("{} must be encrypted (session is locked)", path.display())))?;
+        encrypt_with_session(session, content.as_bytes())
+    } else {
+        encrypt_with_recipients(content.as_bytes(), &keys)
+    }
+    .map_err(|e| FsError::Encrypted(format!("{}: {}", path.display(), e)))?;
+
+    Ok(armor_encrypt(&ciphertext))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::commands::write_vault_config;
+    use crate::fs::encryption::generate_identity;
+    use crate::fs::types::VaultConfig;
+
+    #[test]
+    fn encrypts_only_matching_folders() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = dir.path().join("vault");
+        let key = generate_identity(&dir.path().join("id.txt").to_string_lossy()).unwrap();
+
+        let mut config = VaultConfig::default();
+        config.encryption.enabled = true;
+        config.encryption.policies = vec![
+            EncryptionPolicy {
+                pattern: "Private/**".to_string(),
+                encrypt: true,
+                recipients: vec![key],
+            },
+            EncryptionPolicy {
+                pattern: "**".to_string(),
+                encrypt: false,
+                recipients: Vec::new(),
+            },
+        ];
+        write_vault_config(&vault, &config).unwrap();
+
+        let private = apply_encryption_policy(&vault.join("Private/deep/a.md"), "secret".to_string(), None).unwrap();
+        assert!(is_encrypted(&private));
+        let public = apply_encryption_policy(&vault.join("Public/a.md"), "hello".to_string(), None).unwrap();
+        assert_eq!(public, "hello");
+    }
+}
@@ -1,15 +1,36 @@
 use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Instant;
 use tokio::process::Command;
 use tokio::sync::Mutex;
 
-use super::commands::{CodeExecutionResult, FsError};
+use super::commands::{read_notebook_index, CodeExecutionResult, FsError};
+use super::env::execution_env;
+use super::runtime::{prepare_invocation, resolve_working_dir, ContainerRun};
+
+/// A tracked process
+struct TrackedProcess {
+    pid: u32,
+    language: String,
+    started: Instant,
+    /// Container the process runs the block in
+    container: Option<ContainerRun>,
+}
+
+/// A running execution as shown to the frontend
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RunningExecution {
+    pub block_id: String,
+    pub pid: u32,
+    pub language: String,
+    pub elapsed_ms: u64,
+}
 
 /// Tracks running processes by their PID
 pub struct ProcessManager {
-    /// Map of block_id -> process PID
-    pids: HashMap<String, u32>,
+    /// Map of block_id -> tracked process
+    pids: HashMap<String, TrackedProcess>,
 }
 
 impl ProcessManager {
@@ -19,13 +40,21 @@ impl ProcessManager {
         }
     }
 
-    /// Track a process PID
-    pub fn track(&mut self, block_id: String, pid: u32) {
+    /// Track a process PID and the container it runs in
+    pub(crate) fn track(&mut self, block_id: String, pid: u32, language: String, container: Option<ContainerRun>) {
         // Kill any existing process for this block
-        if let Some(old_pid) = self.pids.remove(&block_id) {
-            Self::kill_pid(old_pid);
+        if let Some(old) = self.pids.remove(&block_id) {
+            Self::stop(&old);
         }
-        self.pids.insert(block_id, pid);
+        self.pids.insert(
+            block_id,
+            TrackedProcess {
+                pid,
+                language,
+                started: Instant::now(),
+                container,
+            },
+        );
     }
 
     /// Remove a process from tracking
@@ -35,13 +64,48 @@ impl ProcessManager {
 
     /// Kill a running process by block ID
     pub fn kill(&mut self, block_id: &str) -> bool {
-        if let Some(pid) = self.pids.remove(block_id) {
-            Self::kill_pid(pid)
+        if let Some(process) = self.pids.remove(block_id) {
+            Self::stop(&process)
         } else {
             false
         }
     }
 
+    /// Running processes, longest running first
+    pub fn running(&self) -> Vec<RunningExecution> {
+        let mut running: Vec<RunningExecution> = self
+            .pids
+            .iter()
+            .map(|(block_id, process)| RunningExecution {
+                block_id: block_id.clone(),
+                pid: process.pid,
+                language: process.language.clone(),
+                elapsed_ms: process.started.elapsed().as_millis() as u64,
+            })
+            .collect();
+        running.sort_by_key(|r| std::cmp::Reverse(r.elapsed_ms));
+        running
+    }
+
+    /// Kill all tracked processes, returning the block IDs that were stopped
+    pub fn kill_all(&mut self) -> Vec<String> {
+        let mut stopped: Vec<String> = self
+            .pids
+            .drain()
+            .filter(|(_, process)| Self::stop(process))
+            .map(|(block_id, _)| block_id)
+            .collect();
+        stopped.sort();
+        stopped
+    }
+
+    /// Kill a tracked process along with its container, which keeps running
+    /// when only the engine client is killed
+    fn stop(process: &TrackedProcess) -> bool {
+        let container_killed = process.container.as_ref().is_some_and(ContainerRun::kill);
+        Self::kill_pid(process.pid) || container_killed
+    }
+
     /// Kill a process by PID
     fn kill_pid(pid: u32) -> bool {
         #[cfg(unix)]
@@ -84,50 +148,43 @@ impl Default for ProcessManager {
 /// Global process manager state (using tokio Mutex for async)
 pub type ProcessState = Arc<Mutex<ProcessManager>>;
 
-/// Get the default interpreter for a language
-fn get_default_interpreter(language: &str) -> &'static str {
-    match language {
-        "shell" => "bash",
-        "python" => "python3",
-        "ruby" => "ruby",
-        _ => "sh",
-    }
+/// Working directory override stored for a block; blocks of notebooks with an
+/// encrypted index run without one
+fn block_cwd(notebook_path: &std::path::Path, block_id: &str) -> Option<String> {
+    read_notebook_index(notebook_path)
+        .ok()?
+        .blocks
+        .into_iter()
+        .find(|b| b.id == block_id)?
+        .cwd
 }
 
 /// Execute a code block asynchronously with process tracking
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn execute_code_block_async(
     block_id: String,
     language: String,
     code: String,
     working_dir: Option<PathBuf>,
     interpreter: Option<String>,
+    notebook_path: Option<PathBuf>,
+    container: Option<bool>,
     process_state: tauri::State<'_, ProcessState>,
 ) -> Result<CodeExecutionResult, FsError> {
-    let work_dir = working_dir.unwrap_or_else(|| std::env::temp_dir());
-    let lang = language.to_lowercase();
-
-    // Validate language
-    if !matches!(lang.as_str(), "shell" | "python" | "ruby") {
-        return Err(FsError::InvalidPath(format!("Unsupported language: {}", language)));
-    }
-
-    let interp = interpreter.unwrap_or_else(|| get_default_interpreter(&lang).to_string());
-
-    // Get the appropriate argument flag for the language
-    let arg_flag = match lang.as_str() {
-        "shell" => "-c",
-        "python" => "-c",
-        "ruby" => "-e",
-        _ => "-c",
-    };
+    let cwd = notebook_path.as_deref().and_then(|notebook| block_cwd(notebook, &block_id));
+    let work_dir = resolve_working_dir(working_dir, notebook_path.as_deref(), cwd.as_deref())?;
+    let env = execution_env(notebook_path.as_deref(), &work_dir)?;
+    let anchor = notebook_path.as_deref().unwrap_or(&work_dir);
+    let invocation = prepare_invocation(&language, &code, interpreter, container, anchor, &work_dir, &env)?;
 
     // Build command with process group on Unix
     #[cfg(unix)]
     let child = {
-        let mut cmd = Command::new(&interp);
-        cmd.arg(arg_flag).arg(&code);
+        let mut cmd = Command::new(&invocation.program);
+        cmd.args(&invocation.args);
         cmd.current_dir(&work_dir);
+        cmd.envs(&env);
         cmd.stdout(std::process::Stdio::piped());
         cmd.stderr(std::process::Stdio::piped());
         // Create new process group for easier termination
@@ -142,9 +199,10 @@ pub async fn execute_code_block_async(
 
     #[cfg(windows)]
     let child = {
-        let mut cmd = Command::new(&interp);
-        cmd.arg(arg_flag).arg(&code);
+        let mut cmd = Command::new(&invocation.program);
+        cmd.args(&invocation.args);
         cmd.current_dir(&work_dir);
+        cmd.envs(&env);
         cmd.stdout(std::process::Stdio::piped());
         cmd.stderr(std::process::Stdio::piped());
         cmd.spawn().map_err(FsError::Io)?
@@ -154,7 +212,7 @@ pub async fn execute_code_block_async(
     let pid = child.id().unwrap_or(0);
     {
         let mut manager = process_state.lock().await;
-        manager.track(block_id.clone(), pid);
+        manager.track(block_id.clone(), pid, language.clone(), invocation.container.clone());
     }
 
     // Wait for the process to complete
@@ -182,3 +240,21 @@ pub async fn terminate_code_block(
     let mut manager = process_state.lock().await;
     Ok(manager.kill(&block_id))
 }
+
+/// List the code blocks that are currently running
+#[tauri::command]
+pub async fn list_running_executions(
+    process_state: tauri::State<'_, ProcessState>,
+) -> Result<Vec<RunningExecution>, FsError> {
+    let manager = process_state.lock().await;
+    Ok(manager.running())
+}
+
+/// Terminate every running code block, e.g. before closing a vault
+#[tauri::command]
+pub async fn terminate_all_executions(
+    process_state: tauri::State<'_, ProcessState>,
+) -> Result<Vec<String>, FsError> {
+    let mut manager = process_state.lock().await;
+    Ok(manager.kill_all())
+}
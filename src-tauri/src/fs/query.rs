@@ -0,0 +1,547 @@
+//! Metadata queries over a vault's notes, for dynamic lists embedded in notes.
+//!
+//! The text form reads
+//!
+//! ```text
+//! [SELECT field, ...] [WHERE] condition [SORT BY field [ASC|DESC], ...] [LIMIT n]
+//! ```
+//!
+//! Conditions compare a field with `=`, `!=`, `<`, `<=`, `>`, `>=` or
+//! `CONTAINS` and combine with `AND`, `OR`, `NOT` and parentheses. Values
+//! are numbers, `true`/`false`, `null`, `YYYY-MM-DD` dates or text, quoted
+//! when it has spaces. Comparing a list tests its items, so
+//! `file.tags = work` matches notes tagged `work`.
+
+use chrono::{DateTime, Local, NaiveDate};
+use serde_json::json;
+use std::cmp::Ordering;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::commands::{read_markdown_text, walk_notes, FsError};
+use super::encryption::is_encrypted;
+use super::frontmatter::parse_frontmatter;
+use super::links::note_name;
+use super::stats::note_stats;
+use super::tags::note_tags;
+use super::types::{NoteQuery, QueryComparison, QueryExpr, QueryInput, QueryResult, QueryRow, QuerySort};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Quoted(String),
+    Op(QueryComparison),
+    Open,
+    Close,
+    Comma,
+}
+
+fn query_error(message: impl Into<String>) -> FsError {
+    FsError::InvalidPath(format!("Invalid query: {}", message.into()))
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, FsError> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' | ')' | ',' => {
+                chars.next();
+                tokens.push(match c {
+                    '(' => Token::Open,
+                    ')' => Token::Close,
+                    _ => Token::Comma,
+                });
+            }
+            '"' | '\'' => {
+                chars.next();
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some(q) if q == c => break,
+                        Some(ch) => value.push(ch),
+                        None => return Err(query_error("unterminated quote")),
+                    }
+                }
+                tokens.push(Token::Quoted(value));
+            }
+            '=' | '!' | '<' | '>' => {
+                chars.next();
+                let equals = chars.next_if_eq(&'=').is_some();
+                let op = match (c, equals) {
+                    ('=', _) => QueryComparison::Eq,
+                    ('!', true) => QueryComparison::Ne,
+                    ('<', false) => QueryComparison::Lt,
+                    ('<', true) => QueryComparison::Le,
+                    ('>', false) => QueryComparison::Gt,
+                    ('>', true) => QueryComparison::Ge,
+                    _ => return Err(query_error("expected !=")),
+                };
+                tokens.push(Token::Op(op));
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(ch) = chars.next_if(|ch| !ch.is_whitespace() && !"()=!<>,\"'".contains(*ch)) {
+                    word.push(ch);
+                }
+                tokens.push(Token::Word(word));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        matches!(self.tokens.get(self.pos), Some(Token::Word(w)) if w.eq_ignore_ascii_case(keyword))
+    }
+
+    fn keyword(&mut self, keyword: &str) -> bool {
+        let found = self.peek_keyword(keyword);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn field(&mut self) -> Result<String, FsError> {
+        match self.next() {
+            Some(Token::Word(word)) | Some(Token::Quoted(word)) => Ok(word),
+            _ => Err(query_error("expected a field name")),
+        }
+    }
+
+    fn query(&mut self) -> Result<NoteQuery, FsError> {
+        let mut query = NoteQuery::default();
+        if self.keyword("SELECT") {
+            query.select.push(self.field()?);
+            while self.tokens.get(self.pos) == Some(&Token::Comma) {
+                self.pos += 1;
+                query.select.push(self.field()?);
+            }
+        }
+        self.keyword("WHERE");
+        if self.pos < self.tokens.len() && !self.peek_keyword("SORT") && !self.peek_keyword("LIMIT") {
+            query.filter = Some(self.or()?);
+        }
+        if self.keyword("SORT") {
+            if !self.keyword("BY") {
+                return Err(query_error("expected SORT BY"));
+            }
+            loop {
+                let field = self.field()?;
+                let descending = self.keyword("DESC");
+                if !descending {
+                    self.keyword("ASC");
+                }
+                query.sort.push(QuerySort { field, descending });
+                if self.tokens.get(self.pos) != Some(&Token::Comma) {
+                    break;
+                }
+                self.pos += 1;
+            }
+        }
+        if self.keyword("LIMIT") {
+            match self.next() {
+                Some(Token::Word(n)) => query.limit = Some(n.parse().map_err(|_| query_error("LIMIT needs a number"))?),
+                _ => return Err(query_error("LIMIT needs a number")),
+            }
+        }
+        if self.pos < self.tokens.len() {
+            return Err(query_error(format!("unexpected {:?}", self.tokens[self.pos])));
+        }
+        Ok(query)
+    }
+
+    fn or(&mut self) -> Result<QueryExpr, FsError> {
+        let mut items = vec![self.and()?];
+        while self.keyword("OR") {
+            items.push(self.and()?);
+        }
+        Ok(if items.len() == 1 { items.remove(0) } else { QueryExpr::Or { items } })
+    }
+
+    fn and(&mut self) -> Result<QueryExpr, FsError> {
+        let mut items = vec![self.unary()?];
+        while self.keyword("AND") {
+            items.push(self.unary()?);
+        }
+        Ok(if items.len() == 1 { items.remove(0) } else { QueryExpr::And { items } })
+    }
+
+    fn unary(&mut self) -> Result<QueryExpr, FsError> {
+        if self.keyword("NOT") {
+            return Ok(QueryExpr::Not {
+                expr: Box::new(self.unary()?),
+            });
+        }
+        if self.tokens.get(self.pos) == Some(&Token::Open) {
+            self.pos += 1;
+            let expr = self.or()?;
+            if self.next() != Some(Token::Close) {
+                return Err(query_error("missing )"));
+            }
+            return Ok(expr);
+        }
+
+        let field = self.field()?;
+        let cmp = match self.next() {
+            Some(Token::Op(op)) => op,
+            Some(Token::Word(w)) if w.eq_ignore_ascii_case("CONTAINS") => QueryComparison::Contains,
+            _ => return Err(query_error(format!("expected a comparison after {}", field))),
+        };
+        let value = match self.next() {
+            Some(Token::Quoted(text)) => json!(text),
+            Some(Token::Word(word)) => literal(&word),
+            _ => return Err(query_error(format!("expected a value for {}", field))),
+        };
+        Ok(QueryExpr::Compare { field, cmp, value })
+    }
+}
+
+/// JSON value of an unquoted word
+fn literal(word: &str) -> serde_json::Value {
+    match word.to_ascii_lowercase().as_str() {
+        "true" => return json!(true),
+        "false" => return json!(false),
+        "null" => return serde_json::Value::Null,
+        _ => {}
+    }
+    match word.parse::<f64>() {
+        Ok(n) if n.is_finite() => json!(n),
+        _ => json!(word),
+    }
+}
+
+/// Parse the text form of a query
+pub(crate) fn parse_query(text: &str) -> Result<NoteQuery, FsError> {
+    Parser {
+        tokens: tokenize(text)?,
+        pos: 0,
+    }
+    .query()
+}
+
+/// A field's value, normalized for comparison
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    Date(NaiveDate),
+    Text(String),
+    List(Vec<Value>),
+}
+
+impl Value {
+    fn text(text: &str) -> Self {
+        // Timestamps compare by their day
+        let date = text
+            .get(..10)
+            .filter(|_| matches!(text.as_bytes().get(10), None | Some(b'T') | Some(b' ')))
+            .and_then(|day| NaiveDate::parse_from_str(day, "%Y-%m-%d").ok());
+        match date {
+            Some(date) => Value::Date(date),
+            None => Value::Text(text.to_string()),
+        }
+    }
+
+    fn from_yaml(value: &serde_yaml::Value) -> Self {
+        match value {
+            serde_yaml::Value::Bool(b) => Value::Bool(*b),
+            serde_yaml::Value::Number(n) => n.as_f64().map_or(Value::Null, Value::Number),
+            serde_yaml::Value::String(s) => Value::text(s),
+            serde_yaml::Value::Sequence(items) => Value::List(items.iter().map(Value::from_yaml).collect()),
+            serde_yaml::Value::Tagged(tagged) => Value::from_yaml(&tagged.value),
+            serde_yaml::Value::Null | serde_yaml::Value::Mapping(_) => Value::Null,
+        }
+    }
+
+    fn from_json(value: &serde_json::Value) -> Self {
+        match value {
+            serde_json::Value::Bool(b) => Value::Bool(*b),
+            serde_json::Value::Number(n) => n.as_f64().map_or(Value::Null, Value::Number),
+            serde_json::Value::String(s) => Value::text(s),
+            serde_json::Value::Array(items) => Value::List(items.iter().map(Value::from_json).collect()),
+            serde_json::Value::Null | serde_json::Value::Object(_) => Value::Null,
+        }
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        match self {
+            Value::Null => serde_json::Value::Null,
+            Value::Bool(b) => json!(b),
+            Value::Number(n) if n.fract() == 0.0 && n.abs() < 1e15 => json!(*n as i64),
+            Value::Number(n) => json!(n),
+            Value::Date(d) => json!(d.format("%Y-%m-%d").to_string()),
+            Value::Text(s) => json!(s),
+            Value::List(items) => serde_json::Value::Array(items.iter().map(Value::to_json).collect()),
+        }
+    }
+
+    /// Order of two scalars of the same kind; text ignores case
+    fn compare(&self, other: &Value) -> Option<Ordering> {
+        match (self, other) {
+            (Value::Number(a), Value::Number(b)) => a.partial_cmp(b),
+            (Value::Date(a), Value::Date(b)) => Some(a.cmp(b)),
+            (Value::Text(a), Value::Text(b)) => Some(a.to_lowercase().cmp(&b.to_lowercase())),
+            (Value::Bool(a), Value::Bool(b)) => Some(a.cmp(b)),
+            // Numbers written as text, e.g. quoted in frontmatter
+            (Value::Text(a), Value::Number(b)) => a.trim().parse::<f64>().ok()?.partial_cmp(b),
+            (Value::Number(a), Value::Text(b)) => a.partial_cmp(&b.trim().parse::<f64>().ok()?),
+            _ => None,
+        }
+    }
+
+    fn equals(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::List(items), _) => items.iter().any(|item| item.equals(other)),
+            (Value::Null, Value::Null) => true,
+            _ => self.compare(other) == Some(Ordering::Equal),
+        }
+    }
+
+    fn contains(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::List(items), _) => items.iter().any(|item| item.equals(other)),
+            (Value::Text(text), Value::Text(part)) => text.to_lowercase().contains(&part.to_lowercase()),
+            _ => false,
+        }
+    }
+
+    fn matches(&self, cmp: QueryComparison, other: &Value) -> bool {
+        let order = |accept: fn(Ordering) -> bool| match self {
+            Value::List(items) => items.iter().any(|item| item.compare(other).is_some_and(accept)),
+            _ => self.compare(other).is_some_and(accept),
+        };
+        match cmp {
+            QueryComparison::Eq => self.equals(other),
+            QueryComparison::Ne => !self.equals(other),
+            QueryComparison::Lt => order(Ordering::is_lt),
+            QueryComparison::Le => order(Ordering::is_le),
+            QueryComparison::Gt => order(Ordering::is_gt),
+            QueryComparison::Ge => order(Ordering::is_ge),
+            QueryComparison::Contains => self.contains(other),
+        }
+    }
+}
+
+/// What a query can see of one note
+struct NoteRecord {
+    path: PathBuf,
+    name: String,
+    folder: String,
+    frontmatter: serde_yaml::Mapping,
+    tags: Vec<String>,
+    modified: Option<NaiveDate>,
+    tasks: usize,
+    done_tasks: usize,
+}
+
+impl NoteRecord {
+    fn load(vault: &Path, path: PathBuf) -> Option<Self> {
+        let content = read_markdown_text(&path).ok()?;
+        if is_encrypted(&content) {
+            return None;
+        }
+        let stats = note_stats(&content);
+        let folder = path
+            .parent()
+            .and_then(|dir| dir.strip_prefix(vault).ok())
+            .map(|dir| dir.to_string_lossy().replace('\\', "/"))
+            .unwrap_or_default();
+        let modified = fs::metadata(&path)
+            .and_then(|m| m.modified())
+            .ok()
+            .map(|time| DateTime::<Local>::from(time).date_naive());
+        Some(Self {
+            name: note_name(&path),
+            folder,
+            frontmatter: parse_frontmatter(&content).unwrap_or_default(),
+            tags: note_tags(&content),
+            modified,
+            tasks: stats.tasks,
+            done_tasks: stats.completed_tasks,
+            path,
+        })
+    }
+
+    fn field(&self, name: &str) -> Value {
+        match name {
+            "file.name" => Value::Text(self.name.clone()),
+            "file.folder" => Value::Text(self.folder.clone()),
+            "file.path" => Value::Text(self.path.to_string_lossy().to_string()),
+            "file.modified" => self.modified.map_or(Value::Null, Value::Date),
+            "file.tags" => Value::List(self.tags.iter().map(|t| Value::Text(t.clone())).collect()),
+            "file.tasks" => Value::Number(self.tasks as f64),
+            "file.done_tasks" => Value::Number(self.done_tasks as f64),
+            "file.open_tasks" => Value::Number((self.tasks - self.done_tasks) as f64),
+            _ => self.frontmatter.get(name).map_or(Value::Null, Value::from_yaml),
+        }
+    }
+
+    fn matches(&self, expr: &QueryExpr) -> bool {
+        match expr {
+            QueryExpr::And { items } => items.iter().all(|item| self.matches(item)),
+            QueryExpr::Or { items } => items.iter().any(|item| self.matches(item)),
+            QueryExpr::Not { expr } => !self.matches(expr),
+            QueryExpr::Compare { field, cmp, value } => self.field(field).matches(*cmp, &Value::from_json(value)),
+        }
+    }
+}
+
+/// Fields a condition mentions, in order
+fn condition_fields(expr: &QueryExpr, fields: &mut Vec<String>) {
+    match expr {
+        QueryExpr::And { items } | QueryExpr::Or { items } => {
+            items.iter().for_each(|item| condition_fields(item, fields));
+        }
+        QueryExpr::Not { expr } => condition_fields(expr, fields),
+        QueryExpr::Compare { field, .. } => {
+            if !fields.contains(field) {
+                fields.push(field.clone());
+            }
+        }
+    }
+}
+
+fn run_query(vault: &Path, query: &NoteQuery) -> QueryResult {
+    let mut paths = Vec::new();
+    walk_notes(vault, &mut paths);
+    let mut notes: Vec<NoteRecord> = paths
+        .into_iter()
+        .filter_map(|path| NoteRecord::load(vault, path))
+        .filter(|note| query.filter.as_ref().is_none_or(|filter| note.matches(filter)))
+        .collect();
+
+    notes.sort_by(|a, b| {
+        for key in &query.sort {
+            let (x, y) = (a.field(&key.field), b.field(&key.field));
+            // Notes without the field go last either way
+            let order = match (x == Value::Null, y == Value::Null) {
+                (true, true) => Ordering::Equal,
+                (true, false) => return Ordering::Greater,
+                (false, true) => return Ordering::Less,
+                _ => x.compare(&y).unwrap_or(Ordering::Equal),
+            };
+            let order = if key.descending { order.reverse() } else { order };
+            if order != Ordering::Equal {
+                return order;
+            }
+        }
+        a.path.cmp(&b.path)
+    });
+    if let Some(limit) = query.limit {
+        notes.truncate(limit);
+    }
+
+    let mut columns = query.select.clone();
+    if columns.is_empty() {
+        if let Some(filter) = &query.filter {
+            condition_fields(filter, &mut columns);
+        }
+        for key in &query.sort {
+            if !columns.contains(&key.field) {
+                columns.push(key.field.clone());
+            }
+        }
+    }
+
+    let rows = notes
+        .into_iter()
+        .map(|note| QueryRow {
+            values: columns.iter().map(|c| note.field(c).to_json()).collect(),
+            name: note.name,
+            path: note.path,
+        })
+        .collect();
+    QueryResult { columns, rows }
+}
+
+/// Notes matching a metadata query, as rows for a dynamic list
+#[tauri::command]
+pub async fn query_notes(vault_path: PathBuf, query: QueryInput) -> Result<QueryResult, FsError> {
+    if !vault_path.is_dir() {
+        return Err(FsError::NotFound(vault_path.display().to_string()));
+    }
+    let query = match query {
+        QueryInput::Text(text) => parse_query(&text)?,
+        QueryInput::Structured(query) => query,
+    };
+    Ok(run_query(&vault_path, &query))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_text_queries() {
+        let query = parse_query("status = active AND (due < 2025-07-01 OR NOT file.tags contains 'urgent work') SORT BY due DESC, file.name LIMIT 5").unwrap();
+        let expected: NoteQuery = serde_json::from_value(json!({
+            "filter": {"op": "and", "items": [
+                {"op": "compare", "field": "status", "cmp": "eq", "value": "active"},
+                {"op": "or", "items": [
+                    {"op": "compare", "field": "due", "cmp": "lt", "value": "2025-07-01"},
+                    {"op": "not", "expr": {"op": "compare", "field": "file.tags", "cmp": "contains", "value": "urgent work"}}
+                ]}
+            ]},
+            "sort": [{"field": "due", "descending": true}, {"field": "file.name"}],
+            "limit": 5
+        }))
+        .unwrap();
+        assert_eq!(query, expected);
+
+        assert!(parse_query("status =").is_err());
+        assert!(parse_query("(a = 1").is_err());
+        assert!(parse_query("a = 1 b").is_err());
+        assert_eq!(parse_query("SELECT due, owner").unwrap().select, vec!["due", "owner"]);
+    }
+
+    #[test]
+    fn filters_and_sorts_notes() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = dir.path();
+        fs::create_dir(vault.join("Projects")).unwrap();
+        fs::write(vault.join("Projects/Alpha.md"), "---\nstatus: active\ndue: 2025-06-01\nlabels: [work]\n---\n- [ ] one\n- [x] two\n").unwrap();
+        fs::write(vault.join("Projects/Beta.md"), "---\nstatus: Active\ndue: 2025-05-01\n---\n#work\n").unwrap();
+        fs::write(vault.join("Projects/Gamma.md"), "---\nstatus: active\ndue: 2025-08-01\n---\n").unwrap();
+        fs::write(vault.join("Delta.md"), "---\nstatus: done\n---\n").unwrap();
+
+        let run = |text: &str| {
+            let query = QueryInput::Text(text.to_string());
+            tauri::async_runtime::block_on(query_notes(vault.to_path_buf(), query)).unwrap()
+        };
+        let names = |result: &QueryResult| result.rows.iter().map(|r| r.name.clone()).collect::<Vec<_>>();
+
+        let result = run("status = active AND due < 2025-07-01 SORT BY due");
+        assert_eq!(names(&result), vec!["Beta", "Alpha"]);
+        assert_eq!(result.columns, vec!["status", "due"]);
+        assert_eq!(result.rows[0].values, vec![json!("Active"), json!("2025-05-01")]);
+
+        let result = run("file.tags = work AND file.open_tasks > 0");
+        assert_eq!(names(&result), vec!["Alpha"]);
+
+        let result = run("SELECT file.folder WHERE NOT file.folder = Projects SORT BY due DESC");
+        assert_eq!(names(&result), vec!["Delta"]);
+        assert_eq!(result.rows[0].values, vec![json!("")]);
+
+        let result = run("file.folder = Projects SORT BY due DESC LIMIT 2");
+        assert_eq!(names(&result), vec!["Gamma", "Alpha"]);
+    }
+}
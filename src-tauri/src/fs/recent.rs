@@ -0,0 +1,111 @@
+//! Recently opened files and pinned favorites.
+//!
+//! Both live in the vault's local state so every window shares them.
+//! `read_note` and `read_notebook` record each opened path; the frontend
+//! only reads the list and toggles pins.
+
+use std::path::{Path, PathBuf};
+
+use super::commands::{find_vault_root, read_local_state, write_local_state, FsError};
+use super::types::{LocalState, RecentFile};
+
+/// How many recently opened files are remembered
+const MAX_RECENT_FILES: usize = 50;
+
+/// Move `path` to the front of the recent files, dropping the oldest ones
+fn push_recent(state: &mut LocalState, path: &str, opened: String) {
+    state.recent_files.retain(|r| r.path != path);
+    state.recent_files.insert(
+        0,
+        RecentFile {
+            path: path.to_string(),
+            opened,
+        },
+    );
+    state.recent_files.truncate(MAX_RECENT_FILES);
+}
+
+/// Record that a note or notebook was opened; a no-op outside a vault
+///
+/// Unreadable local state is left as it is and returned as the error.
+pub(crate) fn record_recent(path: &Path) -> Result<(), FsError> {
+    let Some(vault) = find_vault_root(path) else {
+        return Ok(());
+    };
+    let mut state = read_local_state(&vault)?;
+    push_recent(&mut state, &path.to_string_lossy(), chrono::Utc::now().to_rfc3339());
+    write_local_state(&vault, &state)
+}
+
+/// Recently opened notes and notebooks that still exist, most recent first
+///
+/// Fails when the local state can't be read, which is also why opened files
+/// stop being recorded.
+#[tauri::command]
+pub async fn get_recent_files(vault_path: PathBuf) -> Result<Vec<RecentFile>, FsError> {
+    let state = read_local_state(&vault_path)?;
+    Ok(state
+        .recent_files
+        .into_iter()
+        .filter(|r| Path::new(&r.path).exists())
+        .collect())
+}
+
+/// Pin or unpin a note or notebook; returns whether it is now pinned
+#[tauri::command]
+pub async fn toggle_pin(vault_path: PathBuf, path: String) -> Result<bool, FsError> {
+    let mut state = read_local_state(&vault_path)?;
+    let pinned = if state.pinned.contains(&path) {
+        state.pinned.retain(|p| p != &path);
+        false
+    } else {
+        state.pinned.push(path);
+        true
+    };
+    write_local_state(&vault_path, &state)?;
+    Ok(pinned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_recent_files_unique_and_bounded() {
+        let mut state = LocalState::default();
+        for i in 0..MAX_RECENT_FILES + 5 {
+            push_recent(&mut state, &format!("/vault/{}.md", i), i.to_string());
+        }
+        push_recent(&mut state, "/vault/10.md", "latest".to_string());
+
+        assert_eq!(state.recent_files.len(), MAX_RECENT_FILES);
+        assert_eq!(state.recent_files[0].path, "/vault/10.md");
+        assert_eq!(state.recent_files[0].opened, "latest");
+        assert_eq!(state.recent_files.iter().filter(|r| r.path == "/vault/10.md").count(), 1);
+    }
+
+    #[test]
+    fn toggles_pins() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = dir.path().to_path_buf();
+        let note = "/vault/Note.md".to_string();
+
+        assert!(tauri::async_runtime::block_on(toggle_pin(vault.clone(), note.clone())).unwrap());
+        assert_eq!(read_local_state(&vault).unwrap().pinned, vec![note.clone()]);
+        assert!(!tauri::async_runtime::block_on(toggle_pin(vault.clone(), note)).unwrap());
+        assert!(read_local_state(&vault).unwrap().pinned.is_empty());
+    }
+
+    #[test]
+    fn reports_broken_local_state() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = dir.path().to_path_buf();
+        let state = vault.join(".notemaker/.local/state.json");
+        std::fs::create_dir_all(state.parent().unwrap()).unwrap();
+        std::fs::write(&state, "{ not json").unwrap();
+
+        assert!(record_recent(&vault.join("Note.md")).is_err());
+        assert_eq!(std::fs::read_to_string(&state).unwrap(), "{ not json");
+        assert!(tauri::async_runtime::block_on(get_recent_files(vault)).is_err());
+    }
+}
@@ -0,0 +1,184 @@
+//! Key rotation: re-encrypt everything in a vault with new credentials.
+
+use age::secrecy::zeroize::Zeroizing;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::commands::write_atomic;
+use super::encryption::{
+    armor_encrypt, dearmor_decrypt, decrypt_with_session, encrypt_with_session, is_encrypted, EncryptionError,
+    EncryptionSession,
+};
+use super::types::{ReencryptFailure, ReencryptReport};
+
+/// Header of a binary (unarmored) age file
+const AGE_BINARY_HEADER: &[u8] = b"age-encryption.org/v1";
+
+/// Collect every file below a vault, skipping `.git`
+fn walk_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_dir() {
+            if entry.file_name() != ".git" {
+                walk_files(&path, files);
+            }
+        } else if file_type.is_file() {
+            files.push(path);
+        }
+    }
+}
+
+/// Ciphertext of a file, when it is encrypted, and whether it is stored as
+/// a binary age file rather than armored
+fn read_ciphertext(path: &Path) -> Result<Option<(Vec<u8>, bool)>, String> {
+    let bytes = fs::read(path).map_err(|e| e.to_string())?;
+    if bytes.starts_with(AGE_BINARY_HEADER) {
+        return Ok(Some((bytes, true)));
+    }
+    match std::str::from_utf8(&bytes) {
+        Ok(content) if is_encrypted(content) => dearmor_decrypt(content)
+            .map(|ciphertext| Some((ciphertext, false)))
+            .map_err(|e| e.to_string()),
+        _ => Ok(None),
+    }
+}
+
+/// Check that a session can decrypt content of the vault
+///
+/// Tries the encrypted files in path order until one decrypts. A vault with
+/// nothing encrypted has nothing to check against and passes.
+pub(crate) fn session_opens_vault(vault_path: &Path, session: &EncryptionSession) -> bool {
+    let mut files = Vec::new();
+    walk_files(vault_path, &mut files);
+    files.sort();
+
+    let mut found = false;
+    for path in files {
+        let Ok(Some((ciphertext, _))) = read_ciphertext(&path) else {
+            continue;
+        };
+        found = true;
+        if decrypt_with_session(session, &ciphertext).is_ok() {
+            return true;
+        }
+    }
+    !found
+}
+
+/// Decrypt with the current session and encrypt with the next one
+fn rotate(current: &EncryptionSession, next: &EncryptionSession, ciphertext: &[u8]) -> Result<Vec<u8>, EncryptionError> {
+    let plaintext = Zeroizing::new(decrypt_with_session(current, ciphertext)?);
+    encrypt_with_session(next, &plaintext)
+}
+
+/// Re-encrypt a single file, returning `Ok(false)` when it is not encrypted
+fn reencrypt_file(path: &Path, current: &EncryptionSession, next: &EncryptionSession) -> Result<bool, String> {
+    let Some((ciphertext, binary)) = read_ciphertext(path)? else {
+        return Ok(false);
+    };
+    let rotated = rotate(current, next, &ciphertext).map_err(|e| e.to_string())?;
+
+    // Attachments encrypted as binary age files stay binary
+    let output = if binary { rotated } else { armor_encrypt(&rotated).into_bytes() };
+
+    // A file cut short here could be decrypted with neither key
+    write_atomic(path, output).map_err(|e| e.to_string())?;
+    Ok(true)
+}
+
+/// Re-encrypt every encrypted note, notebook block, index and attachment in a
+/// vault from the `current` session to the `next` one
+///
+/// Files that fail are reported and left as they were; the rest are rotated.
+pub(crate) fn reencrypt_vault_files(
+    vault_path: &Path,
+    current: &EncryptionSession,
+    next: &EncryptionSession,
+) -> Result<ReencryptReport, EncryptionError> {
+    if !current.is_unlocked() {
+        return Err(EncryptionError::SessionLocked);
+    }
+    // Fail before touching any file when the new credentials are unusable
+    encrypt_with_session(next, b"")?;
+
+    let mut files = Vec::new();
+    walk_files(vault_path, &mut files);
+    files.sort();
+
+    let mut report = ReencryptReport::default();
+    for path in files {
+        match reencrypt_file(&path, current, next) {
+            Ok(true) => report.reencrypted.push(path),
+            Ok(false) => {}
+            Err(error) => report.failures.push(ReencryptFailure { path, error }),
+        }
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::encryption::generate_identity;
+
+    #[test]
+    fn rotates_to_new_recipient() {
+        let dir = tempfile::tempdir().unwrap();
+        let old_identity = dir.path().join("old.txt").to_string_lossy().to_string();
+        let new_identity = dir.path().join("new.txt").to_string_lossy().to_string();
+        generate_identity(&old_identity).unwrap();
+        let new_key = generate_identity(&new_identity).unwrap();
+
+        let vault = dir.path().join("vault");
+        fs::create_dir_all(vault.join("sub")).unwrap();
+        let current = EncryptionSession::new();
+        current.set_identity_file(old_identity);
+        let armored = armor_encrypt(&encrypt_with_session(&current, b"secret").unwrap());
+        fs::write(vault.join("sub/note.md"), &armored).unwrap();
+        fs::write(vault.join("plain.md"), "hello").unwrap();
+        fs::write(vault.join("image.png.age"), encrypt_with_session(&current, b"png").unwrap()).unwrap();
+        let stranger = EncryptionSession::new();
+        stranger.set_password("other".to_string());
+        fs::write(vault.join("foreign.md"), armor_encrypt(&encrypt_with_session(&stranger, b"x").unwrap())).unwrap();
+
+        let next = EncryptionSession::new();
+        next.set_public_keys(vec![new_key]);
+        let report = reencrypt_vault_files(&vault, &current, &next).unwrap();
+        assert_eq!(report.reencrypted.len(), 2);
+        assert_eq!(report.failures.len(), 1);
+        assert!(report.failures[0].path.ends_with("foreign.md"));
+
+        let reader = EncryptionSession::new();
+        reader.set_identity_file(new_identity);
+        let content = fs::read_to_string(vault.join("sub/note.md")).unwrap();
+        let plaintext = decrypt_with_session(&reader, &dearmor_decrypt(&content).unwrap()).unwrap();
+        assert_eq!(plaintext, b"secret");
+        let binary = fs::read(vault.join("image.png.age")).unwrap();
+        assert_eq!(decrypt_with_session(&reader, &binary).unwrap(), b"png");
+        assert_eq!(fs::read_to_string(vault.join("plain.md")).unwrap(), "hello");
+    }
+
+    #[test]
+    fn checks_session_against_vault() {
+        let dir = tempfile::tempdir().unwrap();
+        let session = EncryptionSession::new();
+        session.set_password("old".to_string());
+        assert!(session_opens_vault(dir.path(), &session));
+
+        let armored = armor_encrypt(&encrypt_with_session(&session, b"secret").unwrap());
+        fs::write(dir.path().join("note.md"), armored).unwrap();
+        assert!(session_opens_vault(dir.path(), &session));
+
+        let wrong = EncryptionSession::new();
+        wrong.set_password("wrong".to_string());
+        assert!(!session_opens_vault(dir.path(), &wrong));
+    }
+}
@@ -0,0 +1,191 @@
+//! Due dates of kanban tasks and notes, and reminders for them.
+//!
+//! Open tasks (not in a done column) with a `due` date and notes with a
+//! `due:` frontmatter field are collected from the vault. A background
+//! scheduler shows an OS notification once per item when it becomes due,
+//! `days_before` days ahead if configured. Encrypted boards and notes are
+//! skipped.
+
+use chrono::{Duration, NaiveDate, Utc};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+use tauri_plugin_notification::NotificationExt;
+
+use super::commands::{is_kanban, is_notebook, read_kanban_index, read_vault_config, walk_notes, FsError};
+use super::encryption::is_encrypted;
+use super::frontmatter::{parse_frontmatter, string_field};
+use super::kanban::{done_statuses, parse_date};
+use super::types::DueItem;
+use super::watcher::WatcherState;
+
+/// How often the scheduler looks for due items
+const TICK: std::time::Duration = std::time::Duration::from_secs(300);
+
+/// Kanban boards below a path, skipping hidden entries
+pub(crate) fn walk_boards(path: &Path, boards: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(path) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let entry_path = entry.path();
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        if is_kanban(&entry_path) {
+            boards.push(entry_path);
+        } else if entry_path.is_dir() && !is_notebook(&entry_path) {
+            walk_boards(&entry_path, boards);
+        }
+    }
+}
+
+/// Items due on or before `until`, overdue ones included, earliest first
+pub(crate) fn collect_due_items(vault: &Path, until: NaiveDate, today: NaiveDate) -> Vec<DueItem> {
+    let mut items = Vec::new();
+    let mut push = |path: &Path, task_id: Option<String>, title: String, due: NaiveDate| {
+        if due <= until {
+            items.push(DueItem {
+                path: path.to_path_buf(),
+                task_id,
+                title,
+                due: due.format("%Y-%m-%d").to_string(),
+                overdue: due < today,
+            });
+        }
+    };
+
+    let mut boards = Vec::new();
+    walk_boards(vault, &mut boards);
+    for board in boards {
+        let Ok(index) = read_kanban_index(&board, None) else {
+            continue;
+        };
+        let done = done_statuses(&index.columns);
+        for task in index.tasks.iter().filter(|t| !done.contains(&t.status)) {
+            if let Some(due) = task.due.as_deref().and_then(parse_date) {
+                push(&board, Some(task.id.clone()), task.title.clone(), due);
+            }
+        }
+    }
+
+    let mut notes = Vec::new();
+    walk_notes(vault, &mut notes);
+    for note in notes.iter().filter(|n| n.is_file()) {
+        let Ok(content) = fs::read_to_string(note) else {
+            continue;
+        };
+        if is_encrypted(&content) {
+            continue;
+        }
+        let Some(frontmatter) = parse_frontmatter(&content) else {
+            continue;
+        };
+        if let Some(due) = string_field(&frontmatter, "due").as_deref().and_then(parse_date) {
+            let title = string_field(&frontmatter, "title").unwrap_or_else(|| {
+                note.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default()
+            });
+            push(note, None, title, due);
+        }
+    }
+
+    items.sort_by(|a, b| a.due.cmp(&b.due).then_with(|| a.title.cmp(&b.title)));
+    items
+}
+
+/// Overdue items and those due within `horizon` days (default 7), for an
+/// agenda view
+#[tauri::command]
+pub async fn list_upcoming_due(vault_path: PathBuf, horizon: Option<u32>) -> Result<Vec<DueItem>, FsError> {
+    if !vault_path.is_dir() {
+        return Err(FsError::NotFound(vault_path.display().to_string()));
+    }
+    let today = Utc::now().date_naive();
+    let until = today + Duration::days(i64::from(horizon.unwrap_or(7)));
+    Ok(tauri::async_runtime::spawn_blocking(move || collect_due_items(&vault_path, until, today))
+        .await
+        .unwrap_or_default())
+}
+
+/// Spawn the reminder scheduler for the open vault; it runs for the lifetime
+/// of the app
+pub fn spawn_reminders(app_handle: AppHandle, watcher_state: WatcherState) {
+    tauri::async_runtime::spawn(async move {
+        // Items already notified about, by path, task and due date
+        let mut notified: HashSet<(PathBuf, Option<String>, String)> = HashSet::new();
+
+        loop {
+            let vault = match watcher_state.lock() {
+                Ok(watcher) => watcher.watched_path().cloned(),
+                Err(_) => None,
+            };
+            let settings = vault
+                .as_ref()
+                .and_then(|vault| read_vault_config(vault).ok())
+                .map(|config| config.reminders);
+
+            if let (Some(vault), Some(settings)) = (vault, settings.filter(|s| s.enabled)) {
+                let today = Utc::now().date_naive();
+                let until = today + Duration::days(i64::from(settings.days_before));
+                let items = tauri::async_runtime::spawn_blocking(move || collect_due_items(&vault, until, today))
+                    .await
+                    .unwrap_or_default();
+
+                for item in items {
+                    if !notified.insert((item.path.clone(), item.task_id.clone(), item.due.clone())) {
+                        continue;
+                    }
+                    let body = if item.overdue {
+                        format!("Overdue since {}", item.due)
+                    } else {
+                        format!("Due {}", item.due)
+                    };
+                    let _ = app_handle.notification().builder().title(&item.title).body(body).show();
+                }
+            }
+
+            tokio::time::sleep(TICK).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::commands::write_kanban_index;
+    use crate::fs::types::{KanbanIndex, KanbanTask};
+
+    #[test]
+    fn collects_due_tasks_and_notes() {
+        let dir = tempfile::tempdir().unwrap();
+        let board = dir.path().join("Work.kanban");
+        fs::create_dir(&board).unwrap();
+        let task = |id: &str, status: &str, due: &str| KanbanTask {
+            id: id.to_string(),
+            title: id.to_string(),
+            status: status.to_string(),
+            due: Some(due.to_string()),
+            ..Default::default()
+        };
+        let index = KanbanIndex {
+            tasks: vec![
+                task("late", "todo", "2024-04-30"),
+                task("finished", "done", "2024-04-30"),
+                task("later", "todo", "2024-06-01"),
+            ],
+            ..Default::default()
+        };
+        write_kanban_index(&board, &index).unwrap();
+        fs::create_dir(dir.path().join("Notes")).unwrap();
+        fs::write(dir.path().join("Notes/Report.md"), "---\ndue: 2024-05-03\n---\n# Report").unwrap();
+        fs::write(dir.path().join("Notes/Plain.md"), "# Plain").unwrap();
+
+        let today = NaiveDate::from_ymd_opt(2024, 5, 1).unwrap();
+        let items = collect_due_items(dir.path(), today + Duration::days(7), today);
+        let titles: Vec<&str> = items.iter().map(|i| i.title.as_str()).collect();
+        assert_eq!(titles, vec!["late", "Report"]);
+        assert!(items[0].overdue && !items[1].overdue);
+        assert_eq!(items[0].task_id.as_deref(), Some("late"));
+    }
+}
@@ -0,0 +1,164 @@
+//! Execution results kept next to a notebook's blocks.
+//!
+//! The last result of each block lives in `<notebook>/.outputs.json`, keyed
+//! by block ID. Outputs of encrypted blocks can contain their plaintext, so
+//! the file is stored encrypted while any stored output belongs to one.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::State;
+
+use super::commands::{
+    decrypt_armored, encrypt_armored, read_notebook_index_with, CodeExecutionResult, FsError,
+};
+use super::encryption::{is_encrypted, EncryptionSession};
+use super::encryption_commands::EncryptionState;
+use super::types::StoredOutput;
+
+/// File holding the outputs of a notebook
+const OUTPUTS_FILE: &str = ".outputs.json";
+
+/// Stored outputs of a notebook's blocks
+pub(crate) fn read_block_outputs(
+    notebook_path: &Path,
+    session: Option<&EncryptionSession>,
+) -> Result<BTreeMap<String, StoredOutput>, FsError> {
+    let path = notebook_path.join(OUTPUTS_FILE);
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+    let mut content = fs::read_to_string(&path)?;
+    if is_encrypted(&content) {
+        content = decrypt_armored(session, &content, &path)?;
+    }
+    serde_json::from_str(&content).map_err(|e| FsError::InvalidPath(format!("Invalid {}: {}", OUTPUTS_FILE, e)))
+}
+
+/// Write the outputs of a notebook, dropping those of deleted blocks
+pub(crate) fn write_block_outputs(
+    notebook_path: &Path,
+    mut outputs: BTreeMap<String, StoredOutput>,
+    session: Option<&EncryptionSession>,
+) -> Result<(), FsError> {
+    let index = read_notebook_index_with(notebook_path, session)?;
+    outputs.retain(|id, _| index.blocks.iter().any(|b| &b.id == id));
+
+    let path = notebook_path.join(OUTPUTS_FILE);
+    if outputs.is_empty() {
+        if path.exists() {
+            fs::remove_file(&path)?;
+        }
+        return Ok(());
+    }
+
+    let encrypt = index.encrypted
+        || index
+            .blocks
+            .iter()
+            .any(|b| b.encrypted.unwrap_or(false) && outputs.contains_key(&b.id));
+    let content =
+        serde_json::to_string_pretty(&outputs).map_err(|e| FsError::InvalidPath(format!("Invalid outputs: {}", e)))?;
+    let content = if encrypt {
+        encrypt_armored(session, &content, &path)?
+    } else {
+        content
+    };
+    fs::write(&path, content)?;
+    Ok(())
+}
+
+/// Store the result of one block run
+pub(crate) fn record_block_output(
+    notebook_path: &Path,
+    block_id: &str,
+    result: &CodeExecutionResult,
+    session: Option<&EncryptionSession>,
+) -> Result<StoredOutput, FsError> {
+    let mut outputs = read_block_outputs(notebook_path, session)?;
+    let output = StoredOutput {
+        stdout: result.stdout.clone(),
+        stderr: result.stderr.clone(),
+        exit_code: result.exit_code,
+        executed_at: chrono::Utc::now().to_rfc3339(),
+    };
+    outputs.insert(block_id.to_string(), output.clone());
+    write_block_outputs(notebook_path, outputs, session)?;
+    Ok(output)
+}
+
+/// Rewrite the outputs file after blocks were encrypted or decrypted
+pub(crate) fn reseal_block_outputs(notebook_path: &Path, session: Option<&EncryptionSession>) -> Result<(), FsError> {
+    if !notebook_path.join(OUTPUTS_FILE).exists() {
+        return Ok(());
+    }
+    let outputs = read_block_outputs(notebook_path, session)?;
+    write_block_outputs(notebook_path, outputs, session)
+}
+
+/// Store the result of running a block so it is shown after a restart
+#[tauri::command]
+pub async fn save_block_output(
+    notebook_path: PathBuf,
+    block_id: String,
+    result: CodeExecutionResult,
+    state: State<'_, EncryptionState>,
+) -> Result<StoredOutput, FsError> {
+    record_block_output(&notebook_path, &block_id, &result, Some(&state.session(&notebook_path)))
+}
+
+/// Remove the stored output of one block, or of all blocks
+#[tauri::command]
+pub async fn clear_block_outputs(
+    notebook_path: PathBuf,
+    block_id: Option<String>,
+    state: State<'_, EncryptionState>,
+) -> Result<(), FsError> {
+    let session = state.session(&notebook_path);
+    let session = Some(session.as_ref());
+    let mut outputs = read_block_outputs(&notebook_path, session)?;
+    match block_id {
+        Some(id) => {
+            outputs.remove(&id);
+        }
+        None => outputs.clear(),
+    }
+    write_block_outputs(&notebook_path, outputs, session)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::commands::write_notebook_index_with;
+    use crate::fs::types::{BlockType, NotebookBlock, NotebookIndex};
+
+    #[test]
+    fn keeps_outputs_of_existing_blocks() {
+        let dir = tempfile::tempdir().unwrap();
+        let notebook = dir.path().join("Run.md");
+        fs::create_dir_all(&notebook).unwrap();
+        let index = NotebookIndex {
+            blocks: vec![NotebookBlock {
+                id: "a".to_string(),
+                block_type: BlockType::Code,
+                file: "a.sh".to_string(),
+                language: Some("shell".to_string()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        write_notebook_index_with(&notebook, &index, None).unwrap();
+
+        let result = CodeExecutionResult {
+            stdout: "hi\n".to_string(),
+            stderr: String::new(),
+            exit_code: 0,
+        };
+        record_block_output(&notebook, "a", &result, None).unwrap();
+        record_block_output(&notebook, "gone", &result, None).unwrap();
+
+        let outputs = read_block_outputs(&notebook, None).unwrap();
+        assert_eq!(outputs.keys().collect::<Vec<_>>(), vec!["a"]);
+        assert_eq!(outputs["a"].stdout, "hi\n");
+    }
+}
@@ -0,0 +1,436 @@
+//! Language runtimes for code block execution.
+//!
+//! Interpreted languages get their code as an argument (`-c`/`-e`/`eval`);
+//! compiled ones are written to a scratch directory that lives as long as
+//! the returned [`Invocation`].
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::commands::{find_vault_root, read_vault_config, FsError};
+use super::types::{ContainerSettings, InterpreterSettings};
+use crate::export::pdf::find_program;
+
+/// Canonical runtime name of a code block language
+pub(crate) fn runtime_name(language: &str) -> Option<&'static str> {
+    match language.to_lowercase().as_str() {
+        "shell" | "bash" | "sh" => Some("shell"),
+        "python" | "py" => Some("python"),
+        "ruby" | "rb" => Some("ruby"),
+        "node" | "javascript" | "js" => Some("node"),
+        "deno" | "typescript" | "ts" => Some("deno"),
+        "go" | "golang" => Some("go"),
+        "rust" | "rs" => Some("rust"),
+        _ => None,
+    }
+}
+
+/// Get the default interpreter for a runtime
+pub(crate) fn default_interpreter(runtime: &str) -> &'static str {
+    match runtime {
+        "shell" => "bash",
+        "python" => "python3",
+        "ruby" => "ruby",
+        "node" => "node",
+        "deno" => "deno",
+        "go" => "go",
+        "rust" => "cargo",
+        _ => "sh",
+    }
+}
+
+/// Interpreter configured in the vault settings for a runtime
+fn configured_interpreter(settings: &InterpreterSettings, runtime: &str) -> Option<String> {
+    match runtime {
+        "shell" => settings.shell.clone(),
+        "python" => settings.python.clone(),
+        "ruby" => settings.ruby.clone(),
+        "node" => settings.node.clone(),
+        "deno" => settings.deno.clone(),
+        "go" => settings.go.clone(),
+        "rust" => settings.rust.clone(),
+        _ => None,
+    }
+}
+
+/// Directory a block runs in
+///
+/// A block's `cwd` (relative to the notebook's folder, and kept inside the
+/// vault) wins over an explicit `working_dir`; without either, blocks run in
+/// the folder containing the notebook or note so relative paths to its
+/// assets work. Only blocks without a notebook fall back to the temp dir.
+pub(crate) fn resolve_working_dir(
+    working_dir: Option<PathBuf>,
+    notebook_path: Option<&Path>,
+    cwd: Option<&str>,
+) -> Result<PathBuf, FsError> {
+    let notebook_dir = notebook_path.and_then(Path::parent).map(Path::to_path_buf);
+
+    if let Some(cwd) = cwd.map(str::trim).filter(|c| !c.is_empty()) {
+        let base = notebook_dir
+            .clone()
+            .or_else(|| working_dir.clone())
+            .unwrap_or_else(std::env::temp_dir);
+        let dir = base.join(cwd);
+        if !dir.is_dir() {
+            return Err(FsError::NotFound(dir.display().to_string()));
+        }
+        if let Some(vault) = find_vault_root(&base) {
+            if !dir.canonicalize()?.starts_with(vault.canonicalize()?) {
+                return Err(FsError::PathTraversal);
+            }
+        }
+        return Ok(dir);
+    }
+
+    Ok(working_dir.or(notebook_dir).unwrap_or_else(std::env::temp_dir))
+}
+
+/// Temporary directory removed when dropped
+pub(crate) struct ScratchDir(PathBuf);
+
+impl ScratchDir {
+    pub(crate) fn create() -> Result<Self, FsError> {
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.subsec_nanos())
+            .unwrap_or(0);
+        let dir = std::env::temp_dir().join(format!(
+            "notemaker-run-{}-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed),
+            nanos
+        ));
+        fs::create_dir_all(&dir)?;
+        Ok(Self(dir))
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Program and arguments that run a code block
+pub(crate) struct Invocation {
+    pub program: String,
+    pub args: Vec<String>,
+    /// Container the block runs in, if any
+    pub container: Option<ContainerRun>,
+    /// Source files of compiled languages; kept until the run finishes
+    scratch: Option<ScratchDir>,
+}
+
+impl Invocation {
+    fn inline(program: String, flag: &str, code: &str) -> Self {
+        Self {
+            program,
+            args: vec![flag.to_string(), code.to_string()],
+            container: None,
+            scratch: None,
+        }
+    }
+}
+
+/// A named container a block runs in
+///
+/// Killing the engine client doesn't stop the container, so a cancelled run
+/// kills it by name; `--rm` removes it afterwards.
+#[derive(Debug, Clone)]
+pub(crate) struct ContainerRun {
+    pub engine: String,
+    pub name: String,
+}
+
+impl ContainerRun {
+    fn new(engine: String) -> Self {
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+        let name = format!(
+            "notemaker-run-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        );
+        Self { engine, name }
+    }
+
+    /// Kill the container, returning whether it was running
+    pub(crate) fn kill(&self) -> bool {
+        std::process::Command::new(&self.engine)
+            .args(["kill", &self.name])
+            .output()
+            .map(|o| o.status.success())
+            .unwrap_or(false)
+    }
+}
+
+/// Go sources need a package clause; snippets without one are treated as `main`
+fn go_source(code: &str) -> String {
+    if code.lines().any(|line| line.trim_start().starts_with("package ")) {
+        code.to_string()
+    } else {
+        format!("package main\n\n{}", code)
+    }
+}
+
+/// Rust snippets without a `main` function run as its body
+fn rust_source(code: &str) -> String {
+    if code.contains("fn main(") {
+        code.to_string()
+    } else {
+        format!("fn main() {{\n{}\n}}\n", code)
+    }
+}
+
+/// Default container image of a runtime
+fn default_image(runtime: &str) -> &'static str {
+    match runtime {
+        "python" => "python:3-slim",
+        "ruby" => "ruby:3-slim",
+        "node" => "node:lts-slim",
+        "deno" => "denoland/deno",
+        "go" => "golang:1",
+        "rust" => "rust:1-slim",
+        _ => "bash:5",
+    }
+}
+
+/// Container engine to run blocks with
+fn container_engine(settings: &ContainerSettings) -> Result<String, FsError> {
+    if let Some(engine) = settings.engine.as_deref().filter(|e| !e.trim().is_empty()) {
+        return Ok(engine.to_string());
+    }
+    ["docker", "podman"]
+        .iter()
+        .find(|name| find_program(name).is_some())
+        .map(|name| name.to_string())
+        .ok_or_else(|| FsError::NotFound("docker or podman (needed for container execution)".to_string()))
+}
+
+/// Wrap an invocation in `<engine> run` with `work_dir` mounted as `/work`
+///
+/// The container is named so it can be killed when the run is cancelled.
+/// Scratch sources are mounted at the same path so the arguments stay valid.
+/// Variables are passed by name only, so their values don't show up in the
+/// engine's command line.
+fn containerize(
+    invocation: Invocation,
+    runtime: &str,
+    settings: &ContainerSettings,
+    work_dir: &Path,
+    env: &BTreeMap<String, String>,
+) -> Result<Invocation, FsError> {
+    let image = settings
+        .images
+        .get(runtime)
+        .or(settings.image.as_ref())
+        .cloned()
+        .unwrap_or_else(|| default_image(runtime).to_string());
+
+    let container = ContainerRun::new(container_engine(settings)?);
+    let mut args = vec![
+        "run".to_string(),
+        "--rm".to_string(),
+        "-i".to_string(),
+        "--name".to_string(),
+        container.name.clone(),
+    ];
+    if !settings.network {
+        args.extend(["--network".to_string(), "none".to_string()]);
+    }
+    args.extend([
+        "-v".to_string(),
+        format!("{}:/work", work_dir.to_string_lossy()),
+        "-w".to_string(),
+        "/work".to_string(),
+    ]);
+    if let Some(scratch) = &invocation.scratch {
+        let path = scratch.path().to_string_lossy();
+        args.extend(["-v".to_string(), format!("{}:{}", path, path)]);
+    }
+    for name in env.keys() {
+        args.extend(["-e".to_string(), name.clone()]);
+    }
+    args.push(image);
+    args.push(invocation.program);
+    args.extend(invocation.args);
+
+    Ok(Invocation {
+        program: container.engine.clone(),
+        args,
+        container: Some(container),
+        scratch: invocation.scratch,
+    })
+}
+
+/// Build the invocation for a code block
+///
+/// `interpreter` overrides the vault's interpreter settings, which are found
+/// from `anchor`. `container` overrides the vault's container setting; inside
+/// a container the runtime's default interpreter is used.
+pub(crate) fn prepare_invocation(
+    language: &str,
+    code: &str,
+    interpreter: Option<String>,
+    container: Option<bool>,
+    anchor: &Path,
+    work_dir: &Path,
+    env: &BTreeMap<String, String>,
+) -> Result<Invocation, FsError> {
+    let runtime =
+        runtime_name(language).ok_or_else(|| FsError::InvalidPath(format!("Unsupported language: {}", language)))?;
+
+    let config = find_vault_root(anchor)
+        .and_then(|vault| read_vault_config(&vault).ok())
+        .unwrap_or_default();
+    let in_container = container.unwrap_or(config.container.enabled);
+    let program = interpreter
+        .or_else(|| {
+            if in_container {
+                None
+            } else {
+                configured_interpreter(&config.interpreters, runtime)
+            }
+        })
+        .unwrap_or_else(|| default_interpreter(runtime).to_string());
+
+    let invocation = runtime_invocation(runtime, code, program)?;
+    if in_container {
+        containerize(invocation, runtime, &config.container, work_dir, env)
+    } else {
+        Ok(invocation)
+    }
+}
+
+/// Program and arguments running `code` with a runtime on the host
+fn runtime_invocation(runtime: &str, code: &str, program: String) -> Result<Invocation, FsError> {
+    Ok(match runtime {
+        "shell" | "python" => Invocation::inline(program, "-c", code),
+        "ruby" | "node" => Invocation::inline(program, "-e", code),
+        "deno" => Invocation::inline(program, "eval", code),
+        "go" => {
+            let scratch = ScratchDir::create()?;
+            let main = scratch.path().join("main.go");
+            fs::write(&main, go_source(code))?;
+            Invocation {
+                program,
+                args: vec!["run".to_string(), main.to_string_lossy().to_string()],
+                container: None,
+                scratch: Some(scratch),
+            }
+        }
+        _ => {
+            let scratch = ScratchDir::create()?;
+            let source = rust_source(code);
+            let is_script = Path::new(&program)
+                .file_stem()
+                .map(|stem| stem == "rust-script")
+                .unwrap_or(false);
+
+            if is_script {
+                let main = scratch.path().join("main.rs");
+                fs::write(&main, source)?;
+                Invocation {
+                    program,
+                    args: vec![main.to_string_lossy().to_string()],
+                    container: None,
+                    scratch: Some(scratch),
+                }
+            } else {
+                fs::create_dir_all(scratch.path().join("src"))?;
+                fs::write(
+                    scratch.path().join("Cargo.toml"),
+                    "[package]\nname = \"notemaker-block\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[dependencies]\n",
+                )?;
+                fs::write(scratch.path().join("src").join("main.rs"), source)?;
+                // A shared target directory keeps the standard library build cached
+                let target_dir = std::env::temp_dir().join("notemaker-rust-target");
+                Invocation {
+                    program,
+                    args: vec![
+                        "run".to_string(),
+                        "--quiet".to_string(),
+                        "--manifest-path".to_string(),
+                        scratch.path().join("Cargo.toml").to_string_lossy().to_string(),
+                        "--target-dir".to_string(),
+                        target_dir.to_string_lossy().to_string(),
+                    ],
+                    container: None,
+                    scratch: Some(scratch),
+                }
+            }
+        }
+    })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::commands::write_vault_config;
+    use crate::fs::types::VaultConfig;
+
+    #[test]
+    fn prepares_runtimes() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = VaultConfig::default();
+        config.interpreters.node = Some("/opt/node/bin/node".to_string());
+        write_vault_config(dir.path(), &config).unwrap();
+        let env = BTreeMap::new();
+
+        let node = prepare_invocation("javascript", "console.log(1)", None, None, dir.path(), dir.path(), &env).unwrap();
+        assert_eq!(node.program, "/opt/node/bin/node");
+        assert_eq!(node.args, vec!["-e", "console.log(1)"]);
+
+        let go = prepare_invocation("go", "func main() {}", None, None, dir.path(), dir.path(), &env).unwrap();
+        let scratch = go.scratch.as_ref().unwrap().path().to_path_buf();
+        assert_eq!(go.args[0], "run");
+        assert!(fs::read_to_string(scratch.join("main.go")).unwrap().starts_with("package main"));
+        drop(go);
+        assert!(!scratch.exists());
+
+        let rust = prepare_invocation("rust", "println!(\"hi\");", None, None, dir.path(), dir.path(), &env).unwrap();
+        let main = rust.scratch.as_ref().unwrap().path().join("src").join("main.rs");
+        assert!(fs::read_to_string(main).unwrap().starts_with("fn main() {"));
+
+        let script = prepare_invocation("rust", "fn main() {}", Some("rust-script".to_string()), None, dir.path(), dir.path(), &env).unwrap();
+        assert!(script.args[0].ends_with("main.rs"));
+
+        // Host interpreter paths don't apply inside a container
+        config.container.engine = Some("podman".to_string());
+        config.container.images.insert("node".to_string(), "node:22".to_string());
+        write_vault_config(dir.path(), &config).unwrap();
+        let env = BTreeMap::from([("TOKEN".to_string(), "secret".to_string())]);
+        let boxed = prepare_invocation("js", "1", None, Some(true), dir.path(), dir.path(), &env).unwrap();
+        assert_eq!(boxed.program, "podman");
+        assert!(boxed.args.windows(2).any(|w| w == ["--network", "none"]));
+        assert!(boxed.args.windows(2).any(|w| w == ["-e", "TOKEN"]));
+        assert!(!boxed.args.iter().any(|a| a.contains("secret")));
+        assert_eq!(boxed.args[boxed.args.len() - 4..], ["node:22", "node", "-e", "1"]);
+        let container = boxed.container.as_ref().unwrap();
+        assert_eq!(container.engine, "podman");
+        assert_eq!(boxed.args[..5], ["run", "--rm", "-i", "--name", container.name.as_str()]);
+        let again = prepare_invocation("js", "1", None, Some(true), dir.path(), dir.path(), &env).unwrap();
+        assert_ne!(again.container.unwrap().name, container.name);
+        assert!(node.container.is_none());
+
+        let notebook = dir.path().join("Notes").join("Run.md");
+        fs::create_dir_all(notebook.join("data")).unwrap();
+        assert_eq!(resolve_working_dir(None, Some(&notebook), None).unwrap(), dir.path().join("Notes"));
+        assert_eq!(
+            resolve_working_dir(Some(dir.path().to_path_buf()), Some(&notebook), Some("Run.md/data")).unwrap(),
+            notebook.join("data")
+        );
+        assert!(resolve_working_dir(None, Some(&notebook), Some("../..")).is_err());
+
+        assert!(prepare_invocation("cobol", "", None, None, dir.path(), dir.path(), &env).is_err());
+    }
+}
@@ -0,0 +1,233 @@
+//! Keeping command file access inside the open vault.
+//!
+//! `open_vault` and `create_vault` record the vault root in [`VaultScope`].
+//! Before any command runs, its path arguments are checked against that
+//! root, so the frontend can't read or write files elsewhere on disk. Paths
+//! must be absolute, since a command would resolve a relative one against
+//! the working directory of the app. A few arguments point outside the
+//! vault by design (identity files, clone and import sources and targets,
+//! export destinations, extra watched folders) and only have to be
+//! absolute; git commands take file paths relative to the repository.
+
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use super::commands::{read_vault_config, validate_path, FsError};
+
+/// Argument names (as sent by the frontend) that hold paths; nested
+/// arguments are named with dots
+const PATH_ARGS: &[&str] = &[
+    "path",
+    "paths",
+    "from",
+    "to",
+    "toDir",
+    "targetDir",
+    "vaultPath",
+    "notePath",
+    "notebookPath",
+    "kanbanPath",
+    "folderPath",
+    "fromBoard",
+    "toBoard",
+    "sourceNotebook",
+    "targetNotebook",
+    "oldPath",
+    "newPath",
+    "filePath",
+    "workingDir",
+    "outputDir",
+    "outputPath",
+    "options.outputPath",
+    "source",
+    "target",
+    "zipPath",
+    "identityPaths",
+];
+
+/// Where a path argument of a command may point
+#[derive(Debug, PartialEq)]
+enum PathArg {
+    /// An absolute path inside the vault
+    Vault,
+    /// A path relative to the vault root, or an absolute one inside it
+    VaultRelative,
+    /// Any absolute path
+    Outside,
+    /// Not a path in this command, e.g. the tags of `merge_tags`
+    NotAPath,
+}
+
+/// How `command` treats its argument `name`
+fn path_arg(command: &str, name: &str) -> PathArg {
+    match (command, name) {
+        ("open_vault" | "create_vault", "path") => PathArg::Outside,
+        ("git_clone" | "import_notion_export", "targetDir") => PathArg::Outside,
+        ("import_notion_export", "zipPath") => PathArg::Outside,
+        ("import_obsidian_vault", "source" | "target") => PathArg::Outside,
+        // Destinations picked in a save dialog
+        ("export_site" | "export_folder", "outputDir") => PathArg::Outside,
+        ("export_notebook_ipynb", "outputPath") => PathArg::Outside,
+        ("export_note_html" | "export_note_pdf" | "export_note_docx", "options.outputPath") => PathArg::Outside,
+        // Roots besides the vault, e.g. an external templates folder
+        ("start_watching", "path") | ("set_watch_subscriptions", "paths") => PathArg::Outside,
+        (
+            "set_encryption_identity"
+            | "set_encryption_identity_with_save"
+            | "get_public_key_from_identity_file"
+            | "generate_identity_file"
+            | "add_recipient_identity",
+            "path",
+        ) => PathArg::Outside,
+        ("setup_recipients_encryption", "identityPaths") => PathArg::Outside,
+        (
+            "git_stage"
+            | "git_unstage"
+            | "git_file_history"
+            | "git_show_file"
+            | "git_discard"
+            | "git_diff_workdir"
+            | "git_conflict_versions"
+            | "git_resolve_ours"
+            | "git_resolve_theirs",
+            "filePath",
+        ) => PathArg::VaultRelative,
+        ("git_notebook_history" | "git_show_notebook", "notebookPath") => PathArg::VaultRelative,
+        ("is_ignored", "path") => PathArg::VaultRelative,
+        ("merge_tags", "from") | ("resolve_wikilink" | "resolve_attachment", "target") => PathArg::NotAPath,
+        _ => PathArg::Vault,
+    }
+}
+
+/// Paths given for the argument `name`, which may be nested
+fn arg_paths<'a>(args: &'a Value, name: &str) -> Vec<&'a str> {
+    match name.split('.').try_fold(args, |value, key| value.get(key)) {
+        Some(Value::String(path)) => vec![path.as_str()],
+        Some(Value::Array(items)) => items.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// The open vault
+struct OpenVault {
+    root: PathBuf,
+    /// Symlinked folders inside the vault may lead outside of it
+    follow_symlinks: bool,
+}
+
+/// Root of the vault commands are limited to, managed by Tauri
+#[derive(Default)]
+pub struct VaultScope {
+    vault: Mutex<Option<OpenVault>>,
+}
+
+impl VaultScope {
+    /// Limit commands to the vault at `root`
+    pub(crate) fn open(&self, root: &Path) -> Result<(), FsError> {
+        let follow_symlinks = read_vault_config(root)?.file_tree.follow_symlinks;
+        *self.vault.lock().unwrap() = Some(OpenVault {
+            root: root.canonicalize()?,
+            follow_symlinks,
+        });
+        Ok(())
+    }
+
+    /// Resolve a path against the open vault, rejecting anything outside it
+    ///
+    /// Relative paths are taken relative to the vault root.
+    pub(crate) fn check(&self, path: &Path) -> Result<PathBuf, FsError> {
+        let guard = self.vault.lock().unwrap();
+        let vault = guard
+            .as_ref()
+            .ok_or_else(|| FsError::InvalidPath("No vault is open".to_string()))?;
+
+        let full = vault.root.join(path);
+        if vault.follow_symlinks
+            && full.starts_with(&vault.root)
+            && !path.components().any(|c| c == std::path::Component::ParentDir)
+        {
+            return Ok(full);
+        }
+        validate_path(&vault.root, path)
+    }
+
+    /// Check the path arguments of a command invocation
+    pub(crate) fn check_args(&self, command: &str, args: &Value) -> Result<(), FsError> {
+        for name in PATH_ARGS {
+            let kind = path_arg(command, name);
+            if kind == PathArg::NotAPath {
+                continue;
+            }
+            for path in arg_paths(args, name) {
+                let path = Path::new(path);
+                if kind != PathArg::VaultRelative && !path.is_absolute() {
+                    return Err(FsError::InvalidPath(format!("Not an absolute path: {}", path.display())));
+                }
+                if kind != PathArg::Outside {
+                    self.check(path)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::fs;
+
+    #[test]
+    fn rejects_paths_outside_the_vault() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = dir.path().join("Vault");
+        fs::create_dir_all(vault.join(".notemaker")).unwrap();
+        fs::write(dir.path().join("secret.md"), "").unwrap();
+
+        let scope = VaultScope::default();
+        assert!(scope.check(&vault.join("Note.md")).is_err());
+        scope.open(&vault).unwrap();
+
+        assert!(scope.check(&vault.join("Projects/New/Note.md")).is_ok());
+        assert!(scope.check(Path::new("Projects/Note.md")).is_ok());
+        assert!(matches!(scope.check(&dir.path().join("secret.md")), Err(FsError::PathTraversal)));
+        assert!(matches!(scope.check(Path::new("../secret.md")), Err(FsError::PathTraversal)));
+
+        let outside = dir.path().join("secret.md").to_string_lossy().to_string();
+        assert!(scope.check_args("read_note", &json!({ "path": outside })).is_err());
+        assert!(scope.check_args("bulk_delete", &json!({ "paths": ["Note.md", outside] })).is_err());
+        assert!(scope.check_args("generate_identity_file", &json!({ "path": outside })).is_ok());
+        assert!(scope.check_args("find_notes", &json!({ "query": outside })).is_ok());
+    }
+
+    #[test]
+    fn rejects_relative_and_nested_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = dir.path().join("Vault");
+        fs::create_dir_all(vault.join(".notemaker")).unwrap();
+        let scope = VaultScope::default();
+        scope.open(&vault).unwrap();
+
+        let inside = vault.join("Note.md").to_string_lossy().to_string();
+        let outside = dir.path().join("out.html").to_string_lossy().to_string();
+        assert!(scope.check_args("read_note", &json!({ "path": inside })).is_ok());
+        assert!(matches!(
+            scope.check_args("read_note", &json!({ "path": ".ssh/id_rsa" })),
+            Err(FsError::InvalidPath(_))
+        ));
+        assert!(scope.check_args("bulk_move", &json!({ "paths": [inside], "targetDir": "Archive" })).is_err());
+
+        let options = |output: &str| json!({ "path": inside, "options": { "outputPath": output } });
+        assert!(scope.check_args("export_note_html", &options(&outside)).is_ok());
+        assert!(scope.check_args("export_note_html", &options("out.html")).is_err());
+        assert!(scope.check_args("export_site", &json!({ "vaultPath": inside, "outputDir": "site" })).is_err());
+        assert!(scope.check_args("import_notion_export", &json!({ "zipPath": "export.zip", "targetDir": outside })).is_err());
+
+        let vault_path = vault.to_string_lossy().to_string();
+        assert!(scope.check_args("git_stage", &json!({ "vaultPath": vault_path, "filePath": "Note.md" })).is_ok());
+        assert!(scope.check_args("git_stage", &json!({ "vaultPath": vault_path, "filePath": "../x.md" })).is_err());
+        assert!(scope.check_args("merge_tags", &json!({ "vaultPath": vault_path, "from": ["work"] })).is_ok());
+    }
+}
@@ -0,0 +1,86 @@
+//! Plain text search over a vault's notes.
+//!
+//! A line matches when it contains every word of the query, ignoring case.
+//! Notes and the markdown blocks of notebooks are searched in path order;
+//! encrypted ones only when the session is unlocked.
+
+use std::fs;
+use std::path::Path;
+
+use super::commands::{decrypt_armored, markdown_files, walk_notes};
+use super::encryption::{is_encrypted, EncryptionSession};
+use super::types::SearchHit;
+
+/// Lines of the vault's notes matching `query`, at most `limit` of them
+pub fn search_vault(
+    vault: &Path,
+    query: &str,
+    limit: Option<usize>,
+    session: Option<&EncryptionSession>,
+) -> Vec<SearchHit> {
+    let words: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+    let limit = limit.unwrap_or(usize::MAX);
+    if words.is_empty() || limit == 0 {
+        return Vec::new();
+    }
+
+    let mut notes = Vec::new();
+    walk_notes(vault, &mut notes);
+    notes.sort();
+
+    let mut hits = Vec::new();
+    for note in notes {
+        for file in markdown_files(&note).unwrap_or_default() {
+            let Ok(stored) = fs::read_to_string(&file) else {
+                continue;
+            };
+            let content = if is_encrypted(&stored) {
+                match decrypt_armored(session, &stored, &file) {
+                    Ok(content) => content,
+                    Err(_) => continue,
+                }
+            } else {
+                stored
+            };
+
+            for (i, line) in content.lines().enumerate() {
+                let lower = line.to_lowercase();
+                if !words.iter().all(|word| lower.contains(word.as_str())) {
+                    continue;
+                }
+                hits.push(SearchHit {
+                    note: note.clone(),
+                    path: file.clone(),
+                    line: i + 1,
+                    text: line.trim().to_string(),
+                });
+                if hits.len() >= limit {
+                    return hits;
+                }
+            }
+        }
+    }
+    hits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_lines_with_all_words() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = dir.path();
+        fs::create_dir(vault.join("Work")).unwrap();
+        fs::write(vault.join("Work/Plan.md"), "# Plan\nShip the Release notes\nrelease party\n").unwrap();
+        fs::write(vault.join("Ideas.md"), "Notes on the next release\n").unwrap();
+
+        let hits = search_vault(vault, "release  NOTES", None, None);
+        let found: Vec<(&Path, usize)> = hits.iter().map(|h| (h.path.as_path(), h.line)).collect();
+        assert_eq!(found, vec![(vault.join("Ideas.md").as_path(), 1), (vault.join("Work/Plan.md").as_path(), 2)]);
+        assert_eq!(hits[1].text, "Ship the Release notes");
+
+        assert_eq!(search_vault(vault, "release", Some(2), None).len(), 2);
+        assert!(search_vault(vault, "  ", None, None).is_empty());
+    }
+}
@@ -0,0 +1,197 @@
+//! SQL blocks run against SQLite files in the vault or configured databases.
+
+use futures_util::TryStreamExt;
+use serde_json::Value;
+use sqlx::any::{install_default_drivers, AnyRow};
+use sqlx::{AnyConnection, Column, Connection, Either, Row};
+use std::path::{Path, PathBuf};
+use url::Url;
+
+use super::commands::{find_vault_root, read_vault_config, FsError};
+use super::encryption::{delete_database_password, load_database_password, save_database_password};
+use super::types::SqlResult;
+
+/// Rows returned to the frontend at most
+const MAX_SQL_ROWS: usize = 1000;
+
+fn db_error(e: sqlx::Error) -> FsError {
+    FsError::Database(e.to_string())
+}
+
+/// Connection URL of an SQLite file
+fn sqlite_url(path: &Path) -> String {
+    format!("sqlite://{}", path.to_string_lossy().replace('\\', "/"))
+}
+
+/// Connection URL for `database`, which is either the name of a connection
+/// from the vault config or an SQLite file relative to `base_dir` or the vault
+pub(crate) fn resolve_database_url(database: &str, base_dir: &Path) -> Result<String, FsError> {
+    let vault = find_vault_root(base_dir).ok_or_else(|| FsError::NotFound(format!("Vault of {}", base_dir.display())))?;
+    let config = read_vault_config(&vault)?;
+
+    if let Some(connection) = config.databases.iter().find(|c| c.name == database) {
+        if let Some(file) = connection.url.strip_prefix("sqlite://").or_else(|| connection.url.strip_prefix("sqlite:")) {
+            let path = Path::new(file);
+            return Ok(if path.is_absolute() {
+                connection.url.clone()
+            } else {
+                sqlite_url(&vault.join(path))
+            });
+        }
+
+        let mut url = Url::parse(&connection.url)
+            .map_err(|e| FsError::Database(format!("{}: invalid URL: {}", connection.name, e)))?;
+        let password = load_database_password(&vault, &connection.name).map_err(|e| FsError::Keychain(e.to_string()))?;
+        if let Some(password) = password {
+            url.set_password(Some(&password))
+                .map_err(|_| FsError::Database(format!("{}: URL cannot carry a password", connection.name)))?;
+        }
+        return Ok(url.to_string());
+    }
+
+    let candidates: [PathBuf; 2] = [base_dir.join(database), vault.join(database)];
+    let file = candidates
+        .iter()
+        .find(|p| p.is_file())
+        .ok_or_else(|| FsError::NotFound(format!("No database connection or SQLite file named {}", database)))?;
+
+    // Only files inside the vault can be opened by path
+    let file = file.canonicalize()?;
+    if !file.starts_with(vault.canonicalize()?) {
+        return Err(FsError::PathTraversal);
+    }
+    Ok(sqlite_url(&file))
+}
+
+/// Decode one cell into a JSON value
+fn cell_value(row: &AnyRow, index: usize) -> Value {
+    if let Ok(value) = row.try_get::<Option<i64>, _>(index) {
+        return value.map(Value::from).unwrap_or(Value::Null);
+    }
+    if let Ok(value) = row.try_get::<Option<f64>, _>(index) {
+        return value.map(Value::from).unwrap_or(Value::Null);
+    }
+    if let Ok(value) = row.try_get::<Option<bool>, _>(index) {
+        return value.map(Value::from).unwrap_or(Value::Null);
+    }
+    if let Ok(value) = row.try_get::<Option<String>, _>(index) {
+        return value.map(Value::from).unwrap_or(Value::Null);
+    }
+    if let Ok(value) = row.try_get::<Option<Vec<u8>>, _>(index) {
+        return value
+            .map(|bytes| Value::from(format!("<{} bytes>", bytes.len())))
+            .unwrap_or(Value::Null);
+    }
+    Value::from(format!("<unsupported {}>", row.columns()[index].type_info()))
+}
+
+/// Run the statements of a SQL block and collect the last result set
+pub(crate) async fn run_sql(url: &str, query: &str) -> Result<SqlResult, FsError> {
+    install_default_drivers();
+    let mut conn = AnyConnection::connect(url).await.map_err(db_error)?;
+
+    let mut result = SqlResult::default();
+    // Whether the rows collected so far belong to a finished statement
+    let mut statement_done = false;
+    let mut statement_rows = 0;
+
+    {
+        let mut stream = sqlx::raw_sql(query).fetch_many(&mut conn);
+        while let Some(item) = stream.try_next().await.map_err(db_error)? {
+            match item {
+                Either::Left(done) => {
+                    if statement_rows == 0 {
+                        result.rows_affected += done.rows_affected();
+                    }
+                    statement_done = true;
+                    statement_rows = 0;
+                }
+                Either::Right(row) => {
+                    if statement_done && statement_rows == 0 {
+                        result.columns.clear();
+                        result.rows.clear();
+                        result.truncated = false;
+                    }
+                    if statement_rows == 0 {
+                        result.columns = row.columns().iter().map(|c| c.name().to_string()).collect();
+                    }
+                    statement_rows += 1;
+                    if result.rows.len() < MAX_SQL_ROWS {
+                        result.rows.push((0..row.len()).map(|i| cell_value(&row, i)).collect());
+                    } else {
+                        result.truncated = true;
+                    }
+                }
+            }
+        }
+    }
+
+    conn.close().await.map_err(db_error)?;
+    Ok(result)
+}
+
+/// Execute a SQL block
+///
+/// `database` names a connection from the vault config or an SQLite file in
+/// the vault, relative to the notebook's folder or the vault root.
+#[tauri::command]
+pub async fn execute_sql_block(
+    database: String,
+    query: String,
+    working_dir: Option<PathBuf>,
+    notebook_path: Option<PathBuf>,
+) -> Result<SqlResult, FsError> {
+    let base_dir = match (&notebook_path, working_dir) {
+        (Some(notebook), _) => notebook.parent().map(Path::to_path_buf).unwrap_or_else(|| notebook.clone()),
+        (None, Some(dir)) => dir,
+        (None, None) => return Err(FsError::InvalidPath("SQL blocks need a notebook or working directory".to_string())),
+    };
+    let url = resolve_database_url(&database, &base_dir)?;
+    run_sql(&url, &query).await
+}
+
+/// Store the password of a configured database connection
+#[tauri::command]
+pub async fn set_database_password(vault_path: PathBuf, name: String, password: String) -> Result<(), FsError> {
+    save_database_password(&vault_path, &name, &password).map_err(|e| FsError::Keychain(e.to_string()))
+}
+
+/// Remove the stored password of a database connection
+#[tauri::command]
+pub async fn clear_database_password(vault_path: PathBuf, name: String) -> Result<(), FsError> {
+    delete_database_password(&vault_path, &name).map_err(|e| FsError::Keychain(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[tokio::test]
+    async fn runs_sql_against_vault_sqlite_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = dir.path().canonicalize().unwrap();
+        fs::create_dir_all(vault.join(".notemaker")).unwrap();
+        let db = vault.join("data.db");
+
+        let setup = format!("{}?mode=rwc", sqlite_url(&db));
+        let created = run_sql(
+            &setup,
+            "CREATE TABLE items (id INTEGER, name TEXT, price REAL); \
+             INSERT INTO items VALUES (1, 'pen', 1.5), (2, 'ink', NULL);",
+        )
+        .await
+        .unwrap();
+        assert_eq!(created.rows_affected, 2);
+
+        let url = resolve_database_url("data.db", &vault.join("Notes")).unwrap();
+        let result = run_sql(&url, "SELECT 1; SELECT id, name, price FROM items ORDER BY id").await.unwrap();
+        assert_eq!(result.columns, vec!["id", "name", "price"]);
+        assert_eq!(result.rows.len(), 2);
+        assert_eq!(result.rows[0][1], Value::from("pen"));
+        assert_eq!(result.rows[0][2], Value::from(1.5));
+        assert_eq!(result.rows[1][2], Value::Null);
+
+        assert!(resolve_database_url("../outside.db", &vault).is_err());
+    }
+}
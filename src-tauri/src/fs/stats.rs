@@ -0,0 +1,126 @@
+//! Word counts and reading time for the status bar.
+//!
+//! Only prose counts: frontmatter and code blocks are skipped, and notebooks
+//! contribute their markdown blocks. Results are cached per note until one
+//! of its files changes.
+
+use pulldown_cmark::{Event, Options, Parser, Tag, TagEnd};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+use std::time::SystemTime;
+
+use super::commands::{markdown_files, FsError};
+use super::encryption::is_encrypted;
+use super::frontmatter::split_frontmatter;
+use super::links::extract_wikilinks;
+use super::types::NoteStats;
+
+/// Average reading speed used for the estimate
+const WORDS_PER_MINUTE: usize = 200;
+
+/// Modification time and size of every file a note is read from
+pub(crate) type Fingerprint = Vec<(SystemTime, u64)>;
+
+fn stats_cache() -> &'static Mutex<HashMap<PathBuf, (Fingerprint, NoteStats)>> {
+    static CACHE: OnceLock<Mutex<HashMap<PathBuf, (Fingerprint, NoteStats)>>> = OnceLock::new();
+    CACHE.get_or_init(Default::default)
+}
+
+/// Statistics of a note's markdown text
+pub(crate) fn note_stats(content: &str) -> NoteStats {
+    let (_, body) = split_frontmatter(content);
+    let mut stats = NoteStats::default();
+    let mut prose = String::new();
+    let mut in_code = false;
+
+    for event in Parser::new_ext(body, Options::ENABLE_TASKLISTS | Options::ENABLE_TABLES) {
+        match event {
+            Event::Start(Tag::CodeBlock(_)) => in_code = true,
+            Event::End(TagEnd::CodeBlock) => in_code = false,
+            Event::Start(Tag::Heading { .. }) => stats.headings += 1,
+            Event::Start(Tag::Link { .. }) => stats.links += 1,
+            Event::TaskListMarker(checked) => {
+                stats.tasks += 1;
+                if checked {
+                    stats.completed_tasks += 1;
+                }
+            }
+            Event::Text(text) | Event::Code(text) if !in_code => prose.push_str(&text),
+            Event::SoftBreak | Event::HardBreak => prose.push(' '),
+            Event::End(TagEnd::Paragraph | TagEnd::Heading(_) | TagEnd::Item | TagEnd::TableCell) => {
+                prose.push('\n')
+            }
+            _ => {}
+        }
+    }
+
+    stats.links += extract_wikilinks(&prose).iter().filter(|l| !l.embed).count();
+    stats.words = prose.split_whitespace().count();
+    stats.characters = prose.chars().filter(|&c| c != '\n').count();
+    stats.reading_minutes = stats.words.div_ceil(WORDS_PER_MINUTE) as u32;
+    stats
+}
+
+/// Word, link and task counts of a note or notebook, with reading time
+#[tauri::command]
+pub async fn get_note_stats(path: PathBuf) -> Result<NoteStats, FsError> {
+    if !path.exists() {
+        return Err(FsError::NotFound(path.display().to_string()));
+    }
+    let files = markdown_files(&path)?;
+    let fingerprint = fingerprint(&files)?;
+    if let Some((cached, stats)) = stats_cache().lock().unwrap().get(&path) {
+        if *cached == fingerprint {
+            return Ok(stats.clone());
+        }
+    }
+
+    let mut parts = Vec::new();
+    for file in &files {
+        let content = fs::read_to_string(file)?;
+        if is_encrypted(&content) {
+            return Err(FsError::Encrypted(path.display().to_string()));
+        }
+        parts.push(content);
+    }
+    let stats = note_stats(&parts.join("\n\n"));
+    stats_cache().lock().unwrap().insert(path, (fingerprint, stats.clone()));
+    Ok(stats)
+}
+
+/// Fingerprint of the files a note is read from, to tell whether the
+/// cached stats are still current
+pub(crate) fn fingerprint(files: &[PathBuf]) -> Result<Fingerprint, FsError> {
+    files
+        .iter()
+        .map(|file| {
+            let metadata = fs::metadata(file)?;
+            Ok((metadata.modified()?, metadata.len()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_prose_only() {
+        let content = "---\ntitle: Plan\ntags: [a, b]\n---\n# Plan\n\nSee [[Goals]] and [docs](https://example.com).\n\n![[chart.png]]\n\n```rust\nfn main() { println!(\"not counted\"); }\n```\n\n- [x] write `draft`\n- [ ] review\n";
+        let stats = note_stats(content);
+
+        assert_eq!(stats.headings, 1);
+        assert_eq!(stats.links, 2);
+        assert_eq!(stats.tasks, 2);
+        assert_eq!(stats.completed_tasks, 1);
+        // Plan / See [[Goals]] and docs. / ![[chart.png]] / write draft / review
+        assert_eq!(stats.words, 9);
+        assert_eq!(stats.reading_minutes, 1);
+        assert_eq!(note_stats("").reading_minutes, 0);
+
+        let long = "word ".repeat(450);
+        assert_eq!(note_stats(&long).reading_minutes, 3);
+    }
+}
@@ -0,0 +1,149 @@
+//! Passphrase strength estimation.
+//!
+//! A small take on zxcvbn: the entropy of a passphrase is estimated from its
+//! character classes, then discounted for the patterns attackers try first
+//! (common passwords, repeats, sequences, keyboard rows and years).
+
+use super::types::PassphraseStrength;
+
+/// Passwords that crack instantly whatever their length
+const COMMON_PASSWORDS: &[&str] = &[
+    "password", "passw0rd", "123456", "12345678", "123456789", "1234567890", "qwerty", "qwertyuiop", "abc123",
+    "letmein", "welcome", "monkey", "dragon", "iloveyou", "admin", "login", "master", "sunshine", "princess",
+    "football", "baseball", "shadow", "superman", "trustno1", "secret", "changeme", "111111", "000000",
+];
+
+/// Keyboard rows and alphabetic/numeric runs, checked forwards and backwards
+const SEQUENCES: &[&str] = &[
+    "abcdefghijklmnopqrstuvwxyz",
+    "0123456789",
+    "qwertyuiop",
+    "asdfghjkl",
+    "zxcvbnm",
+    "qwertzuiop",
+    "azertyuiop",
+];
+
+/// Bits per word of a passphrase made of dictionary words
+const BITS_PER_WORD: f64 = 11.0;
+
+/// Size of the alphabet a password draws from
+fn charset_size(password: &str) -> f64 {
+    let mut size = 0.0;
+    if password.chars().any(|c| c.is_ascii_lowercase()) {
+        size += 26.0;
+    }
+    if password.chars().any(|c| c.is_ascii_uppercase()) {
+        size += 26.0;
+    }
+    if password.chars().any(|c| c.is_ascii_digit()) {
+        size += 10.0;
+    }
+    if password.chars().any(|c| c.is_ascii_punctuation() || c == ' ') {
+        size += 33.0;
+    }
+    if !password.is_ascii() {
+        size += 100.0;
+    }
+    size
+}
+
+/// Whether the three characters at `i` continue a known sequence
+fn in_sequence(chars: &[char], i: usize) -> bool {
+    let window: String = chars[i..i + 3].iter().collect::<String>().to_lowercase();
+    let reversed: String = window.chars().rev().collect();
+    SEQUENCES.iter().any(|seq| seq.contains(&window) || seq.contains(&reversed))
+}
+
+/// Number of characters that add little to the guessing effort: repeats of
+/// the previous character, sequence continuations and four-digit years
+fn predictable_chars(password: &str) -> usize {
+    let chars: Vec<char> = password.chars().collect();
+    let mut predictable = vec![false; chars.len()];
+
+    for i in 1..chars.len() {
+        if chars[i] == chars[i - 1] {
+            predictable[i] = true;
+        }
+    }
+    for i in 0..chars.len().saturating_sub(2) {
+        if in_sequence(&chars, i) {
+            predictable[i + 1] = true;
+            predictable[i + 2] = true;
+        }
+    }
+    for i in 0..chars.len().saturating_sub(3) {
+        let year: String = chars[i..i + 4].iter().collect();
+        if matches!(year.parse::<u32>(), Ok(1900..=2099)) {
+            predictable[i + 1..i + 4].iter_mut().for_each(|p| *p = true);
+        }
+    }
+
+    predictable.iter().filter(|p| **p).count()
+}
+
+/// Estimate how hard a passphrase is to guess
+pub(crate) fn passphrase_strength(password: &str) -> PassphraseStrength {
+    let mut feedback = Vec::new();
+    let lower = password.to_lowercase();
+
+    // Trailing digits and `!` are the usual way to "strengthen" a common one
+    let stem = lower.trim_end_matches(|c: char| c.is_ascii_digit() || c == '!');
+    if COMMON_PASSWORDS.contains(&stem) || COMMON_PASSWORDS.contains(&lower.as_str()) {
+        return PassphraseStrength {
+            score: 0,
+            entropy_bits: 0.0,
+            feedback: vec!["This is one of the most common passwords".to_string()],
+        };
+    }
+
+    let length = password.chars().count();
+    let effective = length - predictable_chars(password);
+    let char_bits = effective as f64 * charset_size(password).max(1.0).log2();
+
+    // Several words are a passphrase: count words rather than letters
+    let words = password
+        .split(|c: char| c.is_whitespace() || matches!(c, '-' | '_' | '.'))
+        .filter(|w| w.chars().count() >= 3)
+        .count();
+    let word_bits = if words >= 3 { words as f64 * BITS_PER_WORD } else { 0.0 };
+    let entropy_bits = char_bits.max(word_bits);
+
+    let score = match entropy_bits {
+        b if b < 28.0 => 0,
+        b if b < 36.0 => 1,
+        b if b < 50.0 => 2,
+        b if b < 64.0 => 3,
+        _ => 4,
+    };
+
+    if length < 12 {
+        feedback.push("Use at least 12 characters".to_string());
+    }
+    if effective < length {
+        feedback.push("Avoid repeats, sequences, keyboard patterns and years".to_string());
+    }
+    if score < 3 && words < 3 {
+        feedback.push("A few unrelated words make a strong, memorable passphrase".to_string());
+    }
+
+    PassphraseStrength {
+        score,
+        entropy_bits,
+        feedback,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scores_passphrases() {
+        assert_eq!(passphrase_strength("password123").score, 0);
+        assert!(passphrase_strength("aaaaaaaaaaaa").score <= 1);
+        assert!(passphrase_strength("qwerty2024!").score <= 2);
+        assert!(passphrase_strength("correct horse battery staple").score >= 3);
+        assert_eq!(passphrase_strength("T7#kq!v9Zr@4mWx2").score, 4);
+    }
+}
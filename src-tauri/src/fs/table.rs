@@ -0,0 +1,231 @@
+//! Table blocks: notebook blocks stored as CSV, or TSV when the block's
+//! language is `tsv`.
+//!
+//! The block file holds the table as a plain grid whose first row names the
+//! columns. Rows shorter than the widest row are padded with empty cells.
+
+use std::path::{Path, PathBuf};
+use tauri::State;
+
+use super::commands::{
+    ensure_unlocked, is_block_encrypted, read_block_content, read_notebook_index_with, write_block_content, FsError,
+};
+use super::encryption::EncryptionSession;
+use super::encryption_commands::EncryptionState;
+use super::types::{BlockType, NotebookBlock, TableData};
+
+/// Field delimiter of a table block
+pub(crate) fn table_delimiter(language: Option<&str>) -> u8 {
+    if language == Some("tsv") {
+        b'\t'
+    } else {
+        b','
+    }
+}
+
+/// Rows of a CSV/TSV document, padded to the same width
+pub(crate) fn parse_table(content: &str, delimiter: u8) -> Result<Vec<Vec<String>>, FsError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .delimiter(delimiter)
+        .from_reader(content.as_bytes());
+
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(|e| FsError::InvalidPath(format!("Invalid table: {}", e)))?;
+        rows.push(record.iter().map(str::to_string).collect::<Vec<_>>());
+    }
+    let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+    for row in &mut rows {
+        row.resize(width, String::new());
+    }
+    Ok(rows)
+}
+
+fn write_table(rows: &[Vec<String>], delimiter: u8) -> Result<String, FsError> {
+    let invalid = |e: String| FsError::InvalidPath(format!("Invalid table: {}", e));
+    let mut writer = csv::WriterBuilder::new().delimiter(delimiter).from_writer(Vec::new());
+    for row in rows {
+        writer.write_record(row).map_err(|e| invalid(e.to_string()))?;
+    }
+    let bytes = writer.into_inner().map_err(|e| invalid(e.to_string()))?;
+    String::from_utf8(bytes).map_err(|e| invalid(e.to_string()))
+}
+
+/// Render a table block as a markdown table, for export and conversion to a
+/// note; content that isn't a valid table is returned unchanged
+pub(crate) fn table_markdown(content: &str, language: Option<&str>) -> String {
+    let rows = match parse_table(content, table_delimiter(language)) {
+        Ok(rows) if !rows.is_empty() && !rows[0].is_empty() => rows,
+        _ => return content.trim_end().to_string(),
+    };
+    let line = |row: &[String]| {
+        let cells: Vec<String> = row.iter().map(|c| c.replace('|', "\\|").replace('\n', "<br>")).collect();
+        format!("| {} |", cells.join(" | "))
+    };
+
+    let mut lines = vec![line(&rows[0]), format!("|{}", " --- |".repeat(rows[0].len()))];
+    lines.extend(rows[1..].iter().map(|row| line(row)));
+    lines.join("\n")
+}
+
+/// Read a table block, apply `edit` to its rows and write them back
+fn edit_table(
+    notebook_path: &Path,
+    block_id: &str,
+    session: Option<&EncryptionSession>,
+    edit: impl FnOnce(&mut Vec<Vec<String>>) -> Result<(), FsError>,
+) -> Result<TableData, FsError> {
+    let index = read_notebook_index_with(notebook_path, session)?;
+    let block = table_block(&index.blocks, block_id)?;
+    ensure_unlocked(block)?;
+    let delimiter = table_delimiter(block.language.as_deref());
+    let mut rows = parse_table(&read_block_content(notebook_path, &index, block, session)?, delimiter)?;
+
+    edit(&mut rows)?;
+    let content = write_table(&rows, delimiter)?;
+    write_block_content(notebook_path, is_block_encrypted(&index, block), &block.file, &content, session)?;
+    Ok(TableData {
+        block_id: block_id.to_string(),
+        rows,
+    })
+}
+
+fn table_block<'a>(blocks: &'a [NotebookBlock], block_id: &str) -> Result<&'a NotebookBlock, FsError> {
+    let block = blocks
+        .iter()
+        .find(|b| b.id == block_id)
+        .ok_or_else(|| FsError::NotFound(format!("Block not found: {}", block_id)))?;
+    if block.block_type != BlockType::Table {
+        return Err(FsError::InvalidPath(format!("Not a table block: {}", block_id)));
+    }
+    Ok(block)
+}
+
+/// Set one cell; row 0 is the header
+pub(crate) fn set_table_cell(
+    rows: &mut [Vec<String>],
+    row: usize,
+    column: usize,
+    value: String,
+) -> Result<(), FsError> {
+    let cell = rows
+        .get_mut(row)
+        .and_then(|r| r.get_mut(column))
+        .ok_or_else(|| FsError::InvalidPath(format!("Cell out of range: {}, {}", row, column)))?;
+    *cell = value;
+    Ok(())
+}
+
+/// Insert an empty row at `position`, or at the end
+pub(crate) fn insert_table_row(rows: &mut Vec<Vec<String>>, position: Option<usize>) {
+    let width = rows.first().map_or(1, Vec::len).max(1);
+    let position = position.unwrap_or(rows.len()).min(rows.len());
+    rows.insert(position, vec![String::new(); width]);
+}
+
+/// Insert a column named `name` at `position`, or at the end
+pub(crate) fn insert_table_column(rows: &mut Vec<Vec<String>>, name: String, position: Option<usize>) {
+    if rows.is_empty() {
+        rows.push(Vec::new());
+    }
+    let width = rows[0].len();
+    let position = position.unwrap_or(width).min(width);
+    for (i, row) in rows.iter_mut().enumerate() {
+        row.insert(position, if i == 0 { name.clone() } else { String::new() });
+    }
+}
+
+/// Rows of a table block, the header first
+#[tauri::command]
+pub async fn get_table_block(
+    notebook_path: PathBuf,
+    block_id: String,
+    state: State<'_, EncryptionState>,
+) -> Result<TableData, FsError> {
+    let session = state.session(&notebook_path);
+    let session = Some(session.as_ref());
+    let index = read_notebook_index_with(&notebook_path, session)?;
+    let block = table_block(&index.blocks, &block_id)?;
+    let content = read_block_content(&notebook_path, &index, block, session)?;
+    Ok(TableData {
+        rows: parse_table(&content, table_delimiter(block.language.as_deref()))?,
+        block_id,
+    })
+}
+
+/// Set the value of one cell of a table block
+#[tauri::command]
+pub async fn update_table_cell(
+    notebook_path: PathBuf,
+    block_id: String,
+    row: usize,
+    column: usize,
+    value: String,
+    state: State<'_, EncryptionState>,
+) -> Result<TableData, FsError> {
+    edit_table(&notebook_path, &block_id, Some(state.session(&notebook_path).as_ref()), |rows| {
+        set_table_cell(rows, row, column, value)
+    })
+}
+
+/// Add an empty row to a table block
+#[tauri::command]
+pub async fn add_table_row(
+    notebook_path: PathBuf,
+    block_id: String,
+    position: Option<usize>,
+    state: State<'_, EncryptionState>,
+) -> Result<TableData, FsError> {
+    edit_table(&notebook_path, &block_id, Some(state.session(&notebook_path).as_ref()), |rows| {
+        insert_table_row(rows, position);
+        Ok(())
+    })
+}
+
+/// Add a column to a table block
+#[tauri::command]
+pub async fn add_table_column(
+    notebook_path: PathBuf,
+    block_id: String,
+    name: String,
+    position: Option<usize>,
+    state: State<'_, EncryptionState>,
+) -> Result<TableData, FsError> {
+    edit_table(&notebook_path, &block_id, Some(state.session(&notebook_path).as_ref()), |rows| {
+        insert_table_column(rows, name, position);
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edits_tables() {
+        let mut rows = parse_table("name,qty\n\"a, b\",2\nc\n", b',').unwrap();
+        assert_eq!(rows[1], vec!["a, b", "2"]);
+        assert_eq!(rows[2], vec!["c", ""]);
+
+        set_table_cell(&mut rows, 2, 1, "5".to_string()).unwrap();
+        assert!(set_table_cell(&mut rows, 3, 0, "x".to_string()).is_err());
+        insert_table_row(&mut rows, Some(1));
+        insert_table_column(&mut rows, "note".to_string(), None);
+        assert_eq!(rows[0], vec!["name", "qty", "note"]);
+        assert_eq!(rows[1], vec!["", "", ""]);
+
+        let content = write_table(&rows, b'\t').unwrap();
+        assert_eq!(parse_table(&content, b'\t').unwrap(), rows);
+        assert_eq!(
+            table_markdown("name,qty\na|b,2\n", None),
+            "| name | qty |\n| --- | --- |\n| a\\|b | 2 |"
+        );
+
+        let mut empty = Vec::new();
+        insert_table_column(&mut empty, "first".to_string(), None);
+        insert_table_row(&mut empty, None);
+        assert_eq!(empty, vec![vec!["first".to_string()], vec![String::new()]]);
+    }
+}
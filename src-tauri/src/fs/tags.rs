@@ -0,0 +1,410 @@
+//! Tags of a vault: frontmatter labels and inline `#tags`.
+//!
+//! Tags compare case-insensitively and may be nested as `parent/child`.
+//! A query ending in `/*` matches a tag and everything nested below it.
+//! Renaming or merging a tag carries its nested tags along, so renaming
+//! `project` turns `project/alpha` into the new name's `alpha`.
+
+use serde_yaml::{Mapping, Value};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use super::commands::{markdown_files, read_markdown_text, walk_notes, write_atomic, FsError};
+use super::encryption::is_encrypted;
+use super::frontmatter::{parse_frontmatter, patch_frontmatter, split_frontmatter, string_list};
+use super::types::TagTreeNode;
+
+/// Frontmatter fields holding tags
+const TAG_FIELDS: [&str; 2] = ["labels", "tags"];
+
+/// Byte ranges of the inline tags in a note body, without their `#`
+///
+/// Headings, code fences and inline code are skipped.
+pub(crate) fn inline_tag_ranges(body: &str) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut in_fence = false;
+    let mut offset = 0;
+
+    for line in body.split_inclusive('\n') {
+        let line_start = offset;
+        offset += line.len();
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+
+        let mut in_code = false;
+        let mut prev = ' ';
+        let mut chars = line.char_indices().peekable();
+        while let Some((i, c)) = chars.next() {
+            if c == '`' {
+                in_code = !in_code;
+            } else if c == '#' && !in_code && prev.is_whitespace() {
+                let len: usize = line[i + 1..]
+                    .chars()
+                    .take_while(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '/'))
+                    .map(char::len_utf8)
+                    .sum();
+                let tag = line[i + 1..i + 1 + len].trim_end_matches('/');
+                if !tag.is_empty() && !tag.chars().all(|c| c.is_ascii_digit()) {
+                    let end = i + 1 + tag.len();
+                    ranges.push(line_start + i + 1..line_start + end);
+                    prev = tag.chars().last().unwrap_or(' ');
+                    while chars.peek().is_some_and(|(j, _)| *j < end) {
+                        chars.next();
+                    }
+                    continue;
+                }
+            }
+            prev = c;
+        }
+    }
+
+    ranges
+}
+
+/// Inline tags of a note body in order of appearance, without duplicates
+pub(crate) fn collect_inline_tags(body: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for range in inline_tag_ranges(body) {
+        let tag = &body[range];
+        if !tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+            tags.push(tag.to_string());
+        }
+    }
+    tags
+}
+
+/// Whether `tag` matches a query: the tag itself, or with a trailing `/*`
+/// the tag and its nested tags
+pub(crate) fn tag_matches(tag: &str, query: &str) -> bool {
+    let tag = tag.trim_start_matches('#');
+    let query = query.trim().trim_start_matches('#');
+    match query.strip_suffix("/*") {
+        Some(prefix) => tag.get(..prefix.len()).is_some_and(|head| {
+            let rest = &tag[prefix.len()..];
+            head.eq_ignore_ascii_case(prefix) && (rest.is_empty() || rest.starts_with('/'))
+        }),
+        None => tag.eq_ignore_ascii_case(query),
+    }
+}
+
+/// All tags of a note's markdown text: frontmatter labels, then inline tags
+pub(crate) fn note_tags(content: &str) -> Vec<String> {
+    let frontmatter = parse_frontmatter(content).unwrap_or_default();
+    let (_, body) = split_frontmatter(content);
+    TAG_FIELDS
+        .iter()
+        .flat_map(|field| string_list(&frontmatter, field))
+        .map(|tag| tag.trim_start_matches('#').to_string())
+        .chain(collect_inline_tags(body))
+        .filter(|tag| !tag.is_empty())
+        .collect()
+}
+
+/// Notes carrying one tag
+pub(crate) struct TaggedNotes {
+    /// The tag as first seen
+    pub name: String,
+    pub notes: BTreeSet<PathBuf>,
+}
+
+/// Every tag of a vault and the notes and notebooks carrying it
+#[derive(Default)]
+pub(crate) struct TagIndex {
+    /// Keyed by the lowercased tag
+    pub tags: BTreeMap<String, TaggedNotes>,
+}
+
+impl TagIndex {
+    /// Scan the vault's notes and notebooks
+    pub fn build(vault_path: &Path) -> Result<Self, FsError> {
+        if !vault_path.is_dir() {
+            return Err(FsError::NotFound(vault_path.display().to_string()));
+        }
+        let mut paths = Vec::new();
+        walk_notes(vault_path, &mut paths);
+
+        let mut index = Self::default();
+        for path in paths {
+            let content = read_markdown_text(&path).unwrap_or_default();
+            if is_encrypted(&content) {
+                continue;
+            }
+            for tag in note_tags(&content) {
+                index
+                    .tags
+                    .entry(tag.to_lowercase())
+                    .or_insert_with(|| TaggedNotes {
+                        name: tag,
+                        notes: BTreeSet::new(),
+                    })
+                    .notes
+                    .insert(path.clone());
+            }
+        }
+        Ok(index)
+    }
+
+    /// Notes carrying a tag that matches `query`
+    pub fn notes_matching(&self, query: &str) -> BTreeSet<PathBuf> {
+        self.tags
+            .values()
+            .filter(|tagged| tag_matches(&tagged.name, query))
+            .flat_map(|tagged| tagged.notes.iter().cloned())
+            .collect()
+    }
+
+    /// Tags arranged by nesting, with note counts
+    pub fn tree(&self) -> Vec<TagTreeNode> {
+        let mut root = TagBranch::default();
+        for tagged in self.tags.values() {
+            let mut branch = &mut root;
+            for segment in tagged.name.split('/').filter(|s| !s.is_empty()) {
+                // Nodes keep the casing of the tag that created them
+                let tag = match branch.tag.as_str() {
+                    "" => segment.to_string(),
+                    parent => format!("{}/{}", parent, segment),
+                };
+                branch = branch
+                    .children
+                    .entry(segment.to_lowercase())
+                    .or_insert_with(|| TagBranch {
+                        name: segment.to_string(),
+                        tag,
+                        ..Default::default()
+                    });
+            }
+            branch.notes.extend(tagged.notes.iter().cloned());
+        }
+        root.children.into_values().map(|branch| branch.into_node().0).collect()
+    }
+
+    /// Notes carrying a tag `renamed` applies to
+    fn notes_to_retag(&self, renamed: &impl Fn(&str) -> Option<String>) -> BTreeSet<PathBuf> {
+        self.tags
+            .values()
+            .filter(|tagged| renamed(&tagged.name).is_some())
+            .flat_map(|tagged| tagged.notes.iter().cloned())
+            .collect()
+    }
+}
+
+/// A tag tree node while the index is being arranged
+#[derive(Default)]
+struct TagBranch {
+    name: String,
+    tag: String,
+    notes: BTreeSet<PathBuf>,
+    children: BTreeMap<String, TagBranch>,
+}
+
+impl TagBranch {
+    /// The finished node and the notes of its whole subtree
+    fn into_node(self) -> (TagTreeNode, BTreeSet<PathBuf>) {
+        let count = self.notes.len();
+        let mut subtree = self.notes;
+        let mut children = Vec::new();
+        for child in self.children.into_values() {
+            let (node, notes) = child.into_node();
+            subtree.extend(notes);
+            children.push(node);
+        }
+        let node = TagTreeNode {
+            name: self.name,
+            tag: self.tag,
+            count,
+            total: subtree.len(),
+            children,
+        };
+        (node, subtree)
+    }
+}
+
+/// `tag` under its new name if it is `from` or nested below it
+fn rename_one(tag: &str, from: &str, to: &str) -> Option<String> {
+    let tag = tag.trim_start_matches('#');
+    let prefix = tag.get(..from.len())?;
+    let rest = &tag[from.len()..];
+    if !prefix.eq_ignore_ascii_case(from) || !(rest.is_empty() || rest.starts_with('/')) {
+        return None;
+    }
+    Some(format!("{}{}", to, rest))
+}
+
+/// Frontmatter tag lists with renamed tags, deduplicated
+fn retag_frontmatter(content: &str, renamed: &impl Fn(&str) -> Option<String>) -> Result<String, FsError> {
+    let Some(frontmatter) = parse_frontmatter(content) else {
+        return Ok(content.to_string());
+    };
+    let mut patch = Mapping::new();
+    for field in TAG_FIELDS {
+        let tags = string_list(&frontmatter, field);
+        if !tags.iter().any(|tag| renamed(tag).is_some()) {
+            continue;
+        }
+        let mut retagged: Vec<String> = Vec::new();
+        for tag in tags {
+            let tag = renamed(&tag).unwrap_or(tag);
+            if !retagged.iter().any(|t| t.eq_ignore_ascii_case(&tag)) {
+                retagged.push(tag);
+            }
+        }
+        patch.insert(
+            Value::String(field.to_string()),
+            Value::Sequence(retagged.into_iter().map(Value::String).collect()),
+        );
+    }
+    if patch.is_empty() {
+        return Ok(content.to_string());
+    }
+    Ok(patch_frontmatter(content, &patch)?)
+}
+
+/// A note's markdown with its frontmatter and inline tags renamed
+fn retag_content(content: &str, renamed: &impl Fn(&str) -> Option<String>) -> Result<String, FsError> {
+    let content = retag_frontmatter(content, renamed)?;
+    let (_, body) = split_frontmatter(&content);
+    let body_start = content.len() - body.len();
+
+    let mut result = String::with_capacity(content.len());
+    let mut last = 0;
+    for range in inline_tag_ranges(body) {
+        let Some(tag) = renamed(&body[range.clone()]) else {
+            continue;
+        };
+        result.push_str(&content[last..body_start + range.start]);
+        result.push_str(&tag);
+        last = body_start + range.end;
+    }
+    result.push_str(&content[last..]);
+    Ok(result)
+}
+
+/// Rewrite the tags `renamed` applies to across the vault, returning the
+/// files written
+fn retag_vault(vault_path: &Path, renamed: impl Fn(&str) -> Option<String>) -> Result<Vec<PathBuf>, FsError> {
+    let index = TagIndex::build(vault_path)?;
+    let mut modified = Vec::new();
+    for note in index.notes_to_retag(&renamed) {
+        for file in markdown_files(&note)? {
+            let content = fs::read_to_string(&file)?;
+            let retagged = retag_content(&content, &renamed)?;
+            if retagged != content {
+                write_atomic(&file, retagged)?;
+                modified.push(file);
+            }
+        }
+    }
+    Ok(modified)
+}
+
+/// A tag as given by the user, without `#`; tags can't contain whitespace
+fn tag_name(tag: &str) -> Result<&str, FsError> {
+    let tag = tag.trim().trim_start_matches('#').trim_end_matches('/');
+    if tag.is_empty() || tag.contains(char::is_whitespace) {
+        return Err(FsError::InvalidPath(format!("Invalid tag: {}", tag)));
+    }
+    Ok(tag)
+}
+
+/// Rename a tag, and the tags nested below it, in every note
+///
+/// Returns the files that changed, ready to be committed.
+#[tauri::command]
+pub async fn rename_tag(vault_path: PathBuf, old: String, new: String) -> Result<Vec<PathBuf>, FsError> {
+    let (old, new) = (tag_name(&old)?, tag_name(&new)?);
+    retag_vault(&vault_path, |tag| rename_one(tag, old, new))
+}
+
+/// Replace several tags with one in every note
+///
+/// Returns the files that changed, ready to be committed.
+#[tauri::command]
+pub async fn merge_tags(vault_path: PathBuf, from: Vec<String>, into: String) -> Result<Vec<PathBuf>, FsError> {
+    let into = tag_name(&into)?;
+    let from = from.iter().map(|tag| tag_name(tag)).collect::<Result<Vec<_>, _>>()?;
+    retag_vault(&vault_path, |tag| from.iter().find_map(|old| rename_one(tag, old, into)))
+}
+
+/// Tags of a vault arranged by nesting, for the tag pane
+#[tauri::command]
+pub async fn get_tag_tree(vault_path: PathBuf) -> Result<Vec<TagTreeNode>, FsError> {
+    Ok(TagIndex::build(&vault_path)?.tree())
+}
+
+/// Notes and notebooks carrying a tag; `parent/*` also matches nested tags
+#[tauri::command]
+pub async fn find_notes_by_tag(vault_path: PathBuf, query: String) -> Result<Vec<PathBuf>, FsError> {
+    Ok(TagIndex::build(&vault_path)?.notes_matching(&query).into_iter().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renames_labels_and_inline_tags() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = dir.path();
+        let plan = vault.join("Plan.md");
+        let other = vault.join("Other.md");
+        fs::write(&plan, "---\n# keep\nlabels: [Project, project/alpha, done]\n---\n# Plan\nWork on #project/alpha and #projects.\n`#project`\n").unwrap();
+        fs::write(&other, "---\nlabels: [misc]\n---\nNothing #here\n").unwrap();
+
+        let modified = tauri::async_runtime::block_on(rename_tag(
+            vault.to_path_buf(),
+            "#project".to_string(),
+            "work".to_string(),
+        ))
+        .unwrap();
+        assert_eq!(modified, vec![plan.clone()]);
+        assert_eq!(
+            fs::read_to_string(&plan).unwrap(),
+            "---\n# keep\nlabels:\n- work\n- work/alpha\n- done\n---\n# Plan\nWork on #work/alpha and #projects.\n`#project`\n"
+        );
+
+        let modified = tauri::async_runtime::block_on(merge_tags(
+            vault.to_path_buf(),
+            vec!["done".to_string(), "misc".to_string()],
+            "archive".to_string(),
+        ))
+        .unwrap();
+        assert_eq!(modified.len(), 2);
+        assert_eq!(fs::read_to_string(&other).unwrap(), "---\nlabels:\n- archive\n---\nNothing #here\n");
+
+        let index = TagIndex::build(vault).unwrap();
+        assert_eq!(index.tags["work/alpha"].notes.len(), 1);
+        assert!(!index.tags.contains_key("project"));
+    }
+
+    #[test]
+    fn arranges_nested_tags() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = dir.path();
+        fs::write(vault.join("A.md"), "---\nlabels: [project/alpha, Project/beta]\n---\n").unwrap();
+        fs::write(vault.join("B.md"), "Working on #project and #project/alpha/ui\n").unwrap();
+        fs::write(vault.join("C.md"), "#projects\n").unwrap();
+
+        let index = TagIndex::build(vault).unwrap();
+        assert_eq!(index.notes_matching("project/*").len(), 2);
+        assert_eq!(index.notes_matching("#Project").len(), 1);
+        assert!(tag_matches("project/alpha", "project/*"));
+        assert!(!tag_matches("projects", "project/*"));
+
+        let tree = index.tree();
+        assert_eq!(tree.iter().map(|n| n.tag.as_str()).collect::<Vec<_>>(), vec!["project", "projects"]);
+        let project = &tree[0];
+        assert_eq!((project.count, project.total), (1, 2));
+        assert_eq!(project.children.iter().map(|n| n.tag.as_str()).collect::<Vec<_>>(), vec!["project/alpha", "project/beta"]);
+        let alpha = &project.children[0];
+        assert_eq!((alpha.count, alpha.total), (1, 2));
+        assert_eq!(alpha.children[0].tag, "project/alpha/ui");
+    }
+}
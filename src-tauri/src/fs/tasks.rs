@@ -0,0 +1,256 @@
+//! Checkbox items across a vault's notes, for a task list outside kanban.
+//!
+//! Any list item starting with `[ ]` or `[x]` counts, in notes and in the
+//! markdown blocks of notebooks; frontmatter and code fences are skipped.
+//! An item is due on the date following `📅`, `due:` or `@due(`, and its
+//! inline `#tags` are its tags. Encrypted notes and blocks are skipped.
+
+use chrono::NaiveDate;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::commands::{is_notebook, read_notebook_index, walk_notes, write_atomic, FsError};
+use super::encryption::is_encrypted;
+use super::frontmatter::split_frontmatter;
+use super::kanban::parse_date;
+use super::tags::{collect_inline_tags, tag_matches};
+use super::types::{BlockType, ChecklistFilter, ChecklistItem};
+
+/// Text introducing an item's due date, matched case-insensitively
+const DUE_MARKERS: [&str; 3] = ["📅", "due:", "@due("];
+
+/// Byte offset of the mark inside a list item's checkbox, and whether the
+/// box is checked
+fn checkbox(line: &str) -> Option<(usize, bool)> {
+    let item = line.trim_start();
+    let rest = match item.strip_prefix(['-', '*', '+']) {
+        Some(rest) => rest,
+        None => {
+            let digits = item.len() - item.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+            if digits == 0 {
+                return None;
+            }
+            item[digits..].strip_prefix(['.', ')'])?
+        }
+    };
+    let boxed = rest.strip_prefix(' ')?.trim_start_matches(' ');
+    let bytes = boxed.as_bytes();
+    if bytes.len() < 3 || bytes[0] != b'[' || bytes[2] != b']' {
+        return None;
+    }
+    let checked = match bytes[1] {
+        b' ' => false,
+        b'x' | b'X' => true,
+        _ => return None,
+    };
+    if !matches!(bytes.get(3), None | Some(b' ') | Some(b'\t') | Some(b'\r')) {
+        return None;
+    }
+    Some((line.len() - boxed.len() + 1, checked))
+}
+
+fn task_due(text: &str) -> Option<NaiveDate> {
+    let lower = text.to_ascii_lowercase();
+    DUE_MARKERS.iter().find_map(|marker| {
+        let start = lower.find(marker)? + marker.len();
+        parse_date(text[start..].trim_start())
+    })
+}
+
+/// Checkbox items of a markdown file's text, with their lines
+fn checklist(content: &str) -> Vec<(usize, bool, &str)> {
+    let (_, body) = split_frontmatter(content);
+    let skipped = content[..content.len() - body.len()].matches('\n').count();
+    let mut in_fence = false;
+    let mut items = Vec::new();
+
+    for (i, line) in body.lines().enumerate() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+        if let Some((mark, checked)) = checkbox(line) {
+            items.push((skipped + i + 1, checked, line[mark + 2..].trim()));
+        }
+    }
+    items
+}
+
+/// Files of a note that can hold items, with the notebook block of each
+fn task_files(note: &Path) -> Vec<(PathBuf, Option<String>)> {
+    if !is_notebook(note) {
+        return vec![(note.to_path_buf(), None)];
+    }
+    let Ok(index) = read_notebook_index(note) else {
+        return Vec::new();
+    };
+    index
+        .blocks
+        .iter()
+        .filter(|b| b.block_type == BlockType::Markdown)
+        .map(|b| (note.join(&b.file), Some(b.id.clone())))
+        .filter(|(file, _)| file.is_file())
+        .collect()
+}
+
+/// Checkbox items of a note or notebook, in order
+pub(crate) fn note_checklist(note: &Path) -> Vec<ChecklistItem> {
+    let mut items = Vec::new();
+    for (file, block_id) in task_files(note) {
+        let Ok(content) = fs::read_to_string(&file) else {
+            continue;
+        };
+        if is_encrypted(&content) {
+            continue;
+        }
+        for (line, checked, text) in checklist(&content) {
+            items.push(ChecklistItem {
+                note: note.to_path_buf(),
+                path: file.clone(),
+                block_id: block_id.clone(),
+                line,
+                text: text.to_string(),
+                checked,
+                due: task_due(text).map(|d| d.format("%Y-%m-%d").to_string()),
+                tags: collect_inline_tags(text),
+            });
+        }
+    }
+    items
+}
+
+/// Whether an item matches all criteria of the filter
+fn item_matches(item: &ChecklistItem, filter: &ChecklistFilter) -> bool {
+    if !filter
+        .tags
+        .iter()
+        .all(|query| item.tags.iter().any(|tag| tag_matches(tag, query)))
+    {
+        return false;
+    }
+
+    let from = filter.due_from.as_deref().and_then(parse_date);
+    let to = filter.due_to.as_deref().and_then(parse_date);
+    if from.is_some() || to.is_some() {
+        let Some(due) = item.due.as_deref().and_then(parse_date) else {
+            return false;
+        };
+        if from.is_some_and(|from| due < from) || to.is_some_and(|to| due > to) {
+            return false;
+        }
+    }
+
+    match filter.text.as_deref().map(str::trim).filter(|t| !t.is_empty()) {
+        Some(text) => item.text.to_lowercase().contains(&text.to_lowercase()),
+        None => true,
+    }
+}
+
+/// Unchecked items of the vault matching a filter, earliest due first and
+/// undated ones last
+pub fn open_tasks(vault: &Path, filter: &ChecklistFilter) -> Vec<ChecklistItem> {
+    let mut notes = Vec::new();
+    walk_notes(vault, &mut notes);
+    let mut items: Vec<ChecklistItem> = notes
+        .iter()
+        .flat_map(|note| note_checklist(note))
+        .filter(|item| !item.checked && item_matches(item, filter))
+        .collect();
+    items.sort_by(|a, b| {
+        (a.due.is_none(), &a.due, &a.path, a.line).cmp(&(b.due.is_none(), &b.due, &b.path, b.line))
+    });
+    items
+}
+
+#[tauri::command]
+pub async fn list_open_tasks(vault_path: PathBuf, filter: ChecklistFilter) -> Result<Vec<ChecklistItem>, FsError> {
+    if !vault_path.is_dir() {
+        return Err(FsError::NotFound(vault_path.display().to_string()));
+    }
+    Ok(open_tasks(&vault_path, &filter))
+}
+
+/// Check or uncheck the item on a line (starting at 1) of a note or block
+/// file, returning whether it is now checked
+#[tauri::command]
+pub async fn toggle_task(path: PathBuf, line: usize) -> Result<bool, FsError> {
+    if !path.is_file() {
+        return Err(FsError::NotFound(path.display().to_string()));
+    }
+    let mut content = fs::read_to_string(&path)?;
+    if is_encrypted(&content) {
+        return Err(FsError::Encrypted(path.display().to_string()));
+    }
+
+    let not_a_task = || FsError::InvalidPath(format!("No task on line {} of {}", line, path.display()));
+    let start: usize = content
+        .split_inclusive('\n')
+        .take(line.checked_sub(1).ok_or_else(not_a_task)?)
+        .map(str::len)
+        .sum();
+    let text = content[start..].lines().next().ok_or_else(not_a_task)?;
+    let (mark, checked) = checkbox(text).ok_or_else(not_a_task)?;
+
+    let mark = start + mark;
+    content.replace_range(mark..mark + 1, if checked { " " } else { "x" });
+    write_atomic(&path, content)?;
+    Ok(!checked)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_items_with_due_dates_and_tags() {
+        let content = "---\ntodo: [ ]\n---\n- [ ] Call Ann 📅 2024-05-03 #phone\n  * [x] done\n1. [ ] Report due: 2024-05-01\n```\n- [ ] not a task\n```\n- [] nope\n- [ ]tight\n";
+        let items = checklist(content);
+        assert_eq!(
+            items,
+            vec![
+                (4, false, "Call Ann 📅 2024-05-03 #phone"),
+                (5, true, "done"),
+                (6, false, "Report due: 2024-05-01"),
+            ]
+        );
+        assert_eq!(task_due(items[0].2), NaiveDate::from_ymd_opt(2024, 5, 3));
+        assert_eq!(task_due("Ship it @DUE(2024-06-01)"), NaiveDate::from_ymd_opt(2024, 6, 1));
+        assert_eq!(task_due("no date"), None);
+    }
+
+    #[test]
+    fn lists_and_toggles_open_tasks() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = dir.path();
+        fs::write(vault.join("Plan.md"), "# Plan\n- [ ] Later #work\n- [ ] Soon due: 2024-05-01 #work/urgent\n- [x] Done\n").unwrap();
+        fs::write(vault.join("Home.md"), "- [ ] Groceries 📅 2024-04-01\n").unwrap();
+
+        let list = |filter: ChecklistFilter| {
+            tauri::async_runtime::block_on(list_open_tasks(vault.to_path_buf(), filter))
+                .unwrap()
+                .into_iter()
+                .map(|item| item.text)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            list(ChecklistFilter::default()),
+            vec!["Groceries 📅 2024-04-01", "Soon due: 2024-05-01 #work/urgent", "Later #work"]
+        );
+        let work = ChecklistFilter {
+            tags: vec!["work/*".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(list(work.clone()), vec!["Soon due: 2024-05-01 #work/urgent", "Later #work"]);
+
+        let plan = vault.join("Plan.md");
+        assert!(tauri::async_runtime::block_on(toggle_task(plan.clone(), 3)).unwrap());
+        assert_eq!(list(work), vec!["Later #work"]);
+        assert!(!tauri::async_runtime::block_on(toggle_task(plan.clone(), 4)).unwrap());
+        assert!(fs::read_to_string(&plan).unwrap().ends_with("- [ ] Done\n"));
+        assert!(tauri::async_runtime::block_on(toggle_task(plan, 1)).is_err());
+    }
+}
@@ -0,0 +1,444 @@
+//! Variable expansion for note, notebook and kanban templates.
+//!
+//! Templates live in `.notemaker/templates/<name>.md` and may contain:
+//!
+//! - `{{title}}`, `{{vault}}`, `{{date}}`, `{{time}}`, `{{datetime}}`,
+//!   `{{year}}`, `{{month}}` and `{{day}}`
+//! - `{{date:%d.%m.%Y}}` with chrono format specifiers
+//! - `{{prompt:Question}}`, filled in from what the user answered
+//! - `{{#if name}}...{{else}}...{{/if}}`, taken when the value is non-empty
+//!
+//! Values passed by the caller win over the built-in ones. Unknown names are
+//! left in place so a typo shows up in the note.
+
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Local, NaiveDate};
+use serde_yaml::{Mapping, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::commands::{find_vault_root, read_vault_config, write_atomic, FsError};
+use super::encryption::is_encrypted;
+use super::frontmatter::{parse_frontmatter, patch_frontmatter, string_field};
+
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+const DEFAULT_TIME_FORMAT: &str = "%H:%M";
+
+#[derive(Debug, PartialEq)]
+enum Node {
+    Text(String),
+    Value(String),
+    If {
+        name: String,
+        then: Vec<Node>,
+        otherwise: Vec<Node>,
+    },
+}
+
+/// An `{{#if}}` whose body is still being parsed
+struct Frame {
+    name: String,
+    then: Option<Vec<Node>>,
+    parent: Vec<Node>,
+}
+
+/// Length of the line break to drop after a block tag that is alone on its
+/// line, so `{{#if}}` and friends don't leave blank lines behind
+///
+/// `before` is the template up to the tag, `after` what follows it. The
+/// indentation before the tag is trimmed from `nodes` along the way.
+fn trim_tag_line(nodes: &mut [Node], before: &str, after: &str) -> usize {
+    let line_start = before.rsplit('\n').next().unwrap_or("");
+    let line_end = after.find('\n').filter(|&end| after[..end].trim().is_empty());
+
+    match line_end {
+        Some(end) if line_start.trim().is_empty() => {
+            if let Some(Node::Text(text)) = nodes.last_mut() {
+                let kept = text.trim_end_matches([' ', '\t']).len();
+                text.truncate(kept);
+            }
+            end + 1
+        }
+        _ => 0,
+    }
+}
+
+fn parse(template: &str) -> Result<Vec<Node>, FsError> {
+    let mut stack: Vec<Frame> = Vec::new();
+    let mut nodes = Vec::new();
+    let mut rest = template;
+
+    while let Some(open) = rest.find("{{") {
+        let Some(close) = rest[open..].find("}}").map(|c| open + c) else {
+            break;
+        };
+        if open > 0 {
+            nodes.push(Node::Text(rest[..open].to_string()));
+        }
+        let tag = rest[open + 2..close].trim();
+        let before = &template[..template.len() - rest.len() + open];
+        rest = &rest[close + 2..];
+
+        let is_block = tag.starts_with('#') || tag == "else" || tag.starts_with('/');
+        if is_block {
+            let skip = trim_tag_line(&mut nodes, before, rest);
+            rest = &rest[skip..];
+        }
+
+        if let Some(name) = tag.strip_prefix("#if ") {
+            stack.push(Frame {
+                name: name.trim().to_string(),
+                then: None,
+                parent: std::mem::take(&mut nodes),
+            });
+        } else if tag == "else" {
+            let frame = stack
+                .last_mut()
+                .filter(|frame| frame.then.is_none())
+                .ok_or_else(|| FsError::InvalidPath("Template has {{else}} outside {{#if}}".to_string()))?;
+            frame.then = Some(std::mem::take(&mut nodes));
+        } else if tag == "/if" {
+            let frame = stack
+                .pop()
+                .ok_or_else(|| FsError::InvalidPath("Template has {{/if}} without {{#if}}".to_string()))?;
+            let body = std::mem::replace(&mut nodes, frame.parent);
+            let (then, otherwise) = match frame.then {
+                Some(then) => (then, body),
+                None => (body, Vec::new()),
+            };
+            nodes.push(Node::If {
+                name: frame.name,
+                then,
+                otherwise,
+            });
+        } else if is_block {
+            return Err(FsError::InvalidPath(format!("Unknown template tag: {{{{{}}}}}", tag)));
+        } else {
+            nodes.push(Node::Value(tag.to_string()));
+        }
+    }
+
+    if let Some(frame) = stack.last() {
+        return Err(FsError::InvalidPath(format!("Template has unclosed {{{{#if {}}}}}", frame.name)));
+    }
+    if !rest.is_empty() {
+        nodes.push(Node::Text(rest.to_string()));
+    }
+    Ok(nodes)
+}
+
+/// Format `time`, or `None` when the format has invalid specifiers
+fn format_time(time: &DateTime<Local>, format: &str) -> Option<String> {
+    let items: Vec<Item> = StrftimeItems::new(format).collect();
+    if items.iter().any(|item| matches!(item, Item::Error)) {
+        return None;
+    }
+    Some(time.format_with_items(items.into_iter()).to_string())
+}
+
+/// Values a template is rendered with
+pub(crate) struct TemplateContext {
+    pub vars: HashMap<String, String>,
+    pub vault: String,
+    pub now: DateTime<Local>,
+}
+
+impl TemplateContext {
+    /// Context for creating something at `path`, titled `title`
+    pub(crate) fn for_path(path: &Path, title: &str) -> Self {
+        let vault = find_vault_root(path)
+            .map(|root| vault_name(&root))
+            .unwrap_or_default();
+        Self {
+            vars: HashMap::from([("title".to_string(), title.to_string())]),
+            vault,
+            now: Local::now(),
+        }
+    }
+
+    fn value(&self, name: &str) -> Option<String> {
+        if let Some(value) = self.vars.get(name) {
+            return Some(value.clone());
+        }
+        if let Some(question) = name.strip_prefix("prompt:") {
+            return Some(self.vars.get(question.trim()).cloned().unwrap_or_default());
+        }
+        if let Some(format) = name.strip_prefix("date:") {
+            return format_time(&self.now, format);
+        }
+        match name {
+            "title" => Some(String::new()),
+            "vault" => Some(self.vault.clone()),
+            "date" => format_time(&self.now, DEFAULT_DATE_FORMAT),
+            "time" => format_time(&self.now, DEFAULT_TIME_FORMAT),
+            "datetime" => Some(self.now.to_rfc3339()),
+            "year" => format_time(&self.now, "%Y"),
+            "month" => format_time(&self.now, "%m"),
+            "day" => format_time(&self.now, "%d"),
+            _ => None,
+        }
+    }
+
+    fn render_nodes(&self, nodes: &[Node], out: &mut String) {
+        for node in nodes {
+            match node {
+                Node::Text(text) => out.push_str(text),
+                Node::Value(name) => match self.value(name) {
+                    Some(value) => out.push_str(&value),
+                    None => {
+                        out.push_str("{{");
+                        out.push_str(name);
+                        out.push_str("}}");
+                    }
+                },
+                Node::If { name, then, otherwise } => {
+                    let taken = self.value(name).is_some_and(|v| !v.trim().is_empty());
+                    self.render_nodes(if taken { then } else { otherwise }, out);
+                }
+            }
+        }
+    }
+
+    /// Expand a template's variables and conditionals
+    pub(crate) fn render(&self, template: &str) -> Result<String, FsError> {
+        let nodes = parse(template)?;
+        let mut out = String::with_capacity(template.len());
+        self.render_nodes(&nodes, &mut out);
+        Ok(out)
+    }
+}
+
+/// The vault's configured name, or its folder name
+fn vault_name(vault: &Path) -> String {
+    read_vault_config(vault)
+        .ok()
+        .map(|config| config.vault.name)
+        .filter(|name| !name.is_empty())
+        .or_else(|| vault.file_name().map(|n| n.to_string_lossy().to_string()))
+        .unwrap_or_default()
+}
+
+fn template_path(vault: &Path, name: &str) -> Result<PathBuf, FsError> {
+    let name = name.trim_end_matches(".md");
+    if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+        return Err(FsError::InvalidPath(format!("Invalid template name: {}", name)));
+    }
+    Ok(vault.join(".notemaker").join("templates").join(format!("{}.md", name)))
+}
+
+/// Content of a vault template, if it exists
+pub fn read_vault_template(vault: &Path, name: &str) -> Result<Option<String>, FsError> {
+    let path = template_path(vault, name)?;
+    match fs::read_to_string(path) {
+        Ok(content) => Ok(Some(content)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn add_prompt(found: &mut Vec<String>, name: &str) {
+    if let Some(question) = name.strip_prefix("prompt:") {
+        let question = question.trim().to_string();
+        if !found.contains(&question) {
+            found.push(question);
+        }
+    }
+}
+
+/// Questions a template asks through `{{prompt:...}}`, in order of appearance
+fn prompts(nodes: &[Node], found: &mut Vec<String>) {
+    for node in nodes {
+        match node {
+            Node::Text(_) => {}
+            Node::Value(name) => add_prompt(found, name),
+            Node::If { name, then, otherwise } => {
+                add_prompt(found, name);
+                prompts(then, found);
+                prompts(otherwise, found);
+            }
+        }
+    }
+}
+
+fn load_template(vault: &Path, name: &str) -> Result<String, FsError> {
+    read_vault_template(vault, name)?.ok_or_else(|| FsError::NotFound(format!("Template not found: {}", name)))
+}
+
+/// Render a vault template with the given variables
+///
+/// `vars` holds `title`, custom variables and the answers to the template's
+/// prompts, keyed by question.
+#[tauri::command]
+pub fn render_template(vault_path: PathBuf, name: String, vars: HashMap<String, String>) -> Result<String, FsError> {
+    let template = load_template(&vault_path, &name)?;
+    let context = TemplateContext {
+        vars,
+        vault: vault_name(&vault_path),
+        now: Local::now(),
+    };
+    context.render(&template)
+}
+
+/// Questions to ask before rendering a vault template
+#[tauri::command]
+pub fn get_template_prompts(vault_path: PathBuf, name: String) -> Result<Vec<String>, FsError> {
+    let template = load_template(&vault_path, &name)?;
+    let mut found: Vec<String> = Vec::new();
+    prompts(&parse(&template)?, &mut found);
+    Ok(found)
+}
+
+/// Frontmatter fields that describe one note rather than the template
+const VOLATILE_FIELDS: &[&str] = &["created", "modified", "updated"];
+
+/// Titles shorter than this are too likely to match unrelated text to be
+/// turned into `{{title}}`
+const MIN_PARAMETERIZED_TITLE: usize = 3;
+
+/// Replace `YYYY-MM-DD` dates with `{{date}}`
+fn parameterize_dates(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = String::with_capacity(text.len());
+    let mut copied = 0;
+    let mut i = 0;
+
+    while i + 10 <= bytes.len() {
+        let candidate = &bytes[i..i + 10];
+        let shaped = candidate
+            .iter()
+            .enumerate()
+            .all(|(j, b)| if j == 4 || j == 7 { *b == b'-' } else { b.is_ascii_digit() });
+        let bounded = (i == 0 || !bytes[i - 1].is_ascii_digit())
+            && bytes.get(i + 10).is_none_or(|b| !b.is_ascii_digit());
+        if shaped && bounded && NaiveDate::parse_from_str(&text[i..i + 10], "%Y-%m-%d").is_ok() {
+            out.push_str(&text[copied..i]);
+            out.push_str("{{date}}");
+            i += 10;
+            copied = i;
+        } else {
+            i += 1;
+        }
+    }
+
+    out.push_str(&text[copied..]);
+    out
+}
+
+/// Template text for a note
+///
+/// Volatile frontmatter is dropped. With `parameterize`, the title and dates
+/// become `{{title}}` and `{{date}}`, and `created` is kept as `{{date}}`.
+fn note_template(content: &str, title: &str, parameterize: bool) -> Result<String, FsError> {
+    let frontmatter = parse_frontmatter(content).unwrap_or_default();
+    let mut patch = Mapping::new();
+    for field in VOLATILE_FIELDS {
+        let value = if parameterize && *field == "created" && frontmatter.contains_key(*field) {
+            Value::String("{{date}}".to_string())
+        } else {
+            Value::Null
+        };
+        patch.insert(Value::String(field.to_string()), value);
+    }
+    if parameterize && frontmatter.contains_key("title") {
+        patch.insert(Value::String("title".to_string()), Value::String("{{title}}".to_string()));
+    }
+    let mut template = patch_frontmatter(content, &patch)?;
+
+    if parameterize {
+        if title.chars().count() >= MIN_PARAMETERIZED_TITLE {
+            template = template.replace(title, "{{title}}");
+        }
+        template = parameterize_dates(&template);
+    }
+    Ok(template)
+}
+
+/// Save a note as a vault template
+///
+/// Returns the template's path. An existing template of that name is not
+/// overwritten.
+#[tauri::command]
+pub fn create_template_from_note(
+    note_path: PathBuf,
+    template_name: String,
+    parameterize: bool,
+) -> Result<PathBuf, FsError> {
+    let vault = find_vault_root(&note_path)
+        .ok_or_else(|| FsError::InvalidPath(format!("Not in a vault: {}", note_path.display())))?;
+    let path = template_path(&vault, template_name.trim())?;
+    if path.exists() {
+        return Err(FsError::InvalidPath(format!("Template already exists: {}", template_name)));
+    }
+
+    let content = fs::read_to_string(&note_path)?;
+    if is_encrypted(&content) {
+        return Err(FsError::Encrypted(format!("{} cannot be a template", note_path.display())));
+    }
+    let title = parse_frontmatter(&content)
+        .and_then(|map| string_field(&map, "title"))
+        .or_else(|| note_path.file_stem().map(|s| s.to_string_lossy().to_string()))
+        .unwrap_or_default();
+
+    let template = note_template(&content, &title, parameterize)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    write_atomic(&path, template)?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn context(vars: &[(&str, &str)]) -> TemplateContext {
+        TemplateContext {
+            vars: vars.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+            vault: "Notes".to_string(),
+            now: Local.with_ymd_and_hms(2024, 3, 9, 14, 5, 0).unwrap(),
+        }
+    }
+
+    #[test]
+    fn expands_variables_and_dates() {
+        let rendered = context(&[("title", "Plan")])
+            .render("# {{title}} ({{vault}})\n{{date}} {{time}} {{date:%d.%m.%Y}} {{unknown}} {{date:%Q}}")
+            .unwrap();
+        assert_eq!(rendered, "# Plan (Notes)\n2024-03-09 14:05 09.03.2024 {{unknown}} {{date:%Q}}");
+    }
+
+    #[test]
+    fn renders_conditionals_and_prompts() {
+        let template = "Title\n{{#if prompt:Attendees}}\nWith {{prompt:Attendees}}\n{{else}}\nAlone\n{{/if}}\nEnd\n";
+        assert_eq!(context(&[("Attendees", "Ann")]).render(template).unwrap(), "Title\nWith Ann\nEnd\n");
+        assert_eq!(context(&[]).render(template).unwrap(), "Title\nAlone\nEnd\n");
+        assert_eq!(context(&[]).render("a {{#if x}}b{{/if}} c").unwrap(), "a  c");
+
+        let mut found: Vec<String> = Vec::new();
+        prompts(&parse(template).unwrap(), &mut found);
+        assert_eq!(found, vec!["Attendees"]);
+    }
+
+    #[test]
+    fn turns_notes_into_templates() {
+        let note = "---\ntitle: Weekly sync\ncreated: 2024-03-09T10:00:00Z\nmodified: 2024-03-10\n# kept\nlabels: [meeting]\n---\n\n# Weekly sync\n\nHeld 2024-03-09, ticket 12024-03-091.\n";
+
+        let plain = note_template(note, "Weekly sync", false).unwrap();
+        assert_eq!(plain, "---\ntitle: Weekly sync\n# kept\nlabels: [meeting]\n---\n\n# Weekly sync\n\nHeld 2024-03-09, ticket 12024-03-091.\n");
+
+        let template = note_template(note, "Weekly sync", true).unwrap();
+        assert!(template.contains("title: '{{title}}'\n"));
+        assert!(template.contains("created: '{{date}}'\n"));
+        assert!(!template.contains("modified"));
+        assert!(template.ends_with("# {{title}}\n\nHeld {{date}}, ticket 12024-03-091.\n"));
+    }
+
+    #[test]
+    fn rejects_unbalanced_blocks() {
+        assert!(parse("{{#if a}}x").is_err());
+        assert!(parse("x{{/if}}").is_err());
+        assert!(parse("{{else}}").is_err());
+    }
+}
@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 
 /// Encryption method configuration
@@ -48,6 +49,34 @@ pub struct EncryptionSettings {
     /// List of recipients for multi-recipient encryption
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub recipients: Vec<Recipient>,
+    /// Folder-scoped rules, checked in order; the first match wins
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub policies: Vec<EncryptionPolicy>,
+    /// scrypt work factor (log2 of N) for password encryption; age calibrates
+    /// one that takes about a second on this device when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scrypt_work_factor: Option<u8>,
+    /// Minimum passphrase score (0-4) required to enable password encryption
+    #[serde(default = "default_min_passphrase_score")]
+    pub min_passphrase_score: u8,
+}
+
+fn default_min_passphrase_score() -> u8 {
+    3
+}
+
+/// Encryption rule for the paths matching a glob pattern
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncryptionPolicy {
+    /// Vault-relative glob, e.g. `Private/**`
+    pub pattern: String,
+    /// Whether matching notes are always written encrypted
+    #[serde(default)]
+    pub encrypt: bool,
+    /// Recipient ids, names or age public keys to encrypt to; the current
+    /// session credentials are used when empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub recipients: Vec<String>,
 }
 
 impl Default for EncryptionSettings {
@@ -57,6 +86,9 @@ impl Default for EncryptionSettings {
             method: EncryptionMethodConfig::Password,
             identity_file: None,
             recipients: Vec::new(),
+            policies: Vec::new(),
+            scrypt_work_factor: None,
+            min_passphrase_score: default_min_passphrase_score(),
         }
     }
 }
@@ -71,16 +103,327 @@ pub struct VaultInfo {
     pub has_config: bool,
 }
 
+/// Options of the new-vault wizard
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CreateVaultOptions {
+    /// Display name stored in the vault config
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Initialize a git repository
+    #[serde(default)]
+    pub init_git: bool,
+    /// Vault-relative folders to create, e.g. `Projects` or `Areas/Home`
+    #[serde(default)]
+    pub folders: Vec<String>,
+    /// Add a welcome note explaining the basics
+    #[serde(default)]
+    pub welcome_note: bool,
+}
+
 /// A file or directory entry
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileEntry {
     pub name: String,
     pub path: PathBuf,
     pub is_directory: bool,
+    /// Whether the entry is a symbolic link (to a file or folder)
+    #[serde(default)]
+    pub is_symlink: bool,
+    /// MIME type guessed from the extension, for files
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
     pub modified: u64,
     pub size: u64,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub children: Option<Vec<FileEntry>>,
+    /// Number of visible entries in a directory, also when `children` was
+    /// not loaded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub child_count: Option<u64>,
+}
+
+/// Parsed frontmatter and derived statistics of a note
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NoteMetadata {
+    pub title: Option<String>,
+    pub labels: Vec<String>,
+    pub created: Option<String>,
+    /// Remaining frontmatter fields, in file order
+    pub custom: serde_yaml::Mapping,
+    /// Words in the body, frontmatter excluded
+    pub word_count: usize,
+    pub outline: Vec<OutlineHeading>,
+}
+
+/// Size and structure counts of a note's body, code blocks excluded
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct NoteStats {
+    pub words: usize,
+    /// Characters of prose, spaces included
+    pub characters: usize,
+    pub headings: usize,
+    /// Markdown and wiki links, embeds excluded
+    pub links: usize,
+    pub tasks: usize,
+    pub completed_tasks: usize,
+    /// Estimated minutes to read, rounded up
+    pub reading_minutes: u32,
+}
+
+/// How closely the notes of a [`DuplicateGroup`] match
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DuplicateKind {
+    /// Same body text, ignoring frontmatter and whitespace
+    Exact,
+    /// Mostly the same wording
+    Near,
+}
+
+/// A note in a [`DuplicateGroup`]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DuplicateNote {
+    pub path: PathBuf,
+    /// Size of the note's markdown in bytes
+    pub size: u64,
+}
+
+/// Notes with the same or nearly the same body
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DuplicateGroup {
+    pub kind: DuplicateKind,
+    /// Lowest similarity (0 to 1) between linked notes of the group
+    pub similarity: f64,
+    pub notes: Vec<DuplicateNote>,
+}
+
+/// A heading in a note's outline
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OutlineHeading {
+    pub level: u8,
+    pub text: String,
+    /// 1-based line in the note, frontmatter included
+    pub line: usize,
+}
+
+/// Kind of entry in the file tree
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum EntryKind {
+    Note,
+    Notebook,
+    Kanban,
+}
+
+/// What `get_completion_data` completes
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CompletionKind {
+    Tags,
+    Notes,
+    Templates,
+    People,
+}
+
+/// An autocomplete candidate
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompletionItem {
+    pub value: String,
+    /// Notes carrying a tag, links to a note, or tasks assigned to a person
+    pub count: usize,
+    /// The note or template a candidate stands for
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+}
+
+/// A tag in the tag pane, with the tags nested below it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TagTreeNode {
+    /// Last segment of the tag
+    pub name: String,
+    /// Full tag, e.g. `project/alpha`
+    pub tag: String,
+    /// Notes carrying exactly this tag
+    pub count: usize,
+    /// Notes carrying this tag or one nested below it
+    pub total: usize,
+    pub children: Vec<TagTreeNode>,
+}
+
+/// Criteria for `filter_vault`; unset criteria match everything
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FilterCriteria {
+    /// Modified at or after this Unix timestamp (seconds)
+    #[serde(default)]
+    pub modified_after: Option<u64>,
+    /// Modified at or before this Unix timestamp (seconds)
+    #[serde(default)]
+    pub modified_before: Option<u64>,
+    /// Labels an entry must all carry; `parent/*` matches nested labels too
+    #[serde(default)]
+    pub labels: Vec<String>,
+    #[serde(default)]
+    pub kinds: Vec<EntryKind>,
+    /// Case-insensitive glob matched against the file name
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+/// A non-markdown file read for display
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BinaryFile {
+    pub path: PathBuf,
+    pub mime_type: String,
+    pub size: u64,
+    /// Base64-encoded content
+    pub data: String,
+}
+
+/// A path a bulk operation succeeded for
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BulkSuccess {
+    pub path: PathBuf,
+    /// Where the entry ended up, for moves
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_path: Option<PathBuf>,
+}
+
+/// A path a bulk operation failed for
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BulkFailure {
+    pub path: PathBuf,
+    pub error: String,
+}
+
+/// A line of a note matching a text search
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SearchHit {
+    /// Note or notebook the line belongs to
+    pub note: PathBuf,
+    /// File holding the line: the note itself or a notebook block file
+    pub path: PathBuf,
+    /// Line number in `path`, starting at 1
+    pub line: usize,
+    pub text: String,
+}
+
+/// A query for `query_notes`: either the text form, e.g.
+/// `status = active AND due < 2025-07-01 SORT BY due`, or its parsed form
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum QueryInput {
+    Text(String),
+    Structured(NoteQuery),
+}
+
+/// Which notes to list, in what order, with which columns
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct NoteQuery {
+    /// Columns of the result; the fields the query mentions when empty
+    #[serde(default)]
+    pub select: Vec<String>,
+    #[serde(default)]
+    pub filter: Option<QueryExpr>,
+    #[serde(default)]
+    pub sort: Vec<QuerySort>,
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+/// Condition on a note's fields
+///
+/// Fields are frontmatter keys, or `file.name`, `file.folder`, `file.path`,
+/// `file.modified`, `file.tags`, `file.tasks`, `file.open_tasks` and
+/// `file.done_tasks`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum QueryExpr {
+    And { items: Vec<QueryExpr> },
+    Or { items: Vec<QueryExpr> },
+    Not { expr: Box<QueryExpr> },
+    Compare {
+        field: String,
+        cmp: QueryComparison,
+        value: serde_json::Value,
+    },
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryComparison {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Contains,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct QuerySort {
+    pub field: String,
+    #[serde(default)]
+    pub descending: bool,
+}
+
+/// Notes matching a query, one row per note
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueryResult {
+    pub columns: Vec<String>,
+    pub rows: Vec<QueryRow>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueryRow {
+    pub path: PathBuf,
+    pub name: String,
+    /// Values of the result's columns, in order; dates as `YYYY-MM-DD`
+    pub values: Vec<serde_json::Value>,
+}
+
+/// A declarative change applied to the frontmatter of every note by
+/// `migrate_frontmatter`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FrontmatterRule {
+    /// Rename a field, unless the note already has the new one
+    RenameField { from: String, to: String },
+    /// Rewrite a date field in another format, e.g. `%d.%m.%Y` to `%Y-%m-%d`
+    ConvertDate {
+        field: String,
+        /// Format the dates are in; common formats are tried when unset
+        #[serde(default)]
+        from_format: Option<String>,
+        to_format: String,
+    },
+    /// Add a label to notes that don't have it yet
+    AddLabel { label: String },
+}
+
+/// Frontmatter changes made (or, in a dry run, planned) for one note
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FrontmatterChange {
+    pub path: PathBuf,
+    /// One line per applied rule
+    pub changes: Vec<String>,
+}
+
+/// Outcome of `migrate_frontmatter`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FrontmatterMigration {
+    pub dry_run: bool,
+    pub changed: Vec<FrontmatterChange>,
+    /// Notes no rule applied to
+    pub unchanged: usize,
+    pub failed: Vec<BulkFailure>,
+}
+
+/// Outcome of a bulk operation, per path
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BulkReport {
+    pub succeeded: Vec<BulkSuccess>,
+    pub failed: Vec<BulkFailure>,
 }
 
 /// Content of a note with metadata
@@ -89,18 +432,37 @@ pub struct NoteContent {
     pub path: PathBuf,
     pub content: String,
     pub modified: u64,
+    /// SHA-256 of the content as stored, for conflict checks in `write_note`
+    pub hash: String,
+}
+
+/// A note that changed on disk since it was read, refused by `write_note`
+#[derive(Debug, Clone, Serialize)]
+pub struct WriteConflict {
+    pub path: PathBuf,
+    /// Content on disk, decrypted when the session is unlocked
+    pub content: String,
+    /// Whether `content` is still encrypted
+    pub encrypted: bool,
+    /// Hash of the content on disk; writing with it overwrites the note
+    pub hash: String,
 }
 
 /// Notebook block type
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "lowercase")]
 pub enum BlockType {
+    #[default]
     Markdown,
     Code,
+    /// CSV, or TSV when the language is `tsv`
+    Table,
+    /// LaTeX math, without `$$` delimiters
+    Math,
 }
 
 /// A block in a notebook
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct NotebookBlock {
     pub id: String,
     #[serde(rename = "type")]
@@ -111,6 +473,41 @@ pub struct NotebookBlock {
     /// Whether this block is encrypted
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub encrypted: Option<bool>,
+    /// Blocks that have to run before this one
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub depends_on: Vec<String>,
+    /// Named values this block passes to the blocks depending on it
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub outputs: Vec<String>,
+    /// Working directory for running the block, relative to the notebook's folder
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
+    /// Presentation state of the block
+    #[serde(default, skip_serializing_if = "BlockMeta::is_empty")]
+    pub meta: BlockMeta,
+    /// Read-only: content, type and the block itself can't be changed or deleted
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub locked: bool,
+}
+
+/// Presentation state of a notebook block, kept in the index so it is
+/// versioned with the notebook
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct BlockMeta {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub collapsed: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub pinned: bool,
+}
+
+impl BlockMeta {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
 }
 
 /// Notebook index file structure
@@ -118,6 +515,14 @@ pub struct NotebookBlock {
 pub struct NotebookIndex {
     pub version: u32,
     pub blocks: Vec<NotebookBlock>,
+    /// Whole notebook encrypted: block files are stored encrypted and
+    /// decrypted in memory when read
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub encrypted: bool,
+    /// The index itself is stored encrypted and block files carry no
+    /// language extension
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub encrypted_index: bool,
 }
 
 /// Full notebook with content
@@ -128,8 +533,33 @@ pub struct Notebook {
     pub blocks: Vec<NotebookBlockWithContent>,
 }
 
-/// Block with its content loaded
+/// Changes made by `repair_notebook`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RepairReport {
+    /// The index could not be parsed and was rebuilt from the block files
+    pub rebuilt_index: bool,
+    /// Files that were not in the index and became blocks
+    pub adopted: Vec<String>,
+    /// Blocks removed because their file was missing or they repeated another
+    /// entry
+    pub pruned: Vec<String>,
+    /// Blocks whose missing file was recreated empty
+    pub recreated: Vec<String>,
+    /// Blocks sharing an ID with an earlier block, under their new ID
+    pub reassigned_ids: Vec<String>,
+    /// Block files renamed to the extension of their type and language
+    pub renamed_files: Vec<String>,
+}
+
+/// Rows of a table block; the first row holds the column names
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TableData {
+    pub block_id: String,
+    pub rows: Vec<Vec<String>>,
+}
+
+/// Block with its content loaded
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct NotebookBlockWithContent {
     pub id: String,
     #[serde(rename = "type")]
@@ -139,13 +569,57 @@ pub struct NotebookBlockWithContent {
     /// Whether this block is encrypted
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub encrypted: Option<bool>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub depends_on: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub outputs: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
+    #[serde(default, skip_serializing_if = "BlockMeta::is_empty")]
+    pub meta: BlockMeta,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub locked: bool,
+    /// Result of the block's last execution
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_output: Option<StoredOutput>,
+}
+
+impl NotebookBlockWithContent {
+    /// Block from the index together with its content
+    pub fn from_block(block: &NotebookBlock, content: String) -> Self {
+        Self {
+            id: block.id.clone(),
+            block_type: block.block_type.clone(),
+            language: block.language.clone(),
+            content,
+            encrypted: block.encrypted,
+            depends_on: block.depends_on.clone(),
+            outputs: block.outputs.clone(),
+            cwd: block.cwd.clone(),
+            meta: block.meta.clone(),
+            locked: block.locked,
+            last_output: None,
+        }
+    }
+}
+
+/// Execution result kept in a notebook's `.outputs.json`
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct StoredOutput {
+    pub stdout: String,
+    pub stderr: String,
+    pub exit_code: i32,
+    /// When the block ran (ISO 8601)
+    pub executed_at: String,
 }
 
 impl Default for NotebookIndex {
     fn default() -> Self {
         Self {
-            version: 1,
+            version: super::migrations::NOTEBOOK_INDEX_VERSION,
             blocks: vec![],
+            encrypted: false,
+            encrypted_index: false,
         }
     }
 }
@@ -157,7 +631,24 @@ pub struct FileChangeEvent {
     pub kind: FileChangeKind,
 }
 
+/// Changes under one watched root, emitted together as `files-changed`
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileChangeBatch {
+    /// ID the root was watched under
+    pub root: String,
+    pub changes: Vec<FileChangeEvent>,
+}
+
+/// Payload of `config-changed`, `notebook-changed` and `kanban-changed`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexChangeEvent {
+    /// ID the root was watched under
+    pub root: String,
+    /// The vault, notebook or board whose configuration or index changed
+    pub path: PathBuf,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
 pub enum FileChangeKind {
     Create,
@@ -181,6 +672,15 @@ pub struct InterpreterSettings {
     /// Path to Node.js interpreter (default: node)
     #[serde(default)]
     pub node: Option<String>,
+    /// Path to Deno (default: deno)
+    #[serde(default)]
+    pub deno: Option<String>,
+    /// Path to the Go toolchain (default: go)
+    #[serde(default)]
+    pub go: Option<String>,
+    /// Path to cargo or rust-script (default: cargo)
+    #[serde(default)]
+    pub rust: Option<String>,
 }
 
 impl Default for InterpreterSettings {
@@ -190,10 +690,47 @@ impl Default for InterpreterSettings {
             python: None,
             ruby: None,
             node: None,
+            deno: None,
+            go: None,
+            rust: None,
         }
     }
 }
 
+/// Renderers for diagram blocks; each is detected from PATH when not set
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct DiagramSettings {
+    /// Mermaid CLI (`mmdc`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mermaid: Option<String>,
+    /// Graphviz `dot`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub graphviz: Option<String>,
+    /// `plantuml` executable or path to `plantuml.jar`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plantuml: Option<String>,
+}
+
+/// Running code blocks inside a Docker/Podman container
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ContainerSettings {
+    /// Run code blocks in a container unless a block opts out
+    #[serde(default)]
+    pub enabled: bool,
+    /// Container engine (default: docker, falling back to podman)
+    #[serde(default)]
+    pub engine: Option<String>,
+    /// Image for runtimes without an entry in `images`
+    #[serde(default)]
+    pub image: Option<String>,
+    /// Image per runtime, e.g. `python: python:3.12-slim`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub images: BTreeMap<String, String>,
+    /// Allow network access from the container
+    #[serde(default)]
+    pub network: bool,
+}
+
 /// Vault configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VaultConfig {
@@ -211,6 +748,335 @@ pub struct VaultConfig {
     pub encryption: EncryptionSettings,
     #[serde(default)]
     pub interpreters: InterpreterSettings,
+    #[serde(default)]
+    pub container: ContainerSettings,
+    #[serde(default)]
+    pub diagrams: DiagramSettings,
+    #[serde(default)]
+    pub export: ExportSettings,
+    #[serde(default)]
+    pub compatibility: CompatibilitySettings,
+    #[serde(default)]
+    pub reminders: ReminderSettings,
+    #[serde(default)]
+    pub archive: ArchiveSettings,
+    #[serde(default)]
+    pub backup: BackupSettings,
+    #[serde(default)]
+    pub periodic: PeriodicSettings,
+    #[serde(default)]
+    pub mcp: McpSettings,
+    /// Endpoints notified of vault events
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub webhooks: Vec<WebhookSettings>,
+    /// Database connections SQL blocks can run against
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub databases: Vec<DatabaseConnection>,
+}
+
+/// A named database connection for SQL blocks
+///
+/// The password is kept out of the URL and stored in the system keychain.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatabaseConnection {
+    pub name: String,
+    /// Connection URL, e.g. `postgres://user@localhost/app` or `sqlite://data/app.db`
+    pub url: String,
+}
+
+/// Compatibility with vaults created by other note apps
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct CompatibilitySettings {
+    /// Keep Obsidian-style `[[wikilinks]]` and `![[embeds]]` as written and
+    /// resolve them by file name anywhere in the vault
+    #[serde(default)]
+    pub obsidian_links: bool,
+}
+
+/// Notifications for due kanban tasks and notes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReminderSettings {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Days before the due date to start reminding
+    #[serde(default)]
+    pub days_before: u32,
+}
+
+impl Default for ReminderSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            days_before: 0,
+        }
+    }
+}
+
+/// Where archived notes are kept
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveSettings {
+    /// Vault-relative folder mirroring the structure of archived notes
+    #[serde(default = "default_archive_folder")]
+    pub folder: String,
+}
+
+impl Default for ArchiveSettings {
+    fn default() -> Self {
+        Self {
+            folder: default_archive_folder(),
+        }
+    }
+}
+
+fn default_archive_folder() -> String {
+    "Archive".to_string()
+}
+
+/// Scheduled zip snapshots of the vault
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Hours between scheduled backups
+    #[serde(default = "default_backup_interval")]
+    pub interval_hours: u32,
+    /// Number of backups kept; older ones are deleted
+    #[serde(default = "default_backup_keep")]
+    pub keep: u32,
+    /// Folder the backups are written to, `.notemaker/.local/backups` if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub location: Option<PathBuf>,
+}
+
+impl Default for BackupSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_hours: default_backup_interval(),
+            keep: default_backup_keep(),
+            location: None,
+        }
+    }
+}
+
+fn default_backup_interval() -> u32 {
+    24
+}
+
+fn default_backup_keep() -> u32 {
+    7
+}
+
+/// A vault backup archive
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupInfo {
+    /// File name, used to pick a backup to restore
+    pub name: String,
+    pub path: PathBuf,
+    /// Unix timestamp (seconds) the backup was written
+    pub created: u64,
+    /// Archive size in bytes
+    pub size: u64,
+}
+
+/// MCP server for local agents; nothing is exposed until enabled, and each
+/// tool needs its own permission
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct McpSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub tools: McpToolPermissions,
+}
+
+/// Tools agents may call
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct McpToolPermissions {
+    #[serde(default)]
+    pub search_notes: bool,
+    #[serde(default)]
+    pub read_note: bool,
+    #[serde(default)]
+    pub append_note: bool,
+    #[serde(default)]
+    pub list_tasks: bool,
+}
+
+/// Vault events webhooks can be sent for
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEvent {
+    NoteSaved,
+    TaskMoved,
+    CommitCreated,
+    SyncCompleted,
+}
+
+/// An endpoint that gets a JSON `POST` for vault events
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookSettings {
+    pub url: String,
+    /// Events to send; all of them when empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<WebhookEvent>,
+    /// Key the body is signed with, sent as `X-Notemaker-Signature: sha256=<hex HMAC>`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+}
+
+/// A webhook request that failed, sent as a `webhook-failed` event
+#[derive(Debug, Clone, Serialize)]
+pub struct WebhookFailure {
+    pub url: String,
+    pub event: WebhookEvent,
+    pub error: String,
+}
+
+/// Length of the period a periodic note covers
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PeriodKind {
+    Daily,
+    Weekly,
+    Monthly,
+    Quarterly,
+}
+
+/// Where one kind of periodic note is kept and what it starts from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PeriodicNoteSettings {
+    /// Vault-relative path without `.md`, expanded like a template for the
+    /// period's first day; `{{quarter}}` holds the quarter number
+    pub path: String,
+    /// Vault template new notes are rendered from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+}
+
+impl PeriodicNoteSettings {
+    fn new(path: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            template: None,
+        }
+    }
+}
+
+fn default_daily_notes() -> PeriodicNoteSettings {
+    PeriodicNoteSettings::new("daily/{{date:%Y-%m-%d}}")
+}
+
+fn default_weekly_notes() -> PeriodicNoteSettings {
+    PeriodicNoteSettings::new("weekly/{{date:%G-W%V}}")
+}
+
+fn default_monthly_notes() -> PeriodicNoteSettings {
+    PeriodicNoteSettings::new("monthly/{{date:%Y-%m}}")
+}
+
+fn default_quarterly_notes() -> PeriodicNoteSettings {
+    PeriodicNoteSettings::new("quarterly/{{date:%Y}}-Q{{quarter}}")
+}
+
+/// Daily, weekly, monthly and quarterly notes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PeriodicSettings {
+    #[serde(default = "default_daily_notes")]
+    pub daily: PeriodicNoteSettings,
+    #[serde(default = "default_weekly_notes")]
+    pub weekly: PeriodicNoteSettings,
+    #[serde(default = "default_monthly_notes")]
+    pub monthly: PeriodicNoteSettings,
+    #[serde(default = "default_quarterly_notes")]
+    pub quarterly: PeriodicNoteSettings,
+}
+
+impl PeriodicSettings {
+    pub fn get(&self, kind: PeriodKind) -> &PeriodicNoteSettings {
+        match kind {
+            PeriodKind::Daily => &self.daily,
+            PeriodKind::Weekly => &self.weekly,
+            PeriodKind::Monthly => &self.monthly,
+            PeriodKind::Quarterly => &self.quarterly,
+        }
+    }
+}
+
+impl Default for PeriodicSettings {
+    fn default() -> Self {
+        Self {
+            daily: default_daily_notes(),
+            weekly: default_weekly_notes(),
+            monthly: default_monthly_notes(),
+            quarterly: default_quarterly_notes(),
+        }
+    }
+}
+
+/// The note for one period
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PeriodicNote {
+    pub kind: PeriodKind,
+    pub path: PathBuf,
+    pub title: String,
+    /// First and last day of the period, `YYYY-MM-DD`
+    pub start: String,
+    pub end: String,
+    pub exists: bool,
+}
+
+/// Export settings
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ExportSettings {
+    #[serde(default)]
+    pub pdf: PdfSettings,
+    #[serde(default)]
+    pub pandoc: PandocSettings,
+}
+
+/// Pandoc settings used for DOCX/ODT export
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct PandocSettings {
+    /// Path to the pandoc executable; detected from PATH when not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// Reference document providing styles (`--reference-doc`), relative to
+    /// the vault root
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reference_doc: Option<String>,
+}
+
+/// PDF export settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PdfSettings {
+    /// CSS page size: "A4", "Letter", "Legal", ...
+    #[serde(default = "default_page_size")]
+    pub page_size: String,
+    /// Page margin in millimeters
+    #[serde(default = "default_margin_mm")]
+    pub margin_mm: u32,
+    /// Path to the HTML-to-PDF converter (Chromium-based browser or
+    /// wkhtmltopdf); detected from PATH when not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub engine: Option<String>,
+}
+
+impl Default for PdfSettings {
+    fn default() -> Self {
+        Self {
+            page_size: default_page_size(),
+            margin_mm: default_margin_mm(),
+            engine: None,
+        }
+    }
+}
+
+fn default_page_size() -> String {
+    "A4".to_string()
+}
+
+fn default_margin_mm() -> u32 {
+    20
 }
 
 /// File tree settings
@@ -219,12 +1085,16 @@ pub struct FileTreeSettings {
     /// Default expanded state: "all", "none", or "remember"
     #[serde(default = "default_tree_expanded")]
     pub default_expanded: String,
+    /// Descend into symlinked folders; when off, links are left out
+    #[serde(default = "default_true")]
+    pub follow_symlinks: bool,
 }
 
 impl Default for FileTreeSettings {
     fn default() -> Self {
         Self {
             default_expanded: default_tree_expanded(),
+            follow_symlinks: true,
         }
     }
 }
@@ -242,6 +1112,20 @@ pub struct LocalState {
     /// Last opened note/notebook path
     #[serde(default)]
     pub last_opened: Option<String>,
+    /// Recently opened notes and notebooks, most recent first
+    #[serde(default)]
+    pub recent_files: Vec<RecentFile>,
+    /// Pinned favorite note/notebook paths
+    #[serde(default)]
+    pub pinned: Vec<String>,
+}
+
+/// A recently opened note or notebook
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RecentFile {
+    pub path: String,
+    /// RFC 3339 timestamp of the last time it was opened
+    pub opened: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -274,6 +1158,30 @@ pub struct GitSettings {
     pub remote: String,
     #[serde(default = "default_branch")]
     pub branch: String,
+    #[serde(default)]
+    pub signing: CommitSigningSettings,
+}
+
+/// Signature format for commits
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SigningFormat {
+    #[default]
+    Gpg,
+    Ssh,
+}
+
+/// Commit signing for commits made by the app
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct CommitSigningSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub format: SigningFormat,
+    /// GPG key id, or path to an SSH key; a `.pub` path signs through
+    /// ssh-agent. Falls back to git's `user.signingkey`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -330,12 +1238,23 @@ impl Default for VaultConfig {
             file_tree: FileTreeSettings::default(),
             encryption: EncryptionSettings::default(),
             interpreters: InterpreterSettings::default(),
+            container: ContainerSettings::default(),
+            diagrams: DiagramSettings::default(),
+            export: ExportSettings::default(),
+            compatibility: CompatibilitySettings::default(),
+            reminders: ReminderSettings::default(),
+            archive: ArchiveSettings::default(),
+            backup: BackupSettings::default(),
+            periodic: PeriodicSettings::default(),
+            mcp: McpSettings::default(),
+            webhooks: Vec::new(),
+            databases: Vec::new(),
         }
     }
 }
 
 /// A task in a kanban board
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct KanbanTask {
     /// Unique identifier for this task
     pub id: String,
@@ -349,10 +1268,67 @@ pub struct KanbanTask {
     /// Optional due date in ISO 8601 format
     #[serde(skip_serializing_if = "Option::is_none")]
     pub due: Option<String>,
+    /// Names of the board labels attached to this task
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<String>,
+    /// Person from the board's people list working on this task
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub assignee: Option<String>,
     /// When this task was created (ISO 8601)
     pub created: String,
     /// When this task was last updated (ISO 8601)
     pub updated: String,
+    /// Statuses the task has been in, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub history: Vec<StatusChange>,
+}
+
+/// A task entering a status
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StatusChange {
+    pub status: String,
+    /// When the task entered the status (ISO 8601)
+    pub at: String,
+}
+
+/// Kind of a task activity entry
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ActivityKind {
+    Created,
+    Comment,
+    Change,
+    Moved,
+}
+
+/// Entry of a task's activity log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskActivity {
+    /// When it happened (ISO 8601)
+    pub at: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    pub kind: ActivityKind,
+    /// Changed field
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+    /// Previous value of the field, or the board a task was moved from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+    /// New value of the field, or the board a task was moved to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to: Option<String>,
+    /// Comment text
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+}
+
+/// A label defined on a kanban board
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct KanbanLabel {
+    pub name: String,
+    /// CSS color, e.g. "#e5484d"
+    pub color: String,
 }
 
 /// Settings for kanban board display
@@ -364,6 +1340,12 @@ pub struct KanbanSettings {
     /// Whether to show closed/done tasks
     #[serde(default)]
     pub show_closed: bool,
+    /// Labels tasks can be tagged with
+    #[serde(default)]
+    pub labels: Vec<KanbanLabel>,
+    /// People tasks can be assigned to
+    #[serde(default)]
+    pub people: Vec<String>,
 }
 
 fn default_card_density() -> String {
@@ -375,6 +1357,8 @@ impl Default for KanbanSettings {
         Self {
             card_density: default_card_density(),
             show_closed: false,
+            labels: Vec::new(),
+            people: Vec::new(),
         }
     }
 }
@@ -391,6 +1375,10 @@ pub struct KanbanIndex {
     /// Display settings
     #[serde(default)]
     pub settings: KanbanSettings,
+    /// Board encrypted: the index and task descriptions are stored encrypted
+    /// and decrypted in memory when read
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub encrypted: bool,
 }
 
 impl Default for KanbanIndex {
@@ -404,6 +1392,7 @@ impl Default for KanbanIndex {
             ],
             tasks: vec![],
             settings: KanbanSettings::default(),
+            encrypted: false,
         }
     }
 }
@@ -422,7 +1411,7 @@ pub struct Kanban {
 }
 
 /// A kanban task with its description content loaded
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct KanbanTaskWithContent {
     /// Unique identifier for this task
     pub id: String,
@@ -436,6 +1425,12 @@ pub struct KanbanTaskWithContent {
     /// Optional due date in ISO 8601 format
     #[serde(skip_serializing_if = "Option::is_none")]
     pub due: Option<String>,
+    /// Names of the board labels attached to this task
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<String>,
+    /// Person from the board's people list working on this task
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub assignee: Option<String>,
     /// When this task was created (ISO 8601)
     pub created: String,
     /// When this task was last updated (ISO 8601)
@@ -444,6 +1439,24 @@ pub struct KanbanTaskWithContent {
     pub description: String,
 }
 
+impl KanbanTaskWithContent {
+    /// Task from the index together with its description
+    pub fn from_task(task: &KanbanTask, description: String) -> Self {
+        Self {
+            id: task.id.clone(),
+            title: task.title.clone(),
+            status: task.status.clone(),
+            priority: task.priority.clone(),
+            due: task.due.clone(),
+            labels: task.labels.clone(),
+            assignee: task.assignee.clone(),
+            created: task.created.clone(),
+            updated: task.updated.clone(),
+            description,
+        }
+    }
+}
+
 /// Partial updates for a task (all fields optional)
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct TaskUpdates {
@@ -459,7 +1472,216 @@ pub struct TaskUpdates {
     /// New due date
     #[serde(skip_serializing_if = "Option::is_none")]
     pub due: Option<String>,
+    /// New set of labels
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub labels: Option<Vec<String>>,
+    /// New assignee
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub assignee: Option<String>,
     /// New description/body content
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
 }
+
+/// Something with a due date: an open kanban task or a note with `due:`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DueItem {
+    /// Note file or kanban board
+    pub path: PathBuf,
+    /// Task on the board, for kanban tasks
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub task_id: Option<String>,
+    pub title: String,
+    /// Due date (`YYYY-MM-DD`)
+    pub due: String,
+    pub overdue: bool,
+}
+
+/// A note whose frontmatter `date:` puts it on a calendar day
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CalendarEvent {
+    pub path: PathBuf,
+    pub title: String,
+}
+
+/// Everything on one day of the calendar
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct CalendarDay {
+    /// `YYYY-MM-DD`
+    pub date: String,
+    /// Notes created that day, by their `created:` field or else the file
+    pub created: Vec<PathBuf>,
+    /// Notes last modified that day
+    pub modified: Vec<PathBuf>,
+    /// The day's daily note, when it exists
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub daily_note: Option<PathBuf>,
+    /// Open kanban tasks and notes due that day
+    pub due: Vec<DueItem>,
+    pub events: Vec<CalendarEvent>,
+}
+
+/// Criteria for filtering kanban tasks; empty criteria match every task
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct TaskFilter {
+    /// Tasks must carry all of these labels
+    #[serde(default)]
+    pub labels: Vec<String>,
+    /// Tasks must have one of these priorities
+    #[serde(default)]
+    pub priorities: Vec<String>,
+    /// Tasks must be assigned to this person; an empty name matches
+    /// unassigned tasks
+    #[serde(default)]
+    pub assignee: Option<String>,
+    /// Earliest due date (inclusive, `YYYY-MM-DD`)
+    #[serde(default)]
+    pub due_from: Option<String>,
+    /// Latest due date (inclusive, `YYYY-MM-DD`)
+    #[serde(default)]
+    pub due_to: Option<String>,
+    /// Case-insensitive text searched in titles and descriptions
+    #[serde(default)]
+    pub text: Option<String>,
+}
+
+/// A checkbox item (`- [ ]`) in a note or a notebook's markdown block
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ChecklistItem {
+    /// Note or notebook the item belongs to
+    pub note: PathBuf,
+    /// File holding the item: the note itself or a notebook block file
+    pub path: PathBuf,
+    /// Notebook block holding the item
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_id: Option<String>,
+    /// Line of the item in `path`, starting at 1
+    pub line: usize,
+    pub text: String,
+    pub checked: bool,
+    /// Due date (`YYYY-MM-DD`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub due: Option<String>,
+    /// Inline tags of the item, without `#`
+    pub tags: Vec<String>,
+}
+
+/// Criteria for `list_open_tasks`; empty criteria match every open item
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ChecklistFilter {
+    /// Items must carry all of these tags; `parent/*` matches nested tags
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Earliest due date (inclusive, `YYYY-MM-DD`)
+    #[serde(default)]
+    pub due_from: Option<String>,
+    /// Latest due date (inclusive, `YYYY-MM-DD`)
+    #[serde(default)]
+    pub due_to: Option<String>,
+    /// Case-insensitive text searched in item texts
+    #[serde(default)]
+    pub text: Option<String>,
+}
+
+/// Dates limiting kanban statistics (inclusive, `YYYY-MM-DD`)
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct DateRange {
+    #[serde(default)]
+    pub from: Option<String>,
+    #[serde(default)]
+    pub to: Option<String>,
+}
+
+/// Summary of durations in days
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct DurationStats {
+    pub count: usize,
+    pub average_days: Option<f64>,
+    pub median_days: Option<f64>,
+}
+
+/// Tasks finished in a week
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WeeklyThroughput {
+    /// Monday of the week (`YYYY-MM-DD`)
+    pub week_start: String,
+    pub completed: usize,
+}
+
+/// Number of tasks per column at the end of a day
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FlowPoint {
+    pub date: String,
+    pub counts: BTreeMap<String, usize>,
+}
+
+/// Flow statistics of a kanban board
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct KanbanStats {
+    /// From the task leaving the first column to it being done
+    pub cycle_time: DurationStats,
+    /// From the task being created to it being done
+    pub lead_time: DurationStats,
+    pub throughput: Vec<WeeklyThroughput>,
+    pub cumulative_flow: Vec<FlowPoint>,
+}
+
+/// A file that could not be re-encrypted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReencryptFailure {
+    pub path: PathBuf,
+    pub error: String,
+}
+
+/// Result of re-encrypting a vault with new credentials
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReencryptReport {
+    /// Files now encrypted with the new credentials
+    pub reencrypted: Vec<PathBuf>,
+    /// Files left untouched because they could not be re-encrypted
+    pub failures: Vec<ReencryptFailure>,
+}
+
+/// Estimated strength of a passphrase
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PassphraseStrength {
+    /// 0 (trivially guessable) to 4 (very strong)
+    pub score: u8,
+    /// Estimated entropy after discounting predictable patterns
+    pub entropy_bits: f64,
+    /// Suggestions for a stronger passphrase
+    pub feedback: Vec<String>,
+}
+
+/// Rows returned by a SQL block
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SqlResult {
+    /// Column names of the last statement that returned rows
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<serde_json::Value>>,
+    /// Rows changed by statements that don't return rows
+    pub rows_affected: u64,
+    /// More rows were returned than are included
+    pub truncated: bool,
+}
+
+/// Diagram rendered from a block
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RenderedDiagram {
+    pub path: PathBuf,
+    /// Link target relative to the notebook, e.g. `./Notebook.assets/diagram-abc.svg`
+    pub relative_path: String,
+}
+
+/// Response to a request of an HTTP block
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpResponse {
+    pub method: String,
+    pub url: String,
+    pub status: u16,
+    pub status_text: String,
+    pub headers: Vec<(String, String)>,
+    /// Body, pretty-printed when it is JSON
+    pub body: String,
+    pub elapsed_ms: u64,
+}
@@ -1,29 +1,234 @@
 use notify::{Config, Event, RecommendedWatcher, RecursiveMode, Watcher};
-use std::path::PathBuf;
-use std::sync::mpsc::channel;
-use std::sync::{Arc, Mutex};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex, OnceLock};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tauri::{AppHandle, Emitter};
 
-use super::types::{FileChangeEvent, FileChangeKind};
+use super::echo::is_own_write;
+use super::exclude::{VaultIgnore, IGNORE_FILE};
+use super::types::{FileChangeBatch, FileChangeEvent, FileChangeKind, IndexChangeEvent};
 
+/// Quiet time after the last raw event before a batch is emitted
+const DEBOUNCE: Duration = Duration::from_millis(250);
+
+/// Longest a change is held back while events keep arriving
+const MAX_DELAY: Duration = Duration::from_secs(2);
+
+/// How long after a pause ends late events of the paused work are still held back
+const RESUME_GRACE: Duration = Duration::from_millis(500);
+
+/// Nested pauses of one directory
+#[derive(Default)]
+struct PausedDir {
+    count: usize,
+    quiet_until: Option<Instant>,
+}
+
+impl PausedDir {
+    fn active(&self, now: Instant) -> bool {
+        self.count > 0 || self.quiet_until.is_some_and(|until| now < until)
+    }
+}
+
+fn paused_dirs() -> &'static Mutex<HashMap<PathBuf, PausedDir>> {
+    static PAUSES: OnceLock<Mutex<HashMap<PathBuf, PausedDir>>> = OnceLock::new();
+    PAUSES.get_or_init(Default::default)
+}
+
+fn pause(dir: &Path) {
+    paused_dirs().lock().unwrap().entry(dir.to_path_buf()).or_default().count += 1;
+}
+
+fn resume(dir: &Path) {
+    let mut dirs = paused_dirs().lock().unwrap();
+    if let Some(paused) = dirs.get_mut(dir).filter(|paused| paused.count > 0) {
+        paused.count -= 1;
+        if paused.count == 0 {
+            paused.quiet_until = Some(Instant::now() + RESUME_GRACE);
+        }
+    }
+}
+
+/// Whether change events for `path` are currently held back
+fn is_paused(path: &Path) -> bool {
+    let now = Instant::now();
+    let mut dirs = paused_dirs().lock().unwrap();
+    dirs.retain(|_, paused| paused.active(now));
+    dirs.keys().any(|dir| path.starts_with(dir))
+}
+
+/// Holds back change events below a directory while alive, for backend
+/// work that rewrites many files there (imports, exports, git checkouts)
+///
+/// The held back changes are emitted as one batch once the pause ends.
+pub(crate) struct WatchPause(PathBuf);
+
+impl WatchPause {
+    pub(crate) fn new(dir: &Path) -> Self {
+        pause(dir);
+        Self(dir.to_path_buf())
+    }
+}
+
+impl Drop for WatchPause {
+    fn drop(&mut self) {
+        resume(&self.0);
+    }
+}
+
+/// Event emitted for a file the app keeps its own state from, if `path` is one
+///
+/// Returns the event name and the path it is about: the vault for its
+/// configuration, the notebook or board for an index file.
+fn index_event(root: &Path, path: &Path) -> Option<(&'static str, PathBuf)> {
+    if path == root.join(".notemaker").join("config.yaml") {
+        return Some(("config-changed", root.to_path_buf()));
+    }
+    if path.file_name()? != ".index.json" {
+        return None;
+    }
+    let owner = path.parent()?;
+    match owner.extension()?.to_str()? {
+        "md" => Some(("notebook-changed", owner.to_path_buf())),
+        "kanban" => Some(("kanban-changed", owner.to_path_buf())),
+        _ => None,
+    }
+}
+
+/// Files whose content hash the watcher remembers before starting over
+const MAX_HASHED_FILES: usize = 2048;
+
+/// Last seen content hash per file, to drop events of editors and git that
+/// only touch modification times
+#[derive(Default)]
+struct ContentHashes {
+    hashes: HashMap<PathBuf, u64>,
+}
+
+impl ContentHashes {
+    /// Remember the current content of a changed path; false for a
+    /// modification that left the content as it was last seen
+    fn changed(&mut self, change: &FileChangeEvent) -> bool {
+        if change.kind == FileChangeKind::Delete {
+            self.hashes.remove(&change.path);
+            return true;
+        }
+        // Directories and unreadable files always count as changed
+        let Ok(content) = fs::read(&change.path) else {
+            return true;
+        };
+        let mut hasher = DefaultHasher::new();
+        content.hash(&mut hasher);
+        let hash = hasher.finish();
+
+        if self.hashes.len() >= MAX_HASHED_FILES && !self.hashes.contains_key(&change.path) {
+            self.hashes.clear();
+        }
+        let previous = self.hashes.insert(change.path.clone(), hash);
+        change.kind != FileChangeKind::Modify || previous != Some(hash)
+    }
+}
+
+/// Changes waiting to be emitted, coalesced per path
+#[derive(Default)]
+struct PendingChanges {
+    changes: BTreeMap<PathBuf, FileChangeKind>,
+    first: Option<Instant>,
+    last: Option<Instant>,
+}
+
+impl PendingChanges {
+    /// Record a change, merging it with an earlier one for the same path
+    fn add(&mut self, path: PathBuf, kind: FileChangeKind) {
+        use FileChangeKind::*;
+
+        let merged = match (self.changes.remove(&path), kind) {
+            (None, kind) => Some(kind),
+            // A file that came and went within one batch never existed for the frontend
+            (Some(Create), Delete) => None,
+            (Some(Create), _) => Some(Create),
+            (Some(Delete), Create) => Some(Modify),
+            (Some(_), kind) => Some(kind),
+        };
+        if let Some(kind) = merged {
+            self.changes.insert(path, kind);
+        }
+
+        let now = Instant::now();
+        self.first.get_or_insert(now);
+        self.last = Some(now);
+    }
+
+    /// How long to wait for more events before the batch is due
+    fn wait(&self) -> Duration {
+        match (self.first, self.last) {
+            (Some(first), Some(last)) => DEBOUNCE
+                .saturating_sub(last.elapsed())
+                .min(MAX_DELAY.saturating_sub(first.elapsed())),
+            // Nothing pending; just wait for the next event
+            _ => Duration::from_secs(3600),
+        }
+    }
+
+    /// Move the changes `released` lets go of over to `to`
+    fn release(&mut self, to: &mut PendingChanges, released: impl Fn(&Path) -> bool) {
+        let paths: Vec<PathBuf> = self.changes.keys().filter(|path| released(path)).cloned().collect();
+        for path in paths {
+            if let Some(kind) = self.changes.remove(&path) {
+                to.add(path, kind);
+            }
+        }
+        if self.changes.is_empty() {
+            self.first = None;
+            self.last = None;
+        }
+    }
+
+    /// The pending changes, if the batch is due
+    fn take_due(&mut self) -> Option<Vec<FileChangeEvent>> {
+        if self.first.is_none() || !self.wait().is_zero() {
+            return None;
+        }
+        self.first = None;
+        self.last = None;
+        let changes = std::mem::take(&mut self.changes);
+        Some(changes.into_iter().map(|(path, kind)| FileChangeEvent { path, kind }).collect())
+    }
+}
+
+/// ID of the root holding the open vault, used when a command names none
+pub const VAULT_WATCH_ID: &str = "vault";
+
+/// A watched directory tree
+struct WatchedRoot {
+    /// Dropping the watcher ends the root's event thread
+    _watcher: RecommendedWatcher,
+    path: PathBuf,
+    /// Subtrees events are limited to; empty means the whole root
+    subscriptions: Arc<Mutex<Vec<PathBuf>>>,
+}
+
+/// Watches any number of directory trees by ID, e.g. the vaults of a
+/// workspace and an external templates folder
 pub struct FileWatcher {
-    watcher: Option<RecommendedWatcher>,
-    watched_path: Option<PathBuf>,
+    roots: HashMap<String, WatchedRoot>,
 }
 
 impl FileWatcher {
     pub fn new() -> Self {
-        Self {
-            watcher: None,
-            watched_path: None,
-        }
+        Self { roots: HashMap::new() }
     }
 
-    pub fn watch(&mut self, path: PathBuf, app_handle: AppHandle) -> Result<(), String> {
+    /// Watch `path` under `id`, replacing whatever was watched under it
+    pub fn watch_root(&mut self, id: &str, path: PathBuf, app_handle: AppHandle) -> Result<(), String> {
         // Stop existing watcher
-        self.stop();
+        self.stop_root(id);
 
         let (tx, rx) = channel();
 
@@ -41,60 +246,54 @@ impl FileWatcher {
             .watch(&path, RecursiveMode::Recursive)
             .map_err(|e| e.to_string())?;
 
-        self.watcher = Some(watcher);
-        self.watched_path = Some(path.clone());
-
-        // Spawn thread to process events
-        thread::spawn(move || {
-            while let Ok(event) = rx.recv() {
-                for path in event.paths {
-                    // Skip hidden files and .notemaker directory internals
-                    if let Some(name) = path.file_name() {
-                        let name_str = name.to_string_lossy();
-                        if name_str.starts_with('.') && name_str != ".notemaker" {
-                            continue;
-                        }
-                    }
+        let subscriptions = Arc::new(Mutex::new(Vec::new()));
+        let events = RootEvents {
+            id: id.to_string(),
+            path: path.clone(),
+            subscriptions: subscriptions.clone(),
+            app_handle,
+        };
+        thread::spawn(move || events.run(rx));
 
-                    // Skip non-markdown files for note events
-                    let is_md = path.extension().map(|e| e == "md").unwrap_or(false);
-                    let is_dir = path.is_dir();
-
-                    if !is_md && !is_dir {
-                        continue;
-                    }
-
-                    let kind = match event.kind {
-                        notify::EventKind::Create(_) => FileChangeKind::Create,
-                        notify::EventKind::Modify(_) => FileChangeKind::Modify,
-                        notify::EventKind::Remove(_) => FileChangeKind::Delete,
-                        _ => continue,
-                    };
-
-                    let change_event = FileChangeEvent {
-                        path: path.clone(),
-                        kind,
-                    };
-
-                    let _ = app_handle.emit("file-changed", change_event);
-                }
-            }
-        });
+        self.roots.insert(
+            id.to_string(),
+            WatchedRoot {
+                _watcher: watcher,
+                path,
+                subscriptions,
+            },
+        );
+        Ok(())
+    }
 
+    /// Limit events of a root to the given subtrees; an empty list restores
+    /// events for the whole root
+    pub fn subscribe(&self, id: &str, paths: Vec<PathBuf>) -> Result<(), String> {
+        let root = self.roots.get(id).ok_or_else(|| format!("Nothing is watched as {}", id))?;
+        if let Some(outside) = paths.iter().find(|p| !p.starts_with(&root.path)) {
+            return Err(format!("{} is not inside {}", outside.display(), root.path.display()));
+        }
+        *root.subscriptions.lock().map_err(|e| e.to_string())? = paths;
         Ok(())
     }
 
+    /// Stop watching every root
     pub fn stop(&mut self) {
-        self.watcher = None;
-        self.watched_path = None;
+        self.roots.clear();
+    }
+
+    /// Stop watching one root
+    pub fn stop_root(&mut self, id: &str) {
+        self.roots.remove(id);
     }
 
     pub fn is_watching(&self) -> bool {
-        self.watcher.is_some()
+        !self.roots.is_empty()
     }
 
+    /// Path of the open vault
     pub fn watched_path(&self) -> Option<&PathBuf> {
-        self.watched_path.as_ref()
+        self.roots.get(VAULT_WATCH_ID).map(|root| &root.path)
     }
 }
 
@@ -104,24 +303,275 @@ impl Default for FileWatcher {
     }
 }
 
+/// Event handling of one watched root, run on its own thread
+struct RootEvents {
+    id: String,
+    path: PathBuf,
+    subscriptions: Arc<Mutex<Vec<PathBuf>>>,
+    app_handle: AppHandle,
+}
+
+impl RootEvents {
+    /// Whether events for `path` are wanted under the current subscriptions
+    fn subscribed(&self, path: &Path) -> bool {
+        match self.subscriptions.lock() {
+            Ok(subscriptions) => subscriptions.is_empty() || subscriptions.iter().any(|s| path.starts_with(s)),
+            Err(_) => true,
+        }
+    }
+
+    /// Process raw events until the watcher is dropped; changes are emitted
+    /// in batches as `files-changed` once events quiet down
+    fn run(self, rx: Receiver<Event>) {
+        // A broken ignore file is reported by the file tree
+        let mut ignore = VaultIgnore::load(&self.path).unwrap_or_default();
+        let mut pending = PendingChanges::default();
+        // Changes below paused directories, emitted once their pause ends
+        let mut held = PendingChanges::default();
+        let mut hashes = ContentHashes::default();
+        loop {
+            let wait = if held.changes.is_empty() {
+                pending.wait()
+            } else {
+                // Check back for the end of the pause
+                pending.wait().min(RESUME_GRACE)
+            };
+            let event = match rx.recv_timeout(wait) {
+                Ok(event) => event,
+                Err(RecvTimeoutError::Timeout) => Event::new(notify::EventKind::Any),
+                // The watcher was stopped
+                Err(RecvTimeoutError::Disconnected) => break,
+            };
+
+            // Older than anything in this event, so released first
+            held.release(&mut pending, |path| !is_paused(path));
+
+            for path in event.paths {
+                // Pick up edits to the ignore patterns
+                if path == self.path.join(IGNORE_FILE) {
+                    ignore = VaultIgnore::load(&self.path).unwrap_or_default();
+                    continue;
+                }
+                let queue = if is_paused(&path) { &mut held } else { &mut pending };
+
+                // Configuration and index files have events of their own
+                if index_event(&self.path, &path).is_some() {
+                    match event.kind {
+                        notify::EventKind::Create(_) | notify::EventKind::Modify(_) => {
+                            queue.add(path, FileChangeKind::Modify)
+                        }
+                        _ => {}
+                    }
+                    continue;
+                }
+
+                // Skip hidden files and .notemaker directory internals
+                if let Some(name) = path.file_name() {
+                    let name_str = name.to_string_lossy();
+                    if name_str.starts_with('.') && name_str != ".notemaker" {
+                        continue;
+                    }
+                }
+
+                // Skip non-markdown files for note events
+                let is_md = path.extension().map(|e| e == "md").unwrap_or(false);
+                let is_dir = path.is_dir();
+
+                if !is_md && !is_dir {
+                    continue;
+                }
+
+                if ignore.is_ignored(&path, is_dir) || !self.subscribed(&path) {
+                    continue;
+                }
+
+                let kind = match event.kind {
+                    notify::EventKind::Create(_) => FileChangeKind::Create,
+                    notify::EventKind::Modify(_) => FileChangeKind::Modify,
+                    notify::EventKind::Remove(_) => FileChangeKind::Delete,
+                    _ => continue,
+                };
+
+                queue.add(path, kind);
+            }
+
+            if let Some(mut changes) = pending.take_due() {
+                changes.retain(|change| hashes.changed(change));
+                // The frontend already knows about the app's own writes.
+                // Checked at emit time so the write has surely been recorded.
+                changes.retain(|change| change.kind == FileChangeKind::Delete || !is_own_write(&change.path));
+
+                changes.retain(|change| {
+                    let Some((name, path)) = index_event(&self.path, &change.path) else {
+                        return true;
+                    };
+                    let event = IndexChangeEvent {
+                        root: self.id.clone(),
+                        path,
+                    };
+                    let _ = self.app_handle.emit(name, event);
+                    false
+                });
+                if !changes.is_empty() {
+                    let batch = FileChangeBatch {
+                        root: self.id.clone(),
+                        changes,
+                    };
+                    let _ = self.app_handle.emit("files-changed", batch);
+                }
+            }
+        }
+    }
+}
+
 /// Global file watcher state
 pub type WatcherState = Arc<Mutex<FileWatcher>>;
 
-/// Start watching a vault directory
+/// Start watching a directory, the open vault unless another `id` is given
 #[tauri::command]
 pub async fn start_watching(
     path: PathBuf,
+    id: Option<String>,
     app_handle: AppHandle,
     watcher_state: tauri::State<'_, WatcherState>,
 ) -> Result<(), String> {
     let mut watcher = watcher_state.lock().map_err(|e| e.to_string())?;
-    watcher.watch(path, app_handle)
+    watcher.watch_root(id.as_deref().unwrap_or(VAULT_WATCH_ID), path, app_handle)
 }
 
-/// Stop watching
+/// Stop watching the root `id`, or everything when no ID is given
 #[tauri::command]
-pub async fn stop_watching(watcher_state: tauri::State<'_, WatcherState>) -> Result<(), String> {
+pub async fn stop_watching(id: Option<String>, watcher_state: tauri::State<'_, WatcherState>) -> Result<(), String> {
     let mut watcher = watcher_state.lock().map_err(|e| e.to_string())?;
-    watcher.stop();
+    match id {
+        Some(id) => watcher.stop_root(&id),
+        None => watcher.stop(),
+    }
     Ok(())
 }
+
+/// Hold back change events below `path` until `resume_watching`; pauses nest
+#[tauri::command]
+pub async fn pause_watching(path: PathBuf) -> Result<(), String> {
+    pause(&path);
+    Ok(())
+}
+
+/// Undo one `pause_watching` of `path`; the changes held back are emitted
+/// as one batch
+#[tauri::command]
+pub async fn resume_watching(path: PathBuf) -> Result<(), String> {
+    resume(&path);
+    Ok(())
+}
+
+/// Limit events of a watched root to the given subtrees (empty for all)
+#[tauri::command]
+pub async fn set_watch_subscriptions(
+    paths: Vec<PathBuf>,
+    id: Option<String>,
+    watcher_state: tauri::State<'_, WatcherState>,
+) -> Result<(), String> {
+    let watcher = watcher_state.lock().map_err(|e| e.to_string())?;
+    watcher.subscribe(id.as_deref().unwrap_or(VAULT_WATCH_ID), paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn coalesces_changes_per_path() {
+        let mut pending = PendingChanges::default();
+        let path = |name: &str| PathBuf::from("/vault").join(name);
+
+        pending.add(path("new.md"), FileChangeKind::Create);
+        pending.add(path("new.md"), FileChangeKind::Modify);
+        pending.add(path("temp.md"), FileChangeKind::Create);
+        pending.add(path("temp.md"), FileChangeKind::Delete);
+        pending.add(path("saved.md"), FileChangeKind::Delete);
+        pending.add(path("saved.md"), FileChangeKind::Create);
+        pending.add(path("gone.md"), FileChangeKind::Modify);
+        pending.add(path("gone.md"), FileChangeKind::Delete);
+
+        assert!(pending.take_due().is_none());
+        thread::sleep(DEBOUNCE);
+        let batch: Vec<(PathBuf, FileChangeKind)> =
+            pending.take_due().unwrap().into_iter().map(|e| (e.path, e.kind)).collect();
+        assert_eq!(
+            batch,
+            vec![
+                (path("gone.md"), FileChangeKind::Delete),
+                (path("new.md"), FileChangeKind::Create),
+                (path("saved.md"), FileChangeKind::Modify),
+            ]
+        );
+        assert!(pending.take_due().is_none());
+    }
+
+    #[test]
+    fn drops_modifications_without_content_change() {
+        let dir = tempfile::tempdir().unwrap();
+        let note = dir.path().join("Note.md");
+        let change = |kind| FileChangeEvent {
+            path: note.clone(),
+            kind,
+        };
+        let mut hashes = ContentHashes::default();
+
+        fs::write(&note, "# Note\n").unwrap();
+        assert!(hashes.changed(&change(FileChangeKind::Create)));
+        // Touched, same content
+        fs::write(&note, "# Note\n").unwrap();
+        assert!(!hashes.changed(&change(FileChangeKind::Modify)));
+        fs::write(&note, "# Edited\n").unwrap();
+        assert!(hashes.changed(&change(FileChangeKind::Modify)));
+
+        assert!(hashes.changed(&change(FileChangeKind::Delete)));
+        assert!(hashes.changed(&change(FileChangeKind::Modify)));
+    }
+
+    #[test]
+    fn recognizes_config_and_index_files() {
+        let root = Path::new("/vault");
+        let event = |path: &str| index_event(root, &root.join(path));
+
+        assert_eq!(event(".notemaker/config.yaml"), Some(("config-changed", root.to_path_buf())));
+        assert_eq!(event("Plan.md/.index.json"), Some(("notebook-changed", root.join("Plan.md"))));
+        assert_eq!(event("Work/Tasks.kanban/.index.json"), Some(("kanban-changed", root.join("Work/Tasks.kanban"))));
+        assert_eq!(event("Folder/.index.json"), None);
+        assert_eq!(event("Note.md"), None);
+    }
+
+    #[test]
+    fn pauses_nest_per_directory() {
+        let vault = Path::new("/paused/vault");
+        let outer = WatchPause::new(vault);
+        let inner = WatchPause::new(vault);
+        drop(inner);
+        assert!(is_paused(&vault.join("Note.md")));
+        assert!(!is_paused(Path::new("/paused/other/Note.md")));
+        drop(outer);
+        // Late events of the paused work are still held back for a moment
+        assert!(is_paused(&vault.join("Note.md")));
+        thread::sleep(RESUME_GRACE);
+        assert!(!is_paused(&vault.join("Note.md")));
+    }
+
+    #[test]
+    fn releases_held_changes() {
+        let mut held = PendingChanges::default();
+        let mut pending = PendingChanges::default();
+        held.add(PathBuf::from("/a/New.md"), FileChangeKind::Create);
+        held.add(PathBuf::from("/b/Note.md"), FileChangeKind::Modify);
+
+        held.release(&mut pending, |path| path.starts_with("/a"));
+        thread::sleep(DEBOUNCE);
+        let batch: Vec<PathBuf> = pending.take_due().unwrap().into_iter().map(|e| e.path).collect();
+        assert_eq!(batch, vec![PathBuf::from("/a/New.md")]);
+
+        held.release(&mut pending, |_| true);
+        assert!(held.changes.is_empty());
+        assert!(pending.changes.contains_key(Path::new("/b/Note.md")));
+    }
+}
@@ -0,0 +1,185 @@
+//! Webhooks: JSON `POST`s to the endpoints in the vault's `webhooks` config
+//! when notes are saved, kanban tasks move, commits are made or a sync ends.
+//!
+//! Each body is `{"event", "vault", "time", "data"}` and the request carries
+//! `X-Notemaker-Event`; a hook with a `secret` also gets
+//! `X-Notemaker-Signature: sha256=<hex HMAC-SHA256 of the body>`. Requests go
+//! out in the background, so a slow endpoint never holds up the change that
+//! caused them; failures are reported with a `webhook-failed` event. Saves
+//! are sent once a note has been left alone for a few seconds, not for every
+//! autosave.
+//!
+//! Only the app sends webhooks, from its commands and background sync:
+//! changes made through `notemaker-cli` don't trigger them.
+
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+use super::commands::{find_vault_root, read_vault_config};
+use super::types::{VaultConfig, WebhookEvent, WebhookFailure, WebhookSettings};
+
+/// Time allowed for each webhook request
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long a note has to stay unchanged before its save is sent
+const SAVE_QUIET: Duration = Duration::from_secs(5);
+
+/// Hex HMAC-SHA256 of `body` keyed with `secret`
+pub(crate) fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(body);
+    mac.finalize().into_bytes().iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Hooks of the vault that want `event`
+fn subscribers(config: &VaultConfig, event: WebhookEvent) -> Vec<WebhookSettings> {
+    config
+        .webhooks
+        .iter()
+        .filter(|hook| hook.events.is_empty() || hook.events.contains(&event))
+        .cloned()
+        .collect()
+}
+
+fn payload(vault: &Path, config: &VaultConfig, event: WebhookEvent, data: Value) -> Value {
+    let name = match config.vault.name.as_str() {
+        "" => vault.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default(),
+        name => name.to_string(),
+    };
+    json!({
+        "event": event,
+        "vault": name,
+        "time": Utc::now().to_rfc3339(),
+        "data": data,
+    })
+}
+
+async fn deliver(client: &reqwest::Client, hook: &WebhookSettings, event: &str, body: &str) -> Result<(), String> {
+    let mut request = client
+        .post(&hook.url)
+        .header("Content-Type", "application/json")
+        .header("X-Notemaker-Event", event)
+        .body(body.to_string());
+    if let Some(secret) = hook.secret.as_deref().filter(|s| !s.is_empty()) {
+        request = request.header("X-Notemaker-Signature", format!("sha256={}", sign(secret, body.as_bytes())));
+    }
+    request
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+async fn post(app: AppHandle, hooks: Vec<WebhookSettings>, event: WebhookEvent, body: String) {
+    let client = reqwest::Client::builder()
+        .timeout(WEBHOOK_TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string());
+    let name = json!(event).as_str().unwrap_or_default().to_string();
+    for hook in hooks {
+        let result = match &client {
+            Ok(client) => deliver(client, &hook, &name, &body).await,
+            Err(e) => Err(e.clone()),
+        };
+        if let Err(error) = result {
+            let _ = app.emit(
+                "webhook-failed",
+                WebhookFailure {
+                    url: hook.url,
+                    event,
+                    error,
+                },
+            );
+        }
+    }
+}
+
+/// Send `event` to the vault's webhooks that want it
+pub(crate) fn dispatch_webhooks(app: &AppHandle, vault: &Path, event: WebhookEvent, data: Value) {
+    let Ok(config) = read_vault_config(vault) else {
+        return;
+    };
+    let hooks = subscribers(&config, event);
+    if hooks.is_empty() {
+        return;
+    }
+    let body = payload(vault, &config, event, data).to_string();
+    tauri::async_runtime::spawn(post(app.clone(), hooks, event, body));
+}
+
+/// Send `note_saved` for a note once it has stopped changing
+pub(crate) fn note_saved(app: &AppHandle, path: &Path) {
+    static PENDING: OnceLock<Mutex<HashMap<PathBuf, u64>>> = OnceLock::new();
+    let Some(vault) = find_vault_root(path) else {
+        return;
+    };
+    let pending = PENDING.get_or_init(Default::default);
+    let generation = {
+        let mut pending = pending.lock().unwrap();
+        let generation = pending.entry(path.to_path_buf()).or_default();
+        *generation += 1;
+        *generation
+    };
+
+    let path = path.to_path_buf();
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(SAVE_QUIET).await;
+        {
+            let mut pending = pending.lock().unwrap();
+            // A later save restarted the wait
+            if pending.get(&path) != Some(&generation) {
+                return;
+            }
+            pending.remove(&path);
+        }
+        let note = path.strip_prefix(&vault).unwrap_or(&path);
+        dispatch_webhooks(&app, &vault, WebhookEvent::NoteSaved, json!({ "path": note }));
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::types::VaultSettings;
+
+    #[test]
+    fn signs_and_filters_hooks() {
+        // RFC 4231, test case 2
+        assert_eq!(
+            sign("Jefe", b"what do ya want for nothing?"),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+
+        let hook = |url: &str, events: Vec<WebhookEvent>| WebhookSettings {
+            url: url.to_string(),
+            events,
+            secret: None,
+        };
+        let config = VaultConfig {
+            vault: VaultSettings {
+                name: String::new(),
+                created: String::new(),
+            },
+            webhooks: vec![
+                hook("https://all.example", Vec::new()),
+                hook("https://tasks.example", vec![WebhookEvent::TaskMoved]),
+            ],
+            ..Default::default()
+        };
+        let urls = |event| subscribers(&config, event).into_iter().map(|h| h.url).collect::<Vec<_>>();
+        assert_eq!(urls(WebhookEvent::TaskMoved), vec!["https://all.example", "https://tasks.example"]);
+        assert_eq!(urls(WebhookEvent::SyncCompleted), vec!["https://all.example"]);
+
+        let body = payload(Path::new("/notes/work"), &config, WebhookEvent::CommitCreated, json!({ "commit": "abc" }));
+        assert_eq!((&body["event"], &body["vault"]), (&json!("commit_created"), &json!("work")));
+        assert_eq!(body["data"]["commit"], "abc");
+    }
+}
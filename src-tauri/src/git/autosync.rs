@@ -0,0 +1,110 @@
+//! Background auto-commit and auto-sync for the open vault.
+//!
+//! The vault being watched for file changes is the one the user has open.
+//! Every `auto_sync_interval` minutes its changes are committed (when
+//! `auto_commit` is on) and, when the configured remote exists, pulled and
+//! pushed. Each run emits a `git-autosync` event with the result.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+use super::sync::{notify_sync, sync_vault};
+use super::types::AutoSyncEvent;
+use crate::fs::{read_vault_config, GitSettings, WatcherState};
+
+/// How often the scheduler checks whether a run is due
+const TICK: Duration = Duration::from_secs(30);
+
+/// Commit local changes and sync with the remote once
+pub(crate) fn run_autosync(vault: &Path, settings: &GitSettings) -> AutoSyncEvent {
+    let result = sync_vault(vault, &settings.remote, settings.auto_commit, |_| {});
+    AutoSyncEvent {
+        vault_path: vault.to_string_lossy().to_string(),
+        error: result.as_ref().err().map(|e| e.to_string()),
+        result: result.unwrap_or_default(),
+    }
+}
+
+/// Spawn the scheduler; it runs for the lifetime of the app
+pub fn spawn_autosync(app_handle: AppHandle, watcher_state: WatcherState) {
+    tauri::async_runtime::spawn(async move {
+        let mut last_run: Option<(PathBuf, Instant)> = None;
+
+        loop {
+            tokio::time::sleep(TICK).await;
+
+            let vault = match watcher_state.lock() {
+                Ok(watcher) => watcher.watched_path().cloned(),
+                Err(_) => None,
+            };
+            let Some(vault) = vault else {
+                continue;
+            };
+            let Ok(config) = read_vault_config(&vault) else {
+                continue;
+            };
+            let settings = config.git;
+            if !settings.enabled || settings.auto_sync_interval == 0 || !vault.join(".git").exists() {
+                continue;
+            }
+
+            let interval = Duration::from_secs(u64::from(settings.auto_sync_interval) * 60);
+            // The interval starts when a vault is opened
+            let due = match &last_run {
+                Some((path, at)) if *path == vault => at.elapsed() >= interval,
+                _ => {
+                    last_run = Some((vault.clone(), Instant::now()));
+                    false
+                }
+            };
+            if !due {
+                continue;
+            }
+            last_run = Some((vault.clone(), Instant::now()));
+
+            let handle = app_handle.clone();
+            let event = tauri::async_runtime::spawn_blocking(move || {
+                let event = run_autosync(&vault, &settings);
+                if event.error.is_none() {
+                    notify_sync(&handle, &vault, &event.result);
+                }
+                event
+            })
+            .await;
+            if let Ok(event) = event {
+                let _ = app_handle.emit("git-autosync", event);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commits_changes_without_remote() {
+        let dir = tempfile::tempdir().unwrap();
+        git2::Repository::init(dir.path()).unwrap();
+        std::fs::write(dir.path().join("Note.md"), "# Note").unwrap();
+
+        let settings = GitSettings {
+            enabled: true,
+            auto_commit: true,
+            auto_sync_interval: 5,
+            remote: "origin".to_string(),
+            branch: "main".to_string(),
+            signing: Default::default(),
+        };
+        let event = run_autosync(dir.path(), &settings);
+        assert_eq!(event.error, None);
+        assert_eq!(event.result.files_committed, 1);
+        assert!(event.result.commit.is_some());
+        assert!(event.result.pull.is_none());
+
+        // Nothing left to commit on the next run
+        let event = run_autosync(dir.path(), &settings);
+        assert_eq!(event.result.commit, None);
+    }
+}
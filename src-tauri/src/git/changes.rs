@@ -0,0 +1,213 @@
+//! Feed of recently changed notes for the home view.
+//!
+//! Modification times on disk cover edits not committed yet; the git log,
+//! when the vault is a repository, adds deletions and renames and tells who
+//! changed what. Each note appears once, with its latest change.
+
+use git2::{Delta, Repository};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use super::types::{ChangeSource, ChangeType, GitError, RecentChange};
+use crate::fs::walk_notes;
+
+/// Seconds since the epoch of a time, 0 when unknown
+fn unix_secs(time: std::io::Result<std::time::SystemTime>) -> i64 {
+    time.ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+/// Latest modification below a path, for notebooks and boards the newest of
+/// their files
+fn latest_modification(path: &Path) -> i64 {
+    let own = unix_secs(fs::metadata(path).and_then(|m| m.modified()));
+    if !path.is_dir() {
+        return own;
+    }
+    fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|e| latest_modification(&e.path()))
+                .fold(own, i64::max)
+        })
+        .unwrap_or(own)
+}
+
+/// The note, notebook or board a changed file belongs to
+fn owning_entry(rel: &Path) -> Option<PathBuf> {
+    let mut owner = PathBuf::new();
+    for component in rel.components() {
+        let Component::Normal(name) = component else {
+            return None;
+        };
+        owner.push(name);
+        let name = name.to_string_lossy();
+        if name.ends_with(".md") || name.ends_with(".kanban") {
+            return Some(owner);
+        }
+    }
+    None
+}
+
+/// Changes on disk since `since`
+fn filesystem_changes(vault: &Path, since: i64) -> Vec<RecentChange> {
+    let mut notes = Vec::new();
+    walk_notes(vault, &mut notes);
+    notes
+        .into_iter()
+        .filter_map(|path| {
+            let timestamp = latest_modification(&path);
+            if timestamp < since {
+                return None;
+            }
+            let created = unix_secs(fs::metadata(&path).and_then(|m| m.created()));
+            let rel = path.strip_prefix(vault).ok()?;
+            Some(RecentChange {
+                path: rel.to_string_lossy().replace('\\', "/"),
+                old_path: None,
+                change: if created >= since { ChangeType::Created } else { ChangeType::Modified },
+                timestamp,
+                source: ChangeSource::Filesystem,
+                author: None,
+                commit: None,
+            })
+        })
+        .collect()
+}
+
+/// Changes committed since `since`, newest first
+fn git_changes(repo: &Repository, since: i64) -> Result<Vec<RecentChange>, GitError> {
+    let mut revwalk = repo.revwalk()?;
+    if revwalk.push_head().is_err() {
+        // No commits yet
+        return Ok(Vec::new());
+    }
+    revwalk.set_sorting(git2::Sort::TIME)?;
+
+    let mut changes = Vec::new();
+    for oid in revwalk {
+        let commit = repo.find_commit(oid?)?;
+        let timestamp = commit.time().seconds();
+        if timestamp < since {
+            break;
+        }
+
+        let parent_tree = match commit.parent(0) {
+            Ok(parent) => Some(parent.tree()?),
+            Err(_) => None,
+        };
+        let tree = commit.tree()?;
+        let mut diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), None)?;
+        diff.find_similar(None)?;
+
+        for delta in diff.deltas() {
+            let status = delta.status();
+            let file = match status {
+                Delta::Deleted => delta.old_file(),
+                _ => delta.new_file(),
+            };
+            let Some(file_path) = file.path() else {
+                continue;
+            };
+            let Some(path) = owning_entry(file_path) else {
+                continue;
+            };
+
+            let change = if path != file_path {
+                // A file inside a notebook or board: what happened to the whole
+                let existed = parent_tree.as_ref().is_some_and(|t| t.get_path(&path).is_ok());
+                let exists = tree.get_path(&path).is_ok();
+                match (existed, exists) {
+                    (false, _) => ChangeType::Created,
+                    (_, false) => ChangeType::Deleted,
+                    _ => ChangeType::Modified,
+                }
+            } else {
+                match status {
+                    Delta::Added | Delta::Copied => ChangeType::Created,
+                    Delta::Deleted => ChangeType::Deleted,
+                    Delta::Renamed => ChangeType::Renamed,
+                    _ => ChangeType::Modified,
+                }
+            };
+            let old_path = (change == ChangeType::Renamed)
+                .then(|| delta.old_file().path().map(|p| p.to_string_lossy().replace('\\', "/")))
+                .flatten();
+
+            changes.push(RecentChange {
+                path: path.to_string_lossy().replace('\\', "/"),
+                old_path,
+                change,
+                timestamp,
+                source: ChangeSource::Git,
+                author: commit.author().name().map(str::to_string),
+                commit: Some(commit.id().to_string()),
+            });
+        }
+    }
+    Ok(changes)
+}
+
+/// Notes, notebooks and boards changed since the Unix timestamp `since`,
+/// newest first
+#[tauri::command]
+pub fn get_recent_changes(vault_path: String, since: i64) -> Result<Vec<RecentChange>, GitError> {
+    let vault = Path::new(&vault_path);
+    if !vault.is_dir() {
+        return Err(GitError::InvalidPath(vault_path));
+    }
+
+    let mut changes = filesystem_changes(vault, since);
+    if let Ok(repo) = Repository::open(vault) {
+        changes.extend(git_changes(&repo, since)?);
+    }
+
+    // Keep the latest change per path; on a tie the commit tells more
+    changes.sort_by_key(|c| (std::cmp::Reverse(c.timestamp), c.source == ChangeSource::Filesystem));
+    let mut latest: HashMap<String, RecentChange> = HashMap::new();
+    for change in changes {
+        latest.entry(change.path.clone()).or_insert(change);
+    }
+    let mut feed: Vec<RecentChange> = latest.into_values().collect();
+    feed.sort_by(|a, b| b.timestamp.cmp(&a.timestamp).then_with(|| a.path.cmp(&b.path)));
+    Ok(feed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::commands::commit_all;
+
+    #[test]
+    fn merges_disk_and_git_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        Repository::init(dir.path()).unwrap();
+        let vault = dir.path().to_string_lossy().to_string();
+        std::fs::write(dir.path().join("Gone.md"), "# Gone\n").unwrap();
+        std::fs::write(dir.path().join("Kept.md"), "# Kept\n").unwrap();
+        commit_all(dir.path(), "Add notes").unwrap();
+        std::fs::remove_file(dir.path().join("Gone.md")).unwrap();
+        commit_all(dir.path(), "Remove note").unwrap();
+        std::fs::write(dir.path().join("Draft.md"), "# Draft\n").unwrap();
+
+        let feed = get_recent_changes(vault, 0).unwrap();
+        let by_path: HashMap<&str, &RecentChange> = feed.iter().map(|c| (c.path.as_str(), c)).collect();
+        assert_eq!(feed.len(), 3);
+        assert_eq!(by_path["Gone.md"].change, ChangeType::Deleted);
+        assert_eq!(by_path["Gone.md"].source, ChangeSource::Git);
+        assert_eq!(by_path["Draft.md"].source, ChangeSource::Filesystem);
+        assert_eq!(by_path["Kept.md"].change, ChangeType::Created);
+    }
+
+    #[test]
+    fn collapses_notebook_files() {
+        assert_eq!(owning_entry(Path::new("Run.md/a.md")), Some(PathBuf::from("Run.md")));
+        assert_eq!(owning_entry(Path::new("Board.kanban/tasks/t1.md")), Some(PathBuf::from("Board.kanban")));
+        assert_eq!(owning_entry(Path::new(".gitignore")), None);
+    }
+}
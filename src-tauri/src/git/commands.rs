@@ -1,6 +1,16 @@
-use super::types::{BranchInfo, CommitInfo, CommitDiff, DiffFile, DiffHunk, DiffLine, FileHistory, FileStatus, GitError, GitStatus, PullResult};
-use git2::{Diff, DiffOptions, Repository, Signature, StatusOptions};
-use std::path::Path;
+use super::types::{BranchInfo, CloneProgress, ConflictVersions, CommitInfo, CommitDiff, DiffFile, DiffHunk, DiffLine, FileHistory, FileStatus, GitCredentials, GitError, GitStatus, LogFilter, PullResult, SyncStage};
+use super::signing::{sign_commit_buffer, signing_settings, verify_commit};
+use super::word_diff::add_word_segments;
+use git2::{Cred, CredentialType, Diff, DiffOptions, RemoteCallbacks, Repository, Signature, StatusOptions};
+use keyring::Entry;
+use serde_json::json;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter};
+
+use crate::fs::webhooks::dispatch_webhooks;
+use crate::fs::{WatchPause, WebhookEvent};
+
+const KEYCHAIN_SERVICE: &str = "com.notemaker.git";
 
 /// Initialize a git repository in the vault
 #[tauri::command]
@@ -45,6 +55,9 @@ pub fn git_status(vault_path: String) -> Result<GitStatus, GitError> {
                 staged_count: 0,
                 unstaged_count: 0,
                 untracked_count: 0,
+                upstream: None,
+                ahead: 0,
+                behind: 0,
             });
         }
     };
@@ -79,6 +92,11 @@ pub fn git_status(vault_path: String) -> Result<GitStatus, GitError> {
         }
     }
 
+    let (upstream, ahead, behind) = match upstream_divergence(&repo) {
+        Some((name, ahead, behind)) => (Some(name), ahead as u32, behind as u32),
+        None => (None, 0, 0),
+    };
+
     Ok(GitStatus {
         is_repo: true,
         branch,
@@ -86,9 +104,54 @@ pub fn git_status(vault_path: String) -> Result<GitStatus, GitError> {
         staged_count,
         unstaged_count,
         untracked_count,
+        upstream,
+        ahead,
+        behind,
     })
 }
 
+/// Upstream of the current branch with ahead/behind counts
+///
+/// Uses the configured upstream, or `origin/<branch>` since pushes from the
+/// app do not set one.
+fn upstream_divergence(repo: &Repository) -> Option<(String, usize, usize)> {
+    let head = repo.head().ok()?;
+    let local = head.target()?;
+    let branch_name = head.shorthand()?;
+
+    let upstream = repo
+        .find_branch(branch_name, git2::BranchType::Local)
+        .ok()
+        .and_then(|b| b.upstream().ok())
+        .map(|u| u.into_reference())
+        .or_else(|| repo.find_reference(&format!("refs/remotes/origin/{}", branch_name)).ok())?;
+
+    let name = upstream.shorthand()?.to_string();
+    let (ahead, behind) = repo.graph_ahead_behind(local, upstream.target()?).ok()?;
+    Some((name, ahead, behind))
+}
+
+/// Fetch the vault's remote without merging, returning the updated status
+#[tauri::command]
+pub async fn git_fetch(vault_path: String) -> Result<GitStatus, GitError> {
+    let remote_name = crate::fs::read_vault_config(Path::new(&vault_path))
+        .map(|config| config.git.remote)
+        .unwrap_or_else(|_| "origin".to_string());
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let repo = Repository::open(&vault_path).map_err(|_| GitError::NotARepository)?;
+        let mut remote = repo.find_remote(&remote_name)?;
+        let mut fetch_options = git2::FetchOptions::new();
+        fetch_options.remote_callbacks(remote_callbacks(&repo));
+        let fetch_refspecs: &[&str] = &[];
+        remote.fetch(fetch_refspecs, Some(&mut fetch_options), None)?;
+
+        git_status(vault_path)
+    })
+    .await
+    .map_err(|e| GitError::Generic(e.to_string()))?
+}
+
 /// Get list of changed files
 #[tauri::command]
 pub fn git_changed_files(vault_path: String) -> Result<Vec<FileStatus>, GitError> {
@@ -181,10 +244,35 @@ pub fn git_unstage(vault_path: String, file_path: String) -> Result<(), GitError
 
 /// Commit staged changes
 #[tauri::command]
-pub fn git_commit(vault_path: String, message: String) -> Result<String, GitError> {
+pub fn git_commit(app_handle: AppHandle, vault_path: String, message: String) -> Result<String, GitError> {
     let path = Path::new(&vault_path);
+    let commit = commit_staged(path, &message)?;
+    let data = json!({ "commit": commit, "message": message.lines().next().unwrap_or_default() });
+    dispatch_webhooks(&app_handle, path, WebhookEvent::CommitCreated, data);
+    Ok(commit)
+}
+
+/// Commit the staged changes of the repository at `path`, signed when the
+/// vault asks for it
+pub fn commit_staged(path: &Path, message: &str) -> Result<String, GitError> {
+    let repo = Repository::open(path).map_err(|_| GitError::NotARepository)?;
+    commit_index(&repo, message)
+}
+
+/// Stage every change, including deletions, and commit it
+pub fn commit_all(path: &Path, message: &str) -> Result<String, GitError> {
     let repo = Repository::open(path).map_err(|_| GitError::NotARepository)?;
 
+    let mut index = repo.index()?;
+    index.add_all(["*"].iter(), git2::IndexAddOption::DEFAULT, None)?;
+    index.update_all(["*"].iter(), None)?;
+    index.write()?;
+
+    commit_index(&repo, message)
+}
+
+/// Commit the current index on top of HEAD
+fn commit_index(repo: &Repository, message: &str) -> Result<String, GitError> {
     let mut index = repo.index()?;
     let tree_id = index.write_tree()?;
     let tree = repo.find_tree(tree_id)?;
@@ -198,18 +286,49 @@ pub fn git_commit(vault_path: String, message: String) -> Result<String, GitErro
     // Get parent commit (if any)
     let parent = repo.head().ok().and_then(|h| h.peel_to_commit().ok());
 
-    let commit_id = if let Some(parent) = parent {
-        repo.commit(Some("HEAD"), &sig, &sig, &message, &tree, &[&parent])?
-    } else {
-        repo.commit(Some("HEAD"), &sig, &sig, &message, &tree, &[])?
+    let parents: Vec<&git2::Commit> = parent.iter().collect();
+
+    let commit_id = match signing_settings(repo) {
+        Some(signing) => {
+            let buffer = repo.commit_create_buffer(&sig, &sig, message, &tree, &parents)?;
+            let buffer = buffer
+                .as_str()
+                .ok_or_else(|| GitError::Generic("Commit is not valid UTF-8".to_string()))?;
+            let signature = sign_commit_buffer(repo, &signing, buffer)?;
+            let commit_id = repo.commit_signed(buffer, &signature, None)?;
+            advance_head(repo, commit_id, message)?;
+            commit_id
+        }
+        None => repo.commit(Some("HEAD"), &sig, &sig, message, &tree, &parents)?,
     };
 
     Ok(commit_id.to_string())
 }
 
+/// Point HEAD (or the branch it refers to) at a new commit
+fn advance_head(repo: &Repository, commit_id: git2::Oid, message: &str) -> Result<(), GitError> {
+    let head = repo.find_reference("HEAD")?;
+    let summary = message.lines().next().unwrap_or("");
+    match head.symbolic_target() {
+        Some(branch) => {
+            repo.reference(branch, commit_id, true, &format!("commit: {}", summary))?;
+        }
+        None => repo.set_head_detached(commit_id)?,
+    }
+    Ok(())
+}
+
 /// Get commit history for the vault
+///
+/// `offset` skips that many matching commits, for paging through long
+/// histories together with `limit`.
 #[tauri::command]
-pub fn git_log(vault_path: String, limit: Option<u32>) -> Result<Vec<CommitInfo>, GitError> {
+pub fn git_log(
+    vault_path: String,
+    limit: Option<u32>,
+    offset: Option<u32>,
+    filter: Option<LogFilter>,
+) -> Result<Vec<CommitInfo>, GitError> {
     let path = Path::new(&vault_path);
     let repo = Repository::open(path).map_err(|_| GitError::NotARepository)?;
 
@@ -218,27 +337,78 @@ pub fn git_log(vault_path: String, limit: Option<u32>) -> Result<Vec<CommitInfo>
     revwalk.set_sorting(git2::Sort::TIME)?;
 
     let limit = limit.unwrap_or(50) as usize;
+    let mut skip = offset.unwrap_or(0) as usize;
+    let filter = filter.unwrap_or_default();
     let mut commits = Vec::new();
 
-    for (i, oid) in revwalk.enumerate() {
-        if i >= limit {
+    for oid in revwalk {
+        if commits.len() >= limit {
             break;
         }
 
         let oid = oid?;
         let commit = repo.find_commit(oid)?;
+        if !commit_matches(&repo, &commit, &filter)? {
+            continue;
+        }
+        if skip > 0 {
+            skip -= 1;
+            continue;
+        }
 
         commits.push(CommitInfo {
             id: oid.to_string(),
             message: commit.message().unwrap_or("").to_string(),
             author: commit.author().name().unwrap_or("Unknown").to_string(),
             timestamp: commit.time().seconds(),
+            signature: verify_commit(&repo, oid),
         });
     }
 
     Ok(commits)
 }
 
+/// Whether a commit passes a log filter; cheap checks run first
+pub(crate) fn commit_matches(repo: &Repository, commit: &git2::Commit, filter: &LogFilter) -> Result<bool, GitError> {
+    let time = commit.time().seconds();
+    if filter.since.is_some_and(|since| time < since) || filter.until.is_some_and(|until| time > until) {
+        return Ok(false);
+    }
+
+    if let Some(author) = &filter.author {
+        let needle = author.to_lowercase();
+        let signature = commit.author();
+        let name = signature.name().unwrap_or("").to_lowercase();
+        let email = signature.email().unwrap_or("").to_lowercase();
+        if !name.contains(&needle) && !email.contains(&needle) {
+            return Ok(false);
+        }
+    }
+
+    if let Some(message) = &filter.message {
+        let haystack = commit.message().unwrap_or("").to_lowercase();
+        if !haystack.contains(&message.to_lowercase()) {
+            return Ok(false);
+        }
+    }
+
+    if let Some(prefix) = filter.path.as_deref().filter(|p| !p.is_empty()) {
+        // Only the changes of this commit, limited to the prefix
+        let parent_tree = match commit.parent(0) {
+            Ok(parent) => Some(parent.tree()?),
+            Err(_) => None,
+        };
+        let mut diff_opts = DiffOptions::new();
+        diff_opts.pathspec(prefix);
+        let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), Some(&mut diff_opts))?;
+        if diff.deltas().len() == 0 {
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
 /// Get history for a specific file
 #[tauri::command]
 pub fn git_file_history(
@@ -272,6 +442,7 @@ pub fn git_file_history(
                     message: commit.message().unwrap_or("").to_string(),
                     author: commit.author().name().unwrap_or("Unknown").to_string(),
                     timestamp: commit.time().seconds(),
+                    signature: verify_commit(&repo, oid),
                 });
             }
         }
@@ -352,7 +523,19 @@ pub fn git_diff(vault_path: String, commit_id: String) -> Result<CommitDiff, Git
         Some(&mut diff_opts),
     )?;
 
-    // Parse the diff into our structures
+    let files = diff_files(&diff)?;
+
+    Ok(CommitDiff {
+        commit_id,
+        message,
+        author,
+        time,
+        files,
+    })
+}
+
+/// Parse a diff into our structures
+fn diff_files(diff: &Diff) -> Result<Vec<DiffFile>, GitError> {
     let mut files: Vec<DiffFile> = Vec::new();
     let mut current_file: Option<DiffFile> = None;
     let mut current_hunk: Option<DiffHunk> = None;
@@ -364,7 +547,7 @@ pub fn git_diff(vault_path: String, commit_id: String) -> Result<CommitDiff, Git
 
             // Determine file status
             let status = match delta.status() {
-                git2::Delta::Added => "added",
+                git2::Delta::Added | git2::Delta::Untracked => "added",
                 git2::Delta::Deleted => "deleted",
                 git2::Delta::Modified => "modified",
                 git2::Delta::Renamed => "renamed",
@@ -397,8 +580,9 @@ pub fn git_diff(vault_path: String, commit_id: String) -> Result<CommitDiff, Git
             }
         }
 
-        // Handle hunk header
-        if let Some(hunk_info) = hunk {
+        // Handle hunk header; every line of a hunk carries it, so only the
+        // header line starts a new one
+        if let Some(hunk_info) = hunk.filter(|_| line.origin() == 'H') {
             // Save previous hunk if exists
             if let Some(hunk) = current_hunk.take() {
                 if let Some(ref mut file) = current_file {
@@ -436,6 +620,7 @@ pub fn git_diff(vault_path: String, commit_id: String) -> Result<CommitDiff, Git
             old_line_no: line.old_lineno(),
             new_line_no: line.new_lineno(),
             content,
+            segments: None,
         };
 
         if let Some(ref mut hunk) = current_hunk {
@@ -455,12 +640,76 @@ pub fn git_diff(vault_path: String, commit_id: String) -> Result<CommitDiff, Git
         files.push(file);
     }
 
-    Ok(CommitDiff {
-        commit_id,
-        message,
-        author,
-        time,
-        files,
+    for hunk in files.iter_mut().flat_map(|f| f.hunks.iter_mut()) {
+        add_word_segments(hunk);
+    }
+
+    Ok(files)
+}
+
+/// Diff of a file's uncommitted changes, staged and unstaged, against HEAD
+///
+/// Returns `None` when the file has no changes.
+#[tauri::command]
+pub fn git_diff_workdir(vault_path: String, file_path: String) -> Result<Option<DiffFile>, GitError> {
+    let path = Path::new(&vault_path);
+    let repo = Repository::open(path).map_err(|_| GitError::NotARepository)?;
+
+    // A repository without commits diffs against an empty tree
+    let head_tree = repo.head().ok().and_then(|h| h.peel_to_tree().ok());
+
+    let mut diff_opts = DiffOptions::new();
+    diff_opts
+        .pathspec(&file_path)
+        .disable_pathspec_match(true)
+        .include_untracked(true)
+        .show_untracked_content(true);
+    let diff = repo.diff_tree_to_workdir_with_index(head_tree.as_ref(), Some(&mut diff_opts))?;
+
+    Ok(diff_files(&diff)?.into_iter().next())
+}
+
+/// Undo a commit by creating a new commit that reverses its changes
+///
+/// On conflicts nothing is committed; the conflicts are left to resolve like
+/// those of a pull.
+#[tauri::command]
+pub fn git_revert(vault_path: String, commit_id: String) -> Result<PullResult, GitError> {
+    let path = Path::new(&vault_path);
+    let _pause = WatchPause::new(path);
+    let repo = Repository::open(path).map_err(|_| GitError::NotARepository)?;
+
+    if repo.state() != git2::RepositoryState::Clean {
+        return Err(GitError::Generic("A merge or rebase is in progress".to_string()));
+    }
+
+    let commit = repo.find_commit(git2::Oid::from_str(&commit_id)?)?;
+    let mut revert_opts = git2::RevertOptions::new();
+    // Merge commits are reverted relative to the branch they were merged into
+    if commit.parent_count() > 1 {
+        revert_opts.mainline(1);
+    }
+    repo.revert(&commit, Some(&mut revert_opts))?;
+
+    let short_id = &commit_id[..commit_id.len().min(7)];
+    let index = repo.index()?;
+    if index.has_conflicts() {
+        return Ok(PullResult {
+            success: false,
+            conflicts: conflict_paths(&index)?,
+            message: format!("Reverting {} caused conflicts. Please resolve conflicts and commit.", short_id),
+        });
+    }
+
+    let summary = commit.summary().unwrap_or("").to_string();
+    let message = format!("Revert \"{}\"\n\nThis reverts commit {}.\n", summary, commit.id());
+    commit_index(&repo, &message)?;
+    repo.cleanup_state()?;
+
+    Ok(PullResult {
+        success: true,
+        conflicts: Vec::new(),
+        message: format!("Reverted {}", short_id),
     })
 }
 
@@ -495,6 +744,7 @@ pub fn git_branches(vault_path: String) -> Result<Vec<BranchInfo>, GitError> {
 #[tauri::command]
 pub fn git_checkout_branch(vault_path: String, branch_name: String) -> Result<(), GitError> {
     let path = Path::new(&vault_path);
+    let _pause = WatchPause::new(path);
     let repo = Repository::open(path).map_err(|_| GitError::NotARepository)?;
 
     let branch = repo.find_branch(&branch_name, git2::BranchType::Local)?;
@@ -508,14 +758,291 @@ pub fn git_checkout_branch(vault_path: String, branch_name: String) -> Result<()
     Ok(())
 }
 
+/// Create a local branch, starting at HEAD unless `start_point` names a branch
+/// or commit
+#[tauri::command]
+pub fn git_branch_create(
+    vault_path: String,
+    branch_name: String,
+    start_point: Option<String>,
+    checkout: bool,
+) -> Result<BranchInfo, GitError> {
+    let path = Path::new(&vault_path);
+    let repo = Repository::open(path).map_err(|_| GitError::NotARepository)?;
+
+    if !git2::Branch::name_is_valid(&branch_name)? {
+        return Err(GitError::InvalidPath(format!("Invalid branch name: {}", branch_name)));
+    }
+
+    let target = match &start_point {
+        Some(start) => repo.revparse_single(start)?.peel_to_commit()?,
+        None => repo.head()?.peel_to_commit()?,
+    };
+    repo.branch(&branch_name, &target, false)?;
+
+    if checkout {
+        git_checkout_branch(vault_path, branch_name.clone())?;
+    }
+
+    Ok(BranchInfo {
+        name: branch_name,
+        is_current: checkout,
+        is_remote: false,
+    })
+}
+
+/// Delete a local branch
+///
+/// Branches with commits that are not merged into HEAD are only deleted when
+/// `force` is set.
+#[tauri::command]
+pub fn git_branch_delete(vault_path: String, branch_name: String, force: bool) -> Result<(), GitError> {
+    let path = Path::new(&vault_path);
+    let repo = Repository::open(path).map_err(|_| GitError::NotARepository)?;
+
+    let mut branch = repo.find_branch(&branch_name, git2::BranchType::Local)?;
+    if branch.is_head() {
+        return Err(GitError::Generic(format!("Cannot delete the current branch '{}'", branch_name)));
+    }
+
+    if !force {
+        let head = repo.head()?.peel_to_commit()?.id();
+        let tip = branch.get().peel_to_commit()?.id();
+        if tip != head && !repo.graph_descendant_of(head, tip)? {
+            return Err(GitError::Generic(format!("Branch '{}' is not fully merged", branch_name)));
+        }
+    }
+
+    branch.delete()?;
+    Ok(())
+}
+
+/// Merge a local branch into the current branch
+#[tauri::command]
+pub fn git_merge_branch(vault_path: String, branch_name: String) -> Result<PullResult, GitError> {
+    let path = Path::new(&vault_path);
+    let _pause = WatchPause::new(path);
+    let repo = Repository::open(path).map_err(|_| GitError::NotARepository)?;
+
+    let current = repo
+        .head()?
+        .shorthand()
+        .ok_or_else(|| GitError::InvalidPath("Cannot determine current branch".to_string()))?
+        .to_string();
+    if current == branch_name {
+        return Err(GitError::Generic("Cannot merge a branch into itself".to_string()));
+    }
+
+    let branch = repo.find_branch(&branch_name, git2::BranchType::Local)?;
+    let commit = repo.reference_to_annotated_commit(branch.get())?;
+    let message = format!("Merge branch '{}'", branch_name);
+    merge_into_head(&repo, &current, &commit, &message)
+}
+
+// ============================================================================
+// Credentials
+// ============================================================================
+
+/// Keychain key for a remote: its host, so one token serves every repository
+/// on the same server
+fn credential_host(url: &str) -> String {
+    let without_scheme = url.split_once("://").map(|(_, rest)| rest).unwrap_or(url);
+    let authority = without_scheme.split(['/', ':']).next().unwrap_or(without_scheme);
+    let host = authority.rsplit('@').next().unwrap_or(authority);
+    host.to_lowercase()
+}
+
+fn keychain_entry(url: &str) -> Result<Entry, GitError> {
+    Entry::new(KEYCHAIN_SERVICE, &credential_host(url)).map_err(|e| GitError::Credentials(e.to_string()))
+}
+
+/// Load stored HTTPS credentials for a remote URL
+fn load_credentials(url: &str) -> Result<Option<GitCredentials>, GitError> {
+    match keychain_entry(url)?.get_password() {
+        Ok(stored) => serde_json::from_str(&stored)
+            .map(Some)
+            .map_err(|e| GitError::Credentials(format!("Invalid stored credentials: {}", e))),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(GitError::Credentials(e.to_string())),
+    }
+}
+
+/// Remote callbacks that authenticate fetch and push
+///
+/// SSH remotes use the SSH agent. HTTPS remotes use credentials stored with
+/// `git_set_credentials`, then the git credential helper configured for the
+/// repository. Each method is tried once so a rejected credential fails the
+/// operation instead of retrying forever.
+pub(crate) fn remote_callbacks(repo: &Repository) -> RemoteCallbacks<'static> {
+    credential_callbacks(repo.config().ok(), None)
+}
+
+/// Credential callbacks with optional credentials to try before the keychain
+fn credential_callbacks(config: Option<git2::Config>, explicit: Option<GitCredentials>) -> RemoteCallbacks<'static> {
+    let mut tried_explicit = false;
+    let mut tried_agent = false;
+    let mut tried_stored = false;
+    let mut tried_helper = false;
+    let mut tried_default = false;
+
+    let mut callbacks = RemoteCallbacks::new();
+    callbacks.credentials(move |url, username_from_url, allowed_types| {
+        if allowed_types.contains(CredentialType::SSH_KEY) && !tried_agent {
+            tried_agent = true;
+            return Cred::ssh_key_from_agent(username_from_url.unwrap_or("git"));
+        }
+
+        if allowed_types.contains(CredentialType::USER_PASS_PLAINTEXT) {
+            if !tried_explicit {
+                tried_explicit = true;
+                if let Some(credentials) = &explicit {
+                    return Cred::userpass_plaintext(&credentials.username, &credentials.token);
+                }
+            }
+            if !tried_stored {
+                tried_stored = true;
+                if let Ok(Some(credentials)) = load_credentials(url) {
+                    return Cred::userpass_plaintext(&credentials.username, &credentials.token);
+                }
+            }
+            if !tried_helper {
+                tried_helper = true;
+                if let Some(config) = &config {
+                    if let Ok(cred) = Cred::credential_helper(config, url, username_from_url) {
+                        return Ok(cred);
+                    }
+                }
+            }
+        }
+
+        if allowed_types.contains(CredentialType::DEFAULT) && !tried_default {
+            tried_default = true;
+            return Cred::default();
+        }
+
+        Err(git2::Error::from_str(&format!(
+            "No valid credentials for {}; set a token for {} or configure a credential helper",
+            url,
+            credential_host(url)
+        )))
+    });
+    callbacks
+}
+
+fn store_credentials(remote_url: &str, credentials: &GitCredentials) -> Result<(), GitError> {
+    if credentials.username.trim().is_empty() || credentials.token.is_empty() {
+        return Err(GitError::Credentials("Username and token are required".to_string()));
+    }
+    let stored = serde_json::to_string(credentials).map_err(|e| GitError::Credentials(e.to_string()))?;
+    keychain_entry(remote_url)?
+        .set_password(&stored)
+        .map_err(|e| GitError::Credentials(e.to_string()))
+}
+
+/// Store a username and token for HTTPS remotes on the same host
+#[tauri::command]
+pub fn git_set_credentials(remote_url: String, username: String, token: String) -> Result<(), GitError> {
+    store_credentials(&remote_url, &GitCredentials { username, token })
+}
+
+/// Remove stored credentials for a remote's host
+#[tauri::command]
+pub fn git_delete_credentials(remote_url: String) -> Result<(), GitError> {
+    match keychain_entry(&remote_url)?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(GitError::Credentials(e.to_string())),
+    }
+}
+
+/// Check whether credentials are stored for a remote's host
+#[tauri::command]
+pub fn git_has_credentials(remote_url: String) -> Result<bool, GitError> {
+    Ok(load_credentials(&remote_url)?.is_some())
+}
+
+/// Clone a remote repository into `target_dir` to open it as a vault
+///
+/// Progress is emitted as `git-clone-progress` events. Credentials passed in
+/// `auth` are saved to the keychain once the clone succeeds, so later pulls
+/// and pushes reuse them.
+#[tauri::command]
+pub async fn git_clone(
+    app_handle: AppHandle,
+    url: String,
+    target_dir: String,
+    auth: Option<GitCredentials>,
+) -> Result<String, GitError> {
+    let target = PathBuf::from(&target_dir);
+    let occupied = target
+        .read_dir()
+        .map(|mut entries| entries.next().is_some())
+        .unwrap_or(false);
+    if occupied {
+        return Err(GitError::InvalidPath(format!("{} is not empty", target_dir)));
+    }
+
+    let url_for_progress = url.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut callbacks = credential_callbacks(git2::Config::open_default().ok(), auth.clone());
+        let mut last_percent = None;
+        callbacks.transfer_progress(move |progress| {
+            let total = progress.total_objects() + progress.total_deltas();
+            let done = progress.received_objects() + progress.indexed_deltas();
+            let percent = (done * 100).checked_div(total).unwrap_or(0);
+            // One event per percent is plenty for a progress bar
+            if last_percent != Some(percent) {
+                last_percent = Some(percent);
+                let _ = app_handle.emit(
+                    "git-clone-progress",
+                    CloneProgress {
+                        url: url_for_progress.clone(),
+                        received_objects: progress.received_objects(),
+                        total_objects: progress.total_objects(),
+                        indexed_deltas: progress.indexed_deltas(),
+                        total_deltas: progress.total_deltas(),
+                        received_bytes: progress.received_bytes(),
+                        percent: percent as u32,
+                    },
+                );
+            }
+            true
+        });
+
+        let mut fetch_options = git2::FetchOptions::new();
+        fetch_options.remote_callbacks(callbacks);
+        git2::build::RepoBuilder::new()
+            .fetch_options(fetch_options)
+            .clone(&url, &target)?;
+
+        if let Some(auth) = &auth {
+            store_credentials(&url, auth)?;
+        }
+        Ok(target.to_string_lossy().to_string())
+    })
+    .await
+    .map_err(|e| GitError::Generic(e.to_string()))?
+}
+
 /// Pull changes from remote origin
 #[tauri::command]
 pub fn git_pull(vault_path: String) -> Result<PullResult, GitError> {
-    let path = Path::new(&vault_path);
+    let _pause = WatchPause::new(Path::new(&vault_path));
+    pull_remote(Path::new(&vault_path), "origin")
+}
+
+/// Fetch a remote and merge it into the current branch
+pub(crate) fn pull_remote(path: &Path, remote_name: &str) -> Result<PullResult, GitError> {
+    pull_remote_with(path, remote_name, |_| {})
+}
+
+/// `pull_remote`, reporting when fetching and merging start
+pub(crate) fn pull_remote_with<F>(path: &Path, remote_name: &str, mut on_stage: F) -> Result<PullResult, GitError>
+where
+    F: FnMut(SyncStage),
+{
     let repo = Repository::open(path).map_err(|_| GitError::NotARepository)?;
 
-    // Find remote "origin"
-    let mut remote = repo.find_remote("origin")?;
+    let mut remote = repo.find_remote(remote_name)?;
 
     // Get current branch name
     let head = repo.head()?;
@@ -525,15 +1052,58 @@ pub fn git_pull(vault_path: String) -> Result<PullResult, GitError> {
         .to_string();
 
     // Fetch from remote
+    on_stage(SyncStage::Fetching);
     let fetch_refspecs: &[&str] = &[];
-    remote.fetch(fetch_refspecs, None, None)?;
-
-    // Get FETCH_HEAD reference
-    let fetch_head = repo.find_reference("FETCH_HEAD")?;
+    let mut fetch_options = git2::FetchOptions::new();
+    fetch_options.remote_callbacks(remote_callbacks(&repo));
+    remote.fetch(fetch_refspecs, Some(&mut fetch_options), None)?;
+
+    on_stage(SyncStage::Merging);
+    // Prefer the remote-tracking branch; FETCH_HEAD may name another branch
+    let tracking = format!("refs/remotes/{}/{}", remote_name, branch_name);
+    let Ok(fetch_head) = repo
+        .find_reference(&tracking)
+        .or_else(|_| repo.find_reference("FETCH_HEAD"))
+    else {
+        // The branch does not exist on the remote yet
+        return Ok(PullResult {
+            success: true,
+            conflicts: Vec::new(),
+            message: format!("Nothing to pull from {}/{}", remote_name, branch_name),
+        });
+    };
     let fetch_commit = repo.reference_to_annotated_commit(&fetch_head)?;
 
+    let message = format!("Merge remote-tracking branch '{}/{}'", remote_name, branch_name);
+    merge_into_head(&repo, &branch_name, &fetch_commit, &message)
+}
+
+/// Paths of all files with conflicts in the index
+fn conflict_paths(index: &git2::Index) -> Result<Vec<String>, GitError> {
+    let mut conflicts = Vec::new();
+    for conflict in index.conflicts()?.flatten() {
+        for entry in [conflict.ancestor, conflict.our, conflict.their].into_iter().flatten() {
+            let path = String::from_utf8_lossy(&entry.path).to_string();
+            if !conflicts.contains(&path) {
+                conflicts.push(path);
+            }
+        }
+    }
+    Ok(conflicts)
+}
+
+/// Merge a commit into the current branch
+///
+/// Fast-forwards when possible, otherwise merges and commits with `message`.
+/// Conflicts are left in the index for the user to resolve.
+fn merge_into_head(
+    repo: &Repository,
+    branch_name: &str,
+    fetch_commit: &git2::AnnotatedCommit,
+    message: &str,
+) -> Result<PullResult, GitError> {
     // Perform merge analysis
-    let (analysis, _preference) = repo.merge_analysis(&[&fetch_commit])?;
+    let (analysis, _preference) = repo.merge_analysis(&[fetch_commit])?;
 
     if analysis.is_up_to_date() {
         return Ok(PullResult {
@@ -564,36 +1134,13 @@ pub fn git_pull(vault_path: String) -> Result<PullResult, GitError> {
 
     // Normal merge required
     let fetch_commit_obj = repo.find_commit(fetch_commit.id())?;
-    repo.merge(&[&fetch_commit], None, None)?;
+    repo.merge(&[fetch_commit], None, None)?;
 
     // Check for conflicts
     let mut index = repo.index()?;
 
     if index.has_conflicts() {
-        // Collect conflicted file paths
-        let mut conflicts = Vec::new();
-        for conflict in index.conflicts()? {
-            if let Ok(conflict) = conflict {
-                if let Some(ancestor) = conflict.ancestor {
-                    let path = String::from_utf8_lossy(&ancestor.path).to_string();
-                    if !conflicts.contains(&path) {
-                        conflicts.push(path);
-                    }
-                }
-                if let Some(our) = conflict.our {
-                    let path = String::from_utf8_lossy(&our.path).to_string();
-                    if !conflicts.contains(&path) {
-                        conflicts.push(path);
-                    }
-                }
-                if let Some(their) = conflict.their {
-                    let path = String::from_utf8_lossy(&their.path).to_string();
-                    if !conflicts.contains(&path) {
-                        conflicts.push(path);
-                    }
-                }
-            }
-        }
+        let conflicts = conflict_paths(&index)?;
 
         return Ok(PullResult {
             success: false,
@@ -616,12 +1163,11 @@ pub fn git_pull(vault_path: String) -> Result<PullResult, GitError> {
     let head_commit = repo.head()?.peel_to_commit()?;
 
     // Create merge commit with two parents
-    let message = format!("Merge remote-tracking branch 'origin/{}'", branch_name);
     repo.commit(
         Some("HEAD"),
         &sig,
         &sig,
-        &message,
+        message,
         &tree,
         &[&head_commit, &fetch_commit_obj],
     )?;
@@ -639,31 +1185,30 @@ pub fn git_pull(vault_path: String) -> Result<PullResult, GitError> {
 /// Push changes to remote origin
 #[tauri::command]
 pub fn git_push(path: &str) -> Result<String, GitError> {
+    push_remote(Path::new(path), "origin")
+}
+
+/// Push the current branch to a remote
+pub(crate) fn push_remote(path: &Path, remote_name: &str) -> Result<String, GitError> {
     let repo = Repository::open(path).map_err(|e| GitError::OpenRepo(e.message().to_string()))?;
 
-    let mut remote = repo.find_remote("origin")
-        .map_err(|e| GitError::Generic(format!("No remote 'origin': {}", e.message())))?;
+    let mut remote = repo.find_remote(remote_name)
+        .map_err(|e| GitError::Generic(format!("No remote '{}': {}", remote_name, e.message())))?;
 
     let head = repo.head()
         .map_err(|e| GitError::Generic(e.message().to_string()))?;
 
     let branch_name = head.shorthand().unwrap_or("master");
 
-    // Push requires callbacks for authentication - use default credentials
-    let mut callbacks = git2::RemoteCallbacks::new();
-    callbacks.credentials(|_url, username_from_url, _allowed_types| {
-        git2::Cred::ssh_key_from_agent(username_from_url.unwrap_or("git"))
-    });
-
     let mut push_options = git2::PushOptions::new();
-    push_options.remote_callbacks(callbacks);
+    push_options.remote_callbacks(remote_callbacks(&repo));
 
     remote.push(
         &[&format!("refs/heads/{}:refs/heads/{}", branch_name, branch_name)],
         Some(&mut push_options)
     ).map_err(|e| GitError::Generic(format!("Push failed: {}", e.message())))?;
 
-    Ok(format!("Pushed to origin/{}", branch_name))
+    Ok(format!("Pushed to {}/{}", remote_name, branch_name))
 }
 
 /// List files with merge conflicts
@@ -684,6 +1229,61 @@ pub fn git_conflicted_files(path: &str) -> Result<Vec<String>, GitError> {
     Ok(conflicts)
 }
 
+/// Ancestor, ours and theirs contents of a conflicted file, for a merge editor
+#[tauri::command]
+pub fn git_conflict_versions(path: &str, file_path: &str) -> Result<ConflictVersions, GitError> {
+    let repo = Repository::open(path).map_err(|e| GitError::OpenRepo(e.message().to_string()))?;
+    let index = repo.index()?;
+
+    let conflict = index
+        .conflicts()?
+        .filter_map(|c| c.ok())
+        .find(|c| {
+            [&c.our, &c.their, &c.ancestor]
+                .into_iter()
+                .flatten()
+                .any(|e| e.path == file_path.as_bytes())
+        })
+        .ok_or_else(|| GitError::Generic(format!("{} has no conflict", file_path)))?;
+
+    let mut binary = false;
+    let mut content = |entry: &Option<git2::IndexEntry>| -> Result<Option<String>, GitError> {
+        let Some(entry) = entry else {
+            return Ok(None);
+        };
+        let blob = repo.find_blob(entry.id)?;
+        if blob.is_binary() {
+            binary = true;
+            return Ok(None);
+        }
+        Ok(Some(String::from_utf8_lossy(blob.content()).to_string()))
+    };
+    let ancestor = content(&conflict.ancestor)?;
+    let ours = content(&conflict.our)?;
+    let theirs = content(&conflict.their)?;
+
+    // The merge leaves the file with conflict markers in the working tree;
+    // build them when one side deleted it
+    let workdir_file = repo.workdir().map(|w| w.join(file_path));
+    let merged = match workdir_file.and_then(|f| std::fs::read(f).ok()) {
+        Some(bytes) => String::from_utf8_lossy(&bytes).to_string(),
+        None => format!(
+            "<<<<<<< ours\n{}=======\n{}>>>>>>> theirs\n",
+            ours.as_deref().unwrap_or(""),
+            theirs.as_deref().unwrap_or("")
+        ),
+    };
+
+    Ok(ConflictVersions {
+        path: file_path.to_string(),
+        ancestor,
+        ours,
+        theirs,
+        merged,
+        binary,
+    })
+}
+
 /// Resolve conflict by accepting our version
 #[tauri::command]
 pub fn git_resolve_ours(path: &str, file_path: &str) -> Result<(), GitError> {
@@ -904,3 +1504,138 @@ pub fn git_continue_rebase(path: &str) -> Result<PullResult, GitError> {
         message: "Rebase completed".to_string(),
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn repo_with_commit() -> (tempfile::TempDir, String) {
+        let dir = tempfile::tempdir().unwrap();
+        Repository::init(dir.path()).unwrap();
+        std::fs::write(dir.path().join("Note.md"), "one\n").unwrap();
+        commit_all(dir.path(), "Initial commit").unwrap();
+        let path = dir.path().to_string_lossy().to_string();
+        (dir, path)
+    }
+
+    #[test]
+    fn merges_branches_and_reports_conflicts() {
+        let (dir, path) = repo_with_commit();
+        let main = Repository::open(dir.path()).unwrap().head().unwrap().shorthand().unwrap().to_string();
+
+        git_branch_create(path.clone(), "feature".to_string(), None, true).unwrap();
+        std::fs::write(dir.path().join("Note.md"), "feature\n").unwrap();
+        commit_all(dir.path(), "Feature change").unwrap();
+
+        git_checkout_branch(path.clone(), main.clone()).unwrap();
+        let result = git_merge_branch(path.clone(), "feature".to_string()).unwrap();
+        assert!(result.success);
+        assert_eq!(std::fs::read_to_string(dir.path().join("Note.md")).unwrap(), "feature\n");
+        git_branch_delete(path.clone(), "feature".to_string(), false).unwrap();
+
+        git_branch_create(path.clone(), "other".to_string(), None, true).unwrap();
+        std::fs::write(dir.path().join("Note.md"), "other\n").unwrap();
+        commit_all(dir.path(), "Other change").unwrap();
+        git_checkout_branch(path.clone(), main).unwrap();
+        std::fs::write(dir.path().join("Note.md"), "main\n").unwrap();
+        commit_all(dir.path(), "Main change").unwrap();
+
+        assert!(git_branch_delete(path.clone(), "other".to_string(), false).is_err());
+        let result = git_merge_branch(path.clone(), "other".to_string()).unwrap();
+        assert!(!result.success);
+        assert_eq!(result.conflicts, vec!["Note.md"]);
+
+        let versions = git_conflict_versions(&path, "Note.md").unwrap();
+        assert_eq!(versions.ancestor.as_deref(), Some("feature\n"));
+        assert_eq!(versions.ours.as_deref(), Some("main\n"));
+        assert_eq!(versions.theirs.as_deref(), Some("other\n"));
+        assert!(versions.merged.contains("<<<<<<<"));
+    }
+
+    #[test]
+    fn diffs_uncommitted_changes() {
+        let (dir, path) = repo_with_commit();
+        assert!(git_diff_workdir(path.clone(), "Note.md".to_string()).unwrap().is_none());
+
+        std::fs::write(dir.path().join("Note.md"), "one\ntwo\n").unwrap();
+        let file = git_diff_workdir(path.clone(), "Note.md".to_string()).unwrap().unwrap();
+        assert_eq!(file.status, "modified");
+        let added: Vec<&str> = file.hunks[0]
+            .lines
+            .iter()
+            .filter(|l| l.line_type == "add")
+            .map(|l| l.content.as_str())
+            .collect();
+        assert_eq!(added, vec!["two\n"]);
+
+        std::fs::write(dir.path().join("New.md"), "new\n").unwrap();
+        let file = git_diff_workdir(path, "New.md".to_string()).unwrap().unwrap();
+        assert_eq!(file.status, "added");
+    }
+
+    #[test]
+    fn counts_commits_ahead_of_upstream() {
+        let (dir, path) = repo_with_commit();
+        let repo = Repository::open(dir.path()).unwrap();
+        let branch = repo.head().unwrap().shorthand().unwrap().to_string();
+        let head = repo.head().unwrap().target().unwrap();
+        repo.reference(&format!("refs/remotes/origin/{}", branch), head, true, "test").unwrap();
+
+        std::fs::write(dir.path().join("Note.md"), "two\n").unwrap();
+        commit_all(dir.path(), "Second").unwrap();
+
+        let status = git_status(path).unwrap();
+        assert_eq!(status.upstream, Some(format!("origin/{}", branch)));
+        assert_eq!((status.ahead, status.behind), (1, 0));
+    }
+
+    #[test]
+    fn filters_and_pages_the_log() {
+        let (dir, path) = repo_with_commit();
+        std::fs::create_dir(dir.path().join("Work")).unwrap();
+        for i in 1..=3 {
+            std::fs::write(dir.path().join("Work/Plan.md"), format!("{}\n", i)).unwrap();
+            commit_all(dir.path(), &format!("Plan update {}", i)).unwrap();
+        }
+        std::fs::write(dir.path().join("Note.md"), "changed\n").unwrap();
+        commit_all(dir.path(), "Note update").unwrap();
+
+        let by_path = LogFilter {
+            path: Some("Work".to_string()),
+            ..Default::default()
+        };
+        let page = git_log(path.clone(), Some(2), Some(1), Some(by_path)).unwrap();
+        let messages: Vec<&str> = page.iter().map(|c| c.message.as_str()).collect();
+        assert_eq!(messages.len(), 2);
+        assert!(messages.iter().all(|m| m.starts_with("Plan update")));
+
+        let by_message = LogFilter {
+            message: Some("note".to_string()),
+            ..Default::default()
+        };
+        let found = git_log(path, None, None, Some(by_message)).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].message, "Note update");
+    }
+
+    #[test]
+    fn reverts_a_commit() {
+        let (dir, path) = repo_with_commit();
+        std::fs::write(dir.path().join("Note.md"), "bad bulk change\n").unwrap();
+        let bad = commit_all(dir.path(), "Bulk change").unwrap();
+
+        let result = git_revert(path.clone(), bad).unwrap();
+        assert!(result.success);
+        assert_eq!(std::fs::read_to_string(dir.path().join("Note.md")).unwrap(), "one\n");
+        let log = git_log(path, Some(1), None, None).unwrap();
+        assert!(log[0].message.starts_with("Revert \"Bulk change\""));
+    }
+
+    #[test]
+    fn credential_host_from_remote_urls() {
+        assert_eq!(credential_host("https://github.com/user/notes.git"), "github.com");
+        assert_eq!(credential_host("https://token@GitLab.com/group/notes"), "gitlab.com");
+        assert_eq!(credential_host("git@github.com:user/notes.git"), "github.com");
+        assert_eq!(credential_host("ssh://git@example.org/notes.git"), "example.org");
+    }
+}
@@ -0,0 +1,172 @@
+//! Git history of notebooks.
+//!
+//! A notebook is a folder of block files plus an index, so its history is
+//! made of every commit touching anything under the folder. Older versions
+//! are rebuilt from the index and block files stored in a commit.
+
+use git2::{ObjectType, Repository, Tree};
+use std::path::Path;
+
+use super::commands::commit_matches;
+use super::signing::verify_commit;
+use super::types::{CommitInfo, FileHistory, GitError, LogFilter};
+use crate::fs::migrations::migrate_index;
+use crate::fs::{is_encrypted, Notebook, NotebookBlockWithContent, NotebookIndex};
+
+/// Text of the blob at `path` in `tree`
+fn blob_text(repo: &Repository, tree: &Tree, path: &Path) -> Result<String, GitError> {
+    let entry = tree.get_path(path)?;
+    let blob = repo.find_blob(entry.id())?;
+    Ok(String::from_utf8_lossy(blob.content()).to_string())
+}
+
+/// Commits changing any file of a notebook, newest first
+///
+/// Commits from before a note was converted into a notebook at the same
+/// path are included, since they change the same path.
+#[tauri::command]
+pub fn git_notebook_history(
+    vault_path: String,
+    notebook_path: String,
+    limit: Option<u32>,
+) -> Result<FileHistory, GitError> {
+    let repo = Repository::open(Path::new(&vault_path)).map_err(|_| GitError::NotARepository)?;
+
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push_head()?;
+    revwalk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::TIME)?;
+
+    let limit = limit.unwrap_or(20) as usize;
+    let filter = LogFilter {
+        path: Some(notebook_path.trim_end_matches('/').to_string()),
+        ..Default::default()
+    };
+    let mut commits = Vec::new();
+
+    for oid in revwalk {
+        if commits.len() >= limit {
+            break;
+        }
+        let oid = oid?;
+        let commit = repo.find_commit(oid)?;
+        if !commit_matches(&repo, &commit, &filter)? {
+            continue;
+        }
+        commits.push(CommitInfo {
+            id: oid.to_string(),
+            message: commit.message().unwrap_or("").to_string(),
+            author: commit.author().name().unwrap_or("Unknown").to_string(),
+            timestamp: commit.time().seconds(),
+            signature: verify_commit(&repo, oid),
+        });
+    }
+
+    Ok(FileHistory {
+        path: notebook_path,
+        commits,
+    })
+}
+
+/// A notebook as it was at a commit
+///
+/// Encrypted blocks keep their armored content and are marked encrypted; a
+/// notebook whose index was encrypted can't be rebuilt. If the path was
+/// still a note at that commit, the note comes back as a single markdown
+/// block.
+#[tauri::command]
+pub fn git_show_notebook(vault_path: String, notebook_path: String, commit_id: String) -> Result<Notebook, GitError> {
+    let repo = Repository::open(Path::new(&vault_path)).map_err(|_| GitError::NotARepository)?;
+    let commit = repo.find_commit(git2::Oid::from_str(&commit_id)?)?;
+    let tree = commit.tree()?;
+
+    let rel = Path::new(notebook_path.trim_end_matches('/'));
+    let name = rel
+        .file_name()
+        .map(|n| n.to_string_lossy().trim_end_matches(".md").to_string())
+        .unwrap_or_default();
+    let path = Path::new(&vault_path).join(rel);
+    let entry = tree.get_path(rel)?;
+
+    if entry.kind() == Some(ObjectType::Blob) {
+        let content = blob_text(&repo, &tree, rel)?;
+        let encrypted = is_encrypted(&content).then_some(true);
+        let block = NotebookBlockWithContent {
+            id: "note".to_string(),
+            content,
+            encrypted,
+            ..Default::default()
+        };
+        return Ok(Notebook {
+            path,
+            name,
+            blocks: vec![block],
+        });
+    }
+
+    let index_text = blob_text(&repo, &tree, &rel.join(".index.json"))?;
+    if is_encrypted(&index_text) {
+        return Err(GitError::Generic("The notebook index is encrypted at this commit".to_string()));
+    }
+    let mut index_value: serde_json::Value =
+        serde_json::from_str(&index_text).map_err(|e| GitError::Generic(format!("Invalid index.json: {}", e)))?;
+    migrate_index(&mut index_value).map_err(|e| GitError::Generic(e.to_string()))?;
+    let index: NotebookIndex =
+        serde_json::from_value(index_value).map_err(|e| GitError::Generic(format!("Invalid index.json: {}", e)))?;
+
+    let blocks = index
+        .blocks
+        .iter()
+        .map(|block| {
+            // Blocks whose file was not committed show up empty
+            let content = blob_text(&repo, &tree, &rel.join(&block.file)).unwrap_or_default();
+            let mut with_content = NotebookBlockWithContent::from_block(block, content);
+            if index.encrypted || is_encrypted(&with_content.content) {
+                with_content.encrypted = Some(true);
+            }
+            with_content
+        })
+        .collect();
+
+    Ok(Notebook { path, name, blocks })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::commands::commit_all;
+
+    #[test]
+    fn shows_notebook_history() {
+        let dir = tempfile::tempdir().unwrap();
+        Repository::init(dir.path()).unwrap();
+        let vault = dir.path().to_string_lossy().to_string();
+        std::fs::write(dir.path().join("Run.md"), "# Run\n").unwrap();
+        let first = commit_all(dir.path(), "Add note").unwrap();
+
+        let notebook = dir.path().join("Run.md");
+        std::fs::remove_file(&notebook).unwrap();
+        std::fs::create_dir(&notebook).unwrap();
+        std::fs::write(notebook.join("a.md"), "# Run\n").unwrap();
+        std::fs::write(notebook.join("b.py"), "print(1)\n").unwrap();
+        let index = r#"{"version":1,"blocks":[{"id":"a","type":"markdown","file":"a.md"},
+            {"id":"b","type":"code","file":"b.py","language":"python"}]}"#;
+        std::fs::write(notebook.join(".index.json"), index).unwrap();
+        let second = commit_all(dir.path(), "Convert to notebook").unwrap();
+        std::fs::write(notebook.join("b.py"), "print(2)\n").unwrap();
+        commit_all(dir.path(), "Edit block").unwrap();
+        std::fs::write(dir.path().join("Other.md"), "other\n").unwrap();
+        commit_all(dir.path(), "Unrelated").unwrap();
+
+        let history = git_notebook_history(vault.clone(), "Run.md".to_string(), None).unwrap();
+        let messages: Vec<&str> = history.commits.iter().map(|c| c.message.trim()).collect();
+        assert_eq!(messages, vec!["Edit block", "Convert to notebook", "Add note"]);
+
+        let shown = git_show_notebook(vault.clone(), "Run.md".to_string(), second).unwrap();
+        assert_eq!(shown.name, "Run");
+        assert_eq!(shown.blocks.len(), 2);
+        assert_eq!(shown.blocks[1].content, "print(1)\n");
+
+        let note = git_show_notebook(vault, "Run.md".to_string(), first).unwrap();
+        assert_eq!(note.blocks[0].content, "# Run\n");
+    }
+}
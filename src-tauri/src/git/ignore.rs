@@ -0,0 +1,121 @@
+//! Editing the vault's `.gitignore`.
+
+use git2::Repository;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::types::GitError;
+
+fn gitignore_path(vault_path: &str) -> PathBuf {
+    Path::new(vault_path).join(".gitignore")
+}
+
+fn read_gitignore(path: &Path) -> Result<String, GitError> {
+    match fs::read_to_string(path) {
+        Ok(content) => Ok(content),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(String::new()),
+        Err(e) => Err(GitError::Generic(format!("Failed to read .gitignore: {}", e))),
+    }
+}
+
+fn write_gitignore(path: &Path, content: &str) -> Result<(), GitError> {
+    fs::write(path, content).map_err(|e| GitError::Generic(format!("Failed to write .gitignore: {}", e)))
+}
+
+/// Active rules of a `.gitignore`, without comments and blank lines
+fn rules(content: &str) -> impl Iterator<Item = &str> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+}
+
+fn validate_rule(rule: &str) -> Result<&str, GitError> {
+    let rule = rule.trim();
+    if rule.is_empty() || rule.starts_with('#') || rule.contains(['\n', '\r']) {
+        return Err(GitError::InvalidPath(format!("Invalid ignore rule: {:?}", rule)));
+    }
+    Ok(rule)
+}
+
+/// List the rules of the vault's `.gitignore`
+#[tauri::command]
+pub fn get_gitignore_rules(vault_path: String) -> Result<Vec<String>, GitError> {
+    let content = read_gitignore(&gitignore_path(&vault_path))?;
+    Ok(rules(&content).map(str::to_string).collect())
+}
+
+/// Append a rule such as `Private/` to the vault's `.gitignore`
+///
+/// Returns `false` when the rule is already present.
+#[tauri::command]
+pub fn add_gitignore_rule(vault_path: String, rule: String) -> Result<bool, GitError> {
+    let rule = validate_rule(&rule)?;
+    let path = gitignore_path(&vault_path);
+    let mut content = read_gitignore(&path)?;
+
+    if rules(&content).any(|existing| existing == rule) {
+        return Ok(false);
+    }
+
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
+    }
+    content.push_str(rule);
+    content.push('\n');
+    write_gitignore(&path, &content)?;
+    Ok(true)
+}
+
+/// Remove a rule from the vault's `.gitignore`, keeping comments and other rules
+///
+/// Returns `false` when the rule was not present.
+#[tauri::command]
+pub fn remove_gitignore_rule(vault_path: String, rule: String) -> Result<bool, GitError> {
+    let rule = rule.trim();
+    let path = gitignore_path(&vault_path);
+    let content = read_gitignore(&path)?;
+
+    let kept: Vec<&str> = content.lines().filter(|line| line.trim() != rule).collect();
+    if kept.len() == content.lines().count() {
+        return Ok(false);
+    }
+
+    let mut updated = kept.join("\n");
+    if !updated.is_empty() {
+        updated.push('\n');
+    }
+    write_gitignore(&path, &updated)?;
+    Ok(true)
+}
+
+/// Check whether a path, absolute or relative to the vault, is ignored by git
+#[tauri::command]
+pub fn is_ignored(vault_path: String, path: String) -> Result<bool, GitError> {
+    let repo = Repository::open(&vault_path).map_err(|_| GitError::NotARepository)?;
+    let path = Path::new(&path);
+    let relative = path.strip_prefix(&vault_path).unwrap_or(path);
+    Ok(repo.is_path_ignored(relative)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn adds_and_removes_rules() {
+        let dir = tempfile::tempdir().unwrap();
+        Repository::init(dir.path()).unwrap();
+        let vault = dir.path().to_string_lossy().to_string();
+        fs::write(dir.path().join(".gitignore"), "# Local\n.DS_Store").unwrap();
+
+        assert!(add_gitignore_rule(vault.clone(), "Private/".to_string()).unwrap());
+        assert!(!add_gitignore_rule(vault.clone(), " Private/ ".to_string()).unwrap());
+        assert_eq!(get_gitignore_rules(vault.clone()).unwrap(), vec![".DS_Store", "Private/"]);
+        assert!(is_ignored(vault.clone(), "Private/Diary.md".to_string()).unwrap());
+
+        assert!(remove_gitignore_rule(vault.clone(), "Private/".to_string()).unwrap());
+        assert!(!is_ignored(vault.clone(), "Private/Diary.md".to_string()).unwrap());
+        assert_eq!(fs::read_to_string(dir.path().join(".gitignore")).unwrap(), "# Local\n.DS_Store\n");
+    }
+}
@@ -1,5 +1,17 @@
+pub mod autosync;
+pub mod changes;
 pub mod commands;
+pub mod history;
+pub mod ignore;
+pub mod signing;
+pub mod sync;
 pub mod types;
+pub mod word_diff;
 
+pub use autosync::spawn_autosync;
+pub use changes::*;
 pub use commands::*;
+pub use history::*;
+pub use ignore::*;
+pub use sync::*;
 pub use types::*;
@@ -0,0 +1,218 @@
+//! Commit signing and signature verification through `gpg` and `ssh-keygen`.
+//!
+//! Which key signs comes from the vault config, but the programs run are
+//! only taken from git config (`gpg.program`, `gpg.ssh.program`), never
+//! from the versioned vault, so cloning or pulling a vault can't make the
+//! app run a binary it names.
+
+use git2::{Oid, Repository};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use super::types::{GitError, SignatureStatus};
+use crate::fs::{expand_tilde, read_vault_config, CommitSigningSettings, SigningFormat};
+
+/// Signing settings of the vault a repository belongs to, when signing is on
+pub(crate) fn signing_settings(repo: &Repository) -> Option<CommitSigningSettings> {
+    let config = read_vault_config(repo.workdir()?).ok()?;
+    let signing = config.git.signing;
+    signing.enabled.then_some(signing)
+}
+
+/// Program handling signatures of a format, as set in git config
+fn program(repo: &Repository, format: SigningFormat) -> String {
+    let (key, default) = match format {
+        SigningFormat::Gpg => ("gpg.program", "gpg"),
+        SigningFormat::Ssh => ("gpg.ssh.program", "ssh-keygen"),
+    };
+    repo.config()
+        .and_then(|config| config.get_string(key))
+        .unwrap_or_else(|_| default.to_string())
+}
+
+/// Temporary file removed on drop
+struct TempFile(PathBuf);
+
+impl TempFile {
+    fn new(suffix: &str, contents: &[u8]) -> Result<Self, GitError> {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or(0);
+        let path = std::env::temp_dir().join(format!("notemaker-git-{}-{}{}", std::process::id(), nanos, suffix));
+        std::fs::write(&path, contents).map_err(|e| GitError::Generic(e.to_string()))?;
+        Ok(Self(path))
+    }
+
+    fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+/// Run a command with `input` on stdin, returning (success, stdout, stderr)
+fn run_with_input(command: &mut Command, input: &[u8]) -> Result<(bool, String, String), GitError> {
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| GitError::Generic(format!("Failed to run signing program: {}", e)))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(input)
+            .map_err(|e| GitError::Generic(e.to_string()))?;
+    }
+    let output = child
+        .wait_with_output()
+        .map_err(|e| GitError::Generic(e.to_string()))?;
+
+    Ok((
+        output.status.success(),
+        String::from_utf8_lossy(&output.stdout).to_string(),
+        String::from_utf8_lossy(&output.stderr).to_string(),
+    ))
+}
+
+/// Sign a commit buffer, returning the armored signature
+pub(crate) fn sign_commit_buffer(
+    repo: &Repository,
+    settings: &CommitSigningSettings,
+    buffer: &str,
+) -> Result<String, GitError> {
+    let key = settings
+        .key
+        .clone()
+        .or_else(|| repo.config().ok()?.get_string("user.signingkey").ok());
+
+    let mut command = Command::new(program(repo, settings.format));
+    match settings.format {
+        SigningFormat::Gpg => {
+            command.args(["--status-fd=2", "-bsa"]);
+            if let Some(key) = &key {
+                command.args(["-u", key]);
+            }
+        }
+        SigningFormat::Ssh => {
+            let key = key.ok_or_else(|| GitError::Generic("No SSH signing key configured".to_string()))?;
+            let key = expand_tilde(&key);
+            command.args(["-Y", "sign", "-n", "git", "-f"]).arg(key);
+        }
+    }
+
+    let (success, signature, stderr) = run_with_input(&mut command, buffer.as_bytes())?;
+    if !success || signature.trim().is_empty() {
+        return Err(GitError::Generic(format!("Signing failed: {}", stderr.trim())));
+    }
+    Ok(signature)
+}
+
+/// Check the signature of a commit
+pub(crate) fn verify_commit(repo: &Repository, oid: Oid) -> SignatureStatus {
+    let Ok((signature, signed_data)) = repo.extract_signature(&oid, None) else {
+        return SignatureStatus::Unsigned;
+    };
+    let Ok(signature_file) = TempFile::new(".sig", &signature) else {
+        return SignatureStatus::Unknown;
+    };
+
+    if signature.starts_with(b"-----BEGIN SSH SIGNATURE") {
+        let mut command = Command::new(program(repo, SigningFormat::Ssh));
+        command
+            .args(["-Y", "check-novalidate", "-n", "git", "-s"])
+            .arg(signature_file.path());
+        return match run_with_input(&mut command, &signed_data) {
+            Ok((true, _, _)) => SignatureStatus::Valid,
+            Ok((false, _, _)) => SignatureStatus::Invalid,
+            Err(_) => SignatureStatus::Unknown,
+        };
+    }
+
+    let mut command = Command::new(program(repo, SigningFormat::Gpg));
+    command
+        .args(["--status-fd=1", "--verify"])
+        .arg(signature_file.path())
+        .arg("-");
+    match run_with_input(&mut command, &signed_data) {
+        Ok((_, status, _)) if status.contains("[GNUPG:] GOODSIG") => SignatureStatus::Valid,
+        Ok((_, status, _)) if status.contains("[GNUPG:] BADSIG") => SignatureStatus::Invalid,
+        _ => SignatureStatus::Unknown,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::commit_all;
+
+    fn write_config(dir: &Path, yaml: &str) {
+        std::fs::create_dir_all(dir.join(".notemaker")).unwrap();
+        let config = format!("version: 1\nvault:\n  name: Notes\n  created: ''\n{}", yaml);
+        std::fs::write(dir.join(".notemaker/config.yaml"), config).unwrap();
+    }
+
+    #[test]
+    fn reads_signing_settings_from_vault_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        assert!(signing_settings(&repo).is_none());
+
+        write_config(dir.path(), "git:\n  signing:\n    enabled: false\n    format: ssh\n");
+        assert!(signing_settings(&repo).is_none());
+
+        write_config(dir.path(), "git:\n  signing:\n    enabled: true\n");
+        let gpg = signing_settings(&repo).unwrap();
+        assert_eq!((gpg.format, gpg.key.as_deref()), (SigningFormat::Gpg, None));
+
+        write_config(
+            dir.path(),
+            "git:\n  signing:\n    enabled: true\n    format: ssh\n    key: ~/.ssh/id.pub\n",
+        );
+        let ssh = signing_settings(&repo).unwrap();
+        assert_eq!((ssh.format, ssh.key.as_deref()), (SigningFormat::Ssh, Some("~/.ssh/id.pub")));
+
+        write_config(dir.path(), "git:\n  signing:\n    format: x509\n");
+        assert!(signing_settings(&repo).is_none());
+    }
+
+    #[test]
+    fn takes_programs_from_git_config_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        write_config(dir.path(), "git:\n  signing:\n    enabled: true\n    program: /tmp/evil\n");
+        assert!(signing_settings(&repo).is_some());
+        assert_eq!(program(&repo, SigningFormat::Ssh), "ssh-keygen");
+
+        let mut config = repo.config().unwrap();
+        config.set_str("gpg.program", "/opt/gpg2").unwrap();
+        config.set_str("gpg.ssh.program", "/opt/ssh-keygen").unwrap();
+        assert_eq!(program(&repo, SigningFormat::Gpg), "/opt/gpg2");
+        assert_eq!(program(&repo, SigningFormat::Ssh), "/opt/ssh-keygen");
+    }
+
+    #[test]
+    fn expands_home_in_key_paths() {
+        let home = std::env::var_os("HOME").map(PathBuf::from);
+        match home {
+            Some(home) => assert_eq!(expand_tilde("~/.ssh/id"), home.join(".ssh/id")),
+            None => assert_eq!(expand_tilde("~/.ssh/id"), PathBuf::from("~/.ssh/id")),
+        }
+        assert_eq!(expand_tilde("/keys/id"), PathBuf::from("/keys/id"));
+    }
+
+    #[test]
+    fn reports_unsigned_commits() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        std::fs::write(dir.path().join("Note.md"), "one\n").unwrap();
+        let oid = commit_all(dir.path(), "Initial commit").unwrap();
+        assert_eq!(verify_commit(&repo, Oid::from_str(&oid).unwrap()), SignatureStatus::Unsigned);
+    }
+}
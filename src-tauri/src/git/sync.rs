@@ -0,0 +1,155 @@
+//! Commit, pull and push in one step.
+
+use git2::{Repository, RepositoryState, StatusOptions};
+use serde_json::json;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter};
+
+use super::commands::{commit_all, pull_remote_with, push_remote};
+use super::types::{GitError, SyncProgress, SyncResult, SyncStage};
+use crate::fs::webhooks::dispatch_webhooks;
+use crate::fs::{read_vault_config, WebhookEvent};
+
+/// Commit message for an automatic commit of `files`
+fn auto_commit_message(files: &[String]) -> String {
+    let timestamp = chrono::Local::now().format("%Y-%m-%d %H:%M");
+    let mut message = match files {
+        [file] => format!("Auto-commit {}: update {}", timestamp, file),
+        _ => format!("Auto-commit {}: update {} files", timestamp, files.len()),
+    };
+    if files.len() > 1 {
+        message.push('\n');
+        for file in files {
+            message.push_str(&format!("\n- {}", file));
+        }
+    }
+    message
+}
+
+/// Files with uncommitted changes, including untracked ones
+fn changed_files(repo: &Repository) -> Result<Vec<String>, GitError> {
+    let mut opts = StatusOptions::new();
+    opts.include_untracked(true).recurse_untracked_dirs(true);
+    let statuses = repo.statuses(Some(&mut opts))?;
+
+    Ok(statuses
+        .iter()
+        .filter(|e| !e.status().is_ignored())
+        .filter_map(|e| e.path().map(str::to_string))
+        .collect())
+}
+
+/// Commit local changes (when `commit` is set), pull and push
+///
+/// Pull and push are skipped when `remote_name` does not exist. A pull that
+/// ends in conflicts stops before pushing; the conflicts are in the result.
+pub fn sync_vault<F>(vault: &Path, remote_name: &str, commit: bool, mut on_stage: F) -> Result<SyncResult, GitError>
+where
+    F: FnMut(SyncStage),
+{
+    let repo = Repository::open(vault).map_err(|_| GitError::NotARepository)?;
+    // Never commit over a merge or rebase the user is resolving
+    if repo.state() != RepositoryState::Clean {
+        return Err(GitError::Generic("A merge or rebase is in progress".to_string()));
+    }
+
+    let mut result = SyncResult::default();
+
+    if commit {
+        let files = changed_files(&repo)?;
+        if !files.is_empty() {
+            on_stage(SyncStage::Committing);
+            result.commit = Some(commit_all(vault, &auto_commit_message(&files))?);
+            result.files_committed = files.len() as u32;
+        }
+    }
+
+    if repo.find_remote(remote_name).is_err() {
+        result.message = format!("No remote '{}' configured", remote_name);
+        return Ok(result);
+    }
+
+    let pull = pull_remote_with(vault, remote_name, &mut on_stage)?;
+    if !pull.success {
+        result.message = pull.message.clone();
+        result.pull = Some(pull);
+        return Ok(result);
+    }
+    result.pull = Some(pull);
+
+    on_stage(SyncStage::Pushing);
+    result.message = push_remote(vault, remote_name)?;
+    result.pushed = true;
+
+    Ok(result)
+}
+
+/// Remote the vault syncs with, `origin` unless configured otherwise
+pub fn vault_remote(vault: &Path) -> String {
+    read_vault_config(vault)
+        .map(|config| config.git.remote)
+        .unwrap_or_else(|_| "origin".to_string())
+}
+
+/// Tell the vault's webhooks about a finished sync and the commit it made
+pub(crate) fn notify_sync(app: &AppHandle, vault: &Path, result: &SyncResult) {
+    if let Some(commit) = &result.commit {
+        let data = json!({ "commit": commit, "files": result.files_committed });
+        dispatch_webhooks(app, vault, WebhookEvent::CommitCreated, data);
+    }
+    dispatch_webhooks(app, vault, WebhookEvent::SyncCompleted, json!(result));
+}
+
+/// Commit local changes, pull and push the vault in one call
+///
+/// Each step is announced with a `git-sync-progress` event.
+#[tauri::command]
+pub async fn git_sync(app_handle: AppHandle, vault_path: String) -> Result<SyncResult, GitError> {
+    let vault = PathBuf::from(&vault_path);
+    let remote = vault_remote(&vault);
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let result = sync_vault(&vault, &remote, true, |stage| {
+            let _ = app_handle.emit(
+                "git-sync-progress",
+                SyncProgress {
+                    vault_path: vault_path.clone(),
+                    stage,
+                },
+            );
+        })?;
+        notify_sync(&app_handle, &vault, &result);
+        Ok::<_, GitError>(result)
+    })
+    .await
+    .map_err(|e| GitError::Generic(e.to_string()))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commits_and_pushes_to_remote() {
+        let dir = tempfile::tempdir().unwrap();
+        let remote = dir.path().join("remote.git");
+        Repository::init_bare(&remote).unwrap();
+        let vault = dir.path().join("vault");
+        let repo = Repository::init(&vault).unwrap();
+        repo.remote("origin", &remote.to_string_lossy()).unwrap();
+        std::fs::write(vault.join("Note.md"), "# Note\n").unwrap();
+
+        let mut stages = Vec::new();
+        let result = sync_vault(&vault, "origin", true, |stage| stages.push(stage)).unwrap();
+        assert_eq!(result.files_committed, 1);
+        assert!(result.pushed);
+        assert_eq!(
+            stages,
+            vec![SyncStage::Committing, SyncStage::Fetching, SyncStage::Merging, SyncStage::Pushing]
+        );
+
+        let head = repo.head().unwrap().peel_to_commit().unwrap().id();
+        let remote_head = Repository::open_bare(&remote).unwrap().head().unwrap().peel_to_commit().unwrap().id();
+        assert_eq!(head, remote_head);
+    }
+}
@@ -15,6 +15,8 @@ pub enum GitError {
     NoChanges,
     #[error("Failed to open repository: {0}")]
     OpenRepo(String),
+    #[error("Credential error: {0}")]
+    Credentials(String),
     #[error("{0}")]
     Generic(String),
 }
@@ -36,6 +38,12 @@ pub struct GitStatus {
     pub staged_count: u32,
     pub unstaged_count: u32,
     pub untracked_count: u32,
+    /// Upstream branch the counts below are relative to, e.g. `origin/main`
+    pub upstream: Option<String>,
+    /// Local commits not on the upstream
+    pub ahead: u32,
+    /// Upstream commits not merged locally, as of the last fetch
+    pub behind: u32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -51,6 +59,72 @@ pub struct CommitInfo {
     pub message: String,
     pub author: String,
     pub timestamp: i64,
+    #[serde(default)]
+    pub signature: SignatureStatus,
+}
+
+/// Verification result of a commit signature
+///
+/// `Valid` means the signature matches the commit; for SSH signatures the
+/// signer is not checked against an allowed signers list.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SignatureStatus {
+    #[default]
+    Unsigned,
+    Valid,
+    Invalid,
+    /// Signed, but the key or the verification tool is not available
+    Unknown,
+}
+
+/// Filters for `git_log`; all set filters must match
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct LogFilter {
+    /// Substring of the author name or email, case-insensitive
+    pub author: Option<String>,
+    /// Substring of the commit message, case-insensitive
+    pub message: Option<String>,
+    /// Only commits changing files under this path prefix
+    pub path: Option<String>,
+    /// Unix timestamps bounding the commit time, inclusive
+    pub since: Option<i64>,
+    pub until: Option<i64>,
+}
+
+/// What happened to a note in the recent-changes feed
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeType {
+    Created,
+    Modified,
+    Deleted,
+    Renamed,
+}
+
+/// Where a recent change was found
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeSource {
+    /// Modification time on disk, possibly not committed yet
+    Filesystem,
+    Git,
+}
+
+/// Latest change of a note, notebook or board
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecentChange {
+    /// Vault-relative path
+    pub path: String,
+    /// Previous path of a renamed note
+    pub old_path: Option<String>,
+    pub change: ChangeType,
+    /// Unix timestamp of the change
+    pub timestamp: i64,
+    pub source: ChangeSource,
+    /// Commit author, for changes from git
+    pub author: Option<String>,
+    pub commit: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -65,6 +139,17 @@ pub struct DiffLine {
     pub old_line_no: Option<u32>,
     pub new_line_no: Option<u32>,
     pub content: String,
+    /// Word-level segments for a changed line paired with its counterpart
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub segments: Option<Vec<DiffSegment>>,
+}
+
+/// Part of a changed line; `changed` marks words that differ from the
+/// paired line
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct DiffSegment {
+    pub text: String,
+    pub changed: bool,
 }
 
 #[derive(Debug, Serialize, Clone)]
@@ -102,3 +187,76 @@ pub struct PullResult {
     pub conflicts: Vec<String>,
     pub message: String,
 }
+
+/// The three sides of a conflicted file and its current merged text
+#[derive(Debug, Serialize, Clone)]
+pub struct ConflictVersions {
+    pub path: String,
+    /// Common ancestor; `None` when both sides added the file
+    pub ancestor: Option<String>,
+    /// Current branch; `None` when it deleted the file
+    pub ours: Option<String>,
+    /// Branch being merged; `None` when it deleted the file
+    pub theirs: Option<String>,
+    /// Working tree text with `<<<<<<<`/`>>>>>>>` conflict markers
+    pub merged: String,
+    /// One of the sides is binary and has no text versions
+    pub binary: bool,
+}
+
+/// Username/token pair used for HTTPS remotes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitCredentials {
+    pub username: String,
+    /// Personal access token or password
+    pub token: String,
+}
+
+/// Progress of `git_clone`, emitted as `git-clone-progress`
+#[derive(Debug, Clone, Serialize)]
+pub struct CloneProgress {
+    pub url: String,
+    pub received_objects: usize,
+    pub total_objects: usize,
+    pub indexed_deltas: usize,
+    pub total_deltas: usize,
+    pub received_bytes: usize,
+    pub percent: u32,
+}
+
+/// Step of a sync, emitted as `git-sync-progress`
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SyncStage {
+    Committing,
+    Fetching,
+    Merging,
+    Pushing,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncProgress {
+    pub vault_path: String,
+    pub stage: SyncStage,
+}
+
+/// Result of committing local changes and syncing with the remote
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct SyncResult {
+    /// Id of the commit created for local changes, if any
+    pub commit: Option<String>,
+    pub files_committed: u32,
+    /// `None` when the vault has no remote
+    pub pull: Option<PullResult>,
+    pub pushed: bool,
+    pub message: String,
+}
+
+/// Outcome of one auto-commit/auto-sync run, emitted as `git-autosync`
+#[derive(Debug, Clone, Serialize)]
+pub struct AutoSyncEvent {
+    pub vault_path: String,
+    #[serde(flatten)]
+    pub result: SyncResult,
+    pub error: Option<String>,
+}
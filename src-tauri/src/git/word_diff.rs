@@ -0,0 +1,99 @@
+//! Word-level highlighting for changed lines.
+//!
+//! Within a hunk, a run of deleted lines followed by a run of added lines is
+//! treated as a rewrite: the lines are paired in order and each pair is
+//! diffed word by word.
+
+use similar::{ChangeTag, TextDiff};
+
+use super::types::{DiffHunk, DiffLine, DiffSegment};
+
+/// Lines longer than this are left without segments
+const MAX_LINE_LEN: usize = 10_000;
+
+/// Append `text` to the segments, merging with the previous one when it has
+/// the same state
+fn push_segment(segments: &mut Vec<DiffSegment>, text: &str, changed: bool) {
+    match segments.last_mut() {
+        Some(last) if last.changed == changed => last.text.push_str(text),
+        _ => segments.push(DiffSegment {
+            text: text.to_string(),
+            changed,
+        }),
+    }
+}
+
+/// Word segments of an old and a new version of a line
+pub(crate) fn word_segments(old: &str, new: &str) -> (Vec<DiffSegment>, Vec<DiffSegment>) {
+    let diff = TextDiff::from_words(old, new);
+    let mut old_segments = Vec::new();
+    let mut new_segments = Vec::new();
+
+    for change in diff.iter_all_changes() {
+        let text = change.value();
+        match change.tag() {
+            ChangeTag::Equal => {
+                push_segment(&mut old_segments, text, false);
+                push_segment(&mut new_segments, text, false);
+            }
+            ChangeTag::Delete => push_segment(&mut old_segments, text, true),
+            ChangeTag::Insert => push_segment(&mut new_segments, text, true),
+        }
+    }
+
+    (old_segments, new_segments)
+}
+
+fn pair_lines(deleted: &mut [DiffLine], added: &mut [DiffLine]) {
+    for (old, new) in deleted.iter_mut().zip(added.iter_mut()) {
+        if old.content.len() > MAX_LINE_LEN || new.content.len() > MAX_LINE_LEN {
+            continue;
+        }
+        let (old_segments, new_segments) = word_segments(&old.content, &new.content);
+        old.segments = Some(old_segments);
+        new.segments = Some(new_segments);
+    }
+}
+
+/// Fill in word segments for the rewritten lines of a hunk
+pub(crate) fn add_word_segments(hunk: &mut DiffHunk) {
+    let lines = &mut hunk.lines;
+    let mut i = 0;
+
+    while i < lines.len() {
+        if lines[i].line_type != "delete" {
+            i += 1;
+            continue;
+        }
+        let deletes_start = i;
+        while i < lines.len() && lines[i].line_type == "delete" {
+            i += 1;
+        }
+        let adds_start = i;
+        while i < lines.len() && lines[i].line_type == "add" {
+            i += 1;
+        }
+
+        let (deleted, added) = lines[deletes_start..i].split_at_mut(adds_start - deletes_start);
+        pair_lines(deleted, added);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn highlights_changed_words() {
+        let (old, new) = word_segments("The quick brown fox\n", "The slow brown fox\n");
+        assert_eq!(
+            old,
+            vec![
+                DiffSegment { text: "The ".to_string(), changed: false },
+                DiffSegment { text: "quick".to_string(), changed: true },
+                DiffSegment { text: " brown fox\n".to_string(), changed: false },
+            ]
+        );
+        assert_eq!(new[1], DiffSegment { text: "slow".to_string(), changed: true });
+    }
+}
@@ -0,0 +1,23 @@
+//! Vault operations usable without the app, for `notemaker-cli`.
+//!
+//! The app's commands are thin wrappers around these functions: they add
+//! what only the app has, like the encryption session held in managed
+//! state, recent files, webhooks and progress events. Notes are read,
+//! written, created, renamed, moved and deleted here; git commits and syncs
+//! and encryption of single notes work the same way. Nothing here needs a
+//! window, managed state or an event loop; encrypted content is only read or
+//! written with an unlocked session.
+
+pub use crate::export::render_note_html;
+pub use crate::export::types::{ExportError, HtmlExport, HtmlExportOptions};
+pub use crate::fs::{
+    append_to_note, copy_entry, decrypt_note_file, delete_entry, encrypt_note_file, find_vault_root, load_note,
+    make_directory, move_directory, move_entry, new_note, read_vault_template, remove_directory, rename_entry,
+    save_note, search_vault, unlock_session_from_keychain, vault_note_path, EncryptionError, EncryptionSession,
+    FsError, NoteContent, SearchHit,
+};
+pub use crate::git::sync::{sync_vault, vault_remote};
+pub use crate::git::{
+    commit_all, commit_staged, git_log, git_status, CommitInfo, GitError, GitStatus, PullResult, SyncResult, SyncStage,
+};
+pub use crate::mcp::{serve_stdio, McpServer};
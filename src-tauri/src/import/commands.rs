@@ -0,0 +1,364 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::notion::{
+    extract_archive, kanban_status, parse_due, rewrite_links, strip_notion_id, strip_title_heading, table_cell,
+    Database, NotionTree,
+};
+use super::obsidian::{convert_links, is_markdown, merge_frontmatter_tags, SourceIndex};
+use super::types::{ImportError, ImportReport, ObsidianImportOptions};
+use crate::fs::frontmatter::split_frontmatter;
+use crate::fs::links::relative_path;
+use crate::fs::tags::collect_inline_tags;
+use crate::fs::{
+    generate_task_id, get_task_file_path, read_vault_config, write_atomic, write_kanban_index, write_vault_config,
+    KanbanIndex, KanbanTask, WatchPause, DEFAULT_COLUMNS,
+};
+
+/// Check that the source exists and the target is not inside it
+pub(crate) fn validate_import_paths(source: &Path, target: &Path) -> Result<PathBuf, ImportError> {
+    if !source.is_dir() {
+        return Err(ImportError::NotFound(source.display().to_string()));
+    }
+    fs::create_dir_all(target)?;
+
+    let source = source.canonicalize()?;
+    let target = target.canonicalize()?;
+    if target.starts_with(&source) {
+        return Err(ImportError::InvalidPath(
+            "Target vault cannot be inside the source folder".to_string(),
+        ));
+    }
+
+    Ok(source)
+}
+
+/// Import an Obsidian vault into a notemaker vault
+///
+/// Notes and attachments are copied with their folder structure; `.obsidian`,
+/// `.trash` and other hidden entries are skipped. Wiki links and `![[embeds]]`
+/// are rewritten to markdown links and inline `#tags` (including nested
+/// `#parent/child` tags) are added to the frontmatter. Existing files in the
+/// target are never overwritten.
+///
+/// With `convert_wikilinks` disabled, links are kept as written and the target
+/// vault is switched to Obsidian link compatibility.
+#[tauri::command]
+pub async fn import_obsidian_vault(
+    source: PathBuf,
+    target: PathBuf,
+    options: Option<ObsidianImportOptions>,
+) -> Result<ImportReport, ImportError> {
+    let _pause = WatchPause::new(&target);
+    let options = options.unwrap_or_default();
+    let source = validate_import_paths(&source, &target)?;
+    let index = SourceIndex::build(&source);
+
+    let mut report = ImportReport {
+        target: target.clone(),
+        ..Default::default()
+    };
+
+    for rel in &index.files {
+        let from = source.join(rel);
+        let to = target.join(rel);
+        if to.exists() {
+            report.skipped.push(to);
+            continue;
+        }
+        if let Some(parent) = to.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        if !is_markdown(rel) {
+            fs::copy(&from, &to)?;
+            report.attachments += 1;
+            continue;
+        }
+
+        let mut content = fs::read_to_string(&from)?;
+
+        if options.convert_wikilinks {
+            let conversion = convert_links(&content, rel, &index);
+            report.links_converted += conversion.converted;
+            for target in conversion.unresolved {
+                if !report.unresolved_links.contains(&target) {
+                    report.unresolved_links.push(target);
+                }
+            }
+            content = conversion.content;
+        }
+
+        if options.collect_tags {
+            let (_, body) = split_frontmatter(&content);
+            let tags = collect_inline_tags(body);
+            if let Some(updated) = merge_frontmatter_tags(&content, &tags) {
+                content = updated;
+            }
+        }
+
+        write_atomic(&to, content)?;
+        report.notes += 1;
+    }
+
+    let mut config = read_vault_config(&target)?;
+    config.compatibility.obsidian_links = !options.convert_wikilinks;
+    write_vault_config(&target, &config)?;
+
+    Ok(report)
+}
+
+/// Import a Notion "Markdown & CSV" export into a notemaker vault
+///
+/// The zip (including nested `Part-N.zip` archives) is unpacked to a staging
+/// folder, the hex IDs Notion appends to every name are stripped and links
+/// are rewritten to the new names. Databases with a status column become
+/// kanban boards whose tasks carry the row pages as descriptions, with
+/// statuses mapped onto the board's columns; other
+/// databases become a note with a markdown table linking to the row pages.
+/// Assets stay in the page folder beside their note. Existing files in the
+/// target are never overwritten.
+#[tauri::command]
+pub async fn import_notion_export(zip_path: PathBuf, target_dir: PathBuf) -> Result<ImportReport, ImportError> {
+    let _pause = WatchPause::new(&target_dir);
+    if !zip_path.is_file() {
+        return Err(ImportError::NotFound(zip_path.display().to_string()));
+    }
+    fs::create_dir_all(&target_dir)?;
+
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    let staging = std::env::temp_dir().join(format!("notemaker-notion-{}-{}", std::process::id(), nanos));
+    fs::create_dir_all(&staging)?;
+
+    let result = import_notion_tree(&zip_path, &staging, &target_dir);
+    let _ = fs::remove_dir_all(&staging);
+    result
+}
+
+fn import_notion_tree(zip_path: &Path, staging: &Path, target: &Path) -> Result<ImportReport, ImportError> {
+    extract_archive(fs::File::open(zip_path)?, staging)?;
+
+    // Exports are sometimes wrapped in a single top-level folder
+    let mut root = staging.to_path_buf();
+    loop {
+        let entries: Vec<PathBuf> = fs::read_dir(&root)?.flatten().map(|e| e.path()).collect();
+        match entries.as_slice() {
+            [only] if only.is_dir() => root = only.clone(),
+            _ => break,
+        }
+    }
+
+    let mut tree = NotionTree::build(&root);
+    let mut report = ImportReport {
+        target: target.to_path_buf(),
+        ..Default::default()
+    };
+    let mut consumed: HashSet<PathBuf> = HashSet::new();
+
+    let databases: Vec<PathBuf> = tree
+        .entries
+        .iter()
+        .filter(|rel| rel.extension().map(|e| e == "csv").unwrap_or(false))
+        .filter(|rel| {
+            // Prefer the `_all` variant, which includes every view's rows
+            let stem = rel.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+            !root.join(rel.with_file_name(format!("{}_all.csv", stem))).exists()
+        })
+        .cloned()
+        .collect();
+
+    for csv_rel in &databases {
+        let stem = csv_rel.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+        let base = stem.strip_suffix("_all").unwrap_or(&stem);
+        let folder_rel = csv_rel.with_file_name(base);
+        // Links may point at either CSV variant
+        let csv_variants = [csv_rel.clone(), csv_rel.with_file_name(format!("{}.csv", base))];
+        let name = strip_notion_id(&stem);
+        let new_parent = csv_rel
+            .parent()
+            .and_then(|p| tree.renamed(p).cloned())
+            .unwrap_or_default();
+
+        let database = Database::read(&root.join(csv_rel))?;
+
+        // Row pages live in a folder named like the database
+        let rows: Vec<(String, PathBuf)> = tree
+            .entries
+            .iter()
+            .filter(|rel| rel.parent() == Some(folder_rel.as_path()) && is_markdown(rel))
+            .map(|rel| {
+                let title = rel.file_stem().map(|s| strip_notion_id(&s.to_string_lossy())).unwrap_or_default();
+                (title, rel.clone())
+            })
+            .collect();
+        let row_page = |title: &str| rows.iter().find(|(t, _)| t == title).map(|(_, rel)| rel.clone());
+
+        if let Some(status) = database.status_column() {
+            let board_rel = new_parent.join(format!("{}.kanban", name));
+            let board = target.join(&board_rel);
+            if board.exists() {
+                report.skipped.push(board);
+                continue;
+            }
+            fs::create_dir_all(&board)?;
+
+            let priority = database.priority_column();
+            let due = database.due_column();
+            let now = chrono::Utc::now().to_rfc3339();
+            let mut tasks: Vec<KanbanTask> = Vec::new();
+
+            for row in &database.rows {
+                let title = database.cell(row, 0);
+                if title.is_empty() {
+                    continue;
+                }
+                let mut id = generate_task_id();
+                while tasks.iter().any(|t| t.id == id) {
+                    id = generate_task_id();
+                }
+
+                tasks.push(KanbanTask {
+                    id: id.clone(),
+                    title: title.to_string(),
+                    status: kanban_status(database.cell(row, status)).to_string(),
+                    priority: priority.map(|c| database.cell(row, c)).filter(|v| !v.is_empty()).map(str::to_string),
+                    due: due.and_then(|c| parse_due(database.cell(row, c))),
+                    created: now.clone(),
+                    updated: now.clone(),
+                    ..Default::default()
+                });
+
+                if let Some(page) = row_page(title) {
+                    let content = fs::read_to_string(root.join(&page))?;
+                    let description = strip_properties(strip_title_heading(&content), &database.headers);
+                    let rewrite = rewrite_links(description, &folder_rel, &board_rel, &tree);
+                    report.links_converted += rewrite.converted;
+                    push_unresolved(&mut report, rewrite.unresolved);
+                    if !rewrite.content.trim().is_empty() {
+                        write_atomic(get_task_file_path(&board, &id), rewrite.content)?;
+                    }
+                    tree.redirect(&page, board_rel.clone());
+                    consumed.insert(page);
+                }
+            }
+
+            write_kanban_index(
+                &board,
+                &KanbanIndex {
+                    version: 1,
+                    columns: DEFAULT_COLUMNS.iter().map(|c| c.to_string()).collect(),
+                    tasks,
+                    ..Default::default()
+                },
+            )?;
+            for csv in &csv_variants {
+                tree.redirect(csv, board_rel.clone());
+            }
+        } else {
+            let note_rel = new_parent.join(format!("{}.md", name));
+            let note = target.join(&note_rel);
+            if note.exists() {
+                report.skipped.push(note);
+                continue;
+            }
+
+            let mut content = format!("# {}\n\n", name);
+            content.push_str(&format!(
+                "| {} |\n",
+                database.headers.iter().map(|h| table_cell(h)).collect::<Vec<_>>().join(" | ")
+            ));
+            content.push_str(&format!("|{}\n", " --- |".repeat(database.headers.len().max(1))));
+            for row in &database.rows {
+                let cells: Vec<String> = (0..database.headers.len())
+                    .map(|i| {
+                        let value = table_cell(database.cell(row, i));
+                        let page = if i == 0 { row_page(database.cell(row, 0)) } else { None };
+                        match page.and_then(|p| tree.renamed(&p).cloned()) {
+                            Some(new) => {
+                                let dir = note_rel.parent().unwrap_or(Path::new(""));
+                                let href = relative_path(dir, &new).to_string_lossy().replace('\\', "/");
+                                format!("[{}](<{}>)", value, href)
+                            }
+                            None => value,
+                        }
+                    })
+                    .collect();
+                content.push_str(&format!("| {} |\n", cells.join(" | ")));
+            }
+
+            if let Some(parent) = note.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            write_atomic(&note, content)?;
+            for csv in &csv_variants {
+                tree.redirect(csv, note_rel.clone());
+            }
+            report.notes += 1;
+        }
+
+        report.databases += 1;
+    }
+
+    for rel in &tree.entries {
+        let from = root.join(rel);
+        if from.is_dir() || consumed.contains(rel) || rel.extension().map(|e| e == "csv").unwrap_or(false) {
+            continue;
+        }
+        let Some(new_rel) = tree.renamed(rel).cloned() else {
+            continue;
+        };
+        let to = target.join(&new_rel);
+        if to.exists() {
+            report.skipped.push(to);
+            continue;
+        }
+        if let Some(parent) = to.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        if !is_markdown(rel) {
+            fs::copy(&from, &to)?;
+            report.attachments += 1;
+            continue;
+        }
+
+        let content = fs::read_to_string(&from)?;
+        let old_dir = rel.parent().unwrap_or(Path::new(""));
+        let new_dir = new_rel.parent().unwrap_or(Path::new(""));
+        let rewrite = rewrite_links(&content, old_dir, new_dir, &tree);
+        report.links_converted += rewrite.converted;
+        push_unresolved(&mut report, rewrite.unresolved);
+        write_atomic(&to, rewrite.content)?;
+        report.notes += 1;
+    }
+
+    Ok(report)
+}
+
+/// Drop the `Property: value` lines Notion writes below a row page's title
+fn strip_properties<'a>(content: &'a str, headers: &[String]) -> &'a str {
+    let mut offset = 0;
+    for line in content.split_inclusive('\n') {
+        let trimmed = line.trim();
+        let is_property = headers
+            .iter()
+            .any(|h| trimmed.strip_prefix(h.as_str()).map(|r| r.starts_with(':')).unwrap_or(false));
+        if !trimmed.is_empty() && !is_property {
+            break;
+        }
+        offset += line.len();
+    }
+    &content[offset..]
+}
+
+fn push_unresolved(report: &mut ImportReport, targets: Vec<String>) {
+    for target in targets {
+        if !report.unresolved_links.contains(&target) {
+            report.unresolved_links.push(target);
+        }
+    }
+}
@@ -0,0 +1,6 @@
+pub mod commands;
+pub mod notion;
+pub mod obsidian;
+pub mod types;
+
+pub use commands::*;
@@ -0,0 +1,373 @@
+//! Conversion of Notion's "Markdown & CSV" export into notemaker's structure.
+//!
+//! Notion appends a 32-character hex ID to every page, folder and database
+//! name (`Meeting notes 1f2e...9a.md`) and links pages through those names.
+//! The importer strips the IDs, rewrites links to the new names and turns
+//! databases into kanban boards (when they have a status column) or tables.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{Cursor, Read, Seek};
+use std::path::{Component, Path, PathBuf};
+
+use super::types::ImportError;
+use crate::fs::links::{relative_path, rewrite_markdown_links};
+
+/// Column names treated as a kanban status, compared case-insensitively
+const STATUS_COLUMNS: &[&str] = &["status", "stage", "state"];
+const PRIORITY_COLUMNS: &[&str] = &["priority"];
+const DUE_COLUMNS: &[&str] = &["due", "due date", "deadline", "date"];
+
+/// Extract a zip archive, unpacking nested zips (Notion splits large exports
+/// into `Part-N.zip` files inside the download)
+pub(crate) fn extract_archive<R: Read + Seek>(reader: R, dest: &Path) -> Result<(), ImportError> {
+    let mut archive = zip::ZipArchive::new(reader).map_err(|e| ImportError::Archive(e.to_string()))?;
+
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i).map_err(|e| ImportError::Archive(e.to_string()))?;
+        // Entries escaping the destination are ignored
+        let Some(rel) = entry.enclosed_name() else {
+            continue;
+        };
+        let out = dest.join(rel);
+
+        if entry.is_dir() {
+            fs::create_dir_all(&out)?;
+            continue;
+        }
+
+        let mut bytes = Vec::new();
+        entry.read_to_end(&mut bytes)?;
+
+        if out.extension().map(|e| e == "zip").unwrap_or(false) {
+            extract_archive(Cursor::new(bytes), dest)?;
+            continue;
+        }
+
+        if let Some(parent) = out.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&out, bytes)?;
+    }
+
+    Ok(())
+}
+
+/// Strip Notion's trailing ID from a file or folder name
+///
+/// `Tasks 0123456789abcdef0123456789abcdef_all.csv` becomes `Tasks.csv`.
+pub(crate) fn strip_notion_id(name: &str) -> String {
+    let (stem, extension) = match name.rfind('.') {
+        Some(dot) if dot > 0 => (&name[..dot], &name[dot..]),
+        _ => (name, ""),
+    };
+    let stem = stem.strip_suffix("_all").unwrap_or(stem);
+
+    match stem.rsplit_once(' ') {
+        Some((base, id)) if id.len() == 32 && id.chars().all(|c| c.is_ascii_hexdigit()) && !base.is_empty() => {
+            format!("{}{}", base.trim_end(), extension)
+        }
+        _ => format!("{}{}", stem, extension),
+    }
+}
+
+/// Decode `%XX` escapes in a link target
+pub(crate) fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() && bytes[i + 1].is_ascii_hexdigit() && bytes[i + 2].is_ascii_hexdigit()
+        {
+            if let Ok(byte) = u8::from_str_radix(&text[i + 1..i + 3], 16) {
+                out.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Resolve `..` and `.` in a relative path without touching the filesystem
+fn normalize(path: &Path) -> PathBuf {
+    let mut result = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::ParentDir => {
+                result.pop();
+            }
+            Component::CurDir => {}
+            other => result.push(other.as_os_str()),
+        }
+    }
+    result
+}
+
+fn walk(root: &Path, dir: &Path, entries: &mut Vec<PathBuf>) {
+    let Ok(read) = fs::read_dir(dir) else {
+        return;
+    };
+    let mut paths: Vec<PathBuf> = read.flatten().map(|e| e.path()).collect();
+    paths.sort();
+
+    for path in paths {
+        if let Ok(rel) = path.strip_prefix(root) {
+            entries.push(rel.to_path_buf());
+        }
+        if path.is_dir() {
+            walk(root, &path, entries);
+        }
+    }
+}
+
+/// Mapping from exported (ID-suffixed) paths to their cleaned names
+pub(crate) struct NotionTree {
+    /// Every exported file and folder, relative to `root`
+    pub entries: Vec<PathBuf>,
+    renamed: HashMap<PathBuf, PathBuf>,
+}
+
+impl NotionTree {
+    /// Scan an extracted export and assign a clean, unique name to every entry
+    pub fn build(root: &Path) -> Self {
+        let mut entries = Vec::new();
+        walk(root, root, &mut entries);
+
+        let mut renamed: HashMap<PathBuf, PathBuf> = HashMap::new();
+        let mut taken: HashSet<PathBuf> = HashSet::new();
+
+        for rel in &entries {
+            let parent = rel
+                .parent()
+                .and_then(|p| renamed.get(p).cloned())
+                .unwrap_or_default();
+            let name = rel
+                .file_name()
+                .map(|n| strip_notion_id(&n.to_string_lossy()))
+                .unwrap_or_default();
+
+            let mut candidate = parent.join(&name);
+            let mut n = 2;
+            while taken.contains(&candidate) {
+                let path = Path::new(&name);
+                let stem = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+                candidate = match path.extension() {
+                    Some(ext) => parent.join(format!("{} {}.{}", stem, n, ext.to_string_lossy())),
+                    None => parent.join(format!("{} {}", name, n)),
+                };
+                n += 1;
+            }
+
+            taken.insert(candidate.clone());
+            renamed.insert(rel.clone(), candidate);
+        }
+
+        Self {
+            entries,
+            renamed,
+        }
+    }
+
+    /// Clean name of an exported path
+    pub fn renamed(&self, rel: &Path) -> Option<&PathBuf> {
+        self.renamed.get(rel)
+    }
+
+    /// Point an exported path at a different cleaned location (e.g. a
+    /// database CSV that became a kanban board)
+    pub fn redirect(&mut self, rel: &Path, to: PathBuf) {
+        self.renamed.insert(rel.to_path_buf(), to);
+    }
+}
+
+/// Result of rewriting the links of one page
+pub(crate) struct LinkRewrite {
+    pub content: String,
+    pub converted: usize,
+    pub unresolved: Vec<String>,
+}
+
+/// Rewrite relative markdown links of a page from exported names to cleaned names
+///
+/// `old_dir` is the page's folder in the export and `new_dir` the folder the
+/// content will live in after import.
+pub(crate) fn rewrite_links(content: &str, old_dir: &Path, new_dir: &Path, tree: &NotionTree) -> LinkRewrite {
+    let mut converted = 0;
+    let mut unresolved = Vec::new();
+
+    let content = rewrite_markdown_links(content, |target| {
+        let (path_part, fragment) = match target.split_once('#') {
+            Some((p, f)) => (p, Some(f)),
+            None => (target, None),
+        };
+        if path_part.is_empty() || path_part.contains("://") || path_part.starts_with("mailto:") {
+            return None;
+        }
+
+        let old = normalize(&old_dir.join(percent_decode(path_part)));
+        let Some(new) = tree.renamed(&old) else {
+            unresolved.push(percent_decode(path_part));
+            return None;
+        };
+        let mut href = relative_path(new_dir, new).to_string_lossy().replace('\\', "/");
+        if let Some(fragment) = fragment {
+            href.push('#');
+            href.push_str(fragment);
+        }
+        converted += 1;
+        Some(href)
+    });
+
+    LinkRewrite {
+        content,
+        converted,
+        unresolved,
+    }
+}
+
+/// Drop the `# Title` heading Notion puts at the top of every page
+pub(crate) fn strip_title_heading(content: &str) -> &str {
+    let trimmed = content.trim_start();
+    if trimmed.starts_with("# ") {
+        match trimmed.find('\n') {
+            Some(newline) => trimmed[newline + 1..].trim_start_matches(['\r', '\n']),
+            None => "",
+        }
+    } else {
+        content
+    }
+}
+
+/// A Notion database exported as CSV
+pub(crate) struct Database {
+    pub headers: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+impl Database {
+    pub fn read(path: &Path) -> Result<Self, ImportError> {
+        let bytes = fs::read(path)?;
+        // Notion writes a UTF-8 BOM
+        let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(&bytes);
+        let mut reader = csv::ReaderBuilder::new().flexible(true).from_reader(bytes);
+
+        let headers = reader
+            .headers()
+            .map_err(|e| ImportError::Archive(e.to_string()))?
+            .iter()
+            .map(str::to_string)
+            .collect();
+        let rows = reader
+            .records()
+            .filter_map(Result::ok)
+            .map(|record| record.iter().map(str::to_string).collect())
+            .collect();
+
+        Ok(Self { headers, rows })
+    }
+
+    /// Index of the first column whose name is in `names`
+    pub fn column(&self, names: &[&str]) -> Option<usize> {
+        self.headers
+            .iter()
+            .position(|h| names.contains(&h.trim().to_lowercase().as_str()))
+    }
+
+    pub fn status_column(&self) -> Option<usize> {
+        self.column(STATUS_COLUMNS)
+    }
+
+    pub fn priority_column(&self) -> Option<usize> {
+        self.column(PRIORITY_COLUMNS)
+    }
+
+    pub fn due_column(&self) -> Option<usize> {
+        self.column(DUE_COLUMNS)
+    }
+
+    /// Cell value, empty when the row is short
+    pub fn cell<'a>(&self, row: &'a [String], column: usize) -> &'a str {
+        row.get(column).map(|s| s.trim()).unwrap_or("")
+    }
+}
+
+/// Escape a value for a markdown table cell
+pub(crate) fn table_cell(value: &str) -> String {
+    value.replace('|', "\\|").replace(['\r', '\n'], " ")
+}
+
+/// Notion dates look like "March 5, 2024" or "March 5, 2024 → March 7, 2024";
+/// keep the start date as ISO 8601 when it can be parsed
+pub(crate) fn parse_due(value: &str) -> Option<String> {
+    let start = value.split('→').next()?.trim();
+    if start.is_empty() {
+        return None;
+    }
+    for format in ["%B %d, %Y", "%Y-%m-%d", "%Y/%m/%d", "%m/%d/%Y"] {
+        if let Ok(date) = chrono::NaiveDate::parse_from_str(start, format) {
+            return Some(date.format("%Y-%m-%d").to_string());
+        }
+    }
+    let date_part: String = start.split(' ').take(3).collect::<Vec<_>>().join(" ");
+    chrono::NaiveDate::parse_from_str(&date_part, "%B %d, %Y")
+        .ok()
+        .map(|d| d.format("%Y-%m-%d").to_string())
+}
+
+/// Board column for a Notion status: Notion's own "Not started",
+/// "In progress" and "Done" and common workflow names are recognized,
+/// anything else lands in the backlog
+pub(crate) fn kanban_status(value: &str) -> &'static str {
+    let value = value.trim().to_lowercase().replace(['-', '_'], " ");
+    match value.as_str() {
+        "ready" | "to do" | "todo" | "next" | "up next" | "planned" => "ready",
+        "in progress" | "doing" | "working" | "started" | "in review" | "review" | "blocked" => "working",
+        "done" | "complete" | "completed" | "finished" | "shipped" => "done",
+        "closed" | "archived" | "cancelled" | "canceled" | "won't do" | "wont do" => "closed",
+        _ => "backlog",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_notion_ids() {
+        assert_eq!(strip_notion_id("Meeting notes 0123456789abcdef0123456789abcdef.md"), "Meeting notes.md");
+        assert_eq!(strip_notion_id("Tasks 0123456789abcdef0123456789abcdef_all.csv"), "Tasks.csv");
+        assert_eq!(strip_notion_id("Projects 0123456789abcdef0123456789abcdef"), "Projects");
+        assert_eq!(strip_notion_id("image.png"), "image.png");
+    }
+
+    #[test]
+    fn maps_statuses_to_columns() {
+        assert_eq!(kanban_status("Not started"), "backlog");
+        assert_eq!(kanban_status("To-do"), "ready");
+        assert_eq!(kanban_status("In progress"), "working");
+        assert_eq!(kanban_status("Done"), "done");
+        assert_eq!(kanban_status("Archived"), "closed");
+        assert_eq!(kanban_status(""), "backlog");
+        assert_eq!(kanban_status("Waiting on legal"), "backlog");
+    }
+
+    #[test]
+    fn rewrites_links_to_clean_names() {
+        let dir = tempfile::tempdir().unwrap();
+        let id = "0123456789abcdef0123456789abcdef";
+        fs::create_dir_all(dir.path().join(format!("Home {}", id))).unwrap();
+        fs::write(dir.path().join(format!("Home {}.md", id)), "").unwrap();
+        fs::write(dir.path().join(format!("Home {}/Child {}.md", id, id)), "").unwrap();
+        let tree = NotionTree::build(dir.path());
+
+        let content = format!("See [Child](Home%20{}/Child%20{}.md#Intro) and [web](https://notion.so)", id, id);
+        let rewrite = rewrite_links(&content, Path::new(""), Path::new(""), &tree);
+        assert_eq!(rewrite.content, "See [Child](<Home/Child.md#Intro>) and [web](https://notion.so)");
+        assert_eq!(rewrite.converted, 1);
+    }
+}
@@ -0,0 +1,254 @@
+//! Conversion of Obsidian vault conventions into notemaker's structure.
+
+use serde_yaml::{Mapping, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::fs::frontmatter::{extract_aliases, parse_frontmatter, split_frontmatter, string_list};
+use crate::fs::links::{extract_wikilinks, relative_path, WikiLink};
+
+/// Files of an Obsidian vault, keyed the way Obsidian resolves link targets
+pub(crate) struct SourceIndex {
+    /// All importable files, relative to the vault root
+    pub files: Vec<PathBuf>,
+    notes: HashMap<String, PathBuf>,
+    attachments: HashMap<String, PathBuf>,
+}
+
+fn key(target: &str) -> String {
+    target.trim().trim_start_matches("./").replace('\\', "/").to_lowercase()
+}
+
+fn note_key(target: &str) -> String {
+    let target = key(target);
+    target.strip_suffix(".md").map(str::to_string).unwrap_or(target)
+}
+
+fn walk(root: &Path, dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let hidden = path
+            .file_name()
+            .map(|n| n.to_string_lossy().starts_with('.'))
+            .unwrap_or(false);
+        if hidden {
+            continue;
+        }
+
+        if path.is_dir() {
+            walk(root, &path, files);
+        } else if let Ok(rel) = path.strip_prefix(root) {
+            files.push(rel.to_path_buf());
+        }
+    }
+}
+
+impl SourceIndex {
+    /// Scan an Obsidian vault, skipping `.obsidian`, `.trash` and other hidden entries
+    pub fn build(root: &Path) -> Self {
+        let mut files = Vec::new();
+        walk(root, root, &mut files);
+        // Obsidian prefers the shortest path when a file name is ambiguous
+        files.sort_by(|a, b| {
+            a.components()
+                .count()
+                .cmp(&b.components().count())
+                .then_with(|| a.cmp(b))
+        });
+
+        let mut notes = HashMap::new();
+        let mut attachments = HashMap::new();
+        let mut aliases = Vec::new();
+
+        for rel in &files {
+            let rel_key = key(&rel.to_string_lossy());
+            let name_key = rel
+                .file_name()
+                .map(|n| key(&n.to_string_lossy()))
+                .unwrap_or_default();
+
+            if is_markdown(rel) {
+                notes.entry(note_key(&rel_key)).or_insert_with(|| rel.clone());
+                notes.entry(note_key(&name_key)).or_insert_with(|| rel.clone());
+                if let Ok(content) = fs::read_to_string(root.join(rel)) {
+                    aliases.extend(extract_aliases(&content).into_iter().map(|a| (a, rel.clone())));
+                }
+            } else {
+                attachments.entry(rel_key).or_insert_with(|| rel.clone());
+                attachments.entry(name_key).or_insert_with(|| rel.clone());
+            }
+        }
+
+        // Real names win over aliases
+        for (alias, rel) in aliases {
+            notes.entry(note_key(&alias)).or_insert(rel);
+        }
+
+        Self {
+            files,
+            notes,
+            attachments,
+        }
+    }
+
+    /// Resolve a link target to a vault-relative path
+    pub fn resolve(&self, link: &WikiLink) -> Option<&PathBuf> {
+        if link.target.is_empty() {
+            return None;
+        }
+        let target_key = key(&link.target);
+        let has_extension = Path::new(&link.target)
+            .extension()
+            .map(|e| e != "md")
+            .unwrap_or(false);
+
+        if has_extension {
+            self.attachments.get(&target_key)
+        } else {
+            self.notes.get(&note_key(&target_key))
+        }
+    }
+}
+
+pub(crate) fn is_markdown(path: &Path) -> bool {
+    path.extension().map(|e| e == "md").unwrap_or(false)
+}
+
+/// Anchor for a heading, following the GitHub slug rules
+pub(crate) fn heading_anchor(heading: &str) -> String {
+    heading
+        .trim()
+        .to_lowercase()
+        .chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            c if c.is_alphanumeric() || c == '-' || c == '_' => Some(c),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Result of converting the links of one note
+pub(crate) struct LinkConversion {
+    pub content: String,
+    pub converted: usize,
+    pub unresolved: Vec<String>,
+}
+
+/// Rewrite wiki links and embeds of a note into markdown links
+///
+/// Embedded attachments become images, embedded notes become regular links
+/// and `#^block` references are dropped. Unresolved links are left as written.
+pub(crate) fn convert_links(content: &str, note_rel: &Path, index: &SourceIndex) -> LinkConversion {
+    let note_dir = note_rel.parent().unwrap_or(Path::new(""));
+    let mut result = String::with_capacity(content.len());
+    let mut last = 0;
+    let mut converted = 0;
+    let mut unresolved = Vec::new();
+
+    for link in extract_wikilinks(content) {
+        let Some(target) = index.resolve(&link) else {
+            if !link.target.is_empty() {
+                unresolved.push(link.target.clone());
+            }
+            continue;
+        };
+
+        let href = relative_path(note_dir, target).to_string_lossy().replace('\\', "/");
+        let text = link.display.clone().unwrap_or_else(|| link.target.clone());
+        let anchor = link
+            .heading
+            .as_deref()
+            .filter(|h| !h.starts_with('^'))
+            .map(|h| format!("#{}", heading_anchor(h)))
+            .unwrap_or_default();
+
+        let replacement = if link.embed && !is_markdown(target) {
+            let alt = link.display.clone().unwrap_or_default();
+            format!("![{}](<{}>)", alt, href)
+        } else {
+            format!("[{}](<{}{}>)", text, href, anchor)
+        };
+
+        result.push_str(&content[last..link.start]);
+        result.push_str(&replacement);
+        last = link.end;
+        converted += 1;
+    }
+
+    result.push_str(&content[last..]);
+    LinkConversion {
+        content: result,
+        converted,
+        unresolved,
+    }
+}
+
+/// Merge tags into the frontmatter `labels` list, returning the new content
+///
+/// Obsidian's `tags`/`tag` fields are folded into `labels`, where notemaker
+/// reads them, and lose a leading `#`, which Obsidian tolerates but notemaker
+/// does not. Returns `None` when nothing changes.
+pub(crate) fn merge_frontmatter_tags(content: &str, tags: &[String]) -> Option<String> {
+    let (yaml, body) = split_frontmatter(content);
+    let mut map = match yaml {
+        // Frontmatter that is not a mapping is left alone
+        Some(yaml) if !yaml.trim().is_empty() => parse_frontmatter(content)?,
+        _ => Mapping::new(),
+    };
+
+    let labels = string_list(&map, "labels");
+    let obsidian = ["tags", "tag"].iter().flat_map(|field| string_list(&map, field));
+    let mut merged: Vec<String> = Vec::new();
+    for tag in labels.iter().cloned().chain(obsidian).chain(tags.iter().cloned()) {
+        let tag = tag.trim_start_matches('#').to_string();
+        if !tag.is_empty() && !merged.iter().any(|t| t.eq_ignore_ascii_case(&tag)) {
+            merged.push(tag);
+        }
+    }
+
+    let unchanged = merged == labels && !map.contains_key("tags") && !map.contains_key("tag");
+    if unchanged || merged.is_empty() {
+        return None;
+    }
+
+    map.remove("tags");
+    map.remove("tag");
+    map.insert(
+        Value::String("labels".to_string()),
+        Value::Sequence(merged.into_iter().map(Value::String).collect()),
+    );
+    let yaml = serde_yaml::to_string(&map).ok()?;
+    Some(format!("---\n{}---\n\n{}", yaml, body.trim_start_matches(['\r', '\n'])))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::tags::collect_inline_tags;
+
+    #[test]
+    fn collects_nested_inline_tags() {
+        let body = "# Heading\nSome #project/alpha text #todo.\n```\n#not-a-tag\n```\nIssue #123 and `#code`";
+        assert_eq!(collect_inline_tags(body), vec!["project/alpha", "todo"]);
+    }
+
+    #[test]
+    fn merges_tags_into_labels() {
+        let tags = vec!["todo".to_string(), "Work".to_string()];
+        let merged = merge_frontmatter_tags("---\ntitle: Plan\ntags: ['#work', q3]\n---\nBody\n", &tags).unwrap();
+        let map = parse_frontmatter(&merged).unwrap();
+        assert_eq!(string_list(&map, "labels"), vec!["work", "q3", "todo"]);
+        assert!(!map.contains_key("tags"));
+        assert!(merged.ends_with("---\n\nBody\n"));
+
+        let plain = merge_frontmatter_tags("Body #todo\n", &["todo".to_string()]).unwrap();
+        assert_eq!(plain, "---\nlabels:\n- todo\n---\n\nBody #todo\n");
+        assert_eq!(merge_frontmatter_tags(&plain, &["todo".to_string()]), None);
+    }
+}
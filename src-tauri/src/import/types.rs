@@ -0,0 +1,73 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use thiserror::Error;
+
+use crate::fs::FsError;
+
+#[derive(Error, Debug)]
+pub enum ImportError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Path not found: {0}")]
+    NotFound(String),
+    #[error("Invalid path: {0}")]
+    InvalidPath(String),
+    #[error("{0}")]
+    Fs(#[from] FsError),
+    #[error("Archive error: {0}")]
+    Archive(String),
+}
+
+impl serde::Serialize for ImportError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+/// Options for importing an Obsidian vault
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ObsidianImportOptions {
+    /// Rewrite `[[wikilinks]]` and `![[embeds]]` into markdown links. When
+    /// disabled, links are kept as written and the target vault is switched
+    /// to Obsidian link compatibility instead.
+    #[serde(default = "default_true")]
+    pub convert_wikilinks: bool,
+    /// Copy inline `#tags` into the frontmatter `labels` list
+    #[serde(default = "default_true")]
+    pub collect_tags: bool,
+}
+
+impl Default for ObsidianImportOptions {
+    fn default() -> Self {
+        Self {
+            convert_wikilinks: true,
+            collect_tags: true,
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Summary of an import run
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ImportReport {
+    pub target: PathBuf,
+    /// Number of notes written
+    pub notes: usize,
+    /// Number of attachments copied
+    pub attachments: usize,
+    /// Number of databases converted to kanban boards or tables
+    #[serde(default)]
+    pub databases: usize,
+    /// Number of links rewritten
+    pub links_converted: usize,
+    /// Links whose target could not be found in the source vault
+    pub unresolved_links: Vec<String>,
+    /// Files left out because they already exist in the target
+    pub skipped: Vec<PathBuf>,
+}
@@ -1,10 +1,19 @@
 use std::sync::{Arc, Mutex};
 
+mod api;
 mod commands;
+mod deeplink;
+mod export;
 mod fs;
 mod git;
+pub mod headless;
+mod import;
+mod mcp;
 
-use fs::{EncryptionState, FileWatcher, ProcessManager, ProcessState, WatcherState};
+use fs::{EncryptionState, FileWatcher, ProcessManager, ProcessState, VaultScope, WatcherState};
+use tauri::ipc::{Invoke, InvokeBody};
+use tauri::Manager;
+use tauri_plugin_deep_link::DeepLinkExt;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -17,113 +26,292 @@ pub fn run() {
     // Initialize encryption state
     let encryption_state = EncryptionState::default();
 
+    // The auto-sync, reminder and backup schedulers follow the watched vault
+    let autosync_watcher = watcher_state.clone();
+    let reminders_watcher = watcher_state.clone();
+    let backups_watcher = watcher_state.clone();
+
+    let handler: fn(Invoke) -> bool = tauri::generate_handler![
+        // Basic commands
+        commands::greet,
+        // File system commands
+        fs::open_vault,
+        fs::create_vault,
+        fs::list_directory,
+        fs::list_directory_children,
+        fs::filter_vault,
+        fs::read_note,
+        fs::diff_against_disk,
+        fs::read_file_binary,
+        fs::get_note_metadata,
+        fs::update_note_metadata,
+        fs::get_note_stats,
+        fs::find_duplicate_notes,
+        fs::write_note,
+        fs::create_note,
+        fs::render_template,
+        fs::get_template_prompts,
+        fs::create_template_from_note,
+        fs::get_periodic_note,
+        fs::get_adjacent_periodic_note,
+        fs::delete_note,
+        fs::rename_note,
+        fs::move_note,
+        fs::copy_note,
+        fs::save_attachment,
+        fs::create_directory,
+        fs::delete_directory,
+        fs::rename_directory,
+        fs::bulk_move,
+        fs::bulk_delete,
+        fs::bulk_tag,
+        fs::rename_tag,
+        fs::merge_tags,
+        fs::get_tag_tree,
+        fs::find_notes_by_tag,
+        fs::get_completion_data,
+        fs::query_notes,
+        fs::list_open_tasks,
+        fs::toggle_task,
+        fs::get_calendar,
+        fs::migrate_frontmatter,
+        fs::archive_note,
+        fs::unarchive_note,
+        fs::list_archived_notes,
+        fs::create_backup_now,
+        fs::list_backups,
+        fs::restore_backup,
+        fs::get_vault_config,
+        fs::save_vault_config,
+        fs::get_local_state,
+        fs::save_local_state,
+        fs::get_recent_files,
+        fs::toggle_pin,
+        // Link index commands
+        fs::get_note_index,
+        fs::find_notes,
+        fs::resolve_wikilink,
+        fs::get_backlinks,
+        fs::update_links_after_rename,
+        fs::resolve_attachment,
+        // Notebook commands
+        fs::create_notebook,
+        fs::read_notebook,
+        fs::add_notebook_block,
+        fs::update_notebook_block,
+        fs::delete_notebook_block,
+        fs::move_notebook_block,
+        fs::reorder_notebook_blocks,
+        fs::notebook_needs_migration,
+        fs::change_block_type,
+        fs::get_table_block,
+        fs::update_table_cell,
+        fs::add_table_row,
+        fs::add_table_column,
+        fs::save_notebook,
+        fs::transfer_notebook_block,
+        fs::convert_notebook_to_note,
+        fs::update_block_metadata,
+        fs::set_block_locked,
+        fs::repair_notebook,
+        // Code execution
+        fs::execute_code_block,
+        fs::execute_code_block_async,
+        fs::terminate_code_block,
+        fs::list_running_executions,
+        fs::terminate_all_executions,
+        fs::execute_notebook,
+        fs::set_block_dependencies,
+        fs::set_block_cwd,
+        fs::save_block_output,
+        fs::clear_block_outputs,
+        fs::render_diagram_block,
+        fs::execute_http_block,
+        fs::set_execution_secret,
+        fs::delete_execution_secret,
+        fs::execute_sql_block,
+        fs::set_database_password,
+        fs::clear_database_password,
+        // Note conversion
+        fs::convert_note_to_notebook,
+        // Kanban commands
+        fs::create_kanban,
+        fs::read_kanban,
+        fs::add_kanban_task,
+        fs::update_kanban_task,
+        fs::delete_kanban_task,
+        fs::update_task_description,
+        fs::update_kanban_settings,
+        fs::filter_kanban_tasks,
+        fs::create_kanban_label,
+        fs::update_kanban_label,
+        fs::delete_kanban_label,
+        fs::add_kanban_person,
+        fs::remove_kanban_person,
+        fs::get_kanban_stats,
+        fs::move_kanban_task,
+        fs::add_task_comment,
+        fs::get_task_activity,
+        fs::list_upcoming_due,
+        // File watcher commands
+        fs::start_watching,
+        fs::stop_watching,
+        fs::set_watch_subscriptions,
+        fs::pause_watching,
+        fs::resume_watching,
+        // Encryption commands
+        fs::set_encryption_password,
+        fs::set_encryption_identity,
+        fs::lock_encryption_session,
+        fs::is_encryption_unlocked,
+        fs::encrypt_block,
+        fs::decrypt_block,
+        fs::encrypt_note,
+        fs::decrypt_note,
+        fs::encrypt_notebook,
+        fs::decrypt_notebook,
+        fs::encrypt_notebook_block,
+        fs::decrypt_notebook_block,
+        fs::encrypt_kanban,
+        fs::decrypt_kanban,
+        fs::reencrypt_vault,
+        fs::change_encryption_password,
+        fs::is_note_encrypted,
+        fs::is_content_encrypted,
+        fs::set_scrypt_work_factor,
+        fs::check_passphrase_strength,
+        // Encryption keychain commands
+        fs::set_encryption_password_with_save,
+        fs::set_encryption_identity_with_save,
+        fs::unlock_from_keychain,
+        fs::has_keychain_credentials,
+        fs::clear_keychain_credentials,
+        fs::lock_encryption_session_with_clear,
+        // Multi-recipient encryption commands
+        fs::get_public_key_from_identity_file,
+        fs::generate_identity_file,
+        fs::setup_recipients_encryption,
+        fs::add_recipient_identity,
+        fs::add_recipient_public_key,
+        fs::get_recipient_public_keys,
+        fs::clear_recipients,
+        // Git commands
+        git::git_init,
+        git::git_clone,
+        git::git_status,
+        git::git_changed_files,
+        git::git_stage,
+        git::git_stage_all,
+        git::git_unstage,
+        git::git_commit,
+        git::git_log,
+        git::git_file_history,
+        git::git_show_file,
+        git::git_notebook_history,
+        git::git_show_notebook,
+        git::get_recent_changes,
+        git::git_discard,
+        git::git_revert,
+        git::git_diff,
+        git::git_diff_workdir,
+        git::git_branches,
+        git::git_checkout_branch,
+        git::git_branch_create,
+        git::git_branch_delete,
+        git::git_merge_branch,
+        git::git_fetch,
+        git::git_pull,
+        git::git_push,
+        git::git_sync,
+        git::git_set_credentials,
+        git::git_delete_credentials,
+        git::git_has_credentials,
+        git::git_conflicted_files,
+        git::git_conflict_versions,
+        git::git_resolve_ours,
+        git::git_resolve_theirs,
+        git::git_abort_merge,
+        git::git_merge,
+        git::git_rebase,
+        git::git_abort_rebase,
+        git::git_continue_rebase,
+        git::get_gitignore_rules,
+        git::add_gitignore_rule,
+        git::remove_gitignore_rule,
+        git::is_ignored,
+        // Export commands
+        export::export_note_html,
+        export::export_note_pdf,
+        export::export_note_docx,
+        export::export_notebook_ipynb,
+        export::export_site,
+        export::export_folder,
+        // Import commands
+        import::import_obsidian_vault,
+        import::import_notion_export,
+        // Local API commands
+        api::configure_api_server,
+        api::get_api_server_status,
+        // Deep link commands
+        deeplink::take_deep_links,
+        deeplink::create_link_note,
+    ];
+
     tauri::Builder::default()
+        // Links opened while the app runs reach this instance through the
+        // deep-link plugin instead of starting another one
+        .plugin(tauri_plugin_single_instance::init(|app, _argv, _cwd| {
+            if let Some(window) = app.get_webview_window("main") {
+                let _ = window.set_focus();
+            }
+        }))
+        .plugin(tauri_plugin_deep_link::init())
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_notification::init())
         .manage(watcher_state)
         .manage(process_state)
         .manage(encryption_state)
-        .invoke_handler(tauri::generate_handler![
-            // Basic commands
-            commands::greet,
-            // File system commands
-            fs::open_vault,
-            fs::list_directory,
-            fs::read_note,
-            fs::write_note,
-            fs::create_note,
-            fs::delete_note,
-            fs::rename_note,
-            fs::move_note,
-            fs::save_attachment,
-            fs::create_directory,
-            fs::delete_directory,
-            fs::get_vault_config,
-            fs::save_vault_config,
-            fs::get_local_state,
-            fs::save_local_state,
-            // Notebook commands
-            fs::create_notebook,
-            fs::read_notebook,
-            fs::add_notebook_block,
-            fs::update_notebook_block,
-            fs::delete_notebook_block,
-            fs::move_notebook_block,
-            fs::change_block_type,
-            // Code execution
-            fs::execute_code_block,
-            fs::execute_code_block_async,
-            fs::terminate_code_block,
-            // Note conversion
-            fs::convert_note_to_notebook,
-            // Kanban commands
-            fs::create_kanban,
-            fs::read_kanban,
-            fs::add_kanban_task,
-            fs::update_kanban_task,
-            fs::delete_kanban_task,
-            fs::update_task_description,
-            fs::update_kanban_settings,
-            // File watcher commands
-            fs::start_watching,
-            fs::stop_watching,
-            // Encryption commands
-            fs::set_encryption_password,
-            fs::set_encryption_identity,
-            fs::lock_encryption_session,
-            fs::is_encryption_unlocked,
-            fs::encrypt_block,
-            fs::decrypt_block,
-            fs::encrypt_note,
-            fs::decrypt_note,
-            fs::is_note_encrypted,
-            fs::is_content_encrypted,
-            // Encryption keychain commands
-            fs::set_encryption_password_with_save,
-            fs::set_encryption_identity_with_save,
-            fs::unlock_from_keychain,
-            fs::has_keychain_credentials,
-            fs::clear_keychain_credentials,
-            fs::lock_encryption_session_with_clear,
-            // Multi-recipient encryption commands
-            fs::get_public_key_from_identity_file,
-            fs::generate_identity_file,
-            fs::setup_recipients_encryption,
-            fs::add_recipient_identity,
-            fs::add_recipient_public_key,
-            fs::get_recipient_public_keys,
-            fs::clear_recipients,
-            // Git commands
-            git::git_init,
-            git::git_status,
-            git::git_changed_files,
-            git::git_stage,
-            git::git_stage_all,
-            git::git_unstage,
-            git::git_commit,
-            git::git_log,
-            git::git_file_history,
-            git::git_show_file,
-            git::git_discard,
-            git::git_diff,
-            git::git_branches,
-            git::git_checkout_branch,
-            git::git_pull,
-            git::git_push,
-            git::git_conflicted_files,
-            git::git_resolve_ours,
-            git::git_resolve_theirs,
-            git::git_abort_merge,
-            git::git_merge,
-            git::git_rebase,
-            git::git_abort_rebase,
-            git::git_continue_rebase,
-        ])
-        .setup(|_app| {
+        .manage(VaultScope::default())
+        .manage(api::ApiServerState::default())
+        .manage(deeplink::DeepLinkState::default())
+        .invoke_handler(move |invoke| {
+            // Reject paths outside the open vault before the command runs
+            let checked = match invoke.message.payload() {
+                InvokeBody::Json(args) => invoke
+                    .message
+                    .webview()
+                    .state::<VaultScope>()
+                    .check_args(invoke.message.command(), args),
+                _ => Ok(()),
+            };
+            if let Err(e) = checked {
+                invoke.resolver.reject(e.to_string());
+                return true;
+            }
+            handler(invoke)
+        })
+        .setup(move |app| {
+            git::spawn_autosync(app.handle().clone(), autosync_watcher);
+            fs::spawn_reminders(app.handle().clone(), reminders_watcher);
+            fs::spawn_backups(app.handle().clone(), backups_watcher);
+
+            // Installed bundles register the scheme; dev builds and AppImages do it here
+            #[cfg(any(target_os = "linux", all(debug_assertions, windows)))]
+            app.deep_link().register_all()?;
+            if let Ok(Some(urls)) = app.deep_link().get_current() {
+                app.state::<deeplink::DeepLinkState>().lock().unwrap().extend(urls);
+            }
+            let handle = app.handle().clone();
+            app.deep_link().on_open_url(move |event| {
+                for url in &event.urls() {
+                    deeplink::handle_deep_link(&handle, url);
+                }
+            });
+
             #[cfg(debug_assertions)]
             {
-                use tauri::Manager;
-                let window = _app.get_webview_window("main").unwrap();
+                let window = app.get_webview_window("main").unwrap();
                 window.open_devtools();
             }
             Ok(())
@@ -0,0 +1,5 @@
+pub mod server;
+pub mod transport;
+
+pub use server::*;
+pub use transport::*;
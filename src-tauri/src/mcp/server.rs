@@ -0,0 +1,386 @@
+//! Model Context Protocol tools over a vault.
+//!
+//! Local agents talk JSON-RPC 2.0 to [`McpServer::handle`], through standard
+//! input and output (`notemaker-cli mcp`) or server-sent events from the app's
+//! local API. The vault's `mcp` settings decide what they may do: nothing
+//! unless `enabled`, and each tool only with its own permission. Settings are
+//! read on every call, so revoking a permission takes effect at once.
+//!
+//! Every tool call is appended to `.notemaker/.local/mcp-audit.jsonl` with
+//! its arguments and whether it was allowed. Encrypted notes are never read
+//! or changed.
+
+use chrono::Utc;
+use serde_json::{json, Value};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::fs::echo::forget_write;
+use crate::fs::{
+    append_to_note, is_encrypted, open_tasks, read_markdown_text, read_vault_config, search_vault, vault_note_path,
+    ChecklistFilter, McpSettings,
+};
+
+/// Protocol revision the server speaks
+const PROTOCOL_VERSION: &str = "2024-11-05";
+/// Audit log, relative to the vault
+const AUDIT_LOG: &str = ".notemaker/.local/mcp-audit.jsonl";
+/// Search results returned without a `limit`
+const DEFAULT_SEARCH_LIMIT: usize = 20;
+
+// JSON-RPC error codes
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// Tools with their descriptions and input schemas
+fn tool_definitions() -> Vec<Value> {
+    vec![
+        json!({
+            "name": "search_notes",
+            "description": "Find lines of notes containing all the given words, ignoring case",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "query": { "type": "string", "description": "Words to search for" },
+                    "limit": { "type": "integer", "description": "Most lines to return" }
+                },
+                "required": ["query"]
+            }
+        }),
+        json!({
+            "name": "read_note",
+            "description": "Read the markdown of a note",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string", "description": "Note path relative to the vault, `.md` optional" }
+                },
+                "required": ["path"]
+            }
+        }),
+        json!({
+            "name": "append_note",
+            "description": "Append text to the end of a note, creating the note if needed",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string", "description": "Note path relative to the vault, `.md` optional" },
+                    "text": { "type": "string", "description": "Markdown to append" }
+                },
+                "required": ["path", "text"]
+            }
+        }),
+        json!({
+            "name": "list_tasks",
+            "description": "List unchecked checkbox items of all notes, earliest due first",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "tags": { "type": "array", "items": { "type": "string" }, "description": "Tags items must carry; `parent/*` matches nested tags" },
+                    "due_from": { "type": "string", "description": "Earliest due date, YYYY-MM-DD" },
+                    "due_to": { "type": "string", "description": "Latest due date, YYYY-MM-DD" },
+                    "text": { "type": "string", "description": "Text the items contain" }
+                }
+            }
+        }),
+    ]
+}
+
+fn permitted(settings: &McpSettings, tool: &str) -> bool {
+    let tools = &settings.tools;
+    settings.enabled
+        && match tool {
+            "search_notes" => tools.search_notes,
+            "read_note" => tools.read_note,
+            "append_note" => tools.append_note,
+            "list_tasks" => tools.list_tasks,
+            _ => false,
+        }
+}
+
+fn rpc_result(id: Value, result: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "result": result })
+}
+
+fn rpc_error(id: Value, code: i64, message: impl Into<String>) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message.into() } })
+}
+
+/// Content of a tool result
+fn tool_text(text: impl Into<String>, is_error: bool) -> Value {
+    json!({ "content": [{ "type": "text", "text": text.into() }], "isError": is_error })
+}
+
+/// Serves one vault to one agent connection
+#[derive(Debug, Clone)]
+pub struct McpServer {
+    vault: PathBuf,
+    /// How the agent is connected, recorded in the audit log
+    transport: &'static str,
+}
+
+impl McpServer {
+    pub fn new(vault: &Path, transport: &'static str) -> Self {
+        Self {
+            vault: vault.to_path_buf(),
+            transport,
+        }
+    }
+
+    pub fn vault(&self) -> &Path {
+        &self.vault
+    }
+
+    /// The vault's current MCP settings; a broken config allows nothing
+    pub fn settings(&self) -> McpSettings {
+        read_vault_config(&self.vault).map(|config| config.mcp).unwrap_or_default()
+    }
+
+    /// Answer a JSON-RPC message; notifications get no answer
+    pub fn handle_text(&self, message: &str) -> Option<Value> {
+        match serde_json::from_str::<Value>(message) {
+            Ok(message) => self.handle(&message),
+            Err(e) => Some(rpc_error(Value::Null, PARSE_ERROR, e.to_string())),
+        }
+    }
+
+    pub fn handle(&self, message: &Value) -> Option<Value> {
+        let Some(method) = message.get("method").and_then(Value::as_str) else {
+            let id = message.get("id").cloned().unwrap_or(Value::Null);
+            return Some(rpc_error(id, INVALID_REQUEST, "Not a JSON-RPC request"));
+        };
+        // Notifications, like `notifications/initialized`, need no answer
+        let id = message.get("id")?.clone();
+        let params = message.get("params").cloned().unwrap_or(Value::Null);
+
+        let response = match method {
+            "initialize" => rpc_result(
+                id,
+                json!({
+                    "protocolVersion": PROTOCOL_VERSION,
+                    "capabilities": { "tools": {} },
+                    "serverInfo": { "name": "notemaker", "version": env!("CARGO_PKG_VERSION") }
+                }),
+            ),
+            "ping" => rpc_result(id, json!({})),
+            "tools/list" => {
+                let settings = self.settings();
+                let tools: Vec<Value> = tool_definitions()
+                    .into_iter()
+                    .filter(|tool| permitted(&settings, tool["name"].as_str().unwrap_or_default()))
+                    .collect();
+                rpc_result(id, json!({ "tools": tools }))
+            }
+            "tools/call" => match params.get("name").and_then(Value::as_str) {
+                Some(name) => {
+                    let arguments = params.get("arguments").cloned().unwrap_or_else(|| json!({}));
+                    rpc_result(id, self.call_tool(name, &arguments))
+                }
+                None => rpc_error(id, INVALID_PARAMS, "Missing tool name"),
+            },
+            other => rpc_error(id, METHOD_NOT_FOUND, format!("Unknown method: {}", other)),
+        };
+        Some(response)
+    }
+
+    fn call_tool(&self, name: &str, arguments: &Value) -> Value {
+        if !permitted(&self.settings(), name) {
+            let message = if tool_definitions().iter().any(|tool| tool["name"] == name) {
+                format!("The vault settings don't allow {}", name)
+            } else {
+                format!("Unknown tool: {}", name)
+            };
+            self.audit(name, arguments, false, Some(&message));
+            return tool_text(message, true);
+        }
+
+        let result = match name {
+            "search_notes" => self.search_notes(arguments),
+            "read_note" => self.read_note(arguments),
+            "append_note" => self.append_note(arguments),
+            _ => self.list_tasks(arguments),
+        };
+        self.audit(name, arguments, true, result.as_ref().err().map(String::as_str));
+        match result {
+            Ok(text) => tool_text(text, false),
+            Err(message) => tool_text(message, true),
+        }
+    }
+
+    /// Append a line to the audit log; failing to write it doesn't stop the call
+    fn audit(&self, tool: &str, arguments: &Value, allowed: bool, error: Option<&str>) {
+        let path = self.vault.join(AUDIT_LOG);
+        let mut entry = json!({
+            "time": Utc::now().to_rfc3339(),
+            "transport": self.transport,
+            "tool": tool,
+            "arguments": arguments,
+            "allowed": allowed,
+        });
+        if let Some(error) = error {
+            entry["error"] = json!(error);
+        }
+        if let Some(parent) = path.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        if let Ok(mut file) = OpenOptions::new().create(true).append(true).open(&path) {
+            let _ = writeln!(file, "{}", entry);
+        }
+    }
+
+    fn note(&self, arguments: &Value) -> Result<PathBuf, String> {
+        let name = string_arg(arguments, "path")?;
+        vault_note_path(&self.vault, name).map_err(|e| e.to_string())
+    }
+
+    fn relative<'a>(&self, path: &'a Path) -> &'a Path {
+        path.strip_prefix(&self.vault).unwrap_or(path)
+    }
+
+    fn search_notes(&self, arguments: &Value) -> Result<String, String> {
+        let query = string_arg(arguments, "query")?;
+        let limit = arguments.get("limit").and_then(Value::as_u64).map_or(DEFAULT_SEARCH_LIMIT, |n| n as usize);
+        let hits = search_vault(&self.vault, query, Some(limit), None);
+        if hits.is_empty() {
+            return Ok("No matches".to_string());
+        }
+        Ok(hits
+            .iter()
+            .map(|hit| format!("{}:{}: {}", self.relative(&hit.note).display(), hit.line, hit.text))
+            .collect::<Vec<_>>()
+            .join("\n"))
+    }
+
+    fn read_note(&self, arguments: &Value) -> Result<String, String> {
+        let path = self.note(arguments)?;
+        if !path.exists() {
+            return Err(format!("Note not found: {}", self.relative(&path).display()));
+        }
+        let content = read_markdown_text(&path).map_err(|e| e.to_string())?;
+        if is_encrypted(&content) {
+            return Err(format!("Note is encrypted: {}", self.relative(&path).display()));
+        }
+        Ok(content)
+    }
+
+    fn append_note(&self, arguments: &Value) -> Result<String, String> {
+        let path = self.note(arguments)?;
+        let text = string_arg(arguments, "text")?;
+        if text.trim().is_empty() {
+            return Err("Nothing to append".to_string());
+        }
+        append_to_note(&path, text, None).map_err(|e| e.to_string())?;
+        // An open editor picks the change up like an edit from another program
+        forget_write(&path);
+        Ok(format!("Appended to {}", self.relative(&path).display()))
+    }
+
+    fn list_tasks(&self, arguments: &Value) -> Result<String, String> {
+        let filter: ChecklistFilter = serde_json::from_value(arguments.clone()).map_err(|e| e.to_string())?;
+        let tasks = open_tasks(&self.vault, &filter);
+        if tasks.is_empty() {
+            return Ok("No open tasks".to_string());
+        }
+        Ok(tasks
+            .iter()
+            .map(|task| {
+                let due = task.due.as_ref().map(|due| format!(", due {}", due)).unwrap_or_default();
+                format!("- [ ] {} ({}:{}{})", task.text, self.relative(&task.path).display(), task.line, due)
+            })
+            .collect::<Vec<_>>()
+            .join("\n"))
+    }
+}
+
+fn string_arg<'a>(arguments: &'a Value, name: &str) -> Result<&'a str, String> {
+    arguments
+        .get(name)
+        .and_then(Value::as_str)
+        .ok_or_else(|| format!("Missing argument: {}", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::{write_vault_config, McpToolPermissions, VaultConfig};
+
+    fn call(server: &McpServer, tool: &str, arguments: Value) -> Value {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 7,
+            "method": "tools/call",
+            "params": { "name": tool, "arguments": arguments }
+        });
+        server.handle(&request).unwrap()["result"].clone()
+    }
+
+    #[test]
+    fn serves_permitted_tools_and_audits_calls() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = dir.path();
+        fs::write(vault.join("Plan.md"), "# Plan\n- [ ] Ship release due: 2024-05-01\n").unwrap();
+        let server = McpServer::new(vault, "stdio");
+
+        let init = server.handle_text(r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{}}"#).unwrap();
+        assert_eq!(init["result"]["protocolVersion"], PROTOCOL_VERSION);
+        assert!(server.handle_text(r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#).is_none());
+        assert_eq!(server.handle_text("{").unwrap()["error"]["code"], PARSE_ERROR);
+
+        // Nothing is allowed until the vault enables it
+        let denied = call(&server, "read_note", json!({ "path": "Plan" }));
+        assert_eq!(denied["isError"], true);
+
+        let config = VaultConfig {
+            mcp: McpSettings {
+                enabled: true,
+                tools: McpToolPermissions {
+                    read_note: true,
+                    list_tasks: true,
+                    ..Default::default()
+                },
+            },
+            ..Default::default()
+        };
+        write_vault_config(vault, &config).unwrap();
+
+        let list = server.handle(&json!({ "jsonrpc": "2.0", "id": 2, "method": "tools/list" })).unwrap();
+        let names: Vec<&str> = list["result"]["tools"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|tool| tool["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, vec!["read_note", "list_tasks"]);
+
+        let read = call(&server, "read_note", json!({ "path": "Plan" }));
+        assert_eq!(read["content"][0]["text"], "# Plan\n- [ ] Ship release due: 2024-05-01\n");
+        let tasks = call(&server, "list_tasks", json!({}));
+        assert_eq!(tasks["content"][0]["text"], "- [ ] Ship release due: 2024-05-01 (Plan.md:2, due 2024-05-01)");
+        assert_eq!(call(&server, "append_note", json!({ "path": "Plan", "text": "x" }))["isError"], true);
+        assert_eq!(call(&server, "read_note", json!({ "path": "../Plan" }))["isError"], true);
+        assert_eq!(call(&server, "read_note", json!({ "path": ".notemaker/config.yaml" }))["isError"], true);
+
+        let audit = fs::read_to_string(vault.join(AUDIT_LOG)).unwrap();
+        let entries: Vec<Value> = audit.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        let calls: Vec<(&str, bool)> = entries
+            .iter()
+            .map(|entry| (entry["tool"].as_str().unwrap(), entry["allowed"].as_bool().unwrap()))
+            .collect();
+        assert_eq!(
+            calls,
+            vec![
+                ("read_note", false),
+                ("read_note", true),
+                ("list_tasks", true),
+                ("append_note", false),
+                ("read_note", true),
+                ("read_note", true),
+            ]
+        );
+        assert!(entries[4]["error"].is_string());
+        assert!(entries[5]["error"].is_string());
+    }
+}
@@ -0,0 +1,128 @@
+//! Ways agents reach an [`McpServer`].
+//!
+//! Over stdio each line of input is one JSON-RPC message and each answer is
+//! one line of output. Over server-sent events an agent keeps `GET /mcp/sse`
+//! of the local API open, is told where to post its messages, and gets the
+//! answers as `message` events on that stream.
+
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpStream;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+
+use super::server::McpServer;
+
+/// Interval of comments keeping an idle event stream open
+const KEEPALIVE: Duration = Duration::from_secs(30);
+
+const SSE_HEAD: &[u8] = b"HTTP/1.1 200 OK\r\n\
+Content-Type: text/event-stream\r\n\
+Cache-Control: no-cache\r\n\
+Access-Control-Allow-Origin: *\r\n\
+Connection: keep-alive\r\n\r\n";
+
+/// Answer messages read from `input` until it ends
+pub fn serve_stdio(server: &McpServer, input: impl BufRead, mut output: impl Write) -> std::io::Result<()> {
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some(response) = server.handle_text(&line) {
+            writeln!(output, "{}", response)?;
+            output.flush()?;
+        }
+    }
+    Ok(())
+}
+
+/// Server of one agent and the stream its answers go to
+type Session = (McpServer, UnboundedSender<Value>);
+
+/// Agents connected over server-sent events, by session id
+#[derive(Clone, Default)]
+pub struct SseSessions {
+    sessions: Arc<Mutex<HashMap<String, Session>>>,
+}
+
+impl SseSessions {
+    fn open(&self, server: McpServer) -> (String, UnboundedReceiver<Value>) {
+        static NEXT: AtomicU64 = AtomicU64::new(1);
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().subsec_nanos();
+        let id = format!("{:x}{:08x}", NEXT.fetch_add(1, Ordering::Relaxed), nanos);
+        let (sender, receiver) = unbounded_channel();
+        self.sessions.lock().unwrap().insert(id.clone(), (server, sender));
+        (id, receiver)
+    }
+
+    fn close(&self, id: &str) {
+        self.sessions.lock().unwrap().remove(id);
+    }
+
+    /// Handle a message an agent posted to its session, sending any answer
+    /// down its event stream; false when there is no such session
+    pub fn post(&self, id: &str, message: &str) -> bool {
+        let Some((server, sender)) = self.sessions.lock().unwrap().get(id).cloned() else {
+            return false;
+        };
+        if let Some(response) = server.handle_text(message) {
+            let _ = sender.send(response);
+        }
+        true
+    }
+}
+
+/// Keep an event stream open for `server` until the agent disconnects
+pub async fn serve_sse(stream: &mut TcpStream, sessions: &SseSessions, server: McpServer) -> std::io::Result<()> {
+    let (id, mut messages) = sessions.open(server);
+    let result: std::io::Result<()> = async {
+        stream.write_all(SSE_HEAD).await?;
+        let endpoint = format!("event: endpoint\ndata: /mcp/messages?session_id={}\n\n", id);
+        stream.write_all(endpoint.as_bytes()).await?;
+        loop {
+            match tokio::time::timeout(KEEPALIVE, messages.recv()).await {
+                Ok(Some(message)) => {
+                    let event = format!("event: message\ndata: {}\n\n", message);
+                    stream.write_all(event.as_bytes()).await?;
+                }
+                Ok(None) => return Ok(()),
+                Err(_) => stream.write_all(b": keepalive\n\n").await?,
+            }
+        }
+    }
+    .await;
+    sessions.close(&id);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn answers_lines_from_stdio() {
+        let dir = tempfile::tempdir().unwrap();
+        let server = McpServer::new(dir.path(), "stdio");
+        let input = "{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"ping\"}\n\n{\"jsonrpc\":\"2.0\",\"method\":\"notifications/initialized\"}\n{\"jsonrpc\":\"2.0\",\"id\":2,\"method\":\"tools/list\"}\n";
+        let mut output = Vec::new();
+        serve_stdio(&server, input.as_bytes(), &mut output).unwrap();
+        let answers: Vec<Value> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(
+            answers,
+            vec![
+                json!({ "jsonrpc": "2.0", "id": 1, "result": {} }),
+                json!({ "jsonrpc": "2.0", "id": 2, "result": { "tools": [] } }),
+            ]
+        );
+    }
+}
@@ -8,7 +8,7 @@ import { parseNote } from "./lib/frontmatter/parser";
 import { vaultStore } from "./lib/store/vault";
 import { notebookStore } from "./lib/store/notebook";
 import { kanbanStore } from "./lib/store/kanban";
-import { isKanban } from "./lib/fs";
+import { createLinkNote, isKanban, onDeepLink, onWebhookFailed, takeDeepLinks, type DeepLinkEvent } from "./lib/fs";
 import "./lib/store/theme"; // Initialize theme on load
 import { initializeSettings } from "./lib/settings";
 import { registerCommands, setUICallbacks } from "./lib/commands";
@@ -115,6 +115,7 @@ function App() {
   const [showCommandPalette, setShowCommandPalette] = createSignal(false);
   const [showQuickOpen, setShowQuickOpen] = createSignal(false);
   const [showSearchPanel, setShowSearchPanel] = createSignal(false);
+  const [searchPanelQuery, setSearchPanelQuery] = createSignal("");
   const [showExportDialog, setShowExportDialog] = createSignal(false);
   const [showNewNoteDialog, setShowNewNoteDialog] = createSignal(false);
   const [newNoteName, setNewNoteName] = createSignal("");
@@ -295,6 +296,39 @@ function App() {
     }
   };
 
+  // Follow a notemaker:// link resolved by the backend
+  const handleDeepLink = async (event: DeepLinkEvent) => {
+    if (event.action === "error") {
+      console.error(`Cannot follow ${event.url}: ${event.message}`);
+      return;
+    }
+    // Links can come from any page, so notes are only created on request
+    if (event.action === "new" && !confirm(`Create note "${event.name}" in ${event.vault}?`)) {
+      return;
+    }
+    try {
+      if (vaultStore.vault()?.path !== event.vault) {
+        await vaultStore.openVault(event.vault);
+      }
+      if (event.action === "open") {
+        if (event.path) await vaultStore.selectNote(event.path);
+      } else if (event.action === "new") {
+        const path = await createLinkNote(event.vault, event.name, event.title, event.template);
+        await vaultStore.selectNote(path);
+      } else {
+        setSearchPanelQuery(event.query);
+        setShowSearchPanel(true);
+      }
+    } catch (err) {
+      console.error("Failed to follow link:", err);
+    }
+  };
+
+  const deepLinkListener = onDeepLink(handleDeepLink);
+  const webhookListener = onWebhookFailed((failure) =>
+    console.error(`Webhook ${failure.url} for ${failure.event} failed: ${failure.error}`)
+  );
+
   // Setup commands and keyboard handler
   onMount(() => {
     // Initialize settings and apply CSS variables
@@ -304,7 +338,10 @@ function App() {
     setUICallbacks({
       openCommandPalette: () => setShowCommandPalette(true),
       openQuickOpen: () => setShowQuickOpen(true),
-      openSearchPanel: () => setShowSearchPanel(true),
+      openSearchPanel: () => {
+        setSearchPanelQuery("");
+        setShowSearchPanel(true);
+      },
       openVaultDialog: handleOpenVault,
       openNewNoteDialog: () => {
         if (vaultStore.vault()) {
@@ -350,17 +387,29 @@ function App() {
 
     // Auto-open last vault
     const lastVault = localStorage.getItem("notemaker:last-vault");
-    if (lastVault) {
-      vaultStore.openVault(lastVault).catch((err) => {
-        console.error("Failed to open last vault:", err);
-        // Clear invalid vault path
-        localStorage.removeItem("notemaker:last-vault");
-      });
-    }
+    const lastVaultOpened = lastVault
+      ? vaultStore.openVault(lastVault).catch((err) => {
+          console.error("Failed to open last vault:", err);
+          // Clear invalid vault path
+          localStorage.removeItem("notemaker:last-vault");
+        })
+      : Promise.resolve();
+
+    // Links the app was launched with go to the last vault unless they name one
+    lastVaultOpened
+      .then(() => takeDeepLinks())
+      .then(async (events) => {
+        for (const event of events) {
+          await handleDeepLink(event);
+        }
+      })
+      .catch((err) => console.error("Failed to follow launch links:", err));
   });
 
   onCleanup(() => {
     teardownGlobalKeyboardHandler();
+    deepLinkListener.then((unlisten) => unlisten());
+    webhookListener.then((unlisten) => unlisten());
   });
 
   // Get current content
@@ -509,6 +558,7 @@ function App() {
         isOpen={showSearchPanel()}
         onClose={() => setShowSearchPanel(false)}
         onSelect={handleSelectNote}
+        initialQuery={searchPanelQuery()}
       />
 
       {/* Export Dialog */}
@@ -11,7 +11,7 @@ import { FrontmatterEditor } from "./FrontmatterEditor";
 import { parseNote, serializeNote } from "../lib/frontmatter/parser";
 import { Frontmatter } from "../lib/frontmatter/types";
 import { encryptionStore } from "../lib/store/encryption";
-import { isContentEncrypted, encryptBlock, decryptBlock } from "../lib/fs";
+import { isContentEncrypted } from "../lib/fs";
 
 const STORAGE_KEY = "notemaker:editor-mode";
 const FRONTMATTER_KEY = "notemaker:show-frontmatter";
@@ -65,7 +65,7 @@ export function EditorWithPreview(props: EditorWithPreviewProps) {
 
     setIsEncrypting(true);
     try {
-      const encrypted = await encryptBlock(content);
+      const encrypted = await encryptionStore.encrypt(content);
       props.onChange?.(encrypted);
       setIsEncrypted(true);
       setDecryptedContent(null);
@@ -95,7 +95,7 @@ export function EditorWithPreview(props: EditorWithPreviewProps) {
     if (!content || !isEncrypted()) return;
 
     try {
-      const decrypted = await decryptBlock(content);
+      const decrypted = await encryptionStore.decrypt(content);
       setDecryptedContent(decrypted);
     } catch (e) {
       const msg = e instanceof Error ? e.message : String(e);
@@ -111,7 +111,7 @@ export function EditorWithPreview(props: EditorWithPreviewProps) {
 
     setIsEncrypting(true);
     try {
-      const encrypted = await encryptBlock(decrypted);
+      const encrypted = await encryptionStore.encrypt(decrypted);
       props.onChange?.(encrypted);
       setDecryptedContent(null);
     } catch (e) {
@@ -5,7 +5,7 @@
 import { createSignal, Show, createEffect } from "solid-js";
 import { notebookStore } from "../lib/store/notebook";
 import { encryptionStore } from "../lib/store/encryption";
-import { isContentEncrypted, encryptBlock, decryptBlock, updateNotebookBlock } from "../lib/fs";
+import { isContentEncrypted, updateNotebookBlock } from "../lib/fs";
 import { CodeBlockEditor } from "./CodeBlockEditor";
 import { MarkdownBlockEditor } from "./MarkdownBlockEditor";
 
@@ -91,7 +91,7 @@ export function NotebookBlock(props: NotebookBlockProps) {
 
     setIsEncrypting(true);
     try {
-      const encrypted = await encryptBlock(content);
+      const encrypted = await encryptionStore.encrypt(content);
       await updateNotebookBlock(props.notebookPath, props.id, encrypted);
       notebookStore.updateBlockContent(props.id, encrypted);
       setIsEncrypted(true);
@@ -132,7 +132,7 @@ export function NotebookBlock(props: NotebookBlockProps) {
     if (!content || !isEncrypted()) return;
 
     try {
-      const decrypted = await decryptBlock(content);
+      const decrypted = await encryptionStore.decrypt(content);
       setDecryptedContent(decrypted);
       setIsEncrypted(true);
     } catch (e) {
@@ -185,7 +185,7 @@ export function NotebookBlock(props: NotebookBlockProps) {
 
     setIsEncrypting(true);
     try {
-      const encrypted = await encryptBlock(decrypted);
+      const encrypted = await encryptionStore.encrypt(decrypted);
       await updateNotebookBlock(props.notebookPath, props.id, encrypted);
       notebookStore.updateBlockContent(props.id, encrypted);
       setDecryptedContent(null);
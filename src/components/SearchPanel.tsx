@@ -14,6 +14,8 @@ export interface SearchPanelProps {
   isOpen: boolean;
   onClose: () => void;
   onSelect: (path: string) => void;
+  /** Text to search for when the panel opens */
+  initialQuery?: string;
 }
 
 export function SearchPanel(props: SearchPanelProps) {
@@ -64,10 +66,14 @@ export function SearchPanel(props: SearchPanelProps) {
 
   // Reset and focus when opened
   const handleOpen = () => {
-    setQuery("");
+    const initial = props.initialQuery ?? "";
+    setQuery(initial);
     setSelectedIndex(0);
     setShowFilters(false);
-    setTimeout(() => inputRef?.focus(), 10);
+    setTimeout(() => {
+      if (inputRef) inputRef.value = initial;
+      inputRef?.focus();
+    }, 10);
     indexNotes();
   };
 
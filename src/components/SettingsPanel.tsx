@@ -13,12 +13,13 @@ export interface SettingsPanelProps {
   onClose: () => void;
 }
 
-type SettingsTab = "editor" | "appearance" | "autosave";
+type SettingsTab = "editor" | "appearance" | "autosave" | "api";
 
 const TABS: { id: SettingsTab; label: string }[] = [
   { id: "editor", label: "Editor" },
   { id: "appearance", label: "Appearance" },
   { id: "autosave", label: "Autosave" },
+  { id: "api", label: "Local API" },
 ];
 
 // Deep clone settings to trigger SolidJS reactivity
@@ -26,6 +27,7 @@ const cloneSettings = (s: AppSettings): AppSettings => ({
   editor: { ...s.editor },
   appearance: { ...s.appearance },
   autosave: { ...s.autosave },
+  api: { ...s.api },
 });
 
 export function SettingsPanel(props: SettingsPanelProps) {
@@ -111,6 +113,9 @@ export function SettingsPanel(props: SettingsPanelProps) {
               <Show when={activeTab() === "autosave"}>
                 <AutosaveSettings settings={settings()} onUpdate={updateSetting} />
               </Show>
+              <Show when={activeTab() === "api"}>
+                <ApiSettings settings={settings()} onUpdate={updateSetting} />
+              </Show>
             </div>
           </div>
 
@@ -282,6 +287,53 @@ function AutosaveSettings(props: { settings: AppSettings; onUpdate: UpdateFn })
   );
 }
 
+// Local API Settings
+function ApiSettings(props: { settings: AppSettings; onUpdate: UpdateFn }) {
+  return (
+    <div style={{ display: "flex", "flex-direction": "column", gap: "24px" }}>
+      <SettingGroup title="Local API">
+        <SettingRow label="Enable API Server" description="Serve search, read and append on 127.0.0.1">
+          <Toggle
+            checked={props.settings.api.enabled}
+            onChange={(v) => props.onUpdate("api", "enabled", v)}
+          />
+        </SettingRow>
+        <SettingRow label="Port" description="Port the server listens on">
+          <input
+            type="number"
+            value={props.settings.api.port}
+            onChange={(e) => props.onUpdate("api", "port", parseInt(e.currentTarget.value) || 27123)}
+            min={1024}
+            max={65535}
+            class="w-24 bg-gray-700 border border-gray-600 rounded text-sm text-gray-200"
+            style={{ padding: "4px 8px" }}
+          />
+        </SettingRow>
+        <SettingRow label="Token" description="Send as Authorization: Bearer <token>">
+          <div class="flex items-center" style={{ gap: "8px" }}>
+            <input
+              type="text"
+              readOnly
+              value={props.settings.api.token}
+              placeholder="Generated when enabled"
+              class="w-48 bg-gray-700 border border-gray-600 rounded text-xs text-gray-200 font-mono"
+              style={{ padding: "4px 8px" }}
+            />
+            <button
+              onClick={() => props.onUpdate("api", "token", "")}
+              disabled={!props.settings.api.enabled}
+              class="text-xs text-gray-400 hover:text-gray-200 disabled:opacity-50 transition-colors"
+              title="Replace the token; clients using the old one stop working"
+            >
+              Regenerate
+            </button>
+          </div>
+        </SettingRow>
+      </SettingGroup>
+    </div>
+  );
+}
+
 // Reusable components
 function SettingGroup(props: { title: string; children: any }) {
   return (
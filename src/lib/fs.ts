@@ -1,5 +1,6 @@
 import { invoke } from "@tauri-apps/api/core";
 import { listen, UnlistenFn } from "@tauri-apps/api/event";
+import type { DiffFile } from "./git/api";
 
 // Types
 
@@ -24,6 +25,26 @@ export interface NoteContent {
   path: string;
   content: string;
   modified: number;
+  hash: string;
+}
+
+/** Rejection of `writeNote` when the note changed on disk since it was read */
+export interface WriteConflict {
+  path: string;
+  content: string;
+  encrypted: boolean;
+  hash: string;
+}
+
+export function isWriteConflict(err: unknown): err is WriteConflict {
+  return typeof err === "object" && err !== null && "hash" in err && "content" in err;
+}
+
+export interface BackupInfo {
+  name: string;
+  path: string;
+  created: number;
+  size: number;
 }
 
 export interface FileChangeEvent {
@@ -31,6 +52,11 @@ export interface FileChangeEvent {
   kind: "create" | "modify" | "delete" | "rename";
 }
 
+export interface FileChangeBatch {
+  root: string;
+  changes: FileChangeEvent[];
+}
+
 // Notebook types
 
 export type BlockType = "markdown" | "code";
@@ -140,11 +166,25 @@ export interface VaultConfig {
   };
   encryption: EncryptionSettings;
   interpreters: InterpreterSettings;
+  periodic?: Record<PeriodKind, PeriodicNoteSettings>;
+}
+
+/** `path` is vault-relative without `.md`, expanded like a template */
+export interface PeriodicNoteSettings {
+  path: string;
+  template?: string;
 }
 
 export interface LocalState {
   expanded_paths: string[];
   last_opened: string | null;
+  recent_files?: RecentFile[];
+  pinned?: string[];
+}
+
+export interface RecentFile {
+  path: string;
+  opened: string;
 }
 
 // Vault operations
@@ -175,6 +215,28 @@ export async function saveLocalState(
   return invoke("save_local_state", { vaultPath, state });
 }
 
+export async function getRecentFiles(vaultPath: string): Promise<RecentFile[]> {
+  return invoke<RecentFile[]>("get_recent_files", { vaultPath });
+}
+
+export async function togglePin(vaultPath: string, path: string): Promise<boolean> {
+  return invoke<boolean>("toggle_pin", { vaultPath, path });
+}
+
+// Backups
+
+export async function createBackupNow(vaultPath: string): Promise<BackupInfo> {
+  return invoke<BackupInfo>("create_backup_now", { vaultPath });
+}
+
+export async function listBackups(vaultPath: string): Promise<BackupInfo[]> {
+  return invoke<BackupInfo[]>("list_backups", { vaultPath });
+}
+
+export async function restoreBackup(vaultPath: string, name: string): Promise<void> {
+  return invoke("restore_backup", { vaultPath, name });
+}
+
 // Directory operations
 
 export async function listDirectory(path: string): Promise<FileEntry[]> {
@@ -195,8 +257,90 @@ export async function readNote(path: string): Promise<NoteContent> {
   return invoke<NoteContent>("read_note", { path });
 }
 
-export async function writeNote(path: string, content: string): Promise<void> {
-  return invoke("write_note", { path, content });
+export interface BinaryFile {
+  path: string;
+  mime_type: string;
+  size: number;
+  data: string;
+}
+
+export async function readFileBinary(path: string): Promise<BinaryFile> {
+  return invoke<BinaryFile>("read_file_binary", { path });
+}
+
+export interface OutlineHeading {
+  level: number;
+  text: string;
+  line: number;
+}
+
+export interface NoteMetadata {
+  title: string | null;
+  labels: string[];
+  created: string | null;
+  custom: Record<string, unknown>;
+  word_count: number;
+  outline: OutlineHeading[];
+}
+
+export async function getNoteMetadata(path: string): Promise<NoteMetadata> {
+  return invoke<NoteMetadata>("get_note_metadata", { path });
+}
+
+export interface NoteStats {
+  words: number;
+  characters: number;
+  headings: number;
+  links: number;
+  tasks: number;
+  completed_tasks: number;
+  reading_minutes: number;
+}
+
+/** Unsaved buffer (old side) against the file on disk (new side); null when equal */
+export async function diffAgainstDisk(
+  path: string,
+  bufferContent: string
+): Promise<DiffFile | null> {
+  return invoke<DiffFile | null>("diff_against_disk", { path, bufferContent });
+}
+
+export async function getNoteStats(path: string): Promise<NoteStats> {
+  return invoke<NoteStats>("get_note_stats", { path });
+}
+
+export interface DuplicateNote {
+  path: string;
+  size: number;
+}
+
+export interface DuplicateGroup {
+  kind: "exact" | "near";
+  similarity: number;
+  notes: DuplicateNote[];
+}
+
+export async function findDuplicateNotes(
+  vaultPath: string,
+  threshold?: number
+): Promise<DuplicateGroup[]> {
+  return invoke<DuplicateGroup[]>("find_duplicate_notes", { vaultPath, threshold });
+}
+
+/** Set frontmatter fields; `null` removes a field */
+export async function updateNoteMetadata(
+  path: string,
+  patch: Record<string, unknown>
+): Promise<NoteMetadata> {
+  return invoke<NoteMetadata>("update_note_metadata", { path, patch });
+}
+
+export async function writeNote(
+  path: string,
+  content: string,
+  expectedHash?: string
+): Promise<string> {
+  return invoke<string>("write_note", { path, content, expectedHash });
 }
 
 export async function createNote(
@@ -207,6 +351,188 @@ export async function createNote(
   return invoke("create_note", { path, title, template });
 }
 
+/** Render a vault template; `vars` also holds prompt answers keyed by question */
+export async function renderTemplate(
+  vaultPath: string,
+  name: string,
+  vars: Record<string, string> = {}
+): Promise<string> {
+  return invoke<string>("render_template", { vaultPath, name, vars });
+}
+
+export async function getTemplatePrompts(vaultPath: string, name: string): Promise<string[]> {
+  return invoke<string[]>("get_template_prompts", { vaultPath, name });
+}
+
+/** Save a note as a vault template; `parameterize` turns its title and dates into variables */
+export async function createTemplateFromNote(
+  notePath: string,
+  templateName: string,
+  parameterize = true
+): Promise<string> {
+  return invoke<string>("create_template_from_note", { notePath, templateName, parameterize });
+}
+
+export type CompletionKind = "tags" | "notes" | "templates" | "people";
+
+export interface CompletionItem {
+  value: string;
+  /** Notes carrying a tag, links to a note, or tasks assigned to a person */
+  count: number;
+  path?: string;
+}
+
+/** Autocomplete candidates of one kind, most used first */
+export async function getCompletionData(
+  vaultPath: string,
+  kind: CompletionKind
+): Promise<CompletionItem[]> {
+  return invoke<CompletionItem[]>("get_completion_data", { vaultPath, kind });
+}
+
+export type QueryComparison = "eq" | "ne" | "lt" | "le" | "gt" | "ge" | "contains";
+
+export type QueryExpr =
+  | { op: "and"; items: QueryExpr[] }
+  | { op: "or"; items: QueryExpr[] }
+  | { op: "not"; expr: QueryExpr }
+  | { op: "compare"; field: string; cmp: QueryComparison; value: unknown };
+
+export interface NoteQuery {
+  select?: string[];
+  filter?: QueryExpr;
+  sort?: { field: string; descending?: boolean }[];
+  limit?: number;
+}
+
+export interface QueryResult {
+  columns: string[];
+  rows: { path: string; name: string; values: unknown[] }[];
+}
+
+/** Notes matching a metadata query, e.g. "status = active SORT BY due" */
+export async function queryNotes(
+  vaultPath: string,
+  query: string | NoteQuery
+): Promise<QueryResult> {
+  return invoke<QueryResult>("query_notes", { vaultPath, query });
+}
+
+export interface ChecklistItem {
+  /** Note or notebook the item belongs to */
+  note: string;
+  /** File holding the item: the note or a notebook block file */
+  path: string;
+  block_id?: string;
+  /** Line of the item in `path`, starting at 1 */
+  line: number;
+  text: string;
+  checked: boolean;
+  /** YYYY-MM-DD */
+  due?: string;
+  tags: string[];
+}
+
+export interface ChecklistFilter {
+  tags?: string[];
+  due_from?: string;
+  due_to?: string;
+  text?: string;
+}
+
+/** Unchecked `- [ ]` items across the vault, earliest due first */
+export async function listOpenTasks(
+  vaultPath: string,
+  filter: ChecklistFilter = {}
+): Promise<ChecklistItem[]> {
+  return invoke<ChecklistItem[]>("list_open_tasks", { vaultPath, filter });
+}
+
+/** Check or uncheck a task; resolves to whether it is now checked */
+export async function toggleTask(path: string, line: number): Promise<boolean> {
+  return invoke<boolean>("toggle_task", { path, line });
+}
+
+export interface DueItem {
+  /** Note file or kanban board */
+  path: string;
+  task_id?: string;
+  title: string;
+  due: string;
+  overdue: boolean;
+}
+
+export interface CalendarDay {
+  /** YYYY-MM-DD */
+  date: string;
+  created: string[];
+  modified: string[];
+  daily_note?: string;
+  due: DueItem[];
+  /** Notes whose frontmatter `date:` falls on the day */
+  events: { path: string; title: string }[];
+}
+
+/** Days between `from` and `to` (YYYY-MM-DD, inclusive) with anything on them */
+export async function getCalendar(
+  vaultPath: string,
+  from: string,
+  to: string
+): Promise<CalendarDay[]> {
+  return invoke<CalendarDay[]>("get_calendar", { vaultPath, range: { from, to } });
+}
+
+export interface TagTreeNode {
+  name: string;
+  /** Full tag, e.g. "project/alpha" */
+  tag: string;
+  /** Notes carrying exactly this tag */
+  count: number;
+  /** Notes carrying this tag or one nested below it */
+  total: number;
+  children: TagTreeNode[];
+}
+
+export async function getTagTree(vaultPath: string): Promise<TagTreeNode[]> {
+  return invoke<TagTreeNode[]>("get_tag_tree", { vaultPath });
+}
+
+/** `parent/*` also matches tags nested below `parent` */
+export async function findNotesByTag(vaultPath: string, query: string): Promise<string[]> {
+  return invoke<string[]>("find_notes_by_tag", { vaultPath, query });
+}
+
+export type PeriodKind = "daily" | "weekly" | "monthly" | "quarterly";
+
+export interface PeriodicNote {
+  kind: PeriodKind;
+  path: string;
+  title: string;
+  start: string;
+  end: string;
+  exists: boolean;
+}
+
+/** Note for the period containing `date` (YYYY-MM-DD), created when `create` is set */
+export async function getPeriodicNote(
+  vaultPath: string,
+  kind: PeriodKind,
+  date: string,
+  create = false
+): Promise<PeriodicNote> {
+  return invoke<PeriodicNote>("get_periodic_note", { vaultPath, kind, date, create });
+}
+
+/** Note `offset` periods before (negative) or after the one containing `date` */
+export async function getAdjacentPeriodicNote(
+  vaultPath: string,
+  kind: PeriodKind,
+  date: string,
+  offset: number
+): Promise<PeriodicNote> {
+  return invoke<PeriodicNote>("get_adjacent_periodic_note", { vaultPath, kind, date, offset });
+}
+
 export async function deleteNote(path: string): Promise<void> {
   return invoke("delete_note", { path });
 }
@@ -219,6 +545,10 @@ export async function moveNote(from: string, toDir: string): Promise<string> {
   return invoke<string>("move_note", { from, toDir });
 }
 
+export async function copyNote(from: string, to: string): Promise<void> {
+  return invoke("copy_note", { from, to });
+}
+
 // Attachment operations
 
 export async function saveAttachment(
@@ -406,18 +736,57 @@ export async function terminateCodeBlock(blockId: string): Promise<boolean> {
 
 // File watcher
 
-export async function startWatching(path: string): Promise<void> {
-  return invoke("start_watching", { path });
+/** Watch a directory; without an `id` it is watched as the open vault */
+export async function startWatching(path: string, id?: string): Promise<void> {
+  return invoke("start_watching", { path, id });
+}
+
+/** Stop watching the root `id`, or every root */
+export async function stopWatching(id?: string): Promise<void> {
+  return invoke("stop_watching", { id });
+}
+
+/** Hold back change events below `path`, e.g. around a bulk operation; pauses nest */
+export async function pauseWatching(path: string): Promise<void> {
+  return invoke("pause_watching", { path });
+}
+
+/** End a pause of `path`; the held back changes arrive as one batch */
+export async function resumeWatching(path: string): Promise<void> {
+  return invoke("resume_watching", { path });
 }
 
-export async function stopWatching(): Promise<void> {
-  return invoke("stop_watching");
+/** Only report changes below `paths` (all changes when empty) */
+export async function setWatchSubscriptions(paths: string[], id?: string): Promise<void> {
+  return invoke("set_watch_subscriptions", { paths, id });
+}
+
+/** Changes arrive in debounced batches per watched root, one entry per path */
+export function onFilesChanged(
+  callback: (batch: FileChangeBatch) => void
+): Promise<UnlistenFn> {
+  return listen<FileChangeBatch>("files-changed", (event) => {
+    callback(event.payload);
+  });
 }
 
 export function onFileChange(
   callback: (event: FileChangeEvent) => void
 ): Promise<UnlistenFn> {
-  return listen<FileChangeEvent>("file-changed", (event) => {
+  return onFilesChanged((batch) => batch.changes.forEach(callback));
+}
+
+/** `path` is the vault, notebook or board whose config or index changed */
+export interface IndexChangeEvent {
+  root: string;
+  path: string;
+}
+
+export function onIndexChange(
+  kind: "config" | "notebook" | "kanban",
+  callback: (event: IndexChangeEvent) => void
+): Promise<UnlistenFn> {
+  return listen<IndexChangeEvent>(`${kind}-changed`, (event) => {
     callback(event.payload);
   });
 }
@@ -470,28 +839,28 @@ export function formatRelativeTime(timestamp: number): string {
 
 // Encryption operations
 
-export async function setEncryptionPassword(password: string): Promise<void> {
-  return invoke("set_encryption_password", { password });
+export async function setEncryptionPassword(vaultPath: string, password: string): Promise<void> {
+  return invoke("set_encryption_password", { vaultPath, password });
 }
 
-export async function setEncryptionIdentity(path: string): Promise<void> {
-  return invoke("set_encryption_identity", { path });
+export async function setEncryptionIdentity(vaultPath: string, path: string): Promise<void> {
+  return invoke("set_encryption_identity", { vaultPath, path });
 }
 
-export async function lockEncryptionSession(): Promise<void> {
-  return invoke("lock_encryption_session");
+export async function lockEncryptionSession(vaultPath: string): Promise<void> {
+  return invoke("lock_encryption_session", { vaultPath });
 }
 
-export async function isEncryptionUnlocked(): Promise<boolean> {
-  return invoke<boolean>("is_encryption_unlocked");
+export async function isEncryptionUnlocked(vaultPath: string): Promise<boolean> {
+  return invoke<boolean>("is_encryption_unlocked", { vaultPath });
 }
 
-export async function encryptBlock(content: string): Promise<string> {
-  return invoke<string>("encrypt_block", { content });
+export async function encryptBlock(vaultPath: string, content: string): Promise<string> {
+  return invoke<string>("encrypt_block", { vaultPath, content });
 }
 
-export async function decryptBlock(content: string): Promise<string> {
-  return invoke<string>("decrypt_block", { content });
+export async function decryptBlock(vaultPath: string, content: string): Promise<string> {
+  return invoke<string>("decrypt_block", { vaultPath, content });
 }
 
 export async function encryptNote(path: string): Promise<void> {
@@ -513,35 +882,38 @@ export async function isContentEncrypted(content: string): Promise<boolean> {
 // Keychain operations - persistent credential storage
 
 export async function setEncryptionPasswordWithSave(
+  vaultPath: string,
   password: string,
   saveToKeychain: boolean
 ): Promise<void> {
-  return invoke("set_encryption_password_with_save", { password, saveToKeychain });
+  return invoke("set_encryption_password_with_save", { vaultPath, password, saveToKeychain });
 }
 
 export async function setEncryptionIdentityWithSave(
+  vaultPath: string,
   path: string,
   saveToKeychain: boolean
 ): Promise<void> {
-  return invoke("set_encryption_identity_with_save", { path, saveToKeychain });
+  return invoke("set_encryption_identity_with_save", { vaultPath, path, saveToKeychain });
 }
 
-export async function unlockFromKeychain(): Promise<boolean> {
-  return invoke<boolean>("unlock_from_keychain");
+export async function unlockFromKeychain(vaultPath: string): Promise<boolean> {
+  return invoke<boolean>("unlock_from_keychain", { vaultPath });
 }
 
-export async function hasKeychainCredentials(): Promise<boolean> {
-  return invoke<boolean>("has_keychain_credentials");
+export async function hasKeychainCredentials(vaultPath: string): Promise<boolean> {
+  return invoke<boolean>("has_keychain_credentials", { vaultPath });
 }
 
-export async function clearKeychainCredentials(): Promise<void> {
-  return invoke("clear_keychain_credentials");
+export async function clearKeychainCredentials(vaultPath: string): Promise<void> {
+  return invoke("clear_keychain_credentials", { vaultPath });
 }
 
 export async function lockEncryptionSessionWithClear(
+  vaultPath: string,
   clearKeychain: boolean
 ): Promise<void> {
-  return invoke("lock_encryption_session_with_clear", { clearKeychain });
+  return invoke("lock_encryption_session_with_clear", { vaultPath, clearKeychain });
 }
 
 // Multi-recipient encryption operations
@@ -555,24 +927,89 @@ export async function generateIdentityFile(path: string): Promise<string> {
 }
 
 export async function setupRecipientsEncryption(
+  vaultPath: string,
   publicKeys: string[],
   identityPaths: string[]
 ): Promise<void> {
-  return invoke("setup_recipients_encryption", { publicKeys, identityPaths });
+  return invoke("setup_recipients_encryption", { vaultPath, publicKeys, identityPaths });
+}
+
+export async function addRecipientIdentity(vaultPath: string, path: string): Promise<string> {
+  return invoke<string>("add_recipient_identity", { vaultPath, path });
+}
+
+export async function addRecipientPublicKey(vaultPath: string, publicKey: string): Promise<void> {
+  return invoke("add_recipient_public_key", { vaultPath, publicKey });
+}
+
+export async function getRecipientPublicKeys(vaultPath: string): Promise<string[]> {
+  return invoke<string[]>("get_recipient_public_keys", { vaultPath });
+}
+
+export async function clearRecipients(vaultPath: string): Promise<void> {
+  return invoke("clear_recipients", { vaultPath });
+}
+
+// Local API server
+
+export interface ApiServerSettings {
+  enabled: boolean;
+  port: number;
+  token: string;
+}
+
+export interface ApiServerStatus {
+  running: boolean;
+  port?: number;
+}
+
+export async function configureApiServer(settings: ApiServerSettings): Promise<ApiServerStatus> {
+  return invoke<ApiServerStatus>("configure_api_server", { settings });
+}
+
+export async function getApiServerStatus(): Promise<ApiServerStatus> {
+  return invoke<ApiServerStatus>("get_api_server_status");
 }
 
-export async function addRecipientIdentity(path: string): Promise<string> {
-  return invoke<string>("add_recipient_identity", { path });
+// Deep links (notemaker://open, new, search)
+
+export type DeepLinkEvent =
+  | { action: "open"; vault: string; path: string | null }
+  | { action: "new"; vault: string; name: string; title: string | null; template: string | null }
+  | { action: "search"; vault: string; query: string }
+  | { action: "error"; url: string; message: string };
+
+/** Links the app was launched with; each is returned once */
+export async function takeDeepLinks(): Promise<DeepLinkEvent[]> {
+  return invoke<DeepLinkEvent[]>("take_deep_links");
 }
 
-export async function addRecipientPublicKey(publicKey: string): Promise<void> {
-  return invoke("add_recipient_public_key", { publicKey });
+/** Create the note a `new` link asked for; returns its path */
+export async function createLinkNote(
+  vaultPath: string,
+  name: string,
+  title: string | null,
+  template: string | null
+): Promise<string> {
+  return invoke<string>("create_link_note", { vaultPath, name, title, template });
 }
 
-export async function getRecipientPublicKeys(): Promise<string[]> {
-  return invoke<string[]>("get_recipient_public_keys");
+export function onDeepLink(callback: (event: DeepLinkEvent) => void): Promise<UnlistenFn> {
+  return listen<DeepLinkEvent>("deep-link", (event) => {
+    callback(event.payload);
+  });
 }
 
-export async function clearRecipients(): Promise<void> {
-  return invoke("clear_recipients");
+// Webhooks
+
+export interface WebhookFailure {
+  url: string;
+  event: "note_saved" | "task_moved" | "commit_created" | "sync_completed";
+  error: string;
+}
+
+export function onWebhookFailed(callback: (failure: WebhookFailure) => void): Promise<UnlistenFn> {
+  return listen<WebhookFailure>("webhook-failed", (event) => {
+    callback(event.payload);
+  });
 }
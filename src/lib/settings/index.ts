@@ -3,6 +3,7 @@
  */
 
 import { createSignal } from "solid-js";
+import { configureApiServer } from "../fs";
 
 export interface AppSettings {
   // Editor
@@ -33,6 +34,13 @@ export interface AppSettings {
     enabled: boolean;
     delay: number; // ms
   };
+
+  // Local API server
+  api: {
+    enabled: boolean;
+    port: number;
+    token: string; // generated when the server is first enabled
+  };
 }
 
 const SETTINGS_KEY = "notemaker:settings";
@@ -61,6 +69,11 @@ const DEFAULT_SETTINGS: AppSettings = {
     enabled: true,
     delay: 1000,
   },
+  api: {
+    enabled: false,
+    port: 27123,
+    token: "",
+  },
 };
 
 /**
@@ -99,6 +112,7 @@ function deepMerge(target: AppSettings, source: Partial<AppSettings>): AppSettin
     editor: { ...target.editor, ...source.editor },
     appearance: { ...target.appearance, ...source.appearance },
     autosave: { ...target.autosave, ...source.autosave },
+    api: { ...target.api, ...source.api },
   };
 }
 
@@ -216,16 +230,45 @@ export function applySettingsToCSS(settings: AppSettings): void {
   applyTheme(effectiveTheme);
 }
 
+/**
+ * Generate a random token for the local API
+ */
+function generateApiToken(): string {
+  const bytes = crypto.getRandomValues(new Uint8Array(24));
+  return Array.from(bytes, (b) => b.toString(16).padStart(2, "0")).join("");
+}
+
+let appliedApiSettings = "";
+
+/**
+ * Start, restart or stop the local API server when its settings change
+ */
+function applyApiSettings(settings: AppSettings): void {
+  if (settings.api.enabled && !settings.api.token) {
+    // Notifies listeners again, which then apply the complete settings
+    settingsStore.set("api", "token", generateApiToken());
+    return;
+  }
+  const serialized = JSON.stringify(settings.api);
+  if (serialized === appliedApiSettings) return;
+  appliedApiSettings = serialized;
+  configureApiServer(settings.api).catch((e) => {
+    console.error("Failed to configure API server:", e);
+  });
+}
+
 /**
  * Initialize settings and apply CSS variables
  */
 export function initializeSettings(): void {
   const settings = settingsStore.get();
   applySettingsToCSS(settings);
+  applyApiSettings(settings);
 
   // Re-apply when settings change
   settingsStore.subscribe(() => {
     applySettingsToCSS(settingsStore.get());
+    applyApiSettings(settingsStore.get());
   });
 
   // Listen for system theme changes
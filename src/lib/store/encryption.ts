@@ -1,4 +1,4 @@
-import { createSignal, createRoot } from "solid-js";
+import { createSignal, createRoot, createEffect, on } from "solid-js";
 import {
   isEncryptionUnlocked,
   setEncryptionPasswordWithSave,
@@ -32,23 +32,40 @@ function createEncryptionStore() {
   const [ownIdentityPath, setOwnIdentityPath] = createSignal<string | null>(null);
   const [ownPublicKey, setOwnPublicKey] = createSignal<string | null>(null);
 
+  // Keychain credentials are stored per vault
+  function vaultPath(): string {
+    const vault = vaultStore.vault();
+    if (!vault) {
+      throw new Error("No vault open");
+    }
+    return vault.path;
+  }
+
   // Check initial state and try to auto-unlock from keychain
   async function initialize() {
     try {
+      // Sessions and credentials belong to the open vault
+      const vault = vaultStore.vault();
+      if (!vault) {
+        setIsUnlocked(false);
+        setHasStoredCredentials(false);
+        return;
+      }
+
       // Check if already unlocked
-      const unlocked = await isEncryptionUnlocked();
+      const unlocked = await isEncryptionUnlocked(vault.path);
+      setIsUnlocked(unlocked);
       if (unlocked) {
-        setIsUnlocked(true);
         return;
       }
 
       // Check if we have stored credentials
-      const hasStored = await hasKeychainCredentials();
+      const hasStored = await hasKeychainCredentials(vault.path);
       setHasStoredCredentials(hasStored);
 
       // Try to auto-unlock from keychain
       if (hasStored) {
-        const success = await unlockFromKeychain();
+        const success = await unlockFromKeychain(vault.path);
         if (success) {
           setIsUnlocked(true);
           console.log("Auto-unlocked encryption from keychain");
@@ -82,9 +99,9 @@ function createEncryptionStore() {
       }
 
       // Try keychain first
-      const hasStored = await hasKeychainCredentials();
+      const hasStored = await hasKeychainCredentials(vault.path);
       if (hasStored) {
-        const success = await unlockFromKeychain();
+        const success = await unlockFromKeychain(vault.path);
         if (success) {
           setIsUnlocked(true);
           setHasStoredCredentials(true);
@@ -97,7 +114,7 @@ function createEncryptionStore() {
         const identityPath = config.encryption.identity_file;
         if (identityPath) {
           try {
-            await setEncryptionIdentityWithSave(identityPath, false);
+            await setEncryptionIdentityWithSave(vault.path, identityPath, false);
             setIsUnlocked(true);
             setMethod("identityfile");
             return { success: true };
@@ -120,7 +137,7 @@ function createEncryptionStore() {
               .filter(r => r.identity_file)
               .map(r => r.identity_file!);
 
-            await setupRecipientsEncryption(publicKeys, identityPaths);
+            await setupRecipientsEncryption(vault.path, publicKeys, identityPaths);
             setIsUnlocked(true);
             setMethod("recipients");
             setRecipients(configRecipients);
@@ -145,19 +162,17 @@ function createEncryptionStore() {
   // Check unlock state without auto-unlock
   async function checkUnlockState() {
     try {
-      const unlocked = await isEncryptionUnlocked();
-      setIsUnlocked(unlocked);
-
-      const hasStored = await hasKeychainCredentials();
-      setHasStoredCredentials(hasStored);
+      const vault = vaultStore.vault();
+      setIsUnlocked(vault ? await isEncryptionUnlocked(vault.path) : false);
+      setHasStoredCredentials(vault ? await hasKeychainCredentials(vault.path) : false);
     } catch (e) {
       console.error("Failed to check encryption state:", e);
       setIsUnlocked(false);
     }
   }
 
-  // Initialize on load
-  initialize();
+  // Initialize on load and whenever another vault is opened
+  createEffect(on(() => vaultStore.vault()?.path, () => initialize()));
 
   async function unlockWithPassword(
     password: string,
@@ -167,7 +182,7 @@ function createEncryptionStore() {
     setError(null);
 
     try {
-      await setEncryptionPasswordWithSave(password, saveToKeychain);
+      await setEncryptionPasswordWithSave(vaultPath(), password, saveToKeychain);
       setIsUnlocked(true);
       setMethod("password");
       if (saveToKeychain) {
@@ -191,7 +206,7 @@ function createEncryptionStore() {
     setError(null);
 
     try {
-      await setEncryptionIdentityWithSave(path, saveToKeychain);
+      await setEncryptionIdentityWithSave(vaultPath(), path, saveToKeychain);
       setIsUnlocked(true);
       setMethod("identityfile");
       if (saveToKeychain) {
@@ -209,7 +224,11 @@ function createEncryptionStore() {
 
   async function lock(clearKeychain: boolean = false): Promise<void> {
     try {
-      await lockEncryptionSessionWithClear(clearKeychain);
+      // Without an open vault there is no session to lock
+      const vault = vaultStore.vault();
+      if (vault) {
+        await lockEncryptionSessionWithClear(vault.path, clearKeychain);
+      }
       setIsUnlocked(false);
       if (clearKeychain) {
         setHasStoredCredentials(false);
@@ -221,7 +240,7 @@ function createEncryptionStore() {
 
   async function forgetCredentials(): Promise<void> {
     try {
-      await clearKeychainCredentials();
+      await clearKeychainCredentials(vaultPath());
       setHasStoredCredentials(false);
     } catch (e) {
       console.error("Failed to clear keychain credentials:", e);
@@ -232,14 +251,14 @@ function createEncryptionStore() {
     if (!isUnlocked()) {
       throw new Error("Encryption session is locked");
     }
-    return encryptBlock(content);
+    return encryptBlock(vaultPath(), content);
   }
 
   async function decrypt(content: string): Promise<string> {
     if (!isUnlocked()) {
       throw new Error("Encryption session is locked");
     }
-    return decryptBlock(content);
+    return decryptBlock(vaultPath(), content);
   }
 
   // Setup encryption with multiple recipients
@@ -255,7 +274,7 @@ function createEncryptionStore() {
         .filter(r => r.identity_file)
         .map(r => r.identity_file!);
 
-      await setupRecipientsEncryption(publicKeys, identityPaths);
+      await setupRecipientsEncryption(vaultPath(), publicKeys, identityPaths);
       setIsUnlocked(true);
       setMethod("recipients");
       setRecipients(recipientList);
@@ -274,7 +293,7 @@ function createEncryptionStore() {
     identityPath: string
   ): Promise<{ success: boolean; publicKey?: string; error?: string }> {
     try {
-      const publicKey = await addRecipientIdentity(identityPath);
+      const publicKey = await addRecipientIdentity(vaultPath(), identityPath);
       return { success: true, publicKey };
     } catch (e) {
       const msg = e instanceof Error ? e.message : String(e);
@@ -285,7 +304,7 @@ function createEncryptionStore() {
   // Get currently configured public keys
   async function getCurrentPublicKeys(): Promise<string[]> {
     try {
-      return await getRecipientPublicKeys();
+      return await getRecipientPublicKeys(vaultPath());
     } catch {
       return [];
     }
@@ -294,7 +313,7 @@ function createEncryptionStore() {
   // Clear all recipients and lock session
   async function clearAllRecipients(): Promise<void> {
     try {
-      await clearRecipients();
+      await clearRecipients(vaultPath());
       setRecipients([]);
       setIsUnlocked(false);
     } catch (e) {
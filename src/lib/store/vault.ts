@@ -276,7 +276,10 @@ function createVaultStore() {
     if (!note || !path) return;
 
     try {
-      await fs.writeNote(path, note.content);
+      const hash = await fs.writeNote(path, note.content, note.hash);
+      setCurrentNote((current) =>
+        current?.path === note.path ? { ...current, hash } : current
+      );
       setIsDirty(false);
     } catch (err) {
       console.error("Failed to save note:", err);